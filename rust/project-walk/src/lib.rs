@@ -0,0 +1,409 @@
+//! Project traversal shared by pm_encoder and voyager-ast
+//!
+//! Both walk a project tree to decide which files they look at. This crate
+//! holds the one set of rules they apply:
+//!
+//! - Hygiene exclusions ([`is_hygiene_excluded`]): version control,
+//!   dependency, virtualenv and build directories, editor and OS artifacts.
+//!   They are left out whatever the patterns say, unless a `!` pattern
+//!   re-includes something inside them.
+//! - `.gitignore`, `.ignore` and the global excludes, with or without a git
+//!   checkout; dotfiles are governed by the patterns, not hidden.
+//! - Ignore patterns ([`matches_patterns`]): globs matched against the path
+//!   and each of its parents, `!` negations, last match wins, in either
+//!   [`PatternSyntax`] and with or without case (see [`pattern_syntax`]).
+//!
+//! [`walk_builder`] configures an [`ignore::WalkBuilder`] the same way for
+//! every caller, [`walk_exclusion`] decides what the walk leaves out, and
+//! [`walk_files`] runs it with the rules above. pm_encoder layers its own
+//! filters (nested configs, depth limits, link policies, the exclusion
+//! ledger) on [`walk_builder`] and [`walk_exclusion`].
+
+pub mod pattern_syntax;
+
+use std::path::{Path, PathBuf};
+
+use globset::GlobBuilder;
+
+pub use pattern_syntax::{matches_v2, PatternStyle, PatternSyntax, CASE_INSENSITIVE_FS};
+
+/// Directory and file names that are always left out
+pub const HYGIENE_EXCLUSIONS: &[&str] = &[
+    // Version control
+    ".git",
+    ".hg",
+    ".svn",
+    // Package managers / dependencies
+    "node_modules",
+    ".npm",
+    ".yarn",
+    // Python environments
+    ".venv",
+    "venv",
+    "env",
+    "__pycache__",
+    ".pytest_cache",
+    ".mypy_cache",
+    ".ruff_cache",
+    ".eggs",
+    // Build artifacts
+    "target",
+    "dist",
+    "build",
+    "out",
+    "_build",
+    ".build",
+    // IDE / Editor
+    ".idea",
+    ".vscode",
+    // OS artifacts
+    ".DS_Store",
+    "Thumbs.db",
+    // pm_encoder's own cache
+    ".pm_encoder_cache",
+];
+
+/// Name endings that are always left out
+pub const HYGIENE_WILDCARDS: &[&str] = &[".egg-info", ".swp", ".swo", ".pyc"];
+
+/// Whether any component of `path` is a hygiene exclusion
+pub fn is_hygiene_excluded(path: &Path) -> bool {
+    path.components().any(|c| {
+        let name = c.as_os_str().to_string_lossy();
+        HYGIENE_EXCLUSIONS.iter().any(|&pattern| name == pattern)
+            || HYGIENE_WILDCARDS
+                .iter()
+                .any(|&pattern| name.ends_with(pattern))
+    })
+}
+
+/// Whether the single (non-negated) glob `pattern` matches `path`, one of
+/// its components or one of its parent paths
+///
+/// So `.git` matches `.git/config` and `*.pyc` matches `src/a.pyc`.
+pub fn pattern_matches(path: &str, pattern: &str, case_insensitive: bool) -> bool {
+    let Ok(glob) = GlobBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+    else {
+        return false;
+    };
+    let matcher = glob.compile_matcher();
+
+    if matcher.is_match(path) {
+        return true;
+    }
+    let parts: Vec<&str> = path.split('/').collect();
+    (0..parts.len())
+        .any(|i| matcher.is_match(parts[i]) || (i > 0 && matcher.is_match(parts[..=i].join("/"))))
+}
+
+/// Whether the single (non-negated) `pattern` matches `path` in `style`
+///
+/// `is_dir` only matters to `v2` directory-only patterns.
+pub fn style_matches(path: &str, pattern: &str, style: PatternStyle, is_dir: bool) -> bool {
+    match style.syntax {
+        PatternSyntax::V1 => pattern_matches(path, pattern, style.case_insensitive),
+        PatternSyntax::V2 => matches_v2(path, pattern, is_dir, style.case_insensitive),
+    }
+}
+
+/// The last of `patterns` matching `path`, with its `!` if negated
+pub fn deciding_pattern<'a>(
+    path: &str,
+    patterns: &'a [String],
+    style: PatternStyle,
+    is_dir: bool,
+) -> Option<&'a str> {
+    // Last match wins, so scan from the end
+    patterns.iter().rev().map(String::as_str).find(|pattern| {
+        let pattern = pattern.strip_prefix('!').unwrap_or(pattern);
+        style_matches(path, pattern, style, is_dir)
+    })
+}
+
+/// Verdict of the last of `patterns` matching `path`: `Some(true)` for a
+/// plain pattern, `Some(false)` for a `!` negation, `None` for no match
+///
+/// The `None` lets callers layer pattern lists.
+pub fn pattern_verdict(
+    path: &str,
+    patterns: &[String],
+    style: PatternStyle,
+    is_dir: bool,
+) -> Option<bool> {
+    deciding_pattern(path, patterns, style, is_dir).map(|pattern| !pattern.starts_with('!'))
+}
+
+/// Whether `patterns` leave file `path` out, with gitignore precedence
+///
+/// `["target", "!target/doc/*.md"]` matches `target/debug/app` but not
+/// `target/doc/index.md`.
+pub fn matches_patterns(path: &str, patterns: &[String], style: PatternStyle) -> bool {
+    pattern_verdict(path, patterns, style, false).unwrap_or(false)
+}
+
+/// Whether a `!` pattern could re-include something beneath directory `dir`
+///
+/// Only negations containing a `/` reach into an ignored (or hygiene-excluded)
+/// directory; slash-less ones like `!*.md` apply where the walk already goes,
+/// so a single re-include doesn't stop every ignored tree from being pruned.
+pub fn negation_reaches_into(dir: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| {
+        let Some(negated) = pattern.strip_prefix('!') else {
+            return false;
+        };
+        let negated = negated.trim_start_matches('/');
+        if !negated.contains('/') {
+            return false;
+        }
+        // Literal prefix up to the first glob metacharacter
+        let literal = &negated[..negated.find(['*', '?', '[', '{']).unwrap_or(negated.len())];
+        literal.starts_with(&format!("{}/", dir)) || dir.starts_with(literal)
+    })
+}
+
+/// How a walk treats ignore files, links and patterns
+///
+/// The defaults are pm_encoder's: ignore files apply, links are followed,
+/// and `v1` patterns ignore case where the file system does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Apply `.gitignore`, `.ignore` and the global git excludes
+    pub respect_gitignore: bool,
+    /// Descend into linked directories; loops are detected and skipped
+    pub follow_links: bool,
+    /// How the ignore patterns are matched
+    pub pattern_style: PatternStyle,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            respect_gitignore: true,
+            follow_links: true,
+            pattern_style: PatternStyle {
+                syntax: PatternSyntax::V1,
+                case_insensitive: CASE_INSENSITIVE_FS,
+            },
+        }
+    }
+}
+
+/// A walker over `root` configured by `options`, before any filtering
+pub fn walk_builder(root: &Path, options: &WalkOptions) -> ignore::WalkBuilder {
+    // hidden(false): dotfiles are governed by the patterns, not the walker
+    // require_git(false): .gitignore applies outside git checkouts too
+    let respect = options.respect_gitignore;
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder
+        .follow_links(options.follow_links)
+        .hidden(false)
+        .git_ignore(respect)
+        .git_global(respect)
+        .git_exclude(respect)
+        .ignore(respect)
+        .parents(respect)
+        .require_git(false);
+    builder
+}
+
+/// Why the walk leaves out a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exclusion {
+    /// A hygiene exclusion no `!` pattern re-includes
+    Hygiene,
+    /// A directory the given ignore pattern prunes
+    IgnorePattern(String),
+}
+
+/// Why the walk leaves out `path` (`rel`, relative to the walk root), if it
+/// does
+///
+/// Hygiene exclusions, unless a `!` pattern re-includes them, then for
+/// directories the ignore patterns, unless a `!` pattern reaches into them;
+/// files are matched against the patterns once the walk reaches them.
+pub fn walk_exclusion(
+    path: &Path,
+    rel: &str,
+    is_dir: bool,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+) -> Option<Exclusion> {
+    if is_hygiene_excluded(path) {
+        let reincluded = if is_dir {
+            negation_reaches_into(rel, ignore_patterns)
+        } else {
+            ignore_patterns.iter().any(|p| {
+                p.strip_prefix('!')
+                    .is_some_and(|negated| style_matches(rel, negated, style, false))
+            })
+        };
+        if !reincluded {
+            return Some(Exclusion::Hygiene);
+        }
+    }
+    if !is_dir {
+        return None;
+    }
+    let pattern = deciding_pattern(rel, ignore_patterns, style, true)?;
+    (!pattern.starts_with('!') && !negation_reaches_into(rel, ignore_patterns))
+        .then(|| Exclusion::IgnorePattern(pattern.to_string()))
+}
+
+/// `path` relative to `root`, with `/` separators
+pub fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    Some(rel.to_str()?.replace('\\', "/"))
+}
+
+/// The files below `root` the shared rules admit, sorted by path
+///
+/// Entries that can't be read (broken links, permission errors) are
+/// skipped; only a missing `root` is an error.
+pub fn walk_files(
+    root: &Path,
+    options: &WalkOptions,
+    ignore_patterns: &[String],
+) -> Result<Vec<PathBuf>, String> {
+    if !root.exists() {
+        return Err(format!("Directory not found: {}", root.display()));
+    }
+    let walk_root = root.to_path_buf();
+    let patterns = ignore_patterns.to_vec();
+    let style = options.pattern_style;
+    let mut builder = walk_builder(root, options);
+    builder.filter_entry(move |entry| {
+        let path = entry.path();
+        if path == walk_root {
+            return true;
+        }
+        let Some(rel) = relative_path(&walk_root, path) else {
+            return false;
+        };
+        let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+        walk_exclusion(path, &rel, is_dir, &patterns, style).is_none()
+    });
+
+    let mut files: Vec<PathBuf> = builder
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .map(ignore::DirEntry::into_path)
+        .filter(|path| {
+            relative_path(root, path)
+                .is_some_and(|rel| !matches_patterns(&rel, ignore_patterns, style))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_patterns() {
+        let v1 = PatternStyle::default();
+        let ignore = patterns(&["target", "*.log", "!target/doc/*.md"]);
+        assert!(matches_patterns("target/debug/app", &ignore, v1));
+        assert!(matches_patterns("logs/server.log", &ignore, v1));
+        assert!(!matches_patterns("target/doc/index.md", &ignore, v1));
+        assert!(!matches_patterns("src/main.rs", &ignore, v1));
+        assert!(negation_reaches_into("target", &ignore));
+        assert!(!negation_reaches_into("logs", &ignore));
+        assert!(is_hygiene_excluded(Path::new("web/node_modules/a.js")));
+        assert!(is_hygiene_excluded(Path::new("pkg.egg-info/PKG-INFO")));
+        assert!(!is_hygiene_excluded(Path::new("src/targets.rs")));
+    }
+
+    #[test]
+    fn test_walk_exclusion_styles() {
+        let v2 = PatternStyle {
+            syntax: PatternSyntax::V2,
+            case_insensitive: false,
+        };
+        let exclusion = |rel: &str, is_dir: bool, ignore: &[&str], style: PatternStyle| {
+            walk_exclusion(Path::new(rel), rel, is_dir, &patterns(ignore), style)
+        };
+        assert_eq!(
+            exclusion("src", true, &["/src"], v2),
+            Some(Exclusion::IgnorePattern("/src".to_string()))
+        );
+        assert_eq!(exclusion("lib/src", true, &["/src"], v2), None);
+
+        let folded = PatternStyle {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            exclusion("Logs", true, &["logs"], PatternStyle::default()),
+            None
+        );
+        assert!(exclusion("Logs", true, &["logs"], folded).is_some());
+
+        // Re-including a hygiene exclusion follows the style too
+        let readme = "node_modules/README.MD";
+        assert_eq!(
+            exclusion(readme, false, &[], folded),
+            Some(Exclusion::Hygiene)
+        );
+        assert_eq!(
+            exclusion(readme, false, &["!*.md"], PatternStyle::default()),
+            Some(Exclusion::Hygiene)
+        );
+        assert_eq!(exclusion(readme, false, &["!*.md"], folded), None);
+    }
+
+    #[test]
+    fn test_walk_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["src", "node_modules/pkg", "logs", "target/doc"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/main.rs",
+            "src/generated.rs",
+            ".env.example",
+            "node_modules/pkg/index.js",
+            "logs/a.log",
+            "target/doc/index.md",
+            "target/doc/app.js",
+        ] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "generated.rs\n").unwrap();
+
+        let names = |options: &WalkOptions, ignore: &[String]| -> Vec<String> {
+            walk_files(root, options, ignore)
+                .unwrap()
+                .iter()
+                .map(|p| relative_path(root, p).unwrap())
+                .collect()
+        };
+        let ignore = patterns(&["*.log", "!target/doc/*.md"]);
+        assert_eq!(
+            names(&WalkOptions::default(), &ignore),
+            [
+                ".env.example",
+                ".gitignore",
+                "src/main.rs",
+                "target/doc/index.md"
+            ]
+        );
+        let unignored = WalkOptions {
+            respect_gitignore: false,
+            ..Default::default()
+        };
+        assert!(names(&unignored, &[]).contains(&"src/generated.rs".to_string()));
+
+        assert!(walk_files(&root.join("missing"), &WalkOptions::default(), &[]).is_err());
+    }
+}
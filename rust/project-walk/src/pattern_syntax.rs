@@ -0,0 +1,138 @@
+//! Pattern syntax versions
+//!
+//! The original (`v1`) matcher tries a pattern against the whole path, every
+//! component and every leading part of it, with `*` crossing `/`. That
+//! makes a bare `src` match `lib/src/x.rs` and leaves no way to anchor an
+//! include at the root. `v2` follows gitignore instead:
+//!
+//! - a leading `/` anchors a pattern at the root (`/src/**`), as does a `/`
+//!   anywhere but the end (`docs/*.md`); other patterns match a name at any
+//!   depth
+//! - a trailing `/` matches directories only (`build/`)
+//! - `*` and `?` stop at `/`, while `**` spans directories
+//!
+//! Either way a pattern matching a directory covers everything below it.
+//! In pm_encoder `pattern_syntax = "v2"` in `.pm_encoder_config.json` opts
+//! in; each config file's patterns are read in that file's syntax.
+//!
+//! Patterns ignore case where the file system usually does (Windows,
+//! macOS), so `*.md` catches `README.MD` there; `case_insensitive_patterns`
+//! overrides that either way.
+
+use globset::GlobBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Whether patterns ignore case unless configured: where file systems do
+pub const CASE_INSENSITIVE_FS: bool = cfg!(any(windows, target_os = "macos"));
+
+/// How ignore and include patterns are matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternSyntax {
+    /// Component and prefix matching, as the Python encoder does
+    #[default]
+    V1,
+    /// gitignore semantics: anchoring, directory-only patterns, `**`
+    V2,
+}
+
+impl PatternSyntax {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+}
+
+/// Pattern syntax and case handling, as threaded through the walk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PatternStyle {
+    pub syntax: PatternSyntax,
+    pub case_insensitive: bool,
+}
+
+/// Whether v2 `pattern` (without its `!`) matches `path` or one of its
+/// parent directories; `is_dir` says whether `path` itself is a directory
+pub fn matches_v2(path: &str, pattern: &str, is_dir: bool, case_insensitive: bool) -> bool {
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let anchored = pattern.contains('/');
+    let Ok(glob) = GlobBuilder::new(pattern.trim_start_matches('/'))
+        .literal_separator(true)
+        .case_insensitive(case_insensitive)
+        .build()
+    else {
+        return false;
+    };
+    let matcher = glob.compile_matcher();
+
+    let parents = path.match_indices('/').map(|(i, _)| (&path[..i], true));
+    parents
+        .chain(std::iter::once((path, is_dir)))
+        .filter(|&(_, dir)| dir || !dir_only)
+        .any(|(candidate, _)| {
+            if anchored {
+                matcher.is_match(candidate)
+            } else {
+                let name = candidate.rsplit('/').next().unwrap_or(candidate);
+                matcher.is_match(name)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchoring() {
+        assert!(matches_v2("src/main.rs", "/src/**", false, false));
+        assert!(!matches_v2("lib/src/main.rs", "/src/**", false, false));
+        // A bare name matches at any depth, but only whole names
+        assert!(matches_v2("lib/src/main.rs", "src", false, false));
+        assert!(!matches_v2("mysrc/main.rs", "src", false, false));
+        // A middle slash anchors too
+        assert!(matches_v2("docs/guide.md", "docs/*.md", false, false));
+        assert!(!matches_v2("site/docs/guide.md", "docs/*.md", false, false));
+    }
+
+    #[test]
+    fn test_directory_only() {
+        assert!(matches_v2("build/out.js", "build/", false, false));
+        assert!(matches_v2("build", "build/", true, false));
+        // A file named like the directory is not matched
+        assert!(!matches_v2("scripts/build", "build/", false, false));
+        assert!(matches_v2("scripts/build", "build", false, false));
+    }
+
+    #[test]
+    fn test_double_star() {
+        assert!(!matches_v2("src/a/b.rs", "/src/*.rs", false, false));
+        assert!(matches_v2("src/a/b.rs", "/src/**/*.rs", false, false));
+        assert!(matches_v2("src/b.rs", "/src/**/*.rs", false, false));
+        assert!(matches_v2(
+            "a/b/fixtures/x.json",
+            "**/fixtures",
+            false,
+            false
+        ));
+        assert!(matches_v2("a/b/c.rs", "*.rs", false, false));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        assert!(!matches_v2("docs/README.MD", "*.md", false, false));
+        assert!(matches_v2("docs/README.MD", "*.md", false, true));
+        assert!(matches_v2("Docs/guide.md", "/docs/", false, true));
+    }
+
+    #[test]
+    fn test_syntax_from_config() {
+        let syntax: PatternSyntax = serde_json::from_str(r#""v2""#).unwrap();
+        assert_eq!(syntax, PatternSyntax::V2);
+        assert_eq!(PatternSyntax::default().name(), "v1");
+    }
+}
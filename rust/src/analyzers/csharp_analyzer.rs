@@ -0,0 +1,391 @@
+use super::guard::RegexBudget;
+use super::{signature_end, AnalysisResult, LanguageAnalyzer};
+use crate::python_style_split;
+/// C# source code analyzer
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Leading attributes and modifiers of a member declaration
+const MEMBER_PREFIX: &str = r"^\s*(?:\[[^\]]*\]\s*)*((?:(?:public|private|protected|internal|static|abstract|sealed|virtual|override|async|partial|extern|unsafe|new|readonly|required|file)\s+)*)";
+
+/// A type as written in a declaration: `int`, `List<string>`, `byte[]`, `T?`
+const TYPE: &str = r"[\w.]+(?:<[^()]*?>)?\??(?:\[[,\s]*\])*\??";
+
+lazy_static! {
+    static ref USING_PATTERN: Regex =
+        Regex::new(r"^\s*(?:global\s+)?using\s+(?:static\s+)?([\w.]+(?:\s*=\s*[\w.<>, ]+)?)\s*;")
+            .unwrap();
+    static ref NAMESPACE_PATTERN: Regex = Regex::new(r"^\s*namespace\s+([\w.]+)").unwrap();
+    static ref ATTRIBUTE_PATTERN: Regex =
+        Regex::new(r"^\s*\[\s*(?:\w+\s*:\s*)?[A-Z][\w.]*\s*(?:\(.*\))?\s*(?:,.*)?\]\s*(?://.*)?$")
+            .unwrap();
+    static ref TYPE_PATTERN: Regex = Regex::new(&format!(
+        r"{}(?:record\s+(?:class|struct)|class|struct|interface|enum|record)\s+(\w+)",
+        MEMBER_PREFIX
+    ))
+    .unwrap();
+    static ref METHOD_PATTERN: Regex = Regex::new(&format!(
+        r"{}(?:({})\s+)?(\w+)\s*(?:<[^()]*>)?\s*\(",
+        MEMBER_PREFIX, TYPE
+    ))
+    .unwrap();
+    static ref PROPERTY_PATTERN: Regex = Regex::new(&format!(
+        r"{}({})\s+(\w+)\s*(\{{\s*(?:get|set|init|private|protected|internal)|=>|$)",
+        MEMBER_PREFIX, TYPE
+    ))
+    .unwrap();
+    static ref MARKER_PATTERN: Regex =
+        Regex::new(r"//\s*(TODO|FIXME|XXX|HACK|NOTE):?\s*(.+)").unwrap();
+}
+
+/// Words that start statements, not declarations, when they precede a
+/// name and a parenthesis (`return Foo(x);`, `else if (x)`)
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "return",
+    "await",
+    "throw",
+    "else",
+    "if",
+    "while",
+    "for",
+    "foreach",
+    "switch",
+    "using",
+    "lock",
+    "yield",
+    "case",
+    "var",
+    "goto",
+    "catch",
+    "when",
+    "in",
+    "is",
+    "as",
+    "typeof",
+    "nameof",
+    "sizeof",
+    "default",
+    "new",
+    "out",
+    "ref",
+    "do",
+    "fixed",
+    "checked",
+    "unchecked",
+];
+
+/// Besides the usual metadata, reports the lines smart and structure
+/// truncation keep as `structure_ranges`: usings, namespaces, attributes,
+/// type declarations, method signatures (to the closing parenthesis when
+/// they span lines) and properties, expression-bodied members included.
+pub struct CSharpAnalyzer;
+
+impl Default for CSharpAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CSharpAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Analyze C# source code lines
+    fn analyze_lines(
+        &self,
+        lines: &[&str],
+        file_path: &str,
+        budget: &RegexBudget,
+    ) -> AnalysisResult {
+        let mut result = AnalysisResult::new("C#");
+        let mut types = Vec::new();
+        let mut functions = Vec::new();
+        let mut usings = Vec::new();
+        let mut entry_points = Vec::new();
+        let mut markers = Vec::new();
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let line_num = i + 1;
+            i += 1;
+            if budget.exhausted() {
+                result.budget_exceeded = true;
+                break;
+            }
+            if !budget.admits(line) {
+                continue;
+            }
+
+            // Markers (TODO, FIXME, etc.)
+            if let Some(caps) = MARKER_PATTERN.captures(line) {
+                if let Some(marker_type) = caps.get(1) {
+                    markers.push(format!("{} (line {})", marker_type.as_str(), line_num));
+                }
+            }
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with("/*") {
+                continue;
+            }
+
+            // Usings
+            if let Some(caps) = USING_PATTERN.captures(line) {
+                usings.push(caps[1].trim().to_string());
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Namespaces, block or file-scoped
+            if NAMESPACE_PATTERN.is_match(line) || ATTRIBUTE_PATTERN.is_match(line) {
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Classes, structs, interfaces, records and enums
+            if let Some(caps) = TYPE_PATTERN.captures(line) {
+                types.push(caps[2].to_string());
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Methods and constructors
+            if let Some(caps) = METHOD_PATTERN.captures(line) {
+                let modifiers = caps.get(1).map_or("", |m| m.as_str());
+                let name = &caps[3];
+                let is_method = match caps.get(2) {
+                    Some(return_type) => {
+                        !is_statement_keyword(return_type.as_str())
+                            && !is_statement_keyword(modifiers)
+                    }
+                    // A constructor has no return type but always a modifier
+                    None => {
+                        !modifiers.trim().is_empty()
+                            && modifiers.trim() != "new"
+                            && !is_statement_keyword(name)
+                    }
+                };
+                if is_method {
+                    functions.push(name.to_string());
+                    let end = signature_end(lines, line_num);
+                    result.structure_ranges.push((line_num, end));
+                    i = end;
+                    if name == "Main" && modifiers.contains("static") {
+                        entry_points.push("static Main".to_string());
+                        result.critical_sections.push((line_num, line_num + 20));
+                    }
+                    continue;
+                }
+            }
+
+            // Properties: auto, with accessor blocks, or expression-bodied
+            if let Some(caps) = PROPERTY_PATTERN.captures(line) {
+                let modifiers = caps.get(1).map_or("", |m| m.as_str());
+                let accessors = caps.get(4).map_or("", |m| m.as_str());
+                // A bare `Type Name` line needs a modifier not to be a statement
+                if !is_statement_keyword(&caps[2])
+                    && (!accessors.is_empty() || !modifiers.trim().is_empty())
+                {
+                    result.structure_ranges.push((line_num, line_num));
+                }
+            }
+        }
+
+        // Categorize based on content
+        let category = if !entry_points.is_empty() {
+            "application"
+        } else if file_path.to_lowercase().contains("test") || file_path.contains("tests/") {
+            "test"
+        } else {
+            "library"
+        };
+
+        result.classes = types;
+
+        // Limit to first 20 functions
+        result.functions = functions.into_iter().take(20).collect();
+
+        // Limit to first 10 imports
+        result.imports = usings.into_iter().take(10).collect();
+
+        result.entry_points = entry_points;
+
+        // Limit to first 5 markers
+        result.markers = markers.into_iter().take(5).collect();
+
+        result.category = category.to_string();
+
+        result
+    }
+}
+
+/// Whether `text` starts with a statement keyword
+fn is_statement_keyword(text: &str) -> bool {
+    text.split_whitespace()
+        .next()
+        .is_some_and(|word| STATEMENT_KEYWORDS.contains(&word))
+}
+
+impl LanguageAnalyzer for CSharpAnalyzer {
+    fn analyze(&self, content: &str, file_path: &str) -> AnalysisResult {
+        let lines: Vec<&str> = python_style_split(content);
+        self.analyze_lines(&lines, file_path, &RegexBudget::new())
+    }
+
+    fn supported_extensions(&self) -> Vec<&str> {
+        vec![".cs"]
+    }
+
+    fn language_name(&self) -> &str {
+        "C#"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE: &str = r#"using System;
+using System.Collections.Generic;
+
+namespace Shop.Orders
+{
+    /// <summary>Keeps orders.</summary>
+    [Serializable]
+    [Obsolete("Use OrderStore")]
+    public sealed class OrderService : IOrderService
+    {
+        private readonly List<Order> _orders = new();
+
+        public string Name { get; set; } = "orders";
+
+        public int Count => _orders.Count;
+
+        public decimal Total
+        {
+            get { return 0m; }
+        }
+
+        public OrderService(ILogger logger)
+        {
+            if (logger == null)
+            {
+                throw new ArgumentNullException(nameof(logger));
+            }
+            Console.WriteLine("ready");
+        }
+
+        [HttpGet("{id}")]
+        public async Task<Order?> FindAsync(
+            int id,
+            CancellationToken token)
+        {
+            var order = await Load(id);
+            return Convert(order);
+        }
+
+        public override string ToString() => $"{Name} ({Count})";
+    }
+
+    public record Order(int Id, decimal Amount);
+
+    internal enum Status { Open, Closed }
+}
+"#;
+
+    fn kept_lines(result: &AnalysisResult) -> Vec<String> {
+        let lines: Vec<&str> = SERVICE.lines().collect();
+        result
+            .structure_ranges
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .map(|n| lines[n - 1].trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_declarations() {
+        let result = CSharpAnalyzer::new().analyze(SERVICE, "src/OrderService.cs");
+
+        assert_eq!(result.language, "C#");
+        assert_eq!(result.classes, vec!["OrderService", "Order", "Status"]);
+        assert_eq!(
+            result.functions,
+            vec!["OrderService", "FindAsync", "ToString"]
+        );
+        assert_eq!(result.imports, vec!["System", "System.Collections.Generic"]);
+        assert_eq!(result.category, "library");
+    }
+
+    #[test]
+    fn test_structure_ranges() {
+        let result = CSharpAnalyzer::new().analyze(SERVICE, "src/OrderService.cs");
+
+        assert_eq!(
+            kept_lines(&result),
+            vec![
+                "using System;",
+                "using System.Collections.Generic;",
+                "namespace Shop.Orders",
+                "[Serializable]",
+                "[Obsolete(\"Use OrderStore\")]",
+                "public sealed class OrderService : IOrderService",
+                "public string Name { get; set; } = \"orders\";",
+                "public int Count => _orders.Count;",
+                "public decimal Total",
+                "public OrderService(ILogger logger)",
+                "[HttpGet(\"{id}\")]",
+                "public async Task<Order?> FindAsync(",
+                "int id,",
+                "CancellationToken token)",
+                "public override string ToString() => $\"{Name} ({Count})\";",
+                "public record Order(int Id, decimal Amount);",
+                "internal enum Status { Open, Closed }",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statements_are_not_members() {
+        let content = "return Convert(order);\nelse if (ready)\nnew Foo(1);\nFoo(1);\nvar x = Foo(2);\nyield return Bar(3);\nawait Task.Delay(1);\n";
+        let result = CSharpAnalyzer::new().analyze(content, "Body.cs");
+
+        assert!(result.functions.is_empty());
+        assert!(result.structure_ranges.is_empty());
+    }
+
+    #[test]
+    fn test_main_entry_point() {
+        let content =
+            "class Program\n{\n    static async Task Main(string[] args)\n    {\n    }\n}\n";
+        let result = CSharpAnalyzer::new().analyze(content, "Program.cs");
+
+        assert_eq!(result.entry_points, vec!["static Main"]);
+        assert_eq!(result.critical_sections, vec![(3, 23)]);
+        assert_eq!(result.category, "application");
+    }
+
+    #[test]
+    fn test_file_scoped_namespace_and_aliases() {
+        let content = "global using Xunit;\nusing Json = System.Text.Json.JsonSerializer;\nnamespace Shop.Tests;\n\npublic class OrderTests\n{\n    [Fact]\n    public void Totals() { }\n}\n";
+        let result = CSharpAnalyzer::new().analyze(content, "tests/OrderTests.cs");
+
+        assert_eq!(
+            result.imports,
+            vec!["Xunit", "Json = System.Text.Json.JsonSerializer"]
+        );
+        assert_eq!(
+            result.structure_ranges,
+            vec![(1, 1), (2, 2), (3, 3), (5, 5), (7, 7), (8, 8)]
+        );
+        assert_eq!(result.category, "test");
+    }
+
+    #[test]
+    fn test_regex_budget_exceeded() {
+        let budget = RegexBudget::with_limit(0);
+        let result = CSharpAnalyzer::new().analyze_lines(&["class A {}"], "A.cs", &budget);
+        assert!(result.budget_exceeded);
+        assert!(result.classes.is_empty());
+    }
+}
@@ -4,6 +4,7 @@
 //! Instead of implementing separate analyzers for each language, we configure one
 //! generic analyzer with language-specific patterns.
 
+use super::guard::RegexBudget;
 use super::{AnalysisResult, LanguageAnalyzer};
 use crate::python_style_split;
 use lazy_static::lazy_static;
@@ -96,7 +97,12 @@ impl GenericAnalyzer {
     }
 
     /// Analyze lines using configured patterns
-    fn analyze_lines(&self, lines: &[&str], file_path: &str) -> AnalysisResult {
+    fn analyze_lines(
+        &self,
+        lines: &[&str],
+        file_path: &str,
+        budget: &RegexBudget,
+    ) -> AnalysisResult {
         let mut result = AnalysisResult::new(&self.config.language_name);
         let mut classes = Vec::new();
         let mut functions = Vec::new();
@@ -107,6 +113,13 @@ impl GenericAnalyzer {
 
         for (i, line) in lines.iter().enumerate() {
             let line_num = i + 1;
+            if budget.exhausted() {
+                result.budget_exceeded = true;
+                break;
+            }
+            if !budget.admits(line) {
+                continue;
+            }
 
             // Class detection
             if let Some(ref pattern) = self.config.class_pattern {
@@ -193,7 +206,7 @@ impl GenericAnalyzer {
 impl LanguageAnalyzer for GenericAnalyzer {
     fn analyze(&self, content: &str, file_path: &str) -> AnalysisResult {
         let lines: Vec<&str> = python_style_split(content);
-        self.analyze_lines(&lines, file_path)
+        self.analyze_lines(&lines, file_path, &RegexBudget::new())
     }
 
     fn supported_extensions(&self) -> Vec<&str> {
@@ -295,6 +308,23 @@ pub fn create_yaml_analyzer() -> GenericAnalyzer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analyzers::guard::MAX_MATCH_LINE_LEN;
+
+    #[test]
+    fn test_regex_budget() {
+        let analyzer = create_python_analyzer();
+        let long = format!("def {}(): pass", "x".repeat(MAX_MATCH_LINE_LEN));
+        let lines = ["def short(): pass", long.as_str()];
+
+        let result = analyzer.analyze_lines(&lines, "a.py", &RegexBudget::new());
+        assert_eq!(result.functions, vec!["short"]);
+        assert!(!result.budget_exceeded);
+
+        let budget = RegexBudget::with_limit(0);
+        let result = analyzer.analyze_lines(&lines, "a.py", &budget);
+        assert!(result.budget_exceeded);
+        assert!(result.functions.is_empty());
+    }
 
     #[test]
     fn test_python_analyzer() {
@@ -0,0 +1,90 @@
+//! Bounds on signature regex matching
+//!
+//! The analyzers and the spectrograph's fallback patterns match line by line
+//! over whatever the walker hands them. The `regex` crate never backtracks,
+//! but its matching time still grows with pattern and line size, so a
+//! minified bundle or a file of megabyte-long generated lines can hold a
+//! thread for seconds. Lines longer than [`MAX_MATCH_LINE_LEN`] are skipped,
+//! and each file gets [`FILE_BUDGET`] bytes of matching; a file that runs out
+//! is treated as plain text, with a warning, rather than half-analyzed.
+//!
+//! The budget counts bytes rather than time so that the same input is always
+//! analyzed the same way, whatever the machine's load (`--frozen` output
+//! stays byte-identical).
+
+use std::cell::Cell;
+
+/// Lines longer than this (in bytes) are not matched against signatures
+pub const MAX_MATCH_LINE_LEN: usize = 4096;
+
+/// Line bytes each file may match: twice [`crate::DEFAULT_MAX_FILE_SIZE`],
+/// so every line of a file within the default size limit can be matched twice
+pub const FILE_BUDGET: usize = 2 * crate::DEFAULT_MAX_FILE_SIZE as usize;
+
+/// The matching left for one file
+#[derive(Debug, Clone)]
+pub struct RegexBudget {
+    spent: Cell<usize>,
+    limit: usize,
+}
+
+impl Default for RegexBudget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexBudget {
+    /// A budget of [`FILE_BUDGET`] bytes
+    pub fn new() -> Self {
+        Self::with_limit(FILE_BUDGET)
+    }
+
+    /// A budget of `limit` bytes; with 0 it starts out exhausted
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            spent: Cell::new(0),
+            limit,
+        }
+    }
+
+    /// The bytes this budget allows in total, spent or not
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Whether `line` is short enough to match; if so its bytes are spent
+    pub fn admits(&self, line: &str) -> bool {
+        if line.len() > MAX_MATCH_LINE_LEN {
+            return false;
+        }
+        self.spent.set(self.spent.get() + line.len() + 1);
+        true
+    }
+
+    /// Whether the budget is spent
+    pub fn exhausted(&self) -> bool {
+        self.spent.get() >= self.limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget() {
+        let budget = RegexBudget::new();
+        assert!(!budget.exhausted());
+        assert!(budget.admits("fn main() {}"));
+        assert!(!budget.admits(&"x".repeat(MAX_MATCH_LINE_LEN + 1)));
+        assert!(RegexBudget::with_limit(0).exhausted());
+
+        // Spending is counted per admitted line, newline included
+        let budget = RegexBudget::with_limit(10);
+        assert!(budget.admits("abcd"));
+        assert!(!budget.exhausted());
+        assert!(budget.admits("abcd"));
+        assert!(budget.exhausted());
+    }
+}
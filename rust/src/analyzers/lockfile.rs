@@ -0,0 +1,429 @@
+//! Package manager lockfiles
+//!
+//! A lockfile runs to thousands of lines of hashes, sources and dependency
+//! edges, while what a reader wants from it is which packages are pinned at
+//! which versions. [`Lockfile::parse`] reads that list out of the common
+//! formats, so [`LockfilePolicy::Summary`] can emit it instead of the file.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What the run does with lockfiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockfilePolicy {
+    /// Emit the dependency list, one `name version` per line
+    #[default]
+    Summary,
+    /// Emit them like any other file
+    Keep,
+}
+
+impl LockfilePolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Summary => "summary",
+            Self::Keep => "keep",
+        }
+    }
+}
+
+/// Lockfile formats, by file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// `[[package]]` tables with `name` and `version` keys
+    TomlPackages,
+    Npm,
+    Yarn,
+    Pnpm,
+    Pipenv,
+    Composer,
+    Bundler,
+    GoSum,
+    Nix,
+}
+
+/// Lockfile names, the package manager writing each, and its format
+const LOCKFILES: &[(&str, &str, Format)] = &[
+    ("Cargo.lock", "Cargo", Format::TomlPackages),
+    ("poetry.lock", "Poetry", Format::TomlPackages),
+    ("uv.lock", "uv", Format::TomlPackages),
+    ("package-lock.json", "npm", Format::Npm),
+    ("npm-shrinkwrap.json", "npm", Format::Npm),
+    ("yarn.lock", "Yarn", Format::Yarn),
+    ("pnpm-lock.yaml", "pnpm", Format::Pnpm),
+    ("Pipfile.lock", "Pipenv", Format::Pipenv),
+    ("composer.lock", "Composer", Format::Composer),
+    ("Gemfile.lock", "Bundler", Format::Bundler),
+    ("go.sum", "Go modules", Format::GoSum),
+    ("flake.lock", "Nix", Format::Nix),
+];
+
+fn lookup(file_path: &str) -> Option<&'static (&'static str, &'static str, Format)> {
+    let name = file_path.rsplit(['/', '\\']).next()?;
+    LOCKFILES.iter().find(|(file, _, _)| *file == name)
+}
+
+/// Whether `file_path` names a lockfile [`Lockfile::parse`] reads
+pub fn is_lockfile(file_path: &str) -> bool {
+    lookup(file_path).is_some()
+}
+
+/// One pinned package
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dependency {
+    pub name: String,
+    /// Empty when the lockfile pins the package by source alone
+    pub version: String,
+}
+
+/// The packages a lockfile pins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    /// The package manager that wrote it: `Cargo`, `npm`, `Poetry` ...
+    pub manager: &'static str,
+    /// Sorted by name and version, without duplicates
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Lockfile {
+    /// Read the packages out of `content`; `None` when `file_path` isn't a
+    /// known lockfile, or the content doesn't parse or names no package
+    pub fn parse(content: &str, file_path: &str) -> Option<Self> {
+        let (_, manager, format) = lookup(file_path)?;
+        let mut dependencies = match format {
+            Format::TomlPackages => toml_packages(content),
+            Format::Npm => npm(&serde_json::from_str(content).ok()?),
+            Format::Yarn => yarn(content),
+            Format::Pnpm => pnpm(content),
+            Format::Pipenv => pipenv(&serde_json::from_str(content).ok()?),
+            Format::Composer => composer(&serde_json::from_str(content).ok()?),
+            Format::Bundler => bundler(content),
+            Format::GoSum => go_sum(content),
+            Format::Nix => nix(&serde_json::from_str(content).ok()?),
+        };
+        dependencies.retain(|d| !d.name.is_empty());
+        if dependencies.is_empty() {
+            return None;
+        }
+        dependencies.sort();
+        dependencies.dedup();
+        Some(Self {
+            manager,
+            dependencies,
+        })
+    }
+}
+
+fn dependency(name: &str, version: &str) -> Dependency {
+    Dependency {
+        name: name.to_string(),
+        version: version.to_string(),
+    }
+}
+
+/// The string value of a `key = "value"` line
+fn toml_string<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let value = line.strip_prefix(key)?.trim_start().strip_prefix('=')?;
+    Some(value.trim().trim_matches('"'))
+}
+
+/// `Cargo.lock`, `poetry.lock`, `uv.lock`
+fn toml_packages(content: &str) -> Vec<Dependency> {
+    let mut packages: Vec<Dependency> = Vec::new();
+    // Inside a `[[package]]` table, before any of its sub-tables
+    let mut in_package = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[[package]]";
+            if in_package {
+                packages.push(dependency("", ""));
+            }
+        } else if let (true, Some(package)) = (in_package, packages.last_mut()) {
+            if let Some(name) = toml_string(line, "name") {
+                package.name = name.to_string();
+            } else if let Some(version) = toml_string(line, "version") {
+                package.version = version.to_string();
+            }
+        }
+    }
+    packages
+}
+
+/// `package-lock.json`: `packages` keyed by install path (v2, v3), or
+/// nested `dependencies` (v1)
+fn npm(lock: &Value) -> Vec<Dependency> {
+    fn nested(dependencies: &Value, out: &mut Vec<Dependency>) {
+        for (name, entry) in dependencies.as_object().into_iter().flatten() {
+            let version = entry["version"].as_str().unwrap_or("");
+            out.push(dependency(name, version));
+            nested(&entry["dependencies"], out);
+        }
+    }
+
+    let mut out = Vec::new();
+    if let Some(packages) = lock["packages"].as_object() {
+        for (path, entry) in packages {
+            // "" is the project itself
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            if let Some(version) = entry["version"].as_str() {
+                out.push(dependency(name, version));
+            }
+        }
+    } else {
+        nested(&lock["dependencies"], &mut out);
+    }
+    out
+}
+
+/// `name@range` to `name`, keeping a scope's leading `@`
+fn strip_range(spec: &str) -> &str {
+    match spec.rfind('@') {
+        Some(at) if at > 0 => &spec[..at],
+        _ => spec,
+    }
+}
+
+/// `yarn.lock`, classic (`version "1.2.3"`) and Berry (`version: 1.2.3`)
+fn yarn(content: &str) -> Vec<Dependency> {
+    let mut out: Vec<Dependency> = Vec::new();
+    let mut name = None;
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            // `"a@^1.0.0", a@^1.1.0:`; Berry's `__metadata:` is no package
+            let first = line.trim_end_matches(':').split(", ").next().unwrap_or("");
+            let spec = first.trim_matches('"');
+            name = (spec != "__metadata").then(|| strip_range(spec).to_string());
+            continue;
+        }
+        let field = line.trim_start();
+        let version = field
+            .strip_prefix("version ")
+            .or_else(|| field.strip_prefix("version: "));
+        if let (Some(name), Some(version), 2) = (&name, version, line.len() - field.len()) {
+            out.push(dependency(name, version.trim().trim_matches('"')));
+        }
+    }
+    out
+}
+
+/// `pnpm-lock.yaml`: the keys of `packages`, `/name@1.2.3` (v6),
+/// `name@1.2.3` (v9) or `/name/1.2.3` (v5)
+fn pnpm(content: &str) -> Vec<Dependency> {
+    let mut out = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        if !line.starts_with(' ') && !line.trim().is_empty() {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        let Some(key) = line.strip_prefix("  ").filter(|k| !k.starts_with(' ')) else {
+            continue;
+        };
+        let key = key
+            .trim_end()
+            .trim_end_matches(':')
+            .trim_matches(['\'', '"']);
+        if !in_packages || key.is_empty() {
+            continue;
+        }
+        // Peer dependency suffixes: `(react@18.2.0)` (v6+), `_react@18.2.0` (v5)
+        let key = key.trim_start_matches('/');
+        let key = key.split('(').next().unwrap_or(key);
+        let v5 = key
+            .rsplit_once('/')
+            .filter(|(_, version)| version.starts_with(|c: char| c.is_ascii_digit()));
+        let (name, version) = match (v5, key.rfind('@')) {
+            (Some((name, version)), _) => (name, version.split('_').next().unwrap_or(version)),
+            (None, Some(at)) if at > 0 => (&key[..at], &key[at + 1..]),
+            (None, _) => (key, ""),
+        };
+        out.push(dependency(name, version));
+    }
+    out
+}
+
+/// `Pipfile.lock`: `default` and `develop` packages, versions as `==1.2.3`
+fn pipenv(lock: &Value) -> Vec<Dependency> {
+    ["default", "develop"]
+        .iter()
+        .flat_map(|section| lock[section].as_object().into_iter().flatten())
+        .map(|(name, entry)| {
+            let version = entry["version"].as_str().unwrap_or("");
+            dependency(name, version.trim_start_matches("=="))
+        })
+        .collect()
+}
+
+/// `composer.lock`: `packages` and `packages-dev`
+fn composer(lock: &Value) -> Vec<Dependency> {
+    ["packages", "packages-dev"]
+        .iter()
+        .flat_map(|section| lock[section].as_array().into_iter().flatten())
+        .filter_map(|package| {
+            let name = package["name"].as_str()?;
+            Some(dependency(name, package["version"].as_str().unwrap_or("")))
+        })
+        .collect()
+}
+
+/// `Gemfile.lock`: the `name (1.2.3)` lines four spaces into a `specs:`
+/// block; deeper lines are their dependencies
+fn bundler(content: &str) -> Vec<Dependency> {
+    let mut out = Vec::new();
+    let mut in_specs = false;
+    for line in content.lines() {
+        let spec = line.strip_prefix("    ").filter(|s| !s.starts_with(' '));
+        match spec {
+            Some(spec) if in_specs => {
+                if let Some((name, version)) = spec.split_once(" (") {
+                    out.push(dependency(name, version.trim_end_matches(')')));
+                }
+            }
+            Some(_) => {}
+            None => in_specs = line == "  specs:",
+        }
+    }
+    out
+}
+
+/// `go.sum`: `module version hash` lines, twice per module with `/go.mod`
+fn go_sum(content: &str) -> Vec<Dependency> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (module, version) = (fields.next()?, fields.next()?);
+            Some(dependency(module, version.trim_end_matches("/go.mod")))
+        })
+        .collect()
+}
+
+/// `flake.lock`: each input with the revision it is locked at
+fn nix(lock: &Value) -> Vec<Dependency> {
+    lock["nodes"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| name.as_str() != "root")
+        .map(|(name, node)| {
+            let rev = node["locked"]["rev"].as_str().unwrap_or("");
+            dependency(name, &rev[..rev.len().min(12)])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pins(content: &str, path: &str) -> Vec<String> {
+        Lockfile::parse(content, path)
+            .unwrap()
+            .dependencies
+            .iter()
+            .map(|d| format!("{} {}", d.name, d.version))
+            .collect()
+    }
+
+    #[test]
+    fn test_toml_lockfiles() {
+        let cargo = "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n\
+                     dependencies = [\n \"serde_derive\",\n]\n\n\
+                     [[package]]\nname = \"anyhow\"\nversion = \"1.0.86\"\n\
+                     source = \"registry+https://github.com/rust-lang/crates.io-index\"\n";
+        assert_eq!(
+            pins(cargo, "Cargo.lock"),
+            ["anyhow 1.0.86", "serde 1.0.200"]
+        );
+
+        let poetry = "[[package]]\nname = \"requests\"\nversion = \"2.31.0\"\n\n\
+                      [package.dependencies]\nidna = \">=2.5\"\nversion = \"not me\"\n\n\
+                      [metadata]\nlock-version = \"2.0\"\n";
+        assert_eq!(pins(poetry, "sub/poetry.lock"), ["requests 2.31.0"]);
+        assert_eq!(
+            Lockfile::parse(cargo, "Cargo.lock").unwrap().manager,
+            "Cargo"
+        );
+    }
+
+    #[test]
+    fn test_json_lockfiles() {
+        let npm_v3 = r#"{"lockfileVersion": 3, "packages": {
+            "": {"name": "app", "version": "1.0.0"},
+            "node_modules/@babel/core": {"version": "7.24.0"},
+            "node_modules/a/node_modules/b": {"version": "2.0.0"},
+            "packages/local": {"version": "0.1.0"}}}"#;
+        assert_eq!(
+            pins(npm_v3, "package-lock.json"),
+            ["@babel/core 7.24.0", "b 2.0.0"]
+        );
+        let npm_v1 = r#"{"lockfileVersion": 1, "dependencies": {
+            "a": {"version": "1.0.0", "dependencies": {"b": {"version": "2.0.0"}}}}}"#;
+        assert_eq!(pins(npm_v1, "package-lock.json"), ["a 1.0.0", "b 2.0.0"]);
+
+        let pipfile = r#"{"default": {"django": {"version": "==5.0.1"}},
+                          "develop": {"pytest": {"version": "==8.0.0"}}}"#;
+        assert_eq!(
+            pins(pipfile, "Pipfile.lock"),
+            ["django 5.0.1", "pytest 8.0.0"]
+        );
+
+        let composer = r#"{"packages": [{"name": "monolog/monolog", "version": "3.5.0"}],
+                           "packages-dev": [{"name": "phpunit/phpunit", "version": "10.5.9"}]}"#;
+        assert_eq!(
+            pins(composer, "composer.lock"),
+            ["monolog/monolog 3.5.0", "phpunit/phpunit 10.5.9"]
+        );
+
+        let flake = r#"{"nodes": {"root": {"inputs": {}},
+            "nixpkgs": {"locked": {"rev": "0123456789abcdef0123"}}}}"#;
+        assert_eq!(pins(flake, "flake.lock"), ["nixpkgs 0123456789ab"]);
+
+        assert!(Lockfile::parse("{not json", "package-lock.json").is_none());
+        assert!(Lockfile::parse("{}", "package-lock.json").is_none());
+    }
+
+    #[test]
+    fn test_text_lockfiles() {
+        let yarn = "# yarn lockfile v1\n\n\
+                    \"@babel/code-frame@^7.0.0\", \"@babel/code-frame@^7.10.4\":\n  \
+                    version \"7.12.13\"\n  dependencies:\n    js-tokens \"^4.0.0\"\n\n\
+                    js-tokens@^4.0.0:\n  version \"4.0.0\"\n";
+        assert_eq!(
+            pins(yarn, "yarn.lock"),
+            ["@babel/code-frame 7.12.13", "js-tokens 4.0.0"]
+        );
+        let berry = "__metadata:\n  version: 8\n\n\"lodash@npm:^4.17.21\":\n  version: 4.17.21\n";
+        assert_eq!(pins(berry, "yarn.lock"), ["lodash 4.17.21"]);
+
+        let pnpm = "lockfileVersion: '9.0'\n\nimporters:\n  .:\n    dependencies:\n\n\
+                    packages:\n\n  '@babel/core@7.24.0':\n    resolution: {integrity: x}\n\n  \
+                    react-dom@18.2.0(react@18.2.0):\n    resolution: {integrity: y}\n\n\
+                    snapshots:\n\n  lodash@4.17.21: {}\n";
+        assert_eq!(
+            pins(pnpm, "pnpm-lock.yaml"),
+            ["@babel/core 7.24.0", "react-dom 18.2.0"]
+        );
+        let pnpm_v5 = "packages:\n  /@types/node/20.1.0:\n    dev: true\n  /react-dom/18.2.0_react@18.2.0:\n    dev: false\n";
+        assert_eq!(
+            pins(pnpm_v5, "pnpm-lock.yaml"),
+            ["@types/node 20.1.0", "react-dom 18.2.0"]
+        );
+
+        let gems = "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.0.8)\n    \
+                    rails (7.1.2)\n      rack (>= 2.2.4)\n\nPLATFORMS\n  ruby\n";
+        assert_eq!(pins(gems, "Gemfile.lock"), ["rack 3.0.8", "rails 7.1.2"]);
+
+        let go = "golang.org/x/text v0.14.0 h1:abc=\ngolang.org/x/text v0.14.0/go.mod h1:def=\n";
+        assert_eq!(pins(go, "go.sum"), ["golang.org/x/text v0.14.0"]);
+
+        assert!(!is_lockfile("src/main.rs"));
+        assert!(is_lockfile("web\\yarn.lock"));
+    }
+}
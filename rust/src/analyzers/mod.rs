@@ -1,13 +1,26 @@
+pub mod csharp_analyzer;
 pub mod generic;
+pub mod guard;
+pub mod lockfile;
+pub mod php_analyzer;
+pub mod ruby_analyzer;
 /// Language analyzers for extracting metadata from source files
 pub mod rust_analyzer;
+pub mod schema;
+pub mod sql;
+pub mod tabular;
 
+pub use csharp_analyzer::CSharpAnalyzer;
 pub use generic::{
     create_javascript_analyzer, create_json_analyzer, create_markdown_analyzer,
     create_python_analyzer, create_shell_analyzer, create_yaml_analyzer, AnalyzerConfig,
     GenericAnalyzer,
 };
+pub use php_analyzer::PhpAnalyzer;
+pub use ruby_analyzer::RubyAnalyzer;
 pub use rust_analyzer::RustAnalyzer;
+pub use sql::SqlAnalyzer;
+pub use tabular::TabularAnalyzer;
 
 /// Result of file analysis containing extracted metadata
 #[derive(Debug, Clone)]
@@ -22,8 +35,11 @@ pub struct AnalysisResult {
     pub markers: Vec<String>,
     pub category: String,
     pub critical_sections: Vec<(usize, usize)>,
-    /// Structure ranges for truncation (1-indexed line numbers)
+    /// Structure ranges for truncation (1-indexed line numbers), kept by
+    /// smart and structure truncation along with the lines they recognize
     pub structure_ranges: Vec<(usize, usize)>,
+    /// The file ran out of regex budget; the rest is incomplete
+    pub budget_exceeded: bool,
 }
 
 impl AnalysisResult {
@@ -41,10 +57,34 @@ impl AnalysisResult {
             category: "library".to_string(),
             critical_sections: Vec::new(),
             structure_ranges: Vec::new(),
+            budget_exceeded: false,
         }
     }
 }
 
+/// Lines a signature spanning several lines may take before it is cut off
+const MAX_SIGNATURE_LINES: usize = 10;
+
+/// The line (1-indexed) the signature starting on `start` ends on: where
+/// its parameter list closes
+pub(crate) fn signature_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0i32;
+    let last = (start + MAX_SIGNATURE_LINES - 1).min(lines.len());
+    for (i, line) in lines.iter().enumerate().take(last).skip(start - 1) {
+        for c in line.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth <= 0 {
+            return i + 1;
+        }
+    }
+    start
+}
+
 /// Get the appropriate analyzer for a file based on its extension
 pub fn get_analyzer_for_file(file_path: &str) -> Option<Box<dyn LanguageAnalyzer>> {
     let path = std::path::Path::new(file_path);
@@ -55,13 +95,37 @@ pub fn get_analyzer_for_file(file_path: &str) -> Option<Box<dyn LanguageAnalyzer
         "js" | "jsx" | "ts" | "tsx" | "mjs" => Some(Box::new(create_javascript_analyzer())),
         "sh" | "bash" | "zsh" => Some(Box::new(create_shell_analyzer())),
         "rs" => Some(Box::new(RustAnalyzer::new())),
+        "cs" => Some(Box::new(CSharpAnalyzer::new())),
+        "rb" | "rake" | "gemspec" => Some(Box::new(RubyAnalyzer::new())),
+        "php" | "phtml" | "php3" | "php4" | "php5" | "phps" => Some(Box::new(PhpAnalyzer::new())),
         "md" | "markdown" => Some(Box::new(create_markdown_analyzer())),
         "json" => Some(Box::new(create_json_analyzer())),
         "yml" | "yaml" => Some(Box::new(create_yaml_analyzer())),
+        "csv" | "tsv" => Some(Box::new(TabularAnalyzer)),
+        "sql" => Some(Box::new(SqlAnalyzer)),
         _ => None,
     }
 }
 
+/// Analyze `content` with the analyzer for `file_path`
+///
+/// `None` when no analyzer handles the file, or when its analysis ran out
+/// of regex budget (see [`guard`]), in which case a warning is printed and
+/// the file should be treated as plain text.
+pub fn analyze_within_budget(file_path: &str, content: &str) -> Option<AnalysisResult> {
+    let analyzer = get_analyzer_for_file(file_path)?;
+    let analysis = crate::timing::analysis(|| analyzer.analyze(content, file_path));
+    if analysis.budget_exceeded {
+        eprintln!(
+            "Warning: analysis of {} exceeded its {} byte regex budget; treating it as plain text",
+            file_path,
+            guard::FILE_BUDGET
+        );
+        return None;
+    }
+    Some(analysis)
+}
+
 /// Trait for language analyzers
 pub trait LanguageAnalyzer {
     /// Analyze source code content and extract metadata
@@ -176,6 +240,22 @@ mod tests {
         assert_eq!(a.language_name(), "Rust");
     }
 
+    #[test]
+    fn test_get_analyzer_for_csharp() {
+        let analyzer = get_analyzer_for_file("Services/OrderService.cs");
+        assert_eq!(analyzer.unwrap().language_name(), "C#");
+    }
+
+    #[test]
+    fn test_get_analyzer_for_ruby_and_php() {
+        for path in ["lib/cart.rb", "Rakefile.rake", "shop.gemspec"] {
+            assert_eq!(get_analyzer_for_file(path).unwrap().language_name(), "Ruby");
+        }
+        for path in ["src/Cart.php", "views/cart.phtml"] {
+            assert_eq!(get_analyzer_for_file(path).unwrap().language_name(), "PHP");
+        }
+    }
+
     #[test]
     fn test_get_analyzer_for_markdown() {
         let md = get_analyzer_for_file("README.md");
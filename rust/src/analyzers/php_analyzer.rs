@@ -0,0 +1,337 @@
+use super::guard::RegexBudget;
+use super::{signature_end, AnalysisResult, LanguageAnalyzer};
+use crate::python_style_split;
+/// PHP source code analyzer
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Modifiers a class member may carry
+const MEMBER_MODIFIERS: &str =
+    r"(?:(?:public|private|protected|static|abstract|final|readonly|var)\s+)";
+
+lazy_static! {
+    static ref NAMESPACE_PATTERN: Regex = Regex::new(r"^\s*namespace\s+[\w\\]+\s*[;{]").unwrap();
+    static ref USE_PATTERN: Regex = Regex::new(
+        r"^\s*use\s+(?:function\s+|const\s+)?(\\?[\w\\]+(?:\s*\{[^}]*\})?(?:\s+as\s+\w+)?)\s*;"
+    )
+    .unwrap();
+    static ref INCLUDE_PATTERN: Regex = Regex::new(
+        r"^\s*(?:require|require_once|include|include_once)\b\s*\(?\s*([^;]+?)\s*\)?\s*;"
+    )
+    .unwrap();
+    static ref TYPE_PATTERN: Regex = Regex::new(
+        r"^\s*(?:(?:abstract|final|readonly)\s+)*(?:class|interface|trait|enum)\s+(\w+)"
+    )
+    .unwrap();
+    static ref FUNCTION_PATTERN: Regex = Regex::new(&format!(
+        r"^\s*{}*function\s+&?\s*(\w+)\s*\(",
+        MEMBER_MODIFIERS
+    ))
+    .unwrap();
+    static ref PROPERTY_PATTERN: Regex = Regex::new(&format!(
+        r"^\s*{}+(?:\??[\w\\|]+\s+)?\$\w+\s*[;=,)]",
+        MEMBER_MODIFIERS
+    ))
+    .unwrap();
+    static ref CONST_PATTERN: Regex = Regex::new(&format!(
+        r"^\s*{}*const\s+(?:[\w\\?]+\s+)?\w+\s*=",
+        MEMBER_MODIFIERS
+    ))
+    .unwrap();
+    static ref ENUM_CASE_PATTERN: Regex = Regex::new(r"^\s*case\s+\w+\s*(?:=[^:]*)?;").unwrap();
+    static ref ATTRIBUTE_PATTERN: Regex = Regex::new(r"^\s*#\[.*\]\s*$").unwrap();
+    static ref MARKER_PATTERN: Regex =
+        Regex::new(r"(?://|#|\*)\s*(TODO|FIXME|XXX|HACK|NOTE):?\s*(.+)").unwrap();
+}
+
+/// Besides the usual metadata, reports the lines smart and structure
+/// truncation keep as `structure_ranges`: namespaces, uses and includes,
+/// attributes, type declarations, function signatures (to the closing
+/// parenthesis when they span lines), properties, constants and enum cases.
+pub struct PhpAnalyzer;
+
+impl Default for PhpAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhpAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Analyze PHP source code lines
+    fn analyze_lines(
+        &self,
+        lines: &[&str],
+        file_path: &str,
+        budget: &RegexBudget,
+    ) -> AnalysisResult {
+        let mut result = AnalysisResult::new("PHP");
+        let mut types = Vec::new();
+        let mut functions = Vec::new();
+        let mut imports = Vec::new();
+        let mut markers = Vec::new();
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let line_num = i + 1;
+            i += 1;
+            if budget.exhausted() {
+                result.budget_exceeded = true;
+                break;
+            }
+            if !budget.admits(line) {
+                continue;
+            }
+
+            // Markers (TODO, FIXME, etc.)
+            if let Some(caps) = MARKER_PATTERN.captures(line) {
+                if let Some(marker_type) = caps.get(1) {
+                    markers.push(format!("{} (line {})", marker_type.as_str(), line_num));
+                }
+            }
+
+            // Attributes, before `#` comments are skipped
+            if ATTRIBUTE_PATTERN.is_match(line) {
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("//")
+                || trimmed.starts_with('#')
+                || trimmed.starts_with('*')
+                || trimmed.starts_with("/*")
+            {
+                continue;
+            }
+
+            // Namespaces
+            if NAMESPACE_PATTERN.is_match(line) {
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Imports, trait uses in a class body included
+            if let Some(caps) = USE_PATTERN
+                .captures(line)
+                .or_else(|| INCLUDE_PATTERN.captures(line))
+            {
+                imports.push(caps[1].trim().to_string());
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Classes, interfaces, traits and enums
+            if let Some(caps) = TYPE_PATTERN.captures(line) {
+                types.push(caps[1].to_string());
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Functions and methods
+            if let Some(caps) = FUNCTION_PATTERN.captures(line) {
+                functions.push(caps[1].to_string());
+                let end = signature_end(lines, line_num);
+                result.structure_ranges.push((line_num, end));
+                i = end;
+                continue;
+            }
+
+            // Properties, constants and enum cases
+            if PROPERTY_PATTERN.is_match(line)
+                || CONST_PATTERN.is_match(line)
+                || ENUM_CASE_PATTERN.is_match(line)
+            {
+                result.structure_ranges.push((line_num, line_num));
+            }
+        }
+
+        // Categorize based on content
+        let category = if file_path.to_lowercase().contains("test") || file_path.contains("tests/")
+        {
+            "test"
+        } else {
+            "library"
+        };
+
+        result.classes = types;
+
+        // Limit to first 20 functions
+        result.functions = functions.into_iter().take(20).collect();
+
+        // Limit to first 10 imports
+        result.imports = imports.into_iter().take(10).collect();
+
+        // Limit to first 5 markers
+        result.markers = markers.into_iter().take(5).collect();
+
+        result.category = category.to_string();
+
+        result
+    }
+}
+
+impl LanguageAnalyzer for PhpAnalyzer {
+    fn analyze(&self, content: &str, file_path: &str) -> AnalysisResult {
+        let lines: Vec<&str> = python_style_split(content);
+        self.analyze_lines(&lines, file_path, &RegexBudget::new())
+    }
+
+    fn supported_extensions(&self) -> Vec<&str> {
+        vec![".php", ".phtml", ".php3", ".php4", ".php5", ".phps"]
+    }
+
+    fn language_name(&self) -> &str {
+        "PHP"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CART: &str = r#"<?php
+
+declare(strict_types=1);
+
+namespace App\Shop;
+
+use App\Models\LineItem;
+use Illuminate\Support\{Collection, Str};
+use function App\Helpers\money as fmt;
+
+require_once __DIR__ . '/bootstrap.php';
+
+/**
+ * A shopping cart
+ */
+#[Entity(table: 'carts')]
+final class Cart implements \Countable
+{
+    use HasEvents;
+
+    public const MAX_ITEMS = 50;
+
+    private array $items = [];
+    protected ?string $owner;
+
+    public function __construct(
+        private readonly Clock $clock,
+        string $owner,
+    ) {
+        $this->owner = $owner;
+    }
+
+    // TODO: enforce MAX_ITEMS
+    public function add(LineItem $item, int $quantity = 1): void
+    {
+        $this->items[] = $item;
+    }
+
+    public static function &shared(): self
+    {
+        return static::$instance;
+    }
+}
+
+enum Status: string
+{
+    case Open = 'open';
+    case Closed = 'closed';
+}
+
+function helper(): int
+{
+    switch (1) {
+        case 1:
+            return 1;
+    }
+}
+"#;
+
+    fn kept_lines(result: &AnalysisResult) -> Vec<String> {
+        let lines: Vec<&str> = CART.lines().collect();
+        result
+            .structure_ranges
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .map(|n| lines[n - 1].trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_declarations() {
+        let result = PhpAnalyzer::new().analyze(CART, "src/Shop/Cart.php");
+
+        assert_eq!(result.language, "PHP");
+        assert_eq!(result.classes, vec!["Cart", "Status"]);
+        assert_eq!(
+            result.functions,
+            vec!["__construct", "add", "shared", "helper"]
+        );
+        assert_eq!(
+            result.imports,
+            vec![
+                "App\\Models\\LineItem",
+                "Illuminate\\Support\\{Collection, Str}",
+                "App\\Helpers\\money as fmt",
+                "__DIR__ . '/bootstrap.php'",
+                "HasEvents",
+            ]
+        );
+        assert_eq!(result.markers, vec!["TODO (line 33)"]);
+        assert_eq!(result.category, "library");
+    }
+
+    #[test]
+    fn test_structure_ranges() {
+        let result = PhpAnalyzer::new().analyze(CART, "src/Shop/Cart.php");
+
+        assert_eq!(
+            kept_lines(&result),
+            vec![
+                "namespace App\\Shop;",
+                "use App\\Models\\LineItem;",
+                "use Illuminate\\Support\\{Collection, Str};",
+                "use function App\\Helpers\\money as fmt;",
+                "require_once __DIR__ . '/bootstrap.php';",
+                "#[Entity(table: 'carts')]",
+                "final class Cart implements \\Countable",
+                "use HasEvents;",
+                "public const MAX_ITEMS = 50;",
+                "private array $items = [];",
+                "protected ?string $owner;",
+                "public function __construct(",
+                "private readonly Clock $clock,",
+                "string $owner,",
+                ") {",
+                "public function add(LineItem $item, int $quantity = 1): void",
+                "public static function &shared(): self",
+                "enum Status: string",
+                "case Open = 'open';",
+                "case Closed = 'closed';",
+                "function helper(): int",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_test_category() {
+        let content = "<?php\nclass CartTest extends TestCase\n{\n    public function testAdd(): void {}\n}\n";
+        let result = PhpAnalyzer::new().analyze(content, "tests/CartTest.php");
+
+        assert_eq!(result.category, "test");
+        assert_eq!(result.functions, vec!["testAdd"]);
+    }
+
+    #[test]
+    fn test_regex_budget_exceeded() {
+        let budget = RegexBudget::with_limit(0);
+        let result = PhpAnalyzer::new().analyze_lines(&["class A {}"], "A.php", &budget);
+        assert!(result.budget_exceeded);
+        assert!(result.classes.is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+use super::guard::RegexBudget;
+use super::{signature_end, AnalysisResult, LanguageAnalyzer};
+use crate::python_style_split;
+/// Ruby source code analyzer
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref REQUIRE_PATTERN: Regex =
+        Regex::new(r#"^\s*(?:require|require_relative|load)\s*\(?\s*['"]([^'"]+)['"]"#).unwrap();
+    static ref TYPE_PATTERN: Regex =
+        Regex::new(r"^\s*(?:class|module)\s+((?:::)?[A-Z][\w]*(?:::[A-Z]\w*)*)").unwrap();
+    static ref DEF_PATTERN: Regex = Regex::new(
+        r"^\s*(?:(?:private|protected|public|module_function)\s+)?def\s+(?:(?:self|[A-Z]\w*)\.)?([^\s(;.]+)"
+    )
+    .unwrap();
+    static ref DECLARATION_PATTERN: Regex = Regex::new(
+        r"^\s*(?:attr_(?:reader|writer|accessor)|include|extend|prepend|alias_method|private_constant|module_function|delegate)\b"
+    )
+    .unwrap();
+    static ref CONSTANT_PATTERN: Regex = Regex::new(r"^\s*[A-Z]\w*\s*=[^=~]").unwrap();
+    static ref ENTRY_POINT_PATTERN: Regex =
+        Regex::new(r"^\s*if\s+(?:__FILE__\s*==\s*\$(?:0|PROGRAM_NAME)|\$(?:0|PROGRAM_NAME)\s*==\s*__FILE__)")
+            .unwrap();
+    static ref MARKER_PATTERN: Regex =
+        Regex::new(r"#\s*(TODO|FIXME|XXX|HACK|NOTE):?\s*(.+)").unwrap();
+}
+
+/// Besides the usual metadata, reports the lines smart and structure
+/// truncation keep as `structure_ranges`: requires, modules and classes,
+/// method signatures (to the closing parenthesis when they span lines),
+/// attribute and mixin declarations, and constants.
+pub struct RubyAnalyzer;
+
+impl Default for RubyAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RubyAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Analyze Ruby source code lines
+    fn analyze_lines(
+        &self,
+        lines: &[&str],
+        file_path: &str,
+        budget: &RegexBudget,
+    ) -> AnalysisResult {
+        let mut result = AnalysisResult::new("Ruby");
+        let mut types = Vec::new();
+        let mut functions = Vec::new();
+        let mut requires = Vec::new();
+        let mut entry_points = Vec::new();
+        let mut markers = Vec::new();
+        let mut in_doc_block = false;
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let line_num = i + 1;
+            i += 1;
+            if budget.exhausted() {
+                result.budget_exceeded = true;
+                break;
+            }
+            if !budget.admits(line) {
+                continue;
+            }
+
+            // =begin/=end blocks are comments
+            if in_doc_block {
+                in_doc_block = !line.starts_with("=end");
+                continue;
+            }
+            if line.starts_with("=begin") {
+                in_doc_block = true;
+                continue;
+            }
+
+            // Markers (TODO, FIXME, etc.)
+            if let Some(caps) = MARKER_PATTERN.captures(line) {
+                if let Some(marker_type) = caps.get(1) {
+                    markers.push(format!("{} (line {})", marker_type.as_str(), line_num));
+                }
+            }
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            // Requires
+            if let Some(caps) = REQUIRE_PATTERN.captures(line) {
+                requires.push(caps[1].to_string());
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Modules and classes
+            if let Some(caps) = TYPE_PATTERN.captures(line) {
+                types.push(caps[1].to_string());
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            // Methods, singleton methods included
+            if let Some(caps) = DEF_PATTERN.captures(line) {
+                functions.push(caps[1].to_string());
+                let end = signature_end(lines, line_num);
+                result.structure_ranges.push((line_num, end));
+                i = end;
+                continue;
+            }
+
+            // Attributes, mixins and constants
+            if DECLARATION_PATTERN.is_match(line) || CONSTANT_PATTERN.is_match(line) {
+                result.structure_ranges.push((line_num, line_num));
+                continue;
+            }
+
+            if ENTRY_POINT_PATTERN.is_match(line) {
+                entry_points.push("__FILE__ == $0".to_string());
+                result.critical_sections.push((line_num, line_num + 20));
+            }
+        }
+
+        // Categorize based on content
+        let path_lower = file_path.to_lowercase();
+        let category = if !entry_points.is_empty() {
+            "application"
+        } else if path_lower.contains("test") || path_lower.contains("spec") {
+            "test"
+        } else {
+            "library"
+        };
+
+        result.classes = types;
+
+        // Limit to first 20 functions
+        result.functions = functions.into_iter().take(20).collect();
+
+        // Limit to first 10 imports
+        result.imports = requires.into_iter().take(10).collect();
+
+        result.entry_points = entry_points;
+
+        // Limit to first 5 markers
+        result.markers = markers.into_iter().take(5).collect();
+
+        result.category = category.to_string();
+
+        result
+    }
+}
+
+impl LanguageAnalyzer for RubyAnalyzer {
+    fn analyze(&self, content: &str, file_path: &str) -> AnalysisResult {
+        let lines: Vec<&str> = python_style_split(content);
+        self.analyze_lines(&lines, file_path, &RegexBudget::new())
+    }
+
+    fn supported_extensions(&self) -> Vec<&str> {
+        vec![".rb", ".rake", ".gemspec"]
+    }
+
+    fn language_name(&self) -> &str {
+        "Ruby"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CART: &str = r#"require "json"
+require_relative "line_item"
+
+# A shopping cart
+module Shop
+  class Cart < Base
+    include Enumerable
+    attr_reader :items, :owner
+
+    MAX_ITEMS = 50
+
+    def initialize(owner)
+      @owner = owner
+      @items = []
+    end
+
+    def self.load(path)
+      new(JSON.parse(File.read(path)))
+    end
+
+    def add(item,
+            quantity: 1)
+      # TODO: enforce MAX_ITEMS
+      @items << item
+    end
+
+    def empty? = @items.empty?
+
+    private
+
+    def total
+      @items.sum(&:price)
+    end
+  end
+end
+
+=begin
+def documented_only
+end
+=end
+"#;
+
+    fn kept_lines(result: &AnalysisResult) -> Vec<String> {
+        let lines: Vec<&str> = CART.lines().collect();
+        result
+            .structure_ranges
+            .iter()
+            .flat_map(|&(start, end)| start..=end)
+            .map(|n| lines[n - 1].trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_declarations() {
+        let result = RubyAnalyzer::new().analyze(CART, "lib/shop/cart.rb");
+
+        assert_eq!(result.language, "Ruby");
+        assert_eq!(result.classes, vec!["Shop", "Cart"]);
+        assert_eq!(
+            result.functions,
+            vec!["initialize", "load", "add", "empty?", "total"]
+        );
+        assert_eq!(result.imports, vec!["json", "line_item"]);
+        assert_eq!(result.markers, vec!["TODO (line 23)"]);
+        assert_eq!(result.category, "library");
+    }
+
+    #[test]
+    fn test_structure_ranges() {
+        let result = RubyAnalyzer::new().analyze(CART, "lib/shop/cart.rb");
+
+        assert_eq!(
+            kept_lines(&result),
+            vec![
+                "require \"json\"",
+                "require_relative \"line_item\"",
+                "module Shop",
+                "class Cart < Base",
+                "include Enumerable",
+                "attr_reader :items, :owner",
+                "MAX_ITEMS = 50",
+                "def initialize(owner)",
+                "def self.load(path)",
+                "def add(item,",
+                "quantity: 1)",
+                "def empty? = @items.empty?",
+                "def total",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script_entry_point() {
+        let content = "def main\n  puts 'hi'\nend\n\nif __FILE__ == $0\n  main\nend\n";
+        let result = RubyAnalyzer::new().analyze(content, "bin/greet.rb");
+
+        assert_eq!(result.entry_points, vec!["__FILE__ == $0"]);
+        assert_eq!(result.critical_sections, vec![(5, 25)]);
+        assert_eq!(result.category, "application");
+    }
+
+    #[test]
+    fn test_spec_category() {
+        let content = "require \"spec_helper\"\n\nRSpec.describe Cart do\nend\n";
+        let result = RubyAnalyzer::new().analyze(content, "spec/cart_spec.rb");
+
+        assert_eq!(result.category, "test");
+        assert_eq!(result.imports, vec!["spec_helper"]);
+    }
+
+    #[test]
+    fn test_regex_budget_exceeded() {
+        let budget = RegexBudget::with_limit(0);
+        let result = RubyAnalyzer::new().analyze_lines(&["class A; end"], "a.rb", &budget);
+        assert!(result.budget_exceeded);
+        assert!(result.classes.is_empty());
+    }
+}
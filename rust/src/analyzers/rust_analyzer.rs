@@ -1,3 +1,4 @@
+use super::guard::RegexBudget;
 use super::{AnalysisResult, LanguageAnalyzer};
 use crate::python_style_split;
 /// Rust source code analyzer
@@ -29,7 +30,12 @@ impl RustAnalyzer {
     }
 
     /// Analyze Rust source code lines
-    fn analyze_lines(&self, lines: &[&str], file_path: &str) -> AnalysisResult {
+    fn analyze_lines(
+        &self,
+        lines: &[&str],
+        file_path: &str,
+        budget: &RegexBudget,
+    ) -> AnalysisResult {
         let mut result = AnalysisResult::new("Rust");
         let mut structs = Vec::new();
         let mut enums = Vec::new();
@@ -41,6 +47,13 @@ impl RustAnalyzer {
 
         for (i, line) in lines.iter().enumerate() {
             let line_num = i + 1;
+            if budget.exhausted() {
+                result.budget_exceeded = true;
+                break;
+            }
+            if !budget.admits(line) {
+                continue;
+            }
 
             // Structs
             if let Some(caps) = STRUCT_PATTERN.captures(line) {
@@ -127,7 +140,7 @@ impl RustAnalyzer {
 impl LanguageAnalyzer for RustAnalyzer {
     fn analyze(&self, content: &str, file_path: &str) -> AnalysisResult {
         let lines: Vec<&str> = python_style_split(content);
-        self.analyze_lines(&lines, file_path)
+        self.analyze_lines(&lines, file_path, &RegexBudget::new())
     }
 
     fn supported_extensions(&self) -> Vec<&str> {
@@ -143,6 +156,14 @@ impl LanguageAnalyzer for RustAnalyzer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_regex_budget_exceeded() {
+        let budget = RegexBudget::with_limit(0);
+        let result = RustAnalyzer::new().analyze_lines(&["fn main() {}"], "main.rs", &budget);
+        assert!(result.budget_exceeded);
+        assert!(result.functions.is_empty());
+    }
+
     #[test]
     fn test_struct_detection() {
         let analyzer = RustAnalyzer::new();
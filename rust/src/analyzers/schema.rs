@@ -0,0 +1,766 @@
+//! Schemas of JSON and YAML files
+//!
+//! A large config file or API spec says more through its shape than through
+//! its values. [`Schema`] reduces a file to its key hierarchy, with the types
+//! of the values under each key and the lengths of arrays. The items of an
+//! array are merged into one shape, and so are the values of an object with
+//! more than [`MAP_KEYS`] keys (a map from names or paths, like the `paths`
+//! of an OpenAPI spec), so even a spec of tens of thousands of lines
+//! outlines in a few hundred.
+//!
+//! YAML is read by indentation, which covers block mappings and sequences,
+//! flow collections, block scalars and multiple documents; anchors, tags
+//! and complex keys are passed over. Content that doesn't parse has no
+//! schema.
+
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+/// Keys above which an object is outlined as a map, with one merged value
+pub const MAP_KEYS: usize = 32;
+
+/// Keys of a map named in its outline
+const MAP_EXAMPLES: usize = 3;
+
+/// Whether `file_path` is a JSON or YAML file
+pub fn is_structured(file_path: &str) -> bool {
+    let lower = file_path.to_lowercase();
+    [".json", ".yaml", ".yml"]
+        .iter()
+        .any(|e| lower.ends_with(e))
+}
+
+/// Kinds of scalar value, in the order they are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Null,
+    /// A YAML alias (`*name`), whose value is defined elsewhere
+    Alias,
+}
+
+const SCALARS: [(Scalar, &str); 6] = [
+    (Scalar::String, "string"),
+    (Scalar::Integer, "integer"),
+    (Scalar::Number, "number"),
+    (Scalar::Boolean, "boolean"),
+    (Scalar::Null, "null"),
+    (Scalar::Alias, "alias"),
+];
+
+/// The shape of one value, or of several merged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shape {
+    /// Bit set of the [`Scalar`] kinds seen
+    scalars: u8,
+    /// Fields of the objects seen, in first-seen order
+    object: Option<Vec<(String, Shape)>>,
+    /// Fewest and most items of the arrays seen, and their items merged
+    array: Option<(usize, usize, Option<Box<Shape>>)>,
+}
+
+impl Shape {
+    fn scalar(kind: Scalar) -> Self {
+        Self {
+            scalars: 1 << kind as u8,
+            ..Self::default()
+        }
+    }
+
+    fn object(fields: Vec<(String, Shape)>) -> Self {
+        Self {
+            object: Some(fields),
+            ..Self::default()
+        }
+    }
+
+    fn array(len: usize, item: Option<Shape>) -> Self {
+        Self {
+            array: Some((len, len, item.map(Box::new))),
+            ..Self::default()
+        }
+    }
+
+    /// Widen this shape to also describe `other`
+    pub fn merge(&mut self, other: Shape) {
+        self.scalars |= other.scalars;
+        match (&mut self.object, other.object) {
+            (Some(fields), Some(others)) => {
+                for (key, value) in others {
+                    merge_field(fields, key, value);
+                }
+            }
+            (object @ None, others) => *object = others,
+            _ => {}
+        }
+        match (&mut self.array, other.array) {
+            (Some((min, max, item)), Some((other_min, other_max, other_item))) => {
+                *min = (*min).min(other_min);
+                *max = (*max).max(other_max);
+                merge_item(item, other_item.map(|i| *i));
+            }
+            (array @ None, others) => *array = others,
+            _ => {}
+        }
+    }
+
+    /// What the shape is, without its lengths: `object`, `string | null`
+    fn kind(&self) -> String {
+        let mut kinds = Vec::new();
+        if self.object.is_some() {
+            kinds.push("object");
+        }
+        if self.array.is_some() {
+            kinds.push("array");
+        }
+        kinds.extend(self.scalar_names());
+        kinds.join(" | ")
+    }
+
+    /// `object`, `array of object, 3 items`, `integer | null`
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match &self.object {
+            Some(fields) if fields.len() > MAP_KEYS => {
+                let examples: Vec<String> = fields
+                    .iter()
+                    .take(MAP_EXAMPLES)
+                    .map(|(key, _)| format!("{:?}", key))
+                    .collect();
+                parts.push(format!(
+                    "object, {} keys ({}, ...)",
+                    fields.len(),
+                    examples.join(", ")
+                ));
+            }
+            Some(fields) if fields.is_empty() => parts.push("empty object".to_string()),
+            Some(_) => parts.push("object".to_string()),
+            None => {}
+        }
+        match &self.array {
+            Some((_, _, None)) => parts.push("empty array".to_string()),
+            Some((min, max, Some(item))) => {
+                let kind = item.kind();
+                let kind = if kind.contains(" | ") {
+                    format!("({})", kind)
+                } else {
+                    kind
+                };
+                let items = match (min, max) {
+                    (_, 1) if min == max => "1 item".to_string(),
+                    (min, max) if min == max => format!("{} items", max),
+                    (min, max) => format!("{}-{} items", min, max),
+                };
+                parts.push(format!("array of {}, {}", kind, items));
+            }
+            None => {}
+        }
+        parts.extend(self.scalar_names().map(str::to_string));
+        parts.join(" | ")
+    }
+
+    fn scalar_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        // Integers among numbers are numbers
+        let number = self.scalars & (1 << Scalar::Number as u8) != 0;
+        SCALARS
+            .iter()
+            .filter(move |(kind, _)| {
+                self.scalars & (1 << *kind as u8) != 0 && !(number && *kind == Scalar::Integer)
+            })
+            .map(|(_, name)| *name)
+    }
+
+    /// `name: description`, then what's inside, indented
+    fn render(&self, name: &str, depth: usize, out: &mut Vec<String>) {
+        let name = if name.is_empty() || name.contains([':', '\n']) || name.trim() != name {
+            format!("{:?}", name)
+        } else {
+            name.to_string()
+        };
+        out.push(format!(
+            "{}{}: {}",
+            "  ".repeat(depth),
+            name,
+            self.describe()
+        ));
+        self.render_children(depth + 1, out);
+    }
+
+    fn render_children(&self, depth: usize, out: &mut Vec<String>) {
+        match &self.object {
+            Some(fields) if fields.len() > MAP_KEYS => {
+                let mut values = Shape::default();
+                for (_, value) in fields {
+                    values.merge(value.clone());
+                }
+                values.render("*", depth, out);
+            }
+            Some(fields) => {
+                for (key, value) in fields {
+                    value.render(key, depth, out);
+                }
+            }
+            None => {}
+        }
+        if let Some((_, _, Some(item))) = &self.array {
+            if item.array.is_some() {
+                item.render("[]", depth, out);
+            } else {
+                item.render_children(depth, out);
+            }
+        }
+    }
+}
+
+fn merge_field(fields: &mut Vec<(String, Shape)>, key: String, value: Shape) {
+    match fields.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, shape)) => shape.merge(value),
+        None => fields.push((key, value)),
+    }
+}
+
+fn merge_item(item: &mut Option<Box<Shape>>, other: Option<Shape>) {
+    match (item.as_deref_mut(), other) {
+        (Some(shape), Some(other)) => shape.merge(other),
+        (None, other) => *item = other.map(Box::new),
+        _ => {}
+    }
+}
+
+/// The shape of a JSON or YAML file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// YAML documents in the file, merged into one shape; 1 for JSON
+    pub documents: usize,
+    pub shape: Shape,
+}
+
+impl Schema {
+    /// `None` when `content` doesn't parse, or has no document
+    pub fn parse(content: &str, file_path: &str) -> Option<Self> {
+        if file_path.to_lowercase().ends_with(".json") {
+            let shape = serde_json::from_str(content).ok()?;
+            return Some(Self {
+                documents: 1,
+                shape,
+            });
+        }
+        let (documents, shape) = parse_yaml(content)?;
+        Some(Self { documents, shape })
+    }
+
+    /// `object`, or `3 documents: object` for YAML with several
+    pub fn describe(&self) -> String {
+        match self.documents {
+            1 => self.shape.describe(),
+            n => format!("{} documents: {}", n, self.shape.describe()),
+        }
+    }
+
+    /// One line per key, indented two spaces per level
+    pub fn outline(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        self.shape.render_children(0, &mut out);
+        out
+    }
+}
+
+// =============================================================================
+// JSON
+// =============================================================================
+
+impl<'de> Deserialize<'de> for Shape {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ShapeVisitor)
+    }
+}
+
+/// Builds a [`Shape`] while the value is read, keeping keys in file order
+struct ShapeVisitor;
+
+impl<'de> Visitor<'de> for ShapeVisitor {
+    type Value = Shape;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Shape, E> {
+        Ok(Shape::scalar(Scalar::Boolean))
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Shape, E> {
+        Ok(Shape::scalar(Scalar::Integer))
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Shape, E> {
+        Ok(Shape::scalar(Scalar::Integer))
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Shape, E> {
+        Ok(Shape::scalar(Scalar::Number))
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Shape, E> {
+        Ok(Shape::scalar(Scalar::String))
+    }
+
+    fn visit_unit<E>(self) -> Result<Shape, E> {
+        Ok(Shape::scalar(Scalar::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Shape, A::Error> {
+        let (mut len, mut item) = (0, None);
+        while let Some(shape) = seq.next_element::<Shape>()? {
+            len += 1;
+            merge_into(&mut item, shape);
+        }
+        Ok(Shape::array(len, item))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Shape, A::Error> {
+        let mut fields = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value::<Shape>()?;
+            merge_field(&mut fields, key, value);
+        }
+        Ok(Shape::object(fields))
+    }
+}
+
+fn merge_into(merged: &mut Option<Shape>, shape: Shape) {
+    match merged {
+        Some(merged) => merged.merge(shape),
+        None => *merged = Some(shape),
+    }
+}
+
+// =============================================================================
+// YAML
+// =============================================================================
+
+/// A YAML line with its comment removed
+struct Line {
+    indent: usize,
+    text: String,
+}
+
+/// The documents of a YAML file and their merged shape
+fn parse_yaml(content: &str) -> Option<(usize, Shape)> {
+    let mut documents = vec![Vec::new()];
+    for raw in content.lines() {
+        let raw = raw.trim_end_matches('\r');
+        if raw.starts_with('%') {
+            continue;
+        }
+        if raw == "..." {
+            documents.push(Vec::new());
+            continue;
+        }
+        let raw = match raw.strip_prefix("---") {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                documents.push(Vec::new());
+                rest
+            }
+            _ => raw,
+        };
+        let text = strip_comment(raw);
+        let trimmed = text.trim_start();
+        if !trimmed.is_empty() {
+            documents.last_mut()?.push(Line {
+                indent: text.len() - trimmed.len(),
+                text: trimmed.trim_end().to_string(),
+            });
+        }
+    }
+
+    let (mut count, mut merged) = (0, None);
+    for mut lines in documents.into_iter().filter(|d| !d.is_empty()) {
+        let mut i = 0;
+        let shape = parse_node(&mut lines, &mut i)?;
+        if i < lines.len() {
+            return None;
+        }
+        count += 1;
+        merge_into(&mut merged, shape);
+    }
+    Some((count, merged?))
+}
+
+/// `text` up to a comment: a `#` at the start or after a space, outside quotes
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '#' && previous.is_whitespace() => return &text[..i],
+            None if matches!(c, '"' | '\'') && " \t[{,:".contains(previous) => quote = Some(c),
+            None => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+/// Whether `text` is a sequence item
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// The key and the rest of a `key: value` line
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let (key, rest) = match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = text[1..].find(quote)? + 1;
+            (text[1..end].to_string(), &text[end + 1..])
+        }
+        '[' | '{' | '-' | '?' | '|' | '>' | '*' | '&' | '!' => return None,
+        _ => match text.find(": ") {
+            Some(end) => (text[..end].trim_end().to_string(), &text[end..]),
+            None => (text.strip_suffix(':')?.trim_end().to_string(), ":"),
+        },
+    };
+    let value = rest.strip_prefix(':')?;
+    (value.is_empty() || value.starts_with(' ')).then(|| (key, value.trim()))
+}
+
+/// The node starting on line `i`
+fn parse_node(lines: &mut [Line], i: &mut usize) -> Option<Shape> {
+    let line = lines.get(*i)?;
+    let indent = line.indent;
+    if is_item(&line.text) {
+        parse_sequence(lines, i, indent)
+    } else if split_key(&line.text).is_some() {
+        parse_mapping(lines, i, indent)
+    } else {
+        let text = line.text.clone();
+        *i += 1;
+        parse_value(lines, i, indent, &text, false)
+    }
+}
+
+fn parse_mapping(lines: &mut [Line], i: &mut usize, indent: usize) -> Option<Shape> {
+    let mut fields = Vec::new();
+    while let Some(line) = lines.get(*i) {
+        if line.indent < indent {
+            break;
+        }
+        if line.indent > indent || is_item(&line.text) {
+            return None;
+        }
+        let (key, rest) = split_key(&line.text)?;
+        let rest = rest.to_string();
+        *i += 1;
+        let value = parse_value(lines, i, indent, &rest, true)?;
+        // Merge keys (`<<: *defaults`) bring in fields defined elsewhere
+        if key != "<<" {
+            merge_field(&mut fields, key, value);
+        }
+    }
+    Some(Shape::object(fields))
+}
+
+fn parse_sequence(lines: &mut [Line], i: &mut usize, indent: usize) -> Option<Shape> {
+    let (mut len, mut item) = (0, None);
+    while let Some(line) = lines.get(*i) {
+        if line.indent != indent || !is_item(&line.text) {
+            break;
+        }
+        let rest = line.text[1..].trim_start().to_string();
+        let offset = line.text.len() - rest.len();
+        let shape = if is_item(&rest) || split_key(&rest).is_some() {
+            // `- key: value` and `- - item` start a node at their own column
+            lines[*i] = Line {
+                indent: indent + offset,
+                text: rest,
+            };
+            parse_node(lines, i)?
+        } else {
+            *i += 1;
+            parse_value(lines, i, indent, &rest, false)?
+        };
+        len += 1;
+        merge_into(&mut item, shape);
+    }
+    Some(Shape::array(len, item))
+}
+
+/// The value after a key or item marker: `text` and the lines below
+/// indented deeper than `parent`
+///
+/// A mapping's value may be a sequence at the mapping's own indentation
+/// (`same_indent_items`).
+fn parse_value(
+    lines: &mut [Line],
+    i: &mut usize,
+    parent: usize,
+    text: &str,
+    same_indent_items: bool,
+) -> Option<Shape> {
+    // Anchors and tags
+    let mut text = text;
+    while text.starts_with(['&', '!']) {
+        text = text
+            .split_once(' ')
+            .map_or("", |(_, rest)| rest.trim_start());
+    }
+
+    if text.is_empty() {
+        return match lines.get(*i) {
+            Some(next) if next.indent > parent => parse_node(lines, i),
+            Some(next) if next.indent == parent && same_indent_items && is_item(&next.text) => {
+                parse_sequence(lines, i, parent)
+            }
+            _ => Some(Shape::scalar(Scalar::Null)),
+        };
+    }
+
+    // Block scalars, multi-line plain or quoted scalars and flow collections
+    // carry on over the deeper lines
+    let start = *i;
+    while lines.get(*i).is_some_and(|l| l.indent > parent) {
+        *i += 1;
+    }
+    if text.starts_with(['[', '{']) {
+        let mut flow = text.to_string();
+        for line in &lines[start..*i] {
+            flow.push(' ');
+            flow.push_str(&line.text);
+        }
+        let mut chars = flow.chars().peekable();
+        return parse_flow(&mut chars);
+    }
+    if text.starts_with(['|', '>']) {
+        return Some(Shape::scalar(Scalar::String));
+    }
+    Some(scalar_shape(text))
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_spaces(chars: &mut Chars<'_>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// A flow collection (`[a, b]`, `{a: 1}`) or a scalar inside one
+fn parse_flow(chars: &mut Chars<'_>) -> Option<Shape> {
+    skip_spaces(chars);
+    match chars.peek()? {
+        '[' => {
+            chars.next();
+            let (mut len, mut item) = (0, None);
+            loop {
+                skip_spaces(chars);
+                if chars.next_if_eq(&']').is_some() {
+                    break;
+                }
+                merge_into(&mut item, parse_flow(chars)?);
+                len += 1;
+                skip_spaces(chars);
+                match chars.next()? {
+                    ',' => {}
+                    ']' => break,
+                    _ => return None,
+                }
+            }
+            Some(Shape::array(len, item))
+        }
+        '{' => {
+            chars.next();
+            let mut fields = Vec::new();
+            loop {
+                skip_spaces(chars);
+                if chars.next_if_eq(&'}').is_some() {
+                    break;
+                }
+                let key = flow_scalar(chars, ":,}");
+                let key = key.trim().trim_matches(['"', '\'']).to_string();
+                skip_spaces(chars);
+                let value = if chars.next_if_eq(&':').is_some() {
+                    parse_flow(chars)?
+                } else {
+                    Shape::scalar(Scalar::Null)
+                };
+                merge_field(&mut fields, key, value);
+                skip_spaces(chars);
+                match chars.next()? {
+                    ',' => {}
+                    '}' => break,
+                    _ => return None,
+                }
+            }
+            Some(Shape::object(fields))
+        }
+        _ => Some(scalar_shape(flow_scalar(chars, ",]}").trim())),
+    }
+}
+
+/// Text up to one of `stops`, quoted text taken whole
+fn flow_scalar(chars: &mut Chars<'_>, stops: &str) -> String {
+    let mut text = String::new();
+    if let Some(quote) = chars.next_if(|c| matches!(c, '"' | '\'')) {
+        text.push(quote);
+        for c in chars.by_ref() {
+            text.push(c);
+            if c == quote {
+                break;
+            }
+        }
+        return text;
+    }
+    while let Some(c) = chars.next_if(|c| !stops.contains(*c)) {
+        text.push(c);
+    }
+    text
+}
+
+/// The kind of a plain or quoted scalar
+fn scalar_shape(text: &str) -> Shape {
+    let kind = match text {
+        _ if text.starts_with(['"', '\'']) => Scalar::String,
+        _ if text.starts_with('*') => Scalar::Alias,
+        "" | "~" | "null" | "Null" | "NULL" => Scalar::Null,
+        "true" | "True" | "TRUE" | "false" | "False" | "FALSE" => Scalar::Boolean,
+        _ if text.parse::<i64>().is_ok() => Scalar::Integer,
+        ".inf" | "-.inf" | "+.inf" | ".nan" | ".NaN" => Scalar::Number,
+        _ if text
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+            && text.parse::<f64>().is_ok() =>
+        {
+            Scalar::Number
+        }
+        _ => Scalar::String,
+    };
+    Shape::scalar(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_outline() {
+        let json = r#"{
+            "openapi": "3.0.0",
+            "info": {"title": "Pets", "version": 2},
+            "servers": [{"url": "a"}, {"url": "b", "description": null}],
+            "tags": [],
+            "matrix": [[1, 2.5], [3]],
+            "ids": [1, "x"]
+        }"#;
+        let schema = Schema::parse(json, "api.json").unwrap();
+        assert_eq!(schema.describe(), "object");
+        assert_eq!(
+            schema.outline(),
+            [
+                "openapi: string",
+                "info: object",
+                "  title: string",
+                "  version: integer",
+                "servers: array of object, 2 items",
+                "  url: string",
+                "  description: null",
+                "tags: empty array",
+                "matrix: array of array, 2 items",
+                "  []: array of number, 1-2 items",
+                "ids: array of (string | integer), 2 items",
+            ]
+        );
+        assert!(Schema::parse("{\"open\": ", "x.json").is_none());
+    }
+
+    #[test]
+    fn test_map_values_are_merged() {
+        let paths: Vec<String> = (0..40)
+            .map(|i| format!("\"/p{}\": {{\"get\": {{\"id\": {}}}}}", i, i))
+            .collect();
+        let json = format!("{{\"paths\": {{{}}}}}", paths.join(", "));
+        let schema = Schema::parse(&json, "api.json").unwrap();
+        assert_eq!(
+            schema.outline(),
+            [
+                "paths: object, 40 keys (\"/p0\", \"/p1\", \"/p2\", ...)",
+                "  *: object",
+                "    get: object",
+                "      id: integer",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yaml_outline() {
+        let yaml = "\
+# Deployment
+apiVersion: apps/v1   # pinned
+kind: Deployment
+metadata:
+  name: 'web'
+  labels: {app: web, tier: \"front\"}
+spec:
+  replicas: 3
+  ratio: 0.5
+  paused: false
+  selector:
+  containers:
+  - name: web
+    image: nginx
+    ports:
+      - containerPort: 80
+      - containerPort: 443
+        protocol: TCP
+    args: [--verbose,
+      --port=80]
+    command:
+    - sh
+    - -c
+  - name: sidecar
+    env: *shared
+  notes: |
+    key: not a key
+    - not an item
+---
+apiVersion: v1
+kind: Service
+";
+        let schema = Schema::parse(yaml, "deploy.yaml").unwrap();
+        assert_eq!(schema.describe(), "2 documents: object");
+        assert_eq!(
+            schema.outline(),
+            [
+                "apiVersion: string",
+                "kind: string",
+                "metadata: object",
+                "  name: string",
+                "  labels: object",
+                "    app: string",
+                "    tier: string",
+                "spec: object",
+                "  replicas: integer",
+                "  ratio: number",
+                "  paused: boolean",
+                "  selector: null",
+                "  containers: array of object, 2 items",
+                "    name: string",
+                "    image: string",
+                "    ports: array of object, 2 items",
+                "      containerPort: integer",
+                "      protocol: string",
+                "    args: array of string, 2 items",
+                "    command: array of string, 2 items",
+                "    env: alias",
+                "  notes: string",
+            ]
+        );
+
+        // Not YAML that can be read by indentation
+        assert!(Schema::parse("- a\nb: 1\n", "x.yml").is_none());
+        assert!(Schema::parse("# nothing\n", "x.yml").is_none());
+    }
+}
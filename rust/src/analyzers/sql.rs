@@ -0,0 +1,478 @@
+//! SQL scripts and database dumps
+//!
+//! In a dump the schema is a few hundred lines and the data is the rest:
+//! `INSERT` statements thousands of rows long, or `COPY ... FROM stdin`
+//! blocks. [`parse`] splits a script into [`Statement`]s, honouring
+//! quotes, comments, dollar quoting and MySQL's `DELIMITER`, so truncation
+//! can keep the definitions and routine signatures and say what the data
+//! statements held instead of showing them.
+
+use super::{AnalysisResult, LanguageAnalyzer};
+
+/// Whether `file_path` is a SQL file
+pub fn is_sql(file_path: &str) -> bool {
+    file_path.to_lowercase().ends_with(".sql")
+}
+
+/// What a statement does, as far as truncation cares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    /// `CREATE`, `ALTER`, `DROP`, `COMMENT`, `GRANT`, `REVOKE`
+    Definition,
+    /// `CREATE FUNCTION`, `PROCEDURE` or `TRIGGER`
+    Routine,
+    /// `INSERT`, `REPLACE` or `COPY ... FROM stdin` with its rows
+    Data,
+    /// Anything else: `SET`, `SELECT`, `LOCK TABLES`, `DELIMITER` ...
+    Other,
+}
+
+/// One statement and the lines (1-indexed, inclusive) it spans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The object a `CREATE` defines, or the table a data statement fills
+    pub name: Option<String>,
+    /// Rows a data statement holds (0 for `INSERT ... SELECT`)
+    pub rows: usize,
+    /// Lines of a routine's body, between its signature and its last line
+    pub body: Option<(usize, usize)>,
+}
+
+/// Split `content` into statements; comments between statements belong to
+/// none of them
+pub fn parse(content: &str) -> Vec<Statement> {
+    let mut scanner = Scanner {
+        bytes: content.as_bytes(),
+        i: 0,
+        line: 1,
+    };
+    let mut statements = Vec::new();
+    let mut delimiter = b";".to_vec();
+    while let Some(b) = scanner.peek() {
+        if b.is_ascii_whitespace() {
+            scanner.advance(1);
+            continue;
+        }
+        if scanner.skip_comment() {
+            continue;
+        }
+        // `#` starts a comment in MySQL, but is an operator in Postgres
+        if b == b'#' {
+            scanner.i = scanner.line_end();
+            continue;
+        }
+        if scanner.rest().starts_with(&delimiter) {
+            scanner.advance(delimiter.len());
+            continue;
+        }
+        let (start, start_line) = (scanner.i, scanner.line);
+        if scanner.at_line_start() && starts_with_word(scanner.rest(), b"DELIMITER") {
+            let line_end = scanner.line_end();
+            let words = &content[start..line_end];
+            if let Some(new) = words.split_whitespace().nth(1) {
+                delimiter = new.as_bytes().to_vec();
+            }
+            scanner.i = line_end;
+            statements.push(Statement::other(start_line, start_line));
+            continue;
+        }
+
+        let (end, end_line, dollar_line) = scanner.statement(&delimiter);
+        let mut statement = Statement::classify(&content[start..end], start_line, end_line);
+        if statement.kind == StatementKind::Routine {
+            let body_line = dollar_line.or_else(|| begin_line(&content[start..end], start_line));
+            statement.body = body_line
+                .map(|open| (open + 1, end_line.saturating_sub(1)))
+                .filter(|(first, last)| first <= last);
+        }
+        if statement.kind == StatementKind::Data && is_copy_from_stdin(&content[start..end]) {
+            // The rows follow the statement, one per line, up to `\.`
+            scanner.i = scanner.line_end();
+            while scanner.peek().is_some() {
+                scanner.advance(1);
+                let line_end = scanner.line_end();
+                let row = &content[scanner.i..line_end];
+                scanner.i = line_end;
+                if row.trim_end_matches('\r') == "\\." {
+                    statement.end_line = scanner.line;
+                    break;
+                }
+                statement.rows += 1;
+                statement.end_line = scanner.line;
+            }
+        }
+        statements.push(statement);
+    }
+    statements
+}
+
+impl Statement {
+    fn other(start_line: usize, end_line: usize) -> Self {
+        Self {
+            kind: StatementKind::Other,
+            start_line,
+            end_line,
+            name: None,
+            rows: 0,
+            body: None,
+        }
+    }
+
+    fn classify(text: &str, start_line: usize, end_line: usize) -> Self {
+        let words: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == '(' || c == ';')
+            .filter(|w| !w.is_empty())
+            .take(16)
+            .collect();
+        let upper: Vec<String> = words.iter().map(|w| w.to_ascii_uppercase()).collect();
+        let after = |keyword: &str| {
+            let at = upper.iter().position(|w| w == keyword)?;
+            words[at + 1..]
+                .iter()
+                .zip(&upper[at + 1..])
+                .find(|(_, u)| !matches!(u.as_str(), "IF" | "NOT" | "EXISTS" | "ONLY"))
+                .map(|(w, _)| unquote(w))
+        };
+
+        let mut statement = Self::other(start_line, end_line);
+        match upper.first().map(String::as_str) {
+            Some("INSERT" | "REPLACE") => {
+                statement.kind = StatementKind::Data;
+                statement.name = after("INTO");
+                statement.rows = value_rows(text);
+            }
+            Some("COPY") if is_copy_from_stdin(text) => {
+                statement.kind = StatementKind::Data;
+                statement.name = words.get(1).map(|w| unquote(w));
+            }
+            Some("CREATE" | "ALTER" | "DROP" | "COMMENT" | "GRANT" | "REVOKE") => {
+                statement.kind = StatementKind::Definition;
+                let object = upper.iter().skip(1).find(|w| {
+                    matches!(
+                        w.as_str(),
+                        "TABLE"
+                            | "VIEW"
+                            | "FUNCTION"
+                            | "PROCEDURE"
+                            | "TRIGGER"
+                            | "INDEX"
+                            | "TYPE"
+                            | "SEQUENCE"
+                            | "SCHEMA"
+                            | "DOMAIN"
+                    )
+                });
+                if let (Some(object), "CREATE") = (object, upper[0].as_str()) {
+                    statement.name = after(object);
+                    if matches!(object.as_str(), "FUNCTION" | "PROCEDURE" | "TRIGGER") {
+                        statement.kind = StatementKind::Routine;
+                    }
+                }
+            }
+            _ => {}
+        }
+        statement
+    }
+}
+
+/// A name without its quotes or trailing punctuation
+fn unquote(word: &str) -> String {
+    word.trim_end_matches([',', ';', ')'])
+        .replace(['"', '`', '[', ']'], "")
+}
+
+fn starts_with_word(bytes: &[u8], word: &[u8]) -> bool {
+    bytes.len() > word.len()
+        && bytes[..word.len()].eq_ignore_ascii_case(word)
+        && bytes[word.len()].is_ascii_whitespace()
+}
+
+fn is_copy_from_stdin(text: &str) -> bool {
+    let upper = text.to_ascii_uppercase();
+    upper.starts_with("COPY") && upper.contains("FROM STDIN")
+}
+
+/// The line a routine's `BEGIN` is on, for bodies that aren't dollar-quoted
+fn begin_line(text: &str, start_line: usize) -> Option<usize> {
+    text.lines()
+        .position(|line| {
+            line.split_whitespace()
+                .any(|w| w.trim_end_matches(';').eq_ignore_ascii_case("BEGIN"))
+        })
+        .map(|i| start_line + i)
+}
+
+/// The parenthesised tuples after an `INSERT`'s `VALUES`
+fn value_rows(text: &str) -> usize {
+    let upper = text.to_ascii_uppercase();
+    let Some(at) = upper.find("VALUES") else {
+        return 0;
+    };
+    let bytes = &text.as_bytes()[at..];
+    let (mut rows, mut depth, mut i) = (0, 0, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(bytes, i, quote),
+            b'(' => {
+                if depth == 0 {
+                    rows += 1;
+                }
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth -= 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    rows
+}
+
+/// The index just past the quoted string opening at `start`; `''` and, in
+/// single quotes, `\'` are escapes
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote == b'\'' => i += 2,
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    i: usize,
+    line: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.i).copied()
+    }
+
+    fn rest(&self) -> &[u8] {
+        &self.bytes[self.i..]
+    }
+
+    /// Move `n` bytes on, counting the line breaks passed
+    fn advance(&mut self, n: usize) {
+        let end = (self.i + n).min(self.bytes.len());
+        self.line += self.bytes[self.i..end]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        self.i = end;
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.bytes[..self.i]
+            .iter()
+            .rev()
+            .take_while(|&&b| b != b'\n')
+            .all(u8::is_ascii_whitespace)
+    }
+
+    /// Index of the line break ending the current line, or the end
+    fn line_end(&self) -> usize {
+        self.rest()
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(self.bytes.len(), |p| self.i + p)
+    }
+
+    /// Skip a `--` or `/* */` comment here, if there is one
+    fn skip_comment(&mut self) -> bool {
+        let rest = self.rest();
+        if rest.starts_with(b"--") {
+            self.i = self.line_end();
+            true
+        } else if rest.starts_with(b"/*") {
+            let len = rest[2..]
+                .windows(2)
+                .position(|w| w == b"*/")
+                .map_or(rest.len(), |p| p + 4);
+            self.advance(len);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Scan to the end of the statement starting here: returns where its
+    /// text ends, the line it ends on, and the line its first dollar quote
+    /// opens on
+    fn statement(&mut self, delimiter: &[u8]) -> (usize, usize, Option<usize>) {
+        let (mut end, mut end_line, mut dollar_line) = (self.i, self.line, None);
+        while let Some(b) = self.peek() {
+            if self.rest().starts_with(delimiter) {
+                let end = self.i;
+                self.advance(delimiter.len());
+                return (end, self.line, dollar_line);
+            }
+            if self.skip_comment() {
+                continue;
+            }
+            match b {
+                b'\'' | b'"' | b'`' => {
+                    let to = skip_quoted(self.bytes, self.i, b);
+                    self.advance(to - self.i);
+                }
+                b'$' if self.i == 0 || !is_ident(self.bytes[self.i - 1]) => {
+                    let tag_len = self.rest()[1..]
+                        .iter()
+                        .take_while(|&&c| is_ident(c))
+                        .count();
+                    let tag = &self.rest()[..(tag_len + 2).min(self.rest().len())];
+                    let is_tag = tag.last() == Some(&b'$')
+                        && !tag.get(1).is_some_and(|c| c.is_ascii_digit());
+                    if is_tag {
+                        let tag = tag.to_vec();
+                        dollar_line.get_or_insert(self.line);
+                        let close = self.rest()[tag.len()..]
+                            .windows(tag.len())
+                            .position(|w| w == tag.as_slice())
+                            .map_or(self.rest().len(), |p| p + 2 * tag.len());
+                        self.advance(close);
+                    } else {
+                        self.advance(1);
+                    }
+                }
+                _ => self.advance(1),
+            }
+            if !b.is_ascii_whitespace() {
+                (end, end_line) = (self.i, self.line);
+            }
+        }
+        (end, end_line, dollar_line)
+    }
+}
+
+/// Analyzer for SQL: the tables, views and other objects created as classes,
+/// routines as functions
+pub struct SqlAnalyzer;
+
+impl LanguageAnalyzer for SqlAnalyzer {
+    fn analyze(&self, content: &str, _file_path: &str) -> AnalysisResult {
+        let mut result = AnalysisResult::new(self.language_name());
+        for statement in parse(content) {
+            match statement.kind {
+                StatementKind::Definition => {
+                    result.classes.extend(statement.name.clone());
+                    result
+                        .structure_ranges
+                        .push((statement.start_line, statement.end_line));
+                }
+                StatementKind::Routine => {
+                    result.functions.extend(statement.name.clone());
+                    let signature_end = statement.body.map_or(statement.end_line, |b| b.0 - 1);
+                    result
+                        .structure_ranges
+                        .push((statement.start_line, signature_end));
+                }
+                StatementKind::Data | StatementKind::Other => {}
+            }
+        }
+        result
+    }
+
+    fn supported_extensions(&self) -> Vec<&str> {
+        vec!["sql"]
+    }
+
+    fn language_name(&self) -> &str {
+        "SQL"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(content: &str) -> Vec<(StatementKind, usize, usize, Option<String>, usize)> {
+        parse(content)
+            .into_iter()
+            .map(|s| (s.kind, s.start_line, s.end_line, s.name, s.rows))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_mysql_dump() {
+        let dump = "-- MySQL dump\n\
+                    /*!40101 SET NAMES utf8 */;\n\
+                    DROP TABLE IF EXISTS `users`;\n\
+                    CREATE TABLE `users` (\n  `id` int NOT NULL,\n  `bio` text\n);\n\
+                    LOCK TABLES `users` WRITE;\n\
+                    INSERT INTO `users` VALUES (1,'it\\'s; (fine)'),(2,'a\nb'),(3,NULL);\n\
+                    UNLOCK TABLES;\n\
+                    DELIMITER ;;\n\
+                    CREATE DEFINER=`root`@`%` PROCEDURE `touch`(IN n int)\n\
+                    BEGIN\n  UPDATE users SET id = n;\n  SELECT 1;\n\
+                    END ;;\n\
+                    DELIMITER ;\n";
+        use StatementKind::*;
+        let name = |n: &str| Some(n.to_string());
+        assert_eq!(
+            summary(dump),
+            [
+                (Definition, 3, 3, None, 0),
+                (Definition, 4, 7, name("users"), 0),
+                (Other, 8, 8, None, 0),
+                (Data, 9, 10, name("users"), 3),
+                (Other, 11, 11, None, 0),
+                (Other, 12, 12, None, 0),
+                (Routine, 13, 17, name("touch"), 0),
+                (Other, 18, 18, None, 0),
+            ]
+        );
+        assert_eq!(parse(dump)[6].body, Some((15, 16)));
+    }
+
+    #[test]
+    fn test_parse_postgres_dump() {
+        let dump = "SET statement_timeout = 0;\n\
+                    CREATE FUNCTION public.total(a integer, b integer) RETURNS integer\n\
+                    \x20   LANGUAGE plpgsql\n\
+                    \x20   AS $_$\nBEGIN\n  RETURN $1 + $2; -- not the end;\nEND;\n$_$;\n\
+                    CREATE TABLE public.orders (id integer);\n\
+                    COPY public.orders (id) FROM stdin;\n1\n2\n3\n\\.\n\
+                    ALTER TABLE ONLY public.orders ADD CONSTRAINT orders_pkey PRIMARY KEY (id);\n";
+        use StatementKind::*;
+        let statements = parse(dump);
+        assert_eq!(
+            summary(dump),
+            [
+                (Other, 1, 1, None, 0),
+                (Routine, 2, 8, Some("public.total".to_string()), 0),
+                (Definition, 9, 9, Some("public.orders".to_string()), 0),
+                (Data, 10, 14, Some("public.orders".to_string()), 3),
+                (Definition, 15, 15, None, 0),
+            ]
+        );
+        assert_eq!(statements[1].body, Some((5, 7)));
+    }
+
+    #[test]
+    fn test_analyzer_reports_tables_and_routines() {
+        let sql = "CREATE TABLE t (id int);\nCREATE VIEW v AS SELECT 1;\n\
+                   CREATE OR REPLACE FUNCTION f() RETURNS int AS $$\n  SELECT 1\n$$ LANGUAGE sql;\n\
+                   INSERT INTO t VALUES (1);\n";
+        let result = SqlAnalyzer.analyze(sql, "schema.sql");
+        assert_eq!(result.language, "SQL");
+        assert_eq!(result.classes, ["t", "v"]);
+        assert_eq!(result.functions, ["f"]);
+        assert_eq!(result.structure_ranges, [(1, 1), (2, 2), (3, 3)]);
+    }
+}
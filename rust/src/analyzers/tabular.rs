@@ -0,0 +1,316 @@
+//! CSV and TSV files
+//!
+//! Data files don't truncate well by lines: a cut can fall inside a quoted
+//! field that spans lines, and the rows that survive say nothing about the
+//! ones that didn't. [`Table`] splits a file into records, honouring quotes,
+//! and [`ColumnStats`] sums up each column, so truncation can keep the
+//! header and the first rows whole and describe the rest.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::{AnalysisResult, LanguageAnalyzer};
+
+/// Distinct values counted per column before the count is reported as a floor
+const DISTINCT_LIMIT: usize = 1000;
+
+/// Whether `file_path` is a CSV or TSV file
+pub fn is_table(file_path: &str) -> bool {
+    let lower = file_path.to_lowercase();
+    lower.ends_with(".csv") || lower.ends_with(".tsv")
+}
+
+/// One record: its fields and the lines (1-indexed, inclusive) it spans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub fields: Vec<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// A parsed CSV or TSV file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub delimiter: char,
+    pub header: Record,
+    /// Data records; blank lines are not records
+    pub rows: Vec<Record>,
+}
+
+impl Table {
+    /// Parse `content`; `None` when it has no header or ends inside a quoted field
+    ///
+    /// `.tsv` files are tab-separated. `.csv` files are comma-separated,
+    /// unless the header has more semicolons than commas.
+    pub fn parse(content: &str, file_path: &str) -> Option<Self> {
+        let first = content.lines().next().unwrap_or("");
+        let delimiter = if file_path.to_lowercase().ends_with(".tsv") {
+            '\t'
+        } else if first.matches(';').count() > first.matches(',').count() {
+            ';'
+        } else {
+            ','
+        };
+
+        let mut records = Vec::new();
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut line = 1;
+        let mut start_line = 1;
+        let mut chars = content.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    '\n' => {
+                        line += 1;
+                        field.push(c);
+                    }
+                    _ => field.push(c),
+                }
+                continue;
+            }
+            match c {
+                '"' if field.is_empty() => in_quotes = true,
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' => {
+                    fields.push(std::mem::take(&mut field));
+                    push_record(&mut records, std::mem::take(&mut fields), start_line, line);
+                    line += 1;
+                    start_line = line;
+                }
+                c if c == delimiter => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+        if in_quotes {
+            return None;
+        }
+        if !field.is_empty() || !fields.is_empty() {
+            fields.push(field);
+            push_record(&mut records, fields, start_line, line);
+        }
+
+        let mut records = records.into_iter();
+        let header = records.next()?;
+        Some(Self {
+            delimiter,
+            header,
+            rows: records.collect(),
+        })
+    }
+
+    /// Statistics for each column named in the header
+    pub fn column_stats(&self) -> Vec<ColumnStats> {
+        self.header
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let values = self
+                    .rows
+                    .iter()
+                    .map(|row| row.fields.get(i).map_or("", |v| v.trim()));
+                ColumnStats::collect(name.trim(), values)
+            })
+            .collect()
+    }
+}
+
+fn push_record(records: &mut Vec<Record>, fields: Vec<String>, start_line: usize, end_line: usize) {
+    let blank = fields.len() == 1 && fields[0].trim().is_empty();
+    if !blank {
+        records.push(Record {
+            fields,
+            start_line,
+            end_line,
+        });
+    }
+}
+
+/// What a column holds, judged from its non-empty values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Integer,
+    Number,
+    Boolean,
+    Text,
+}
+
+impl fmt::Display for ColumnKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ColumnKind::Integer => "integer",
+            ColumnKind::Number => "number",
+            ColumnKind::Boolean => "boolean",
+            ColumnKind::Text => "text",
+        })
+    }
+}
+
+/// Summary of one column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub name: String,
+    pub kind: ColumnKind,
+    /// Rows where the column is empty or missing
+    pub empty: usize,
+    /// Distinct non-empty values, at most [`DISTINCT_LIMIT`]
+    pub distinct: usize,
+    /// Smallest, largest and mean value of a numeric column
+    pub range: Option<(f64, f64, f64)>,
+}
+
+impl ColumnStats {
+    fn collect<'a>(name: &str, values: impl Iterator<Item = &'a str>) -> Self {
+        let mut empty = 0;
+        let mut seen = HashSet::new();
+        let mut numbers = Vec::new();
+        let (mut integers, mut floats, mut booleans, mut filled) = (true, true, true, 0);
+        for value in values {
+            if value.is_empty() {
+                empty += 1;
+                continue;
+            }
+            filled += 1;
+            if seen.len() < DISTINCT_LIMIT {
+                seen.insert(value);
+            }
+            integers &= value.parse::<i64>().is_ok();
+            match value.parse::<f64>() {
+                Ok(n) if n.is_finite() => numbers.push(n),
+                _ => floats = false,
+            }
+            booleans &= value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false");
+        }
+
+        let kind = match filled {
+            0 => ColumnKind::Text,
+            _ if integers => ColumnKind::Integer,
+            _ if floats => ColumnKind::Number,
+            _ if booleans => ColumnKind::Boolean,
+            _ => ColumnKind::Text,
+        };
+        let range = matches!(kind, ColumnKind::Integer | ColumnKind::Number).then(|| {
+            let min = numbers.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            (min, max, numbers.iter().sum::<f64>() / numbers.len() as f64)
+        });
+        Self {
+            name: name.to_string(),
+            kind,
+            empty,
+            distinct: seen.len(),
+            range,
+        }
+    }
+}
+
+impl fmt::Display for ColumnStats {
+    /// `amount: number, -3.5 to 912.25, mean 40.12, 3 empty`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.kind)?;
+        match (self.kind, self.range) {
+            (_, Some((min, max, mean))) => write!(f, ", {} to {}, mean {:.2}", min, max, mean)?,
+            (ColumnKind::Text, None) if self.distinct >= DISTINCT_LIMIT => {
+                write!(f, ", {}+ distinct", DISTINCT_LIMIT)?
+            }
+            (ColumnKind::Text, None) => write!(f, ", {} distinct", self.distinct)?,
+            _ => {}
+        }
+        if self.empty > 0 {
+            write!(f, ", {} empty", self.empty)?;
+        }
+        Ok(())
+    }
+}
+
+/// Analyzer for CSV and TSV files: the header's column names as keys
+pub struct TabularAnalyzer;
+
+impl LanguageAnalyzer for TabularAnalyzer {
+    fn analyze(&self, content: &str, file_path: &str) -> AnalysisResult {
+        let mut result = AnalysisResult::new(self.language_name());
+        result.category = "data".to_string();
+        if let Some(table) = Table::parse(content, file_path) {
+            result.config_keys = table
+                .header
+                .fields
+                .iter()
+                .map(|name| name.trim().to_string())
+                .collect();
+            result.structure_ranges = vec![(table.header.start_line, table.header.end_line)];
+        }
+        result
+    }
+
+    fn supported_extensions(&self) -> Vec<&str> {
+        vec!["csv", "tsv"]
+    }
+
+    fn language_name(&self) -> &str {
+        "csv"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quoted_records() {
+        let csv = "id,note\r\n1,\"two\nlines, quoted \"\"here\"\"\"\r\n\r\n2,plain\r\n";
+        let table = Table::parse(csv, "notes.csv").unwrap();
+        assert_eq!(table.delimiter, ',');
+        assert_eq!(table.header.fields, ["id", "note"]);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0].fields[1], "two\nlines, quoted \"here\"");
+        assert_eq!((table.rows[0].start_line, table.rows[0].end_line), (2, 3));
+        assert_eq!((table.rows[1].start_line, table.rows[1].end_line), (5, 5));
+
+        let table = Table::parse("a\tb\n1\t2", "x.tsv").unwrap();
+        assert_eq!(table.delimiter, '\t');
+        assert_eq!(table.rows[0].fields, ["1", "2"]);
+        assert_eq!(
+            Table::parse("a;b;c\n1;2;3\n", "x.csv").unwrap().delimiter,
+            ';'
+        );
+
+        assert!(Table::parse("a,b\n\"open,1\n", "x.csv").is_none());
+        assert!(Table::parse("", "x.csv").is_none());
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let csv = "id,amount,city,active,comment\n\
+                   1,3.5,Oslo,true,\n\
+                   2,-1,Lima,FALSE,\n\
+                   3,10,Oslo,true,\n\
+                   4,,Pune,false,\n";
+        let stats = Table::parse(csv, "t.csv").unwrap().column_stats();
+        let lines: Vec<String> = stats.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "id: integer, 1 to 4, mean 2.50",
+                "amount: number, -1 to 10, mean 4.17, 1 empty",
+                "city: text, 3 distinct",
+                "active: boolean",
+                "comment: text, 0 distinct, 4 empty",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyzer_reports_columns() {
+        let result = TabularAnalyzer.analyze("id,name\n1,a\n", "users.csv");
+        assert_eq!(result.category, "data");
+        assert_eq!(result.config_keys, ["id", "name"]);
+        assert_eq!(result.structure_ranges, [(1, 1)]);
+    }
+}
@@ -0,0 +1,372 @@
+//! Per-path attributes from `.pmattributes` files
+//!
+//! Like `.gitattributes`: each line maps a path pattern to attributes, and a
+//! file can sit in any directory, its patterns matched relative to it.
+//!
+//! ```text
+//! # pattern            attributes
+//! api/*.pb.go          linguist-generated
+//! vendor/              linguist-vendored
+//! src/schema.rs        truncate=structure
+//! ARCHITECTURE.md      pin -truncate
+//! fixtures/tokens.json redact
+//! ```
+//!
+//! Patterns follow the `v2` pattern syntax (see
+//! [`crate::discovery::pattern_syntax`]). An attribute is set by naming it,
+//! unset with `-name`, given a value with `name=value`, and returned to
+//! unspecified with `!name`. Later lines override earlier ones and deeper
+//! files override shallower ones. Unknown attributes are ignored.
+//!
+//! - `linguist-generated` / `linguist-vendored`: the file is generated or
+//!   vendored (or, unset, is not) as far as `--generated` is concerned
+//! - `truncate=MODE`: the file's truncation mode; `-truncate` never
+//!   truncates it
+//! - `pin`: a token budget keeps the file in full, ahead of everything else
+//! - `redact`: the content is replaced by a one-line placeholder
+
+use std::path::Path;
+
+use crate::core::SmartWalker;
+use crate::discovery::pattern_syntax::{matches_v2, CASE_INSENSITIVE_FS};
+use crate::discovery::{Generated, GeneratedKind};
+
+/// File name looked for in every directory
+pub const ATTRIBUTES_FILE_NAME: &str = ".pmattributes";
+
+/// Truncation modes `truncate=` accepts
+const TRUNCATE_MODES: &[&str] = &[
+    "simple",
+    "tail",
+    "smart",
+    "structure",
+    "outline",
+    "tokens",
+    "strip-comments",
+];
+
+/// The resolved attributes of one path; `None` where nothing specifies one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileAttributes {
+    pub generated: Option<bool>,
+    pub vendored: Option<bool>,
+    /// `Some(None)` for `-truncate`: never truncate
+    pub truncate: Option<Option<String>>,
+    pub pin: Option<bool>,
+    pub redact: Option<bool>,
+}
+
+impl FileAttributes {
+    pub fn pinned(&self) -> bool {
+        self.pin == Some(true)
+    }
+
+    pub fn redacted(&self) -> bool {
+        self.redact == Some(true)
+    }
+
+    /// The `truncate` mode: `Some(None)` for `-truncate`
+    pub fn truncate_mode(&self) -> Option<Option<&'static str>> {
+        self.truncate.as_ref().map(|mode| {
+            TRUNCATE_MODES
+                .iter()
+                .copied()
+                .find(|m| Some(*m) == mode.as_deref())
+        })
+    }
+
+    /// Generated/vendored classification per the attributes: `Some(None)`
+    /// when they say neither, `None` when they don't say
+    pub fn generated(&self) -> Option<Option<Generated>> {
+        let reason = |attribute: &str| format!("{} in {}", attribute, ATTRIBUTES_FILE_NAME);
+        match (self.vendored, self.generated) {
+            (Some(true), _) => Some(Some(Generated {
+                kind: GeneratedKind::Vendored,
+                reason: reason("linguist-vendored"),
+            })),
+            (_, Some(true)) => Some(Some(Generated {
+                kind: GeneratedKind::Generated,
+                reason: reason("linguist-generated"),
+            })),
+            (Some(false), _) | (_, Some(false)) => Some(None),
+            (None, None) => None,
+        }
+    }
+}
+
+/// One attribute assignment on a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Assignment {
+    Generated(Option<bool>),
+    Vendored(Option<bool>),
+    Truncate(Option<Option<String>>),
+    Pin(Option<bool>),
+    Redact(Option<bool>),
+}
+
+impl Assignment {
+    fn apply(&self, attributes: &mut FileAttributes) {
+        match self {
+            Self::Generated(v) => attributes.generated = *v,
+            Self::Vendored(v) => attributes.vendored = *v,
+            Self::Truncate(v) => attributes.truncate = v.clone(),
+            Self::Pin(v) => attributes.pin = *v,
+            Self::Redact(v) => attributes.redact = *v,
+        }
+    }
+}
+
+/// A pattern line
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: String,
+    assignments: Vec<Assignment>,
+}
+
+/// One `.pmattributes` file and the directory it applies to
+#[derive(Debug, Clone)]
+struct AttributesFile {
+    /// Directory relative to the project root, `/`-separated
+    dir: String,
+    rules: Vec<Rule>,
+}
+
+/// Parse the content of a `.pmattributes` file
+fn parse(content: &str) -> Result<Vec<Rule>, String> {
+    let mut rules = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let Some(pattern) = words.next() else {
+            continue;
+        };
+        let mut assignments = Vec::new();
+        for word in words {
+            match parse_assignment(word) {
+                Ok(Some(assignment)) => assignments.push(assignment),
+                Ok(None) => {}
+                Err(e) => return Err(format!("line {}: {}", number + 1, e)),
+            }
+        }
+        rules.push(Rule {
+            pattern: pattern.to_string(),
+            assignments,
+        });
+    }
+    Ok(rules)
+}
+
+/// Parse `name`, `-name`, `!name` or `name=value`; `None` for unknown names
+fn parse_assignment(word: &str) -> Result<Option<Assignment>, String> {
+    let (name, state) = if let Some(name) = word.strip_prefix('-') {
+        (name, Some(false))
+    } else if let Some(name) = word.strip_prefix('!') {
+        (name, None)
+    } else {
+        (word, Some(true))
+    };
+    let (name, value) = match name.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (name, None),
+    };
+    let flag = |value: Option<&str>| match value {
+        None => Ok(state),
+        Some("true") => Ok(Some(true)),
+        Some("false") => Ok(Some(false)),
+        Some(other) => Err(format!("'{}' takes true or false, not '{}'", name, other)),
+    };
+    Ok(Some(match name {
+        "linguist-generated" => Assignment::Generated(flag(value)?),
+        "linguist-vendored" => Assignment::Vendored(flag(value)?),
+        "pin" => Assignment::Pin(flag(value)?),
+        "redact" => Assignment::Redact(flag(value)?),
+        "truncate" => Assignment::Truncate(match (state, value) {
+            (_, Some(mode)) if TRUNCATE_MODES.contains(&mode) => Some(Some(mode.to_string())),
+            (_, Some(mode)) => {
+                return Err(format!(
+                    "unknown truncate mode '{}'. Valid options: {}",
+                    mode,
+                    TRUNCATE_MODES.join(", ")
+                ))
+            }
+            (Some(false), None) => Some(None),
+            (None, None) => None,
+            (Some(true), None) => return Err("truncate needs a mode (truncate=MODE)".to_string()),
+        }),
+        _ => return Ok(None),
+    }))
+}
+
+/// `.pmattributes` files found below a project root
+#[derive(Debug, Clone, Default)]
+pub struct PathAttributes {
+    /// Sorted outermost first
+    files: Vec<AttributesFile>,
+}
+
+impl PathAttributes {
+    /// Find and parse every `.pmattributes` file in `root`'s tree,
+    /// including the root's own
+    ///
+    /// Hygiene-excluded and gitignored directories are skipped; a malformed
+    /// file is an error.
+    pub fn discover(root: &Path) -> Result<Self, String> {
+        Self::discover_prefixed(root, "")
+    }
+
+    /// Like [`Self::discover`], for a root whose paths are reported under
+    /// `prefix`
+    pub fn discover_prefixed(root: &Path, prefix: &str) -> Result<Self, String> {
+        let mut files = Vec::new();
+        let walker = ignore::WalkBuilder::new(root)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(|entry| !SmartWalker::is_hygiene_excluded(entry.path()))
+            .build();
+        for entry in walker.flatten() {
+            if entry.file_name() != ATTRIBUTES_FILE_NAME
+                || !entry.file_type().is_some_and(|ft| ft.is_file())
+            {
+                continue;
+            }
+            let Some(rel) = entry
+                .path()
+                .parent()
+                .and_then(|d| d.strip_prefix(root).ok())
+            else {
+                continue;
+            };
+            let content = std::fs::read_to_string(entry.path())
+                .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+            let rules = parse(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", entry.path().display(), e))?;
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            let dir = match (prefix.is_empty(), rel.is_empty()) {
+                (true, _) => rel,
+                (false, true) => prefix.to_string(),
+                (false, false) => format!("{}/{}", prefix, rel),
+            };
+            files.push(AttributesFile { dir, rules });
+        }
+        let mut attributes = Self { files };
+        attributes.sort();
+        Ok(attributes)
+    }
+
+    /// Add the files of another (prefixed) root
+    pub fn extend(&mut self, other: Self) {
+        self.files.extend(other.files);
+        self.sort();
+    }
+
+    /// Outermost first, so deeper files win
+    fn sort(&mut self) {
+        let depth = |f: &AttributesFile| match f.dir.as_str() {
+            "" => 0,
+            dir => dir.matches('/').count() + 1,
+        };
+        self.files
+            .sort_by(|a, b| depth(a).cmp(&depth(b)).then_with(|| a.dir.cmp(&b.dir)));
+    }
+
+    /// Whether no `.pmattributes` files were found
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The attributes of `path` (relative to the root)
+    pub fn for_path(&self, path: &str) -> FileAttributes {
+        let mut attributes = FileAttributes::default();
+        for file in &self.files {
+            let local = if file.dir.is_empty() {
+                Some(path)
+            } else {
+                path.strip_prefix(file.dir.as_str())
+                    .and_then(|p| p.strip_prefix('/'))
+            };
+            let Some(local) = local else {
+                continue;
+            };
+            for rule in &file.rules {
+                if matches_v2(local, &rule.pattern, false, CASE_INSENSITIVE_FS) {
+                    for assignment in &rule.assignments {
+                        assignment.apply(&mut attributes);
+                    }
+                }
+            }
+        }
+        attributes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes(files: &[(&str, &str)]) -> PathAttributes {
+        let temp = tempfile::TempDir::new().unwrap();
+        for (dir, content) in files {
+            let dir = temp.path().join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(ATTRIBUTES_FILE_NAME), content).unwrap();
+        }
+        PathAttributes::discover(temp.path()).unwrap()
+    }
+
+    #[test]
+    fn test_later_and_deeper_rules_win() {
+        let attributes = attributes(&[
+            (
+                "",
+                "# root\n*.json redact\nvendor/ linguist-vendored\ndocs/*.md pin truncate=structure\n",
+            ),
+            ("fixtures", "public.json -redact\n"),
+        ]);
+
+        assert!(attributes.for_path("config/keys.json").redacted());
+        assert!(!attributes.for_path("fixtures/public.json").redacted());
+        assert!(attributes.for_path("fixtures/other.json").redacted());
+
+        let docs = attributes.for_path("docs/guide.md");
+        assert!(docs.pinned());
+        assert_eq!(docs.truncate, Some(Some("structure".to_string())));
+        assert_eq!(attributes.for_path("site/docs/guide.md").pin, None);
+
+        let vendored = attributes
+            .for_path("vendor/lib/a.c")
+            .generated()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            vendored.to_string(),
+            "vendored (linguist-vendored in .pmattributes)"
+        );
+        assert_eq!(
+            attributes.for_path("src/main.rs"),
+            FileAttributes::default()
+        );
+    }
+
+    #[test]
+    fn test_assignment_forms() {
+        let rules = parse("a.lock -linguist-generated !pin truncate=smart\nb -truncate x-custom\n")
+            .unwrap();
+        assert_eq!(
+            rules[0].assignments,
+            vec![
+                Assignment::Generated(Some(false)),
+                Assignment::Pin(None),
+                Assignment::Truncate(Some(Some("smart".to_string()))),
+            ]
+        );
+        // Unknown attributes are skipped
+        assert_eq!(rules[1].assignments, vec![Assignment::Truncate(Some(None))]);
+
+        let err = parse("\n\nsrc/*.rs truncate=fancy\n").unwrap_err();
+        assert!(err.starts_with("line 3: unknown truncate mode 'fancy'"));
+        assert!(parse("x pin=maybe").is_err());
+    }
+}
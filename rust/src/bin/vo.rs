@@ -18,24 +18,114 @@
 #![allow(clippy::ptr_arg)]
 #![allow(clippy::unnecessary_sort_by)]
 
-use clap::{Parser, ValueEnum};
+use clap::{ArgAction, Parser, ValueEnum};
+use pm_encoder::analyzers::lockfile::LockfilePolicy;
 use pm_encoder::core::{
-    ContextEngine, ContextStore, DetailLevel, IntelligentPresenter, ObserversJournal,
-    SemanticDepth, SkeletonMode, ZoomConfig, ZoomTarget, DEFAULT_ALPHA,
+    ColorChoice, ContextEngine, ContextStore, DetailLevel, IntelligentPresenter, ObserversJournal,
+    Palette, SemanticDepth, SkeletonMode, ZoomConfig, ZoomTarget, DEFAULT_ALPHA,
 };
-use pm_encoder::server::McpServer;
+use pm_encoder::discovery::archive::is_archive;
+use pm_encoder::discovery::{BinaryPolicy, GeneratedPolicy, PatternSyntax};
+use pm_encoder::server::{AccessPolicy, AuditLog, McpServer};
 use pm_encoder::{
-    self, apply_token_budget, parse_token_budget, EncoderConfig, LensManager, OutputFormat,
+    self, apply_token_budget_for_format, parse_token_budget, EncoderConfig, LensManager,
+    OutputFormat, StreamOrder, SymlinkPolicy,
 };
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+// =============================================================================
+// Exit codes
+// =============================================================================
+
+/// Process exit codes (a stable contract for scripts and CI)
+///
+/// `PARTIAL`, `BUDGET_EXCEEDED` and `CONFIG` are only used with `--strict`;
+/// without it those conditions are warnings and the run exits 0.
+mod exit_code {
+    /// Any other failure: I/O, serialization, missing input
+    pub const ERROR: i32 = 1;
+    /// Files were found but could not be read
+    pub const PARTIAL: i32 = 2;
+    /// Files were dropped to fit `--token-budget`
+    pub const BUDGET_EXCEEDED: i32 = 3;
+    /// A config file could not be read or parsed
+    pub const CONFIG: i32 = 4;
+    /// Invalid command-line arguments
+    pub const USAGE: i32 = 5;
+}
+
+// =============================================================================
+// Status output (--quiet / --porcelain)
+// =============================================================================
+
+/// How status is reported on stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusMode {
+    /// Human narration (default)
+    Narrate,
+    /// Warnings and errors only
+    Quiet,
+    /// Tab-separated records for scripts, plus errors
+    Porcelain,
+}
+
+static STATUS_MODE: std::sync::OnceLock<StatusMode> = std::sync::OnceLock::new();
+
+fn status_mode() -> StatusMode {
+    STATUS_MODE.get().copied().unwrap_or(StatusMode::Narrate)
+}
+
+/// Human-readable status on stderr, silenced by --quiet and --porcelain
+macro_rules! narrate {
+    ($($arg:tt)*) => {
+        if status_mode() == StatusMode::Narrate {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// `Warning: ...` on stderr, or a `warning` record with --porcelain
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        if status_mode() == StatusMode::Porcelain {
+            porcelain_record("warning", &[&format!($($arg)*)]);
+        } else {
+            eprintln!("Warning: {}", format!($($arg)*));
+        }
+    };
+}
+
+/// Print one `key<TAB>field...` record to stderr (only with --porcelain)
+///
+/// Backslashes, tabs and newlines inside fields are escaped as `\\`, `\t`
+/// and `\n`, so every record is exactly one line.
+fn porcelain_record(key: &str, fields: &[&dyn std::fmt::Display]) {
+    if status_mode() != StatusMode::Porcelain {
+        return;
+    }
+    let mut line = key.to_string();
+    for field in fields {
+        line.push('\t');
+        for c in field.to_string().chars() {
+            match c {
+                '\\' => line.push_str("\\\\"),
+                '\t' => line.push_str("\\t"),
+                '\n' => line.push_str("\\n"),
+                c => line.push(c),
+            }
+        }
+    }
+    eprintln!("{}", line);
+}
+
 /// 🌌 Voyager Observatory: Navigate the code galaxy with ease.
 ///
 /// An intuitive instrument for code exploration with semantic analysis,
 /// constellation mapping, and the Observer's Journal.
 #[derive(Parser, Debug)]
 #[command(name = "vo")]
+#[command(group(clap::ArgGroup::new("dry_run_target").args(["decode", "migrate"]).multiple(true)))]
 #[command(version = pm_encoder::VERSION)]
 #[command(about = "🌌 Voyager Observatory: Navigate the code galaxy")]
 #[command(after_help = "EXAMPLES:
@@ -60,6 +150,14 @@ use std::path::{Path, PathBuf};
   # Zoom into a specific function
   vo . --zoom fn=calculate_total
 
+EXIT CODES:
+  0  success
+  1  error
+  2  files skipped as unreadable (--strict)
+  3  files dropped to fit --token-budget (--strict)
+  4  config file error (--strict)
+  5  invalid arguments
+
 The code galaxy awaits. 🌌
 ")]
 struct Cli {
@@ -70,6 +168,28 @@ struct Cli {
     #[arg(value_name = "PATH", help_heading = "🔭 VIEWFINDER (Essential)")]
     project_root: Option<PathBuf>,
 
+    /// More project directories, combined into the same context (needs --prefix-roots or a --prefix per root)
+    #[arg(value_name = "MORE_PATHS", help_heading = "🔭 VIEWFINDER (Essential)")]
+    extra_roots: Vec<PathBuf>,
+
+    /// Serialize a remote git repository (shallow clone) or tarball URL instead of PATH
+    #[arg(
+        long = "remote",
+        value_name = "URL[#REV]",
+        conflicts_with = "project_root",
+        help_heading = "🔭 VIEWFINDER (Essential)"
+    )]
+    remote: Option<String>,
+
+    /// Serialize PATH as of a git branch, tag or commit, read from the object database (no checkout)
+    #[arg(
+        long = "git-ref",
+        value_name = "REF",
+        conflicts_with_all = ["remote", "since", "changed_since"],
+        help_heading = "🔭 VIEWFINDER (Essential)"
+    )]
+    git_ref: Option<String>,
+
     /// What to look for [architecture, debug, security, onboarding, minimal]
     #[arg(
         long = "lens",
@@ -87,7 +207,16 @@ struct Cli {
     )]
     output: Option<PathBuf>,
 
-    /// Output format [plus-minus, xml, markdown, claude-xml]
+    /// Compress the output file on the fly [gzip, zstd]
+    #[arg(
+        long = "compress",
+        value_enum,
+        value_name = "ALGO",
+        help_heading = "🔭 VIEWFINDER (Essential)"
+    )]
+    compress: Option<CompressArg>,
+
+    /// Output format [plus-minus, xml, markdown, claude-xml, gemini]
     #[arg(
         long = "format",
         value_enum,
@@ -96,6 +225,22 @@ struct Cli {
     )]
     format: OutputFormatArg,
 
+    /// Prefix each content line with its line number (for `file=path:L1-L2` zoom targets)
+    #[arg(long = "line-numbers", help_heading = "🔭 VIEWFINDER (Essential)")]
+    line_numbers: bool,
+
+    /// End the output with a glossary of the project's own acronyms and terms
+    #[arg(long = "glossary", help_heading = "🔭 VIEWFINDER (Essential)")]
+    glossary: bool,
+
+    /// Emit identical files once; later copies become a "duplicate of <path>" stub
+    #[arg(long = "dedupe", help_heading = "🔭 VIEWFINDER (Essential)")]
+    dedupe: bool,
+
+    /// Add language, token-estimate and utility hints to each file header
+    #[arg(long = "header-hints", help_heading = "🔭 VIEWFINDER (Essential)")]
+    header_hints: bool,
+
     // ═══════════════════════════════════════════════════════════════════════════
     // 🔍 LENS FILTERS (Context Control)
     // ═══════════════════════════════════════════════════════════════════════════
@@ -107,6 +252,10 @@ struct Cli {
     #[arg(long = "exclude", value_name = "PATTERN", num_args = 0.., help_heading = "🔍 LENS FILTERS")]
     exclude: Vec<String>,
 
+    /// Exclude files whose content matches regex (e.g. 'PM_ENCODER: SKIP')
+    #[arg(long = "exclude-content", value_name = "REGEX", num_args = 0.., help_heading = "🔍 LENS FILTERS")]
+    exclude_content: Vec<String>,
+
     /// Analysis depth [quick, balanced, deep]
     #[arg(
         long = "semantic-depth",
@@ -116,7 +265,7 @@ struct Cli {
     )]
     semantic_depth: SemanticDepthArg,
 
-    /// Use cached analysis (deterministic output)
+    /// Canonical, byte-identical output: no timestamps, `/` paths, stable order, trailing sha256
     #[arg(long = "frozen", help_heading = "🔍 LENS FILTERS")]
     frozen: bool,
 
@@ -124,6 +273,14 @@ struct Cli {
     #[arg(long = "allow-sensitive", help_heading = "🔍 LENS FILTERS")]
     allow_sensitive: bool,
 
+    /// Focus on files historically associated with a ticket (e.g., JIRA-123, #456)
+    #[arg(
+        long = "focus-ticket",
+        value_name = "TICKET",
+        help_heading = "🔍 LENS FILTERS"
+    )]
+    focus_ticket: Option<String>,
+
     /// Config file path
     #[arg(
         short = 'c',
@@ -144,6 +301,33 @@ struct Cli {
     )]
     zoom: Option<String>,
 
+    /// Export the call graph from --callgraph-root as a graph file [dot, mermaid]
+    #[arg(
+        long = "callgraph-export",
+        value_name = "FORMAT",
+        value_enum,
+        requires = "callgraph_root",
+        help_heading = "🔬 MAGNIFICATION"
+    )]
+    callgraph_export: Option<CallGraphFormat>,
+
+    /// Root function for --callgraph-export
+    #[arg(
+        long = "callgraph-root",
+        value_name = "SYMBOL",
+        help_heading = "🔬 MAGNIFICATION"
+    )]
+    callgraph_root: Option<String>,
+
+    /// Maximum call depth followed by --callgraph-export
+    #[arg(
+        long = "callgraph-depth",
+        value_name = "N",
+        default_value_t = pm_encoder::core::DEFAULT_CALLGRAPH_DEPTH,
+        help_heading = "🔬 MAGNIFICATION"
+    )]
+    callgraph_depth: usize,
+
     /// Show skeleton only (signatures without bodies)
     #[arg(
         long = "skeleton",
@@ -162,7 +346,8 @@ struct Cli {
     )]
     truncate: usize,
 
-    /// Truncation mode [simple, smart, structure]
+    /// Truncation mode [simple, tail, smart, structure, outline, tokens, strip-comments];
+    /// with tokens, --truncate counts estimated tokens
     #[arg(
         long = "truncate-mode",
         value_enum,
@@ -171,6 +356,23 @@ struct Cli {
     )]
     truncate_mode: TruncateMode,
 
+    /// Remove comment lines before truncating, whatever the mode
+    #[arg(long = "strip-comments", help_heading = "🔬 MAGNIFICATION")]
+    strip_comments: bool,
+
+    /// Always keep lines matching REGEX, whatever the truncation mode (e.g. "TODO|FIXME|SAFETY")
+    #[arg(long = "truncate-keep", value_name = "REGEX", num_args = 0.., help_heading = "🔬 MAGNIFICATION")]
+    truncate_keep: Vec<String>,
+
+    /// Leave control characters in file content as they are instead of escaping them as \xNN
+    #[arg(long = "keep-control-chars", help_heading = "⚙️ ADVANCED")]
+    keep_control_chars: bool,
+
+    /// Check the output is valid UTF-8 without control characters (and, in
+    /// Plus/Minus, decodes) before writing it; exit with an error if not
+    #[arg(long = "validate-output", help_heading = "⚙️ ADVANCED")]
+    validate_output: bool,
+
     /// Never truncate files matching pattern
     #[arg(long = "truncate-exclude", value_name = "PATTERN", num_args = 0.., help_heading = "🔬 MAGNIFICATION")]
     truncate_exclude: Vec<String>,
@@ -186,6 +388,24 @@ struct Cli {
     )]
     token_budget: Option<String>,
 
+    /// Split output into parts of at most N tokens (e.g., 100k), cut at file boundaries
+    #[arg(
+        long = "split-by-tokens",
+        value_name = "N",
+        help_heading = "🔋 POWER GRID"
+    )]
+    split_by_tokens: Option<String>,
+
+    /// Split output into sections; roles: a stable system section (tree, index, pinned docs) and a volatile user section (changed files, zoom targets)
+    #[arg(
+        long = "split",
+        value_enum,
+        value_name = "MODE",
+        conflicts_with_all = ["token_budget", "split_by_tokens", "bundle", "stream", "since", "manifest"],
+        help_heading = "🔋 POWER GRID"
+    )]
+    split: Option<SplitArg>,
+
     /// Budget strategy [drop, truncate, hybrid]
     #[arg(
         long = "budget-strategy",
@@ -206,7 +426,7 @@ struct Cli {
     )]
     explore: Option<String>,
 
-    /// Output detail level [summary, smart, detailed]
+    /// Detail level for --explore, --survey and the mission log [summary, smart, detailed]
     #[arg(
         long = "detail",
         value_enum,
@@ -275,7 +495,7 @@ struct Cli {
     #[arg(long = "store-privacy", help_heading = "⚙️ ADVANCED")]
     store_privacy: bool,
 
-    /// Sort files by [name, mtime, ctime]
+    /// Sort files by [name, mtime, ctime, size, ext, utility]
     #[arg(
         long = "sort-by",
         value_enum,
@@ -297,9 +517,146 @@ struct Cli {
     #[arg(long = "stream", help_heading = "⚙️ ADVANCED")]
     stream: bool,
 
-    /// Follow symbolic links (default: skip broken symlinks silently)
-    #[arg(long = "follow-symlinks", help_heading = "⚙️ ADVANCED")]
-    follow_symlinks: bool,
+    /// Order --stream emits files in: as found, or by lens priority and learned utility
+    #[arg(
+        long = "stream-order",
+        value_enum,
+        value_name = "ORDER",
+        default_value = "walk",
+        requires = "stream",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    stream_order: StreamOrderArg,
+
+    /// Write processed files plus manifest.json into a .tar.gz bundle
+    #[arg(long = "bundle", value_name = "FILE", help_heading = "⚙️ ADVANCED")]
+    bundle: Option<PathBuf>,
+
+    /// Diff-embedding mode: unchanged files become structure stubs, changed files carry a diff since REV
+    #[arg(
+        long = "since",
+        value_name = "REV",
+        conflicts_with_all = ["token_budget", "split_by_tokens", "bundle", "stream", "manifest", "prefix_roots", "prefix"],
+        help_heading = "⚙️ ADVANCED"
+    )]
+    since: Option<String>,
+
+    /// Only include files changed since a git revision (HEAD~3, main) or within a window (2h, 7d)
+    #[arg(
+        long = "changed-since",
+        value_name = "REV|DURATION",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    changed_since: Option<String>,
+
+    /// Only include the files listed in FILE, one per line, relative to PATH (`-` reads stdin)
+    #[arg(long = "files-from", value_name = "FILE", help_heading = "⚙️ ADVANCED")]
+    files_from: Option<PathBuf>,
+
+    /// Also write <output>.manifest.json (per-file md5, size, tokens, truncation, lens, budget)
+    #[arg(long = "manifest", help_heading = "⚙️ ADVANCED")]
+    manifest: bool,
+
+    /// Merge the output into the Plus/Minus bundle already at -o instead of overwriting it
+    #[arg(
+        long = "append",
+        requires = "output",
+        conflicts_with_all = ["token_budget", "split_by_tokens", "split", "bundle", "stream", "since", "compress"],
+        help_heading = "⚙️ ADVANCED"
+    )]
+    append: bool,
+
+    /// Which symbolic links to follow: on (default), off, or safe (only those resolving inside the project)
+    #[arg(
+        long = "follow-symlinks",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "on",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    follow_symlinks: Option<SymlinkArg>,
+
+    /// Don't honour .gitignore, .ignore or git exclude files
+    #[arg(long = "no-gitignore", help_heading = "⚙️ ADVANCED")]
+    no_gitignore: bool,
+
+    /// Only include files tracked by git (walks the filesystem outside a repository)
+    #[arg(long = "git-only", help_heading = "⚙️ ADVANCED")]
+    git_only: bool,
+
+    /// Walk at most N directories below the project root
+    #[arg(long = "max-depth", value_name = "N", help_heading = "⚙️ ADVANCED")]
+    max_depth: Option<usize>,
+
+    /// Take at most N files, in path order
+    #[arg(
+        long = "max-total-files",
+        value_name = "N",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    max_total_files: Option<usize>,
+
+    /// Stop taking files past SIZE bytes in total (e.g., 500k, 20M)
+    #[arg(
+        long = "max-total-bytes",
+        value_name = "SIZE",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    max_total_bytes: Option<String>,
+
+    /// List every file left out of the context, and why, on stderr
+    #[arg(long = "show-excluded", help_heading = "⚙️ ADVANCED")]
+    show_excluded: bool,
+
+    /// Prefix every path with its root's directory name (needed for several roots)
+    #[arg(long = "prefix-roots", help_heading = "⚙️ ADVANCED")]
+    prefix_roots: bool,
+
+    /// Put paths under PREFIX (e.g. repo-name/); repeat once per root, or per --concat bundle
+    #[arg(
+        long = "prefix",
+        value_name = "PREFIX",
+        action = clap::ArgAction::Append,
+        conflicts_with = "prefix_roots",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    prefix: Vec<String>,
+
+    /// Color the mission log and budget report [auto, always, never] (auto honours NO_COLOR)
+    #[arg(
+        long = "color",
+        value_enum,
+        default_value = "auto",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    color: ColorArg,
+
+    /// Suppress status narration on stderr (warnings and errors still print)
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        conflicts_with = "porcelain",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    quiet: bool,
+
+    /// Reuse processed files from earlier runs and other branches and worktrees
+    #[arg(long = "cache", help_heading = "⚙️ ADVANCED")]
+    cache: bool,
+
+    /// Diagnostics on stderr: -v lists the 10 slowest files, -vv also each
+    /// file's read, analyze and truncate times
+    #[arg(short = 'v', long = "verbose", action = ArgAction::Count, help_heading = "⚙️ ADVANCED")]
+    verbose: u8,
+
+    /// Report status on stderr as stable tab-separated records instead of narration
+    #[arg(long = "porcelain", help_heading = "⚙️ ADVANCED")]
+    porcelain: bool,
+
+    /// Fail on unreadable files (exit 2), budget drops (exit 3) and config errors (exit 4)
+    #[arg(long = "strict", help_heading = "⚙️ ADVANCED")]
+    strict: bool,
 
     /// Metadata mode [auto, all, none, size-only]
     #[arg(
@@ -323,10 +680,55 @@ struct Cli {
     #[arg(long = "no-truncate-summary", help_heading = "⚙️ ADVANCED")]
     no_truncate_summary: bool,
 
-    /// Show truncation statistics
+    /// Show truncation statistics, including which files look generated or vendored
     #[arg(long = "truncate-stats", help_heading = "⚙️ ADVANCED")]
     truncate_stats: bool,
 
+    /// What to do with generated and vendored files (lockfiles, *.pb.go, vendor/, "DO NOT EDIT" headers)
+    #[arg(
+        long = "generated",
+        value_enum,
+        value_name = "MODE",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    generated: Option<GeneratedArg>,
+
+    /// What to do with binary files: skip them, or keep a "[binary: 1.2MB, image/png]" stub
+    #[arg(
+        long = "binary",
+        value_enum,
+        value_name = "MODE",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    binary: Option<BinaryArg>,
+
+    /// What to do with lockfiles (Cargo.lock, package-lock.json, poetry.lock, ...): summary (default) lists name and version of each package
+    #[arg(
+        long = "lockfiles",
+        value_enum,
+        value_name = "MODE",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    lockfiles: Option<LockfilesArg>,
+
+    /// How --include/--exclude patterns match: v1 (default) or v2 (gitignore rules: /anchored, dir/, **)
+    #[arg(
+        long = "pattern-syntax",
+        value_enum,
+        value_name = "VERSION",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    pattern_syntax: Option<PatternSyntaxArg>,
+
+    /// Whether patterns match case: insensitive by default on Windows and macOS, sensitive elsewhere
+    #[arg(
+        long = "pattern-case",
+        value_enum,
+        value_name = "MODE",
+        help_heading = "⚙️ ADVANCED"
+    )]
+    pattern_case: Option<PatternCaseArg>,
+
     // ═══════════════════════════════════════════════════════════════════════════
     // 📓 OBSERVER'S JOURNAL
     // ═══════════════════════════════════════════════════════════════════════════
@@ -349,6 +751,14 @@ struct Cli {
     #[arg(long = "survey", value_name = "MODE", help_heading = "📊 CENSUS")]
     survey: Option<SurveyMode>,
 
+    /// Also write governance findings as SARIF 2.1.0 (for code scanning upload)
+    #[arg(long = "census-sarif", value_name = "FILE", help_heading = "📊 CENSUS")]
+    census_sarif: Option<PathBuf>,
+
+    /// Count only these declaration kinds: core kinds, custom kinds, `ns:*`
+    #[arg(long = "census-kinds", value_name = "LIST", help_heading = "📊 CENSUS")]
+    census_kinds: Option<String>,
+
     /// Grouping level for survey [constellation, galaxy, sector]
     #[arg(
         long = "by",
@@ -378,14 +788,145 @@ struct Cli {
     // ═══════════════════════════════════════════════════════════════════════════
     // 🚀 SPECIAL MODES
     // ═══════════════════════════════════════════════════════════════════════════
+    /// Check config, state files, cache and grammars, and print fixes for any problems
+    #[arg(long = "doctor", help_heading = "🚀 SPECIAL MODES")]
+    doctor: bool,
+
+    /// Upgrade .pm_encoder/ state files written by older releases (originals kept as .bak)
+    #[arg(long = "migrate", help_heading = "🚀 SPECIAL MODES")]
+    migrate: bool,
+
     /// Run as MCP server (JSON-RPC 2.0 over stdio)
     #[arg(long = "server", help_heading = "🚀 SPECIAL MODES")]
     server: bool,
 
+    /// Project roots served by --server (first is the default; tools pick one with select_project)
+    #[arg(
+        long = "roots",
+        value_name = "DIR",
+        num_args = 1..,
+        requires = "server",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    roots: Vec<PathBuf>,
+
+    /// Only serve files matching these globs in server mode (relative to each root)
+    #[arg(
+        long = "server-allow",
+        value_name = "GLOB",
+        requires = "server",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    server_allow: Vec<String>,
+
+    /// Never serve files matching these globs in server mode (wins over --server-allow)
+    #[arg(
+        long = "server-deny",
+        value_name = "GLOB",
+        requires = "server",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    server_deny: Vec<String>,
+
+    /// Append a JSON-lines audit record for every response served by --server
+    #[arg(
+        long = "audit-log",
+        value_name = "FILE",
+        requires = "server",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    audit_log: Option<PathBuf>,
+
+    /// Paginate --server tool results larger than this many bytes (e.g., 256k; 0 = unlimited)
+    #[arg(
+        long = "max-response-size",
+        value_name = "BYTES",
+        requires = "server",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    max_response_size: Option<String>,
+
+    /// Reconstruct files from Plus/Minus output into -o or PROJECT_ROOT (default: current directory)
+    #[arg(
+        long = "decode",
+        value_name = "FILE",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    decode: Option<PathBuf>,
+
+    /// Abort --decode if any file fails checksum verification
+    #[arg(
+        long = "decode-strict",
+        requires = "decode",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    decode_strict: bool,
+
+    /// Report what --decode or --migrate would write without touching the disk
+    #[arg(
+        long = "dry-run",
+        requires = "dry_run_target",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    dry_run: bool,
+
+    /// Merge Plus/Minus bundles into -o (or stdout); later bundles win for files they share
+    #[arg(
+        long = "concat",
+        value_name = "BUNDLE",
+        num_args = 1..,
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    concat: Vec<PathBuf>,
+
+    /// Find where a quoted excerpt came from in a Plus/Minus context (file + original lines)
+    #[arg(
+        long = "locate",
+        value_name = "CONTEXT",
+        requires = "excerpt_file",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    locate: Option<PathBuf>,
+
+    /// File holding the excerpt to --locate
+    #[arg(
+        long = "excerpt-file",
+        value_name = "FILE",
+        requires = "locate",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    excerpt_file: Option<PathBuf>,
+
+    /// Validate model-cited paths, symbols and line references (JSON) against PROJECT_ROOT
+    #[arg(
+        long = "check-claims",
+        value_name = "FILE",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    check_claims: Option<PathBuf>,
+
+    /// Build a findings bundle from a SARIF log (linter/scanner results)
+    #[arg(long = "sarif", value_name = "FILE", help_heading = "🚀 SPECIAL MODES")]
+    sarif: Option<PathBuf>,
+
+    /// Context lines shown around each SARIF finding region
+    #[arg(
+        long = "sarif-context",
+        value_name = "LINES",
+        default_value = "3",
+        help_heading = "🚀 SPECIAL MODES"
+    )]
+    sarif_context: usize,
+
     /// Generate AI instruction files and exit
     #[arg(long = "init-prompt", help_heading = "🚀 SPECIAL MODES")]
     init_prompt: bool,
 
+    /// Generate README.llm.md (layout, entry points, commands, conventions),
+    /// pin it in .pmattributes and exit
+    #[arg(long = "init-llm-readme", help_heading = "🚀 SPECIAL MODES")]
+    init_llm_readme: bool,
+
     /// Lens for init-prompt
     #[arg(
         long = "init-lens",
@@ -453,36 +994,220 @@ impl From<DetailLevelArg> for DetailLevel {
     }
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
-enum OutputFormatArg {
-    #[value(name = "plus-minus", alias = "pm")]
-    PlusMinus,
-    Xml,
-    #[value(alias = "md")]
-    Markdown,
-    /// Claude-optimized XML with CDATA sections and semantic attributes
-    #[value(name = "claude-xml")]
-    ClaudeXml,
+/// When to color terminal output.
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum ColorArg {
+    /// Color when stderr is a terminal and NO_COLOR is unset
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
-enum SortBy {
-    Name,
-    Mtime,
-    Ctime,
+impl From<ColorArg> for ColorChoice {
+    fn from(arg: ColorArg) -> Self {
+        match arg {
+            ColorArg::Auto => ColorChoice::Auto,
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+        }
+    }
 }
 
+/// Which symbolic links the walk follows.
 #[derive(Debug, Clone, Copy, ValueEnum)]
-enum SortOrder {
-    Asc,
-    Desc,
+enum SymlinkArg {
+    /// Follow every link
+    On,
+    /// Leave links out
+    Off,
+    /// Follow links that resolve inside the project root
+    Safe,
+}
+
+impl From<SymlinkArg> for SymlinkPolicy {
+    fn from(arg: SymlinkArg) -> Self {
+        match arg {
+            SymlinkArg::On => SymlinkPolicy::On,
+            SymlinkArg::Off => SymlinkPolicy::Off,
+            SymlinkArg::Safe => SymlinkPolicy::Safe,
+        }
+    }
+}
+
+/// The order --stream emits files in.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum StreamOrderArg {
+    /// As the walk finds them: lowest latency
+    Walk,
+    /// List the tree first, then emit by lens priority and learned utility
+    Priority,
+}
+
+impl From<StreamOrderArg> for StreamOrder {
+    fn from(arg: StreamOrderArg) -> Self {
+        match arg {
+            StreamOrderArg::Walk => StreamOrder::Walk,
+            StreamOrderArg::Priority => StreamOrder::Priority,
+        }
+    }
+}
+
+/// How --split divides the output.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SplitArg {
+    /// <output>.system and <output>.user sections for chat APIs' prompt caching
+    Roles,
+}
+
+/// What to do with generated and vendored files.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum GeneratedArg {
+    /// Emit them like any other file
+    Keep,
+    /// Reduce them to their structure
+    Structure,
+    /// Leave them out
+    Skip,
+}
+
+impl From<GeneratedArg> for GeneratedPolicy {
+    fn from(arg: GeneratedArg) -> Self {
+        match arg {
+            GeneratedArg::Keep => GeneratedPolicy::Keep,
+            GeneratedArg::Structure => GeneratedPolicy::Structure,
+            GeneratedArg::Skip => GeneratedPolicy::Skip,
+        }
+    }
+}
+
+/// What to do with binary files.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum BinaryArg {
+    /// Leave them out
+    Skip,
+    /// Keep a placeholder with size and media type
+    Stub,
+    /// Placeholder plus base64 content for files up to 16KB
+    Base64,
+}
+
+impl From<BinaryArg> for BinaryPolicy {
+    fn from(arg: BinaryArg) -> Self {
+        match arg {
+            BinaryArg::Skip => BinaryPolicy::Skip,
+            BinaryArg::Stub => BinaryPolicy::Stub,
+            BinaryArg::Base64 => BinaryPolicy::Base64,
+        }
+    }
+}
+
+/// What to do with lockfiles.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LockfilesArg {
+    /// One line per package, with its version
+    Summary,
+    /// Emit them like any other file
+    Keep,
+}
+
+impl From<LockfilesArg> for LockfilePolicy {
+    fn from(arg: LockfilesArg) -> Self {
+        match arg {
+            LockfilesArg::Summary => LockfilePolicy::Summary,
+            LockfilesArg::Keep => LockfilePolicy::Keep,
+        }
+    }
+}
+
+/// How ignore and include patterns are matched.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PatternSyntaxArg {
+    /// Component and prefix matching
+    V1,
+    /// gitignore rules: anchoring, directory-only patterns, `**`
+    V2,
+}
+
+impl From<PatternSyntaxArg> for PatternSyntax {
+    fn from(arg: PatternSyntaxArg) -> Self {
+        match arg {
+            PatternSyntaxArg::V1 => PatternSyntax::V1,
+            PatternSyntaxArg::V2 => PatternSyntax::V2,
+        }
+    }
+}
+
+/// Whether ignore and include patterns match case.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PatternCaseArg {
+    /// `*.md` misses `README.MD`
+    Sensitive,
+    /// `*.md` matches `README.MD`
+    Insensitive,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormatArg {
+    #[value(name = "plus-minus", alias = "pm")]
+    PlusMinus,
+    Xml,
+    #[value(alias = "md")]
+    Markdown,
+    /// Claude-optimized XML with CDATA sections and semantic attributes
+    #[value(name = "claude-xml")]
+    ClaudeXml,
+    /// Gemini long-context layout: file list preamble, fenced blocks, path attributes
+    Gemini,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CompressArg {
+    #[value(alias = "gz")]
+    Gzip,
+    #[value(alias = "zst")]
+    Zstd,
+}
+
+impl From<CompressArg> for pm_encoder::core::OutputCompression {
+    fn from(arg: CompressArg) -> Self {
+        match arg {
+            CompressArg::Gzip => Self::Gzip,
+            CompressArg::Zstd => Self::Zstd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SortBy {
+    Name,
+    Mtime,
+    Ctime,
+    /// File size in bytes
+    Size,
+    /// File extension, then path
+    Ext,
+    /// Learned utility score, most useful first
+    Utility,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SortOrder {
+    Asc,
+    Desc,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum TruncateMode {
     Simple,
+    Tail,
     Smart,
     Structure,
+    Outline,
+    Tokens,
+    StripComments,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -498,6 +1223,15 @@ enum BudgetStrategy {
     Hybrid,
 }
 
+/// Graph format for --callgraph-export
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CallGraphFormat {
+    /// Graphviz DOT digraph
+    Dot,
+    /// Mermaid flowchart (wrapped in a ```mermaid fence when writing a .md file)
+    Mermaid,
+}
+
 /// Survey mode for --survey flag
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum SurveyMode {
@@ -595,6 +1329,23 @@ fn parse_report_utility(s: &str) -> Result<(String, f64, String), String> {
     Ok((path, score, reason))
 }
 
+/// The file a zoom target lies in, symbols resolved; `None` for modules,
+/// which span several
+fn zoom_target_path(
+    zoom: &str,
+    config: &EncoderConfig,
+    root: &Path,
+) -> Result<Option<String>, String> {
+    use pm_encoder::core::SymbolResolver;
+    let resolver = || SymbolResolver::new().with_ignore(config.ignore_patterns.clone());
+    Ok(match parse_zoom_target(zoom)?.target {
+        ZoomTarget::File { path, .. } => Some(path),
+        ZoomTarget::Function(name) => Some(resolver().find_function(&name, root)?.path),
+        ZoomTarget::Class(name) => Some(resolver().find_class(&name, root)?.path),
+        ZoomTarget::Module(_) => None,
+    })
+}
+
 /// Parse a zoom target string into ZoomConfig.
 /// Formats:
 ///   fn=<name>           - Zoom to function
@@ -677,35 +1428,67 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
     use pm_encoder::core::{AstBridge, CelestialCensus, GalaxyCensus};
     #[cfg(feature = "temporal")]
     use pm_encoder::core::{
-        ChronosEngine, StellarDriftAnalyzer, StellarDriftReport, TemporalCensus,
+        ChronosEngine, ChronosState, StellarDriftAnalyzer, StellarDriftReport, TemporalCensus,
     };
     use std::collections::HashMap;
     use std::time::Instant;
+    use voyager_ast::KindFilter;
 
     let start = Instant::now();
 
+    // Summary collapses constellation breakdowns to the galaxy row
+    let detail = DetailLevel::from(cli.detail);
+    let grouping = if detail.shows_breakdowns() {
+        grouping
+    } else {
+        SurveyGrouping::Galaxy
+    };
+
     // Walk directory and collect files
     let ignore_patterns: Vec<String> = cli.exclude.clone();
     let include_patterns: Vec<String> = cli.include.clone();
 
-    let entries = match pm_encoder::walk_directory(
-        root.to_str().unwrap(),
-        &ignore_patterns,
-        &include_patterns,
+    if !root.exists() {
+        eprintln!(
+            "Error walking directory: Directory not found: {}",
+            root.display()
+        );
+        exit(exit_code::ERROR);
+    }
+    let entries: Vec<_> = pm_encoder::walk_directory_iter_with_options(
+        &root.to_string_lossy(),
+        ignore_patterns,
+        include_patterns,
         10_000_000, // 10MB max file size for census
-    ) {
-        Ok(e) => e,
-        Err(e) => {
-            eprintln!("Error walking directory: {}", e);
-            std::process::exit(1);
-        }
-    };
+        !cli.no_gitignore,
+    )
+    .collect();
 
     // Build census
-    let census = CelestialCensus::new();
+    let config = survey_config(root, cli);
+    let rules = pm_encoder::core::KindRules::new(&config.declaration_kinds).unwrap_or_else(|e| {
+        config_failure(
+            cli.strict,
+            &format!("Could not load declaration kinds: {}", e),
+        );
+        Default::default()
+    });
+    let kinds = match cli.census_kinds.as_deref().map(KindFilter::parse) {
+        Some(Ok(kinds)) => kinds,
+        Some(Err(e)) => {
+            eprintln!("Error: --census-kinds: {}", e);
+            exit(exit_code::USAGE);
+        }
+        None => KindFilter::default(),
+    };
+    let census = CelestialCensus::new().with_kinds(kinds);
     let bridge = AstBridge::new();
     let mut galaxy = GalaxyCensus::new(root.to_string_lossy().to_string());
     let mut star_counts: HashMap<String, usize> = HashMap::new();
+    let mut sarif_builder = cli
+        .census_sarif
+        .as_ref()
+        .map(|_| pm_encoder::core::CensusSarifBuilder::new());
 
     // Analyze each file
     for entry in &entries {
@@ -713,16 +1496,38 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
         let language = AstBridge::detect_language(std::path::Path::new(&entry.path));
 
         // Parse file with AST bridge
-        if let Some(file) = bridge.analyze_file(&entry.content, language) {
+        if let Some(mut file) = bridge.analyze_file(&entry.content, language) {
+            rules.apply(&entry.path, &mut file);
             let metrics = census.analyze(&file);
             // Track star counts for drift analysis
             star_counts.insert(entry.path.clone(), metrics.stars.count);
             galaxy.add_file(&entry.path, metrics);
+            if let Some(builder) = sarif_builder.as_mut() {
+                builder.add_file(&entry.path, &file);
+            }
         }
     }
 
     galaxy.finalize();
 
+    if let (Some(builder), Some(sarif_path)) = (&sarif_builder, &cli.census_sarif) {
+        let log = builder.build(&galaxy);
+        let written = serde_json::to_string_pretty(&log)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(sarif_path, json).map_err(|e| e.to_string()));
+        match written {
+            Ok(()) => eprintln!(
+                "SARIF ({} results) written to: {}",
+                log.runs[0].results.len(),
+                sarif_path.display()
+            ),
+            Err(e) => {
+                eprintln!("Error writing SARIF to {}: {}", sarif_path.display(), e);
+                exit(exit_code::ERROR);
+            }
+        }
+    }
+
     // Build temporal census (Chronos Engine)
     // Performance optimization: Only extract git history for evolution mode or health mode
     // This avoids ~2-3 second overhead for composition-only surveys
@@ -732,7 +1537,7 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
     let (temporal_census, drift_report, warp_status): (
         Option<TemporalCensus>,
         Option<StellarDriftReport>,
-        Option<WarpStatus>,
+        Option<(WarpStatus, &'static str)>,
     ) = {
         use pm_encoder::core::{DEFAULT_COMMIT_DEPTH, FULL_COMMIT_DEPTH};
 
@@ -746,30 +1551,22 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
                 ChronosDepth::Full => FULL_COMMIT_DEPTH,
             };
 
-            if let Some(mut engine) = ChronosEngine::with_depth(root, depth) {
-                // Calculate path prefix if survey root differs from git root
-                // This is needed to match star_counts paths (survey-relative) with
-                // temporal_census paths (git-relative)
+            let thresholds = survey_thresholds(root, cli);
+            if let Some(engine) = ChronosEngine::with_depth(root, depth) {
+                let mut engine = engine.with_thresholds(thresholds.clone());
+                // Calculate path prefix if survey root differs from the repository
+                // root. This is needed to match star_counts paths (survey-relative)
+                // with temporal_census paths (repository-relative)
                 let path_prefix: Option<String> = {
-                    let git_root = git2::Repository::discover(root)
-                        .ok()
-                        .and_then(|repo| repo.workdir().map(|p| p.to_path_buf()));
-
-                    if let Some(git_root) = git_root {
-                        // Canonicalize root to get absolute path for comparison
-                        let abs_root = root.canonicalize().ok();
-                        if let Some(abs_root) = abs_root {
-                            abs_root
-                                .strip_prefix(&git_root)
-                                .ok()
-                                .map(|p| p.to_string_lossy().to_string())
-                                .filter(|s| !s.is_empty())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
+                    let repo_root = engine.root().canonicalize().ok();
+                    let abs_root = root.canonicalize().ok();
+                    repo_root.zip(abs_root).and_then(|(repo_root, abs_root)| {
+                        abs_root
+                            .strip_prefix(&repo_root)
+                            .ok()
+                            .map(|p| p.to_string_lossy().to_string())
+                            .filter(|s| !s.is_empty())
+                    })
                 };
 
                 // Use cache unless --no-cache flag is set
@@ -780,11 +1577,11 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
                 };
 
                 if extract_result.is_ok() {
-                    let warp = engine.warp_status();
+                    let warp = (engine.warp_status(), engine.backend());
                     let tc = engine.build_census();
                     // Build stellar drift report for evolution survey
                     let drift = if matches!(mode, SurveyMode::Evolution) {
-                        let analyzer = StellarDriftAnalyzer::new();
+                        let analyzer = StellarDriftAnalyzer::with_thresholds(&thresholds);
                         Some(analyzer.analyze(&tc, &star_counts, None, path_prefix.as_deref()))
                     } else {
                         None
@@ -794,7 +1591,19 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
                     (None, None, None)
                 }
             } else {
-                (None, None, None)
+                // No history (e.g. an exported tarball): fall back to file times
+                let tc = pm_encoder::core::census_from_timestamps(
+                    entries.iter().map(|e| (e.path.as_str(), e.mtime, e.ctime)),
+                    chrono::Utc::now(),
+                    &thresholds,
+                );
+                let drift = if matches!(mode, SurveyMode::Evolution) {
+                    let analyzer = StellarDriftAnalyzer::with_thresholds(&thresholds);
+                    Some(analyzer.analyze(&tc, &star_counts, None, None))
+                } else {
+                    None
+                };
+                (Some(tc), drift, None)
             }
         } else {
             // Skip temporal analysis for composition-only surveys
@@ -817,14 +1626,14 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
                 Ok(json) => println!("{}", json),
                 Err(e) => {
                     eprintln!("Error serializing JSON: {}", e);
-                    std::process::exit(1);
+                    exit(exit_code::ERROR);
                 }
             }
         }
-        OutputFormatArg::Markdown => {
+        OutputFormatArg::Markdown | OutputFormatArg::Gemini => {
             // Markdown output for documentation
             #[cfg(feature = "temporal")]
-            print_census_markdown(&galaxy, mode, temporal_census.as_ref());
+            print_census_markdown(&galaxy, mode, temporal_census.as_ref(), detail);
             #[cfg(not(feature = "temporal"))]
             print_census_markdown(&galaxy, mode, None::<&()>);
         }
@@ -839,9 +1648,9 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
                 }
                 SurveyMode::Health => {
                     #[cfg(feature = "temporal")]
-                    print_health_report(&galaxy, grouping, temporal_census.as_ref());
+                    print_health_report(&galaxy, grouping, temporal_census.as_ref(), detail);
                     #[cfg(not(feature = "temporal"))]
-                    print_health_report(&galaxy, grouping, None::<&()>);
+                    print_health_report(&galaxy, grouping, None::<&()>, detail);
                 }
                 SurveyMode::Evolution => {
                     #[cfg(feature = "temporal")]
@@ -850,9 +1659,10 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
                         grouping,
                         drift_report.as_ref(),
                         temporal_census.as_ref(),
+                        detail,
                     );
                     #[cfg(not(feature = "temporal"))]
-                    print_evolution_report_static(&galaxy);
+                    print_evolution_report_static(&galaxy, detail);
                 }
             }
         }
@@ -861,17 +1671,32 @@ fn run_survey(root: &PathBuf, mode: SurveyMode, grouping: SurveyGrouping, cli: &
     // Print timing with Warp status
     eprintln!();
     #[cfg(feature = "temporal")]
-    if let Some(ref tc) = temporal_census {
+    if let Some(tc) = temporal_census
+        .as_ref()
+        .filter(|tc| matches!(tc.state, ChronosState::FileTimestamps { .. }))
+    {
+        eprintln!(
+            "Survey completed in {:.1}ms ({} files, {} dated by file timestamps: no git history)",
+            elapsed.as_secs_f64() * 1000.0,
+            galaxy.total_files,
+            tc.total_observations
+        );
+    } else if let Some(ref tc) = temporal_census {
         let warp_indicator = match warp_status {
-            Some(WarpStatus::Engaged) => " [Warp Engaged]",
-            Some(WarpStatus::Calibrating) => " [Warp Calibrating]",
+            Some((WarpStatus::Engaged, _)) => " [Warp Engaged]",
+            Some((WarpStatus::Calibrating, _)) => " [Warp Calibrating]",
             _ => "",
         };
+        let backend = match warp_status {
+            Some((_, backend)) if backend != "git" => format!(" from {}", backend),
+            _ => String::new(),
+        };
         eprintln!(
-            "Survey completed in {:.1}ms ({} files, {} chronos events){}",
+            "Survey completed in {:.1}ms ({} files, {} chronos events{}){}",
             elapsed.as_secs_f64() * 1000.0,
             galaxy.total_files,
             tc.total_observations,
+            backend,
             warp_indicator
         );
     } else {
@@ -1108,6 +1933,7 @@ fn print_composition_table(
         "  Health Score:    {:.0}/100",
         totals.derived.health_score * 100.0
     );
+    print_custom_kinds(&totals.stars.custom);
 
     // Temporal metrics
     if let Some(tc) = temporal {
@@ -1220,6 +2046,19 @@ fn print_composition_table<T>(
         "  Health Score:    {:.0}/100",
         totals.derived.health_score * 100.0
     );
+    print_custom_kinds(&totals.stars.custom);
+}
+
+/// The custom kind counts under the derived metrics, when there are any
+fn print_custom_kinds(custom: &std::collections::BTreeMap<String, usize>) {
+    if custom.is_empty() {
+        return;
+    }
+    let counts: Vec<String> = custom
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    println!("  Custom Kinds:    {}", counts.join(", "));
 }
 
 /// Print health diagnostic report (with temporal data)
@@ -1228,6 +2067,7 @@ fn print_health_report(
     galaxy: &pm_encoder::core::GalaxyCensus,
     _grouping: SurveyGrouping,
     temporal: Option<&pm_encoder::core::TemporalCensus>,
+    detail: DetailLevel,
 ) {
     use pm_encoder::core::HealthRating;
 
@@ -1235,6 +2075,7 @@ fn print_health_report(
     println!("║                    CELESTIAL CENSUS: HEALTH REPORT                            ║");
     println!("╚══════════════════════════════════════════════════════════════════════════════╝");
     println!();
+    print_temporal_source(temporal);
 
     // Overall health
     let rating = galaxy.rating.unwrap_or(HealthRating::Stable);
@@ -1256,11 +2097,14 @@ fn print_health_report(
                     "[!!] SUPERNOVAS (Destabilizing refactors - >30 observations in 30 days):"
                 );
             }
-            for nova in tc.supernovas.iter().take(10) {
+            for nova in tc.supernovas.iter().take(detail.list_limit(10)) {
                 println!("  - {} ({} observations)", nova.path, nova.observations_30d);
             }
-            if tc.supernovas.len() > 10 {
-                println!("  ... and {} more", tc.supernovas.len() - 10);
+            if tc.supernovas.len() > detail.list_limit(10) {
+                println!(
+                    "  ... and {} more",
+                    tc.supernovas.len() - detail.list_limit(10)
+                );
             }
             println!();
         } else {
@@ -1283,11 +2127,14 @@ fn print_health_report(
 
     if !red_giants.is_empty() {
         println!("alert RED GIANTS (Large files with high complexity/low documentation):");
-        for rg in red_giants.iter().take(10) {
+        for rg in red_giants.iter().take(detail.list_limit(10)) {
             println!("  - {}", rg);
         }
-        if red_giants.len() > 10 {
-            println!("  ... and {} more", red_giants.len() - 10);
+        if red_giants.len() > detail.list_limit(10) {
+            println!(
+                "  ... and {} more",
+                red_giants.len() - detail.list_limit(10)
+            );
         }
         println!();
     } else {
@@ -1307,7 +2154,7 @@ fn print_health_report(
 
     if !high_dark_matter.is_empty() {
         println!("warning HIGH DARK MATTER REGIONS:");
-        for (path, constellation) in high_dark_matter.iter().take(10) {
+        for (path, constellation) in high_dark_matter.iter().take(detail.list_limit(10)) {
             let dm = constellation.totals.dark_matter.unknown_regions
                 + constellation.totals.dark_matter.volcanic_regions;
             println!("  - {} ({} dark matter units)", path, dm);
@@ -1327,7 +2174,7 @@ fn print_health_report(
             } else {
                 println!("[*] ANCIENT STARS (Core files dormant > 2 years):");
             }
-            for ancient in core_ancient.iter().take(10) {
+            for ancient in core_ancient.iter().take(detail.list_limit(10)) {
                 println!(
                     "  - {} (dormant {} days, {} stars)",
                     ancient.path, ancient.dormant_days, ancient.star_count
@@ -1348,7 +2195,7 @@ fn print_health_report(
 
     if !nurseries.is_empty() {
         println!("star STELLAR NURSERIES (High activity regions):");
-        for (path, count) in nurseries.iter().take(5) {
+        for (path, count) in nurseries.iter().take(detail.list_limit(5)) {
             println!("  - {} ({} stars)", path, count);
         }
         println!();
@@ -1401,6 +2248,7 @@ fn print_health_report<T>(
     galaxy: &pm_encoder::core::GalaxyCensus,
     _grouping: SurveyGrouping,
     _temporal: Option<&T>,
+    detail: DetailLevel,
 ) {
     use pm_encoder::core::HealthRating;
 
@@ -1427,7 +2275,7 @@ fn print_health_report<T>(
 
     if !red_giants.is_empty() {
         println!("alert RED GIANTS (Large files with high complexity/low documentation):");
-        for rg in red_giants.iter().take(10) {
+        for rg in red_giants.iter().take(detail.list_limit(10)) {
             println!("  - {}", rg);
         }
         println!();
@@ -1447,7 +2295,7 @@ fn print_health_report<T>(
 
     if !high_dark_matter.is_empty() {
         println!("warning HIGH DARK MATTER REGIONS:");
-        for (path, constellation) in high_dark_matter.iter().take(10) {
+        for (path, constellation) in high_dark_matter.iter().take(detail.list_limit(10)) {
             let dm = constellation.totals.dark_matter.unknown_regions
                 + constellation.totals.dark_matter.volcanic_regions;
             println!("  - {} ({} dark matter units)", path, dm);
@@ -1468,7 +2316,7 @@ fn print_health_report<T>(
 
     if !nurseries.is_empty() {
         println!("star STELLAR NURSERIES (High activity regions):");
-        for (path, count) in nurseries.iter().take(5) {
+        for (path, count) in nurseries.iter().take(detail.list_limit(5)) {
             println!("  - {} ({} stars)", path, count);
         }
         println!();
@@ -1504,12 +2352,27 @@ fn print_health_report<T>(
 // =============================================================================
 
 /// Print evolution report with full temporal + semantic synthesis
+/// Say so when the temporal data comes from file timestamps, not history
+#[cfg(feature = "temporal")]
+fn print_temporal_source(temporal: Option<&pm_encoder::core::TemporalCensus>) {
+    use pm_encoder::core::ChronosState;
+
+    if let Some(ChronosState::FileTimestamps { file_count, .. }) = temporal.map(|t| &t.state) {
+        println!(
+            "Temporal source: file timestamps of {} files (no git history; ages and churn are approximate)",
+            file_count
+        );
+        println!();
+    }
+}
+
 #[cfg(feature = "temporal")]
 fn print_evolution_report(
     galaxy: &pm_encoder::core::GalaxyCensus,
     _grouping: SurveyGrouping,
     drift: Option<&pm_encoder::core::StellarDriftReport>,
     temporal: Option<&pm_encoder::core::TemporalCensus>,
+    detail: DetailLevel,
 ) {
     use pm_encoder::core::ChurnClassification;
 
@@ -1517,6 +2380,7 @@ fn print_evolution_report(
     println!("║              STELLAR DRIFT REPORT: EVOLUTION ANALYSIS                        ║");
     println!("╚══════════════════════════════════════════════════════════════════════════════╝");
     println!();
+    print_temporal_source(temporal);
 
     // Galaxy overview
     if let Some(dr) = drift {
@@ -1712,7 +2576,7 @@ fn print_evolution_report(
             println!(
                 "─────────────────────────────────────────────────────────────────────────────────"
             );
-            for star in dr.new_stars.iter().take(10) {
+            for star in dr.new_stars.iter().take(detail.list_limit(10)) {
                 let expansion_marker = if star.is_expansion {
                     "+"
                 } else if use_emoji() {
@@ -1725,8 +2589,11 @@ fn print_evolution_report(
                     expansion_marker, star.path, star.age_days, star.star_count
                 );
             }
-            if dr.new_stars.len() > 10 {
-                println!("  ... and {} more new stars", dr.new_stars.len() - 10);
+            if dr.new_stars.len() > detail.list_limit(10) {
+                println!(
+                    "  ... and {} more new stars",
+                    dr.new_stars.len() - detail.list_limit(10)
+                );
             }
             println!();
         }
@@ -1741,17 +2608,17 @@ fn print_evolution_report(
                     println!("[*] ANCIENT STARS (Stable Core)");
                 }
                 println!("─────────────────────────────────────────────────────────────────────────────────");
-                for star in core_ancient.iter().take(10) {
+                for star in core_ancient.iter().take(detail.list_limit(10)) {
                     let marker = if use_emoji() { "📜" } else { ">" };
                     println!(
                         "  {} {} (dormant {} days, {} stars)",
                         marker, star.path, star.dormant_days, star.star_count
                     );
                 }
-                if core_ancient.len() > 10 {
+                if core_ancient.len() > detail.list_limit(10) {
                     println!(
                         "  ... and {} more ancient core stars",
-                        core_ancient.len() - 10
+                        core_ancient.len() - detail.list_limit(10)
                     );
                 }
                 println!();
@@ -1768,7 +2635,7 @@ fn print_evolution_report(
             println!(
                 "─────────────────────────────────────────────────────────────────────────────────"
             );
-            for nova in dr.supernovas.iter().take(5) {
+            for nova in dr.supernovas.iter().take(detail.list_limit(5)) {
                 let marker = if use_emoji() { "💥" } else { "!" };
                 println!(
                     "  {} {} ({} observations in 30d)",
@@ -1819,7 +2686,7 @@ fn print_evolution_report(
 
 /// Print evolution report without temporal data (static galaxy fallback)
 #[cfg(not(feature = "temporal"))]
-fn print_evolution_report_static(galaxy: &pm_encoder::core::GalaxyCensus) {
+fn print_evolution_report_static(galaxy: &pm_encoder::core::GalaxyCensus, detail: DetailLevel) {
     println!("╔══════════════════════════════════════════════════════════════════════════════╗");
     println!("║              STELLAR DRIFT REPORT: STATIC GALAXY                             ║");
     println!("╚══════════════════════════════════════════════════════════════════════════════╝");
@@ -1864,7 +2731,7 @@ fn print_evolution_report_static(galaxy: &pm_encoder::core::GalaxyCensus) {
     let mut sorted_constellations: Vec<_> = galaxy.constellations.iter().collect();
     sorted_constellations.sort_by(|a, b| b.1.totals.stars.count.cmp(&a.1.totals.stars.count));
 
-    for (path, constellation) in sorted_constellations.iter().take(15) {
+    for (path, constellation) in sorted_constellations.iter().take(detail.list_limit(15)) {
         let display_path = if path.len() > 38 {
             format!("...{}", &path[path.len() - 35..])
         } else {
@@ -1882,10 +2749,10 @@ fn print_evolution_report_static(galaxy: &pm_encoder::core::GalaxyCensus) {
         );
     }
 
-    if galaxy.constellations.len() > 15 {
+    if galaxy.constellations.len() > detail.list_limit(15) {
         println!(
             "  ... and {} more constellations",
-            galaxy.constellations.len() - 15
+            galaxy.constellations.len() - detail.list_limit(15)
         );
     }
 
@@ -1901,9 +2768,11 @@ fn print_census_markdown(
     galaxy: &pm_encoder::core::GalaxyCensus,
     mode: SurveyMode,
     temporal: Option<&pm_encoder::core::TemporalCensus>,
+    detail: DetailLevel,
 ) {
     println!("# Celestial Census Report");
     println!();
+    print_temporal_source(temporal);
 
     let rating = galaxy
         .rating
@@ -2034,7 +2903,7 @@ fn print_census_markdown(
                         "> Files with >30 observations in 30 days - potential destabilization."
                     );
                     println!();
-                    for nova in tc.supernovas.iter().take(10) {
+                    for nova in tc.supernovas.iter().take(detail.list_limit(10)) {
                         println!("- `{}` ({} observations)", nova.path, nova.observations_30d);
                     }
                     println!();
@@ -2065,7 +2934,7 @@ fn print_census_markdown(
                     println!();
                     println!("> High churn combined with high dark matter ratio.");
                     println!();
-                    for shift in tc.tectonic_shifts.iter().take(10) {
+                    for shift in tc.tectonic_shifts.iter().take(detail.list_limit(10)) {
                         println!(
                             "- `{}` (churn: {}, dark matter: {:.0}%, risk: {:.0}%)",
                             shift.path,
@@ -2086,7 +2955,7 @@ fn print_census_markdown(
                     println!();
                     println!("> Core files dormant for >2 years - may need archaeological review.");
                     println!();
-                    for ancient in core_ancient.iter().take(10) {
+                    for ancient in core_ancient.iter().take(detail.list_limit(10)) {
                         println!(
                             "- `{}` (dormant {} days, {} stars)",
                             ancient.path, ancient.dormant_days, ancient.star_count
@@ -2441,16 +3310,60 @@ fn print_context_health(output: &str, file_count: usize) {
         0.0
     };
 
-    eprintln!();
-    eprintln!("=== Context Health ===");
-    eprintln!("  Files:            {}", file_count);
-    eprintln!("  Total Tokens:     ~{}", total_tokens);
-    eprintln!("  Token Efficiency: {}%", efficiency);
-    eprintln!("  Zoom Affordances: {}", zoom_count);
+    let conflicted = merge_conflict_files(output);
+    porcelain_record(
+        "health",
+        &[
+            &file_count,
+            &total_tokens,
+            &efficiency,
+            &zoom_count,
+            &conflicted,
+        ],
+    );
+
+    narrate!();
+    narrate!("=== Context Health ===");
+    narrate!("  Files:            {}", file_count);
+    narrate!("  Total Tokens:     ~{}", total_tokens);
+    narrate!("  Token Efficiency: {}%", efficiency);
+    narrate!("  Zoom Affordances: {}", zoom_count);
     if zoom_count > 0 {
-        eprintln!("  Zoom Density:     {:.2} per file", zoom_density);
+        narrate!("  Zoom Density:     {:.2} per file", zoom_density);
+    }
+    if conflicted > 0 {
+        narrate!(
+            "  Merge Conflicts:  {} files (mid-merge bundle)",
+            conflicted
+        );
+    }
+    narrate!("======================");
+}
+
+/// Files whose header flags unresolved merge conflicts, in any built-in format
+fn merge_conflict_files(output: &str) -> usize {
+    output
+        .lines()
+        .filter(|line| {
+            ((line.starts_with("++++++++++ ") || line.starts_with("### "))
+                && line.contains(" [MERGE CONFLICTS: "))
+                || (line.starts_with("<file ") && line.contains(" merge_conflicts=\""))
+                || line.trim_start().starts_with("merge_conflicts=\"")
+        })
+        .count()
+}
+
+/// Warn when the bundle was made mid-merge
+fn warn_merge_conflicts(output: &str) {
+    let conflicted = merge_conflict_files(output);
+    if conflicted > 0 {
+        warning!(
+            "{} file{} contain{} merge conflict markers; the bundle was made mid-merge",
+            conflicted,
+            if conflicted == 1 { "" } else { "s" },
+            if conflicted == 1 { "s" } else { "" }
+        );
     }
-    eprintln!("======================");
 }
 
 /// Find project root by looking for common markers (git, Cargo.toml, package.json, etc.)
@@ -2504,8 +3417,22 @@ fn print_mission_log(
     lens: Option<&str>,
     token_budget: Option<usize>,
     file_count: usize,
+    detail: DetailLevel,
+    palette: Palette,
 ) {
-    let presenter = IntelligentPresenter::new();
+    match status_mode() {
+        StatusMode::Narrate => {}
+        StatusMode::Quiet => return,
+        StatusMode::Porcelain => {
+            porcelain_record("files", &[&file_count]);
+            porcelain_record("tokens", &[&(output.len() / 4)]);
+            return;
+        }
+    }
+
+    let presenter = IntelligentPresenter::new()
+        .with_detail_level(detail)
+        .with_palette(palette);
 
     // Detect languages from output (count file extensions)
     let mut lang_counts: HashMap<String, usize> = HashMap::new();
@@ -2573,6 +3500,13 @@ fn print_mission_log(
 
 /// Main entry point for the Voyager Observatory CLI.
 /// This is public so it can be called from the pm_encoder compatibility wrapper.
+/// Exit with `code`, first removing the temporary directories of
+/// `--remote` and `--git-ref`, whose destructors `std::process::exit` skips
+fn exit(code: i32) -> ! {
+    pm_encoder::discovery::remove_scratch_dirs();
+    std::process::exit(code)
+}
+
 pub fn run() {
     // Fix broken pipe panic when piping to head/tail/etc.
     // Reset SIGPIPE to default behavior (terminate quietly)
@@ -2583,29 +3517,135 @@ pub fn run() {
         }
     }
 
-    let cli = Cli::parse();
+    // clap exits with 2 on bad arguments; that code means "partial" here
+    let mut cli = Cli::try_parse().unwrap_or_else(|e| {
+        if e.use_stderr() {
+            let _ = e.print();
+            exit(exit_code::USAGE);
+        }
+        e.exit()
+    });
+
+    STATUS_MODE
+        .set(if cli.porcelain {
+            StatusMode::Porcelain
+        } else if cli.quiet {
+            StatusMode::Quiet
+        } else {
+            StatusMode::Narrate
+        })
+        .ok();
+
+    // The walk takes roots as strings, so a root must be valid UTF-8
+    for root in cli
+        .project_root
+        .iter()
+        .chain(&cli.roots)
+        .chain(&cli.extra_roots)
+    {
+        if root.to_str().is_none() {
+            eprintln!(
+                "Error: Project root '{}': path is not valid UTF-8",
+                root.display()
+            );
+            exit(exit_code::ERROR);
+        }
+    }
 
     // Handle MCP Server Mode (v2.3.0)
     // When --server is set, run as JSON-RPC server over stdio
     if cli.server {
-        let project_root = match &cli.project_root {
-            Some(path) => path.clone(),
-            None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        // --roots registers several projects; otherwise serve the single project root
+        let roots = if cli.roots.is_empty() {
+            vec![match &cli.project_root {
+                Some(path) => path.clone(),
+                None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            }]
+        } else {
+            cli.roots.clone()
         };
 
-        if !project_root.exists() || !project_root.is_dir() {
-            eprintln!(
-                "Error: Project root '{}' must be a valid directory",
-                project_root.display()
-            );
-            std::process::exit(1);
+        for root in &roots {
+            if !root.exists() || !root.is_dir() {
+                eprintln!(
+                    "Error: Project root '{}' must be a valid directory",
+                    root.display()
+                );
+                exit(exit_code::ERROR);
+            }
+        }
+
+        // CLI flags form the base config; each tool call may override it
+        let mut base_config = EncoderConfig::default();
+        base_config.output_format = match cli.format {
+            OutputFormatArg::PlusMinus => OutputFormat::PlusMinus,
+            OutputFormatArg::Xml => OutputFormat::Xml,
+            OutputFormatArg::Markdown => OutputFormat::Markdown,
+            OutputFormatArg::ClaudeXml => OutputFormat::ClaudeXml,
+            OutputFormatArg::Gemini => OutputFormat::Gemini,
+        };
+        base_config.truncate_lines = cli.truncate;
+        base_config.active_lens = cli.lens.clone();
+        base_config.skeleton_mode =
+            SkeletonMode::parse(&cli.skeleton).unwrap_or(SkeletonMode::Auto);
+        base_config.line_numbers = cli.line_numbers;
+        base_config.header_hints = cli.header_hints;
+        base_config.escape_control_chars = !cli.keep_control_chars;
+        base_config
+            .ignore_patterns
+            .extend(cli.exclude.iter().cloned());
+        if !cli.include.is_empty() {
+            base_config.include_patterns = cli.include.clone();
+        }
+        if let Some(budget_str) = &cli.token_budget {
+            match parse_token_budget(budget_str) {
+                Ok(budget) => base_config.token_budget = Some(budget),
+                Err(e) => {
+                    eprintln!("Error: Invalid token budget: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
         }
 
         // Note: No startup logs here - MCP clients expect clean stdio
-        let mut server = McpServer::new(project_root);
+        let mut roots = roots.into_iter();
+        let default_root = roots.next().unwrap_or_else(|| PathBuf::from("."));
+        let policy = match AccessPolicy::new(cli.server_allow.clone(), cli.server_deny.clone()) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        let mut server = McpServer::new(default_root)
+            .with_roots(roots)
+            .with_base_config(base_config)
+            .with_access_policy(policy);
+        if cli.cache {
+            server = server.with_processing_cache();
+        }
+        if let Some(size_str) = &cli.max_response_size {
+            match parse_token_budget(size_str) {
+                Ok(bytes) => server = server.with_max_response_bytes(bytes),
+                Err(e) => {
+                    eprintln!("Error: Invalid response size: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        }
+        if let Some(audit_path) = &cli.audit_log {
+            match AuditLog::open(audit_path) {
+                Ok(log) => server = server.with_audit_log(log),
+                Err(e) => {
+                    eprintln!("Error opening audit log '{}': {}", audit_path.display(), e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        }
         if let Err(e) = server.run() {
             eprintln!("MCP server error: {}", e);
-            std::process::exit(1);
+            exit(exit_code::ERROR);
         }
         return;
     }
@@ -2636,15 +3676,14 @@ pub fn run() {
         let journal = ObserversJournal::new();
         match journal.save(&journal_root) {
             Ok(_) => {
-                eprintln!("📓 Journal cleared. A new chapter begins.");
-                eprintln!(
-                    "   Path: {}",
-                    ObserversJournal::default_path(&journal_root).display()
-                );
+                let journal_path = ObserversJournal::default_path(&journal_root);
+                narrate!("📓 Journal cleared. A new chapter begins.");
+                narrate!("   Path: {}", journal_path.display());
+                porcelain_record("journal-cleared", &[&journal_path.display()]);
             }
             Err(e) => {
                 eprintln!("Error clearing journal: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
         return;
@@ -2666,19 +3705,20 @@ pub fn run() {
         match journal.save(&journal_root) {
             Ok(_) => {
                 if use_emoji() {
-                    eprintln!("⭐ Marked star: {}", path_to_mark);
+                    narrate!("⭐ Marked star: {}", path_to_mark);
                 } else {
-                    eprintln!("[*] Marked star: {}", path_to_mark);
+                    narrate!("[*] Marked star: {}", path_to_mark);
                 }
-                eprintln!("   Utility: {:.0}% (bright)", MARK_DEFAULT_UTILITY * 100.0);
+                narrate!("   Utility: {:.0}% (bright)", MARK_DEFAULT_UTILITY * 100.0);
 
                 // Show current bright stars count
                 let bright_count = journal.all_bright_stars().len();
-                eprintln!("   Total bright stars: {}", bright_count);
+                narrate!("   Total bright stars: {}", bright_count);
+                porcelain_record("marked", &[path_to_mark, &bright_count]);
             }
             Err(e) => {
                 eprintln!("Error saving journal: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
         return;
@@ -2700,7 +3740,7 @@ pub fn run() {
                 "Error: Survey path '{}' must be a valid directory",
                 survey_root.display()
             );
-            std::process::exit(1);
+            exit(exit_code::ERROR);
         }
 
         // Run the survey
@@ -2720,73 +3760,430 @@ pub fn run() {
                 "Error: Stats path '{}' must be a valid directory",
                 stats_root.display()
             );
-            std::process::exit(1);
+            exit(exit_code::ERROR);
         }
 
         run_stats(&stats_root);
         return;
     }
 
-    // If no project root provided, show usage
-    let project_root = match cli.project_root {
-        Some(path) => path,
-        None => {
-            eprintln!("Error: PROJECT_ROOT argument is required");
-            eprintln!("Usage: pm_encoder <PROJECT_ROOT>");
-            eprintln!("\nTry 'pm_encoder --help' for more information.");
-            std::process::exit(1);
-        }
-    };
+    // Handle --doctor (self-test of config, state files and grammars)
+    if cli.doctor {
+        let doctor_root = cli
+            .project_root
+            .clone()
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
-    // Validate project root exists
-    if !project_root.exists() {
-        eprintln!("Error: Path '{}' does not exist", project_root.display());
-        std::process::exit(1);
+        let report = pm_encoder::doctor::diagnose(&doctor_root, cli.config.as_deref());
+        print!("{}", report.render(Palette::stdout(cli.color.into())));
+        if report.failures() > 0 {
+            exit(exit_code::ERROR);
+        }
+        return;
     }
 
-    // ═══════════════════════════════════════════════════════════════════════════
-    // 🔬 MICROSCOPE AUTO-FOCUS (v1.2.0)
-    // When path is a file instead of a directory, auto-switch to zoom mode
-    // This makes `vo src/core/engine.rs` act like `vo --zoom file=src/core/engine.rs .`
-    // ═══════════════════════════════════════════════════════════════════════════
-    let (project_root, auto_zoom_target) = if project_root.is_file() {
-        // Auto-focus: path is a file, switch to zoom mode
-        // Canonicalize to get absolute path for accurate root detection
-        let file_path = project_root
-            .canonicalize()
-            .unwrap_or_else(|_| project_root.clone());
+    // Handle --migrate (upgrade state files to the current formats)
+    if cli.migrate {
+        use pm_encoder::migrate::{migrate_state, Outcome};
 
-        // Find project root by looking for common markers or use parent directory
-        let parent = file_path
-            .parent()
-            .map(|p| p.to_path_buf())
+        let migrate_root = cli
+            .project_root
+            .clone()
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
 
-        // Try to find actual project root (git root, Cargo.toml, etc.)
-        let actual_root = find_project_root(&parent).unwrap_or(parent);
-
-        // Calculate relative path from project root to file
-        let relative_path = file_path
-            .strip_prefix(&actual_root)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| file_path.display().to_string());
+        let reports = migrate_state(&migrate_root, cli.dry_run);
+        let mut failed = false;
+        for report in &reports {
+            let current = report.kind.current_version();
+            let status = match &report.outcome {
+                Outcome::Missing => continue,
+                Outcome::UpToDate => format!("up to date ({})", current),
+                Outcome::Migrated { from, backup } if cli.dry_run => {
+                    format!(
+                        "would migrate {} -> {} (backup {})",
+                        from,
+                        current,
+                        backup.display()
+                    )
+                }
+                Outcome::Migrated { from, backup } => {
+                    format!(
+                        "migrated {} -> {} (backup {})",
+                        from,
+                        current,
+                        backup.display()
+                    )
+                }
+                Outcome::Failed(e) => {
+                    failed = true;
+                    format!("failed: {}", e)
+                }
+            };
+            println!(
+                "  {:<10} {}  {}",
+                report.kind.name(),
+                report.path.display(),
+                status
+            );
+        }
+        if reports.iter().all(|r| r.outcome == Outcome::Missing) {
+            println!(
+                "No state files in {}",
+                migrate_root.join(".pm_encoder").display()
+            );
+        }
+        if failed {
+            exit(exit_code::ERROR);
+        }
+        return;
+    }
 
-        eprintln!("🔬 Microscope Auto-Focus: Detected file path, zooming in...");
-        eprintln!("   File: {}", relative_path);
-        eprintln!("   Root: {}", actual_root.display());
-        eprintln!();
+    // Handle --decode (reconstruct files from Plus/Minus output)
+    if let Some(decode_path) = &cli.decode {
+        use pm_encoder::decode::{decode, write_files, ChecksumStatus, DecodeOptions};
+
+        // -o names the target directory, as an alternative to PROJECT_ROOT
+        if cli.output.is_some() && cli.project_root.is_some() {
+            eprintln!("Error: give --decode one target directory: PROJECT_ROOT or -o, not both");
+            exit(exit_code::USAGE);
+        }
+        let out_root = cli
+            .output
+            .clone()
+            .or_else(|| cli.project_root.clone())
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+
+        let input = match std::fs::read_to_string(decode_path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", decode_path.display(), e);
+                exit(exit_code::ERROR);
+            }
+        };
+        let files = match decode(&input) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        let options = DecodeOptions {
+            strict: cli.decode_strict,
+            dry_run: cli.dry_run,
+        };
+        let report = match write_files(&files, &out_root, &options) {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        for file in &files {
+            let status = match file.status {
+                ChecksumStatus::Verified => "ok",
+                ChecksumStatus::Modified => "modified",
+                ChecksumStatus::Truncated => "skipped (truncated)",
+            };
+            eprintln!("  {:<20} {}", status, file.path);
+        }
+        eprintln!(
+            "{} {} verified, {} modified, {} skipped into {}",
+            if cli.dry_run {
+                "Would decode:"
+            } else {
+                "Decoded:"
+            },
+            report.verified.len(),
+            report.modified.len(),
+            report.skipped.len(),
+            out_root.display()
+        );
+        return;
+    }
+
+    // Handle --concat (merge Plus/Minus bundles)
+    if !cli.concat.is_empty() {
+        use pm_encoder::concat::{concat_bundles, Bundle};
+
+        if !cli.prefix.is_empty() && cli.prefix.len() != cli.concat.len() {
+            eprintln!(
+                "Error: {} --prefix for {} bundles; give one per bundle",
+                cli.prefix.len(),
+                cli.concat.len()
+            );
+            exit(exit_code::USAGE);
+        }
+        let mut texts = Vec::new();
+        let mut manifests = Vec::new();
+        for path in &cli.concat {
+            match std::fs::read_to_string(path) {
+                Ok(s) => texts.push(s),
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", path.display(), e);
+                    exit(exit_code::ERROR);
+                }
+            }
+            manifests.push(read_sidecar_manifest(path));
+        }
+        let bundles: Vec<Bundle> = texts
+            .iter()
+            .zip(&manifests)
+            .enumerate()
+            .map(|(i, (text, manifest))| Bundle {
+                text,
+                manifest: manifest.as_ref(),
+                prefix: cli.prefix.get(i).map(String::as_str),
+            })
+            .collect();
+        let merged = match concat_bundles(&bundles) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        narrate!(
+            "Concatenated {} bundles: {} files ({} duplicates dropped, {} replaced)",
+            bundles.len(),
+            merged.manifest.files.len(),
+            merged.duplicates,
+            merged.replaced
+        );
+        porcelain_record(
+            "concat",
+            &[
+                &bundles.len(),
+                &merged.manifest.files.len(),
+                &merged.duplicates,
+                &merged.replaced,
+            ],
+        );
+        if let Some(output_path) = &cli.output {
+            match write_output_file(output_path, &merged.output, cli.compress) {
+                Ok(_) => {
+                    narrate!("Output written to: {}", output_path.display());
+                    porcelain_record("output", &[&output_path.display()]);
+                }
+                Err(e) => {
+                    eprintln!("Error writing output: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+            if cli.manifest || manifests.iter().any(Option::is_some) {
+                save_sidecar_manifest(&merged.manifest, output_path);
+            }
+        } else {
+            print!("{}", merged.output);
+        }
+        return;
+    }
+
+    // Handle --locate (reverse lookup of a quoted excerpt)
+    if let (Some(context_path), Some(excerpt_path)) = (&cli.locate, &cli.excerpt_file) {
+        use pm_encoder::decode::ChecksumStatus;
+
+        let read = |path: &Path| match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                exit(exit_code::ERROR);
+            }
+        };
+        let context = read(context_path.as_path());
+        let excerpt = read(excerpt_path.as_path());
+
+        // Use the sidecar manifest's line map for truncated files, if there is one
+        let manifest = read_sidecar_manifest(context_path);
+
+        let locations = match pm_encoder::locate::locate(&context, &excerpt, manifest.as_ref()) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+        if locations.is_empty() {
+            eprintln!("Excerpt not found in {}", context_path.display());
+            exit(exit_code::ERROR);
+        }
+
+        for location in &locations {
+            let lines = match location.original_lines {
+                Some((a, b)) => format!("{}:{}-{}", location.path, a, b),
+                None => format!(
+                    "{} (output lines {}-{}, original lines unknown)",
+                    location.path, location.output_lines.0, location.output_lines.1
+                ),
+            };
+            let note = match location.status {
+                ChecksumStatus::Verified => "",
+                ChecksumStatus::Modified => "  [modified since encoding]",
+                ChecksumStatus::Truncated => "  [truncated]",
+            };
+            println!("{}{}", lines, note);
+        }
+        return;
+    }
+
+    // A remote repository is fetched into a temporary directory, removed
+    // again when the checkout goes out of scope or by `exit`
+    let remote_checkout = cli.remote.as_deref().map(|spec| {
+        let fetched = pm_encoder::discovery::RemoteSpec::parse(spec).and_then(|spec| {
+            narrate!("Fetching {}...", spec.url);
+            pm_encoder::discovery::RemoteCheckout::fetch(&spec)
+        });
+        fetched.unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            exit(exit_code::ERROR);
+        })
+    });
+
+    // If no project root provided, show usage
+    let project_root = match (cli.project_root, &remote_checkout) {
+        (Some(path), _) => path,
+        (None, Some(checkout)) => checkout.path().to_path_buf(),
+        (None, None) => {
+            eprintln!("Error: PROJECT_ROOT argument is required");
+            eprintln!("Usage: pm_encoder <PROJECT_ROOT>");
+            eprintln!("\nTry 'pm_encoder --help' for more information.");
+            exit(exit_code::ERROR);
+        }
+    };
+
+    // Validate project root exists
+    if !project_root.exists() {
+        eprintln!("Error: Path '{}' does not exist", project_root.display());
+        exit(exit_code::ERROR);
+    }
+
+    // With --git-ref every root is archived as of that revision and walked
+    // as an archive; the snapshots are removed when they go out of scope
+    // or by `exit`.
+    // The config file still comes from the working tree
+    let mut git_ref_snapshots = Vec::new();
+    let mut config_root = None;
+    let project_root = match &cli.git_ref {
+        Some(rev) => {
+            narrate!("Reading {} at {}...", project_root.display(), rev);
+            for root in std::iter::once(&project_root).chain(&cli.extra_roots) {
+                match pm_encoder::discovery::GitRefSnapshot::take(root, rev) {
+                    Ok(snapshot) => git_ref_snapshots.push(snapshot),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        exit(exit_code::ERROR);
+                    }
+                }
+            }
+            cli.extra_roots = git_ref_snapshots[1..]
+                .iter()
+                .map(|s| s.path().to_path_buf())
+                .collect();
+            config_root = Some(project_root);
+            git_ref_snapshots[0].path().to_path_buf()
+        }
+        None => project_root,
+    };
+
+    // Several roots share one context, each under its directory name or
+    // its --prefix
+    let roots = std::iter::once(&project_root).chain(&cli.extra_roots);
+    if !cli.prefix.is_empty() && cli.prefix.len() != cli.extra_roots.len() + 1 {
+        eprintln!(
+            "Error: {} --prefix for {} project roots; give one per root",
+            cli.prefix.len(),
+            cli.extra_roots.len() + 1
+        );
+        exit(exit_code::USAGE);
+    }
+    if !cli.extra_roots.is_empty() && !cli.prefix_roots && cli.prefix.is_empty() {
+        eprintln!("Error: several project roots need --prefix-roots or a --prefix per root");
+        exit(exit_code::USAGE);
+    }
+    let mut root_prefixes = Vec::new();
+    if cli.prefix_roots {
+        for root in roots {
+            if !root.is_dir() && !is_archive(root) {
+                eprintln!(
+                    "Error: '{}' is not a directory or archive (--prefix-roots needs project roots)",
+                    root.display()
+                );
+                exit(exit_code::USAGE);
+            }
+            let prefix = pm_encoder::root_prefix(root);
+            if root_prefixes.contains(&prefix) {
+                eprintln!(
+                    "Error: two roots are named '{}'; --prefix-roots needs distinct directory names",
+                    prefix
+                );
+                exit(exit_code::USAGE);
+            }
+            root_prefixes.push(prefix);
+        }
+    } else {
+        for prefix in &cli.prefix {
+            let prefix = match pm_encoder::normalize_prefix(prefix) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    exit(exit_code::USAGE);
+                }
+            };
+            if root_prefixes.contains(&prefix) {
+                eprintln!("Error: --prefix '{}' is given twice", prefix);
+                exit(exit_code::USAGE);
+            }
+            root_prefixes.push(prefix);
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // 🔬 MICROSCOPE AUTO-FOCUS (v1.2.0)
+    // When path is a file instead of a directory, auto-switch to zoom mode
+    // This makes `vo src/core/engine.rs` act like `vo --zoom file=src/core/engine.rs .`
+    // ═══════════════════════════════════════════════════════════════════════════
+    // Archives are walked as directories instead
+    let (project_root, auto_zoom_target) = if project_root.is_file() && !is_archive(&project_root) {
+        // Auto-focus: path is a file, switch to zoom mode
+        // Canonicalize to get absolute path for accurate root detection
+        let file_path = project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.clone());
+
+        // Find project root by looking for common markers or use parent directory
+        let parent = file_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+
+        // Try to find actual project root (git root, Cargo.toml, etc.)
+        let actual_root = find_project_root(&parent).unwrap_or(parent);
+
+        // Calculate relative path from project root to file
+        let relative_path = file_path
+            .strip_prefix(&actual_root)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| file_path.display().to_string());
+
+        narrate!("🔬 Microscope Auto-Focus: Detected file path, zooming in...");
+        narrate!("   File: {}", relative_path);
+        narrate!("   Root: {}", actual_root.display());
+        narrate!();
+        porcelain_record("focus", &[&relative_path, &actual_root.display()]);
 
         (actual_root, Some(format!("file={}", relative_path)))
     } else {
         (project_root, None)
     };
 
-    if !project_root.is_dir() {
+    if !project_root.is_dir() && !is_archive(&project_root) {
         eprintln!(
-            "Error: Path '{}' is not a directory",
+            "Error: Path '{}' is not a directory or archive",
             project_root.display()
         );
-        std::process::exit(1);
+        exit(exit_code::ERROR);
     }
 
     // Handle --report-utility command (Context Store v2.2.0)
@@ -2814,18 +4211,69 @@ pub fn run() {
                     }
                     Err(e) => {
                         eprintln!("Error saving context store: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
                 return;
             }
             Err(e) => {
                 eprintln!("Error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
     }
 
+    // Handle --callgraph-export (render resolved calls from a root symbol)
+    if let (Some(format), Some(root_symbol)) = (cli.callgraph_export, &cli.callgraph_root) {
+        use pm_encoder::core::{CallGraphAnalyzer, SymbolResolver};
+
+        let resolver = SymbolResolver::new().with_ignore(cli.exclude.clone());
+        let analyzer = CallGraphAnalyzer::new().with_max_results(usize::MAX);
+        let graph = match analyzer.build_graph(
+            root_symbol,
+            &resolver,
+            &project_root,
+            cli.callgraph_depth,
+        ) {
+            Ok(g) => g,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        let writes_markdown = cli
+            .output
+            .as_ref()
+            .and_then(|p| p.extension())
+            .is_some_and(|ext| ext == "md");
+        let output = match format {
+            CallGraphFormat::Dot => graph.to_dot(),
+            CallGraphFormat::Mermaid if writes_markdown => {
+                format!("```mermaid\n{}```\n", graph.to_mermaid())
+            }
+            CallGraphFormat::Mermaid => graph.to_mermaid(),
+        };
+
+        if let Some(output_path) = &cli.output {
+            match std::fs::write(output_path, &output) {
+                Ok(_) => eprintln!(
+                    "Call graph ({} functions, {} calls) written to: {}",
+                    graph.nodes.len(),
+                    graph.edges.len(),
+                    output_path.display()
+                ),
+                Err(e) => {
+                    eprintln!("Error writing output: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        } else {
+            print!("{}", output);
+        }
+        return;
+    }
+
     // Handle --explore command (Intent-Driven Exploration v2.4.0)
     if let Some(intent_str) = &cli.explore {
         use pm_encoder::core::{ExplorationIntent, ExplorerConfig, IntentExplorer};
@@ -2838,7 +4286,7 @@ pub fn run() {
                 eprintln!(
                     "Valid intents: business-logic, debugging, onboarding, security, migration"
                 );
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         };
 
@@ -2857,8 +4305,10 @@ pub fn run() {
                 // Output format based on --format flag
                 let output = match cli.format {
                     OutputFormatArg::Xml | OutputFormatArg::ClaudeXml => result.to_xml(),
-                    OutputFormatArg::Markdown => result.to_text(), // Text is markdown-like
-                    OutputFormatArg::PlusMinus => result.to_text(),
+                    OutputFormatArg::Markdown | OutputFormatArg::Gemini => {
+                        result.to_text_with_detail(cli.detail.into()) // Text is markdown-like
+                    }
+                    OutputFormatArg::PlusMinus => result.to_text_with_detail(cli.detail.into()),
                 };
 
                 // Write to file or stdout
@@ -2869,7 +4319,7 @@ pub fn run() {
                         }
                         Err(e) => {
                             eprintln!("Error writing output: {}", e);
-                            std::process::exit(1);
+                            exit(exit_code::ERROR);
                         }
                     }
                 } else {
@@ -2879,9 +4329,107 @@ pub fn run() {
             }
             Err(e) => {
                 eprintln!("Exploration error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
+            }
+        }
+    }
+
+    // Handle --sarif (finding-focused bundle from linter/scanner results)
+    if let Some(sarif_path) = &cli.sarif {
+        use pm_encoder::core::{FindingsBundle, SarifLog};
+
+        let log = match SarifLog::from_file(sarif_path) {
+            Ok(log) => log,
+            Err(e) => {
+                eprintln!("Error reading SARIF file: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        let findings = log.findings();
+        let bundle = FindingsBundle::build(&project_root, &findings, cli.sarif_context);
+
+        let output = match cli.format {
+            OutputFormatArg::Xml | OutputFormatArg::ClaudeXml => bundle.to_xml(),
+            OutputFormatArg::Markdown | OutputFormatArg::PlusMinus | OutputFormatArg::Gemini => {
+                bundle.to_text()
+            }
+        };
+
+        if let Some(output_path) = &cli.output {
+            match std::fs::write(output_path, &output) {
+                Ok(_) => eprintln!(
+                    "Findings bundle ({} findings) written to: {}",
+                    bundle.len(),
+                    output_path.display()
+                ),
+                Err(e) => {
+                    eprintln!("Error writing output: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        } else {
+            print!("{}", output);
+        }
+        return;
+    }
+
+    // Handle --check-claims (hallucination guard for model citations)
+    if let Some(claims_path) = &cli.check_claims {
+        use pm_encoder::core::claims::{check_claims, load_claims};
+
+        let claims = match load_claims(claims_path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Error reading claims file: {}", e);
+                exit(exit_code::ERROR);
             }
+        };
+        let entries = match pm_encoder::walk_project(
+            &project_root.to_string_lossy(),
+            &EncoderConfig::default(),
+        ) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+        let files: Vec<(String, String)> =
+            entries.into_iter().map(|e| (e.path, e.content)).collect();
+
+        let report = check_claims(&files, &claims);
+        for result in &report.results {
+            let claim = &result.claim;
+            let mut cited = claim.path.clone().unwrap_or_default();
+            if let Some(line) = claim.line {
+                cited.push_str(&format!(":{}", line));
+                if let Some(end) = claim.end_line {
+                    cited.push_str(&format!("-{}", end));
+                }
+            }
+            if let Some(symbol) = &claim.symbol {
+                if !cited.is_empty() {
+                    cited.push(' ');
+                }
+                cited.push_str(symbol);
+            }
+            println!(
+                "  {:<20} {}  {}",
+                result.status.as_str(),
+                cited,
+                result.detail
+            );
+        }
+        println!(
+            "Claims: {} checked, {} fabricated",
+            report.results.len(),
+            report.fabricated()
+        );
+        if report.fabricated() > 0 {
+            exit(exit_code::ERROR);
         }
+        return;
     }
 
     // Build config from CLI args
@@ -2889,15 +4437,21 @@ pub fn run() {
         match EncoderConfig::from_file(&config_path) {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("Warning: Could not load config file: {}", e);
+                config_failure(cli.strict, &format!("Could not load config file: {}", e));
                 EncoderConfig::default()
             }
         }
     } else {
         // Try default config path
-        let default_config = project_root.join(".pm_encoder_config.json");
+        let default_config = config_root
+            .as_ref()
+            .unwrap_or(&project_root)
+            .join(".pm_encoder_config.json");
         if default_config.exists() {
-            EncoderConfig::from_file(&default_config).unwrap_or_default()
+            EncoderConfig::from_file(&default_config).unwrap_or_else(|e| {
+                config_failure(cli.strict, &format!("Could not load config file: {}", e));
+                EncoderConfig::default()
+            })
         } else {
             EncoderConfig::default()
         }
@@ -2911,11 +4465,15 @@ pub fn run() {
     if !cli.exclude.is_empty() {
         config.ignore_patterns.extend(cli.exclude);
     }
+    config.exclude_content_patterns.extend(cli.exclude_content);
 
     config.sort_by = match cli.sort_by {
         SortBy::Name => "name".to_string(),
         SortBy::Mtime => "mtime".to_string(),
         SortBy::Ctime => "ctime".to_string(),
+        SortBy::Size => "size".to_string(),
+        SortBy::Ext => "ext".to_string(),
+        SortBy::Utility => "utility".to_string(),
     };
 
     config.sort_order = match cli.sort_order {
@@ -2924,16 +4482,103 @@ pub fn run() {
     };
 
     config.stream = cli.stream;
-    config.follow_symlinks = cli.follow_symlinks;
-
-    // Apply truncation settings
-    config.truncate_lines = cli.truncate;
-    config.truncate_mode = match cli.truncate_mode {
-        TruncateMode::Simple => "simple".to_string(),
-        TruncateMode::Smart => "smart".to_string(),
-        TruncateMode::Structure => "structure".to_string(),
-    };
+    config.stream_order = cli.stream_order.into();
+    if let Some(policy) = cli.follow_symlinks {
+        config.follow_symlinks = policy.into();
+    }
+    if let Some(policy) = cli.generated {
+        config.generated = policy.into();
+    }
+    if let Some(policy) = cli.binary {
+        config.binary = policy.into();
+    }
+    if let Some(policy) = cli.lockfiles {
+        config.lockfiles = policy.into();
+    }
+    if let Some(syntax) = cli.pattern_syntax {
+        config.pattern_syntax = syntax.into();
+    }
+    if let Some(case) = cli.pattern_case {
+        config.case_insensitive_patterns = matches!(case, PatternCaseArg::Insensitive);
+    }
+    config.respect_gitignore = !cli.no_gitignore;
+    config.git_only = cli.git_only;
+    if let Some(depth) = cli.max_depth {
+        config.max_depth = Some(depth);
+    }
+    if let Some(files) = cli.max_total_files {
+        config.max_total_files = Some(files);
+    }
+    if let Some(size) = &cli.max_total_bytes {
+        match parse_token_budget(size) {
+            Ok(bytes) => config.max_total_bytes = Some(bytes as u64),
+            Err(e) => {
+                eprintln!("Error: Invalid --max-total-bytes: {}", e);
+                exit(exit_code::USAGE);
+            }
+        }
+    }
+    config.extra_roots = cli.extra_roots.clone();
+    config.prefix_roots = cli.prefix_roots;
+    if !cli.prefix_roots {
+        config.path_prefixes = root_prefixes.clone();
+    }
+    if let Some(spec) = &cli.changed_since {
+        let since = pm_encoder::discovery::Since::parse(spec);
+        let filter = if !root_prefixes.is_empty() {
+            let roots: Vec<(&Path, String)> = std::iter::once(&project_root)
+                .chain(&cli.extra_roots)
+                .map(|root| root.as_path())
+                .zip(root_prefixes.iter().cloned())
+                .collect();
+            pm_encoder::discovery::SinceFilter::resolve_prefixed(&since, &roots)
+        } else {
+            pm_encoder::discovery::SinceFilter::resolve(&since, &project_root)
+        };
+        match filter {
+            Ok(filter) => config.changed_since = Some(filter),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        }
+    }
+
+    // Apply truncation settings (the config file's stand unless --truncate
+    // or structure mode is given)
+    if cli.truncate > 0
+        || matches!(
+            cli.truncate_mode,
+            TruncateMode::Structure | TruncateMode::Outline | TruncateMode::StripComments
+        )
+    {
+        config.truncate_lines = cli.truncate;
+        config.truncate_mode = match cli.truncate_mode {
+            TruncateMode::Simple => "simple".to_string(),
+            TruncateMode::Tail => "tail".to_string(),
+            TruncateMode::Smart => "smart".to_string(),
+            TruncateMode::Structure => "structure".to_string(),
+            TruncateMode::Outline => "outline".to_string(),
+            TruncateMode::Tokens => "tokens".to_string(),
+            TruncateMode::StripComments => "strip-comments".to_string(),
+        };
+    }
     config.truncate_summary = cli.truncate_summary && !cli.no_truncate_summary;
+    config.strip_comments |= cli.strip_comments;
+    config.truncate_keep_patterns.extend(cli.truncate_keep);
+    if let Err(e) = pm_encoder::KeepPatterns::new(&config.truncate_keep_patterns) {
+        eprintln!("Error: {}", e);
+        exit(exit_code::ERROR);
+    }
+    config.escape_control_chars &= !cli.keep_control_chars;
+
+    // Nested .pm_encoder_config.json files refine ignores and truncation per subtree
+    if let Err(e) = config.discover_directory_configs(&project_root) {
+        config_failure(
+            cli.strict,
+            &format!("Could not load nested config files: {}", e),
+        );
+    }
     config.truncate_exclude = cli.truncate_exclude.clone();
     config.truncate_stats = cli.truncate_stats;
 
@@ -2943,10 +4588,19 @@ pub fn run() {
         OutputFormatArg::Xml => OutputFormat::Xml,
         OutputFormatArg::Markdown => OutputFormat::Markdown,
         OutputFormatArg::ClaudeXml => OutputFormat::ClaudeXml,
+        OutputFormatArg::Gemini => OutputFormat::Gemini,
     };
+    if config.templates.is_some() && config.output_format != OutputFormat::PlusMinus {
+        warning!("config templates only apply to --format plus-minus; ignoring them");
+    }
 
     // Apply determinism and privacy settings (v2.0.0)
     config.frozen = cli.frozen;
+    config.line_numbers = cli.line_numbers;
+    config.glossary = cli.glossary;
+    config.dedupe = cli.dedupe;
+    // The flag turns hints on; the config file's `header_hints` also can
+    config.header_hints |= cli.header_hints;
     config.allow_sensitive = cli.allow_sensitive;
     config.active_lens = cli.lens.clone();
 
@@ -2970,9 +4624,172 @@ pub fn run() {
         pm_encoder::MetadataMode::Auto
     };
 
-    // Streaming mode warning for file output
-    if cli.stream && cli.output.is_some() {
-        eprintln!("Warning: --stream mode writes directly to stdout, ignoring -o/--output");
+    // Utility sort, priority streaming and header hints read the learned
+    // scores; frozen output leaves the store alone, falling back to path order
+    if (config.sort_by == "utility"
+        || config.stream_order == StreamOrder::Priority
+        || config.header_hints)
+        && !cli.frozen
+    {
+        config.utility_store = Some(ContextStore::load_from_file(&ContextStore::default_path(
+            &project_root,
+        )));
+    }
+
+    if cli.cache {
+        config.cache = Some(std::sync::Arc::new(
+            pm_encoder::cache::ProcessingCache::open(&project_root),
+        ));
+    }
+
+    // Strict mode collects unreadable files during the walk, failing the
+    // run with them (see exit_if_skipped)
+    if cli.strict {
+        config.skipped = Some(Default::default());
+    }
+
+    // Per-file timings, reported when the run ends
+    let _timing_report = (cli.verbose > 0).then(|| {
+        let timings = std::sync::Arc::new(pm_encoder::timing::FileTimings::new());
+        config.timings = Some(timings.clone());
+        TimingReport {
+            timings,
+            per_file: cli.verbose > 1,
+        }
+    });
+
+    // Frozen output is canonical: no timestamps, and a fixed batch order
+    if config.frozen {
+        if config.metadata_mode == pm_encoder::MetadataMode::All {
+            config.metadata_mode = pm_encoder::MetadataMode::SizeOnly;
+        }
+        if cli.stream {
+            warning!("--frozen requires batch mode, ignoring --stream");
+            config.stream = false;
+        }
+    }
+
+    if cli.show_excluded {
+        show_excluded_files(&project_root, &config);
+    }
+
+    if cli.truncate_stats {
+        show_truncation_stats(&project_root, &config);
+    }
+
+    // Ticket focus: restrict output to files linked to the ticket via history and comments
+    if let Some(ticket) = &cli.focus_ticket {
+        use pm_encoder::core::build_ticket_index;
+
+        let files: Vec<(String, String)> =
+            match pm_encoder::walk_project(&project_root.to_string_lossy(), &config) {
+                Ok(entries) => entries.into_iter().map(|e| (e.path, e.content)).collect(),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            };
+        exit_if_skipped(&config);
+
+        let full_history = matches!(cli.chronos_depth, ChronosDepth::Full);
+        let index = build_ticket_index(&project_root, &files, full_history);
+
+        // Only keep files that still exist in the walked tree
+        let present: std::collections::HashSet<&str> =
+            files.iter().map(|(p, _)| p.as_str()).collect();
+        let focused: Vec<String> = index
+            .files_for(ticket)
+            .into_iter()
+            .filter(|p| present.contains(p.as_str()))
+            .collect();
+
+        if focused.is_empty() {
+            eprintln!("No files associated with ticket {}", ticket);
+            exit(exit_code::ERROR);
+        }
+
+        narrate!("[TICKET: {}] {} associated files", ticket, focused.len());
+        porcelain_record("ticket", &[ticket, &focused.len()]);
+        config.only_paths = Some(focused);
+    }
+
+    // Explicit file list: `git diff --name-only | vo . --files-from -`
+    if let Some(list_path) = &cli.files_from {
+        let text = if list_path.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())
+        } else {
+            std::fs::read_to_string(list_path)
+        };
+        let listed = text
+            .map_err(|e| format!("Cannot read {}: {}", list_path.display(), e))
+            .and_then(|text| {
+                let paths = pm_encoder::discovery::parse_file_list(&text);
+                pm_encoder::discovery::relative_paths(&project_root, &paths)
+            });
+        match listed {
+            Ok(listed) => {
+                let missing = listed
+                    .iter()
+                    .filter(|p| !project_root.join(p).is_file())
+                    .count();
+                narrate!(
+                    "[FILES-FROM] {} listed files ({} missing)",
+                    listed.len(),
+                    missing
+                );
+                config.restrict_to(listed);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        }
+    }
+
+    if cli.stream && cli.manifest {
+        warning!("--stream mode writes as it goes, ignoring --manifest");
+    }
+    if cli.stream && cli.validate_output {
+        warning!("--stream mode writes as it goes, ignoring --validate-output");
+    }
+    if cli.append && config.output_format != OutputFormat::PlusMinus {
+        eprintln!("Error: --append needs Plus/Minus output");
+        exit(exit_code::ERROR);
+    }
+
+    // Split mode: parse the per-part token limit up front
+    let split_tokens = match cli.split_by_tokens.as_deref().map(parse_token_budget) {
+        Some(Ok(n)) => Some(n),
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            exit(exit_code::ERROR);
+        }
+        None => None,
+    };
+    if split_tokens.is_some() {
+        if cli.stream {
+            warning!("--split-by-tokens requires batch mode, ignoring --stream");
+        }
+        config.stream = false;
+    }
+
+    // Sidecar manifests describe a file written with -o (or split parts)
+    if cli.manifest {
+        if cli.stream {
+            warning!("--manifest requires batch mode, ignoring --stream");
+            config.stream = false;
+        }
+        if cli.output.is_none() && split_tokens.is_none() && cli.bundle.is_none() {
+            warning!("--manifest requires -o/--output, no manifest will be written");
+        }
+    }
+
+    // Bundles need the full sorted file list
+    if cli.bundle.is_some() {
+        if cli.stream {
+            warning!("--bundle requires batch mode, ignoring --stream");
+        }
+        config.stream = false;
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -2998,13 +4815,14 @@ pub fn run() {
                     store.session_count()
                 }) {
                     Ok(count) => {
-                        eprintln!("Created zoom session: {}", name);
-                        eprintln!("Total sessions: {}", count);
-                        eprintln!("Use --zoom to add targets, --zoom-session show to view");
+                        narrate!("Created zoom session: {}", name);
+                        narrate!("Total sessions: {}", count);
+                        narrate!("Use --zoom to add targets, --zoom-session show to view");
+                        porcelain_record("session-created", &[&name, &count]);
                     }
                     Err(e) => {
                         eprintln!("Error creating session: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
                 return;
@@ -3015,15 +4833,16 @@ pub fn run() {
                     store.set_active(name)
                 }) {
                     Ok(Ok(())) => {
-                        eprintln!("Loaded zoom session: {}", name);
+                        narrate!("Loaded zoom session: {}", name);
+                        porcelain_record("session-loaded", &[&name]);
                     }
                     Ok(Err(e)) => {
                         eprintln!("Error: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                     Err(e) => {
                         eprintln!("Error loading sessions: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
                 return;
@@ -3033,19 +4852,20 @@ pub fn run() {
                     Ok(store) => {
                         let sessions = store.list_sessions_with_meta();
                         if sessions.is_empty() {
-                            eprintln!("No zoom sessions found.");
-                            eprintln!("Use --zoom-session create:<name> to create one");
+                            narrate!("No zoom sessions found.");
+                            narrate!("Use --zoom-session create:<name> to create one");
                         } else {
-                            eprintln!("Zoom Sessions:");
+                            narrate!("Zoom Sessions:");
                             for (name, is_active, last_accessed) in sessions {
                                 let marker = if is_active { " *" } else { "" };
-                                eprintln!("  {}{} (last: {})", name, marker, &last_accessed[..10]);
+                                narrate!("  {}{} (last: {})", name, marker, &last_accessed[..10]);
+                                porcelain_record("session", &[&name, &is_active, &last_accessed]);
                             }
                         }
                     }
                     Err(e) => {
                         eprintln!("Error loading sessions: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
                 return;
@@ -3054,35 +4874,40 @@ pub fn run() {
                 match ZoomSessionStore::load(&session_store_path) {
                     Ok(store) => {
                         if let Some(session) = store.active() {
-                            eprintln!("Active Session: {}", session.name);
+                            narrate!("Active Session: {}", session.name);
+                            porcelain_record(
+                                "session",
+                                &[&session.name, &true, &session.created_at],
+                            );
                             if let Some(desc) = &session.description {
-                                eprintln!("  Description: {}", desc);
+                                narrate!("  Description: {}", desc);
                             }
-                            eprintln!("  Created: {}", &session.created_at[..10]);
-                            eprintln!("  Active zooms: {}", session.zoom_count());
+                            narrate!("  Created: {}", &session.created_at[..10]);
+                            narrate!("  Active zooms: {}", session.zoom_count());
                             for (target, depth) in &session.active_zooms {
-                                eprintln!("    - {} ({:?})", target, depth);
+                                narrate!("    - {} ({:?})", target, depth);
+                                porcelain_record("zoom", &[target, &format!("{:?}", depth)]);
                             }
                             if session.history.can_undo() {
-                                eprintln!(
+                                narrate!(
                                     "  History: {} entries (undo available)",
                                     session.history.entries().len()
                                 );
                             }
                         } else {
-                            eprintln!("No active session.");
+                            narrate!("No active session.");
                             let names = store.list_sessions();
                             if !names.is_empty() {
-                                eprintln!("Available: {:?}", names);
-                                eprintln!("Use --zoom-session load:<name> to activate");
+                                narrate!("Available: {:?}", names);
+                                narrate!("Use --zoom-session load:<name> to activate");
                             } else {
-                                eprintln!("Use --zoom-session create:<name> to start");
+                                narrate!("Use --zoom-session create:<name> to start");
                             }
                         }
                     }
                     Err(e) => {
                         eprintln!("Error loading sessions: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
                 return;
@@ -3093,22 +4918,23 @@ pub fn run() {
                     None => {
                         eprintln!("Error: delete requires session name");
                         eprintln!("Usage: --zoom-session delete:<name>");
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 };
                 match ZoomSessionStore::with_persistence(&session_store_path, |store| {
                     store.delete_session(name)
                 }) {
                     Ok(Ok(())) => {
-                        eprintln!("Deleted session: {}", name);
+                        narrate!("Deleted session: {}", name);
+                        porcelain_record("session-deleted", &[&name]);
                     }
                     Ok(Err(e)) => {
                         eprintln!("Error: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                     Err(e) => {
                         eprintln!("Error: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
                 return;
@@ -3116,7 +4942,7 @@ pub fn run() {
             _ => {
                 eprintln!("Unknown zoom-session action: {}", action);
                 eprintln!("Valid actions: create, load, list, delete, show");
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
     }
@@ -3129,7 +4955,8 @@ pub fn run() {
         match ZoomSessionStore::with_persistence(&session_store_path, |store| {
             if let Some(session) = store.active_mut() {
                 if let Some(entry) = session.history.undo() {
-                    eprintln!(
+                    porcelain_record("undo", &[&entry.target, &format!("{:?}", entry.direction)]);
+                    narrate!(
                         "Undo: {:?} {} on {}",
                         entry.direction,
                         if matches!(entry.direction, pm_encoder::core::ZoomDirection::Expand) {
@@ -3141,12 +4968,11 @@ pub fn run() {
                     );
                     true
                 } else {
-                    eprintln!("Nothing to undo");
+                    warning!("nothing to undo");
                     false
                 }
             } else {
-                eprintln!("No active session");
-                eprintln!("Use --zoom-session create:<name> to start a session");
+                warning!("no active zoom session (start one with --zoom-session create:<name>)");
                 false
             }
         }) {
@@ -3163,15 +4989,15 @@ pub fn run() {
         match ZoomSessionStore::with_persistence(&session_store_path, |store| {
             if let Some(session) = store.active_mut() {
                 if let Some(entry) = session.history.redo() {
-                    eprintln!("Redo: {:?} on {}", entry.direction, entry.target);
+                    narrate!("Redo: {:?} on {}", entry.direction, entry.target);
+                    porcelain_record("redo", &[&entry.target, &format!("{:?}", entry.direction)]);
                     true
                 } else {
-                    eprintln!("Nothing to redo");
+                    warning!("nothing to redo");
                     false
                 }
             } else {
-                eprintln!("No active session");
-                eprintln!("Use --zoom-session create:<name> to start a session");
+                warning!("no active zoom session (start one with --zoom-session create:<name>)");
                 false
             }
         }) {
@@ -3191,14 +5017,15 @@ pub fn run() {
                 match ZoomSessionStore::with_persistence(&session_store_path, |store| {
                     if let Some(session) = store.active_mut() {
                         if session.remove_zoom(&target) {
-                            eprintln!("Collapsed: {}", target);
+                            narrate!("Collapsed: {}", target);
+                            porcelain_record("collapsed", &[&target]);
                             true
                         } else {
-                            eprintln!("Target not currently zoomed: {}", target);
+                            warning!("target not currently zoomed: {}", target);
                             false
                         }
                     } else {
-                        eprintln!("No active session");
+                        warning!("no active zoom session");
                         false
                     }
                 }) {
@@ -3209,11 +5036,74 @@ pub fn run() {
             }
             Err(e) => {
                 eprintln!("Error parsing collapse target: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
     }
 
+    // Role split: every file in the stable system section's tree and index,
+    // the working set (changed files, zoom targets) in the user section
+    if let Some(SplitArg::Roles) = cli.split {
+        let changed = config.changed_since.take();
+        let zoomed = match cli
+            .zoom
+            .as_ref()
+            .or(auto_zoom_target.as_ref())
+            .map(|zoom| zoom_target_path(zoom, &config, &project_root))
+            .transpose()
+        {
+            Ok(path) => path.flatten(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+        let mut entries = match pm_encoder::walk_project(&project_root.to_string_lossy(), &config) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+        exit_if_skipped(&config);
+        pm_encoder::sort_entries(&mut entries, &config);
+
+        // Without either, the whole project is the working set
+        let everything = changed.is_none() && zoomed.is_none();
+        let volatile = |entry: &pm_encoder::FileEntry| {
+            everything
+                || changed.as_ref().is_some_and(|since| since.admits(entry))
+                || zoomed.as_deref() == Some(entry.path.as_str())
+        };
+        let sections = match pm_encoder::serialize_roles(&config, &entries, volatile) {
+            Ok(sections) => sections,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        let base = cli.output.clone().unwrap_or_else(|| {
+            PathBuf::from(format!("context.{}", config.output_format.extension()))
+        });
+        for (role, text) in [("system", &sections.system), ("user", &sections.user)] {
+            let path = pm_encoder::role_section_path(&base, role);
+            if let Err(e) = write_output_file(&path, text, cli.compress) {
+                eprintln!("Error writing {}: {}", path.display(), e);
+                exit(exit_code::ERROR);
+            }
+            let tokens = pm_encoder::TokenEstimator::estimate_tokens(text);
+            narrate!(
+                "{} section written to: {} (~{} tokens)",
+                role,
+                path.display(),
+                tokens
+            );
+            porcelain_record("section", &[&role, &path.display(), &tokens]);
+        }
+        return;
+    }
+
     // Zoom mode (v2.0.0) - Fractal Protocol targeted context expansion
     // Includes Microscope Auto-Focus (v1.2.0) - auto-zoom when path is a file
     let effective_zoom = cli.zoom.as_ref().or(auto_zoom_target.as_ref());
@@ -3222,7 +5112,7 @@ pub fn run() {
             Ok(config) => config,
             Err(e) => {
                 eprintln!("Error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         };
 
@@ -3244,11 +5134,18 @@ pub fn run() {
 
                 match resolver.find_function(name, &project_root) {
                     Ok(loc) => {
-                        eprintln!(
+                        narrate!(
                             "Found {} at {}:{}-{}",
-                            name, loc.path, loc.start_line, loc.end_line
+                            name,
+                            loc.path,
+                            loc.start_line,
+                            loc.end_line
+                        );
+                        narrate!("  Signature: {}", loc.signature);
+                        porcelain_record(
+                            "found",
+                            &[name, &loc.path, &loc.start_line, &loc.end_line],
                         );
-                        eprintln!("  Signature: {}", loc.signature);
 
                         // Convert to file target with resolved lines
                         zoom_config.target = ZoomTarget::File {
@@ -3260,7 +5157,7 @@ pub fn run() {
                     }
                     Err(e) => {
                         eprintln!("Symbol resolution failed: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
             }
@@ -3269,11 +5166,19 @@ pub fn run() {
 
                 match resolver.find_class(name, &project_root) {
                     Ok(loc) => {
-                        eprintln!(
+                        narrate!(
                             "Found {} {} at {}:{}-{}",
-                            loc.symbol_type, name, loc.path, loc.start_line, loc.end_line
+                            loc.symbol_type,
+                            name,
+                            loc.path,
+                            loc.start_line,
+                            loc.end_line
+                        );
+                        narrate!("  Signature: {}", loc.signature);
+                        porcelain_record(
+                            "found",
+                            &[name, &loc.path, &loc.start_line, &loc.end_line],
                         );
-                        eprintln!("  Signature: {}", loc.signature);
 
                         zoom_config.target = ZoomTarget::File {
                             path: loc.path.clone(),
@@ -3284,7 +5189,7 @@ pub fn run() {
                     }
                     Err(e) => {
                         eprintln!("Symbol resolution failed: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
                     }
                 }
             }
@@ -3296,7 +5201,7 @@ pub fn run() {
                     format!("{}/mod.rs", name),
                     format!("{}/__init__.py", name),
                 ];
-                eprintln!(
+                narrate!(
                     "Module zoom: Looking for files matching {:?}",
                     module_patterns
                 );
@@ -3305,35 +5210,17 @@ pub fn run() {
             ZoomTarget::File { path, .. } => Some(path.clone()),
         };
 
-        // Build engine with current config
-        let engine = ContextEngine::with_config(pm_encoder::core::EncoderConfig {
-            ignore_patterns: config.ignore_patterns.clone(),
-            include_patterns: config.include_patterns.clone(),
-            max_file_size: config.max_file_size,
-            truncate_lines: config.truncate_lines,
-            truncate_mode: config.truncate_mode.clone(),
-            sort_by: config.sort_by.clone(),
-            sort_order: config.sort_order.clone(),
-            stream: config.stream,
-            truncate_summary: config.truncate_summary,
-            truncate_exclude: config.truncate_exclude.clone(),
-            truncate_stats: config.truncate_stats,
-            output_format: match config.output_format {
-                OutputFormat::PlusMinus => pm_encoder::core::OutputFormat::PlusMinus,
-                OutputFormat::Xml => pm_encoder::core::OutputFormat::Xml,
-                OutputFormat::Markdown => pm_encoder::core::OutputFormat::Markdown,
-                OutputFormat::ClaudeXml => pm_encoder::core::OutputFormat::ClaudeXml,
-            },
-            frozen: config.frozen,
-            allow_sensitive: config.allow_sensitive,
-            active_lens: config.active_lens.clone(),
-            token_budget: config.token_budget,
-            skeleton_mode: config.skeleton_mode,
-            metadata_mode: config.metadata_mode,
-            follow_symlinks: config.follow_symlinks,
-        });
+        // Build engine with current config, formatting files as a normal run does
+        let mut engine_config = config.clone();
+        // Zoom stays inside the project: it follows links only when asked to
+        // follow every one, and not under `safe`, which it can't check link
+        // by link
+        if !matches!(cli.follow_symlinks, Some(SymlinkArg::On)) {
+            engine_config.follow_symlinks = pm_encoder::SymlinkPolicy::Off;
+        }
+        let engine = ContextEngine::with_encoder_config(engine_config);
 
-        match engine.zoom(project_root.to_str().unwrap(), &zoom_config) {
+        match engine.zoom(&project_root.to_string_lossy(), &zoom_config) {
             Ok(output) => {
                 // Apply Zoom Utility Bump (v2.2.0)
                 // When a file is zoomed into, we bump its utility by +0.05
@@ -3347,7 +5234,7 @@ pub fn run() {
                         store.bump_utility(file_path, ZOOM_BUMP, DEFAULT_ALPHA);
 
                         if let Err(e) = store.save_to_file(&store_path) {
-                            eprintln!("Warning: Could not save zoom utility bump: {}", e);
+                            warning!("Could not save zoom utility bump: {}", e);
                         }
                     }
                 }
@@ -3394,11 +5281,14 @@ pub fn run() {
                 let final_output = format!("{}{}", output, zoom_menu);
 
                 if let Some(output_path) = cli.output {
-                    match std::fs::write(&output_path, &final_output) {
-                        Ok(_) => eprintln!("Zoom output written to: {}", output_path.display()),
+                    match write_output_file(&output_path, &final_output, cli.compress) {
+                        Ok(_) => {
+                            narrate!("Zoom output written to: {}", output_path.display());
+                            porcelain_record("output", &[&output_path.display()]);
+                        }
                         Err(e) => {
                             eprintln!("Error writing output: {}", e);
-                            std::process::exit(1);
+                            exit(exit_code::ERROR);
                         }
                     }
                 } else {
@@ -3407,7 +5297,7 @@ pub fn run() {
             }
             Err(e) => {
                 eprintln!("Zoom error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
         return;
@@ -3421,17 +5311,38 @@ pub fn run() {
         };
 
         match pm_encoder::init::init_prompt(
-            project_root.to_str().unwrap(),
+            &project_root.to_string_lossy(),
             &cli.init_lens,
             target_str,
         ) {
             Ok((instruction_path, context_path)) => {
-                eprintln!("Generated: {}", instruction_path);
-                eprintln!("Generated: {}", context_path);
+                narrate!("Generated: {}", instruction_path);
+                narrate!("Generated: {}", context_path);
+                porcelain_record("generated", &[&instruction_path]);
+                porcelain_record("generated", &[&context_path]);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        }
+        return;
+    }
+
+    // README.llm.md for the repository, pinned into every bundle
+    if cli.init_llm_readme {
+        match pm_encoder::init::init_llm_readme(&project_root.to_string_lossy()) {
+            Ok((readme_path, pin_path)) => {
+                narrate!("Generated: {}", readme_path);
+                porcelain_record("generated", &[&readme_path]);
+                if let Some(pin_path) = pin_path {
+                    narrate!("Pinned it in: {}", pin_path);
+                    porcelain_record("generated", &[&pin_path]);
+                }
             }
             Err(e) => {
                 eprintln!("Error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         }
         return;
@@ -3444,18 +5355,13 @@ pub fn run() {
             Ok(b) => b,
             Err(e) => {
                 eprintln!("Error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         };
 
         // Store token budget in config for metadata injection (v2.0.0)
         config.token_budget = Some(budget);
 
-        // Budgeting requires batch mode
-        if cli.stream {
-            eprintln!("Warning: --token-budget requires batch mode, ignoring --stream");
-        }
-
         // Get lens manager for priority resolution
         let mut lens_manager = LensManager::new();
 
@@ -3471,28 +5377,57 @@ pub fn run() {
                     if !applied.include_patterns.is_empty() {
                         config.include_patterns = applied.include_patterns;
                     }
-                    eprintln!("[LENS: {}] Priority groups active", lens_name);
+                    narrate!("[LENS: {}] Priority groups active", lens_name);
+                    porcelain_record("lens", &[lens_name]);
                 }
                 Err(e) => {
                     eprintln!("Error: {}", e);
-                    std::process::exit(1);
+                    exit(exit_code::ERROR);
+                }
+            }
+        }
+
+        // Streaming stops writing once the budget is spent
+        if config.stream {
+            if !matches!(cli.budget_strategy, BudgetStrategy::Drop) {
+                warning!("--budget-strategy requires batch mode, --stream stops at the budget");
+            }
+            let report =
+                match stream_output(&project_root, &config, cli.output.as_deref(), cli.compress) {
+                    Ok(report) => report.expect("streaming with a token budget reports it"),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        exit(exit_code::ERROR);
+                    }
+                };
+            // Streamed files are already out; the exit code still reports the loss
+            exit_if_skipped(&config);
+            match status_mode() {
+                StatusMode::Narrate => {
+                    report.print_report_with_palette(Palette::stderr(cli.color.into()))
                 }
+                StatusMode::Quiet => {}
+                StatusMode::Porcelain => print_budget_records(&report),
             }
+            if cli.strict && report.dropped_count > 0 {
+                eprintln!(
+                    "Error: {} file(s) omitted once the token budget was reached (--strict)",
+                    report.dropped_count
+                );
+                exit(exit_code::BUDGET_EXCEEDED);
+            }
+            return;
         }
 
         // Walk directory and collect files
-        let entries = match pm_encoder::walk_directory(
-            project_root.to_str().unwrap(),
-            &config.ignore_patterns,
-            &config.include_patterns,
-            config.max_file_size,
-        ) {
+        let entries = match pm_encoder::walk_project(&project_root.to_string_lossy(), &config) {
             Ok(e) => e,
             Err(e) => {
                 eprintln!("Error: {}", e);
-                std::process::exit(1);
+                exit(exit_code::ERROR);
             }
         };
+        exit_if_skipped(&config);
 
         // Convert to (path, content) tuples
         let files: Vec<(String, String)> =
@@ -3504,60 +5439,133 @@ pub fn run() {
             BudgetStrategy::Truncate => "truncate",
             BudgetStrategy::Hybrid => "hybrid",
         };
-        let (selected, report) = apply_token_budget(files, budget, &lens_manager, strategy_str);
+        // Selected entries carry no timestamps; the budget counts the
+        // markup of the format as they'll be serialized
+        let mut budget_config = config.clone();
+        budget_config.metadata_mode = pm_encoder::MetadataMode::None;
+        let overhead = pm_encoder::format_overhead(&budget_config).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            exit(exit_code::ERROR);
+        });
+        // Files pinned in .pmattributes are kept in full
+        let pinned = |path: &str| config.attributes_for(path).pinned();
+        let (selected, report) = apply_token_budget_for_format(
+            files,
+            budget,
+            &lens_manager,
+            strategy_str,
+            &pinned,
+            &overhead,
+        );
 
         // Print budget report to stderr
-        report.print_report();
+        match status_mode() {
+            StatusMode::Narrate => {
+                report.print_report_with_palette(Palette::stderr(cli.color.into()))
+            }
+            StatusMode::Quiet => {}
+            StatusMode::Porcelain => print_budget_records(&report),
+        }
+        if cli.strict && report.dropped_count > 0 {
+            eprintln!(
+                "Error: {} file(s) dropped to fit the token budget (--strict)",
+                report.dropped_count
+            );
+            exit(exit_code::BUDGET_EXCEEDED);
+        }
 
         // Build file entries for serialization
         let entries: Vec<pm_encoder::FileEntry> = selected
             .iter()
-            .map(|(path, content)| pm_encoder::FileEntry {
-                path: path.clone(),
-                size: content.len() as u64,
-                content: content.clone(),
-                md5: pm_encoder::calculate_md5(content),
-                mtime: 0,
-                ctime: 0,
-            })
+            .map(|(path, content)| pm_encoder::FileEntry::new(path.clone(), content.clone()))
             .collect();
 
-        // Serialize selected files with configured format and truncation
-        let output = if config.output_format == OutputFormat::ClaudeXml {
-            // Use streaming XmlWriter for ClaudeXml format with budget report (Fractal Protocol v2.0)
-            // This includes hotspots/coldspots in attention_map from BudgetReport
-            pm_encoder::serialize_entries_claude_xml_with_report(&config, &entries, &report)
-                .unwrap_or_else(|e| {
-                    eprintln!("Error serializing XML: {}", e);
-                    std::process::exit(1);
-                })
-        } else {
-            // Use standard serialization for other formats
-            let mut output = String::new();
-            for entry in &entries {
-                output.push_str(&pm_encoder::serialize_file_with_format(
-                    entry,
-                    config.truncate_lines,
-                    &config.truncate_mode,
-                    config.output_format,
-                ));
-            }
-            output
+        // Serialize selected files with configured format and truncation;
+        // Claude-XML's attention_map also lists the files the budget dropped
+        // (Fractal Protocol v2.0).
+        let output = pm_encoder::serialize_budgeted_entries(&budget_config, &entries, &report)
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            });
+        let (output, appended_manifest) = match (&cli.output, cli.append) {
+            (Some(path), true) => {
+                let manifest = cli
+                    .manifest
+                    .then(|| pm_encoder::build_sidecar_manifest(&budget_config, &entries, &output));
+                append_to_bundle(path, output, manifest)
+            }
+            _ => (output, None),
         };
+        if split_tokens.is_none() {
+            validate_or_exit(
+                cli.validate_output,
+                std::slice::from_ref(&output),
+                config.output_format,
+            );
+        }
 
         // Write output
-        if let Some(output_path) = cli.output.clone() {
-            match std::fs::write(&output_path, &output) {
-                Ok(_) => eprintln!("Output written to: {}", output_path.display()),
+        if let Some(max_tokens) = split_tokens {
+            match pm_encoder::split_entries_by_tokens(&config, &entries, max_tokens) {
+                Ok(parts) => {
+                    validate_or_exit(cli.validate_output, &parts, config.output_format);
+                    let base =
+                        write_split_parts(&parts, cli.output.as_deref(), &config, cli.compress);
+                    if cli.manifest {
+                        write_sidecar_manifest(&config, &entries, &parts, &base, true);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        } else if let Some(bundle_path) = &cli.bundle {
+            match pm_encoder::write_bundle(&config, &entries, &output, bundle_path) {
+                Ok(manifest) => {
+                    narrate!(
+                        "Bundle written to: {} ({} files)",
+                        bundle_path.display(),
+                        manifest.files.len()
+                    );
+                    porcelain_record("bundle", &[&bundle_path.display(), &manifest.files.len()]);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        } else if let Some(output_path) = cli.output.clone() {
+            match write_output_file(&output_path, &output, cli.compress) {
+                Ok(_) => {
+                    narrate!("Output written to: {}", output_path.display());
+                    porcelain_record("output", &[&output_path.display()]);
+                }
                 Err(e) => {
                     eprintln!("Error writing output: {}", e);
-                    std::process::exit(1);
+                    exit(exit_code::ERROR);
+                }
+            }
+            if cli.append {
+                if let Some(manifest) = &appended_manifest {
+                    save_sidecar_manifest(manifest, &output_path);
                 }
+            } else if cli.manifest {
+                write_sidecar_manifest(
+                    &config,
+                    &entries,
+                    std::slice::from_ref(&output),
+                    &output_path,
+                    false,
+                );
             }
         } else {
             print!("{}", output);
         }
 
+        warn_merge_conflicts(&output);
+
         // Print Context Health if requested
         if cli.health {
             print_context_health(&output, entries.len());
@@ -3578,34 +5586,215 @@ pub fn run() {
             cli.lens.as_deref(),
             token_budget_parsed,
             entries.len(),
+            cli.detail.into(),
+            Palette::stderr(cli.color.into()),
+        );
+        return;
+    }
+
+    // Diff-embedding mode: full content + diff for files changed since a revision
+    if let Some(rev) = &cli.since {
+        use pm_encoder::core::temporal::ChronosEngine;
+
+        let changes = match ChronosEngine::new(&project_root) {
+            Some(engine) => engine.changes_since(rev, &project_root),
+            None => Err("--since requires a git repository".to_string()),
+        };
+        let changes = match changes {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+
+        let mut entries = match pm_encoder::walk_project(&project_root.to_string_lossy(), &config) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+        exit_if_skipped(&config);
+        pm_encoder::sort_entries(&mut entries, &config);
+
+        let output =
+            match pm_encoder::serialize_sorted_entries_since(&config, &entries, rev, &changes) {
+                Ok(o) => o,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            };
+        let changed = entries
+            .iter()
+            .filter(|e| changes.contains_key(&e.path))
+            .count();
+        narrate!(
+            "[SINCE: {}] {} changed, {} stubbed",
+            rev,
+            changed,
+            entries.len() - changed
         );
+        porcelain_record("since", &[rev, &changed, &(entries.len() - changed)]);
+        validate_or_exit(
+            cli.validate_output,
+            std::slice::from_ref(&output),
+            config.output_format,
+        );
+
+        if let Some(output_path) = cli.output.clone() {
+            match write_output_file(&output_path, &output, cli.compress) {
+                Ok(_) => {
+                    narrate!("Output written to: {}", output_path.display());
+                    porcelain_record("output", &[&output_path.display()]);
+                }
+                Err(e) => {
+                    eprintln!("Error writing output: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        } else {
+            print!("{}", output);
+        }
+        return;
+    }
+
+    // Split mode (non-budgeted): write token-bounded parts
+    if let Some(max_tokens) = split_tokens {
+        let mut entries = match pm_encoder::walk_project(&project_root.to_string_lossy(), &config) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+        exit_if_skipped(&config);
+        pm_encoder::sort_entries(&mut entries, &config);
+
+        match pm_encoder::split_entries_by_tokens(&config, &entries, max_tokens) {
+            Ok(parts) => {
+                validate_or_exit(cli.validate_output, &parts, config.output_format);
+                let base = write_split_parts(&parts, cli.output.as_deref(), &config, cli.compress);
+                if cli.manifest {
+                    write_sidecar_manifest(&config, &entries, &parts, &base, true);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        }
+        return;
+    }
+
+    // Bundle mode (non-budgeted): archive instead of printing
+    if let Some(bundle_path) = &cli.bundle {
+        match pm_encoder::serialize_project_to_bundle(
+            &project_root.to_string_lossy(),
+            &config,
+            bundle_path,
+        ) {
+            Ok(manifest) => {
+                narrate!(
+                    "Bundle written to: {} ({} files)",
+                    bundle_path.display(),
+                    manifest.files.len()
+                );
+                porcelain_record("bundle", &[&bundle_path.display(), &manifest.files.len()]);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        }
+        // The bundle is written as it's walked
+        exit_if_skipped(&config);
+        return;
+    }
+
+    // Streaming writes each file to stdout or -o as it's walked
+    if config.stream {
+        if let Err(e) = stream_output(&project_root, &config, cli.output.as_deref(), cli.compress) {
+            eprintln!("Error: {}", e);
+            exit(exit_code::ERROR);
+        }
+        exit_if_skipped(&config);
         return;
     }
 
+    // Sidecar manifests need the selected entries, so walk here instead
+    let manifest_entries = match (cli.manifest, &cli.output) {
+        (true, Some(_)) => {
+            match pm_encoder::walk_project(&project_root.to_string_lossy(), &config) {
+                Ok(mut entries) => {
+                    pm_encoder::sort_entries(&mut entries, &config);
+                    Some(entries)
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    exit(exit_code::ERROR);
+                }
+            }
+        }
+        _ => None,
+    };
+
     // Serialize the project (non-budgeted mode)
-    match pm_encoder::serialize_project_with_config(project_root.to_str().unwrap(), &config) {
+    let result = match &manifest_entries {
+        Some(entries) => pm_encoder::serialize_sorted_entries(&config, entries),
+        None => pm_encoder::serialize_project_with_config(&project_root.to_string_lossy(), &config),
+    };
+    // Batch output is still unwritten here
+    exit_if_skipped(&config);
+    match result {
         Ok(output) => {
-            // In streaming mode, output was already written directly to stdout
-            if cli.stream {
-                // Nothing more to do - streaming already wrote to stdout
-                return;
-            }
+            let (output, appended_manifest) = match (&cli.output, cli.append) {
+                (Some(path), true) => {
+                    let manifest = manifest_entries.as_ref().map(|entries| {
+                        pm_encoder::build_sidecar_manifest(&config, entries, &output)
+                    });
+                    append_to_bundle(path, output, manifest)
+                }
+                _ => (output, None),
+            };
 
             // Batch mode: write to file or stdout
+            validate_or_exit(
+                cli.validate_output,
+                std::slice::from_ref(&output),
+                config.output_format,
+            );
             if let Some(ref output_path) = cli.output {
-                match std::fs::write(output_path, &output) {
+                match write_output_file(output_path, &output, cli.compress) {
                     Ok(_) => {
-                        eprintln!("Output written to: {}", output_path.display());
+                        narrate!("Output written to: {}", output_path.display());
+                        porcelain_record("output", &[&output_path.display()]);
                     }
                     Err(e) => {
                         eprintln!("Error writing output: {}", e);
-                        std::process::exit(1);
+                        exit(exit_code::ERROR);
+                    }
+                }
+                if cli.append {
+                    if let Some(manifest) = &appended_manifest {
+                        save_sidecar_manifest(manifest, output_path);
                     }
+                } else if let Some(entries) = &manifest_entries {
+                    write_sidecar_manifest(
+                        &config,
+                        entries,
+                        std::slice::from_ref(&output),
+                        output_path,
+                        false,
+                    );
                 }
             } else {
                 print!("{}", output);
             }
 
+            warn_merge_conflicts(&output);
+
             // Print Context Health if requested
             if cli.health {
                 // Count files in output (each file starts with "++++++++++ ")
@@ -3629,11 +5818,260 @@ pub fn run() {
                 cli.lens.as_deref(),
                 token_budget_parsed,
                 file_count,
+                cli.detail.into(),
+                Palette::stderr(cli.color.into()),
             );
         }
         Err(e) => {
             eprintln!("Error: {}", e);
-            std::process::exit(1);
+            exit(exit_code::ERROR);
+        }
+    }
+}
+
+/// Prints the per-file timings on stderr when dropped, at the end of the run
+struct TimingReport {
+    timings: std::sync::Arc<pm_encoder::timing::FileTimings>,
+    /// Every file's times (-vv), not just the slowest
+    per_file: bool,
+}
+
+impl Drop for TimingReport {
+    fn drop(&mut self) {
+        if status_mode() == StatusMode::Porcelain {
+            // One record per file, slowest first, in microseconds
+            let n = if self.per_file { usize::MAX } else { 10 };
+            for t in self.timings.slowest(n) {
+                porcelain_record(
+                    "timing",
+                    &[
+                        &t.path,
+                        &t.total().as_micros(),
+                        &t.read.as_micros(),
+                        &t.analyze.as_micros(),
+                        &t.truncate.as_micros(),
+                    ],
+                );
+            }
+            return;
+        }
+        if self.per_file {
+            eprint!("{}", self.timings.report());
+        }
+        eprint!("{}", self.timings.slowest_report(10));
+    }
+}
+
+/// With `--validate-output`, report what is wrong with any of `outputs`
+/// and exit before anything is written
+fn validate_or_exit(enabled: bool, outputs: &[String], format: OutputFormat) {
+    if !enabled {
+        return;
+    }
+    let mut valid = true;
+    for (i, output) in outputs.iter().enumerate() {
+        for problem in pm_encoder::formats::validate_output(output.as_bytes(), format) {
+            match outputs.len() {
+                1 => eprintln!("Error: invalid output: {}", problem),
+                _ => eprintln!("Error: invalid output (part {}): {}", i + 1, problem),
+            }
+            valid = false;
+        }
+    }
+    if !valid {
+        exit(exit_code::ERROR);
+    }
+}
+
+/// The sidecar manifest next to `output_path`, if there is a readable one
+fn read_sidecar_manifest(output_path: &Path) -> Option<pm_encoder::core::SidecarManifest> {
+    let manifest_path = pm_encoder::core::SidecarManifest::path_for(output_path);
+    if !manifest_path.exists() {
+        return None;
+    }
+    match pm_encoder::core::SidecarManifest::read_from(&manifest_path) {
+        Ok(m) => Some(m),
+        Err(e) => {
+            warning!("ignoring {}: {}", manifest_path.display(), e);
+            None
+        }
+    }
+}
+
+/// Merge fresh Plus/Minus output into the bundle already at `path` (see --append)
+///
+/// `manifest` describes `output`. Returns the merged output and, when either
+/// side had one, the merged manifest.
+fn append_to_bundle(
+    path: &Path,
+    output: String,
+    manifest: Option<pm_encoder::core::SidecarManifest>,
+) -> (String, Option<pm_encoder::core::SidecarManifest>) {
+    use pm_encoder::concat::{concat_bundles, Bundle};
+
+    let existing = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (output, manifest),
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path.display(), e);
+            exit(exit_code::ERROR);
+        }
+    };
+    let existing_manifest = read_sidecar_manifest(path);
+    let merged = concat_bundles(&[
+        Bundle {
+            text: &existing,
+            manifest: existing_manifest.as_ref(),
+            prefix: None,
+        },
+        Bundle {
+            text: &output,
+            manifest: manifest.as_ref(),
+            prefix: None,
+        },
+    ])
+    .unwrap_or_else(|e| {
+        eprintln!("Error: cannot append to {}: {}", path.display(), e);
+        exit(exit_code::ERROR);
+    });
+
+    narrate!(
+        "Appended to {}: {} files ({} duplicates dropped, {} replaced)",
+        path.display(),
+        merged.manifest.files.len(),
+        merged.duplicates,
+        merged.replaced
+    );
+    let has_manifest = existing_manifest.is_some() || manifest.is_some();
+    (merged.output, has_manifest.then_some(merged.manifest))
+}
+
+/// Stream the project to `-o`, compressed if `--compress` was given, or
+/// to stdout
+fn stream_output(
+    project_root: &Path,
+    config: &EncoderConfig,
+    output: Option<&Path>,
+    compress: Option<CompressArg>,
+) -> Result<Option<pm_encoder::BudgetReport>, String> {
+    let root = project_root.to_string_lossy();
+    let Some(path) = output else {
+        return pm_encoder::stream_project(&root, config);
+    };
+    let mut sink = pm_encoder::core::compress::OutputSink::create(path, compress.map(Into::into))
+        .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    let report = pm_encoder::stream_project_to(&root, config, &mut sink)?;
+    sink.finish()
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    narrate!("Output written to: {}", path.display());
+    porcelain_record("output", &[&path.display()]);
+    Ok(report)
+}
+
+/// Write a context file, compressing it if `--compress` was given
+fn write_output_file(
+    path: &Path,
+    content: &str,
+    compress: Option<CompressArg>,
+) -> std::io::Result<()> {
+    pm_encoder::core::compress::write_output(path, content, compress.map(Into::into))
+}
+
+/// Write split parts as `<stem>.partN.<ext>` next to `-o` (default: `context.<ext>`)
+///
+/// Returns the base path the parts were named after.
+fn write_split_parts(
+    parts: &[String],
+    output: Option<&Path>,
+    config: &EncoderConfig,
+    compress: Option<CompressArg>,
+) -> PathBuf {
+    let base = match output {
+        Some(path) => path.to_path_buf(),
+        None => PathBuf::from(format!("context.{}", config.output_format.extension())),
+    };
+
+    for (i, part) in parts.iter().enumerate() {
+        let part_path = pm_encoder::split_part_path(&base, i + 1);
+        if let Err(e) = write_output_file(&part_path, part, compress) {
+            eprintln!("Error writing {}: {}", part_path.display(), e);
+            exit(exit_code::ERROR);
+        }
+        let tokens = pm_encoder::TokenEstimator::estimate_tokens(part);
+        narrate!(
+            "Part {}/{} written to: {} (~{} tokens)",
+            i + 1,
+            parts.len(),
+            part_path.display(),
+            tokens
+        );
+        porcelain_record("part", &[&(i + 1), &part_path.display(), &tokens]);
+    }
+
+    base
+}
+
+/// Budget report as `budget`, `framing`, `truncated` and `dropped` porcelain records
+fn print_budget_records(report: &pm_encoder::BudgetReport) {
+    porcelain_record("budget", &[&report.used, &report.budget, &report.strategy]);
+    porcelain_record(
+        "framing",
+        &[&report.framing.overhead.format, &report.framing.tokens],
+    );
+    for (path, _, tokens, method) in &report.included_files {
+        if method == "truncated" {
+            porcelain_record("truncated", &[path, tokens]);
+        }
+    }
+    for (path, _, tokens) in &report.dropped_files {
+        porcelain_record("dropped", &[path, tokens]);
+    }
+}
+
+/// Write `<output>.manifest.json` for the serialized entries (see --manifest)
+///
+/// `parts` holds the serialized output; for split output the part file names
+/// are listed in the manifest, which sits next to the split base path.
+fn write_sidecar_manifest(
+    config: &EncoderConfig,
+    entries: &[pm_encoder::FileEntry],
+    parts: &[String],
+    output_path: &Path,
+    split: bool,
+) {
+    let mut manifest = pm_encoder::build_sidecar_manifest(config, entries, &parts.concat());
+    if split {
+        manifest.parts = (1..=parts.len())
+            .map(|i| {
+                pm_encoder::split_part_path(output_path, i)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            })
+            .collect();
+    }
+
+    save_sidecar_manifest(&manifest, output_path);
+}
+
+/// Write `manifest` next to `output_path`
+fn save_sidecar_manifest(manifest: &pm_encoder::core::SidecarManifest, output_path: &Path) {
+    let manifest_path = pm_encoder::core::SidecarManifest::path_for(output_path);
+    match manifest.write_to(&manifest_path) {
+        Ok(_) => {
+            narrate!(
+                "Manifest written to: {} ({} files)",
+                manifest_path.display(),
+                manifest.files.len()
+            );
+            porcelain_record(
+                "manifest",
+                &[&manifest_path.display(), &manifest.files.len()],
+            );
+        }
+        Err(e) => {
+            eprintln!("Error writing manifest: {}", e);
+            exit(exit_code::ERROR);
         }
     }
 }
@@ -3653,6 +6091,132 @@ struct LanguageStats {
 }
 
 /// Run tokei-style lines-of-code statistics
+/// Report a config load failure: a warning, or exit 4 under `--strict`
+/// Temporal thresholds from `--config` or the survey root's config file
+#[cfg(feature = "temporal")]
+fn survey_thresholds(root: &Path, cli: &Cli) -> pm_encoder::core::TemporalThresholds {
+    survey_config(root, cli).temporal_thresholds
+}
+
+/// `--config`, or the survey root's config file if it has one
+fn survey_config(root: &Path, cli: &Cli) -> EncoderConfig {
+    let path = match &cli.config {
+        Some(path) => path.clone(),
+        None => root.join(".pm_encoder_config.json"),
+    };
+    if cli.config.is_none() && !path.exists() {
+        return Default::default();
+    }
+    EncoderConfig::from_file(&path).unwrap_or_else(|e| {
+        config_failure(cli.strict, &format!("Could not load config file: {}", e));
+        Default::default()
+    })
+}
+
+fn config_failure(strict: bool, message: &str) {
+    if strict {
+        eprintln!("Error: {}", message);
+        exit(exit_code::CONFIG);
+    }
+    warning!("{}", message);
+}
+
+/// With --strict, exit 2 if the walk came across files it could not read
+fn exit_if_skipped(config: &EncoderConfig) {
+    let Some(skipped) = &config.skipped else {
+        return;
+    };
+    let skipped = skipped.lock().unwrap();
+    if skipped.is_empty() {
+        return;
+    }
+    for file in skipped.iter() {
+        if status_mode() == StatusMode::Porcelain {
+            porcelain_record("skipped", &[&file.path, &file.reason]);
+        } else {
+            eprintln!("Error: cannot read {}: {}", file.path, file.reason);
+        }
+    }
+    eprintln!(
+        "Error: {} file(s) could not be read (--strict)",
+        skipped.len()
+    );
+    exit(exit_code::PARTIAL);
+}
+
+/// Report how truncation shortened the files, and which were generated
+fn show_truncation_stats(project_root: &Path, config: &EncoderConfig) {
+    let stats =
+        match pm_encoder::walk_project_with_exclusions(&project_root.to_string_lossy(), config) {
+            Ok((entries, excluded)) => {
+                pm_encoder::TruncationStats::collect(config, &entries, &excluded)
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+    match status_mode() {
+        StatusMode::Narrate => eprint!("{}", stats.report()),
+        StatusMode::Quiet => {}
+        StatusMode::Porcelain => {
+            porcelain_record(
+                "truncation",
+                &[
+                    &stats.files,
+                    &stats.truncated,
+                    &stats.original_lines,
+                    &stats.final_lines,
+                ],
+            );
+            for (path, generated) in &stats.generated {
+                porcelain_record(
+                    "generated",
+                    &[
+                        path,
+                        &generated.kind.name(),
+                        &generated.reason,
+                        &stats.generated_policy.name(),
+                    ],
+                );
+            }
+            for (path, minified) in &stats.minified {
+                porcelain_record(
+                    "minified",
+                    &[
+                        path,
+                        &minified.bytes,
+                        &minified.lines,
+                        &minified.average_line_len,
+                    ],
+                );
+            }
+        }
+    }
+}
+
+/// List the files the walk leaves out, with the reason for each
+fn show_excluded_files(project_root: &Path, config: &EncoderConfig) {
+    let excluded =
+        match pm_encoder::walk_project_with_exclusions(&project_root.to_string_lossy(), config) {
+            Ok((_, excluded)) => excluded,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit(exit_code::ERROR);
+            }
+        };
+    if status_mode() == StatusMode::Porcelain {
+        for file in &excluded {
+            porcelain_record("excluded", &[&file.path, &file.reason.kind(), &file.reason]);
+        }
+        return;
+    }
+    eprintln!("🚫 Excluded ({}):", excluded.len());
+    for file in &excluded {
+        eprintln!("   {}  ({})", file.path, file.reason);
+    }
+}
+
 fn run_stats(root: &PathBuf) {
     use pm_encoder::core::STELLAR_LIBRARY;
     use std::collections::HashMap;
@@ -3662,7 +6226,7 @@ fn run_stats(root: &PathBuf) {
 
     // Walk directory and collect files
     let entries = match pm_encoder::walk_directory(
-        root.to_str().unwrap(),
+        &root.to_string_lossy(),
         &[],        // no exclude patterns
         &[],        // no include patterns
         10_000_000, // 10MB max file size
@@ -3670,7 +6234,7 @@ fn run_stats(root: &PathBuf) {
         Ok(e) => e,
         Err(e) => {
             eprintln!("Error walking directory: {}", e);
-            std::process::exit(1);
+            exit(exit_code::ERROR);
         }
     };
 
@@ -0,0 +1,157 @@
+//! Content-addressed blob store
+//!
+//! Processed artifacts are kept one file each under the hash of everything
+//! they were computed from, so any checkout holding the same content finds
+//! them again: switching branches back and forth, or opening another
+//! worktree, reuses what was already computed instead of starting over.
+//!
+//! In a git repository the store lives in the git directory shared by all
+//! worktrees (`.git/pm_encoder_cache/blobs/`), which checkouts never touch;
+//! elsewhere it is `.pm_encoder_cache/blobs/` in the project root. Blobs
+//! are only ever added; deleting the directory starts over.
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Directory, in the git directory, shared stores live in
+const GIT_STORE_DIR: &str = "pm_encoder_cache";
+
+/// A directory of artifacts addressed by [`BlobStore::key`]
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    dir: PathBuf,
+}
+
+impl BlobStore {
+    /// The store in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The store of the project at `root`, shared with every worktree of
+    /// its repository
+    pub fn for_project(root: &Path) -> Self {
+        let dir = match git_common_dir(root) {
+            Some(git_dir) => git_dir.join(GIT_STORE_DIR),
+            None => root.join(crate::cache::CACHE_DIR),
+        };
+        Self::new(dir.join("blobs"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The address of an artifact computed from `parts`
+    pub fn key(parts: &[&str]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(key)
+    }
+
+    /// The artifact stored under `key`; `None` if there is none or it
+    /// can't be read
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let bytes = std::fs::read(self.path(key)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Store `value` under `key`
+    ///
+    /// The blob is written beside its final name and renamed into place, so
+    /// concurrent runs never see half a blob.
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<(), String> {
+        let path = self.path(key);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+        let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&temp, json)
+            .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+        std::fs::rename(&temp, &path)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// The git directory shared by all worktrees of the repository `root` is
+/// in, if any
+fn git_common_dir(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    for dir in root.ancestors() {
+        let git = dir.join(".git");
+        if git.is_dir() {
+            return Some(git);
+        }
+        if git.is_file() {
+            // A worktree: `.git` names its own git directory, whose
+            // `commondir` leads to the shared one
+            let content = std::fs::read_to_string(&git).ok()?;
+            let git_dir = dir.join(content.strip_prefix("gitdir:")?.trim());
+            return Some(match std::fs::read_to_string(git_dir.join("commondir")) {
+                Ok(common) => git_dir.join(common.trim()),
+                Err(_) => git_dir,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_and_get() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        let key = BlobStore::key(&["src/lib.rs", "abc"]);
+        assert_ne!(key, BlobStore::key(&["src/lib.r", "sabc"]));
+
+        assert_eq!(store.get::<Vec<u32>>(&key), None);
+        store.put(&key, &vec![1, 2, 3]).unwrap();
+        assert_eq!(store.get::<Vec<u32>>(&key), Some(vec![1, 2, 3]));
+        assert!(temp.path().join(&key[..2]).join(&key).exists());
+    }
+
+    #[test]
+    fn test_worktrees_share_the_store() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let main = temp.path().join("main");
+        let worktree = temp.path().join("feature");
+        let git_dir = main.join(".git/worktrees/feature");
+        std::fs::create_dir_all(&git_dir).unwrap();
+        std::fs::create_dir_all(main.join("src")).unwrap();
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        std::fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", git_dir.display()),
+        )
+        .unwrap();
+
+        let shared = BlobStore::for_project(&main.join("src"));
+        let other = BlobStore::for_project(&worktree);
+        shared.put("ab12", &1).unwrap();
+        assert_eq!(other.get::<i32>("ab12"), Some(1));
+        assert!(main.join(".git/pm_encoder_cache/blobs/ab/ab12").exists());
+
+        // Outside git the store is the project's own
+        let plain = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            BlobStore::for_project(plain.path()).dir(),
+            plain.path().join(".pm_encoder_cache/blobs")
+        );
+    }
+}
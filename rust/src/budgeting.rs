@@ -12,6 +12,7 @@
 //! 4. Other (docs, scripts) - Lowest priority
 
 use crate::core::engine::FileTier;
+use crate::core::presenter::{ColorChoice, Palette, Style};
 use crate::lenses::LensManager;
 use crate::truncate_structure;
 use std::path::Path;
@@ -19,6 +20,9 @@ use std::path::Path;
 /// Threshold for hybrid strategy: files > 10% of budget get auto-truncated
 const HYBRID_THRESHOLD: f64 = 0.10;
 
+/// Bytes the heuristic counts as one token
+pub const BYTES_PER_TOKEN: usize = 4;
+
 /// Token estimation using heuristic (4 chars per token)
 ///
 /// Note: Rust implementation uses heuristic only. For precise counting,
@@ -31,17 +35,14 @@ impl TokenEstimator {
     /// The heuristic of len/4 is based on the observation that
     /// English text averages about 4 characters per token for GPT tokenizers.
     pub fn estimate_tokens(content: &str) -> usize {
-        content.len() / 4
+        content.len() / BYTES_PER_TOKEN
     }
 
     /// Estimate tokens for a file including PM format overhead
     ///
     /// Accounts for the ++++/---- markers and path repetition
     pub fn estimate_file_tokens(path: &Path, content: &str) -> usize {
-        let path_str = path.to_string_lossy();
-        // PM format: "++++++++++ path ++++++++++\n" + content + "\n---------- path checksum path ----------\n"
-        let overhead = 20 + path_str.len() * 2 + 50; // Approximate overhead
-        Self::estimate_tokens(content) + (overhead / 4)
+        FormatOverhead::default().file_tokens(&path.to_string_lossy(), content)
     }
 
     /// Get the estimation method name
@@ -50,6 +51,62 @@ impl TokenEstimator {
     }
 }
 
+/// Markup an output format wraps around file content
+///
+/// Measured per format by rendering files (see
+/// [`crate::format_overhead`]): a fixed envelope per output, plus per file a
+/// fixed frame and the path, repeated as often as the format repeats it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOverhead {
+    /// Format name
+    pub format: String,
+    /// Bytes of the envelope around all files (Claude-XML's `<context>`)
+    pub envelope_bytes: usize,
+    /// Bytes framing each file, besides its path
+    pub file_bytes: usize,
+    /// How often each file's path appears in its frame
+    pub path_repeats: usize,
+}
+
+impl Default for FormatOverhead {
+    /// The plus/minus estimate: `++++++++++ path ++++++++++` and
+    /// `---------- path checksum path ----------`
+    fn default() -> Self {
+        Self {
+            format: "plus_minus".to_string(),
+            envelope_bytes: 0,
+            file_bytes: 70,
+            path_repeats: 2,
+        }
+    }
+}
+
+impl FormatOverhead {
+    /// Tokens of `content` at `path`, frame included
+    pub fn file_tokens(&self, path: &str, content: &str) -> usize {
+        TokenEstimator::estimate_tokens(content) + self.frame_tokens(path)
+    }
+
+    /// Tokens of the frame around the file at `path`
+    pub fn frame_tokens(&self, path: &str) -> usize {
+        (self.file_bytes + self.path_repeats * path.len()) / 4
+    }
+
+    /// Tokens of the envelope
+    pub fn envelope_tokens(&self) -> usize {
+        self.envelope_bytes / 4
+    }
+}
+
+/// Framing counted against a budget
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FramingCost {
+    /// The format's overhead
+    pub overhead: FormatOverhead,
+    /// Tokens of envelope and frames among those used
+    pub tokens: usize,
+}
+
 /// Parse a token budget string with optional k/M suffix
 ///
 /// # Arguments
@@ -133,6 +190,8 @@ pub struct BudgetReport {
     pub included_files: Vec<(String, i32, usize, String)>,
     /// Count of auto-truncated files
     pub truncated_count: usize,
+    /// Markup among the tokens used
+    pub framing: FramingCost,
 }
 
 impl BudgetReport {
@@ -151,46 +210,120 @@ impl BudgetReport {
     }
 
     /// Print a formatted budget report to stderr
+    ///
+    /// Colored when stderr is a terminal and `NO_COLOR` is unset.
     pub fn print_report(&self) {
-        eprintln!("{}", "=".repeat(70));
-        eprintln!("TOKEN BUDGET REPORT");
-        eprintln!("{}", "=".repeat(70));
-        eprintln!("Budget:     {:>10} tokens", format_number(self.budget));
-        eprintln!(
-            "Used:       {:>10} tokens ({:.1}%)",
-            format_number(self.used),
-            self.used_percentage()
-        );
-        eprintln!("Remaining:  {:>10} tokens", format_number(self.remaining()));
-        eprintln!("Estimation: {}", self.estimation_method);
-        eprintln!("Strategy:   {}", self.strategy);
-        eprintln!();
+        self.print_report_with_palette(Palette::stderr(ColorChoice::Auto));
+    }
+
+    /// Print the budget report to stderr styled with `palette`
+    pub fn print_report_with_palette(&self, palette: Palette) {
+        eprint!("{}", self.render_report(palette));
+    }
+
+    /// Render the budget report as text styled with `palette`
+    pub fn render_report(&self, palette: Palette) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        let rule = "=".repeat(70);
+        let used_style = match self.used_percentage() {
+            p if p > 100.0 => Style::Bad,
+            p if p >= 90.0 => Style::Warn,
+            _ => Style::Good,
+        };
+
+        writeln!(out, "{}", rule).ok();
+        writeln!(
+            out,
+            "{}",
+            palette.paint(Style::Heading, "TOKEN BUDGET REPORT")
+        )
+        .ok();
+        writeln!(out, "{}", rule).ok();
+        writeln!(out, "Budget:     {:>10} tokens", format_number(self.budget)).ok();
+        writeln!(
+            out,
+            "Used:       {} tokens ({})",
+            palette.paint(used_style, format!("{:>10}", format_number(self.used))),
+            palette.paint(used_style, format!("{:.1}%", self.used_percentage()))
+        )
+        .ok();
+        writeln!(
+            out,
+            "Remaining:  {:>10} tokens",
+            format_number(self.remaining())
+        )
+        .ok();
+        writeln!(out, "Estimation: {}", self.estimation_method).ok();
+        writeln!(out, "Strategy:   {}", self.strategy).ok();
+        writeln!(
+            out,
+            "Framing:    {:>10} tokens ({} markup)",
+            format_number(self.framing.tokens),
+            self.framing.overhead.format
+        )
+        .ok();
+        writeln!(out).ok();
 
         let full_count = self
             .included_files
             .iter()
             .filter(|(_, _, _, m)| m == "full")
             .count();
-        eprintln!(
+        writeln!(
+            out,
             "Files included: {} ({} full, {} truncated)",
-            self.selected_count, full_count, self.truncated_count
-        );
-        eprintln!(
-            "Files dropped:  {} (lowest priority first)",
-            self.dropped_count
-        );
+            self.selected_count,
+            full_count,
+            palette.paint(
+                if self.truncated_count > 0 {
+                    Style::Warn
+                } else {
+                    Style::Good
+                },
+                self.truncated_count
+            )
+        )
+        .ok();
+        // Streaming omits whatever comes after the budget ran out
+        let dropped_order = if self.strategy == "stream" {
+            "in stream order"
+        } else {
+            "lowest priority first"
+        };
+        writeln!(
+            out,
+            "Files dropped:  {} ({})",
+            palette.paint(
+                if self.dropped_count > 0 {
+                    Style::Bad
+                } else {
+                    Style::Good
+                },
+                self.dropped_count
+            ),
+            dropped_order
+        )
+        .ok();
 
         if self.truncated_count > 0 {
-            eprintln!();
-            eprintln!("Auto-truncated files (structure mode):");
+            writeln!(out).ok();
+            writeln!(
+                out,
+                "{}",
+                palette.paint(Style::Warn, "Auto-truncated files (structure mode):")
+            )
+            .ok();
             for (path, priority, tokens, method) in self.included_files.iter().take(5) {
                 if method == "truncated" {
-                    eprintln!(
-                        "  [P:{:3}] {} ({} tokens)",
-                        priority,
-                        path,
+                    writeln!(
+                        out,
+                        "  {} {} ({} tokens)",
+                        palette.paint(Style::Dim, format!("[P:{:3}]", priority)),
+                        palette.paint(Style::Accent, path),
                         format_number(*tokens)
-                    );
+                    )
+                    .ok();
                 }
             }
             let truncated_list: Vec<_> = self
@@ -199,27 +332,30 @@ impl BudgetReport {
                 .filter(|(_, _, _, m)| m == "truncated")
                 .collect();
             if truncated_list.len() > 5 {
-                eprintln!("  ... and {} more", truncated_list.len() - 5);
+                writeln!(out, "  ... and {} more", truncated_list.len() - 5).ok();
             }
         }
 
         if !self.dropped_files.is_empty() {
-            eprintln!();
-            eprintln!("Dropped files:");
+            writeln!(out).ok();
+            writeln!(out, "{}", palette.paint(Style::Bad, "Dropped files:")).ok();
             for (path, priority, tokens) in self.dropped_files.iter().take(10) {
-                eprintln!(
-                    "  [P:{:3}] {} ({} tokens)",
-                    priority,
-                    path,
+                writeln!(
+                    out,
+                    "  {} {} ({} tokens)",
+                    palette.paint(Style::Dim, format!("[P:{:3}]", priority)),
+                    palette.paint(Style::Accent, path),
                     format_number(*tokens)
-                );
+                )
+                .ok();
             }
             if self.dropped_files.len() > 10 {
-                eprintln!("  ... and {} more", self.dropped_files.len() - 10);
+                writeln!(out, "  ... and {} more", self.dropped_files.len() - 10).ok();
             }
         }
 
-        eprintln!("{}", "=".repeat(70));
+        writeln!(out, "{}", rule).ok();
+        out
     }
 }
 
@@ -266,6 +402,39 @@ pub fn apply_token_budget(
     budget: usize,
     lens_manager: &LensManager,
     strategy: &str,
+) -> (Vec<(String, String)>, BudgetReport) {
+    apply_token_budget_with_pins(files, budget, lens_manager, strategy, &|_| false)
+}
+
+/// Like [`apply_token_budget`], keeping the files `pinned` says (the
+/// `pin` attribute of `.pmattributes`) in full ahead of all others, even
+/// past the budget
+pub fn apply_token_budget_with_pins(
+    files: Vec<(String, String)>,
+    budget: usize,
+    lens_manager: &LensManager,
+    strategy: &str,
+    pinned: &dyn Fn(&str) -> bool,
+) -> (Vec<(String, String)>, BudgetReport) {
+    apply_token_budget_for_format(
+        files,
+        budget,
+        lens_manager,
+        strategy,
+        pinned,
+        &FormatOverhead::default(),
+    )
+}
+
+/// Like [`apply_token_budget_with_pins`], counting the markup of the format
+/// `overhead` describes: its envelope once, and each file's frame
+pub fn apply_token_budget_for_format(
+    files: Vec<(String, String)>,
+    budget: usize,
+    lens_manager: &LensManager,
+    strategy: &str,
+    pinned: &dyn Fn(&str) -> bool,
+    overhead: &FormatOverhead,
 ) -> (Vec<(String, String)>, BudgetReport) {
     // Step 1: Calculate tokens and get priorities, applying group-based truncation
     let mut file_data: Vec<FileData> = files
@@ -275,7 +444,7 @@ pub fn apply_token_budget(
             let group_config = lens_manager.get_file_group_config(path_obj);
 
             // Calculate original tokens before any truncation
-            let original_tokens = TokenEstimator::estimate_file_tokens(path_obj, &content);
+            let original_tokens = overhead.file_tokens(&path, &content);
 
             // Apply group-level truncation if specified (e.g., structure mode for *.py)
             let (final_content, method) = if let Some(ref mode) = group_config.truncate_mode {
@@ -293,7 +462,7 @@ pub fn apply_token_budget(
                 (content, "full".to_string())
             };
 
-            let tokens = TokenEstimator::estimate_file_tokens(path_obj, &final_content);
+            let tokens = overhead.file_tokens(&path, &final_content);
 
             FileData {
                 path,
@@ -306,13 +475,17 @@ pub fn apply_token_budget(
         })
         .collect();
 
-    // Step 2: Sort by tier (ASC), then priority (DESC), then path (ASC) for determinism
+    // Step 2: Sort pinned files first, then by tier (ASC), then priority (DESC),
+    // then path (ASC) for determinism
     // Tiered allocation ensures Core files get budget before Config, Tests, Other
     file_data.sort_by(|a, b| {
         let tier_a = FileTier::classify(&a.path, None) as u8;
         let tier_b = FileTier::classify(&b.path, None) as u8;
 
-        match tier_a.cmp(&tier_b) {
+        match pinned(&b.path)
+            .cmp(&pinned(&a.path))
+            .then(tier_a.cmp(&tier_b))
+        {
             std::cmp::Ordering::Equal => {
                 // Within same tier, sort by priority (highest first)
                 match b.priority.cmp(&a.priority) {
@@ -328,13 +501,11 @@ pub fn apply_token_budget(
     if strategy == "hybrid" {
         let budget_threshold = (budget as f64 * HYBRID_THRESHOLD) as usize;
         for fd in &mut file_data {
-            if fd.tokens > budget_threshold {
+            if fd.tokens > budget_threshold && !pinned(&fd.path) {
                 let (truncated_content, was_truncated) =
                     try_truncate_to_structure(&fd.path, &fd.content);
                 if was_truncated {
-                    let path_obj = Path::new(&fd.path);
-                    let new_tokens =
-                        TokenEstimator::estimate_file_tokens(path_obj, &truncated_content);
+                    let new_tokens = overhead.file_tokens(&fd.path, &truncated_content);
                     fd.content = truncated_content;
                     fd.tokens = new_tokens;
                     fd.method = "truncated".to_string();
@@ -346,17 +517,20 @@ pub fn apply_token_budget(
     // Step 4: Accumulate files within budget with strategy-specific handling
     let mut selected = Vec::new();
     let mut included_files = Vec::new();
-    let mut total_tokens = 0;
+    // The envelope is paid once, whatever fits
+    let mut total_tokens = overhead.envelope_tokens();
+    let mut framing_tokens = total_tokens;
     let mut dropped = Vec::new();
     let mut truncated_count = 0;
 
     for fd in file_data {
         // Check if file fits in remaining budget
-        if total_tokens + fd.tokens <= budget {
+        if total_tokens + fd.tokens <= budget || pinned(&fd.path) {
             if fd.method == "truncated" {
                 truncated_count += 1;
             }
             included_files.push((fd.path.clone(), fd.priority, fd.tokens, fd.method.clone()));
+            framing_tokens += overhead.frame_tokens(&fd.path);
             selected.push((fd.path, fd.content));
             total_tokens += fd.tokens;
         } else {
@@ -366,9 +540,7 @@ pub fn apply_token_budget(
                 let (truncated_content, was_truncated) =
                     try_truncate_to_structure(&fd.path, &fd.content);
                 if was_truncated {
-                    let path_obj = Path::new(&fd.path);
-                    let new_tokens =
-                        TokenEstimator::estimate_file_tokens(path_obj, &truncated_content);
+                    let new_tokens = overhead.file_tokens(&fd.path, &truncated_content);
                     if total_tokens + new_tokens <= budget {
                         // Truncated version fits!
                         truncated_count += 1;
@@ -378,6 +550,7 @@ pub fn apply_token_budget(
                             new_tokens,
                             "truncated".to_string(),
                         ));
+                        framing_tokens += overhead.frame_tokens(&fd.path);
                         selected.push((fd.path, truncated_content));
                         total_tokens += new_tokens;
                         continue;
@@ -400,6 +573,10 @@ pub fn apply_token_budget(
         strategy: strategy.to_string(),
         included_files,
         truncated_count,
+        framing: FramingCost {
+            overhead: overhead.clone(),
+            tokens: framing_tokens,
+        },
     };
 
     (selected, report)
@@ -460,6 +637,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
         assert!((report.used_percentage() - 50.0).abs() < 0.1);
         assert_eq!(report.remaining(), 500);
@@ -580,6 +758,39 @@ mod tests {
         assert_eq!(report_hybrid.strategy, "hybrid");
     }
 
+    #[test]
+    fn test_budget_counts_format_markup() {
+        let overhead = FormatOverhead {
+            format: "test".to_string(),
+            envelope_bytes: 400,
+            file_bytes: 40,
+            path_repeats: 1,
+        };
+        // Each file: 25 content tokens + (40 + 4) / 4 = 11 frame tokens
+        let files = vec![
+            ("a.py".to_string(), "x".repeat(100)),
+            ("b.py".to_string(), "x".repeat(100)),
+        ];
+        let (selected, report) = apply_token_budget_for_format(
+            files.clone(),
+            150,
+            &LensManager::new(),
+            "drop",
+            &|_| false,
+            &overhead,
+        );
+        // Raw content would fit both; envelope and frames leave room for one
+        assert_eq!(selected.len(), 1);
+        assert_eq!(report.used, 100 + 36);
+        assert_eq!(report.framing.tokens, 100 + 11);
+        assert!(report
+            .render_report(Palette::plain())
+            .contains("Framing:           111 tokens (test markup)"));
+
+        let (selected, _) = apply_token_budget(files, 150, &LensManager::new(), "drop");
+        assert_eq!(selected.len(), 2);
+    }
+
     #[test]
     fn test_file_token_estimation_with_overhead() {
         let path = Path::new("test.py");
@@ -607,6 +818,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
         // Remaining should be 0 when over budget, not negative
         assert_eq!(report.remaining(), 0);
@@ -624,6 +836,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
         // Should handle zero budget gracefully
         assert_eq!(report.used_percentage(), 0.0);
@@ -646,11 +859,61 @@ mod tests {
                 ("file3.py".to_string(), 60, 300, "full".to_string()),
             ],
             truncated_count: 1,
+            framing: FramingCost::default(),
         };
         // Just verify print_report doesn't panic
         report.print_report();
     }
 
+    #[test]
+    fn test_budget_report_render_plain_and_colored() {
+        let report = BudgetReport {
+            budget: 1000,
+            used: 950,
+            selected_count: 1,
+            dropped_count: 1,
+            dropped_files: vec![("dropped.py".to_string(), 50, 500)],
+            estimation_method: "Heuristic".to_string(),
+            strategy: "drop".to_string(),
+            included_files: vec![("kept.py".to_string(), 100, 950, "full".to_string())],
+            truncated_count: 0,
+            framing: FramingCost::default(),
+        };
+
+        let plain = report.render_report(Palette::plain());
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains("Used:              950 tokens (95.0%)"));
+        assert!(plain.contains("  [P: 50] dropped.py (500 tokens)"));
+
+        let colored = report.render_report(Palette::new(true));
+        assert!(colored.contains("\x1b[33m95.0%\x1b[0m"));
+        assert!(colored.contains("\x1b[1;31mDropped files:\x1b[0m"));
+    }
+
+    #[test]
+    fn test_budget_report_stream_order() {
+        let mut report = BudgetReport {
+            budget: 100,
+            used: 90,
+            selected_count: 1,
+            dropped_count: 1,
+            dropped_files: vec![("later.py".to_string(), 50, 500)],
+            estimation_method: "Heuristic".to_string(),
+            strategy: "drop".to_string(),
+            included_files: vec![("first.py".to_string(), 50, 90, "full".to_string())],
+            truncated_count: 0,
+            framing: FramingCost::default(),
+        };
+        assert!(report
+            .render_report(Palette::plain())
+            .contains("Files dropped:  1 (lowest priority first)"));
+
+        report.strategy = "stream".to_string();
+        assert!(report
+            .render_report(Palette::plain())
+            .contains("Files dropped:  1 (in stream order)"));
+    }
+
     #[test]
     fn test_budget_report_print_many_dropped() {
         let mut dropped_files = Vec::new();
@@ -667,6 +930,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
         // Should show "... and X more" for >10 dropped files
         report.print_report();
@@ -688,6 +952,7 @@ mod tests {
             strategy: "hybrid".to_string(),
             included_files,
             truncated_count: 10,
+            framing: FramingCost::default(),
         };
         // Should show "... and X more" for >5 truncated files
         report.print_report();
@@ -900,6 +1165,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![("main.py".to_string(), 100, 200, "full".to_string())],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
 
         let cloned = report.clone();
@@ -978,6 +1244,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
 
         assert_eq!(report.used_percentage(), 100.0);
@@ -1031,6 +1298,7 @@ mod tests {
             strategy: "drop".to_string(),
             included_files: vec![],
             truncated_count: 0,
+            framing: FramingCost::default(),
         };
 
         let debug_str = format!("{:?}", report);
@@ -0,0 +1,156 @@
+//! Persistent cache of processed files
+//!
+//! Truncation (and the language analysis behind it) is most of the work of
+//! a run, and on a large repository most files are unchanged since the
+//! last one. With `--cache` each file's processed content is kept in the
+//! project's [`BlobStore`], addressed by its path, the content processed
+//! (stubs such as `--dedupe`'s hash differently) and how the run transforms
+//! files (see [`crate::transform::TransformPipeline::fingerprint`]), so it
+//! is reused wherever the same file turns up again: in a later run, after
+//! switching branches and back, or in another worktree.
+//!
+//! A missing or unreadable blob just means a miss.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::blobs::BlobStore;
+use crate::transform::Transformed;
+
+/// Directory, in the project root, the cache lives in outside git
+pub const CACHE_DIR: &str = ".pm_encoder_cache";
+
+/// One file's processed content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Artifact {
+    text: String,
+    was_truncated: bool,
+    final_lines: usize,
+}
+
+/// The cache of a project, shared by the threads processing its files
+#[derive(Debug)]
+pub struct ProcessingCache {
+    store: BlobStore,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    /// Whether a failed write was already reported
+    warned: AtomicBool,
+}
+
+impl ProcessingCache {
+    /// The cache of the project at `root`
+    pub fn open(root: &Path) -> Self {
+        Self::in_store(BlobStore::for_project(root))
+    }
+
+    /// A cache kept in `store`
+    pub fn in_store(store: BlobStore) -> Self {
+        Self {
+            store,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Address of the file at `path` processed from content hashing to
+    /// `md5`; the encoder version is part of it, so upgrades start over
+    fn key(path: &str, md5: &str, fingerprint: &str) -> String {
+        BlobStore::key(&[crate::VERSION, path, md5, fingerprint])
+    }
+
+    /// The processed content of the file at `path`, if it was cached from
+    /// the same content (hashing to `md5`) and `fingerprint`
+    pub fn get(&self, path: &str, md5: &str, fingerprint: &str) -> Option<Transformed> {
+        let Some(cached) = self
+            .store
+            .get::<Artifact>(&Self::key(path, md5, fingerprint))
+        else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(Transformed {
+            text: cached.text,
+            was_truncated: cached.was_truncated,
+            final_lines: cached.final_lines,
+        })
+    }
+
+    /// Remember the processed content of the file at `path`, whose content
+    /// hashes to `md5`; a failure to write is reported once and otherwise
+    /// ignored
+    pub fn put(&self, path: &str, md5: &str, fingerprint: &str, transformed: &Transformed) {
+        let artifact = Artifact {
+            text: transformed.text.clone(),
+            was_truncated: transformed.was_truncated,
+            final_lines: transformed.final_lines,
+        };
+        if let Err(e) = self
+            .store
+            .put(&Self::key(path, md5, fingerprint), &artifact)
+        {
+            if !self.warned.swap(true, Ordering::Relaxed) {
+                eprintln!("Warning: cache not saved: {}", e);
+            }
+        }
+    }
+
+    /// How many lookups were answered from the cache
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// How many lookups found nothing cached
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileEntry;
+
+    #[test]
+    fn test_reuse_while_unchanged() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut entry = FileEntry::new("src/lib.rs", "fn a() {}\n");
+        entry.mtime = 100;
+        let transformed = Transformed {
+            text: "fn a() {}\n".to_string(),
+            was_truncated: false,
+            final_lines: 2,
+        };
+
+        let md5 = entry.md5.clone();
+
+        let cache = ProcessingCache::in_store(BlobStore::new(temp.path()));
+        assert_eq!(cache.get(&entry.path, &md5, "truncate:0"), None);
+        cache.put(&entry.path, &md5, "truncate:0", &transformed);
+        drop(cache);
+
+        let cache = ProcessingCache::in_store(BlobStore::new(temp.path()));
+        assert_eq!(
+            cache.get(&entry.path, &md5, "truncate:0"),
+            Some(transformed.clone())
+        );
+        assert_eq!((cache.hits(), cache.misses()), (1, 0));
+        // Other settings or content miss
+        assert_eq!(cache.get(&entry.path, &md5, "truncate:10"), None);
+        assert_eq!(cache.get(&entry.path, "0000", "truncate:0"), None);
+        // A touched file with the same content (a branch switched back) hits
+        entry.mtime = 101;
+        assert_eq!(
+            cache.get(&entry.path, &md5, "truncate:0"),
+            Some(transformed)
+        );
+        // The same content elsewhere is a different file
+        let moved = FileEntry::new("tests/lib.rs", "fn a() {}\n");
+        assert_eq!(cache.get(&moved.path, &md5, "truncate:0"), None);
+        assert_eq!((cache.hits(), cache.misses()), (2, 3));
+    }
+}
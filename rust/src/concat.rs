@@ -0,0 +1,380 @@
+//! Merging Plus/Minus bundles
+//!
+//! Context is often built up by several tools, each writing its own bundle.
+//! [`concat_bundles`] merges them into one: file blocks are copied verbatim,
+//! in order of first appearance, and a path seen again is resolved by the
+//! newest bundle (the later one in the list). A block with the same path and
+//! checksum as the one already kept is a plain duplicate and is dropped.
+//!
+//! Only file blocks survive; a glossary or frozen hash describes its own
+//! bundle and no longer holds for the merged one.
+//!
+//! A bundle can be given a prefix (`repo-name`), which goes in front of its
+//! paths in headers, footers, zoom targets and the manifest, so bundles of
+//! different repositories don't collide.
+//!
+//! The merged manifest reuses each kept file's entry from its bundle's
+//! sidecar manifest, when there is one with the same checksum, and otherwise
+//! derives the entry from the block itself.
+
+use crate::budgeting::TokenEstimator;
+use crate::core::{SidecarFileEntry, SidecarManifest};
+use crate::decode::{parse_footer, parse_header, FOOTER_MARK, HEADER_MARK};
+
+/// A bundle to merge: its text and its sidecar manifest, if any
+#[derive(Debug, Clone, Copy)]
+pub struct Bundle<'a> {
+    /// Plus/Minus output
+    pub text: &'a str,
+    /// Manifest written next to it with `--manifest`
+    pub manifest: Option<&'a SidecarManifest>,
+    /// Put its paths under this prefix (see [`crate::normalize_prefix`])
+    pub prefix: Option<&'a str>,
+}
+
+/// Result of [`concat_bundles`]
+#[derive(Debug, Clone)]
+pub struct Concatenated {
+    /// Merged Plus/Minus output
+    pub output: String,
+    /// Manifest describing the merged output
+    pub manifest: SidecarManifest,
+    /// Blocks dropped as exact duplicates (same path and checksum)
+    pub duplicates: usize,
+    /// Blocks replaced by a newer version of the same path
+    pub replaced: usize,
+}
+
+/// One file block, verbatim but for its prefix
+struct Block {
+    path: String,
+    /// Path in its own bundle (and manifest)
+    original_path: String,
+    md5: String,
+    truncated: bool,
+    text: String,
+    bundle: usize,
+}
+
+/// Merge bundles in order; later bundles win
+pub fn concat_bundles(bundles: &[Bundle]) -> Result<Concatenated, String> {
+    let mut kept: Vec<Block> = Vec::new();
+    let mut duplicates = 0;
+    let mut replaced = 0;
+
+    for (index, bundle) in bundles.iter().enumerate() {
+        let prefix = bundle
+            .prefix
+            .map(crate::normalize_prefix)
+            .transpose()
+            .map_err(|e| format!("bundle {}: {}", index + 1, e))?;
+        for block in blocks(bundle.text, index, prefix.as_deref())
+            .map_err(|e| format!("bundle {}: {}", index + 1, e))?
+        {
+            match kept.iter_mut().find(|k| k.path == block.path) {
+                Some(existing) if existing.md5 == block.md5 => duplicates += 1,
+                Some(existing) => {
+                    *existing = block;
+                    replaced += 1;
+                }
+                None => kept.push(block),
+            }
+        }
+    }
+
+    let output: String = kept.iter().map(|b| b.text.as_str()).collect();
+    let files = kept
+        .iter()
+        .map(|block| {
+            bundles[block.bundle]
+                .manifest
+                .and_then(|m| {
+                    m.files
+                        .iter()
+                        .find(|f| f.path == block.original_path && f.md5 == block.md5)
+                })
+                .map(|entry| SidecarFileEntry {
+                    path: block.path.clone(),
+                    ..entry.clone()
+                })
+                .unwrap_or_else(|| block_entry(block))
+        })
+        .collect();
+
+    // Run settings come from the newest bundle that recorded them
+    let newest = bundles.iter().rev().find_map(|b| b.manifest);
+    let manifest = SidecarManifest {
+        encoder_version: crate::VERSION.to_string(),
+        format: crate::OutputFormat::PlusMinus.name().to_string(),
+        generated_at: Some(chrono::Utc::now().to_rfc3339()),
+        lens: newest.and_then(|m| m.lens.clone()),
+        token_budget: newest.and_then(|m| m.token_budget),
+        truncate_lines: newest.map_or(0, |m| m.truncate_lines),
+        truncate_mode: newest.map_or_else(|| "simple".to_string(), |m| m.truncate_mode.clone()),
+        total_tokens: TokenEstimator::estimate_tokens(&output),
+        parts: Vec::new(),
+        files,
+    };
+
+    Ok(Concatenated {
+        output,
+        manifest,
+        duplicates,
+        replaced,
+    })
+}
+
+/// Split Plus/Minus output into its file blocks, under `prefix` if given
+fn blocks(text: &str, bundle: usize, prefix: Option<&str>) -> Result<Vec<Block>, String> {
+    let mut blocks = Vec::new();
+    let mut lines = text.split_inclusive('\n').enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let path = match parse_header(line.trim_end_matches(['\n', '\r'])) {
+            Some(p) => p.to_string(),
+            None => continue,
+        };
+
+        let mut block = line.to_string();
+        let mut footer = None;
+        for (_, body_line) in lines.by_ref() {
+            block.push_str(body_line);
+            if let Some((md5, truncated)) =
+                parse_footer(body_line.trim_end_matches(['\n', '\r']), &path)
+            {
+                footer = Some((md5.to_string(), truncated));
+                break;
+            }
+        }
+
+        let (md5, truncated) =
+            footer.ok_or_else(|| format!("line {}: no footer found for '{}'", index + 1, path))?;
+        if !block.ends_with('\n') {
+            block.push('\n');
+        }
+        let original_path = path.clone();
+        let path = match prefix {
+            Some(prefix) => {
+                let prefixed = format!("{}/{}", prefix, path);
+                block = prefix_block(&block, &path, &prefixed);
+                prefixed
+            }
+            None => path,
+        };
+        blocks.push(Block {
+            path,
+            original_path,
+            md5,
+            truncated,
+            text: block,
+            bundle,
+        });
+    }
+    Ok(blocks)
+}
+
+/// `block` of the file at `path`, framed and zoomed as `prefixed` instead
+fn prefix_block(block: &str, path: &str, prefixed: &str) -> String {
+    let header = format!("{} {}", HEADER_MARK, path);
+    let footer_start = format!("{} {} ", FOOTER_MARK, path);
+    let footer_end = format!(" {} {}", path, FOOTER_MARK);
+    let zoom = format!("--zoom file={} ", path);
+    let include = format!("--include \"{}\"", path);
+
+    let lines: Vec<&str> = block.split_inclusive('\n').collect();
+    let last = lines.len() - 1;
+    let mut result = String::with_capacity(block.len() + 4 * prefixed.len());
+    for (i, line) in lines.into_iter().enumerate() {
+        if i == 0 {
+            result.push_str(&format!("{} {}", HEADER_MARK, prefixed));
+            result.push_str(&line[header.len()..]);
+        } else if i == last {
+            let body = line.trim_end_matches(['\n', '\r']);
+            let middle = &body[footer_start.len()..body.len() - footer_end.len()];
+            result.push_str(&format!(
+                "{} {} {} {} {}{}",
+                FOOTER_MARK,
+                prefixed,
+                middle,
+                prefixed,
+                FOOTER_MARK,
+                &line[body.len()..]
+            ));
+        } else {
+            result.push_str(
+                &line
+                    .replace(&zoom, &format!("--zoom file={} ", prefixed))
+                    .replace(&include, &format!("--include \"{}\"", prefixed)),
+            );
+        }
+    }
+    result
+}
+
+/// Manifest entry for a block whose bundle had none
+fn block_entry(block: &Block) -> SidecarFileEntry {
+    let content = crate::decode::decode(&block.text)
+        .ok()
+        .and_then(|files| files.into_iter().next())
+        .map(|f| f.content)
+        .unwrap_or_default();
+    let final_lines = crate::count_lines_python_style(&content);
+
+    SidecarFileEntry {
+        path: block.path.clone(),
+        md5: block.md5.clone(),
+        size: if block.truncated {
+            0
+        } else {
+            content.len() as u64
+        },
+        tokens: TokenEstimator::estimate_tokens(&content),
+        truncation: if block.truncated { "truncated" } else { "none" }.to_string(),
+        original_lines: footer_original_lines(&block.text).unwrap_or(final_lines),
+        final_lines,
+        line_map: Vec::new(),
+    }
+}
+
+/// Original line count from a `[TRUNCATED:original→final]` footer
+fn footer_original_lines(block: &str) -> Option<usize> {
+    let footer = block.trim_end_matches(['\n', '\r']).rsplit('\n').next()?;
+    let marker = footer.split("[TRUNCATED:").nth(1)?;
+    marker.split('→').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(path: &str, content: &str) -> String {
+        let md5 = crate::calculate_md5(content);
+        format!(
+            "++++++++++ {} ++++++++++\n{}---------- {} {} {} ----------\n",
+            path, content, path, md5, path
+        )
+    }
+
+    #[test]
+    fn test_concat_bundles() {
+        let a = block("a.txt", "one\n") + &block("b.txt", "two\n");
+        let b = block("b.txt", "two\n") + &block("a.txt", "uno\n") + &block("c.txt", "three\n");
+        let merged = concat_bundles(&[
+            Bundle {
+                text: &a,
+                manifest: None,
+                prefix: None,
+            },
+            Bundle {
+                text: &b,
+                manifest: None,
+                prefix: None,
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(
+            merged.output,
+            block("a.txt", "uno\n") + &block("b.txt", "two\n") + &block("c.txt", "three\n")
+        );
+        assert_eq!(merged.duplicates, 1);
+        assert_eq!(merged.replaced, 1);
+
+        let paths: Vec<_> = merged
+            .manifest
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(paths, ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(merged.manifest.files[0].md5, crate::calculate_md5("uno\n"));
+        assert_eq!(merged.manifest.files[0].size, 4);
+        assert_eq!(merged.manifest.files[0].truncation, "none");
+    }
+
+    #[test]
+    fn test_concat_reuses_manifest_entries() {
+        let a = "++++++++++ big.rs [TRUNCATED: 40 lines] ++++++++++\nfn main() {}\n\
+                 ---------- big.rs [TRUNCATED:40→1] 0123456789abcdef0123456789abcdef big.rs ----------\n";
+        let b = block("small.rs", "x\n");
+        let mut manifest = concat_bundles(&[Bundle {
+            text: a,
+            manifest: None,
+            prefix: None,
+        }])
+        .unwrap()
+        .manifest;
+        assert_eq!(manifest.files[0].truncation, "truncated");
+        assert_eq!(manifest.files[0].original_lines, 40);
+
+        manifest.files[0].truncation = "smart".to_string();
+        manifest.lens = Some("architecture".to_string());
+        let merged = concat_bundles(&[
+            Bundle {
+                text: a,
+                manifest: Some(&manifest),
+                prefix: None,
+            },
+            Bundle {
+                text: &b,
+                manifest: None,
+                prefix: None,
+            },
+        ])
+        .unwrap();
+        assert_eq!(merged.manifest.files[0].truncation, "smart");
+        assert_eq!(merged.manifest.files[1].path, "small.rs");
+        assert_eq!(merged.manifest.lens.as_deref(), Some("architecture"));
+
+        let broken = "++++++++++ a.txt ++++++++++\nhi\n";
+        let err = concat_bundles(&[Bundle {
+            text: broken,
+            manifest: None,
+            prefix: None,
+        }])
+        .unwrap_err();
+        assert_eq!(err, "bundle 1: line 1: no footer found for 'a.txt'");
+    }
+
+    #[test]
+    fn test_concat_prefixes_paths() {
+        let truncated = "++++++++++ src/a.rs [TRUNCATED: 40 lines] ++++++++++\nfn a() {}\n\
+                         To get full content: --include \"src/a.rs\" --truncate 0\n\
+                         /* ZOOM_AFFORDANCE: pm_encoder --zoom file=src/a.rs */\n\
+                         ---------- src/a.rs [TRUNCATED:40→3] 0123456789abcdef0123456789abcdef src/a.rs ----------\n";
+        let other = block("src/a.rs", "fn b() {}\n");
+        let merged = concat_bundles(&[
+            Bundle {
+                text: truncated,
+                manifest: None,
+                prefix: Some("api/"),
+            },
+            Bundle {
+                text: &other,
+                manifest: None,
+                prefix: Some("web"),
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(merged.replaced, 0);
+        assert!(merged.output.starts_with(
+            "++++++++++ api/src/a.rs [TRUNCATED: 40 lines] ++++++++++\nfn a() {}\n\
+             To get full content: --include \"api/src/a.rs\" --truncate 0\n\
+             /* ZOOM_AFFORDANCE: pm_encoder --zoom file=api/src/a.rs */\n\
+             ---------- api/src/a.rs [TRUNCATED:40→3] 0123456789abcdef0123456789abcdef api/src/a.rs ----------\n"
+        ));
+        let decoded = crate::decode::decode(&merged.output).unwrap();
+        assert_eq!(decoded[1].path, "web/src/a.rs");
+        assert_eq!(decoded[1].status, crate::decode::ChecksumStatus::Verified);
+        assert_eq!(merged.manifest.files[1].path, "web/src/a.rs");
+
+        let err = concat_bundles(&[Bundle {
+            text: &other,
+            manifest: None,
+            prefix: Some("../up"),
+        }])
+        .unwrap_err();
+        assert!(err.starts_with("bundle 1: Invalid path prefix '../up'"));
+    }
+}
@@ -0,0 +1,440 @@
+//! Builder for [`EncoderConfig`]
+//!
+//! `EncoderConfig` is `#[non_exhaustive]`, so code outside this crate can't
+//! use struct literals that break whenever a field is added. Start from
+//! [`EncoderConfig::builder`] (or [`ConfigBuilder::from`] an existing config),
+//! set what differs from the defaults and call [`ConfigBuilder::build`]:
+//!
+//! ```
+//! use pm_encoder::{EncoderConfig, OutputFormat};
+//!
+//! let config = EncoderConfig::builder()
+//!     .ignore_pattern("*.lock")
+//!     .truncate(200, "smart")
+//!     .output_format(OutputFormat::Markdown)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(config.truncate_lines, 200);
+//! ```
+
+use crate::discovery::SinceFilter;
+use crate::formats::{self, Templates};
+use crate::transform::ContentTransform;
+use crate::{EncoderConfig, MetadataMode, OutputFormat, SkeletonMode, StreamOrder, SymlinkPolicy};
+
+/// Builds an [`EncoderConfig`], starting from the defaults
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: EncoderConfig,
+}
+
+impl From<EncoderConfig> for ConfigBuilder {
+    fn from(config: EncoderConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl EncoderConfig {
+    /// Start building a config from the defaults
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+impl ConfigBuilder {
+    /// Replace the ignore patterns (the defaults skip `.git`, `target`, ...)
+    pub fn ignore_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.ignore_patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add one ignore pattern to the current list
+    pub fn ignore_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.config.ignore_patterns.push(pattern.into());
+        self
+    }
+
+    /// Replace the include patterns
+    pub fn include_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.include_patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sort by `name`, `mtime`, `ctime`, `size`, `ext` or `utility`, `asc`
+    /// or `desc`
+    pub fn sort(mut self, by: &str, order: &str) -> Self {
+        self.config.sort_by = by.to_string();
+        self.config.sort_order = order.to_string();
+        self
+    }
+
+    /// Learned utility scores for the `utility` sort key
+    pub fn utility_store(mut self, store: crate::core::ContextStore) -> Self {
+        self.config.utility_store = Some(store);
+        self
+    }
+
+    /// Truncate files to `lines` lines (0 = never) using `simple`, `smart`
+    /// or `structure` mode
+    pub fn truncate(mut self, lines: usize, mode: &str) -> Self {
+        self.config.truncate_lines = lines;
+        self.config.truncate_mode = mode.to_string();
+        self
+    }
+
+    /// Never truncate files matching these patterns
+    pub fn truncate_exclude<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.truncate_exclude = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Keep lines matching any of these regexes whatever the truncation mode
+    pub fn truncate_keep_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.truncate_keep_patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Include summary markers in truncated output
+    pub fn truncate_summary(mut self, enabled: bool) -> Self {
+        self.config.truncate_summary = enabled;
+        self
+    }
+
+    /// Skip files larger than `bytes`
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.config.max_file_size = bytes;
+        self
+    }
+
+    /// Walk at most this many directories below each root
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.config.max_depth = Some(depth);
+        self
+    }
+
+    /// Take at most this many files, in path order
+    pub fn max_total_files(mut self, files: usize) -> Self {
+        self.config.max_total_files = Some(files);
+        self
+    }
+
+    /// Stop taking files once their sizes would add up to more than `bytes`
+    pub fn max_total_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_total_bytes = Some(bytes);
+        self
+    }
+
+    /// Cutoffs for the temporal survey's classifications
+    pub fn temporal_thresholds(mut self, thresholds: crate::core::TemporalThresholds) -> Self {
+        self.config.temporal_thresholds = thresholds;
+        self
+    }
+
+    /// Emit files as they are found, without a global sort
+    pub fn stream(mut self, enabled: bool) -> Self {
+        self.config.stream = enabled;
+        self
+    }
+
+    /// The order streaming emits files in (walk order by default)
+    pub fn stream_order(mut self, order: StreamOrder) -> Self {
+        self.config.stream_order = order;
+        self
+    }
+
+    /// Output format (Plus/Minus by default)
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        self.config.output_format = format;
+        self
+    }
+
+    /// Canonical, byte-identical output
+    pub fn frozen(mut self, enabled: bool) -> Self {
+        self.config.frozen = enabled;
+        self
+    }
+
+    /// End the output with a glossary of the project's own terms
+    pub fn glossary(mut self, enabled: bool) -> Self {
+        self.config.glossary = enabled;
+        self
+    }
+
+    /// Allow sensitive metadata in the output
+    pub fn allow_sensitive(mut self, enabled: bool) -> Self {
+        self.config.allow_sensitive = enabled;
+        self
+    }
+
+    /// Lens name recorded in the output metadata
+    pub fn active_lens(mut self, lens: impl Into<String>) -> Self {
+        self.config.active_lens = Some(lens.into());
+        self
+    }
+
+    /// Token budget recorded in the output metadata
+    pub fn token_budget(mut self, tokens: usize) -> Self {
+        self.config.token_budget = Some(tokens);
+        self
+    }
+
+    /// When to reduce files to signatures
+    pub fn skeleton_mode(mut self, mode: SkeletonMode) -> Self {
+        self.config.skeleton_mode = mode;
+        self
+    }
+
+    /// How much size and time metadata file headers show
+    pub fn metadata_mode(mut self, mode: MetadataMode) -> Self {
+        self.config.metadata_mode = mode;
+        self
+    }
+
+    /// Which symbolic links the walk follows
+    pub fn follow_symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.config.follow_symlinks = policy;
+        self
+    }
+
+    /// Restrict output to exactly these relative paths
+    pub fn only_paths<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.only_paths = Some(paths.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Remove comment lines before truncating
+    pub fn strip_comments(mut self, enabled: bool) -> Self {
+        self.config.strip_comments = enabled;
+        self
+    }
+
+    /// Escape control characters in file content
+    pub fn escape_control_chars(mut self, enabled: bool) -> Self {
+        self.config.escape_control_chars = enabled;
+        self
+    }
+
+    /// Prefix each content line with its line number
+    pub fn line_numbers(mut self, enabled: bool) -> Self {
+        self.config.line_numbers = enabled;
+        self
+    }
+
+    /// What to do with generated and vendored files
+    pub fn generated(mut self, policy: crate::discovery::GeneratedPolicy) -> Self {
+        self.config.generated = policy;
+        self
+    }
+
+    /// How ignore and include patterns are matched
+    pub fn pattern_syntax(mut self, syntax: crate::discovery::PatternSyntax) -> Self {
+        self.config.pattern_syntax = syntax;
+        self
+    }
+
+    /// Match ignore and include patterns ignoring case
+    pub fn case_insensitive_patterns(mut self, enabled: bool) -> Self {
+        self.config.case_insensitive_patterns = enabled;
+        self
+    }
+
+    /// Leave out files whose content matches any of these regexes
+    pub fn exclude_content_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.exclude_content_patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// What to do with binary files
+    pub fn binary(mut self, policy: crate::discovery::BinaryPolicy) -> Self {
+        self.config.binary = policy;
+        self
+    }
+
+    /// What to do with lockfiles
+    pub fn lockfiles(mut self, policy: crate::analyzers::lockfile::LockfilePolicy) -> Self {
+        self.config.lockfiles = policy;
+        self
+    }
+
+    /// Emit repeated contents once, and later copies as a stub
+    pub fn dedupe(mut self, enabled: bool) -> Self {
+        self.config.dedupe = enabled;
+        self
+    }
+
+    /// Add language, token and utility hints to file headers
+    pub fn header_hints(mut self, enabled: bool) -> Self {
+        self.config.header_hints = enabled;
+        self
+    }
+
+    /// Custom Plus/Minus delimiters
+    pub fn templates(mut self, templates: Templates) -> Self {
+        self.config.templates = Some(templates);
+        self
+    }
+
+    /// Honour `.gitignore`, `.ignore` and git exclude files
+    pub fn respect_gitignore(mut self, enabled: bool) -> Self {
+        self.config.respect_gitignore = enabled;
+        self
+    }
+
+    /// List files with `git ls-files` instead of walking
+    pub fn git_only(mut self, enabled: bool) -> Self {
+        self.config.git_only = enabled;
+        self
+    }
+
+    /// Keep only files changed since a revision or time window
+    pub fn changed_since(mut self, filter: SinceFilter) -> Self {
+        self.config.changed_since = Some(filter);
+        self
+    }
+
+    /// Walk these roots after the main one, into the same context
+    pub fn extra_roots<I, P>(mut self, roots: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<std::path::PathBuf>,
+    {
+        self.config.extra_roots = roots.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Report paths under their root's directory name
+    pub fn prefix_roots(mut self, enabled: bool) -> Self {
+        self.config.prefix_roots = enabled;
+        self
+    }
+
+    /// Report the paths of each root under one of `prefixes` (main root
+    /// first) instead of its directory name
+    pub fn path_prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.path_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add a content transform to every file's pipeline (see [`crate::transform`])
+    pub fn transform(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.config.transforms = self.config.transforms.with(transform);
+        self
+    }
+
+    /// Check the settings and return the config
+    ///
+    /// Rejects unknown sort keys, sort orders and truncation modes,
+    /// templates that don't parse, invalid keep patterns and path prefixes,
+    /// and extra roots without `prefix_roots` or a path prefix each.
+    pub fn build(self) -> Result<EncoderConfig, String> {
+        let config = self.config;
+        if !["name", "mtime", "ctime", "size", "ext", "utility"].contains(&config.sort_by.as_str())
+        {
+            return Err(format!(
+                "Unknown sort key '{}'. Valid options: name, mtime, ctime, size, ext, utility",
+                config.sort_by
+            ));
+        }
+        if !["asc", "desc"].contains(&config.sort_order.as_str()) {
+            return Err(format!(
+                "Unknown sort order '{}'. Valid options: asc, desc",
+                config.sort_order
+            ));
+        }
+        if ![
+            "simple",
+            "tail",
+            "smart",
+            "structure",
+            "outline",
+            "tokens",
+            "strip-comments",
+        ]
+        .contains(&config.truncate_mode.as_str())
+        {
+            return Err(format!(
+                "Unknown truncation mode '{}'. Valid options: simple, tail, smart, structure, outline, tokens, strip-comments",
+                config.truncate_mode
+            ));
+        }
+        crate::KeepPatterns::new(&config.truncate_keep_patterns)?;
+        if let Some(ref templates) = config.templates {
+            formats::TemplateOutput::new(templates)?;
+        }
+        for prefix in &config.path_prefixes {
+            crate::normalize_prefix(prefix)?;
+        }
+        if !config.path_prefixes.is_empty()
+            && config.path_prefixes.len() != config.extra_roots.len() + 1
+        {
+            return Err("Give one path prefix per project root".to_string());
+        }
+        if !config.extra_roots.is_empty() && !config.prefix_roots && config.path_prefixes.is_empty()
+        {
+            return Err("Several project roots need prefix_roots or path_prefixes".to_string());
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_keeps_defaults() {
+        let built = EncoderConfig::builder()
+            .ignore_pattern("*.lock")
+            .build()
+            .unwrap();
+        let defaults = EncoderConfig::default();
+        assert_eq!(built.ignore_patterns.last().unwrap(), "*.lock");
+        assert_eq!(
+            built.ignore_patterns.len(),
+            defaults.ignore_patterns.len() + 1
+        );
+        assert_eq!(built.max_file_size, defaults.max_file_size);
+        assert!(built.respect_gitignore);
+    }
+
+    #[test]
+    fn test_builder_rejects_bad_values() {
+        let err = EncoderConfig::builder()
+            .truncate(10, "fancy")
+            .build()
+            .unwrap_err();
+        assert!(err.contains("fancy"));
+        assert!(EncoderConfig::builder()
+            .sort("colour", "asc")
+            .build()
+            .is_err());
+    }
+}
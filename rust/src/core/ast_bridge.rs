@@ -13,8 +13,8 @@
 
 use std::path::Path;
 use voyager_ast::{
-    AdapterRegistry, AstError, Declaration, DeclarationKind, File as AstFile, LanguageId,
-    Visibility,
+    AdapterCapabilities, AdapterRegistry, AstError, Declaration, DeclarationKind, File as AstFile,
+    LanguageId, Visibility,
 };
 
 /// Bridge for AST-based code analysis
@@ -35,6 +35,11 @@ impl AstBridge {
         self.registry.supports(language)
     }
 
+    /// Capabilities of the adapter for a language, if one is registered
+    pub fn capabilities(&self, language: LanguageId) -> Option<AdapterCapabilities> {
+        self.registry.capabilities(language)
+    }
+
     /// Detect language from file extension
     pub fn detect_language(path: &Path) -> LanguageId {
         path.extension()
@@ -100,6 +105,10 @@ impl AstBridge {
             import_count: file.imports.len(),
             has_errors: file.has_errors(),
             stars: Vec::new(),
+            fidelity_notes: self
+                .capabilities(file.language)
+                .map(|c| c.fidelity_notes().into_iter().map(String::from).collect())
+                .unwrap_or_default(),
         };
 
         // Collect top-level stars
@@ -157,7 +166,7 @@ pub struct Star {
 impl Star {
     /// Create a star from an AST declaration
     pub fn from_declaration(decl: &Declaration, file_path: &str) -> Self {
-        let kind = match decl.kind {
+        let kind = match decl.kind.base() {
             DeclarationKind::Function => StarKind::Function,
             DeclarationKind::Method => StarKind::Method,
             DeclarationKind::Class => StarKind::Class,
@@ -172,7 +181,7 @@ impl Star {
             DeclarationKind::Namespace => StarKind::Namespace,
             DeclarationKind::Impl => StarKind::Implementation,
             DeclarationKind::Macro => StarKind::Macro,
-            DeclarationKind::Other => StarKind::Other,
+            DeclarationKind::Other | DeclarationKind::Custom(_) => StarKind::Other,
         };
 
         let is_public = matches!(decl.visibility, Visibility::Public);
@@ -279,6 +288,8 @@ pub struct FileSummary {
     pub import_count: usize,
     pub has_errors: bool,
     pub stars: Vec<StarSummary>,
+    /// What the adapter could not extract for this language
+    pub fidelity_notes: Vec<String>,
 }
 
 /// Summary of a single star
@@ -293,24 +304,7 @@ pub struct StarSummary {
 
 /// Helper to convert declaration kind to string
 fn declaration_kind_to_string(kind: DeclarationKind) -> String {
-    match kind {
-        DeclarationKind::Function => "function",
-        DeclarationKind::Method => "method",
-        DeclarationKind::Class => "class",
-        DeclarationKind::Struct => "struct",
-        DeclarationKind::Enum => "enum",
-        DeclarationKind::Interface => "interface",
-        DeclarationKind::Trait => "trait",
-        DeclarationKind::Type => "type",
-        DeclarationKind::Constant => "constant",
-        DeclarationKind::Variable => "variable",
-        DeclarationKind::Module => "module",
-        DeclarationKind::Namespace => "namespace",
-        DeclarationKind::Impl => "impl",
-        DeclarationKind::Macro => "macro",
-        DeclarationKind::Other => "other",
-    }
-    .to_string()
+    kind.as_str().to_string()
 }
 
 /// Helper to convert visibility to string
@@ -386,8 +380,8 @@ struct Point {
     #[test]
     fn test_unsupported_language() {
         let bridge = AstBridge::new();
-        // Go is not yet supported in voyager-ast (Phase 1B Core Fleet: Rust, Python, TypeScript, JavaScript)
-        let result = bridge.analyze_file("package main\nfunc main() {}", LanguageId::Go);
+        // Ruby has no voyager-ast adapter yet
+        let result = bridge.analyze_file("def main; end", LanguageId::Ruby);
         assert!(result.is_none());
     }
 
@@ -876,4 +870,21 @@ pub fn undocumented() {}
         assert!(undoc_star.is_some());
         assert!(!undoc_star.unwrap().has_doc);
     }
+
+    #[test]
+    fn test_file_summary_fidelity_notes() {
+        let bridge = AstBridge::new();
+
+        let rust = bridge
+            .analyze_file("pub fn a() {}\n", LanguageId::Rust)
+            .unwrap();
+        assert!(bridge.get_file_summary(&rust).fidelity_notes.is_empty());
+
+        let ts = bridge
+            .analyze_file("export function a() {}\n", LanguageId::TypeScript)
+            .unwrap();
+        let notes = bridge.get_file_summary(&ts).fidelity_notes;
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("doc comments"));
+    }
 }
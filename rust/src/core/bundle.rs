@@ -0,0 +1,231 @@
+//! Tarball bundle output
+//!
+//! Packs a serialization run into a single `.tar.gz` archive for
+//! reproducibility: the serialized context, every processed file under
+//! `files/`, and a `manifest.json` describing paths, checksums and
+//! truncation metadata together with the voyager-ast `IR_VERSION`.
+//!
+//! Archive entries carry a zeroed mtime so that identical inputs produce
+//! byte-identical bundles.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::core::error::{EncoderError, Result};
+
+/// Name of the manifest entry inside the archive
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Directory prefix for processed files inside the archive
+pub const FILES_PREFIX: &str = "files";
+
+/// Manifest describing the contents of a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// voyager-ast IR schema version
+    pub ir_version: String,
+    /// Encoder version that produced the bundle
+    pub encoder_version: String,
+    /// Output format of the serialized context
+    pub format: String,
+    /// Archive path of the serialized context
+    pub context_file: String,
+    /// Generation timestamp (omitted in frozen mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    /// Processed files, in output order
+    pub files: Vec<BundleFileEntry>,
+}
+
+/// Per-file manifest entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleFileEntry {
+    /// Project-relative path
+    pub path: String,
+    /// MD5 of the original content
+    pub md5: String,
+    /// Original size in bytes
+    pub size: u64,
+    /// Whether the stored content was truncated
+    pub truncated: bool,
+    /// Line count before truncation
+    pub original_lines: usize,
+    /// Line count after truncation
+    pub final_lines: usize,
+}
+
+/// An in-memory bundle ready to be written to disk
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    /// Bundle manifest
+    pub manifest: BundleManifest,
+    /// Processed file contents, parallel to `manifest.files`
+    contents: Vec<String>,
+    /// Serialized context
+    context: String,
+}
+
+impl Bundle {
+    /// Create an empty bundle for the given output format
+    ///
+    /// `frozen` omits the generation timestamp for deterministic archives.
+    pub fn new(format: &str, context_extension: &str, frozen: bool) -> Self {
+        let generated_at = if frozen {
+            None
+        } else {
+            Some(chrono::Utc::now().to_rfc3339())
+        };
+
+        Self {
+            manifest: BundleManifest {
+                ir_version: voyager_ast::IR_VERSION.to_string(),
+                encoder_version: crate::VERSION.to_string(),
+                format: format.to_string(),
+                context_file: format!("context.{}", context_extension),
+                generated_at,
+                files: Vec::new(),
+            },
+            contents: Vec::new(),
+            context: String::new(),
+        }
+    }
+
+    /// Add a processed file
+    pub fn add_file(&mut self, entry: BundleFileEntry, content: String) {
+        self.manifest.files.push(entry);
+        self.contents.push(content);
+    }
+
+    /// Set the serialized context
+    pub fn set_context(&mut self, context: String) {
+        self.context = context;
+    }
+
+    /// Write the bundle as a gzip-compressed tarball
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)?;
+        let encoder = GzEncoder::new(file, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        let manifest_json = serde_json::to_string_pretty(&self.manifest)?;
+        append_entry(&mut builder, MANIFEST_NAME, manifest_json.as_bytes())?;
+        append_entry(
+            &mut builder,
+            &self.manifest.context_file,
+            self.context.as_bytes(),
+        )?;
+
+        for (entry, content) in self.manifest.files.iter().zip(&self.contents) {
+            let archive_path = format!("{}/{}", FILES_PREFIX, entry.path);
+            append_entry(&mut builder, &archive_path, content.as_bytes())?;
+        }
+
+        builder.into_inner()?.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Read only the manifest from an existing bundle
+    pub fn read_manifest(path: &Path) -> Result<BundleManifest> {
+        let file = File::open(path)?;
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.path()?.to_str() == Some(MANIFEST_NAME) {
+                let mut json = String::new();
+                entry.read_to_string(&mut json)?;
+                return Ok(serde_json::from_str(&json)?);
+            }
+        }
+
+        Err(EncoderError::FileNotFound {
+            path: Path::new(MANIFEST_NAME).to_path_buf(),
+        })
+    }
+}
+
+/// Append a regular file entry with deterministic metadata
+fn append_entry<W: Write>(builder: &mut tar::Builder<W>, path: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_bundle(frozen: bool) -> Bundle {
+        let mut bundle = Bundle::new("plus_minus", "txt", frozen);
+        bundle.add_file(
+            BundleFileEntry {
+                path: "src/main.rs".to_string(),
+                md5: "abc".to_string(),
+                size: 12,
+                truncated: false,
+                original_lines: 1,
+                final_lines: 1,
+            },
+            "fn main() {}".to_string(),
+        );
+        bundle.set_context("++++++++++ src/main.rs ++++++++++\n".to_string());
+        bundle
+    }
+
+    #[test]
+    fn test_manifest_metadata() {
+        let bundle = sample_bundle(false);
+        assert_eq!(bundle.manifest.ir_version, voyager_ast::IR_VERSION);
+        assert_eq!(bundle.manifest.context_file, "context.txt");
+        assert!(bundle.manifest.generated_at.is_some());
+        assert!(sample_bundle(true).manifest.generated_at.is_none());
+    }
+
+    #[test]
+    fn test_write_and_read_manifest() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.tar.gz");
+        let bundle = sample_bundle(true);
+        bundle.write_to(&path).unwrap();
+
+        let manifest = Bundle::read_manifest(&path).unwrap();
+        assert_eq!(manifest, bundle.manifest);
+    }
+
+    #[test]
+    fn test_archive_contains_files_and_context() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("out.tar.gz");
+        sample_bundle(true).write_to(&path).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&path).unwrap()));
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+
+        assert!(names.contains(&"manifest.json".to_string()));
+        assert!(names.contains(&"context.txt".to_string()));
+        assert!(names.contains(&"files/src/main.rs".to_string()));
+    }
+
+    #[test]
+    fn test_frozen_bundles_are_deterministic() {
+        let temp = TempDir::new().unwrap();
+        let a = temp.path().join("a.tar.gz");
+        let b = temp.path().join("b.tar.gz");
+        sample_bundle(true).write_to(&a).unwrap();
+        sample_bundle(true).write_to(&b).unwrap();
+        assert_eq!(std::fs::read(a).unwrap(), std::fs::read(b).unwrap());
+    }
+}
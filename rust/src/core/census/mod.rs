@@ -38,10 +38,13 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use voyager_ast::ir::{CommentKind, Declaration, DeclarationKind, File, Span};
+use voyager_ast::ir::{CommentKind, Declaration, DeclarationKind, File, KindFilter, Span};
 
 use super::metrics::{MetricCollector, MetricRegistry, MetricResult};
 use super::spectrograph::{Hemisphere, STELLAR_LIBRARY};
+use crate::analyzers::guard::RegexBudget;
+
+pub mod sarif;
 
 // =============================================================================
 // Census Result Types
@@ -75,6 +78,10 @@ pub struct StarMetrics {
     pub types: usize,
     /// Constants count
     pub constants: usize,
+    /// Declarations of each custom kind (`react:component`), which also
+    /// count as their core kind
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, usize>,
 }
 
 /// Nebulae (Knowledge) metrics
@@ -163,6 +170,8 @@ pub struct CelestialCensus {
     volcanic_threshold: usize,
     /// Threshold for parameter-heavy functions (default: 5)
     param_threshold: usize,
+    /// Declarations counted as stars and documented stars
+    kinds: KindFilter,
 }
 
 impl Default for CelestialCensus {
@@ -177,6 +186,7 @@ impl CelestialCensus {
         Self {
             volcanic_threshold: 4,
             param_threshold: 5,
+            kinds: KindFilter::default(),
         }
     }
 
@@ -185,9 +195,16 @@ impl CelestialCensus {
         Self {
             volcanic_threshold,
             param_threshold,
+            kinds: KindFilter::default(),
         }
     }
 
+    /// Count only the declarations `kinds` selects
+    pub fn with_kinds(mut self, kinds: KindFilter) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
     /// Analyze a file and produce census metrics
     pub fn analyze(&self, file: &File) -> CensusMetrics {
         let total_lines = self.count_lines(file);
@@ -254,32 +271,41 @@ impl CelestialCensus {
 
     fn count_stars_recursive(&self, decls: &[Declaration], metrics: &mut StarMetrics) {
         for decl in decls {
-            match decl.kind {
-                DeclarationKind::Function => {
-                    metrics.functions += 1;
-                    metrics.count += 1;
-                }
-                DeclarationKind::Method => {
-                    metrics.methods += 1;
-                    metrics.count += 1;
-                }
-                DeclarationKind::Class
-                | DeclarationKind::Struct
-                | DeclarationKind::Interface
-                | DeclarationKind::Trait
-                | DeclarationKind::Enum => {
-                    metrics.types += 1;
-                }
-                DeclarationKind::Constant => {
-                    metrics.constants += 1;
-                }
-                _ => {}
+            if self.kinds.matches(decl.kind) {
+                self.count_star(decl.kind, metrics);
             }
             // Recurse into nested declarations
             self.count_stars_recursive(&decl.children, metrics);
         }
     }
 
+    fn count_star(&self, kind: DeclarationKind, metrics: &mut StarMetrics) {
+        if let DeclarationKind::Custom(custom) = kind {
+            *metrics.custom.entry(custom.name().to_string()).or_default() += 1;
+        }
+        match kind.base() {
+            DeclarationKind::Function => {
+                metrics.functions += 1;
+                metrics.count += 1;
+            }
+            DeclarationKind::Method => {
+                metrics.methods += 1;
+                metrics.count += 1;
+            }
+            DeclarationKind::Class
+            | DeclarationKind::Struct
+            | DeclarationKind::Interface
+            | DeclarationKind::Trait
+            | DeclarationKind::Enum => {
+                metrics.types += 1;
+            }
+            DeclarationKind::Constant => {
+                metrics.constants += 1;
+            }
+            _ => {}
+        }
+    }
+
     /// Count nebulae (documentation/comments)
     fn count_nebulae(&self, file: &File) -> NebulaeMetrics {
         let mut metrics = NebulaeMetrics::default();
@@ -302,10 +328,12 @@ impl CelestialCensus {
     fn count_nebulae_recursive(&self, decls: &[Declaration], metrics: &mut NebulaeMetrics) {
         for decl in decls {
             // Count this as a star if it's a function/method
-            if matches!(
-                decl.kind,
-                DeclarationKind::Function | DeclarationKind::Method
-            ) {
+            if self.kinds.matches(decl.kind)
+                && matches!(
+                    decl.kind.base(),
+                    DeclarationKind::Function | DeclarationKind::Method
+                )
+            {
                 metrics.total_stars += 1;
                 if decl.doc_comment.is_some() {
                     metrics.documented_stars += 1;
@@ -733,6 +761,9 @@ impl GalaxyCensus {
         self.totals.stars.methods += metrics.stars.methods;
         self.totals.stars.types += metrics.stars.types;
         self.totals.stars.constants += metrics.stars.constants;
+        for (kind, count) in &metrics.stars.custom {
+            *self.totals.stars.custom.entry(kind.clone()).or_default() += count;
+        }
 
         self.totals.nebulae.doc_lines += metrics.nebulae.doc_lines;
         self.totals.nebulae.comment_lines += metrics.nebulae.comment_lines;
@@ -785,6 +816,9 @@ impl ConstellationCensus {
         self.totals.stars.methods += metrics.stars.methods;
         self.totals.stars.types += metrics.stars.types;
         self.totals.stars.constants += metrics.stars.constants;
+        for (kind, count) in &metrics.stars.custom {
+            *self.totals.stars.custom.entry(kind.clone()).or_default() += count;
+        }
 
         self.totals.nebulae.doc_lines += metrics.nebulae.doc_lines;
         self.totals.nebulae.comment_lines += metrics.nebulae.comment_lines;
@@ -854,7 +888,20 @@ impl PatternFallbackAnalyzer {
     ///
     /// Returns CensusMetrics with star counts based on regex patterns.
     /// This is used for languages without Tree-sitter support.
+    ///
+    /// Patterns are matched line by line within a [`RegexBudget`]: overlong
+    /// lines are skipped, and a source that runs out of budget is reported
+    /// with line counts only.
     pub fn analyze_source(&self, language: &str, source: &str) -> CensusMetrics {
+        self.analyze_source_within(language, source, &RegexBudget::new())
+    }
+
+    fn analyze_source_within(
+        &self,
+        language: &str,
+        source: &str,
+        budget: &RegexBudget,
+    ) -> CensusMetrics {
         let mut metrics = CensusMetrics::default();
 
         // Get the spectral signature for this language
@@ -867,45 +914,68 @@ impl PatternFallbackAnalyzer {
         metrics.total_lines = source.lines().count();
 
         // Count stars using the star pattern
+        let mut exceeded = false;
         if let Ok(star_regex) = regex::Regex::new(signature.star_pattern) {
-            for cap in star_regex.captures_iter(source) {
-                metrics.stars.count += 1;
-                // Try to classify the star based on capture groups
-                // For most patterns, group 1 is the name
-                if cap
-                    .get(0)
-                    .map(|m| m.as_str().contains("class"))
-                    .unwrap_or(false)
-                    || cap
-                        .get(0)
-                        .map(|m| m.as_str().contains("struct"))
-                        .unwrap_or(false)
-                    || cap
-                        .get(0)
-                        .map(|m| m.as_str().contains("type"))
-                        .unwrap_or(false)
-                    || cap
+            for line in source.lines().filter(|line| budget.admits(line)) {
+                if budget.exhausted() {
+                    exceeded = true;
+                    break;
+                }
+                for cap in star_regex.captures_iter(line) {
+                    metrics.stars.count += 1;
+                    // Try to classify the star based on capture groups
+                    // For most patterns, group 1 is the name
+                    if cap
                         .get(0)
-                        .map(|m| m.as_str().contains("interface"))
+                        .map(|m| m.as_str().contains("class"))
                         .unwrap_or(false)
-                {
-                    metrics.stars.types += 1;
-                } else {
-                    metrics.stars.functions += 1;
+                        || cap
+                            .get(0)
+                            .map(|m| m.as_str().contains("struct"))
+                            .unwrap_or(false)
+                        || cap
+                            .get(0)
+                            .map(|m| m.as_str().contains("type"))
+                            .unwrap_or(false)
+                        || cap
+                            .get(0)
+                            .map(|m| m.as_str().contains("interface"))
+                            .unwrap_or(false)
+                    {
+                        metrics.stars.types += 1;
+                    } else {
+                        metrics.stars.functions += 1;
+                    }
+                    metrics.nebulae.total_stars += 1;
                 }
-                metrics.nebulae.total_stars += 1;
             }
         }
 
         // Count single-line comments (nebulae)
         if signature.comment_single != "$^" {
             if let Ok(comment_regex) = regex::Regex::new(signature.comment_single) {
-                for _cap in comment_regex.find_iter(source) {
-                    metrics.nebulae.comment_lines += 1;
+                for line in source.lines().filter(|line| budget.admits(line)) {
+                    if budget.exhausted() {
+                        exceeded = true;
+                        break;
+                    }
+                    metrics.nebulae.comment_lines += comment_regex.find_iter(line).count();
                 }
             }
         }
 
+        if exceeded {
+            eprintln!(
+                "Warning: {} pattern analysis exceeded its {} byte regex budget; counting lines only",
+                language,
+                budget.limit()
+            );
+            return CensusMetrics {
+                total_lines: metrics.total_lines,
+                ..Default::default()
+            };
+        }
+
         // Calculate derived metrics
         let total_lines_f = metrics.total_lines.max(1) as f64;
         metrics.derived.stellar_density = (metrics.stars.count as f64 / total_lines_f) * 1000.0;
@@ -1028,6 +1098,31 @@ mod tests {
         assert_eq!(metrics.stars.methods, 1);
     }
 
+    #[test]
+    fn test_custom_kinds_and_filter() {
+        let handler =
+            voyager_ast::CustomKind::register("test-web:handler", DeclarationKind::Function)
+                .unwrap();
+        let mut file = make_test_file();
+        file.declarations[1].kind = DeclarationKind::Custom(handler);
+
+        let metrics = CelestialCensus::new().analyze(&file);
+        assert_eq!(metrics.stars.count, 3);
+        assert_eq!(metrics.stars.functions, 2);
+        assert_eq!(metrics.stars.custom.get("test-web:handler"), Some(&1));
+
+        let kinds = KindFilter::parse("test-web:*").unwrap();
+        let metrics = CelestialCensus::new().with_kinds(kinds).analyze(&file);
+        assert_eq!(metrics.stars.count, 1);
+        assert_eq!(metrics.nebulae.total_stars, 1);
+        assert_eq!(metrics.nebulae.documented_stars, 0);
+
+        let kinds = KindFilter::parse("method").unwrap();
+        let metrics = CelestialCensus::new().with_kinds(kinds).analyze(&file);
+        assert_eq!((metrics.stars.count, metrics.stars.methods), (1, 1));
+        assert!(metrics.stars.custom.is_empty());
+    }
+
     #[test]
     fn test_nebulae_count() {
         let file = make_test_file();
@@ -1323,4 +1418,23 @@ def hello():
             "Should count Python comments"
         );
     }
+
+    #[test]
+    fn test_fallback_regex_budget() {
+        use crate::analyzers::guard::MAX_MATCH_LINE_LEN;
+
+        let analyzer = PatternFallbackAnalyzer::new();
+        let long = format!("def {}(): pass", "x".repeat(MAX_MATCH_LINE_LEN));
+        let source = format!("def short(): pass\n{}\n", long);
+
+        // Overlong lines are not matched
+        let metrics = analyzer.analyze_source("python", &source);
+        assert_eq!(metrics.stars.count, 1);
+
+        // Out of budget: line counts only
+        let budget = RegexBudget::with_limit(0);
+        let metrics = analyzer.analyze_source_within("python", &source, &budget);
+        assert_eq!(metrics.total_lines, 2);
+        assert_eq!(metrics.stars.count, 0);
+    }
 }
@@ -0,0 +1,393 @@
+//! SARIF export for governance findings
+//!
+//! Maps Celestial Census findings onto SARIF 2.1.0 results so they can be
+//! uploaded to code scanning dashboards:
+//!
+//! - **Red Giants**: large files with high dark matter or low documentation
+//! - **Volcanic regions**: declarations nested deeper than the volcanic threshold
+//! - **Unknown nodes**: regions the AST layer could not parse
+//! - **Constellation health**: directories rated `HighDarkMatter` or `Critical`
+//!
+//! Per-file findings need the parsed AST, so files are fed to
+//! [`CensusSarifBuilder::add_file`] during the census walk; galaxy-level
+//! findings are added from the finalized [`GalaxyCensus`].
+
+use serde_json::{json, Map, Value};
+use voyager_ast::ir::{Declaration, File};
+
+use super::{DarkMatterMetrics, GalaxyCensus, HealthRating};
+use crate::core::sarif::{
+    SarifArtifactLocation, SarifDriver, SarifLocation, SarifLog, SarifMessage,
+    SarifPhysicalLocation, SarifRegion, SarifResult, SarifRule, SarifRuleConfiguration, SarifRun,
+    SarifTool,
+};
+
+/// SARIF schema URI
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule: large file with issues
+pub const RULE_RED_GIANT: &str = "VO001";
+/// Rule: excessive nesting depth
+pub const RULE_VOLCANIC_REGION: &str = "VO002";
+/// Rule: unparsed region
+pub const RULE_UNKNOWN_NODE: &str = "VO003";
+/// Rule: unhealthy constellation
+pub const RULE_CONSTELLATION_HEALTH: &str = "VO004";
+
+/// Map a health rating to a SARIF level (`None` for ratings that are not findings)
+pub fn health_level(rating: HealthRating) -> Option<&'static str> {
+    match rating {
+        HealthRating::Critical => Some("error"),
+        HealthRating::HighDarkMatter => Some("warning"),
+        HealthRating::Healthy | HealthRating::Stable => None,
+    }
+}
+
+/// Dark matter metrics as a SARIF property bag
+pub fn dark_matter_properties(metrics: &DarkMatterMetrics) -> Map<String, Value> {
+    let mut properties = Map::new();
+    properties.insert("unknownRegions".into(), json!(metrics.unknown_regions));
+    properties.insert("unknownBytes".into(), json!(metrics.unknown_bytes));
+    properties.insert("volcanicRegions".into(), json!(metrics.volcanic_regions));
+    properties.insert("maxNestingDepth".into(), json!(metrics.max_nesting_depth));
+    properties.insert("parameterHeavy".into(), json!(metrics.parameter_heavy));
+    properties
+}
+
+/// Accumulates census findings and renders them as a SARIF log
+pub struct CensusSarifBuilder {
+    volcanic_threshold: usize,
+    results: Vec<SarifResult>,
+}
+
+impl Default for CensusSarifBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CensusSarifBuilder {
+    /// Create a builder using the census default volcanic threshold (4)
+    pub fn new() -> Self {
+        Self {
+            volcanic_threshold: 4,
+            results: Vec::new(),
+        }
+    }
+
+    /// Use a custom volcanic threshold (match `CelestialCensus::with_thresholds`)
+    pub fn with_volcanic_threshold(mut self, threshold: usize) -> Self {
+        self.volcanic_threshold = threshold;
+        self
+    }
+
+    /// Record unknown nodes and volcanic regions of a parsed file
+    pub fn add_file(&mut self, path: &str, file: &File) {
+        for unknown in &file.unknown_regions {
+            let reason = unknown.reason.as_deref().unwrap_or("unparsed region");
+            self.results.push(result(
+                RULE_UNKNOWN_NODE,
+                "note",
+                format!(
+                    "Dark matter: {} ({} bytes the AST could not parse)",
+                    reason,
+                    unknown.span.len()
+                ),
+                path,
+                Some((unknown.span.start_line, unknown.span.end_line)),
+                None,
+            ));
+        }
+
+        for decl in &file.declarations {
+            self.add_volcanic(path, decl, 1);
+        }
+    }
+
+    fn add_volcanic(&mut self, path: &str, decl: &Declaration, depth: usize) {
+        if depth > self.volcanic_threshold {
+            self.results.push(result(
+                RULE_VOLCANIC_REGION,
+                "warning",
+                format!(
+                    "Volcanic region: '{}' is nested {} levels deep (threshold {})",
+                    decl.name, depth, self.volcanic_threshold
+                ),
+                path,
+                Some((decl.span.start_line, decl.span.end_line)),
+                None,
+            ));
+        }
+        for child in &decl.children {
+            self.add_volcanic(path, child, depth + 1);
+        }
+    }
+
+    /// Number of results recorded so far (excluding galaxy-level findings)
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether no per-file results have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Render all findings, adding Red Giants and constellation health from `galaxy`
+    pub fn build(&self, galaxy: &GalaxyCensus) -> SarifLog {
+        let mut results = self.results.clone();
+
+        for (path, constellation) in &galaxy.constellations {
+            let rating = constellation.rating;
+
+            for red_giant in &constellation.red_giants {
+                let level = if rating == Some(HealthRating::Critical) {
+                    "error"
+                } else {
+                    "warning"
+                };
+                results.push(result(
+                    RULE_RED_GIANT,
+                    level,
+                    format!(
+                        "Red Giant: large file in constellation '{}' with high dark matter or low documentation",
+                        path
+                    ),
+                    red_giant,
+                    None,
+                    None,
+                ));
+            }
+
+            if let Some((rating, level)) = rating.and_then(|r| health_level(r).map(|l| (r, l))) {
+                let dark = &constellation.totals.dark_matter;
+                let mut properties = dark_matter_properties(dark);
+                properties.insert(
+                    "healthScore".into(),
+                    json!(constellation.totals.derived.health_score),
+                );
+                properties.insert("rating".into(), json!(rating.description()));
+                results.push(result(
+                    RULE_CONSTELLATION_HEALTH,
+                    level,
+                    format!(
+                        "{}: {} unknown regions ({} bytes), {} volcanic regions, max nesting {}",
+                        rating.description(),
+                        dark.unknown_regions,
+                        dark.unknown_bytes,
+                        dark.volcanic_regions,
+                        dark.max_nesting_depth
+                    ),
+                    path,
+                    None,
+                    Some(properties),
+                ));
+            }
+        }
+
+        SarifLog {
+            schema: Some(SARIF_SCHEMA.to_string()),
+            version: "2.1.0".to_string(),
+            runs: vec![SarifRun {
+                tool: SarifTool {
+                    driver: SarifDriver {
+                        name: "voyager-observatory".to_string(),
+                        version: Some(crate::VERSION.to_string()),
+                        information_uri: Some(
+                            "https://github.com/alanbld/voyager-observatory".to_string(),
+                        ),
+                        rules: rules(),
+                    },
+                },
+                results,
+            }],
+        }
+    }
+}
+
+/// Render galaxy-level findings only (Red Giants and constellation health)
+pub fn galaxy_to_sarif(galaxy: &GalaxyCensus) -> SarifLog {
+    CensusSarifBuilder::new().build(galaxy)
+}
+
+/// Rule catalogue for census findings
+fn rules() -> Vec<SarifRule> {
+    let rule = |id: &str, name: &str, description: &str, level: &str| SarifRule {
+        id: id.to_string(),
+        name: Some(name.to_string()),
+        short_description: Some(SarifMessage {
+            text: description.to_string(),
+        }),
+        full_description: None,
+        help_uri: None,
+        default_configuration: Some(SarifRuleConfiguration {
+            level: Some(level.to_string()),
+        }),
+    };
+
+    vec![
+        rule(
+            RULE_RED_GIANT,
+            "RedGiant",
+            "Large file with high dark matter or low documentation",
+            "warning",
+        ),
+        rule(
+            RULE_VOLCANIC_REGION,
+            "VolcanicRegion",
+            "Declaration nested beyond the volcanic threshold",
+            "warning",
+        ),
+        rule(
+            RULE_UNKNOWN_NODE,
+            "UnknownNode",
+            "Region the AST layer could not parse",
+            "note",
+        ),
+        rule(
+            RULE_CONSTELLATION_HEALTH,
+            "ConstellationHealth",
+            "Directory rated High Dark Matter or Critical",
+            "warning",
+        ),
+    ]
+}
+
+fn result(
+    rule_id: &str,
+    level: &str,
+    message: String,
+    uri: &str,
+    lines: Option<(usize, usize)>,
+    properties: Option<Map<String, Value>>,
+) -> SarifResult {
+    let region = lines
+        .filter(|(start, _)| *start > 0)
+        .map(|(start, end)| SarifRegion {
+            start_line: Some(start),
+            end_line: Some(end.max(start)),
+        });
+
+    SarifResult {
+        rule_id: Some(rule_id.to_string()),
+        rule_index: None,
+        level: Some(level.to_string()),
+        message: SarifMessage { text: message },
+        locations: vec![SarifLocation {
+            physical_location: Some(SarifPhysicalLocation {
+                artifact_location: SarifArtifactLocation {
+                    uri: uri.to_string(),
+                },
+                region,
+            }),
+        }],
+        properties,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::census::CensusMetrics;
+    use voyager_ast::ir::{DeclarationKind, LanguageId, Span, UnknownNode};
+
+    fn nested_file(depth: usize) -> File {
+        let mut file = File::new("deep.rs".to_string(), LanguageId::Rust);
+        let mut decl = Declaration::new(
+            format!("level{}", depth),
+            DeclarationKind::Function,
+            Span::new(0, 10, depth, depth),
+        );
+        for level in (1..depth).rev() {
+            let mut parent = Declaration::new(
+                format!("level{}", level),
+                DeclarationKind::Function,
+                Span::new(0, 10, level, level),
+            );
+            parent.children.push(decl);
+            decl = parent;
+        }
+        file.declarations.push(decl);
+        file
+    }
+
+    #[test]
+    fn test_health_level_mapping() {
+        assert_eq!(health_level(HealthRating::Critical), Some("error"));
+        assert_eq!(health_level(HealthRating::HighDarkMatter), Some("warning"));
+        assert_eq!(health_level(HealthRating::Healthy), None);
+        assert_eq!(health_level(HealthRating::Stable), None);
+    }
+
+    #[test]
+    fn test_volcanic_and_unknown_results() {
+        let mut file = nested_file(6);
+        file.unknown_regions.push(UnknownNode {
+            span: Span::new(100, 140, 20, 22),
+            reason: Some("syntax error".to_string()),
+            raw_text: None,
+        });
+
+        let mut builder = CensusSarifBuilder::new();
+        builder.add_file("src/deep.rs", &file);
+        // Depths 5 and 6 exceed the default threshold of 4
+        assert_eq!(builder.len(), 3);
+
+        let log = builder.build(&GalaxyCensus::new(".".to_string()));
+        let results = &log.runs[0].results;
+        let unknown = results
+            .iter()
+            .find(|r| r.rule_id.as_deref() == Some(RULE_UNKNOWN_NODE))
+            .unwrap();
+        let region = unknown.locations[0]
+            .physical_location
+            .as_ref()
+            .unwrap()
+            .region
+            .as_ref()
+            .unwrap();
+        assert_eq!(region.start_line, Some(20));
+        assert_eq!(region.end_line, Some(22));
+    }
+
+    #[test]
+    fn test_red_giants_and_constellation_health() {
+        let mut galaxy = GalaxyCensus::new(".".to_string());
+        let mut metrics = CensusMetrics::default();
+        metrics.total_lines = 800;
+        metrics.dark_matter.unknown_regions = 4;
+        metrics.dark_matter.unknown_bytes = 5000;
+        galaxy.add_file("src/big.rs", metrics);
+        galaxy.finalize();
+
+        let log = galaxy_to_sarif(&galaxy);
+        let results = &log.runs[0].results;
+        assert!(results
+            .iter()
+            .any(|r| r.rule_id.as_deref() == Some(RULE_RED_GIANT)));
+
+        let health = results
+            .iter()
+            .find(|r| r.rule_id.as_deref() == Some(RULE_CONSTELLATION_HEALTH))
+            .unwrap();
+        assert!(matches!(health.level.as_deref(), Some("error" | "warning")));
+        assert_eq!(health.properties.as_ref().unwrap()["unknownRegions"], 4);
+    }
+
+    #[test]
+    fn test_sarif_round_trips_through_ingestion() {
+        let mut builder = CensusSarifBuilder::new();
+        builder.add_file("src/deep.rs", &nested_file(5));
+        let log = builder.build(&GalaxyCensus::new(".".to_string()));
+
+        let json = serde_json::to_string(&log).unwrap();
+        assert!(json.contains("\"$schema\""));
+        assert!(json.contains("\"version\":\"2.1.0\""));
+
+        let parsed = SarifLog::parse(&json).unwrap();
+        let findings = parsed.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule_id, RULE_VOLCANIC_REGION);
+        assert_eq!(findings[0].path, "src/deep.rs");
+    }
+}
@@ -0,0 +1,369 @@
+//! Hallucination guard: validate model-cited paths, symbols and lines
+//!
+//! A claims file is a JSON list of citations a model produced, each with any
+//! of a `path`, a `symbol` and a `line` (optionally `end_line`):
+//!
+//! ```json
+//! [
+//!   { "path": "src/lib.rs", "symbol": "parse_token_budget", "line": 120 },
+//!   { "symbol": "ContextEngine" }
+//! ]
+//! ```
+//!
+//! `{ "claims": [...] }` is accepted too. Each claim is checked against the
+//! project's files: the path must exist, the lines must be inside the file,
+//! and the symbol must be defined (in the cited file, if a path is given, and
+//! on the cited lines, if lines are given). Symbol definitions come from the
+//! same patterns [`SymbolResolver`] uses for `--zoom`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::core::error::{EncoderError, Result};
+use crate::core::search::SymbolResolver;
+
+/// A single citation produced by a model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Claim {
+    /// Cited file path, relative to the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Cited symbol name (`Type::method` and `module.func` use the last segment)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Cited line (1-based), or first line of a range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Last line of a cited range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ClaimsFile {
+    List(Vec<Claim>),
+    Wrapped { claims: Vec<Claim> },
+}
+
+/// Verdict for one claim
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimStatus {
+    /// Everything cited exists
+    Valid,
+    /// The symbol is not defined, but its name does occur in the file(s)
+    MentionedOnly,
+    /// The cited file does not exist in the project
+    MissingPath,
+    /// The symbol is not defined where cited (or anywhere, without a path)
+    MissingSymbol,
+    /// The cited lines are past the end of the file
+    LineOutOfRange,
+    /// The symbol exists, but not on the cited lines
+    SymbolNotAtLine,
+}
+
+impl ClaimStatus {
+    /// Whether this verdict means the citation is fabricated
+    pub fn is_fabricated(&self) -> bool {
+        !matches!(self, ClaimStatus::Valid | ClaimStatus::MentionedOnly)
+    }
+
+    /// Short label for reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimStatus::Valid => "valid",
+            ClaimStatus::MentionedOnly => "mentioned only",
+            ClaimStatus::MissingPath => "missing path",
+            ClaimStatus::MissingSymbol => "missing symbol",
+            ClaimStatus::LineOutOfRange => "line out of range",
+            ClaimStatus::SymbolNotAtLine => "symbol not at line",
+        }
+    }
+}
+
+/// Result of checking one claim
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClaimResult {
+    /// The claim as given
+    pub claim: Claim,
+    /// Verdict
+    pub status: ClaimStatus,
+    /// Human-readable explanation (e.g. where the symbol actually is)
+    pub detail: String,
+}
+
+/// Outcome of checking a claims file
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ClaimReport {
+    /// One result per claim, in input order
+    pub results: Vec<ClaimResult>,
+}
+
+impl ClaimReport {
+    /// Number of fabricated claims
+    pub fn fabricated(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| r.status.is_fabricated())
+            .count()
+    }
+}
+
+/// Parse a claims file (a list, or `{ "claims": [...] }`)
+pub fn parse_claims(json: &str) -> Result<Vec<Claim>> {
+    Ok(match serde_json::from_str(json)? {
+        ClaimsFile::List(claims) | ClaimsFile::Wrapped { claims } => claims,
+    })
+}
+
+/// Load claims from a file
+pub fn load_claims(path: &Path) -> Result<Vec<Claim>> {
+    if !path.exists() {
+        return Err(EncoderError::FileNotFound {
+            path: path.to_path_buf(),
+        });
+    }
+    parse_claims(&fs::read_to_string(path)?)
+}
+
+/// Check claims against the project's files
+///
+/// `files` are `(relative_path, content)` pairs.
+pub fn check_claims(files: &[(String, String)], claims: &[Claim]) -> ClaimReport {
+    let index: BTreeMap<String, Indexed> = files
+        .iter()
+        .map(|(path, content)| (normalize_path(path), Indexed { path, content }))
+        .collect();
+    let resolver = SymbolResolver::new();
+
+    let results = claims
+        .iter()
+        .map(|claim| {
+            let (status, detail) = check_claim(&index, &resolver, claim);
+            ClaimResult {
+                claim: claim.clone(),
+                status,
+                detail,
+            }
+        })
+        .collect();
+
+    ClaimReport { results }
+}
+
+struct Indexed<'a> {
+    path: &'a str,
+    content: &'a str,
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./").to_string()
+}
+
+/// Last segment of `Type::method` / `module.func`
+fn symbol_name(symbol: &str) -> &str {
+    let symbol = symbol.trim().trim_end_matches("()");
+    symbol.rsplit([':', '.']).next().unwrap_or(symbol)
+}
+
+fn mentions(content: &str, name: &str) -> bool {
+    content.match_indices(name).any(|(i, _)| {
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        let before = content[..i].chars().next_back();
+        let after = content[i + name.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+fn check_claim(
+    index: &BTreeMap<String, Indexed>,
+    resolver: &SymbolResolver,
+    claim: &Claim,
+) -> (ClaimStatus, String) {
+    let symbol = claim.symbol.as_deref().map(symbol_name);
+    let lines = claim
+        .line
+        .map(|start| (start, claim.end_line.unwrap_or(start)));
+
+    let Some(path) = claim.path.as_deref() else {
+        // Symbol only: it must be defined somewhere
+        let Some(name) = symbol else {
+            return (ClaimStatus::Valid, "nothing to check".to_string());
+        };
+        let defined = index
+            .values()
+            .flat_map(|f| resolver.find_definitions(f.path, f.content, name))
+            .next();
+        if let Some(loc) = defined {
+            return (
+                ClaimStatus::Valid,
+                format!("defined at {}:{}", loc.path, loc.start_line),
+            );
+        }
+        if index.values().any(|f| mentions(f.content, name)) {
+            return (
+                ClaimStatus::MentionedOnly,
+                format!("'{}' occurs but no definition was found", name),
+            );
+        }
+        return (
+            ClaimStatus::MissingSymbol,
+            format!("'{}' does not occur in the project", name),
+        );
+    };
+
+    let Some(file) = index.get(&normalize_path(path)) else {
+        return (ClaimStatus::MissingPath, format!("no file '{}'", path));
+    };
+
+    let line_count = file.content.lines().count();
+    if let Some((start, end)) = lines {
+        if start == 0 || end < start || end > line_count {
+            return (
+                ClaimStatus::LineOutOfRange,
+                format!("{} has {} lines", file.path, line_count),
+            );
+        }
+    }
+
+    let Some(name) = symbol else {
+        return (ClaimStatus::Valid, String::new());
+    };
+
+    let defined = resolver.find_definitions(file.path, file.content, name);
+    if defined.is_empty() {
+        if mentions(file.content, name) {
+            return (
+                ClaimStatus::MentionedOnly,
+                format!("'{}' occurs in {} but no definition was found", name, path),
+            );
+        }
+        let elsewhere = index
+            .values()
+            .flat_map(|f| resolver.find_definitions(f.path, f.content, name))
+            .next();
+        let detail = match elsewhere {
+            Some(loc) => format!("'{}' is defined at {}:{}", name, loc.path, loc.start_line),
+            None => format!("'{}' is not defined in {}", name, path),
+        };
+        return (ClaimStatus::MissingSymbol, detail);
+    }
+
+    if let Some((start, end)) = lines {
+        let overlaps = defined
+            .iter()
+            .any(|loc| start <= loc.end_line && end >= loc.start_line);
+        if !overlaps {
+            let actual: Vec<String> = defined
+                .iter()
+                .map(|loc| format!("{}-{}", loc.start_line, loc.end_line))
+                .collect();
+            return (
+                ClaimStatus::SymbolNotAtLine,
+                format!("'{}' is at lines {}", name, actual.join(", ")),
+            );
+        }
+    }
+
+    (
+        ClaimStatus::Valid,
+        format!("defined at {}:{}", file.path, defined[0].start_line),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, content: &str) -> (String, String) {
+        (path.to_string(), content.to_string())
+    }
+
+    fn files() -> Vec<(String, String)> {
+        vec![
+            entry(
+                "src/lib.rs",
+                "use std::fmt;\n\npub fn parse(input: &str) -> usize {\n    input.len()\n}\n\npub struct Config {\n    pub name: String,\n}\n",
+            ),
+            entry("app.py", "def main():\n    print(helper)\n"),
+        ]
+    }
+
+    fn check(claim: Claim) -> ClaimStatus {
+        check_claims(&files(), &[claim]).results[0].status
+    }
+
+    fn claim(path: Option<&str>, symbol: Option<&str>, line: Option<usize>) -> Claim {
+        Claim {
+            path: path.map(String::from),
+            symbol: symbol.map(String::from),
+            line,
+            end_line: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_list_and_wrapped() {
+        let list = parse_claims(r#"[{"path": "a.rs", "line": 3}]"#).unwrap();
+        assert_eq!(list[0].line, Some(3));
+        let wrapped = parse_claims(r#"{"claims": [{"symbol": "main"}]}"#).unwrap();
+        assert_eq!(wrapped[0].symbol.as_deref(), Some("main"));
+        assert!(parse_claims("{}").is_err());
+    }
+
+    #[test]
+    fn test_valid_claims() {
+        assert_eq!(
+            check(claim(Some("src/lib.rs"), Some("parse"), Some(3))),
+            ClaimStatus::Valid
+        );
+        assert_eq!(
+            check(claim(Some("./src/lib.rs"), Some("Config"), None)),
+            ClaimStatus::Valid
+        );
+        assert_eq!(
+            check(claim(None, Some("crate::Config"), None)),
+            ClaimStatus::Valid
+        );
+        assert_eq!(
+            check(claim(Some("app.py"), None, Some(2))),
+            ClaimStatus::Valid
+        );
+    }
+
+    #[test]
+    fn test_fabricated_claims() {
+        assert_eq!(
+            check(claim(Some("src/parser.rs"), None, None)),
+            ClaimStatus::MissingPath
+        );
+        assert_eq!(
+            check(claim(Some("app.py"), None, Some(40))),
+            ClaimStatus::LineOutOfRange
+        );
+        assert_eq!(
+            check(claim(None, Some("tokenize"), None)),
+            ClaimStatus::MissingSymbol
+        );
+        assert_eq!(
+            check(claim(Some("app.py"), Some("parse"), None)),
+            ClaimStatus::MissingSymbol
+        );
+        assert_eq!(
+            check(claim(Some("src/lib.rs"), Some("parse"), Some(7))),
+            ClaimStatus::SymbolNotAtLine
+        );
+    }
+
+    #[test]
+    fn test_mentioned_only_is_not_fabricated() {
+        let report = check_claims(&files(), &[claim(Some("app.py"), Some("helper"), None)]);
+        assert_eq!(report.results[0].status, ClaimStatus::MentionedOnly);
+        assert_eq!(report.fabricated(), 0);
+    }
+}
@@ -0,0 +1,208 @@
+//! Compressed output files
+//!
+//! An [`OutputSink`] is the `-o` file behind a gzip or zstd encoder, so
+//! `--compress` needs no separate `gzip` pass. `--stream` writes each file
+//! into the sink as it is walked; batch output goes through
+//! [`write_output`] once serialized. zstd support sits behind the default
+//! `zstd` feature.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Compression applied to the output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputCompression {
+    /// gzip (`.gz`)
+    Gzip,
+    /// Zstandard (`.zst`)
+    Zstd,
+}
+
+impl OutputCompression {
+    /// Parse an algorithm name
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "gzip" | "gz" => Ok(Self::Gzip),
+            "zstd" | "zst" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "Unknown compression: {} (expected gzip or zstd)",
+                s
+            )),
+        }
+    }
+
+    /// Algorithm name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Conventional file extension (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+}
+
+/// A file being written, through an encoder when compressed
+///
+/// Call [`OutputSink::finish`] once everything is written: it writes the
+/// compressed stream's trailer, without which the file can't be decoded.
+pub struct OutputSink {
+    inner: Sink,
+}
+
+enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+
+impl OutputSink {
+    /// Create (or truncate) `path`, compressing what is written if requested
+    pub fn create(path: &Path, compression: Option<OutputCompression>) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let inner = match compression {
+            None => Sink::Plain(file),
+            Some(OutputCompression::Gzip) => Sink::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            Some(OutputCompression::Zstd) => zstd_sink(file)?,
+        };
+        Ok(Self { inner })
+    }
+
+    /// End the compressed stream and flush the file
+    pub fn finish(self) -> io::Result<()> {
+        match self.inner {
+            Sink::Plain(mut file) => file.flush(),
+            Sink::Gzip(encoder) => encoder.finish()?.flush(),
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for OutputSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Sink::Plain(file) => file.write(buf),
+            Sink::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Sink::Plain(file) => file.flush(),
+            Sink::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_sink(file: BufWriter<File>) -> io::Result<Sink> {
+    Ok(Sink::Zstd(zstd::stream::write::Encoder::new(file, 0)?))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_sink(_file: BufWriter<File>) -> io::Result<Sink> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "zstd support not compiled in (enable the `zstd` feature)",
+    ))
+}
+
+/// Write `content` to `path`, compressing it on the way if requested
+pub fn write_output(
+    path: &Path,
+    content: &str,
+    compression: Option<OutputCompression>,
+) -> io::Result<()> {
+    let mut sink = OutputSink::create(path, compression)?;
+    sink.write_all(content.as_bytes())?;
+    sink.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    const CONTENT: &str = "++++++++++ src/main.rs ++++++++++\nfn main() {}\n";
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            OutputCompression::parse("gzip").unwrap(),
+            OutputCompression::Gzip
+        );
+        assert_eq!(
+            OutputCompression::parse("ZST").unwrap(),
+            OutputCompression::Zstd
+        );
+        assert!(OutputCompression::parse("brotli").is_err());
+        assert_eq!(OutputCompression::Zstd.extension(), "zst");
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("context.txt");
+        write_output(&path, CONTENT, None).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn test_gzip_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("context.txt.gz");
+        write_output(&path, CONTENT, Some(OutputCompression::Gzip)).unwrap();
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, CONTENT);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("context.txt.zst");
+        write_output(&path, CONTENT, Some(OutputCompression::Zstd)).unwrap();
+
+        let decoded = zstd::decode_all(File::open(&path).unwrap()).unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn test_sink_written_in_pieces() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("context.txt.gz");
+        let mut sink = OutputSink::create(&path, Some(OutputCompression::Gzip)).unwrap();
+        for line in CONTENT.split_inclusive('\n') {
+            sink.write_all(line.as_bytes()).unwrap();
+            sink.flush().unwrap();
+        }
+        sink.finish().unwrap();
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, CONTENT);
+    }
+}
@@ -3,17 +3,21 @@
 //! The ContextEngine is the primary interface for serializing project contexts.
 //! It coordinates file walking, analysis, truncation, and serialization.
 
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
 use crate::core::error::{EncoderError, Result};
 use crate::core::manifest::{ProjectManifest, ProjectType};
 use crate::core::models::{
     CompressionLevel, EncoderConfig, FileEntry, OutputFormat, ProcessedFile,
 };
-use crate::core::serialization::{get_serializer, Serializer};
 use crate::core::skeleton::{AdaptiveAllocator, FileAllocation, Language, Skeletonizer};
 use crate::core::walker::{DefaultWalker, FileWalker, WalkConfig};
 #[cfg(test)]
 use crate::core::zoom::ZoomDepth;
-use crate::core::zoom::{ZoomAction, ZoomConfig, ZoomTarget};
+use crate::core::zoom::{ZoomConfig, ZoomTarget};
+use crate::formats::{formatter_for, BundleFormatter, OutputFile};
 
 /// File tier for prioritized budgeting
 /// Core domain files get budget first, then config, tests last
@@ -160,17 +164,13 @@ impl FileTier {
         // Project-type specific logic
         if let Some(m) = manifest {
             match m.project_type {
-                ProjectType::Rust => {
-                    // Rust: src/ is core, also lib.rs, main.rs at root
-                    if path == "lib.rs" || path == "main.rs" {
-                        return true;
-                    }
+                // Rust: src/ is core, also lib.rs, main.rs at root
+                ProjectType::Rust if path == "lib.rs" || path == "main.rs" => {
+                    return true;
                 }
-                ProjectType::Python => {
-                    // Python: any .py file not in tests
-                    if path.ends_with(".py") && !Self::is_test_file(path) {
-                        return true;
-                    }
+                // Python: any .py file not in tests
+                ProjectType::Python if path.ends_with(".py") && !Self::is_test_file(path) => {
+                    return true;
                 }
                 // Node: src/, lib/, index.js, index.ts
                 ProjectType::Node if path == "index.js" || path == "index.ts" => {
@@ -185,13 +185,16 @@ impl FileTier {
 }
 
 /// The main context serialization engine
+///
+/// Files are prepared and written by the [`BundleFormatter`] for the full
+/// [`crate::EncoderConfig`], so output matches a CLI run of the same config.
 pub struct ContextEngine {
     /// Engine configuration
     config: EncoderConfig,
+    /// The full config files are formatted with
+    output: crate::EncoderConfig,
     /// File walker implementation
     walker: Box<dyn FileWalker>,
-    /// Output serializer
-    serializer: Box<dyn Serializer>,
 }
 
 impl ContextEngine {
@@ -202,11 +205,20 @@ impl ContextEngine {
 
     /// Create a new ContextEngine with custom configuration
     pub fn with_config(config: EncoderConfig) -> Self {
-        let serializer = get_serializer(config.output_format);
         Self {
+            output: (&config).into(),
             config,
             walker: Box::new(DefaultWalker::new()),
-            serializer,
+        }
+    }
+
+    /// Create a ContextEngine from a full config, formatting files with
+    /// its templates, line numbers, header hints and transforms
+    pub fn with_encoder_config(config: crate::EncoderConfig) -> Self {
+        Self {
+            config: (&config).into(),
+            output: config,
+            walker: Box::new(DefaultWalker::new()),
         }
     }
 
@@ -219,7 +231,7 @@ impl ContextEngine {
     /// Builder: set output format
     pub fn with_format(mut self, format: OutputFormat) -> Self {
         self.config.output_format = format;
-        self.serializer = get_serializer(format);
+        self.output.output_format = format.into();
         self
     }
 
@@ -242,8 +254,9 @@ impl ContextEngine {
         // Sort entries
         let sorted = self.sort_entries(entries);
 
-        // Process files (language detection, truncation)
-        let processed = self.process_files(&sorted);
+        // Prepare files (truncation and the run's transforms)
+        let mut formatter = self.formatter()?;
+        let (prepared, processed) = self.process_files(formatter.as_ref(), &sorted);
 
         // Apply token budget if set
         let final_files = if let Some(budget) = self.config.token_budget {
@@ -252,12 +265,7 @@ impl ContextEngine {
             processed
         };
 
-        // Serialize based on format
-        if self.config.output_format == OutputFormat::ClaudeXml {
-            self.serialize_claude_xml(&final_files)
-        } else {
-            Ok(self.serializer.serialize_files(&final_files))
-        }
+        self.serialize_processed(formatter.as_mut(), &sorted, prepared, &final_files)
     }
 
     /// Serialize a zoom target
@@ -290,8 +298,65 @@ impl ContextEngine {
         }
 
         // Process and serialize
-        let processed = self.process_files(&filtered);
-        Ok(self.serializer.serialize_files(&processed))
+        let mut formatter = self.formatter()?;
+        let (prepared, processed) = self.process_files(formatter.as_ref(), &filtered);
+        self.serialize_processed(formatter.as_mut(), &filtered, prepared, &processed)
+    }
+
+    /// The formatter for the full config (see [`crate::formats::formatter_for`])
+    fn formatter(&self) -> Result<Box<dyn BundleFormatter + '_>> {
+        formatter_for(&self.output).map_err(EncoderError::invalid_config)
+    }
+
+    /// Write the files left after budgeting with `formatter`
+    ///
+    /// Each file is written as prepared, except that skeletons replace the
+    /// content of files the budget compressed. The glossary and frozen
+    /// context hash follow, as in a CLI run.
+    fn serialize_processed(
+        &self,
+        formatter: &mut dyn BundleFormatter,
+        entries: &[FileEntry],
+        prepared: Vec<OutputFile>,
+        files: &[ProcessedFile],
+    ) -> Result<String> {
+        let mut prepared: HashMap<String, OutputFile> = prepared
+            .into_iter()
+            .map(|file| (file.path.clone(), file))
+            .collect();
+        let files: Vec<OutputFile> = files
+            .iter()
+            .filter_map(|processed| {
+                let mut file = prepared.remove(&processed.path)?;
+                if processed.is_skeleton() {
+                    file.final_lines = processed.content.lines().count();
+                    file.content = processed.content.clone();
+                    file.was_truncated = true;
+                }
+                Some(file)
+            })
+            .collect();
+
+        let mut output = Vec::new();
+        formatter.begin(&mut output, Some(&files))?;
+        for file in &files {
+            formatter.file(&mut output, file)?;
+        }
+        formatter.end(&mut output, Some(&files))?;
+        let mut output = String::from_utf8(output)?;
+
+        if self.output.glossary {
+            let entries: Vec<crate::FileEntry> = entries.iter().map(Into::into).collect();
+            let terms = crate::glossary::extract_glossary(&entries, crate::glossary::MAX_TERMS);
+            output.push_str(&crate::glossary::render_glossary(
+                &terms,
+                self.output.output_format,
+            ));
+        }
+        if self.output.frozen {
+            crate::append_context_hash(&mut output, self.output.output_format);
+        }
+        Ok(output)
     }
 
     /// Sort entries based on configuration
@@ -320,6 +385,26 @@ impl ContextEngine {
                     entries.sort_by(|a, b| a.ctime.cmp(&b.ctime));
                 }
             }
+            "size" => {
+                if is_desc {
+                    entries.sort_by(|a, b| b.size.cmp(&a.size));
+                } else {
+                    entries.sort_by(|a, b| a.size.cmp(&b.size));
+                }
+            }
+            "ext" => {
+                let ext = |e: &FileEntry| {
+                    std::path::Path::new(&e.path)
+                        .extension()
+                        .map(|x| x.to_string_lossy().to_lowercase())
+                        .unwrap_or_default()
+                };
+                if is_desc {
+                    entries.sort_by_cached_key(|e| std::cmp::Reverse(ext(e)));
+                } else {
+                    entries.sort_by_cached_key(ext);
+                }
+            }
             _ => {
                 entries.sort_by(|a, b| a.path.cmp(&b.path));
             }
@@ -328,47 +413,36 @@ impl ContextEngine {
         entries
     }
 
-    /// Process files (detect language, apply truncation)
-    fn process_files(&self, entries: &[FileEntry]) -> Vec<ProcessedFile> {
-        use crate::core::serialization::truncation_marker;
-
-        entries
+    /// Prepare each entry with `formatter`, and the processed file the
+    /// budget sees for it (the prepared content and its token estimate)
+    fn process_files(
+        &self,
+        formatter: &dyn BundleFormatter,
+        entries: &[FileEntry],
+    ) -> (Vec<OutputFile>, Vec<ProcessedFile>) {
+        let prepared: Vec<OutputFile> = entries
+            .par_iter()
+            .map(|entry| formatter.prepare(&entry.into()))
+            .collect();
+        let processed = entries
             .iter()
-            .map(|entry| {
-                let language = detect_language(&entry.path);
+            .zip(&prepared)
+            .map(|(entry, file)| {
                 let priority = 50; // TODO: Get from lens manager
 
-                let mut processed = ProcessedFile::from_entry(entry, &language, priority);
-
-                // Apply truncation if configured
-                if self.config.truncate_lines > 0 {
-                    let lines: Vec<&str> = entry.content.lines().collect();
-                    if lines.len() > self.config.truncate_lines {
-                        let kept_lines = self.config.truncate_lines;
-                        let original_lines = lines.len();
-                        let original_tokens = entry.token_estimate();
-
-                        // Create zoom action for this truncated file
-                        let zoom_action = ZoomAction::for_file(&entry.path, original_tokens);
-
-                        // Build truncated content with zoom affordance
-                        let mut truncated: String = lines[..kept_lines].join("\n");
-                        if self.config.truncate_summary {
-                            truncated.push('\n');
-                            truncated.push_str(&truncation_marker(
-                                original_lines,
-                                kept_lines,
-                                Some(&zoom_action),
-                            ));
-                        }
-
-                        processed = processed.with_truncation(truncated, original_tokens);
+                let processed = ProcessedFile::from_walked_entry(entry, priority);
+                if file.was_truncated {
+                    processed.with_truncation(file.content.clone(), entry.token_estimate())
+                } else {
+                    ProcessedFile {
+                        tokens: file.content.len() / 4,
+                        content: file.content.clone(),
+                        ..processed
                     }
                 }
-
-                processed
             })
-            .collect()
+            .collect();
+        (prepared, processed)
     }
 
     /// Apply token budget with tiered allocation strategy
@@ -557,83 +631,6 @@ impl ContextEngine {
         stats
     }
 
-    /// Serialize to Claude-XML format
-    fn serialize_claude_xml(&self, files: &[ProcessedFile]) -> Result<String> {
-        use crate::formats::{AttentionEntry, XmlConfig, XmlWriter};
-
-        let mut buffer = Vec::new();
-
-        let xml_config = XmlConfig {
-            package: "pm_encoder".to_string(),
-            version: crate::VERSION.to_string(),
-            lens: self.config.active_lens.clone(),
-            token_budget: self.config.token_budget,
-            utilized_tokens: Some(files.iter().map(|f| f.tokens).sum()),
-            frozen: self.config.frozen,
-            allow_sensitive: self.config.allow_sensitive,
-            snapshot_id: if self.config.frozen {
-                Some("FROZEN_SNAPSHOT".to_string())
-            } else {
-                None
-            },
-        };
-
-        let mut writer = XmlWriter::new(&mut buffer, xml_config);
-
-        // Build attention entries
-        let attention_entries: Vec<AttentionEntry> = files
-            .iter()
-            .map(|f| AttentionEntry {
-                path: f.path.clone(),
-                priority: f.priority,
-                tokens: f.tokens,
-                truncated: f.truncated,
-                dropped: false,
-                utility_score: None,
-            })
-            .collect();
-
-        writer
-            .write_context_start()
-            .map_err(|e| EncoderError::xml_error(e.to_string()))?;
-        writer
-            .write_metadata(&attention_entries)
-            .map_err(|e| EncoderError::xml_error(e.to_string()))?;
-        writer
-            .write_files_start()
-            .map_err(|e| EncoderError::xml_error(e.to_string()))?;
-
-        for file in files {
-            let zoom_cmd = if file.truncated {
-                Some(format!("--include {} --truncate 0", file.path))
-            } else {
-                None
-            };
-
-            writer
-                .write_file(
-                    &file.path,
-                    &file.language,
-                    &file.md5,
-                    file.priority,
-                    &file.content,
-                    file.truncated,
-                    file.original_tokens,
-                    zoom_cmd.as_deref(),
-                )
-                .map_err(|e| EncoderError::xml_error(e.to_string()))?;
-        }
-
-        writer
-            .write_files_end()
-            .map_err(|e| EncoderError::xml_error(e.to_string()))?;
-        writer
-            .write_context_end()
-            .map_err(|e| EncoderError::xml_error(e.to_string()))?;
-
-        String::from_utf8(buffer).map_err(EncoderError::from)
-    }
-
     // Zoom helper methods
 
     fn find_function(&self, entries: &[FileEntry], name: &str) -> Vec<FileEntry> {
@@ -690,12 +687,9 @@ impl ContextEngine {
                     let e_idx = end.unwrap_or(lines.len()).min(lines.len());
                     let content = lines[s..e_idx].join("\n");
                     FileEntry {
-                        path: e.path.clone(),
                         size: content.len() as u64,
                         content,
-                        md5: e.md5.clone(),
-                        mtime: e.mtime,
-                        ctime: e.ctime,
+                        ..e.clone()
                     }
                 } else {
                     e.clone()
@@ -807,7 +801,8 @@ mod tests {
         let engine = ContextEngine::with_config(config);
 
         let entries = vec![FileEntry::new("test.py", "line1\nline2\nline3\nline4")];
-        let processed = engine.process_files(&entries);
+        let formatter = engine.formatter().unwrap();
+        let (_, processed) = engine.process_files(formatter.as_ref(), &entries);
 
         assert_eq!(processed.len(), 1);
         assert!(processed[0].truncated);
@@ -840,6 +835,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "small.py".to_string(),
@@ -852,6 +848,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
         ];
 
@@ -960,6 +957,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "src/main.rs".to_string(),
@@ -972,6 +970,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "README.md".to_string(),
@@ -984,6 +983,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
         ];
 
@@ -1014,6 +1014,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "tests/test.py".to_string(), // Tests
@@ -1026,6 +1027,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "Cargo.toml".to_string(), // Config
@@ -1038,6 +1040,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "src/lib.rs".to_string(), // Core
@@ -1050,6 +1053,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
         ];
 
@@ -1080,6 +1084,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "src/lib.rs".to_string(),
@@ -1092,6 +1097,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "Cargo.toml".to_string(),
@@ -1104,6 +1110,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "tests/test.py".to_string(),
@@ -1116,6 +1123,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
         ];
 
@@ -1155,6 +1163,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
             ProcessedFile {
                 path: "src/high_priority.rs".to_string(),
@@ -1167,6 +1176,7 @@ mod tests {
                 original_tokens: None,
                 compression_level: CompressionLevel::Full,
                 utility: None,
+                ..Default::default()
             },
         ];
 
@@ -1259,6 +1269,7 @@ mod tests {
                 mtime: 300,
                 ctime: 0,
                 size: 1,
+                ..Default::default()
             },
             FileEntry {
                 path: "b.txt".to_string(),
@@ -1267,6 +1278,7 @@ mod tests {
                 mtime: 100,
                 ctime: 0,
                 size: 1,
+                ..Default::default()
             },
             FileEntry {
                 path: "c.txt".to_string(),
@@ -1275,6 +1287,7 @@ mod tests {
                 mtime: 200,
                 ctime: 0,
                 size: 1,
+                ..Default::default()
             },
         ];
 
@@ -1299,6 +1312,7 @@ mod tests {
                 mtime: 100,
                 ctime: 0,
                 size: 1,
+                ..Default::default()
             },
             FileEntry {
                 path: "b.txt".to_string(),
@@ -1307,6 +1321,7 @@ mod tests {
                 mtime: 300,
                 ctime: 0,
                 size: 1,
+                ..Default::default()
             },
         ];
 
@@ -1330,6 +1345,7 @@ mod tests {
                 mtime: 0,
                 ctime: 300,
                 size: 1,
+                ..Default::default()
             },
             FileEntry {
                 path: "b.txt".to_string(),
@@ -1338,6 +1354,7 @@ mod tests {
                 mtime: 0,
                 ctime: 100,
                 size: 1,
+                ..Default::default()
             },
         ];
 
@@ -1361,6 +1378,7 @@ mod tests {
                 mtime: 0,
                 ctime: 100,
                 size: 1,
+                ..Default::default()
             },
             FileEntry {
                 path: "b.txt".to_string(),
@@ -1369,6 +1387,7 @@ mod tests {
                 mtime: 0,
                 ctime: 300,
                 size: 1,
+                ..Default::default()
             },
         ];
 
@@ -1576,6 +1595,30 @@ mod tests {
         assert!(output.contains("</context>"));
     }
 
+    #[test]
+    fn test_zoom_claude_xml_format() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "fn target_func() {}\n").unwrap();
+
+        let mut config = EncoderConfig::default();
+        config.output_format = OutputFormat::ClaudeXml;
+        let engine = ContextEngine::with_config(config);
+        let zoom_config = ZoomConfig {
+            target: ZoomTarget::Function("target_func".to_string()),
+            budget: None,
+            depth: ZoomDepth::Full,
+            include_tests: false,
+            context_lines: 0,
+        };
+
+        let output = engine
+            .zoom(temp_dir.path().to_str().unwrap(), &zoom_config)
+            .unwrap();
+        assert!(output.starts_with("<context"));
+        assert!(output.contains("target_func"));
+        assert!(output.contains("</context>"));
+    }
+
     #[test]
     fn test_apply_budget_with_skeleton_enabled() {
         use crate::core::models::CompressionLevel;
@@ -1596,6 +1639,7 @@ mod tests {
             original_tokens: None,
             compression_level: CompressionLevel::Full,
             utility: None,
+            ..Default::default()
         }];
 
         // With skeleton mode enabled, files should be compressed
@@ -1654,7 +1698,8 @@ mod tests {
         let engine = ContextEngine::new(); // Default: truncate_lines = 0
 
         let entries = vec![FileEntry::new("test.py", "line1\nline2\nline3")];
-        let processed = engine.process_files(&entries);
+        let formatter = engine.formatter().unwrap();
+        let (_, processed) = engine.process_files(formatter.as_ref(), &entries);
 
         assert_eq!(processed.len(), 1);
         assert!(!processed[0].truncated);
@@ -26,6 +26,7 @@ use serde::{Deserialize, Serialize};
 use crate::core::fractal::{
     ContextLayer, FeatureVector, LayerContent, SymbolVectorizer, Visibility,
 };
+use crate::core::orchestrator::DetailLevel;
 
 use super::{
     ExplorationIntent, IntentComposition, IntentResult, ReadingDecision, StopReadingEngine,
@@ -92,6 +93,14 @@ pub struct ExplorationResult {
 impl ExplorationResult {
     /// Convert to human-readable text output
     pub fn to_text(&self) -> String {
+        self.to_text_with_detail(DetailLevel::Smart)
+    }
+
+    /// Convert to human-readable text output at the given detail level
+    ///
+    /// Insights follow [`DetailLevel::list_limit`]; `Detailed` also prints
+    /// full symbol names and reasons plus analysis statistics.
+    pub fn to_text_with_detail(&self, detail: DetailLevel) -> String {
         let mut output = String::new();
 
         // Header
@@ -103,11 +112,19 @@ impl ExplorationResult {
         output.push_str(&format!("{}\n\n", self.intent_result.summary));
 
         // Key insights
-        if !self.intent_result.key_insights.is_empty() {
+        let insights = &self.intent_result.key_insights;
+        if !insights.is_empty() {
             output.push_str("Key Insights:\n");
-            for insight in &self.intent_result.key_insights {
+            let shown = detail.list_limit(3).min(insights.len());
+            for insight in &insights[..shown] {
                 output.push_str(&format!("  • {}\n", insight));
             }
+            if insights.len() > shown {
+                output.push_str(&format!(
+                    "  ({} more with --detail detailed)\n",
+                    insights.len() - shown
+                ));
+            }
             output.push_str("\n");
         }
 
@@ -121,12 +138,27 @@ impl ExplorationResult {
 
         for step in &self.intent_result.exploration_path {
             let relevance_pct = format!("{:.0}%", step.relevance_score * 100.0);
+            let (symbol, reason) = if detail.shows_file_details() {
+                (step.symbol.clone(), step.reason.clone())
+            } else {
+                (
+                    truncate_str(&step.symbol, 38),
+                    truncate_str(&step.reason, 30),
+                )
+            };
             output.push_str(&format!(
                 "{:<8} {:<40} {:<12} {}\n",
                 step.decision.to_uppercase(),
-                truncate_str(&step.symbol, 38),
+                symbol,
                 relevance_pct,
-                truncate_str(&step.reason, 30),
+                reason,
+            ));
+        }
+
+        if detail.shows_file_details() {
+            output.push_str(&format!(
+                "\nAnalyzed {} files, {} symbols\n",
+                self.files_analyzed, self.symbols_extracted
             ));
         }
 
@@ -806,6 +838,40 @@ mod tests {
         assert!(text.contains("Estimated reading time: 15 minutes"));
     }
 
+    #[test]
+    fn test_exploration_result_text_detail_levels() {
+        let result = ExplorationResult {
+            intent_result: IntentResult {
+                intent: ExplorationIntent::BusinessLogic,
+                summary: "Test summary".to_string(),
+                total_count: 10,
+                relevant_count: 5,
+                estimated_minutes: 15,
+                exploration_path: vec![],
+                key_insights: (1..=5).map(|i| format!("Insight {}", i)).collect(),
+            },
+            project_root: "/test".to_string(),
+            files_analyzed: 3,
+            symbols_extracted: 10,
+            format_hint: None,
+        };
+
+        let summary = result.to_text_with_detail(DetailLevel::Summary);
+        assert!(summary.contains("Insight 2"));
+        assert!(!summary.contains("Insight 3"));
+        assert!(summary.contains("(3 more with --detail detailed)"));
+
+        let smart = result.to_text();
+        assert!(smart.contains("Insight 3"));
+        assert!(!smart.contains("Insight 4"));
+        assert!(!smart.contains("Analyzed 3 files"));
+
+        let detailed = result.to_text_with_detail(DetailLevel::Detailed);
+        assert!(detailed.contains("Insight 5"));
+        assert!(!detailed.contains("more with --detail"));
+        assert!(detailed.contains("Analyzed 3 files, 10 symbols"));
+    }
+
     #[test]
     fn test_exploration_result_to_xml_format() {
         let result = ExplorationResult {
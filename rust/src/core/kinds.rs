@@ -0,0 +1,206 @@
+//! Framework declaration kinds from config rules
+//!
+//! Frameworks build their constructs out of core kinds: a React component
+//! is a function, a Django view a function taking `request`. A [`KindRule`]
+//! names such a construct as a namespaced kind (`react:component`) and says
+//! how to recognise it; [`KindRules::apply`] reclassifies the matching
+//! declarations of a parsed file, so the census and the kind filters see
+//! them under their own name.
+//!
+//! ```json
+//! "declaration_kinds": [
+//!   {"kind": "django:view", "base": "function", "parameter": "request", "path": "**/views.py"}
+//! ]
+//! ```
+
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use voyager_ast::ir::{CustomKind, Declaration, DeclarationKind, File as AstFile};
+
+/// One custom kind and how to recognise its declarations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KindRule {
+    /// The custom kind, `namespace:kind`
+    pub kind: String,
+    /// The core kind the declarations are parsed as
+    pub base: String,
+    /// Regex the declaration's name must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// A parameter the declaration must take
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+    /// Glob the file's path must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+struct CompiledRule {
+    kind: DeclarationKind,
+    base: DeclarationKind,
+    name: Option<Regex>,
+    parameter: Option<String>,
+    path: Option<GlobMatcher>,
+}
+
+impl CompiledRule {
+    fn matches(&self, path: &str, decl: &Declaration) -> bool {
+        decl.kind.base() == self.base
+            && self.name.as_ref().is_none_or(|re| re.is_match(&decl.name))
+            && self
+                .parameter
+                .as_ref()
+                .is_none_or(|p| decl.parameters.iter().any(|param| &param.name == p))
+            && self.path.as_ref().is_none_or(|glob| glob.is_match(path))
+    }
+}
+
+/// Compiled [`KindRule`]s, applied in order; the first match wins
+#[derive(Default)]
+pub struct KindRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl KindRules {
+    /// Register the rules' kinds and compile their patterns
+    pub fn new(rules: &[KindRule]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let base = DeclarationKind::parse(&rule.base)
+                    .filter(|kind| kind.namespace().is_none())
+                    .ok_or_else(|| format!("'{}' is not a core declaration kind", rule.base))?;
+                let kind = CustomKind::register(&rule.kind, base)?;
+                let name = rule
+                    .name
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| format!("Invalid name pattern for {}: {}", rule.kind, e))?;
+                let path = rule
+                    .path
+                    .as_deref()
+                    .map(|glob| Glob::new(glob).map(|g| g.compile_matcher()))
+                    .transpose()
+                    .map_err(|e| format!("Invalid path glob for {}: {}", rule.kind, e))?;
+                Ok(CompiledRule {
+                    kind: DeclarationKind::Custom(kind),
+                    base,
+                    name,
+                    parameter: rule.parameter.clone(),
+                    path,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Reclassify the declarations in `file` (found at `path`) that a rule
+    /// matches, nested ones included; returns how many changed kind
+    pub fn apply(&self, path: &str, file: &mut AstFile) -> usize {
+        if self.rules.is_empty() {
+            return 0;
+        }
+        let path = path.replace('\\', "/");
+        file.declarations
+            .iter_mut()
+            .map(|decl| self.apply_declaration(&path, decl))
+            .sum()
+    }
+
+    fn apply_declaration(&self, path: &str, decl: &mut Declaration) -> usize {
+        let mut changed = 0;
+        if let Some(rule) = self.rules.iter().find(|rule| rule.matches(path, decl)) {
+            if decl.kind != rule.kind {
+                decl.kind = rule.kind;
+                changed += 1;
+            }
+        }
+        changed
+            + decl
+                .children
+                .iter_mut()
+                .map(|child| self.apply_declaration(path, child))
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::AstBridge;
+    use voyager_ast::ir::LanguageId;
+
+    fn rule(kind: &str, base: &str) -> KindRule {
+        KindRule {
+            kind: kind.to_string(),
+            base: base.to_string(),
+            name: None,
+            parameter: None,
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_rules_reclassify_matching_declarations() {
+        let source = "def index(request):\n    return 1\n\ndef helper(x):\n    return x\n\n\
+                      class Panel:\n    def render(self, request):\n        return 2\n";
+        let views = KindRules::new(&[
+            KindRule {
+                parameter: Some("request".to_string()),
+                path: Some("**/views.py".to_string()),
+                ..rule("test-django:view", "function")
+            },
+            KindRule {
+                name: Some("^render$".to_string()),
+                ..rule("test-django:render", "method")
+            },
+        ])
+        .unwrap();
+
+        let bridge = AstBridge::new();
+        let mut file = bridge.analyze_file(source, LanguageId::Python).unwrap();
+        assert_eq!(views.apply("shop/views.py", &mut file), 2);
+        let kinds: Vec<(&str, &str)> = file
+            .declarations
+            .iter()
+            .flat_map(|d| std::iter::once(d).chain(&d.children))
+            .map(|d| (d.name.as_str(), d.kind.as_str()))
+            .collect();
+        assert!(
+            kinds.contains(&("index", "test-django:view")),
+            "{:?}",
+            kinds
+        );
+        assert!(kinds.contains(&("helper", "function")), "{:?}", kinds);
+        assert!(
+            kinds.contains(&("render", "test-django:render")),
+            "{:?}",
+            kinds
+        );
+
+        // The path glob keeps views elsewhere as they were
+        let mut other = bridge.analyze_file(source, LanguageId::Python).unwrap();
+        views.apply("shop/models.py", &mut other);
+        assert_eq!(other.declarations[0].kind, DeclarationKind::Function);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let err = KindRules::new(&[rule("view", "function")]).err().unwrap();
+        assert!(err.contains("not a namespaced kind"), "{}", err);
+        let err = KindRules::new(&[rule("web:view", "widget")]).err().unwrap();
+        assert!(err.contains("'widget' is not a core"), "{}", err);
+        let bad_regex = KindRule {
+            name: Some("(".to_string()),
+            ..rule("web:view", "function")
+        };
+        assert!(KindRules::new(&[bad_regex]).is_err());
+    }
+}
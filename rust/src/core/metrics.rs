@@ -163,7 +163,7 @@ impl MetricCollector for ParameterComplexityMetric {
             .iter()
             .filter(|d| {
                 matches!(
-                    d.kind,
+                    d.kind.base(),
                     voyager_ast::ir::DeclarationKind::Function
                         | voyager_ast::ir::DeclarationKind::Method
                 )
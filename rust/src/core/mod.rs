@@ -9,21 +9,30 @@
 //! - `error`: Error types using thiserror
 //! - `walker`: Directory traversal with FileWalker trait + SmartWalker
 //! - `manifest`: Project boundary detection
-//! - `serialization`: Output format serializers
+//! - `serialization`: Size and timestamp metadata for file headers
 //! - `engine`: Main ContextEngine orchestration
 //! - `zoom`: Fractal Protocol zoom actions
 //! - `fractal`: Fractal Context Engine for hierarchical, zoomable context
 //! - `syntax`: Tree-sitter based AST parsing (Phase 1A)
 //! - `plugin`: Plugin ecosystem reservation (Phase 2)
 //! - `ast_bridge`: Bridge to voyager-ast structural optics
+//! - `bundle`: Tarball bundle output with manifest
+//! - `compress`: gzip/zstd compression of `-o` output files
 //! - `metrics`: AST-based code metrics collection (Phase 3 foundation)
+//! - `sarif`: SARIF ingestion for finding-focused bundles
+//! - `claims`: Validation of model-cited paths, symbols and lines
+//! - `sidecar`: Per-run `<output>.manifest.json` for drift checks
 
 pub mod ast_bridge;
+pub mod bundle;
 pub mod celestial;
 pub mod census;
+pub mod claims;
+pub mod compress;
 pub mod engine;
 pub mod error;
 pub mod fractal;
+pub mod kinds;
 pub mod manifest;
 pub mod metrics;
 pub mod models;
@@ -32,8 +41,10 @@ pub mod plugin;
 pub mod plugins;
 pub mod presenter;
 pub mod regex_engine;
+pub mod sarif;
 pub mod search;
 pub mod serialization;
+pub mod sidecar;
 pub mod skeleton;
 pub mod spectrograph;
 pub mod store;
@@ -50,7 +61,9 @@ pub use models::{
     CompressionLevel, Config, EncoderConfig, FileEntry, OutputFormat, ProcessedFile, SkeletonMode,
 };
 pub use search::{
+    CallGraph,
     CallGraphAnalyzer,
+    CallGraphNode,
     FunctionCall,
     RelatedContext,
     SymbolLocation,
@@ -60,6 +73,7 @@ pub use search::{
     // Phase 2: Reverse call graph
     UsageLocation,
     ZoomSuggestion,
+    DEFAULT_CALLGRAPH_DEPTH,
 };
 pub use store::{ContextStore, FileUtility, DEFAULT_ALPHA};
 pub use walker::{DefaultWalker, FileWalker, SmartWalkConfig, SmartWalker, WalkEntry};
@@ -98,11 +112,19 @@ pub use orchestrator::{
     SmartDefaults,
     SmartOrchestrator,
 };
+#[cfg(feature = "temporal")]
+pub use presenter::TemporalNarrative;
 pub use presenter::{
+    // Terminal color
+    ColorChoice,
     // Drift Info (v1.1.0)
     DriftInfo,
     EmojiFormatter,
+    // Structured narration for GUIs
+    GovernanceReport,
     IntelligentPresenter,
+    MissionLog,
+    Palette,
     SemanticTransparency,
     Theme,
 };
@@ -123,6 +145,7 @@ pub use syntax::{
 
 // voyager-ast integration (Structural Optics)
 pub use ast_bridge::{AstBridge, FileSummary, Star as AstStar, StarKind, StarSummary};
+pub use kinds::{KindRule, KindRules};
 
 // Phase 0 Hardening: Centralized Regex Engine
 pub use regex_engine::{
@@ -131,6 +154,7 @@ pub use regex_engine::{
 };
 
 // Phase 1C: Celestial Census (Code Health Metrics)
+pub use census::sarif::CensusSarifBuilder;
 pub use census::{
     build_census_registry,
     CelestialCensus,
@@ -152,11 +176,24 @@ pub use census::{
     StellarDensityMetric,
 };
 
+// Tarball bundle output
+pub use bundle::{Bundle, BundleFileEntry, BundleManifest};
+pub use claims::{Claim, ClaimReport, ClaimResult, ClaimStatus};
+pub use compress::OutputCompression;
+
+// SARIF ingestion (finding-focused context)
+pub use sarif::{Finding, FindingsBundle, SarifLog, Severity};
+
+// Sidecar manifest (context drift checks)
+pub use sidecar::{LineMapRange, SidecarFileEntry, SidecarManifest};
+
 // Universal Spectrograph (80+ Language Patterns)
 pub use spectrograph::{Hemisphere, SpectralSignature, StellarLibrary, STELLAR_LIBRARY};
 
 // Phase 2: Temporal (Chronos Engine)
 pub use temporal::{
+    build_ticket_index,
+    census_from_timestamps,
     is_temporal_available,
     temporal_state_description,
     AgeClassification,
@@ -186,6 +223,10 @@ pub use temporal::{
     Supernova,
     TectonicShift,
     TemporalCensus,
+    TemporalThresholds,
+    // Ticket Cross-References
+    TicketEntry,
+    TicketIndex,
     VolcanicChurn,
     WarpStatus,
     // Shallow Chronos (v1.1.0)
@@ -4,9 +4,12 @@
 
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::Arc;
+
+use crate::cache::ProcessingCache;
 
 /// A file entry with its content and metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FileEntry {
     /// Relative path to the file
     pub path: String,
@@ -20,6 +23,12 @@ pub struct FileEntry {
     pub ctime: u64,
     /// File size in bytes
     pub size: u64,
+    /// Language detected from the extension
+    pub language: String,
+    /// Whether any execute permission bit is set
+    pub executable: bool,
+    /// Where the file points, if it was reached through a symlink
+    pub symlink_target: Option<String>,
 }
 
 impl FileEntry {
@@ -28,13 +37,17 @@ impl FileEntry {
         let content = content.into();
         let size = content.len() as u64;
         let md5 = calculate_md5(&content);
+        let path = path.into();
         Self {
-            path: path.into(),
+            language: crate::core::engine::detect_language(&path),
+            path,
             content,
             md5,
             mtime: 0,
             ctime: 0,
             size,
+            executable: false,
+            symlink_target: None,
         }
     }
 
@@ -51,6 +64,18 @@ impl FileEntry {
         self
     }
 
+    /// Create a FileEntry with the executable flag set from file metadata
+    pub fn with_executable(mut self, executable: bool) -> Self {
+        self.executable = executable;
+        self
+    }
+
+    /// Create a FileEntry reached through a symlink to `target`
+    pub fn with_symlink_target(mut self, target: Option<String>) -> Self {
+        self.symlink_target = target;
+        self
+    }
+
     /// Get the file extension
     pub fn extension(&self) -> Option<&str> {
         Path::new(&self.path).extension().and_then(|e| e.to_str())
@@ -62,6 +87,20 @@ impl FileEntry {
     }
 }
 
+/// The same file as the formatters take it
+impl From<&FileEntry> for crate::FileEntry {
+    fn from(entry: &FileEntry) -> Self {
+        Self {
+            mtime: entry.mtime,
+            ctime: entry.ctime,
+            size: entry.size,
+            executable: entry.executable,
+            symlink_target: entry.symlink_target.clone(),
+            ..crate::FileEntry::new(entry.path.clone(), entry.content.clone())
+        }
+    }
+}
+
 /// Configuration loaded from .pm_encoder_config.json
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Config {
@@ -92,6 +131,8 @@ pub enum OutputFormat {
     Markdown,
     /// Claude-optimized XML with CDATA and semantic metadata
     ClaudeXml,
+    /// Gemini long-context layout (file list preamble, fenced blocks, path attributes)
+    Gemini,
 }
 
 impl OutputFormat {
@@ -102,21 +143,47 @@ impl OutputFormat {
             OutputFormat::Xml => "xml",
             OutputFormat::Markdown => "md",
             OutputFormat::ClaudeXml => "xml",
+            OutputFormat::Gemini => "md",
         }
     }
 
     /// Parse format from string
     pub fn parse(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
-            "plus-minus" | "pm" | "plus_minus" => Some(OutputFormat::PlusMinus),
+            "plus-minus" | "plusminus" | "pm" | "plus_minus" => Some(OutputFormat::PlusMinus),
             "xml" => Some(OutputFormat::Xml),
             "markdown" | "md" => Some(OutputFormat::Markdown),
             "claude-xml" | "claude_xml" => Some(OutputFormat::ClaudeXml),
+            "gemini" => Some(OutputFormat::Gemini),
             _ => None,
         }
     }
 }
 
+impl From<crate::OutputFormat> for OutputFormat {
+    fn from(format: crate::OutputFormat) -> Self {
+        match format {
+            crate::OutputFormat::PlusMinus => OutputFormat::PlusMinus,
+            crate::OutputFormat::Xml => OutputFormat::Xml,
+            crate::OutputFormat::Markdown => OutputFormat::Markdown,
+            crate::OutputFormat::ClaudeXml => OutputFormat::ClaudeXml,
+            crate::OutputFormat::Gemini => OutputFormat::Gemini,
+        }
+    }
+}
+
+impl From<OutputFormat> for crate::OutputFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::PlusMinus => crate::OutputFormat::PlusMinus,
+            OutputFormat::Xml => crate::OutputFormat::Xml,
+            OutputFormat::Markdown => crate::OutputFormat::Markdown,
+            OutputFormat::ClaudeXml => crate::OutputFormat::ClaudeXml,
+            OutputFormat::Gemini => crate::OutputFormat::Gemini,
+        }
+    }
+}
+
 /// Runtime configuration for the encoder
 #[derive(Debug, Clone)]
 pub struct EncoderConfig {
@@ -130,7 +197,7 @@ pub struct EncoderConfig {
     pub truncate_lines: usize,
     /// Truncation mode: "simple", "smart", or "structure"
     pub truncate_mode: String,
-    /// Sort field: "name", "mtime", or "ctime"
+    /// Sort field: "name", "mtime", "ctime", "size" or "ext"
     pub sort_by: String,
     /// Sort order: "asc" or "desc"
     pub sort_order: String,
@@ -161,10 +228,12 @@ pub struct EncoderConfig {
     pub metadata_mode: MetadataMode,
     /// Follow symbolic links (default: false, skip broken symlinks silently)
     pub follow_symlinks: bool,
+    /// Reuse truncated files from this cache (see [`crate::cache`])
+    pub cache: Option<Arc<ProcessingCache>>,
 }
 
 /// Skeleton mode configuration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum SkeletonMode {
     /// Enable skeleton compression if token_budget is set
     #[default]
@@ -255,6 +324,7 @@ impl Default for EncoderConfig {
             skeleton_mode: SkeletonMode::Auto,
             metadata_mode: MetadataMode::Auto,
             follow_symlinks: false,
+            cache: None,
         }
     }
 }
@@ -309,6 +379,67 @@ impl EncoderConfig {
     }
 }
 
+/// The walk, sort, truncation and budget settings of a full config
+impl From<&crate::EncoderConfig> for EncoderConfig {
+    fn from(config: &crate::EncoderConfig) -> Self {
+        Self {
+            ignore_patterns: config.ignore_patterns.clone(),
+            include_patterns: config.include_patterns.clone(),
+            max_file_size: config.max_file_size,
+            truncate_lines: config.truncate_lines,
+            truncate_mode: config.truncate_mode.clone(),
+            sort_by: config.sort_by.clone(),
+            sort_order: config.sort_order.clone(),
+            stream: config.stream,
+            truncate_summary: config.truncate_summary,
+            truncate_exclude: config.truncate_exclude.clone(),
+            truncate_stats: config.truncate_stats,
+            output_format: config.output_format.into(),
+            frozen: config.frozen,
+            allow_sensitive: config.allow_sensitive,
+            active_lens: config.active_lens.clone(),
+            token_budget: config.token_budget,
+            skeleton_mode: config.skeleton_mode,
+            metadata_mode: config.metadata_mode,
+            follow_symlinks: config.follow_symlinks == crate::SymlinkPolicy::On,
+            cache: config.cache.clone(),
+        }
+    }
+}
+
+/// A full config with these settings and the defaults for the rest
+impl From<&EncoderConfig> for crate::EncoderConfig {
+    fn from(config: &EncoderConfig) -> Self {
+        Self {
+            ignore_patterns: config.ignore_patterns.clone(),
+            include_patterns: config.include_patterns.clone(),
+            max_file_size: config.max_file_size,
+            truncate_lines: config.truncate_lines,
+            truncate_mode: config.truncate_mode.clone(),
+            sort_by: config.sort_by.clone(),
+            sort_order: config.sort_order.clone(),
+            stream: config.stream,
+            truncate_summary: config.truncate_summary,
+            truncate_exclude: config.truncate_exclude.clone(),
+            truncate_stats: config.truncate_stats,
+            output_format: config.output_format.into(),
+            frozen: config.frozen,
+            allow_sensitive: config.allow_sensitive,
+            active_lens: config.active_lens.clone(),
+            token_budget: config.token_budget,
+            skeleton_mode: config.skeleton_mode,
+            metadata_mode: config.metadata_mode,
+            follow_symlinks: if config.follow_symlinks {
+                crate::SymlinkPolicy::On
+            } else {
+                crate::SymlinkPolicy::Off
+            },
+            cache: config.cache.clone(),
+            ..Default::default()
+        }
+    }
+}
+
 /// Compression level for skeleton protocol
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CompressionLevel {
@@ -345,11 +476,22 @@ pub struct ProcessedFile {
     /// Utility score from Observer's Journal (0.0-1.0)
     /// Stars with utility >= 0.8 are "bright" and display ⭐
     pub utility: Option<f64>,
+    /// Size of the original file in bytes
+    pub size: u64,
+    /// Whether the original file is executable
+    pub executable: bool,
+    /// Symlink target of the original file, if any
+    pub symlink_target: Option<String>,
 }
 
 impl ProcessedFile {
-    /// Create from a FileEntry
+    /// Create from a FileEntry; an empty `language` falls back to the entry's own
     pub fn from_entry(entry: &FileEntry, language: &str, priority: i32) -> Self {
+        let language = if language.is_empty() {
+            &entry.language
+        } else {
+            language
+        };
         Self {
             path: entry.path.clone(),
             content: entry.content.clone(),
@@ -361,9 +503,17 @@ impl ProcessedFile {
             original_tokens: None,
             compression_level: CompressionLevel::Full,
             utility: None,
+            size: entry.size,
+            executable: entry.executable,
+            symlink_target: entry.symlink_target.clone(),
         }
     }
 
+    /// Create from a FileEntry using the language detected by the walker
+    pub fn from_walked_entry(entry: &FileEntry, priority: i32) -> Self {
+        Self::from_entry(entry, &entry.language, priority)
+    }
+
     /// Set utility score from journal
     pub fn with_utility(mut self, utility: f64) -> Self {
         self.utility = Some(utility);
@@ -423,6 +573,9 @@ impl Default for ProcessedFile {
             original_tokens: None,
             compression_level: CompressionLevel::Full,
             utility: None,
+            size: 0,
+            executable: false,
+            symlink_target: None,
         }
     }
 }
@@ -490,6 +643,23 @@ mod tests {
         assert!(!processed.truncated);
     }
 
+    #[test]
+    fn test_processed_file_from_walked_entry() {
+        let entry = FileEntry::new("run.py", "print(1)")
+            .with_executable(true)
+            .with_symlink_target(Some("bin/run.py".to_string()));
+        let processed = ProcessedFile::from_walked_entry(&entry, 50);
+
+        assert_eq!(processed.language, "python");
+        assert!(processed.executable);
+        assert_eq!(processed.symlink_target.as_deref(), Some("bin/run.py"));
+        assert_eq!(ProcessedFile::from_entry(&entry, "", 50).language, "python");
+        assert_eq!(
+            ProcessedFile::from_entry(&entry, "text", 50).language,
+            "text"
+        );
+    }
+
     #[test]
     fn test_calculate_md5() {
         let hash = calculate_md5("hello world");
@@ -528,6 +698,7 @@ mod tests {
         assert_eq!(OutputFormat::Xml.extension(), "xml");
         assert_eq!(OutputFormat::Markdown.extension(), "md");
         assert_eq!(OutputFormat::ClaudeXml.extension(), "xml");
+        assert_eq!(OutputFormat::Gemini.extension(), "md");
     }
 
     #[test]
@@ -554,6 +725,9 @@ mod tests {
             OutputFormat::parse("claude_xml"),
             Some(OutputFormat::ClaudeXml)
         );
+
+        // Gemini
+        assert_eq!(OutputFormat::parse("gemini"), Some(OutputFormat::Gemini));
     }
 
     #[test]
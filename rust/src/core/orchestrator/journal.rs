@@ -20,6 +20,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
+use crate::migrate::StateKind;
+
 // =============================================================================
 // Journal Entry Types
 // =============================================================================
@@ -146,10 +148,16 @@ impl ObserversJournal {
     }
 
     /// Load journal from a specific file.
+    ///
+    /// Journals written by older releases are upgraded (see [`crate::migrate`]).
     pub fn load_from_file(path: &Path) -> Self {
         if path.exists() {
             match fs::read_to_string(path) {
-                Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|_| Self::new()),
+                Ok(contents) => crate::migrate::load_upgraded(StateKind::Journal, &contents)
+                    .unwrap_or_else(|e| {
+                        eprintln!("Warning: ignoring {}: {}", path.display(), e);
+                        Self::new()
+                    }),
                 Err(_) => Self::new(),
             }
         } else {
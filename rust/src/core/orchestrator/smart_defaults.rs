@@ -101,6 +101,27 @@ impl DetailLevel {
             _ => None,
         }
     }
+
+    /// How many items of a list to show, given the `Smart` limit.
+    ///
+    /// `Summary` shows about half (at least one), `Detailed` shows everything.
+    pub fn list_limit(&self, smart_limit: usize) -> usize {
+        match self {
+            Self::Summary => smart_limit.div_ceil(2).max(1),
+            Self::Smart => smart_limit,
+            Self::Detailed => usize::MAX,
+        }
+    }
+
+    /// Whether per-constellation breakdowns are shown (not in `Summary`).
+    pub fn shows_breakdowns(&self) -> bool {
+        !matches!(self, Self::Summary)
+    }
+
+    /// Whether per-file details are shown (only in `Detailed`).
+    pub fn shows_file_details(&self) -> bool {
+        matches!(self, Self::Detailed)
+    }
 }
 
 impl std::fmt::Display for DetailLevel {
@@ -325,6 +346,19 @@ mod tests {
         assert_eq!(DetailLevel::parse("d"), Some(DetailLevel::Detailed));
     }
 
+    #[test]
+    fn test_detail_level_limits() {
+        assert_eq!(DetailLevel::Summary.list_limit(3), 2);
+        assert_eq!(DetailLevel::Summary.list_limit(10), 5);
+        assert_eq!(DetailLevel::Summary.list_limit(0), 1);
+        assert_eq!(DetailLevel::Smart.list_limit(10), 10);
+        assert_eq!(DetailLevel::Detailed.list_limit(10), usize::MAX);
+        assert!(!DetailLevel::Summary.shows_breakdowns());
+        assert!(DetailLevel::Smart.shows_breakdowns());
+        assert!(!DetailLevel::Smart.shows_file_details());
+        assert!(DetailLevel::Detailed.shows_file_details());
+    }
+
     #[test]
     fn test_detail_level_parse_case_insensitive() {
         assert_eq!(DetailLevel::parse("SUMMARY"), Some(DetailLevel::Summary));
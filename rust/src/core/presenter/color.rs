@@ -0,0 +1,142 @@
+//! Terminal Color Module
+//!
+//! ANSI styling for presenter and report output. Whether color is used is
+//! decided once per stream from `--color auto|always|never`, the `NO_COLOR`
+//! convention and whether the stream is a terminal. A disabled palette
+//! returns text unchanged, so plain output stays byte-for-byte the same.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+// =============================================================================
+// Color Choice
+// =============================================================================
+
+/// When to emit ANSI colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is unset (default)
+    #[default]
+    Auto,
+    /// Always color, even when piped or `NO_COLOR` is set
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolve the choice for a stream.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => is_terminal && !no_color_requested(),
+        }
+    }
+}
+
+/// `NO_COLOR` set to any non-empty value disables automatic color.
+fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+// =============================================================================
+// Palette
+// =============================================================================
+
+/// Semantic styles, mapped to ANSI codes by the palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Section titles
+    Heading,
+    /// Healthy / within budget
+    Good,
+    /// Needs attention
+    Warn,
+    /// Critical / dropped
+    Bad,
+    /// Paths, names and key figures
+    Accent,
+    /// Secondary detail
+    Dim,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Heading => "1;36",
+            Self::Good => "32",
+            Self::Warn => "33",
+            Self::Bad => "1;31",
+            Self::Accent => "1",
+            Self::Dim => "2",
+        }
+    }
+}
+
+/// Applies [`Style`]s when color is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    /// Create a palette with color on or off.
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// A palette that never colors.
+    pub fn plain() -> Self {
+        Self::new(false)
+    }
+
+    /// Palette for output written to stdout.
+    pub fn stdout(choice: ColorChoice) -> Self {
+        Self::new(choice.enabled(std::io::stdout().is_terminal()))
+    }
+
+    /// Palette for output written to stderr.
+    pub fn stderr(choice: ColorChoice) -> Self {
+        Self::new(choice.enabled(std::io::stderr().is_terminal()))
+    }
+
+    /// Whether this palette emits escape codes.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Wrap `text` in the escape codes for `style`.
+    pub fn paint(&self, style: Style, text: impl Display) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice_resolution() {
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+    }
+
+    #[test]
+    fn test_palette_paint() {
+        assert_eq!(Palette::plain().paint(Style::Bad, "x"), "x");
+        assert_eq!(
+            Palette::new(true).paint(Style::Good, 42),
+            "\x1b[32m42\x1b[0m"
+        );
+    }
+}
@@ -9,11 +9,21 @@
 //! - **Progressive disclosure**: Start simple, reveal details on demand
 //! - **Visual hierarchy**: Emojis guide the eye to what matters
 //! - **Actionable output**: Always suggest next steps
+//! - **Color on terminals only**: see [`color`] for `--color` and `NO_COLOR`
 
+pub mod color;
 pub mod emoji_formatter;
+pub mod structured;
 pub mod transparency;
 
+pub use color::{ColorChoice, Palette, Style};
 pub use emoji_formatter::{EmojiFormatter, Theme};
+#[cfg(feature = "temporal")]
+pub use structured::TemporalNarrative;
+pub use structured::{
+    ConfidenceLevel, ConstellationSummary, DarkMatterSeverity, DriftHealth, GovernanceReport,
+    MissionDrift, MissionLog,
+};
 pub use transparency::SemanticTransparency;
 
 use crate::core::census::{CensusMetrics, GalaxyCensus, HealthRating};
@@ -24,7 +34,7 @@ use crate::core::orchestrator::DetailLevel;
 // =============================================================================
 
 /// Information about temporal drift for mission log display.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct DriftInfo {
     /// Galaxy age in days
     pub galaxy_age_days: u64,
@@ -54,6 +64,8 @@ pub struct IntelligentPresenter {
     transparency: SemanticTransparency,
     /// Current detail level
     detail_level: DetailLevel,
+    /// ANSI styling (plain unless set with `with_palette`)
+    palette: Palette,
 }
 
 impl Default for IntelligentPresenter {
@@ -69,6 +81,7 @@ impl IntelligentPresenter {
             emoji_formatter: EmojiFormatter::new(),
             transparency: SemanticTransparency::new(),
             detail_level: DetailLevel::Smart,
+            palette: Palette::plain(),
         }
     }
 
@@ -78,6 +91,12 @@ impl IntelligentPresenter {
         self
     }
 
+    /// Color rendered text with `palette`.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
     /// Enable semantic transparency (technical details).
     pub fn with_transparency(mut self, enabled: bool) -> Self {
         self.transparency = if enabled {
@@ -140,11 +159,7 @@ impl IntelligentPresenter {
 
         let mut output = format!("{} Key Insights:\n", self.emoji_formatter.insight_emoji());
 
-        let max_insights = match self.detail_level {
-            DetailLevel::Summary => 2,
-            DetailLevel::Smart => 3,
-            DetailLevel::Detailed => insights.len(),
-        };
+        let max_insights = self.detail_level.list_limit(3).min(insights.len());
 
         for insight in insights.iter().take(max_insights) {
             output.push_str(&format!(
@@ -224,19 +239,91 @@ impl IntelligentPresenter {
         poi_count: usize,
         nebula_name: Option<&str>,
     ) -> String {
+        self.render_mission_log(&self.mission_log(
+            project_name,
+            hemispheres,
+            lens,
+            confidence,
+            tokens_used,
+            token_budget,
+            poi_count,
+            nebula_name,
+        ))
+    }
+
+    /// Format an extended Voyager Mission Log with temporal drift metrics.
+    ///
+    /// This adds Galaxy Age, Stellar Drift, and Ancient Star information
+    /// when temporal analysis is available.
+    pub fn format_mission_log_with_drift(
+        &self,
+        project_name: &str,
+        hemispheres: (&str, Option<&str>),
+        lens: &str,
+        confidence: f32,
+        tokens_used: usize,
+        token_budget: usize,
+        poi_count: usize,
+        nebula_name: Option<&str>,
+        drift_info: Option<DriftInfo>,
+    ) -> String {
+        let log = self
+            .mission_log(
+                project_name,
+                hemispheres,
+                lens,
+                confidence,
+                tokens_used,
+                token_budget,
+                poi_count,
+                nebula_name,
+            )
+            .with_drift(drift_info);
+        self.render_mission_log(&log)
+    }
+
+    /// Build the Voyager Mission Log as structured data.
+    ///
+    /// Same inputs as [`format_mission_log`](Self::format_mission_log); add
+    /// drift metrics with [`MissionLog::with_drift`].
+    pub fn mission_log(
+        &self,
+        project_name: &str,
+        hemispheres: (&str, Option<&str>),
+        lens: &str,
+        confidence: f32,
+        tokens_used: usize,
+        token_budget: usize,
+        poi_count: usize,
+        nebula_name: Option<&str>,
+    ) -> MissionLog {
+        MissionLog::new(
+            project_name,
+            hemispheres,
+            lens,
+            confidence,
+            tokens_used,
+            token_budget,
+            poi_count,
+            nebula_name,
+        )
+    }
+
+    /// Render a structured Mission Log as terminal text.
+    pub fn render_mission_log(&self, log: &MissionLog) -> String {
         let mut output = String::new();
 
         // Line 1: Observatory pointing
         output.push_str(&format!(
             "{} Observatory pointed at {}.\n",
             self.emoji_formatter.telescope(),
-            project_name
+            self.palette.paint(Style::Accent, &log.project_name)
         ));
 
         // Line 2: Two hemispheres
-        let hemisphere_str = match hemispheres.1 {
-            Some(lang2) => format!("{} | {}", hemispheres.0, lang2),
-            None => hemispheres.0.to_string(),
+        let hemisphere_str = match &log.secondary_hemisphere {
+            Some(lang2) => format!("{} | {}", log.primary_hemisphere, lang2),
+            None => log.primary_hemisphere.clone(),
         };
         output.push_str(&format!(
             "{} Two hemispheres detected: {}.\n",
@@ -245,41 +332,35 @@ impl IntelligentPresenter {
         ));
 
         // Line 3: Spectral filter
-        let confidence_label = if confidence > 0.8 {
-            "High Confidence"
-        } else if confidence > 0.5 {
-            "Medium Confidence"
-        } else {
-            "Low Confidence"
-        };
         output.push_str(&format!(
             "{} Spectral Filter '{}' applied ({}).\n",
             self.emoji_formatter.view_emoji(),
-            capitalize_first(lens),
-            confidence_label
+            capitalize_first(&log.lens),
+            log.confidence_level.label()
         ));
 
         // Line 4: Fuel gauge
-        let fuel_pct = if token_budget > 0 {
-            (tokens_used as f64 / token_budget as f64 * 100.0) as usize
-        } else {
-            0
+        let fuel_style = match log.fuel_percent {
+            0..=89 => Style::Good,
+            90..=100 => Style::Warn,
+            _ => Style::Bad,
         };
         output.push_str(&format!(
-            "{} Fuel: {} / {} tokens ({}%).\n",
+            "{} Fuel: {} / {} tokens ({}).\n",
             self.emoji_formatter.fuel(),
-            format_number(tokens_used),
-            format_number(token_budget),
-            fuel_pct
+            format_number(log.tokens_used),
+            format_number(log.token_budget),
+            self.palette
+                .paint(fuel_style, format!("{}%", log.fuel_percent))
         ));
 
         // Line 5: Points of interest
-        if poi_count > 0 {
-            let nebula_str = nebula_name.unwrap_or("primary cluster");
+        if log.points_of_interest > 0 {
+            let nebula_str = log.nebula_name.as_deref().unwrap_or("primary cluster");
             output.push_str(&format!(
                 "{} {} Points of Interest identified in the '{}'.\n",
                 self.emoji_formatter.gem(),
-                poi_count,
+                log.points_of_interest,
                 nebula_str
             ));
         }
@@ -290,42 +371,17 @@ impl IntelligentPresenter {
             self.emoji_formatter.transmit()
         ));
 
-        output
-    }
-
-    /// Format an extended Voyager Mission Log with temporal drift metrics.
-    ///
-    /// This adds Galaxy Age, Stellar Drift, and Ancient Star information
-    /// when temporal analysis is available.
-    pub fn format_mission_log_with_drift(
-        &self,
-        project_name: &str,
-        hemispheres: (&str, Option<&str>),
-        lens: &str,
-        confidence: f32,
-        tokens_used: usize,
-        token_budget: usize,
-        poi_count: usize,
-        nebula_name: Option<&str>,
-        drift_info: Option<DriftInfo>,
-    ) -> String {
-        let mut output = self.format_mission_log(
-            project_name,
-            hemispheres,
-            lens,
-            confidence,
-            tokens_used,
-            token_budget,
-            poi_count,
-            nebula_name,
-        );
-
         // Add temporal/drift section if available
-        if let Some(drift) = drift_info {
+        if let Some(MissionDrift {
+            info: drift,
+            health,
+        }) = &log.drift
+        {
             output.push_str("\n");
             output.push_str(&format!(
-                "{} Temporal Analysis\n",
-                self.emoji_formatter.insight_emoji()
+                "{} {}\n",
+                self.emoji_formatter.insight_emoji(),
+                self.palette.paint(Style::Heading, "Temporal Analysis")
             ));
             output.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
@@ -344,18 +400,11 @@ impl IntelligentPresenter {
             ));
 
             // Stellar drift
-            let drift_health = if drift.drift_rate_per_year < 20.0 {
-                ("✅", "Stable")
-            } else if drift.drift_rate_per_year < 50.0 {
-                ("📊", "Active")
-            } else if drift.drift_rate_per_year < 100.0 {
-                ("🚀", "Expanding")
-            } else {
-                ("🌋", "Volcanic")
-            };
             output.push_str(&format!(
                 "  {} Stellar Drift: {:.1}%/year {}\n",
-                drift_health.0, drift.drift_rate_per_year, drift_health.1
+                health.emoji(),
+                drift.drift_rate_per_year,
+                self.palette.paint(health.style(), health.label())
             ));
 
             // Ancient stars
@@ -392,22 +441,37 @@ impl IntelligentPresenter {
     /// - ⚠️ High Dark Matter: Significant unparsed or complex regions
     /// - 🔴 Critical: Red Giants detected (large files with issues)
     pub fn format_governance_report(&self, galaxy: &GalaxyCensus) -> String {
+        self.render_governance_report(&self.governance_report(galaxy))
+    }
+
+    /// Build the Governance Report as structured data.
+    ///
+    /// Unlike the text report, every constellation and recommendation is
+    /// included regardless of detail level.
+    pub fn governance_report(&self, galaxy: &GalaxyCensus) -> GovernanceReport {
+        GovernanceReport::from_galaxy(galaxy)
+    }
+
+    /// Render a structured Governance Report as terminal text.
+    pub fn render_governance_report(&self, report: &GovernanceReport) -> String {
         let mut output = String::new();
 
         // Header
         output.push_str("\n");
         output.push_str(&format!(
-            "{} Governance Report\n",
-            self.emoji_formatter.notable_star()
+            "{} {}\n",
+            self.emoji_formatter.notable_star(),
+            self.palette.paint(Style::Heading, "Governance Report")
         ));
         output.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
         // Overall health rating
-        if let Some(rating) = &galaxy.rating {
+        if let Some(rating) = &report.health {
             output.push_str(&format!(
                 "{} Overall Health: {}\n",
                 self.format_health_indicator(rating),
-                rating.description()
+                self.palette
+                    .paint(health_style(rating), rating.description())
             ));
         }
 
@@ -416,37 +480,36 @@ impl IntelligentPresenter {
         output.push_str(&format!(
             "{} Stars (Logic): {} ({} functions, {} methods)\n",
             self.emoji_formatter.notable_star(),
-            galaxy.totals.stars.count,
-            galaxy.totals.stars.functions,
-            galaxy.totals.stars.methods
+            report.stars,
+            report.functions,
+            report.methods
         ));
         output.push_str(&format!(
             "{} Nebulae (Docs): {} lines ({:.0}% coverage)\n",
             self.emoji_formatter.gem(),
-            galaxy.totals.nebulae.doc_lines + galaxy.totals.nebulae.comment_lines,
-            galaxy.totals.derived.nebula_ratio * 100.0
+            report.nebula_lines,
+            report.nebula_ratio * 100.0
         ));
         output.push_str(&format!(
             "{} Dark Matter: {} regions ({} volcanic)\n",
-            self.format_dark_matter_indicator(&galaxy.totals),
-            galaxy.totals.dark_matter.unknown_regions,
-            galaxy.totals.dark_matter.volcanic_regions
+            report.dark_matter.emoji(),
+            self.palette
+                .paint(report.dark_matter.style(), report.unknown_regions),
+            self.palette
+                .paint(report.dark_matter.style(), report.volcanic_regions)
         ));
 
         // Constellation breakdown (if detailed)
-        if matches!(
-            self.detail_level,
-            DetailLevel::Detailed | DetailLevel::Smart
-        ) {
+        if self.detail_level.shows_breakdowns() {
             output.push_str("\n");
             output.push_str(&format!(
                 "{} Constellations ({}):\n",
                 self.emoji_formatter.view_emoji(),
-                galaxy.constellations.len()
+                report.constellations.len()
             ));
 
-            for (path, constellation) in &galaxy.constellations {
-                let indicator = if let Some(rating) = &constellation.rating {
+            for constellation in &report.constellations {
+                let indicator = if let Some(rating) = &constellation.health {
                     self.format_health_indicator(rating)
                 } else {
                     "  ".to_string()
@@ -454,17 +517,19 @@ impl IntelligentPresenter {
 
                 output.push_str(&format!(
                     "  {} {}: {} stars, {} files\n",
-                    indicator, path, constellation.totals.stars.count, constellation.file_count
+                    indicator,
+                    self.palette.paint(Style::Accent, &constellation.path),
+                    constellation.stars,
+                    constellation.files
                 ));
 
                 // Show Red Giants (if any)
-                if !constellation.red_giants.is_empty()
-                    && matches!(self.detail_level, DetailLevel::Detailed)
-                {
+                if !constellation.red_giants.is_empty() && self.detail_level.shows_file_details() {
                     for rg in &constellation.red_giants {
                         output.push_str(&format!(
-                            "      {} Red Giant: {}\n",
+                            "      {} {}: {}\n",
                             self.emoji_formatter.insight_emoji(),
+                            self.palette.paint(Style::Bad, "Red Giant"),
                             rg
                         ));
                     }
@@ -475,19 +540,32 @@ impl IntelligentPresenter {
         // Recommendations
         output.push_str("\n");
         output.push_str(&format!(
-            "{} Recommendations:\n",
-            self.emoji_formatter.hint_emoji()
+            "{} {}:\n",
+            self.emoji_formatter.hint_emoji(),
+            self.palette.paint(Style::Heading, "Recommendations")
         ));
 
-        // Generate recommendations based on metrics
-        let recommendations = self.generate_recommendations(galaxy);
-        for rec in recommendations.iter().take(3) {
+        for rec in report
+            .recommendations
+            .iter()
+            .take(self.detail_level.list_limit(3))
+        {
             output.push_str(&format!("  {} {}\n", self.emoji_formatter.bullet(), rec));
         }
 
         output
     }
 
+    /// Format the Governance Report as SARIF 2.1.0 JSON.
+    ///
+    /// Emits Red Giants and unhealthy constellations; use
+    /// [`CensusSarifBuilder`](crate::core::census::sarif::CensusSarifBuilder)
+    /// directly to also include per-file volcanic regions and unknown nodes.
+    pub fn format_governance_sarif(&self, galaxy: &GalaxyCensus) -> String {
+        let log = crate::core::census::sarif::galaxy_to_sarif(galaxy);
+        serde_json::to_string_pretty(&log).unwrap_or_default()
+    }
+
     /// Format a health indicator emoji for a rating.
     pub fn format_health_indicator(&self, rating: &HealthRating) -> String {
         match rating {
@@ -500,67 +578,14 @@ impl IntelligentPresenter {
 
     /// Format dark matter indicator based on severity.
     fn format_dark_matter_indicator(&self, metrics: &CensusMetrics) -> String {
-        if metrics.dark_matter.unknown_regions == 0 && metrics.dark_matter.volcanic_regions == 0 {
-            "✨".to_string() // Clean
-        } else if metrics.derived.dark_matter_ratio < 0.05 {
-            "🌑".to_string() // Minor dark matter
-        } else {
-            "⚫".to_string() // Significant dark matter
-        }
+        DarkMatterSeverity::from_metrics(metrics)
+            .emoji()
+            .to_string()
     }
 
     /// Generate recommendations based on census metrics.
     fn generate_recommendations(&self, galaxy: &GalaxyCensus) -> Vec<String> {
-        let mut recs = Vec::new();
-
-        // Check documentation coverage
-        if galaxy.totals.derived.nebula_ratio < 0.2 {
-            recs.push("Increase documentation coverage (currently below 20%)".to_string());
-        }
-
-        // Check for volcanic regions
-        if galaxy.totals.dark_matter.volcanic_regions > 5 {
-            recs.push(format!(
-                "Review {} volcanic regions (deep nesting > 4 levels)",
-                galaxy.totals.dark_matter.volcanic_regions
-            ));
-        }
-
-        // Check for unknown regions
-        if galaxy.totals.dark_matter.unknown_regions > 0 {
-            recs.push(format!(
-                "Investigate {} unparsed regions (possible syntax issues)",
-                galaxy.totals.dark_matter.unknown_regions
-            ));
-        }
-
-        // Check stellar density
-        if galaxy.totals.derived.stellar_density > 30.0 {
-            recs.push(format!(
-                "Consider refactoring - high stellar density ({:.1} stars/1k LOC)",
-                galaxy.totals.derived.stellar_density
-            ));
-        }
-
-        // Count red giants
-        let red_giant_count: usize = galaxy
-            .constellations
-            .values()
-            .map(|c| c.red_giants.len())
-            .sum();
-        if red_giant_count > 0 {
-            recs.push(format!(
-                "Review {} Red Giants (large files with high complexity or low docs)",
-                red_giant_count
-            ));
-        }
-
-        // Default recommendation if all looks good
-        if recs.is_empty() {
-            recs.push("Codebase health is good - continue current practices".to_string());
-        }
-
-        recs
+        structured::recommendations(galaxy)
     }
 
     // =========================================================================
@@ -591,6 +616,28 @@ impl IntelligentPresenter {
         tectonic_shifts: &[crate::core::temporal::TectonicShift],
         ancient_stars: &[crate::core::temporal::AncientStar],
     ) -> String {
+        self.render_temporal_narrative(&TemporalNarrative::new(
+            galaxy_age_days,
+            total_observations,
+            observer_count,
+            supernovas,
+            tectonic_shifts,
+            ancient_stars,
+        ))
+    }
+
+    /// Build the temporal narrative as structured data.
+    #[cfg(feature = "temporal")]
+    pub fn temporal_narrative(
+        &self,
+        census: &crate::core::temporal::TemporalCensus,
+    ) -> TemporalNarrative {
+        TemporalNarrative::from_census(census)
+    }
+
+    /// Render a structured temporal narrative as terminal text.
+    #[cfg(feature = "temporal")]
+    pub fn render_temporal_narrative(&self, narrative: &TemporalNarrative) -> String {
         use std::fmt::Write;
         let mut output = String::new();
 
@@ -599,61 +646,59 @@ impl IntelligentPresenter {
         writeln!(output, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━").ok();
 
         // Line 1: Temporal Scan summary
-        let years = galaxy_age_days as f64 / 365.0;
+        let years = narrative.galaxy_age_days as f64 / 365.0;
         let years_str = if years >= 1.0 {
             format!("{:.1} years", years)
         } else {
-            format!("{} days", galaxy_age_days)
+            format!("{} days", narrative.galaxy_age_days)
         };
         writeln!(
             output,
             "⏳ Temporal Scan: {} of history analyzed ({} observations by {} observers).",
-            years_str, total_observations, observer_count
+            years_str, narrative.total_observations, narrative.observer_count
         )
         .ok();
 
         // Line 2: Volcanic activity (Supernovas + Tectonic Shifts)
-        let volcanic_count = supernovas.len() + tectonic_shifts.len();
+        let volcanic_count = narrative.supernovas.len() + narrative.tectonic_shifts.len();
         if volcanic_count > 0 {
-            if !supernovas.is_empty() {
-                let nova_names: Vec<&str> = supernovas
+            if !narrative.supernovas.is_empty() {
+                let nova_count = narrative.supernovas.len();
+                let nova_names: Vec<&str> = narrative
+                    .supernovas
                     .iter()
                     .take(2)
                     .map(|s| s.path.rsplit('/').next().unwrap_or(&s.path))
                     .collect();
-                let nova_summary = if supernovas.len() > 2 {
-                    format!(
-                        "{} and {} others",
-                        nova_names.join(", "),
-                        supernovas.len() - 2
-                    )
+                let nova_summary = if nova_count > 2 {
+                    format!("{} and {} others", nova_names.join(", "), nova_count - 2)
                 } else {
                     nova_names.join(", ")
                 };
                 writeln!(
                     output,
-                    "🌋 Volcanic Activity: {} Supernova{} detected ({}). Active refactoring zone!",
-                    supernovas.len(),
-                    if supernovas.len() == 1 { "" } else { "s" },
+                    "🌋 {}: {} Supernova{} detected ({}). Active refactoring zone!",
+                    self.palette.paint(Style::Warn, "Volcanic Activity"),
+                    nova_count,
+                    if nova_count == 1 { "" } else { "s" },
                     nova_summary
                 )
                 .ok();
             }
 
-            if !tectonic_shifts.is_empty() {
-                let shift_count = tectonic_shifts.len();
-                let high_risk_count = tectonic_shifts
-                    .iter()
-                    .filter(|s| s.risk_score > 0.7)
-                    .count();
+            if !narrative.tectonic_shifts.is_empty() {
+                let shift_count = narrative.tectonic_shifts.len();
+                let high_risk_count = narrative.high_risk_shifts;
                 if high_risk_count > 0 {
                     writeln!(
                         output,
-                        "⚠️  Tectonic Stress: {} shift{} identified ({} high-risk). Consider stabilization.",
+                        "⚠️  {}: {} shift{} identified ({} high-risk). Consider stabilization.",
+                        self.palette.paint(Style::Bad, "Tectonic Stress"),
                         shift_count,
                         if shift_count == 1 { "" } else { "s" },
                         high_risk_count
-                    ).ok();
+                    )
+                    .ok();
                 } else {
                     writeln!(
                         output,
@@ -667,13 +712,18 @@ impl IntelligentPresenter {
         } else {
             writeln!(
                 output,
-                "🌍 Geological Stability: No volcanic activity detected."
+                "🌍 {}: No volcanic activity detected.",
+                self.palette.paint(Style::Good, "Geological Stability")
             )
             .ok();
         }
 
         // Line 3: Ancient Stars (dormant core files)
-        let core_ancient: Vec<_> = ancient_stars.iter().filter(|a| a.is_core).collect();
+        let core_ancient: Vec<_> = narrative
+            .ancient_stars
+            .iter()
+            .filter(|a| a.is_core)
+            .collect();
         if !core_ancient.is_empty() {
             writeln!(
                 output,
@@ -683,7 +733,7 @@ impl IntelligentPresenter {
             ).ok();
 
             // Show top 2 ancient stars in detailed mode
-            if matches!(self.detail_level, DetailLevel::Detailed) {
+            if self.detail_level.shows_file_details() {
                 for ancient in core_ancient.iter().take(2) {
                     let file_name = ancient.path.rsplit('/').next().unwrap_or(&ancient.path);
                     writeln!(
@@ -694,12 +744,16 @@ impl IntelligentPresenter {
                     .ok();
                 }
             }
-        } else if !ancient_stars.is_empty() {
+        } else if !narrative.ancient_stars.is_empty() {
             writeln!(
                 output,
                 "📜 {} dormant file{} in archaeological strata (non-core, low priority).",
-                ancient_stars.len(),
-                if ancient_stars.len() == 1 { "" } else { "s" }
+                narrative.ancient_stars.len(),
+                if narrative.ancient_stars.len() == 1 {
+                    ""
+                } else {
+                    "s"
+                }
             )
             .ok();
         }
@@ -870,6 +924,15 @@ fn capitalize_first(s: &str) -> String {
     }
 }
 
+/// Color style for a health rating.
+fn health_style(rating: &HealthRating) -> Style {
+    match rating {
+        HealthRating::Healthy | HealthRating::Stable => Style::Good,
+        HealthRating::HighDarkMatter => Style::Warn,
+        HealthRating::Critical => Style::Bad,
+    }
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -1020,6 +1083,32 @@ mod tests {
         assert!(log.contains("50%"));
     }
 
+    #[test]
+    fn test_mission_log_palette() {
+        let render = |palette: Palette| {
+            IntelligentPresenter::new()
+                .with_palette(palette)
+                .format_mission_log(
+                    "project",
+                    ("Logic: Go", None),
+                    "auto",
+                    0.7,
+                    95_000,
+                    100_000,
+                    0,
+                    None,
+                )
+        };
+
+        let plain = render(Palette::plain());
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains("Fuel: 95,000 / 100,000 tokens (95%)."));
+
+        let colored = render(Palette::new(true));
+        assert!(colored.contains("\x1b[33m95%\x1b[0m"));
+        assert!(colored.contains("\x1b[1mproject\x1b[0m"));
+    }
+
     #[test]
     fn test_mission_log_points_of_interest() {
         let presenter = IntelligentPresenter::new();
@@ -1187,6 +1276,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_governance_sarif_is_valid_log() {
+        let presenter = IntelligentPresenter::new();
+        let mut galaxy = GalaxyCensus::new(".".to_string());
+        galaxy.finalize();
+        let sarif = presenter.format_governance_sarif(&galaxy);
+        let log = crate::core::sarif::SarifLog::parse(&sarif).unwrap();
+        assert_eq!(log.version, "2.1.0");
+        assert_eq!(log.runs[0].tool.driver.name, "voyager-observatory");
+    }
+
     #[test]
     fn test_governance_report_contains_header() {
         use crate::core::census::{CelestialCensus, GalaxyCensus};
@@ -1216,6 +1316,7 @@ mod tests {
             methods: 4,
             types: 2,
             constants: 1,
+            custom: Default::default(),
         };
         galaxy.add_file("src/test.rs", metrics);
         galaxy.finalize();
@@ -1320,6 +1421,7 @@ mod tests {
             methods: 0,
             types: 0,
             constants: 0,
+            custom: Default::default(),
         };
         metrics.nebulae = NebulaeMetrics {
             doc_lines: 20,
@@ -2187,6 +2289,7 @@ mod tests {
             methods: 0,
             types: 0,
             constants: 0,
+            custom: Default::default(),
         };
         metrics.nebulae = NebulaeMetrics {
             doc_lines: 30,
@@ -0,0 +1,496 @@
+//! Structured Presenter Output
+//!
+//! Serde-serializable counterparts of the presenter's narrated text:
+//! [`MissionLog`], [`GovernanceReport`] and (with the `temporal` feature)
+//! [`TemporalNarrative`]. GUIs render their own visuals from these instead
+//! of scraping the emoji text; the text formatters on
+//! [`IntelligentPresenter`](super::IntelligentPresenter) are rendered from
+//! the same structs, so both views always agree.
+
+use serde::{Deserialize, Serialize};
+
+use super::color::Style;
+use super::DriftInfo;
+use crate::core::census::{CensusMetrics, GalaxyCensus, HealthRating};
+
+// =============================================================================
+// Mission Log
+// =============================================================================
+
+/// Confidence bucket for the applied spectral filter (lens)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceLevel {
+    /// Confidence above 0.8
+    High,
+    /// Confidence above 0.5
+    Medium,
+    /// Anything lower
+    Low,
+}
+
+impl ConfidenceLevel {
+    /// Bucket a 0.0-1.0 confidence score
+    pub fn from_score(confidence: f32) -> Self {
+        if confidence > 0.8 {
+            Self::High
+        } else if confidence > 0.5 {
+            Self::Medium
+        } else {
+            Self::Low
+        }
+    }
+
+    /// Display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::High => "High Confidence",
+            Self::Medium => "Medium Confidence",
+            Self::Low => "Low Confidence",
+        }
+    }
+}
+
+/// Stellar drift classification by yearly drift rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftHealth {
+    /// Below 20%/year
+    Stable,
+    /// Below 50%/year
+    Active,
+    /// Below 100%/year
+    Expanding,
+    /// 100%/year or more
+    Volcanic,
+}
+
+impl DriftHealth {
+    /// Classify a drift rate (percent per year)
+    pub fn from_rate(drift_rate_per_year: f64) -> Self {
+        if drift_rate_per_year < 20.0 {
+            Self::Stable
+        } else if drift_rate_per_year < 50.0 {
+            Self::Active
+        } else if drift_rate_per_year < 100.0 {
+            Self::Expanding
+        } else {
+            Self::Volcanic
+        }
+    }
+
+    /// Display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Stable => "Stable",
+            Self::Active => "Active",
+            Self::Expanding => "Expanding",
+            Self::Volcanic => "Volcanic",
+        }
+    }
+
+    /// Indicator emoji
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Stable => "✅",
+            Self::Active => "📊",
+            Self::Expanding => "🚀",
+            Self::Volcanic => "🌋",
+        }
+    }
+
+    /// Color style for the label
+    pub fn style(&self) -> Style {
+        match self {
+            Self::Stable => Style::Good,
+            Self::Active => Style::Accent,
+            Self::Expanding => Style::Warn,
+            Self::Volcanic => Style::Bad,
+        }
+    }
+}
+
+/// Temporal drift section of the mission log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissionDrift {
+    /// Raw drift metrics
+    #[serde(flatten)]
+    pub info: DriftInfo,
+    /// Classification of `info.drift_rate_per_year`
+    pub health: DriftHealth,
+}
+
+/// The Voyager Mission Log as data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissionLog {
+    /// Project the observatory is pointed at
+    pub project_name: String,
+    /// Dominant language hemisphere
+    pub primary_hemisphere: String,
+    /// Second hemisphere, if any
+    pub secondary_hemisphere: Option<String>,
+    /// Applied spectral filter (lens)
+    pub lens: String,
+    /// Lens confidence (0.0-1.0)
+    pub confidence: f32,
+    /// Bucketed confidence
+    pub confidence_level: ConfidenceLevel,
+    /// Tokens used
+    pub tokens_used: usize,
+    /// Token budget
+    pub token_budget: usize,
+    /// Fuel used as a whole percentage of the budget (0 without a budget)
+    pub fuel_percent: usize,
+    /// Points of interest identified
+    pub points_of_interest: usize,
+    /// Cluster the points of interest were found in
+    pub nebula_name: Option<String>,
+    /// Temporal drift, when available
+    pub drift: Option<MissionDrift>,
+}
+
+impl MissionLog {
+    /// Build a mission log (see [`IntelligentPresenter::format_mission_log`](super::IntelligentPresenter::format_mission_log))
+    pub fn new(
+        project_name: &str,
+        hemispheres: (&str, Option<&str>),
+        lens: &str,
+        confidence: f32,
+        tokens_used: usize,
+        token_budget: usize,
+        poi_count: usize,
+        nebula_name: Option<&str>,
+    ) -> Self {
+        let fuel_percent = if token_budget > 0 {
+            (tokens_used as f64 / token_budget as f64 * 100.0) as usize
+        } else {
+            0
+        };
+
+        Self {
+            project_name: project_name.to_string(),
+            primary_hemisphere: hemispheres.0.to_string(),
+            secondary_hemisphere: hemispheres.1.map(str::to_string),
+            lens: lens.to_string(),
+            confidence,
+            confidence_level: ConfidenceLevel::from_score(confidence),
+            tokens_used,
+            token_budget,
+            fuel_percent,
+            points_of_interest: poi_count,
+            nebula_name: nebula_name.map(str::to_string),
+            drift: None,
+        }
+    }
+
+    /// Attach temporal drift metrics
+    pub fn with_drift(mut self, drift: Option<DriftInfo>) -> Self {
+        self.drift = drift.map(|info| MissionDrift {
+            health: DriftHealth::from_rate(info.drift_rate_per_year),
+            info,
+        });
+        self
+    }
+}
+
+// =============================================================================
+// Governance Report
+// =============================================================================
+
+/// Severity of the galaxy's dark matter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DarkMatterSeverity {
+    /// No unknown or volcanic regions
+    Clean,
+    /// Dark matter ratio below 5%
+    Minor,
+    /// Dark matter ratio 5% or above
+    Significant,
+}
+
+impl DarkMatterSeverity {
+    /// Classify census metrics
+    pub fn from_metrics(metrics: &CensusMetrics) -> Self {
+        if metrics.dark_matter.unknown_regions == 0 && metrics.dark_matter.volcanic_regions == 0 {
+            Self::Clean
+        } else if metrics.derived.dark_matter_ratio < 0.05 {
+            Self::Minor
+        } else {
+            Self::Significant
+        }
+    }
+
+    /// Indicator emoji
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Clean => "✨",
+            Self::Minor => "🌑",
+            Self::Significant => "⚫",
+        }
+    }
+
+    /// Color style for the region counts
+    pub fn style(&self) -> Style {
+        match self {
+            Self::Clean => Style::Good,
+            Self::Minor => Style::Warn,
+            Self::Significant => Style::Bad,
+        }
+    }
+}
+
+/// One constellation (directory) in the governance report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstellationSummary {
+    /// Directory path
+    pub path: String,
+    /// Health rating, if assessed
+    pub health: Option<HealthRating>,
+    /// Star (function/method) count
+    pub stars: usize,
+    /// File count
+    pub files: usize,
+    /// Files flagged as Red Giants
+    pub red_giants: Vec<String>,
+}
+
+/// The Governance Report as data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GovernanceReport {
+    /// Overall health rating
+    pub health: Option<HealthRating>,
+    /// Stars (functions + methods)
+    pub stars: usize,
+    /// Functions
+    pub functions: usize,
+    /// Methods
+    pub methods: usize,
+    /// Documentation and comment lines
+    pub nebula_lines: usize,
+    /// Documentation coverage ratio (0.0-1.0)
+    pub nebula_ratio: f64,
+    /// Unparsed regions
+    pub unknown_regions: usize,
+    /// Deeply nested regions
+    pub volcanic_regions: usize,
+    /// Dark matter classification
+    pub dark_matter: DarkMatterSeverity,
+    /// Constellations in path order
+    pub constellations: Vec<ConstellationSummary>,
+    /// Recommendations, most important first
+    pub recommendations: Vec<String>,
+}
+
+impl GovernanceReport {
+    /// Build the report from a galaxy census
+    pub fn from_galaxy(galaxy: &GalaxyCensus) -> Self {
+        let totals = &galaxy.totals;
+        Self {
+            health: galaxy.rating,
+            stars: totals.stars.count,
+            functions: totals.stars.functions,
+            methods: totals.stars.methods,
+            nebula_lines: totals.nebulae.doc_lines + totals.nebulae.comment_lines,
+            nebula_ratio: totals.derived.nebula_ratio,
+            unknown_regions: totals.dark_matter.unknown_regions,
+            volcanic_regions: totals.dark_matter.volcanic_regions,
+            dark_matter: DarkMatterSeverity::from_metrics(totals),
+            constellations: galaxy
+                .constellations
+                .iter()
+                .map(|(path, c)| ConstellationSummary {
+                    path: path.clone(),
+                    health: c.rating,
+                    stars: c.totals.stars.count,
+                    files: c.file_count,
+                    red_giants: c.red_giants.clone(),
+                })
+                .collect(),
+            recommendations: recommendations(galaxy),
+        }
+    }
+}
+
+/// Generate recommendations based on census metrics.
+pub(super) fn recommendations(galaxy: &GalaxyCensus) -> Vec<String> {
+    let mut recs = Vec::new();
+
+    // Check documentation coverage
+    if galaxy.totals.derived.nebula_ratio < 0.2 {
+        recs.push("Increase documentation coverage (currently below 20%)".to_string());
+    }
+
+    // Check for volcanic regions
+    if galaxy.totals.dark_matter.volcanic_regions > 5 {
+        recs.push(format!(
+            "Review {} volcanic regions (deep nesting > 4 levels)",
+            galaxy.totals.dark_matter.volcanic_regions
+        ));
+    }
+
+    // Check for unknown regions
+    if galaxy.totals.dark_matter.unknown_regions > 0 {
+        recs.push(format!(
+            "Investigate {} unparsed regions (possible syntax issues)",
+            galaxy.totals.dark_matter.unknown_regions
+        ));
+    }
+
+    // Check stellar density
+    if galaxy.totals.derived.stellar_density > 30.0 {
+        recs.push(format!(
+            "Consider refactoring - high stellar density ({:.1} stars/1k LOC)",
+            galaxy.totals.derived.stellar_density
+        ));
+    }
+
+    // Count red giants
+    let red_giant_count: usize = galaxy
+        .constellations
+        .values()
+        .map(|c| c.red_giants.len())
+        .sum();
+    if red_giant_count > 0 {
+        recs.push(format!(
+            "Review {} Red Giants (large files with high complexity or low docs)",
+            red_giant_count
+        ));
+    }
+
+    // Default recommendation if all looks good
+    if recs.is_empty() {
+        recs.push("Codebase health is good - continue current practices".to_string());
+    }
+
+    recs
+}
+
+// =============================================================================
+// Temporal Narrative
+// =============================================================================
+
+/// The temporal narrative (Geological Strata) as data
+#[cfg(feature = "temporal")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemporalNarrative {
+    /// Repository age in days
+    pub galaxy_age_days: u64,
+    /// Chronos events (commits) analyzed
+    pub total_observations: usize,
+    /// Unique observers (contributors)
+    pub observer_count: usize,
+    /// Files with extreme recent activity
+    pub supernovas: Vec<crate::core::temporal::Supernova>,
+    /// High-risk files (churn + complexity)
+    pub tectonic_shifts: Vec<crate::core::temporal::TectonicShift>,
+    /// Tectonic shifts with a risk score above 0.7
+    pub high_risk_shifts: usize,
+    /// Dormant files
+    pub ancient_stars: Vec<crate::core::temporal::AncientStar>,
+    /// Dormant files that are core to the logic
+    pub core_ancient_stars: usize,
+}
+
+#[cfg(feature = "temporal")]
+impl TemporalNarrative {
+    /// Build the narrative from its parts
+    pub fn new(
+        galaxy_age_days: u64,
+        total_observations: usize,
+        observer_count: usize,
+        supernovas: &[crate::core::temporal::Supernova],
+        tectonic_shifts: &[crate::core::temporal::TectonicShift],
+        ancient_stars: &[crate::core::temporal::AncientStar],
+    ) -> Self {
+        Self {
+            galaxy_age_days,
+            total_observations,
+            observer_count,
+            supernovas: supernovas.to_vec(),
+            tectonic_shifts: tectonic_shifts.to_vec(),
+            high_risk_shifts: tectonic_shifts
+                .iter()
+                .filter(|s| s.risk_score > 0.7)
+                .count(),
+            ancient_stars: ancient_stars.to_vec(),
+            core_ancient_stars: ancient_stars.iter().filter(|a| a.is_core).count(),
+        }
+    }
+
+    /// Build the narrative from a temporal census
+    pub fn from_census(census: &crate::core::temporal::TemporalCensus) -> Self {
+        Self::new(
+            census.galaxy_age_days,
+            census.total_observations,
+            census.observer_count,
+            &census.supernovas,
+            &census.tectonic_shifts,
+            &census.ancient_stars,
+        )
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mission_log_fields() {
+        let log = MissionLog::new(
+            "demo",
+            ("Logic: Rust", None),
+            "architecture",
+            0.6,
+            500,
+            1000,
+            3,
+            Some("core"),
+        );
+        assert_eq!(log.confidence_level, ConfidenceLevel::Medium);
+        assert_eq!(log.fuel_percent, 50);
+        assert!(log.drift.is_none());
+
+        let log = log.with_drift(Some(DriftInfo {
+            drift_rate_per_year: 60.0,
+            ..Default::default()
+        }));
+        assert_eq!(log.drift.unwrap().health, DriftHealth::Expanding);
+    }
+
+    #[test]
+    fn test_mission_log_json() {
+        let log = MissionLog::new("demo", ("Logic: Rust", None), "auto", 0.9, 0, 0, 0, None)
+            .with_drift(Some(DriftInfo::default()));
+        let json = serde_json::to_value(&log).unwrap();
+        assert_eq!(json["confidence_level"], "high");
+        assert_eq!(json["drift"]["health"], "stable");
+        assert_eq!(json["drift"]["galaxy_age_days"], 0);
+
+        let parsed: MissionLog = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, log);
+    }
+
+    #[test]
+    fn test_governance_report_from_galaxy() {
+        let mut galaxy = GalaxyCensus::new(".".to_string());
+        galaxy.totals.stars.count = 4;
+        galaxy.totals.dark_matter.unknown_regions = 1;
+        galaxy.totals.derived.dark_matter_ratio = 0.5;
+
+        let report = GovernanceReport::from_galaxy(&galaxy);
+        assert_eq!(report.stars, 4);
+        assert_eq!(report.dark_matter, DarkMatterSeverity::Significant);
+        assert!(report
+            .recommendations
+            .iter()
+            .any(|r| r.contains("unparsed regions")));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["dark_matter"], "significant");
+    }
+}
@@ -0,0 +1,683 @@
+//! SARIF ingestion for finding-focused context
+//!
+//! Reads SARIF 2.1.0 logs produced by linters and security scanners and turns
+//! each result into a focused excerpt of the offending file region, grouped
+//! by severity. The resulting bundle is meant for "fix these findings" agent
+//! tasks where the full project context would only dilute attention.
+//!
+//! Only the subset of the SARIF schema needed to locate findings is modelled;
+//! unknown properties are ignored.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::core::error::{EncoderError, Result};
+
+/// Default number of context lines shown around each finding region
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+// ============================================================================
+// SARIF Schema (subset)
+// ============================================================================
+
+/// Top-level SARIF log
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SarifLog {
+    /// JSON schema URI
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// SARIF schema version (e.g., "2.1.0")
+    #[serde(default)]
+    pub version: String,
+    /// Tool runs contained in the log
+    #[serde(default)]
+    pub runs: Vec<SarifRun>,
+}
+
+/// A single tool run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SarifRun {
+    /// Tool that produced the results
+    #[serde(default)]
+    pub tool: SarifTool,
+    /// Results reported by the tool
+    #[serde(default)]
+    pub results: Vec<SarifResult>,
+}
+
+/// Tool descriptor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SarifTool {
+    /// Primary tool component
+    #[serde(default)]
+    pub driver: SarifDriver,
+}
+
+/// Tool component (driver) with its rule catalogue
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifDriver {
+    /// Tool name (e.g., "clippy", "semgrep")
+    #[serde(default)]
+    pub name: String,
+    /// Tool version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Informational URI for the tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub information_uri: Option<String>,
+    /// Rules referenced by results
+    #[serde(default)]
+    pub rules: Vec<SarifRule>,
+}
+
+/// Rule metadata
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRule {
+    /// Stable rule identifier
+    pub id: String,
+    /// Human-readable rule name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// One-line description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_description: Option<SarifMessage>,
+    /// Full description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_description: Option<SarifMessage>,
+    /// Documentation link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help_uri: Option<String>,
+    /// Default configuration (carries the default level)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_configuration: Option<SarifRuleConfiguration>,
+}
+
+/// Rule default configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SarifRuleConfiguration {
+    /// Default level for results of this rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+/// A message object (only the plain-text form is used)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SarifMessage {
+    /// Plain-text message
+    #[serde(default)]
+    pub text: String,
+}
+
+/// A single result (finding)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    /// Rule identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    /// Index into the driver's rule array
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_index: Option<usize>,
+    /// Result level ("error", "warning", "note", "none")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    /// Result message
+    #[serde(default)]
+    pub message: SarifMessage,
+    /// Locations where the result was detected
+    #[serde(default)]
+    pub locations: Vec<SarifLocation>,
+    /// Tool-specific properties (property bag)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Result location
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLocation {
+    /// Physical location in an artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physical_location: Option<SarifPhysicalLocation>,
+}
+
+/// Physical location: artifact plus region
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifPhysicalLocation {
+    /// Artifact (file) reference
+    #[serde(default)]
+    pub artifact_location: SarifArtifactLocation,
+    /// Region within the artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<SarifRegion>,
+}
+
+/// Artifact reference
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SarifArtifactLocation {
+    /// Artifact URI (relative or file://)
+    #[serde(default)]
+    pub uri: String,
+}
+
+/// Line region (1-indexed, inclusive)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRegion {
+    /// First line of the region
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    /// Last line of the region (defaults to start_line)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+}
+
+impl SarifLog {
+    /// Parse a SARIF log from a JSON string
+    pub fn parse(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Load a SARIF log from a file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(EncoderError::FileNotFound {
+                path: path.to_path_buf(),
+            });
+        }
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Flatten every run into a list of findings
+    ///
+    /// Results without a physical location are skipped: there is no file
+    /// region to focus on.
+    pub fn findings(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for run in &self.runs {
+            let driver = &run.tool.driver;
+            let rules_by_id: HashMap<&str, &SarifRule> =
+                driver.rules.iter().map(|r| (r.id.as_str(), r)).collect();
+
+            for result in &run.results {
+                let rule = result
+                    .rule_index
+                    .and_then(|i| driver.rules.get(i))
+                    .or_else(|| {
+                        result
+                            .rule_id
+                            .as_deref()
+                            .and_then(|id| rules_by_id.get(id).copied())
+                    });
+
+                let rule_id = result
+                    .rule_id
+                    .clone()
+                    .or_else(|| rule.map(|r| r.id.clone()))
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                // SARIF level precedence: result.level > rule default > "warning"
+                let level = result.level.as_deref().or_else(|| {
+                    rule.and_then(|r| r.default_configuration.as_ref())
+                        .and_then(|c| c.level.as_deref())
+                });
+                let severity = level.map(Severity::parse).unwrap_or(Severity::Warning);
+
+                let rule_description = rule.and_then(|r| {
+                    r.full_description
+                        .as_ref()
+                        .or(r.short_description.as_ref())
+                        .map(|m| m.text.clone())
+                });
+
+                for location in &result.locations {
+                    let Some(physical) = &location.physical_location else {
+                        continue;
+                    };
+                    let region = physical.region.clone().unwrap_or_default();
+                    let start_line = region.start_line.unwrap_or(1).max(1);
+                    let end_line = region.end_line.unwrap_or(start_line).max(start_line);
+
+                    findings.push(Finding {
+                        tool: driver.name.clone(),
+                        rule_id: rule_id.clone(),
+                        rule_description: rule_description.clone(),
+                        help_uri: rule.and_then(|r| r.help_uri.clone()),
+                        severity,
+                        message: result.message.text.clone(),
+                        path: normalize_uri(&physical.artifact_location.uri),
+                        start_line,
+                        end_line,
+                    });
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+/// Strip `file://` schemes and leading `./` from a SARIF artifact URI
+fn normalize_uri(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let path = path.strip_prefix("./").unwrap_or(path);
+    path.replace("%20", " ")
+}
+
+// ============================================================================
+// Findings
+// ============================================================================
+
+/// Finding severity, ordered from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    /// SARIF "error"
+    Error,
+    /// SARIF "warning"
+    Warning,
+    /// SARIF "note"
+    Note,
+    /// SARIF "none"
+    None,
+}
+
+impl Severity {
+    /// Parse a SARIF level string (unknown levels map to Warning)
+    pub fn parse(level: &str) -> Self {
+        match level.to_lowercase().as_str() {
+            "error" => Severity::Error,
+            "note" => Severity::Note,
+            "none" => Severity::None,
+            _ => Severity::Warning,
+        }
+    }
+
+    /// SARIF level string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+            Severity::None => "none",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A single finding resolved to a file region
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Tool that reported the finding
+    pub tool: String,
+    /// Rule identifier
+    pub rule_id: String,
+    /// Rule description from the tool's rule catalogue
+    pub rule_description: Option<String>,
+    /// Rule documentation link
+    pub help_uri: Option<String>,
+    /// Finding severity
+    pub severity: Severity,
+    /// Result message
+    pub message: String,
+    /// Project-relative file path
+    pub path: String,
+    /// First line of the region (1-indexed)
+    pub start_line: usize,
+    /// Last line of the region (1-indexed, inclusive)
+    pub end_line: usize,
+}
+
+/// A finding together with the source excerpt around its region
+#[derive(Debug, Clone)]
+pub struct FindingExcerpt {
+    /// The finding
+    pub finding: Finding,
+    /// First line of the excerpt (1-indexed)
+    pub excerpt_start: usize,
+    /// Last line of the excerpt (1-indexed, inclusive)
+    pub excerpt_end: usize,
+    /// Excerpt text (None if the file could not be read)
+    pub excerpt: Option<String>,
+}
+
+/// Findings grouped by severity (most severe first)
+#[derive(Debug, Clone, Default)]
+pub struct FindingsBundle {
+    /// Excerpts keyed by severity
+    pub groups: BTreeMap<Severity, Vec<FindingExcerpt>>,
+}
+
+impl FindingsBundle {
+    /// Build a bundle by reading each finding's region from disk
+    ///
+    /// `context_lines` lines are added before and after each region.
+    pub fn build(root: &Path, findings: &[Finding], context_lines: usize) -> Self {
+        let mut file_cache: HashMap<String, Option<String>> = HashMap::new();
+        let mut bundle = Self::default();
+
+        for finding in findings {
+            let content = file_cache
+                .entry(finding.path.clone())
+                .or_insert_with(|| read_relative(root, &finding.path));
+            let excerpt = excerpt_for(finding, content.as_deref(), context_lines);
+            bundle
+                .groups
+                .entry(finding.severity)
+                .or_default()
+                .push(excerpt);
+        }
+
+        for excerpts in bundle.groups.values_mut() {
+            excerpts.sort_by(|a, b| {
+                a.finding
+                    .path
+                    .cmp(&b.finding.path)
+                    .then(a.finding.start_line.cmp(&b.finding.start_line))
+            });
+        }
+
+        bundle
+    }
+
+    /// Total number of findings in the bundle
+    pub fn len(&self) -> usize {
+        self.groups.values().map(|v| v.len()).sum()
+    }
+
+    /// Whether the bundle contains no findings
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Render as Plus/Minus-style text
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("=== SARIF Findings ({}) ===\n\n", self.len()));
+
+        for (severity, excerpts) in &self.groups {
+            output.push_str(&format!(
+                "## {} ({})\n\n",
+                severity.as_str().to_uppercase(),
+                excerpts.len()
+            ));
+
+            for item in excerpts {
+                let f = &item.finding;
+                output.push_str(&format!(
+                    "++++++++++ {}:{}-{} [{}] ++++++++++\n",
+                    f.path, item.excerpt_start, item.excerpt_end, f.rule_id
+                ));
+                output.push_str(&format!("# Rule: {}", f.rule_id));
+                if !f.tool.is_empty() {
+                    output.push_str(&format!(" ({})", f.tool));
+                }
+                output.push('\n');
+                if let Some(desc) = &f.rule_description {
+                    output.push_str(&format!("# Description: {}\n", desc));
+                }
+                output.push_str(&format!(
+                    "# Finding (lines {}-{}): {}\n",
+                    f.start_line, f.end_line, f.message
+                ));
+                match &item.excerpt {
+                    Some(text) => {
+                        output.push_str(text);
+                        if !text.ends_with('\n') {
+                            output.push('\n');
+                        }
+                    }
+                    None => output.push_str("# (file not found in project)\n"),
+                }
+                output.push_str(&format!(
+                    "---------- {}:{}-{} ----------\n\n",
+                    f.path, item.excerpt_start, item.excerpt_end
+                ));
+            }
+        }
+
+        output
+    }
+
+    /// Render as XML
+    pub fn to_xml(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("<findings count=\"{}\">\n", self.len()));
+
+        for (severity, excerpts) in &self.groups {
+            output.push_str(&format!(
+                "  <severity level=\"{}\" count=\"{}\">\n",
+                severity,
+                excerpts.len()
+            ));
+
+            for item in excerpts {
+                let f = &item.finding;
+                output.push_str(&format!(
+                    "    <finding rule=\"{}\" tool=\"{}\" path=\"{}\" start_line=\"{}\" end_line=\"{}\">\n",
+                    escape_attr(&f.rule_id),
+                    escape_attr(&f.tool),
+                    escape_attr(&f.path),
+                    f.start_line,
+                    f.end_line
+                ));
+                output.push_str(&format!(
+                    "      <message>{}</message>\n",
+                    escape_text(&f.message)
+                ));
+                if let Some(desc) = &f.rule_description {
+                    output.push_str(&format!(
+                        "      <rule_description>{}</rule_description>\n",
+                        escape_text(desc)
+                    ));
+                }
+                if let Some(text) = &item.excerpt {
+                    output.push_str(&format!(
+                        "      <excerpt start_line=\"{}\" end_line=\"{}\"><![CDATA[\n{}\n]]></excerpt>\n",
+                        item.excerpt_start,
+                        item.excerpt_end,
+                        text.trim_end_matches('\n').replace("]]>", "]]]]><![CDATA[>")
+                    ));
+                }
+                output.push_str("    </finding>\n");
+            }
+
+            output.push_str("  </severity>\n");
+        }
+
+        output.push_str("</findings>\n");
+        output
+    }
+}
+
+/// Read a project-relative file, refusing paths that escape the root
+fn read_relative(root: &Path, rel: &str) -> Option<String> {
+    let candidate = Path::new(rel);
+    let path = if candidate.is_absolute() {
+        candidate.to_path_buf()
+    } else {
+        root.join(candidate)
+    };
+
+    let canonical_root = root.canonicalize().ok()?;
+    let canonical = path.canonicalize().ok()?;
+    if !canonical.starts_with(&canonical_root) {
+        return None;
+    }
+
+    let bytes = fs::read(&canonical).ok()?;
+    crate::read_file_content(&bytes)
+}
+
+/// Cut the excerpt for a finding out of the file content
+fn excerpt_for(finding: &Finding, content: Option<&str>, context_lines: usize) -> FindingExcerpt {
+    let Some(content) = content else {
+        return FindingExcerpt {
+            finding: finding.clone(),
+            excerpt_start: finding.start_line,
+            excerpt_end: finding.end_line,
+            excerpt: None,
+        };
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let total = lines.len().max(1);
+    let start = finding
+        .start_line
+        .saturating_sub(context_lines)
+        .max(1)
+        .min(total);
+    let end = (finding.end_line + context_lines).min(total).max(start);
+
+    let mut excerpt = String::new();
+    for (offset, line) in lines[start - 1..end.min(lines.len())].iter().enumerate() {
+        let line_no = start + offset;
+        let marker = if line_no >= finding.start_line && line_no <= finding.end_line {
+            '>'
+        } else {
+            ' '
+        };
+        excerpt.push_str(&format!("{}{:>5} | {}\n", marker, line_no, line));
+    }
+
+    FindingExcerpt {
+        finding: finding.clone(),
+        excerpt_start: start,
+        excerpt_end: end,
+        excerpt: Some(excerpt),
+    }
+}
+
+fn escape_text(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attr(s: &str) -> String {
+    escape_text(s).replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SAMPLE: &str = r#"{
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {"driver": {
+                "name": "clippy",
+                "rules": [
+                    {"id": "unwrap_used", "shortDescription": {"text": "Avoid unwrap"},
+                     "defaultConfiguration": {"level": "warning"}},
+                    {"id": "panic", "fullDescription": {"text": "Panics in library code"},
+                     "defaultConfiguration": {"level": "error"}}
+                ]
+            }},
+            "results": [
+                {"ruleId": "unwrap_used", "message": {"text": "called unwrap"},
+                 "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": "src/lib.rs"},
+                    "region": {"startLine": 5}}}]},
+                {"ruleId": "panic", "ruleIndex": 1, "message": {"text": "explicit panic"},
+                 "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": "file://src/main.rs"},
+                    "region": {"startLine": 2, "endLine": 3}}}]},
+                {"ruleId": "panic", "message": {"text": "no location"}}
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn test_parse_findings() {
+        let log = SarifLog::parse(SAMPLE).unwrap();
+        let findings = log.findings();
+        assert_eq!(findings.len(), 2);
+
+        let unwrap = &findings[0];
+        assert_eq!(unwrap.rule_id, "unwrap_used");
+        assert_eq!(unwrap.severity, Severity::Warning);
+        assert_eq!(unwrap.rule_description.as_deref(), Some("Avoid unwrap"));
+        assert_eq!((unwrap.start_line, unwrap.end_line), (5, 5));
+
+        let panic = &findings[1];
+        assert_eq!(panic.severity, Severity::Error);
+        assert_eq!(panic.path, "src/main.rs");
+        assert_eq!(
+            panic.rule_description.as_deref(),
+            Some("Panics in library code")
+        );
+    }
+
+    #[test]
+    fn test_severity_parse_and_order() {
+        assert_eq!(Severity::parse("ERROR"), Severity::Error);
+        assert_eq!(Severity::parse("bogus"), Severity::Warning);
+        assert!(Severity::Error < Severity::Warning);
+        assert!(Severity::Warning < Severity::Note);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(SarifLog::parse("not json").is_err());
+    }
+
+    #[test]
+    fn test_bundle_groups_by_severity_with_excerpts() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("src")).unwrap();
+        let lib: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        fs::write(temp.path().join("src/lib.rs"), lib).unwrap();
+        fs::write(
+            temp.path().join("src/main.rs"),
+            "fn main() {\n    panic!();\n}\n",
+        )
+        .unwrap();
+
+        let findings = SarifLog::parse(SAMPLE).unwrap().findings();
+        let bundle = FindingsBundle::build(temp.path(), &findings, 1);
+
+        assert_eq!(bundle.len(), 2);
+        let first_group = bundle.groups.keys().next().copied();
+        assert_eq!(first_group, Some(Severity::Error));
+
+        let warning = &bundle.groups[&Severity::Warning][0];
+        assert_eq!((warning.excerpt_start, warning.excerpt_end), (4, 6));
+        let excerpt = warning.excerpt.as_ref().unwrap();
+        assert!(excerpt.contains(">    5 | line 5"));
+        assert!(excerpt.contains("     4 | line 4"));
+
+        let text = bundle.to_text();
+        assert!(text.find("## ERROR").unwrap() < text.find("## WARNING").unwrap());
+        assert!(text.contains("# Description: Avoid unwrap"));
+
+        let xml = bundle.to_xml();
+        assert!(xml.contains("<severity level=\"error\" count=\"1\">"));
+        assert!(xml.contains("<rule_description>Panics in library code</rule_description>"));
+    }
+
+    #[test]
+    fn test_bundle_missing_file() {
+        let temp = TempDir::new().unwrap();
+        let findings = SarifLog::parse(SAMPLE).unwrap().findings();
+        let bundle = FindingsBundle::build(temp.path(), &findings, 3);
+        assert!(bundle
+            .groups
+            .values()
+            .flatten()
+            .all(|e| e.excerpt.is_none()));
+        assert!(bundle.to_text().contains("file not found"));
+    }
+}
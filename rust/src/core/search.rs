@@ -226,6 +226,29 @@ impl SymbolResolver {
         ))
     }
 
+    /// Every definition of `name` in one file, of any symbol type
+    pub fn find_definitions(&self, path: &str, content: &str, name: &str) -> Vec<SymbolLocation> {
+        let mut results: Vec<SymbolLocation> = Vec::new();
+        for symbol_type in [
+            SymbolType::Function,
+            SymbolType::Struct,
+            SymbolType::Enum,
+            SymbolType::Trait,
+            SymbolType::Class,
+        ] {
+            for loc in self
+                .find_in_file(path, content, name, symbol_type)
+                .unwrap_or_default()
+            {
+                if !results.iter().any(|r| r.start_line == loc.start_line) {
+                    results.push(loc);
+                }
+            }
+        }
+        results.sort_by_key(|r| r.start_line);
+        results
+    }
+
     /// Find symbols in a single file
     fn find_in_file(
         &self,
@@ -638,6 +661,182 @@ impl CallGraphAnalyzer {
     }
 }
 
+/// Default traversal depth for call graph export
+pub const DEFAULT_CALLGRAPH_DEPTH: usize = 3;
+
+/// A node in an exported call graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraphNode {
+    /// Function name
+    pub name: String,
+    /// File path relative to project root
+    pub path: String,
+    /// Definition start line (1-indexed)
+    pub line: usize,
+}
+
+/// Resolved call graph rooted at a single symbol
+///
+/// Nodes are keyed by function name; edges are (caller, callee) pairs.
+/// Both are kept in sorted collections so renders are deterministic.
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    /// Root symbol name
+    pub root: String,
+    /// Nodes by function name
+    pub nodes: std::collections::BTreeMap<String, CallGraphNode>,
+    /// Caller -> callee edges
+    pub edges: std::collections::BTreeSet<(String, String)>,
+}
+
+impl CallGraph {
+    /// Render as a Graphviz DOT digraph
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph callgraph {\n");
+        out.push_str("    rankdir=LR;\n");
+        out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        for node in self.nodes.values() {
+            let style = if node.name == self.root {
+                ", style=bold"
+            } else {
+                ""
+            };
+            out.push_str(&format!(
+                "    \"{}\" [label=\"{}\\n{}:{}\"{}];\n",
+                dot_escape(&node.name),
+                dot_escape(&node.name),
+                dot_escape(&node.path),
+                node.line,
+                style
+            ));
+        }
+        for (from, to) in &self.edges {
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\";\n",
+                dot_escape(from),
+                dot_escape(to)
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart (paste into Markdown inside a `mermaid` fence)
+    pub fn to_mermaid(&self) -> String {
+        let ids: std::collections::BTreeMap<&str, String> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), format!("n{}", i)))
+            .collect();
+
+        let mut out = String::from("flowchart LR\n");
+        for node in self.nodes.values() {
+            out.push_str(&format!(
+                "    {}[\"{}<br/><small>{}:{}</small>\"]\n",
+                ids[node.name.as_str()],
+                mermaid_escape(&node.name),
+                mermaid_escape(&node.path),
+                node.line
+            ));
+        }
+        for (from, to) in &self.edges {
+            if let (Some(a), Some(b)) = (ids.get(from.as_str()), ids.get(to.as_str())) {
+                out.push_str(&format!("    {} --> {}\n", a, b));
+            }
+        }
+        if let Some(root) = ids.get(self.root.as_str()) {
+            out.push_str(&format!("    style {} stroke-width:3px\n", root));
+        }
+        out
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+impl CallGraphAnalyzer {
+    /// Walk resolved calls outward from `root_symbol`, up to `max_depth` hops
+    ///
+    /// Only calls that resolve to a definition in the codebase become nodes;
+    /// each function is expanded at most once, so recursion and cycles
+    /// terminate. Fails if the root symbol cannot be resolved.
+    pub fn build_graph(
+        &self,
+        root_symbol: &str,
+        resolver: &SymbolResolver,
+        root: &Path,
+        max_depth: usize,
+    ) -> Result<CallGraph, String> {
+        let root_loc = resolver.find_function(root_symbol, root)?;
+        let mut graph = CallGraph {
+            root: root_loc.name.clone(),
+            ..Default::default()
+        };
+
+        let mut queue = std::collections::VecDeque::new();
+        graph
+            .nodes
+            .insert(root_loc.name.clone(), node_for(&root_loc));
+        queue.push_back((root_loc, 0usize));
+
+        while let Some((loc, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            let body = match read_symbol_body(root, &loc) {
+                Some(b) => b,
+                None => continue,
+            };
+
+            for (call, callee) in self.get_valid_calls(&body, resolver, root) {
+                if call.name == loc.name && callee.path == loc.path {
+                    // Direct recursion: record the loop, nothing new to expand
+                    graph.edges.insert((loc.name.clone(), loc.name.clone()));
+                    continue;
+                }
+                graph.edges.insert((loc.name.clone(), callee.name.clone()));
+                if !graph.nodes.contains_key(&callee.name) {
+                    graph.nodes.insert(callee.name.clone(), node_for(&callee));
+                    queue.push_back((callee, depth + 1));
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+fn node_for(loc: &SymbolLocation) -> CallGraphNode {
+    CallGraphNode {
+        name: loc.name.clone(),
+        path: loc.path.clone(),
+        line: loc.start_line,
+    }
+}
+
+/// Read the body of a resolved symbol, excluding its signature line
+///
+/// The signature itself (`fn name(`) would otherwise register as a call.
+fn read_symbol_body(root: &Path, loc: &SymbolLocation) -> Option<String> {
+    let content = std::fs::read_to_string(root.join(&loc.path)).ok()?;
+    let first_body_line = loc.start_line.max(1);
+    let len = loc.end_line.saturating_sub(first_body_line);
+    Some(
+        content
+            .lines()
+            .skip(first_body_line)
+            .take(len)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
 // ============================================================================
 // Reverse Call Graph - Find Usages (Phase 2)
 // ============================================================================
@@ -1716,4 +1915,72 @@ mod tests {
         assert_eq!(suggestion.path, "src/lib.rs");
         assert_eq!(suggestion.lines, (10, 20));
     }
+
+    #[test]
+    fn test_build_call_graph_and_render() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("lib.rs"),
+            "fn entry() {\n    parse();\n    render();\n}\n\nfn parse() {\n    tokenize();\n    parse();\n}\n\nfn tokenize() {\n}\n\nfn render() {\n}\n",
+        )
+        .unwrap();
+
+        let analyzer = CallGraphAnalyzer::new();
+        let resolver = SymbolResolver::new();
+        let graph = analyzer
+            .build_graph("entry", &resolver, temp_dir.path(), DEFAULT_CALLGRAPH_DEPTH)
+            .unwrap();
+
+        let names: Vec<&str> = graph.nodes.keys().map(|s| s.as_str()).collect();
+        assert_eq!(names, vec!["entry", "parse", "render", "tokenize"]);
+        assert!(graph
+            .edges
+            .contains(&("entry".to_string(), "parse".to_string())));
+        assert!(graph
+            .edges
+            .contains(&("parse".to_string(), "tokenize".to_string())));
+        assert!(graph
+            .edges
+            .contains(&("parse".to_string(), "parse".to_string())));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph callgraph {"));
+        assert!(dot.contains("\"entry\" -> \"parse\";"));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains(" --> "));
+    }
+
+    #[test]
+    fn test_build_call_graph_depth_limit() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("lib.rs"),
+            "fn a() {\n    b();\n}\n\nfn b() {\n    c();\n}\n\nfn c() {\n}\n",
+        )
+        .unwrap();
+
+        let graph = CallGraphAnalyzer::new()
+            .build_graph("a", &SymbolResolver::new(), temp_dir.path(), 1)
+            .unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(!graph.nodes.contains_key("c"));
+    }
+
+    #[test]
+    fn test_build_call_graph_unknown_root() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let result = CallGraphAnalyzer::new().build_graph(
+            "missing",
+            &SymbolResolver::new(),
+            temp_dir.path(),
+            DEFAULT_CALLGRAPH_DEPTH,
+        );
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,195 @@
+//! Sidecar manifest for serialization runs
+//!
+//! `<output>.manifest.json` records, for every file in the output, its
+//! checksum, size, token estimate and the truncation applied, together with
+//! the active lens and token budget. CI can compare manifests between runs
+//! to detect context drift without diffing the serialized output itself.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::core::error::Result;
+
+/// Suffix appended to the output path
+pub const SIDECAR_SUFFIX: &str = ".manifest.json";
+
+/// Manifest describing one serialization run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarManifest {
+    /// Encoder version that produced the output
+    pub encoder_version: String,
+    /// Output format name
+    pub format: String,
+    /// Generation timestamp (omitted in frozen mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    /// Active lens, if any
+    pub lens: Option<String>,
+    /// Token budget, if any
+    pub token_budget: Option<usize>,
+    /// Configured truncation limit (0 = none)
+    pub truncate_lines: usize,
+    /// Configured truncation mode
+    pub truncate_mode: String,
+    /// Token estimate of the whole output
+    pub total_tokens: usize,
+    /// Output parts written (one entry unless the output was split)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<String>,
+    /// Files in output order
+    pub files: Vec<SidecarFileEntry>,
+}
+
+/// Per-file manifest entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarFileEntry {
+    /// Project-relative path
+    pub path: String,
+    /// MD5 of the original content
+    pub md5: String,
+    /// Original size in bytes
+    pub size: u64,
+    /// Token estimate of the content as emitted
+    pub tokens: usize,
+    /// Truncation mode applied to this file ("none" when untouched)
+    pub truncation: String,
+    /// Line count before truncation
+    pub original_lines: usize,
+    /// Line count after truncation
+    pub final_lines: usize,
+    /// Output-line to original-line ranges (truncated files only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_map: Vec<LineMapRange>,
+}
+
+/// A run of emitted lines that maps onto consecutive original lines
+///
+/// Line numbers are 1-based; output lines count from the first content line
+/// of the file in the serialized output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineMapRange {
+    /// First output line of the run
+    pub output: usize,
+    /// Original line of the first output line
+    pub original: usize,
+    /// Number of lines in the run
+    pub lines: usize,
+}
+
+impl SidecarFileEntry {
+    /// Original line for an output line, if it maps onto one
+    ///
+    /// Files without a line map were emitted in full, so lines map to
+    /// themselves.
+    pub fn original_line(&self, output_line: usize) -> Option<usize> {
+        if self.line_map.is_empty() {
+            return (output_line >= 1 && output_line <= self.final_lines).then_some(output_line);
+        }
+        self.line_map
+            .iter()
+            .find(|r| output_line >= r.output && output_line < r.output + r.lines)
+            .map(|r| r.original + (output_line - r.output))
+    }
+}
+
+impl SidecarManifest {
+    /// Manifest path for an output file: `out.txt` -> `out.txt.manifest.json`
+    pub fn path_for(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_os_string();
+        name.push(SIDECAR_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Write the manifest as pretty-printed JSON
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// Read a manifest written by [`SidecarManifest::write_to`]
+    pub fn read_from(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_path_for_appends_suffix() {
+        assert_eq!(
+            SidecarManifest::path_for(Path::new("out/context.txt")),
+            PathBuf::from("out/context.txt.manifest.json")
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("context.txt.manifest.json");
+        let manifest = SidecarManifest {
+            encoder_version: "1.0.0".to_string(),
+            format: "plus_minus".to_string(),
+            generated_at: None,
+            lens: Some("debug".to_string()),
+            token_budget: Some(1000),
+            truncate_lines: 10,
+            truncate_mode: "simple".to_string(),
+            total_tokens: 42,
+            parts: vec![],
+            files: vec![SidecarFileEntry {
+                path: "src/main.rs".to_string(),
+                md5: "abc".to_string(),
+                size: 12,
+                tokens: 3,
+                truncation: "none".to_string(),
+                original_lines: 1,
+                final_lines: 1,
+                line_map: vec![],
+            }],
+        };
+        manifest.write_to(&path).unwrap();
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(!json.contains("generated_at"));
+        assert!(!json.contains("parts"));
+        assert_eq!(SidecarManifest::read_from(&path).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_original_line_lookup() {
+        let mut entry = SidecarFileEntry {
+            path: "a.py".to_string(),
+            md5: "abc".to_string(),
+            size: 100,
+            tokens: 10,
+            truncation: "none".to_string(),
+            original_lines: 5,
+            final_lines: 5,
+            line_map: vec![],
+        };
+        assert_eq!(entry.original_line(3), Some(3));
+        assert_eq!(entry.original_line(6), None);
+
+        // Lines 1-8 kept, a marker at output line 10, then 95-100
+        entry.line_map = vec![
+            LineMapRange {
+                output: 1,
+                original: 1,
+                lines: 8,
+            },
+            LineMapRange {
+                output: 12,
+                original: 95,
+                lines: 6,
+            },
+        ];
+        assert_eq!(entry.original_line(8), Some(8));
+        assert_eq!(entry.original_line(10), None);
+        assert_eq!(entry.original_line(13), Some(96));
+    }
+}
@@ -23,6 +23,9 @@
 //! - **Nebulae**: Comment blocks (single-line and multi-line)
 
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use regex::Regex;
 
 // =============================================================================
 // STELLAR LIBRARY - Core Pattern Repository
@@ -99,6 +102,165 @@ impl SpectralSignature {
 
         (total, code, comments, blanks)
     }
+
+    /// `content` without its comments, and how many lines lost one
+    ///
+    /// A line goes when it starts (past indentation) with a single-line
+    /// comment or is entirely inside block comments. A block comment that
+    /// closes mid-line is cut out and the code after it stays, so
+    /// `/* note */ let a = 1;` keeps `let a = 1;`. Blocks whose delimiters
+    /// are the same at both ends (Python's `"""`) are docstrings rather than
+    /// comments and stay, as do shebangs and `#[...]` attributes in languages
+    /// that comment with `#`. Line endings are kept as they are.
+    pub fn strip_comments(&self, content: &str) -> (String, usize) {
+        self.strip_comments_keeping(content, |_| false)
+    }
+
+    /// Like [`Self::strip_comments`], keeping the comment lines `keep`
+    /// accepts (without their line ending) whole
+    pub fn strip_comments_keeping(
+        &self,
+        content: &str,
+        keep: impl Fn(&str) -> bool,
+    ) -> (String, usize) {
+        let patterns = self.comment_patterns();
+
+        let mut kept = String::with_capacity(content.len());
+        let mut removed = 0;
+        let mut in_block = false;
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            let body = line.trim_end_matches(['\n', '\r']);
+            let kept_anyway = keep(body);
+            let (indent, code, stripped) = patterns.strip_blocks(body, &mut in_block);
+            if kept_anyway {
+                kept.push_str(line);
+                continue;
+            }
+            if stripped {
+                removed += 1;
+                let only_comment = code.is_empty()
+                    || patterns
+                        .single
+                        .as_ref()
+                        .is_some_and(|single| single.is_match(code));
+                if !only_comment {
+                    kept.push_str(indent);
+                    kept.push_str(code);
+                    kept.push_str(&line[body.len()..]);
+                }
+                continue;
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                kept.push_str(line);
+                continue;
+            }
+            let keep_hash_line = (i == 0 && trimmed.starts_with("#!")) || trimmed.starts_with("#[");
+            if !keep_hash_line
+                && patterns
+                    .single
+                    .as_ref()
+                    .is_some_and(|single| single.is_match(line))
+            {
+                removed += 1;
+                continue;
+            }
+            kept.push_str(line);
+        }
+        (kept, removed)
+    }
+
+    /// This signature's comment patterns, compiled once and shared
+    fn comment_patterns(&self) -> Arc<CommentPatterns> {
+        let key = (
+            self.comment_single,
+            self.comment_multi_start,
+            self.comment_multi_end,
+        );
+        let mut cache = COMMENT_PATTERNS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache
+            .entry(key)
+            .or_insert_with(|| Arc::new(CommentPatterns::new(self)))
+            .clone()
+    }
+}
+
+/// Compiled comment patterns of a [`SpectralSignature`]
+#[derive(Debug)]
+struct CommentPatterns {
+    /// Single-line comment, anchored past indentation
+    single: Option<Regex>,
+    /// Block comment start, anchored past indentation; `None` when the
+    /// block delimiters are the same at both ends
+    block_start: Option<Regex>,
+    /// Block comment end, anywhere
+    block_end: Option<Regex>,
+}
+
+impl CommentPatterns {
+    fn new(signature: &SpectralSignature) -> Self {
+        let anchored = |pattern: &str| Regex::new(&format!(r"^\s*(?:{})", pattern)).ok();
+        let (block_start, block_end) =
+            if signature.comment_multi_start == signature.comment_multi_end {
+                (None, None)
+            } else {
+                (
+                    anchored(signature.comment_multi_start),
+                    Regex::new(signature.comment_multi_end).ok(),
+                )
+            };
+        Self {
+            single: anchored(signature.comment_single),
+            block_start,
+            block_end,
+        }
+    }
+
+    /// Cut the block comments out of the start of `line`, returning the
+    /// indentation to keep, the code left after them, and whether anything was cut
+    ///
+    /// `in_block` carries an unclosed block comment from line to line.
+    fn strip_blocks<'a>(&self, line: &'a str, in_block: &mut bool) -> (&'a str, &'a str, bool) {
+        let code = line.trim_start();
+        // Inside a block, leading space lines up the comment, not the code
+        let indent = if *in_block {
+            ""
+        } else {
+            &line[..line.len() - code.len()]
+        };
+        let mut code = code;
+        let mut stripped = false;
+        loop {
+            if *in_block {
+                stripped = true;
+                match self.block_end.as_ref().and_then(|end| end.find(code)) {
+                    Some(end) => {
+                        code = code[end.end()..].trim_start();
+                        *in_block = false;
+                    }
+                    None => return (indent, "", true),
+                }
+            }
+            match self.block_start.as_ref().and_then(|start| start.find(code)) {
+                Some(start) => {
+                    code = &code[start.end()..];
+                    *in_block = true;
+                }
+                None => return (indent, code, stripped),
+            }
+        }
+    }
+}
+
+/// (single, block start, block end) comment patterns of a signature
+type CommentKey = (&'static str, &'static str, &'static str);
+
+lazy_static::lazy_static! {
+    /// [`CommentPatterns`] by the patterns they were compiled from
+    static ref COMMENT_PATTERNS: Mutex<HashMap<CommentKey, Arc<CommentPatterns>>> =
+        Mutex::new(HashMap::new());
 }
 
 /// Language hemisphere classification for Mission Log
@@ -1690,6 +1852,55 @@ mod tests {
         assert!(rust.extensions.contains(&"rs"));
     }
 
+    #[test]
+    fn test_strip_comments() {
+        let library = StellarLibrary::new();
+
+        let rust = library.get("rust").unwrap();
+        let source = "//! Crate docs\n#[derive(Debug)]\nstruct A; // trailing stays\n\n/*\n * Block\n */\nfn a() {\n    // inside\n    let s = \"//\";\n}\n";
+        let (stripped, removed) = rust.strip_comments(source);
+        assert_eq!(
+            stripped,
+            "#[derive(Debug)]\nstruct A; // trailing stays\n\nfn a() {\n    let s = \"//\";\n}\n"
+        );
+        assert_eq!(removed, 5);
+
+        // Docstrings and shebangs stay
+        let python = library.get("python").unwrap();
+        let source = "#!/usr/bin/env python\n# comment\ndef f():\n    \"\"\"Doc.\"\"\"\n    return 1  # why\n";
+        let (stripped, removed) = python.strip_comments(source);
+        assert_eq!(
+            stripped,
+            "#!/usr/bin/env python\ndef f():\n    \"\"\"Doc.\"\"\"\n    return 1  # why\n"
+        );
+        assert_eq!(removed, 1);
+
+        // Nothing to strip in JSON
+        let json = library.get("json").unwrap();
+        assert_eq!(
+            json.strip_comments("{\"a\": 1}"),
+            ("{\"a\": 1}".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn test_strip_comments_keeps_code_after_block_close() {
+        let library = StellarLibrary::new();
+
+        let c = library.get("c").unwrap();
+        let (stripped, removed) =
+            c.strip_comments("/* inline */ int b = 2;\n    /* a */ /* b */\n");
+        assert_eq!(stripped, "int b = 2;\n");
+        assert_eq!(removed, 2);
+
+        let javascript = library.get("javascript").unwrap();
+        let source =
+            "/** @type {number} */ let b = 2;\n/*\n * Block\n */ let c = 3; // why\n/* x */ // y\n";
+        let (stripped, removed) = javascript.strip_comments(source);
+        assert_eq!(stripped, "let b = 2;\nlet c = 3; // why\n");
+        assert_eq!(removed, 5);
+    }
+
     #[test]
     fn test_multiple_extensions() {
         let library = StellarLibrary::new();
@@ -14,6 +14,8 @@ use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::Path;
 
+use crate::migrate::StateKind;
+
 /// Default EMA alpha coefficient for utility score updates
 /// Higher alpha = more weight on recent feedback, faster adaptation
 /// Lower alpha = more weight on historical data, slower but more stable
@@ -228,13 +230,19 @@ impl ContextStore {
     }
 
     /// Load from file path, returning default if file doesn't exist or is malformed
+    ///
+    /// Stores written by older releases are upgraded (see [`crate::migrate`]).
     pub fn load_from_file(path: &Path) -> Self {
         if !path.exists() {
             return Self::default();
         }
 
         match std::fs::read_to_string(path) {
-            Ok(content) => Self::from_json(&content).unwrap_or_default(),
+            Ok(content) => crate::migrate::load_upgraded(StateKind::Store, &content)
+                .unwrap_or_else(|e| {
+                    eprintln!("Warning: ignoring {}: {}", path.display(), e);
+                    Self::default()
+                }),
             Err(_) => Self::default(),
         }
     }
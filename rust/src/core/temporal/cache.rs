@@ -34,7 +34,7 @@ const CACHE_FILE: &str = "temporal_cache.bin";
 const CACHE_TTL_SECONDS: u64 = 86400;
 
 /// Cache format version (bump to invalidate old caches)
-const CACHE_VERSION: u32 = 1;
+const CACHE_VERSION: u32 = 2;
 
 // =============================================================================
 // Cache Entry
@@ -116,7 +116,7 @@ impl ChronosCacheManager {
     }
 
     /// Get the cache file path
-    fn cache_path(&self) -> PathBuf {
+    pub fn cache_path(&self) -> PathBuf {
         self.cache_dir.join(CACHE_FILE)
     }
 
@@ -307,7 +307,7 @@ mod tests {
 
     #[test]
     fn test_cache_version() {
-        assert_eq!(CACHE_VERSION, 1);
+        assert_eq!(CACHE_VERSION, 2);
     }
 
     #[test]
@@ -528,7 +528,7 @@ mod tests {
             commit_depth: 500,
         };
 
-        assert_eq!(cache.version, 1);
+        assert_eq!(cache.version, 2);
         assert_eq!(cache.git_head_hash, "abc123def456");
         assert_eq!(cache.created_at, 1700000000);
         assert_eq!(cache.ttl_seconds, 3600);
@@ -575,7 +575,7 @@ mod tests {
 
         let debug_str = format!("{:?}", cache);
         assert!(debug_str.contains("ChronosCache"));
-        assert!(debug_str.contains("version: 1"));
+        assert!(debug_str.contains("version: 2"));
     }
 
     #[test]
@@ -1335,6 +1335,6 @@ mod tests {
         assert_eq!(CACHE_DIR, ".voyager/cache/chronos");
         assert_eq!(CACHE_FILE, "temporal_cache.bin");
         assert_eq!(CACHE_TTL_SECONDS, 86400);
-        assert_eq!(CACHE_VERSION, 1);
+        assert_eq!(CACHE_VERSION, 2);
     }
 }
@@ -1,11 +1,12 @@
-//! Chronos Engine - Git History Extraction
+//! Chronos Engine - History Extraction
 //!
-//! This module provides the core temporal analysis engine using git2
-//! for optimized history extraction. Includes the Chronos Warp caching
-//! system for near-instantaneous repeat scans.
+//! This module provides the core temporal analysis engine. Commits come
+//! from a [`HistoryProvider`] (git, Mercurial or Jujutsu). Includes the
+//! Chronos Warp caching system for near-instantaneous repeat scans of git
+//! repositories.
 
 use chrono::{DateTime, Duration, Utc};
-use git2::{Commit, DiffOptions, Oid, Repository};
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
@@ -19,6 +20,10 @@ use super::cache::{
     CachedGalaxyStats, CachedObservation, ChronosCache, ChronosCacheManager, WarpStatus,
 };
 
+use super::history::{self, CommitData, HistoryProvider};
+use super::thresholds::TemporalThresholds;
+use super::tickets::TicketIndex;
+
 // =============================================================================
 // Constants
 // =============================================================================
@@ -35,20 +40,14 @@ const CHURN_WINDOW_30D: i64 = 30;
 const CHURN_WINDOW_90D: i64 = 90;
 const CHURN_WINDOW_YEAR: i64 = 365;
 
-/// Ancient star threshold (2 years)
-const ANCIENT_THRESHOLD_DAYS: u64 = 730;
-
-/// Supernova threshold (30 commits in 30 days)
-const SUPERNOVA_THRESHOLD: usize = 30;
-
 // =============================================================================
 // Chronos Engine
 // =============================================================================
 
 /// The Chronos Engine - temporal analysis of code history
 pub struct ChronosEngine {
-    /// The git repository
-    repo: Repository,
+    /// Where the commits come from
+    history: Box<dyn HistoryProvider>,
     /// Root path of the repository
     root: PathBuf,
     /// Maximum commit depth to analyze
@@ -63,6 +62,8 @@ pub struct ChronosEngine {
     cache_manager: ChronosCacheManager,
     /// Current warp status
     warp_status: WarpStatus,
+    /// Classification cutoffs
+    thresholds: TemporalThresholds,
 }
 
 /// A single observation (commit) affecting a file
@@ -80,19 +81,6 @@ struct FileObservation {
     lines_removed: usize,
 }
 
-/// Extracted data from a commit (for borrow-checker friendly processing)
-#[derive(Debug, Clone)]
-struct CommitData {
-    /// Commit timestamp
-    timestamp: DateTime<Utc>,
-    /// Observer name
-    observer_name: String,
-    /// Observer email
-    observer_email: String,
-    /// Files changed in this commit
-    files_changed: Vec<String>,
-}
-
 /// Galaxy-level statistics
 #[derive(Debug, Clone, Default)]
 struct GalaxyStats {
@@ -125,28 +113,32 @@ impl ChronosEngine {
     }
 
     /// Create a new engine with custom commit depth
+    ///
+    /// `None` when `root` isn't in a git, Mercurial or Jujutsu repository.
     pub fn with_depth(root: &Path, commit_depth: usize) -> Option<Self> {
-        // Try to open the repository
-        let repo = match Repository::discover(root) {
-            Ok(r) => r,
-            Err(_) => return None,
-        };
-
-        let root_path = repo.workdir()?.to_path_buf();
+        let history = history::discover(root)?;
+        let root_path = history.root().to_path_buf();
         let cache_manager = ChronosCacheManager::new(&root_path);
 
         Some(Self {
-            repo,
-            root: root_path.clone(),
+            history,
+            root: root_path,
             commit_depth,
             file_histories: HashMap::new(),
             galaxy_stats: GalaxyStats::default(),
             state: ChronosState::StaticGalaxy,
             cache_manager,
             warp_status: WarpStatus::Calibrating,
+            thresholds: TemporalThresholds::default(),
         })
     }
 
+    /// Classify churn, supernovas and ancient stars with `thresholds`
+    pub fn with_thresholds(mut self, thresholds: TemporalThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
     /// Get the current Warp status
     pub fn warp_status(&self) -> WarpStatus {
         self.warp_status
@@ -157,47 +149,56 @@ impl ChronosEngine {
         &self.state
     }
 
+    /// Get the repository root (working directory)
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The version control system the history comes from: `git`, `hg` or `jj`
+    pub fn backend(&self) -> &'static str {
+        self.history.name()
+    }
+
+    /// Build a ticket cross-reference index from commit messages
+    ///
+    /// Walks up to `commit_depth` commits from HEAD; only commits whose
+    /// message mentions a ticket are diffed.
+    pub fn ticket_index(&self) -> Result<TicketIndex, String> {
+        let mentions_ticket =
+            |message: &str| !super::tickets::extract_ticket_ids(message).is_empty();
+        let (commits, _) = self.history.commits(self.commit_depth, &mentions_ticket)?;
+
+        let mut index = TicketIndex::new();
+        for data in commits {
+            index.record_commit(&data.message, &data.files_changed);
+        }
+        Ok(index)
+    }
+
+    /// Unified diffs of every file changed between `rev` and the working tree
+    ///
+    /// Staged, unstaged and untracked changes are all included. Keys are
+    /// paths relative to `project_root`; files outside it are left out.
+    pub fn changes_since(
+        &self,
+        rev: &str,
+        project_root: &Path,
+    ) -> Result<BTreeMap<String, String>, String> {
+        self.history.changes_since(rev, project_root)
+    }
+
     /// Extract history for all files
     pub fn extract_history(&mut self) -> Result<(), String> {
         let now = Utc::now();
 
         // First, collect all commit data to avoid borrow conflicts
-        let (commit_data, hit_depth_limit): (Vec<CommitData>, bool) = {
-            let mut revwalk = self
-                .repo
-                .revwalk()
-                .map_err(|e| format!("Failed to create revwalk: {}", e))?;
-
-            revwalk
-                .push_head()
-                .map_err(|e| format!("Failed to push HEAD: {}", e))?;
-
-            // Take one extra to detect if we hit the limit
-            let oids: Vec<Oid> = revwalk
-                .take(self.commit_depth + 1)
-                .filter_map(|r| r.ok())
-                .collect();
+        let (commit_data, hit_depth_limit) = self.history.commits(self.commit_depth, &|_| true)?;
 
-            let hit_limit = oids.len() > self.commit_depth;
-            let oids_to_process: Vec<Oid> = if hit_limit {
-                oids.into_iter().take(self.commit_depth).collect()
-            } else {
-                oids
-            };
-
-            // Extract data from each commit
-            let mut data = Vec::with_capacity(oids_to_process.len());
-            for oid in oids_to_process {
-                if let Ok(commit) = self.repo.find_commit(oid) {
-                    if let Some(cd) = self.extract_commit_data(&commit) {
-                        data.push(cd);
-                    }
-                }
-            }
-            (data, hit_limit)
-        };
-
-        // Process the extracted data
+        // Process the extracted data, newest commit first. A file's history
+        // is kept under its current name: when a commit renames `old` to
+        // `new`, older commits touching `old` count for whatever `new` is
+        // called now.
+        let mut current_names: HashMap<String, String> = HashMap::new();
         let mut commit_count = 0;
         let mut first_timestamp: Option<DateTime<Utc>> = None;
         let mut last_timestamp: Option<DateTime<Utc>> = None;
@@ -238,6 +239,7 @@ impl ChronosEngine {
 
             // Add file observations
             for path in data.files_changed {
+                let path = current_names.get(&path).cloned().unwrap_or(path);
                 let observation = FileObservation {
                     timestamp: data.timestamp,
                     observer_name: data.observer_name.clone(),
@@ -251,6 +253,10 @@ impl ChronosEngine {
                     .or_insert_with(Vec::new)
                     .push(observation);
             }
+            for (old, new) in data.renames {
+                let current = current_names.get(&new).cloned().unwrap_or(new);
+                current_names.insert(old, current);
+            }
         }
 
         self.galaxy_stats.first_observation = first_timestamp;
@@ -288,6 +294,12 @@ impl ChronosEngine {
     /// near-instantaneous results. Otherwise, it performs full git analysis and
     /// saves to cache.
     pub fn extract_history_cached(&mut self) -> Result<(), String> {
+        // The cache is keyed by the git HEAD
+        if !self.history.supports_cache() {
+            self.warp_status = WarpStatus::Offline;
+            return self.extract_history();
+        }
+
         // Try loading from cache first
         if let Some(cache) = self.cache_manager.load() {
             // Cache hit - restore state from cache
@@ -422,52 +434,6 @@ impl ChronosEngine {
         self.cache_manager.warp_engaged()
     }
 
-    /// Extract data from a single commit (pure, no mutation)
-    fn extract_commit_data(&self, commit: &Commit) -> Option<CommitData> {
-        let timestamp = commit_timestamp(commit);
-        let author = commit.author();
-        let observer_name = author.name().unwrap_or("Unknown").to_string();
-        let observer_email = author.email().unwrap_or("unknown@unknown").to_string();
-
-        // Get diff with parent
-        let parent = commit.parent(0).ok();
-        let parent_tree = parent.as_ref().and_then(|p| p.tree().ok());
-        let commit_tree = commit.tree().ok()?;
-
-        let mut diff_opts = DiffOptions::new();
-        diff_opts.include_untracked(false);
-
-        let diff = self
-            .repo
-            .diff_tree_to_tree(
-                parent_tree.as_ref(),
-                Some(&commit_tree),
-                Some(&mut diff_opts),
-            )
-            .ok()?;
-
-        // Collect changed files
-        let mut files_changed = Vec::new();
-        let _ = diff.foreach(
-            &mut |delta, _| {
-                if let Some(path) = delta.new_file().path() {
-                    files_changed.push(path.to_string_lossy().to_string());
-                }
-                true
-            },
-            None,
-            None,
-            None,
-        );
-
-        Some(CommitData {
-            timestamp,
-            observer_name,
-            observer_email,
-            files_changed,
-        })
-    }
-
     /// Get metrics for a specific file
     pub fn file_metrics(&self, path: &str) -> ChronosMetrics {
         let now = Utc::now();
@@ -537,7 +503,11 @@ impl ChronosEngine {
             last_year,
             lines_added_90d,
             lines_removed_90d,
-            classification: ChurnClassification::from_counts(last_30_days, last_90_days),
+            classification: ChurnClassification::classify(
+                last_30_days,
+                last_90_days,
+                &self.thresholds,
+            ),
         };
 
         // Calculate primary observers
@@ -617,7 +587,7 @@ impl ChronosEngine {
             census.files.insert(path.clone(), file_churn);
 
             // Identify supernovas
-            if metrics.volcanic_churn.last_30_days > SUPERNOVA_THRESHOLD {
+            if metrics.volcanic_churn.last_30_days > self.thresholds.supernova_30d {
                 census.supernovas.push(Supernova {
                     path: path.clone(),
                     observations_30d: metrics.volcanic_churn.last_30_days,
@@ -631,13 +601,13 @@ impl ChronosEngine {
                 });
             }
 
-            // Identify ancient stars (dormant > 2 years)
+            // Identify ancient stars (dormant > 2 years by default)
             let dormant_days = metrics
                 .last_observation
                 .map(|t| (now - t).num_days().max(0) as u64)
                 .unwrap_or(0);
 
-            if dormant_days > ANCIENT_THRESHOLD_DAYS {
+            if dormant_days > self.thresholds.ancient_dormant_days {
                 census.ancient_stars.push(AncientStar {
                     path: path.clone(),
                     age_days: metrics.stellar_age.age_days,
@@ -673,8 +643,11 @@ impl ChronosEngine {
             };
 
             let max_30d = files.iter().map(|f| f.churn_30d).max().unwrap_or(0);
-            let classification =
-                ChurnClassification::from_counts(max_30d, churn_90d / file_count.max(1));
+            let classification = ChurnClassification::classify(
+                max_30d,
+                churn_90d / file_count.max(1),
+                &self.thresholds,
+            );
 
             census.constellations.insert(
                 path.clone(),
@@ -727,8 +700,10 @@ impl ChronosEngine {
             let metrics = self.calculate_file_metrics(observations, &now);
             let dark_matter = dark_matter_ratios.get(path).copied().unwrap_or(0.0);
 
-            // High churn (>10 in 90d) + High dark matter (>20%)
-            if metrics.volcanic_churn.last_90_days > 10 && dark_matter > 0.2 {
+            // High churn (>10 in 90d) + High dark matter (>20%) by default
+            if metrics.volcanic_churn.last_90_days > self.thresholds.tectonic_churn_90d
+                && dark_matter > self.thresholds.tectonic_dark_matter
+            {
                 let risk_score = (metrics.volcanic_churn.last_90_days as f64 / 30.0).min(1.0)
                     * (dark_matter / 0.5).min(1.0);
 
@@ -760,12 +735,6 @@ impl ChronosEngine {
 // Helper Functions
 // =============================================================================
 
-/// Get timestamp from a commit
-fn commit_timestamp(commit: &Commit) -> DateTime<Utc> {
-    let time = commit.time();
-    DateTime::from_timestamp(time.seconds(), 0).unwrap_or_else(Utc::now)
-}
-
 /// Normalize a file path relative to the repository root
 fn normalize_path(path: &str, root: &Path) -> String {
     let path = Path::new(path);
@@ -794,6 +763,7 @@ fn hash_email(email: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use git2::{Commit, Repository};
     use std::env;
 
     // ==================== Helper Function Tests ====================
@@ -919,7 +889,9 @@ mod tests {
             timestamp: Utc::now(),
             observer_name: "Developer".to_string(),
             observer_email: "dev@example.com".to_string(),
+            message: "Change".to_string(),
             files_changed: vec!["src/main.rs".to_string(), "Cargo.toml".to_string()],
+            renames: vec![],
         };
         assert_eq!(data.files_changed.len(), 2);
     }
@@ -988,8 +960,9 @@ mod tests {
         assert_eq!(CHURN_WINDOW_30D, 30);
         assert_eq!(CHURN_WINDOW_90D, 90);
         assert_eq!(CHURN_WINDOW_YEAR, 365);
-        assert_eq!(ANCIENT_THRESHOLD_DAYS, 730);
-        assert_eq!(SUPERNOVA_THRESHOLD, 30);
+        let thresholds = TemporalThresholds::default();
+        assert_eq!(thresholds.ancient_dormant_days, 730);
+        assert_eq!(thresholds.supernova_30d, 30);
     }
 
     // ==================== Metrics Calculation Tests ====================
@@ -1456,7 +1429,9 @@ mod tests {
             timestamp: Utc::now(),
             observer_name: "Dev".to_string(),
             observer_email: "dev@example.com".to_string(),
+            message: "Change".to_string(),
             files_changed: vec!["file.rs".to_string()],
+            renames: vec![],
         };
         let cloned = data.clone();
         assert_eq!(data.observer_name, cloned.observer_name);
@@ -1498,4 +1473,89 @@ mod tests {
         let cloned = stats.clone();
         assert_eq!(stats.observations, cloned.observations);
     }
+
+    #[test]
+    fn test_changes_since_head() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+        std::fs::create_dir_all(temp.path().join("app")).unwrap();
+        std::fs::write(temp.path().join("app/keep.py"), "x = 1\n").unwrap();
+        std::fs::write(temp.path().join("app/edit.py"), "y = 1\n").unwrap();
+        std::fs::write(temp.path().join("app/gone.py"), "z = 1\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Dev", "dev@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+
+        std::fs::write(temp.path().join("app/edit.py"), "y = 2\n").unwrap();
+        std::fs::remove_file(temp.path().join("app/gone.py")).unwrap();
+        std::fs::write(temp.path().join("app/new.py"), "w = 1\n").unwrap();
+
+        let engine = ChronosEngine::new(temp.path()).unwrap();
+        let changes = engine
+            .changes_since("HEAD", &temp.path().join("app"))
+            .unwrap();
+
+        assert_eq!(
+            changes.keys().collect::<Vec<_>>(),
+            vec!["edit.py", "gone.py", "new.py"]
+        );
+        assert!(changes["edit.py"].contains("-y = 1\n+y = 2\n"));
+        assert!(changes["gone.py"].contains("deleted file mode"));
+        assert!(changes["new.py"].contains("+w = 1"));
+        assert!(engine.changes_since("no-such-rev", temp.path()).is_err());
+    }
+
+    #[test]
+    fn test_extract_history_follows_renames() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+        let commit = |days_ago: i64, message: &str| {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            index.update_all(["*"].iter(), None).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let when = git2::Time::new((Utc::now() - Duration::days(days_ago)).timestamp(), 0);
+            let sig = git2::Signature::new("Dev", "dev@example.com", &when).unwrap();
+            let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<&Commit> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+                .unwrap();
+        };
+        let source = "fn main() {\n    println!(\"hello\");\n}\n";
+
+        std::fs::create_dir_all(temp.path().join("app")).unwrap();
+        std::fs::write(temp.path().join("app/main.rs"), source).unwrap();
+        commit(800, "init");
+        std::fs::write(
+            temp.path().join("app/main.rs"),
+            source.replace("hello", "hi"),
+        )
+        .unwrap();
+        commit(400, "edit");
+        // Move the whole directory, touching the file on the way
+        std::fs::rename(temp.path().join("app"), temp.path().join("core")).unwrap();
+        std::fs::write(
+            temp.path().join("core/main.rs"),
+            source.replace("hello", "hey"),
+        )
+        .unwrap();
+        commit(1, "move app to core");
+
+        let mut engine = ChronosEngine::new(temp.path()).unwrap();
+        engine.extract_history().unwrap();
+        let metrics = engine.file_metrics("core/main.rs");
+        assert_eq!(metrics.total_observations, 3);
+        assert!(metrics.stellar_age.age_days >= 799);
+        assert_eq!(engine.file_metrics("app/main.rs").total_observations, 0);
+    }
 }
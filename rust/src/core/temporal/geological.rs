@@ -10,6 +10,7 @@
 #[cfg(test)]
 use super::metrics::{AgeClassification, ChurnClassification};
 use super::metrics::{AncientStar, FileChurn, Supernova, TectonicShift};
+use super::thresholds::TemporalThresholds;
 use std::collections::HashMap;
 
 // =============================================================================
@@ -47,6 +48,10 @@ pub struct GeologicalAnalyzer {
     pub core_star_threshold: usize,
     /// Supernova threshold
     pub supernova_threshold: usize,
+    /// Weight of churn in the tectonic risk score
+    pub churn_weight: f64,
+    /// Weight of dark matter in the tectonic risk score
+    pub dark_matter_weight: f64,
 }
 
 impl Default for GeologicalAnalyzer {
@@ -64,6 +69,21 @@ impl GeologicalAnalyzer {
             ancient_dormant_days: ANCIENT_DORMANT_DAYS,
             core_star_threshold: ANCIENT_CORE_STAR_THRESHOLD,
             supernova_threshold: SUPERNOVA_THRESHOLD,
+            churn_weight: 0.5,
+            dark_matter_weight: 0.5,
+        }
+    }
+
+    /// Create an analyzer with configured thresholds
+    pub fn with_thresholds(thresholds: &TemporalThresholds) -> Self {
+        Self {
+            tectonic_churn: thresholds.tectonic_churn_90d,
+            tectonic_dark_matter: thresholds.tectonic_dark_matter,
+            ancient_dormant_days: thresholds.ancient_dormant_days,
+            supernova_threshold: thresholds.supernova_30d,
+            churn_weight: thresholds.churn_weight,
+            dark_matter_weight: thresholds.dark_matter_weight,
+            ..Self::new()
         }
     }
 
@@ -84,7 +104,9 @@ impl GeologicalAnalyzer {
                 let churn_factor =
                     (churn.churn_90d as f64 / (self.tectonic_churn as f64 * 3.0)).min(1.0);
                 let dark_factor = (dark_matter / (self.tectonic_dark_matter * 2.0)).min(1.0);
-                let risk_score = (churn_factor + dark_factor) / 2.0;
+                let risk_score = (churn_factor * self.churn_weight
+                    + dark_factor * self.dark_matter_weight)
+                    / (self.churn_weight + self.dark_matter_weight);
 
                 shifts.push(TectonicShift {
                     path: path.clone(),
@@ -481,6 +503,40 @@ mod tests {
         assert_eq!(supernovas[0].path, "exploding.rs");
     }
 
+    #[test]
+    fn test_with_thresholds() {
+        let thresholds = TemporalThresholds {
+            supernova_30d: 40,
+            tectonic_churn_90d: 20,
+            churn_weight: 1.0,
+            dark_matter_weight: 0.0,
+            ..Default::default()
+        };
+        let analyzer = GeologicalAnalyzer::with_thresholds(&thresholds);
+
+        let mut file_churn = HashMap::new();
+        file_churn.insert(
+            "busy.rs".to_string(),
+            make_file_churn("busy.rs", 35, 15, 100),
+        );
+        file_churn.insert(
+            "churning.rs".to_string(),
+            make_file_churn("churning.rs", 10, 60, 100),
+        );
+        let mut dark_matter = HashMap::new();
+        dark_matter.insert("busy.rs".to_string(), 0.9);
+        dark_matter.insert("churning.rs".to_string(), 0.2);
+
+        // 35 observations in 30 days is normal under this cadence
+        assert!(analyzer.identify_supernovas(&file_churn).is_empty());
+
+        let shifts = analyzer.identify_tectonic_shifts(&file_churn, &dark_matter);
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].path, "churning.rs");
+        // Only churn counts, and 60 is at its 3x cap
+        assert_eq!(shifts[0].risk_score, 1.0);
+    }
+
     #[test]
     fn test_identify_supernovas_sorting() {
         let analyzer = GeologicalAnalyzer::new();
@@ -0,0 +1,559 @@
+//! History Providers - where the Chronos Engine's observations come from
+//!
+//! The engine only needs a list of commits, newest first: when, by whom,
+//! with what message, and which files they touched or renamed. A
+//! [`HistoryProvider`] supplies that from one version control system. git is
+//! read in-process through git2; Mercurial and Jujutsu through their own
+//! command-line tools (`hg` and `jj` must be on `PATH`).
+//!
+//! [`discover`] picks the provider for a directory: a git repository first
+//! (so colocated jj repositories are read through git and can use the
+//! Chronos Warp cache), then the nearest Mercurial or Jujutsu one.
+
+use chrono::{DateTime, Utc};
+use git2::{Commit, Delta, DiffFindOptions, DiffFormat, DiffOptions, Repository};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Most added/deleted file pairs compared when looking for renames in one
+/// commit; enough for a mass directory move
+const RENAME_LIMIT: usize = 5_000;
+
+/// Starts each commit's header line in the hg and jj log output
+const COMMIT_MARKER: &str = "@@commit ";
+
+/// hg log template: a header line, then one `M`/`A`/`D` line per file and
+/// a `C source<TAB>copy` line per copy
+const HG_TEMPLATE: &str = "@@commit {date|hgdate}\\t{author|person}\\t{author|email}\\t\
+{join(splitlines(desc), ' ')}\\n\
+{file_mods % 'M {file}\\n'}{file_adds % 'A {file}\\n'}{file_dels % 'D {file}\\n'}\
+{file_copies % 'C {source}\\t{name}\\n'}";
+
+/// jj log template for the header line; `--summary` adds the file lines
+const JJ_TEMPLATE: &str = "\"@@commit \" ++ author.timestamp().format(\"%s\") ++ \"\\t\" \
+++ author.name() ++ \"\\t\" ++ author.email() ++ \"\\t\" \
+++ description.lines().join(\" \") ++ \"\\n\"";
+
+/// Extracted data from a commit (for borrow-checker friendly processing)
+#[derive(Debug, Clone)]
+pub(super) struct CommitData {
+    /// Commit timestamp
+    pub(super) timestamp: DateTime<Utc>,
+    /// Observer name
+    pub(super) observer_name: String,
+    /// Observer email
+    pub(super) observer_email: String,
+    /// Commit message
+    pub(super) message: String,
+    /// Files changed in this commit
+    pub(super) files_changed: Vec<String>,
+    /// Files this commit renamed, as (old path, new path)
+    pub(super) renames: Vec<(String, String)>,
+}
+
+/// A version control system the Chronos Engine can read history from
+pub(super) trait HistoryProvider {
+    /// Short name of the system: `git`, `hg` or `jj`
+    fn name(&self) -> &'static str;
+
+    /// Root of the working copy; commit paths are relative to it
+    fn root(&self) -> &Path;
+
+    /// Up to `limit` commits, newest first, from the working copy's
+    /// ancestry, and whether older ones were left out
+    ///
+    /// Only commits whose message satisfies `wanted` are returned (their
+    /// file lists can be expensive), but all of them count towards `limit`.
+    fn commits(
+        &self,
+        limit: usize,
+        wanted: &dyn Fn(&str) -> bool,
+    ) -> Result<(Vec<CommitData>, bool), String>;
+
+    /// Unified diffs of every file changed between `rev` and the working
+    /// copy, keyed by path relative to `project_root`
+    fn changes_since(
+        &self,
+        _rev: &str,
+        _project_root: &Path,
+    ) -> Result<BTreeMap<String, String>, String> {
+        Err(format!(
+            "Diffing against a revision is only supported in git repositories, not {}",
+            self.name()
+        ))
+    }
+
+    /// Whether results can go in the Chronos Warp cache, which is keyed by
+    /// the git HEAD
+    fn supports_cache(&self) -> bool {
+        false
+    }
+}
+
+/// The history provider for the repository containing `root`
+pub(super) fn discover(root: &Path) -> Option<Box<dyn HistoryProvider>> {
+    if let Some(git) = GitHistory::discover(root) {
+        return Some(Box::new(git));
+    }
+    let root = root.canonicalize().ok()?;
+    let repo = root.ancestors().find_map(|dir| {
+        if dir.join(".hg").is_dir() {
+            Some(("hg", dir))
+        } else if dir.join(".jj").is_dir() {
+            Some(("jj", dir))
+        } else {
+            None
+        }
+    })?;
+    match repo {
+        ("hg", dir) => CommandHistory::new(Vcs::Hg, dir),
+        (_, dir) => CommandHistory::new(Vcs::Jj, dir),
+    }
+    .map(|history| Box::new(history) as Box<dyn HistoryProvider>)
+}
+
+// =============================================================================
+// git
+// =============================================================================
+
+/// History of a git repository, read with git2
+struct GitHistory {
+    repo: Repository,
+    root: PathBuf,
+}
+
+impl GitHistory {
+    fn discover(root: &Path) -> Option<Self> {
+        let repo = Repository::discover(root).ok()?;
+        let root = repo.workdir()?.to_path_buf();
+        Some(Self { repo, root })
+    }
+
+    /// Extract data from a single commit (pure, no mutation)
+    fn commit_data(&self, commit: &Commit) -> Option<CommitData> {
+        let timestamp = commit_timestamp(commit);
+        let author = commit.author();
+        let observer_name = author.name().unwrap_or("Unknown").to_string();
+        let observer_email = author.email().unwrap_or("unknown@unknown").to_string();
+
+        // Get diff with parent
+        let parent = commit.parent(0).ok();
+        let parent_tree = parent.as_ref().and_then(|p| p.tree().ok());
+        let commit_tree = commit.tree().ok()?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.include_untracked(false);
+
+        let mut diff = self
+            .repo
+            .diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&commit_tree),
+                Some(&mut diff_opts),
+            )
+            .ok()?;
+
+        // Pair deleted and added files into renames
+        let mut find_opts = DiffFindOptions::new();
+        find_opts.renames(true).rename_limit(RENAME_LIMIT);
+        let _ = diff.find_similar(Some(&mut find_opts));
+
+        // Collect changed files
+        let mut files_changed = Vec::new();
+        let mut renames = Vec::new();
+        let _ = diff.foreach(
+            &mut |delta, _| {
+                if let Some(path) = delta.new_file().path() {
+                    let path = path.to_string_lossy().to_string();
+                    if delta.status() == Delta::Renamed {
+                        if let Some(old) = delta.old_file().path() {
+                            renames.push((old.to_string_lossy().to_string(), path.clone()));
+                        }
+                    }
+                    files_changed.push(path);
+                }
+                true
+            },
+            None,
+            None,
+            None,
+        );
+
+        Some(CommitData {
+            timestamp,
+            observer_name,
+            observer_email,
+            message: commit.message().unwrap_or("").to_string(),
+            files_changed,
+            renames,
+        })
+    }
+}
+
+impl HistoryProvider for GitHistory {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn commits(
+        &self,
+        limit: usize,
+        wanted: &dyn Fn(&str) -> bool,
+    ) -> Result<(Vec<CommitData>, bool), String> {
+        let mut revwalk = self
+            .repo
+            .revwalk()
+            .map_err(|e| format!("Failed to create revwalk: {}", e))?;
+
+        revwalk
+            .push_head()
+            .map_err(|e| format!("Failed to push HEAD: {}", e))?;
+
+        // Take one extra to detect if we hit the limit
+        let mut oids: Vec<_> = revwalk.take(limit + 1).filter_map(|r| r.ok()).collect();
+        let hit_limit = oids.len() > limit;
+        oids.truncate(limit);
+
+        let mut data = Vec::with_capacity(oids.len());
+        for oid in oids {
+            let Ok(commit) = self.repo.find_commit(oid) else {
+                continue;
+            };
+            if !wanted(commit.message().unwrap_or("")) {
+                continue;
+            }
+            if let Some(cd) = self.commit_data(&commit) {
+                data.push(cd);
+            }
+        }
+        Ok((data, hit_limit))
+    }
+
+    fn changes_since(
+        &self,
+        rev: &str,
+        project_root: &Path,
+    ) -> Result<BTreeMap<String, String>, String> {
+        let tree = self
+            .repo
+            .revparse_single(rev)
+            .and_then(|obj| obj.peel_to_tree())
+            .map_err(|e| format!("Unknown revision '{}': {}", rev, e))?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+
+        let diff = self
+            .repo
+            .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut diff_opts))
+            .map_err(|e| format!("Failed to diff against '{}': {}", rev, e))?;
+
+        // Repository-relative prefix of the project root
+        let workdir = self
+            .root
+            .canonicalize()
+            .unwrap_or_else(|_| self.root.clone());
+        let project = project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.to_path_buf());
+        let prefix = project.strip_prefix(&workdir).unwrap_or(Path::new(""));
+
+        let mut changes: BTreeMap<String, String> = BTreeMap::new();
+        diff.print(DiffFormat::Patch, |delta, _hunk, line| {
+            let path = match delta.new_file().path().or_else(|| delta.old_file().path()) {
+                Some(p) => p,
+                None => return true,
+            };
+            let rel = match path.strip_prefix(prefix) {
+                Ok(r) => r.to_string_lossy().replace('\\', "/"),
+                Err(_) => return true,
+            };
+
+            let text = changes.entry(rel).or_default();
+            let origin = line.origin();
+            if matches!(origin, '+' | '-' | ' ') {
+                text.push(origin);
+            }
+            text.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })
+        .map_err(|e| format!("Failed to render diff: {}", e))?;
+
+        Ok(changes)
+    }
+
+    fn supports_cache(&self) -> bool {
+        true
+    }
+}
+
+/// Get timestamp from a commit
+fn commit_timestamp(commit: &Commit) -> DateTime<Utc> {
+    let time = commit.time();
+    DateTime::from_timestamp(time.seconds(), 0).unwrap_or_else(Utc::now)
+}
+
+// =============================================================================
+// Mercurial and Jujutsu
+// =============================================================================
+
+/// A version control system read through its command-line tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Vcs {
+    Hg,
+    Jj,
+}
+
+impl Vcs {
+    fn program(self) -> &'static str {
+        match self {
+            Self::Hg => "hg",
+            Self::Jj => "jj",
+        }
+    }
+
+    /// Arguments of the log command for `limit` commits
+    fn log_args(self, limit: usize) -> Vec<String> {
+        let limit = limit.to_string();
+        let args: &[&str] = match self {
+            Self::Hg => &["log", "-r", "reverse(::.)", "-l", &limit, "-T", HG_TEMPLATE],
+            Self::Jj => &[
+                "log",
+                "--ignore-working-copy",
+                "--no-graph",
+                "--summary",
+                "-r",
+                "::@ ~ root()",
+                "-n",
+                &limit,
+                "-T",
+                JJ_TEMPLATE,
+            ],
+        };
+        args.iter().map(|a| a.to_string()).collect()
+    }
+}
+
+/// History of a Mercurial or Jujutsu repository, read from its log
+struct CommandHistory {
+    vcs: Vcs,
+    root: PathBuf,
+}
+
+impl CommandHistory {
+    /// The repository at `dir`; `None` when its tool doesn't recognise it
+    /// (or isn't installed)
+    fn new(vcs: Vcs, dir: &Path) -> Option<Self> {
+        let root = run(vcs, dir, &["root"]).ok()?;
+        Some(Self {
+            vcs,
+            root: PathBuf::from(root.trim()),
+        })
+    }
+}
+
+impl HistoryProvider for CommandHistory {
+    fn name(&self) -> &'static str {
+        self.vcs.program()
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn commits(
+        &self,
+        limit: usize,
+        wanted: &dyn Fn(&str) -> bool,
+    ) -> Result<(Vec<CommitData>, bool), String> {
+        let args = self.vcs.log_args(limit + 1);
+        let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+        let mut commits = parse_log(&run(self.vcs, &self.root, &args)?);
+        let hit_limit = commits.len() > limit;
+        commits.truncate(limit);
+        commits.retain(|c| wanted(&c.message));
+        Ok((commits, hit_limit))
+    }
+}
+
+/// Run `vcs` with `args` in `dir`, returning its stdout
+fn run(vcs: Vcs, dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(vcs.program())
+        .args(args)
+        .current_dir(dir)
+        // Keep user configuration from changing hg's output
+        .env("HGPLAIN", "1")
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", vcs.program(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            vcs.program(),
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse hg or jj log output in the templates' format
+///
+/// Each commit is a header line (timestamp, name, email and message,
+/// tab-separated) followed by one line per file: a status letter (`M`,
+/// `A`, `D`, `R` or `C`), a space and the path. hg gives copies as
+/// `C source<TAB>copy`, which count as renames when the source was deleted
+/// in the same commit; jj gives renames and copies as `R dir/{old => new}`.
+fn parse_log(output: &str) -> Vec<CommitData> {
+    let mut commits = Vec::new();
+    let mut copies: Vec<(String, String)> = Vec::new();
+    let mut deleted: HashSet<String> = HashSet::new();
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix(COMMIT_MARKER) {
+            if let Some(commit) = commits.last_mut() {
+                finish_commit(commit, std::mem::take(&mut copies), &deleted);
+                deleted.clear();
+            }
+            let mut fields = header.splitn(4, '\t');
+            let mut field = || fields.next().unwrap_or("").to_string();
+            let timestamp = field()
+                .split_whitespace()
+                .next()
+                .and_then(|secs| secs.parse().ok())
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                .unwrap_or_else(Utc::now);
+            commits.push(CommitData {
+                timestamp,
+                observer_name: field(),
+                observer_email: field(),
+                message: field(),
+                files_changed: Vec::new(),
+                renames: Vec::new(),
+            });
+            continue;
+        }
+        let (Some(commit), Some((status, path))) = (commits.last_mut(), line.split_once(' '))
+        else {
+            continue;
+        };
+        match status {
+            "M" | "A" => commit.files_changed.push(path.to_string()),
+            "D" => {
+                commit.files_changed.push(path.to_string());
+                deleted.insert(path.to_string());
+            }
+            "R" | "C" => {
+                let Some((old, new)) = path.split_once('\t').map_or_else(
+                    || split_rename(path),
+                    |(old, new)| Some((old.to_string(), new.to_string())),
+                ) else {
+                    continue;
+                };
+                if status == "R" {
+                    commit.files_changed.push(new.clone());
+                    commit.renames.push((old, new));
+                } else {
+                    copies.push((old, new));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(commit) = commits.last_mut() {
+        finish_commit(commit, copies, &deleted);
+    }
+    commits
+}
+
+/// Turn the copies whose source `commit` deleted into renames, and leave
+/// the deleted sources out of its files, as git does
+fn finish_commit(
+    commit: &mut CommitData,
+    copies: Vec<(String, String)>,
+    deleted: &HashSet<String>,
+) {
+    let renames: Vec<(String, String)> = copies
+        .into_iter()
+        .filter(|(old, _)| deleted.contains(old))
+        .collect();
+    let sources: HashSet<&String> = renames.iter().map(|(old, _)| old).collect();
+    commit.files_changed.retain(|path| !sources.contains(path));
+    commit.renames.extend(renames);
+}
+
+/// Split jj's `prefix{old => new}suffix` (or plain `old => new`) rename
+fn split_rename(path: &str) -> Option<(String, String)> {
+    let (Some(open), Some(close)) = (path.find('{'), path.rfind('}')) else {
+        let (old, new) = path.split_once(" => ")?;
+        return Some((old.to_string(), new.to_string()));
+    };
+    let (prefix, suffix) = (&path[..open], &path[close + 1..]);
+    let (old, new) = path[open + 1..close].split_once(" => ")?;
+    let join = |middle: &str| {
+        // `{ => sub}/file` has an empty side; don't leave a doubled `/`
+        let joined = format!("{}{}{}", prefix, middle, suffix);
+        joined.replace("//", "/")
+    };
+    Some((join(old), join(new)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hg_log() {
+        let output = "@@commit 1700000000 -3600\tAda\tada@example.com\tMove util (PROJ-7)\n\
+                      M src/main.rs\n\
+                      A src/lib/util.rs\n\
+                      D src/util.rs\n\
+                      C src/util.rs\tsrc/lib/util.rs\n\
+                      @@commit 1690000000 0\tBob\tbob@example.com\tInitial\n\
+                      A src/main.rs\n\
+                      A src/util.rs\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 2);
+        let newest = &commits[0];
+        assert_eq!(newest.timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(newest.observer_name, "Ada");
+        assert_eq!(newest.message, "Move util (PROJ-7)");
+        assert_eq!(newest.files_changed, vec!["src/main.rs", "src/lib/util.rs"]);
+        assert_eq!(
+            newest.renames,
+            vec![("src/util.rs".to_string(), "src/lib/util.rs".to_string())]
+        );
+        assert_eq!(commits[1].files_changed.len(), 2);
+        assert!(commits[1].renames.is_empty());
+    }
+
+    #[test]
+    fn test_parse_jj_log() {
+        let output = "@@commit 1700000000\tAda\tada@example.com\tSplit modules\n\
+                      R src/{util.rs => lib/util.rs}\n\
+                      R {old => new}/mod.rs\n\
+                      M Cargo.toml\n";
+        let commits = parse_log(output);
+        assert_eq!(
+            commits[0].renames,
+            vec![
+                ("src/util.rs".to_string(), "src/lib/util.rs".to_string()),
+                ("old/mod.rs".to_string(), "new/mod.rs".to_string()),
+            ]
+        );
+        assert_eq!(
+            commits[0].files_changed,
+            vec!["src/lib/util.rs", "new/mod.rs", "Cargo.toml"]
+        );
+        assert_eq!(
+            split_rename("src/{ => nested}/a.rs"),
+            Some(("src/a.rs".to_string(), "src/nested/a.rs".to_string()))
+        );
+    }
+}
@@ -7,6 +7,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+use super::thresholds::TemporalThresholds;
+
 // =============================================================================
 // Core Temporal Types
 // =============================================================================
@@ -38,6 +40,13 @@ pub enum ChronosState {
     StaticGalaxy,
     /// No observation history found (.git missing)
     NoRepository,
+    /// No history; a degraded census derived from file timestamps
+    FileTimestamps {
+        /// Files with a usable modification time
+        file_count: usize,
+        /// Age of the oldest file
+        galaxy_age_days: u64,
+    },
     /// Error accessing temporal data
     Error(String),
 }
@@ -152,11 +161,16 @@ pub enum ChurnClassification {
 impl ChurnClassification {
     /// Determine classification from observation counts
     pub fn from_counts(last_30: usize, last_90: usize) -> Self {
-        if last_30 > 30 {
+        Self::classify(last_30, last_90, &TemporalThresholds::default())
+    }
+
+    /// Determine classification from observation counts and `thresholds`
+    pub fn classify(last_30: usize, last_90: usize, thresholds: &TemporalThresholds) -> Self {
+        if last_30 > thresholds.supernova_30d {
             Self::Supernova
-        } else if last_90 > 10 {
+        } else if last_90 > thresholds.high_churn_90d {
             Self::High
-        } else if last_90 > 3 {
+        } else if last_90 > thresholds.moderate_churn_90d {
             Self::Moderate
         } else if last_90 > 0 {
             Self::Low
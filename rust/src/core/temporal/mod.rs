@@ -13,12 +13,15 @@
 //! - **Tectonic Shifts**: High-risk files with churn + complexity
 //! - **Ancient Stars**: Untouched files (> 2 years) still core to logic
 //! - **Supernovas**: Files with extreme recent activity (destabilizing)
+//! - **Ticket Cross-References**: Files linked to issue-tracker IDs via history and comments
 //!
 //! # Feature Gating
 //!
 //! The temporal feature requires the `temporal` feature flag and access to
-//! a repository's history. When disabled or unavailable, the engine returns
-//! a "Static Galaxy" state with no temporal data.
+//! a repository's history: git, Mercurial or Jujutsu. When disabled or unavailable, the engine returns
+//! a "Static Galaxy" state with no temporal data. A tree without history
+//! can still get a degraded census from its file timestamps
+//! ([`census_from_timestamps`]).
 
 #[cfg(feature = "temporal")]
 mod engine;
@@ -26,9 +29,15 @@ mod engine;
 #[cfg(feature = "temporal")]
 mod cache;
 
+#[cfg(feature = "temporal")]
+mod history;
+
 mod geological;
 mod metrics;
 mod stellar_drift;
+mod thresholds;
+pub mod tickets;
+mod timestamps;
 
 #[cfg(feature = "temporal")]
 pub use engine::{ChronosEngine, DEFAULT_COMMIT_DEPTH, FULL_COMMIT_DEPTH};
@@ -46,6 +55,12 @@ pub use metrics::{
 
 pub use geological::{GeologicalActivity, GeologicalAnalyzer, GeologicalSummary};
 
+pub use thresholds::TemporalThresholds;
+
+pub use timestamps::census_from_timestamps;
+
+pub use tickets::{build_ticket_index, extract_ticket_ids, TicketEntry, TicketIndex};
+
 pub use stellar_drift::{
     ConstellationEvolution, NewStar, StellarDriftAnalyzer, StellarDriftReport,
     ANCIENT_STAR_THRESHOLD_DAYS, DRIFT_WINDOW_DAYS, NEW_STAR_THRESHOLD_DAYS,
@@ -66,6 +81,15 @@ impl ChronosEngine {
     pub fn state(&self) -> ChronosState {
         ChronosState::StaticGalaxy
     }
+
+    /// Diffs require the temporal feature
+    pub fn changes_since(
+        &self,
+        _rev: &str,
+        _project_root: &std::path::Path,
+    ) -> Result<std::collections::BTreeMap<String, String>, String> {
+        Err("Diffing requires the temporal feature".to_string())
+    }
 }
 
 // =============================================================================
@@ -84,6 +108,7 @@ pub fn temporal_state_description(state: &ChronosState) -> &'static str {
         ChronosState::ShallowCensus { .. } => "Shallow Chronos (partial history)",
         ChronosState::StaticGalaxy => "Static Galaxy (no temporal data)",
         ChronosState::NoRepository => "No observation history found",
+        ChronosState::FileTimestamps { .. } => "File timestamps (no history; degraded)",
         ChronosState::Error(_) => "Chronos Engine error",
     }
 }
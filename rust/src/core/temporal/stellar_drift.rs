@@ -31,6 +31,7 @@ use super::metrics::{
     AncientStar, ChronosState, ChurnClassification, ConstellationChurn, FileChurn, Supernova,
     TemporalCensus,
 };
+use super::thresholds::TemporalThresholds;
 
 // =============================================================================
 // Constants
@@ -161,6 +162,15 @@ impl StellarDriftAnalyzer {
         }
     }
 
+    /// Create an analyzer with configured thresholds
+    pub fn with_thresholds(thresholds: &TemporalThresholds) -> Self {
+        Self {
+            new_star_threshold: thresholds.new_star_days,
+            ancient_star_threshold: thresholds.ancient_dormant_days,
+            ..Self::new()
+        }
+    }
+
     /// Analyze stellar drift from temporal census and star counts
     ///
     /// # Arguments
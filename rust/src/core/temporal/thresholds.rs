@@ -0,0 +1,123 @@
+//! Temporal Thresholds
+//!
+//! The cutoffs that turn raw observation counts into supernovas, volcanic
+//! churn, tectonic shifts and ancient stars. The defaults suit a project
+//! committing a few times a week; a team that lands many small commits a
+//! day (or releases on a fast cadence) can raise them in the `temporal`
+//! section of `.pm_encoder_config.json` so normal activity doesn't read as
+//! volcanic:
+//!
+//! ```json
+//! { "temporal": { "supernova_30d": 80, "high_churn_90d": 40 } }
+//! ```
+//!
+//! Fields left out keep their defaults.
+
+use serde::{Deserialize, Serialize};
+
+use super::geological::{
+    ANCIENT_DORMANT_DAYS, SUPERNOVA_THRESHOLD, TECTONIC_CHURN_THRESHOLD,
+    TECTONIC_DARK_MATTER_THRESHOLD,
+};
+use super::stellar_drift::NEW_STAR_THRESHOLD_DAYS;
+
+/// Cutoffs for the temporal classifications
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemporalThresholds {
+    /// Observations in 30 days that make a file a supernova
+    pub supernova_30d: usize,
+    /// Observations in 90 days above which churn is high (volcanic)
+    pub high_churn_90d: usize,
+    /// Observations in 90 days above which churn is moderate
+    pub moderate_churn_90d: usize,
+    /// Days without an observation before a file is an ancient star
+    pub ancient_dormant_days: u64,
+    /// Days since the first observation during which a file is a new star
+    pub new_star_days: u64,
+    /// Observations in 90 days for a tectonic shift
+    pub tectonic_churn_90d: usize,
+    /// Dark matter ratio for a tectonic shift
+    pub tectonic_dark_matter: f64,
+    /// Weight of churn in a tectonic shift's risk score
+    pub churn_weight: f64,
+    /// Weight of dark matter in a tectonic shift's risk score
+    pub dark_matter_weight: f64,
+}
+
+impl Default for TemporalThresholds {
+    fn default() -> Self {
+        Self {
+            supernova_30d: SUPERNOVA_THRESHOLD,
+            high_churn_90d: 10,
+            moderate_churn_90d: 3,
+            ancient_dormant_days: ANCIENT_DORMANT_DAYS,
+            new_star_days: NEW_STAR_THRESHOLD_DAYS,
+            tectonic_churn_90d: TECTONIC_CHURN_THRESHOLD,
+            tectonic_dark_matter: TECTONIC_DARK_MATTER_THRESHOLD,
+            churn_weight: 0.5,
+            dark_matter_weight: 0.5,
+        }
+    }
+}
+
+impl TemporalThresholds {
+    /// Check that the thresholds can classify anything sensibly
+    pub fn validate(&self) -> Result<(), String> {
+        if self.moderate_churn_90d > self.high_churn_90d {
+            return Err(format!(
+                "temporal.moderate_churn_90d ({}) is above temporal.high_churn_90d ({})",
+                self.moderate_churn_90d, self.high_churn_90d
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.tectonic_dark_matter) {
+            return Err(format!(
+                "temporal.tectonic_dark_matter must be between 0 and 1, got {}",
+                self.tectonic_dark_matter
+            ));
+        }
+        let weights = self.churn_weight + self.dark_matter_weight;
+        if self.churn_weight < 0.0 || self.dark_matter_weight < 0.0 || weights <= 0.0 {
+            return Err(
+                "temporal.churn_weight and temporal.dark_matter_weight must be non-negative \
+                 and not both zero"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_thresholds_keep_defaults() {
+        let thresholds: TemporalThresholds =
+            serde_json::from_str(r#"{"supernova_30d": 80, "churn_weight": 3.0}"#).unwrap();
+        assert_eq!(thresholds.supernova_30d, 80);
+        assert_eq!(thresholds.high_churn_90d, 10);
+        assert_eq!(thresholds.ancient_dormant_days, 730);
+        assert_eq!(thresholds.churn_weight, 3.0);
+        assert!(thresholds.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_churn_levels() {
+        let thresholds = TemporalThresholds {
+            moderate_churn_90d: 20,
+            ..Default::default()
+        };
+        assert!(thresholds
+            .validate()
+            .unwrap_err()
+            .contains("high_churn_90d"));
+        let thresholds = TemporalThresholds {
+            churn_weight: 0.0,
+            dark_matter_weight: 0.0,
+            ..Default::default()
+        };
+        assert!(thresholds.validate().is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Ticket Cross-Reference - Issue-tracker references in code and history
+//!
+//! Scans source comments and commit messages for ticket identifiers
+//! (`JIRA-123`, `#456`) and builds an index from each ticket to the files
+//! historically associated with it. The history half of the index is fed by
+//! the Chronos Engine when the `temporal` feature is available; without it,
+//! only comment references are indexed.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+lazy_static! {
+    /// Tracker keys like JIRA-123, PROJ2-45, GH-7
+    static ref KEYED_TICKET: Regex = Regex::new(r"\b([A-Z][A-Z0-9]+-[0-9]+)\b").unwrap();
+    /// GitHub/GitLab style #456 (not preceded by a word character or '&')
+    static ref HASH_TICKET: Regex = Regex::new(r"(?:^|[^\w&#])#([0-9]+)\b").unwrap();
+}
+
+/// Uppercase prefixes that look like tracker keys but are standards/encodings
+const NON_TICKET_PREFIXES: &[&str] = &["UTF", "SHA", "ISO", "CVE", "RFC", "AES", "X"];
+
+/// Extract ticket identifiers from free text, in order of first appearance
+pub fn extract_ticket_ids(text: &str) -> Vec<String> {
+    let mut found: Vec<(usize, String)> = Vec::new();
+
+    for caps in KEYED_TICKET.captures_iter(text) {
+        let m = caps.get(1).unwrap();
+        let prefix = m.as_str().split('-').next().unwrap_or("");
+        if !NON_TICKET_PREFIXES.contains(&prefix) {
+            found.push((m.start(), m.as_str().to_string()));
+        }
+    }
+
+    for caps in HASH_TICKET.captures_iter(text) {
+        let m = caps.get(1).unwrap();
+        found.push((m.start(), format!("#{}", m.as_str())));
+    }
+
+    found.sort_by_key(|(pos, _)| *pos);
+    let mut seen = BTreeSet::new();
+    found
+        .into_iter()
+        .filter(|(_, id)| seen.insert(id.clone()))
+        .map(|(_, id)| id)
+        .collect()
+}
+
+/// Normalize a user-supplied ticket ID for lookup (`jira-123` -> `JIRA-123`, `456` -> `#456`)
+pub fn normalize_ticket_id(ticket: &str) -> String {
+    let ticket = ticket.trim();
+    if !ticket.is_empty() && ticket.chars().all(|c| c.is_ascii_digit()) {
+        format!("#{}", ticket)
+    } else {
+        ticket.to_uppercase()
+    }
+}
+
+/// Return the comment portion of a source line, if any
+fn comment_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('*') || trimmed.starts_with("--") || trimmed.starts_with(';') {
+        return Some(trimmed);
+    }
+
+    ["//", "/*", "#", "<!--"]
+        .iter()
+        .filter_map(|marker| line.find(marker))
+        .min()
+        .map(|pos| &line[pos..])
+}
+
+/// Files associated with a single ticket
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TicketEntry {
+    /// Files changed by commits whose message mentions the ticket
+    pub commit_files: BTreeSet<String>,
+    /// Files whose comments mention the ticket
+    pub comment_files: BTreeSet<String>,
+    /// Number of commits mentioning the ticket
+    pub commit_count: usize,
+}
+
+impl TicketEntry {
+    /// All associated files (history and comments), sorted
+    pub fn files(&self) -> Vec<String> {
+        self.commit_files
+            .union(&self.comment_files)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Cross-reference index: ticket ID -> associated files
+#[derive(Debug, Clone, Default)]
+pub struct TicketIndex {
+    entries: BTreeMap<String, TicketEntry>,
+}
+
+impl TicketIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a commit: every ticket in `message` is linked to `files`
+    pub fn record_commit(&mut self, message: &str, files: &[String]) {
+        for ticket in extract_ticket_ids(message) {
+            let entry = self.entries.entry(ticket).or_default();
+            entry.commit_count += 1;
+            entry.commit_files.extend(files.iter().cloned());
+        }
+    }
+
+    /// Scan the comments of a file for ticket references
+    pub fn scan_comments(&mut self, path: &str, content: &str) {
+        for line in content.lines() {
+            if let Some(comment) = comment_text(line) {
+                for ticket in extract_ticket_ids(comment) {
+                    self.entries
+                        .entry(ticket)
+                        .or_default()
+                        .comment_files
+                        .insert(path.to_string());
+                }
+            }
+        }
+    }
+
+    /// Merge another index into this one
+    pub fn merge(&mut self, other: TicketIndex) {
+        for (ticket, entry) in other.entries {
+            let target = self.entries.entry(ticket).or_default();
+            target.commit_count += entry.commit_count;
+            target.commit_files.extend(entry.commit_files);
+            target.comment_files.extend(entry.comment_files);
+        }
+    }
+
+    /// Look up a ticket (accepts unnormalized IDs like `jira-12` or `456`)
+    pub fn get(&self, ticket: &str) -> Option<&TicketEntry> {
+        self.entries.get(&normalize_ticket_id(ticket))
+    }
+
+    /// Files associated with a ticket (empty if unknown)
+    pub fn files_for(&self, ticket: &str) -> Vec<String> {
+        self.get(ticket).map(|e| e.files()).unwrap_or_default()
+    }
+
+    /// Iterate over all tickets in ID order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TicketEntry)> {
+        self.entries.iter()
+    }
+
+    /// Number of distinct tickets
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Build a ticket index from file comments plus repository history
+///
+/// `files` are `(relative_path, content)` pairs. History is read through the
+/// Chronos Engine (shallow depth unless `full_history`) and re-rooted so that
+/// paths are relative to `root` even when it is a subdirectory of the
+/// repository.
+pub fn build_ticket_index(
+    root: &Path,
+    files: &[(String, String)],
+    full_history: bool,
+) -> TicketIndex {
+    let mut index = TicketIndex::new();
+    for (path, content) in files {
+        index.scan_comments(path, content);
+    }
+    if let Some(history) = history_index(root, full_history) {
+        index.merge(history);
+    }
+    index
+}
+
+#[cfg(feature = "temporal")]
+fn history_index(root: &Path, full_history: bool) -> Option<TicketIndex> {
+    let depth = if full_history {
+        super::FULL_COMMIT_DEPTH
+    } else {
+        super::DEFAULT_COMMIT_DEPTH
+    };
+    let engine = super::ChronosEngine::with_depth(root, depth)?;
+    let history = engine.ticket_index().ok()?;
+
+    // Git paths are relative to the workdir; re-root them to `root`
+    let repo_root = engine.root().canonicalize().ok()?;
+    let project_root = root.canonicalize().ok()?;
+    let prefix = project_root.strip_prefix(&repo_root).ok()?.to_path_buf();
+    if prefix.as_os_str().is_empty() {
+        return Some(history);
+    }
+
+    let prefix = format!("{}/", prefix.to_string_lossy().replace('\\', "/"));
+    let mut rerooted = TicketIndex::new();
+    for (ticket, entry) in history.entries {
+        let commit_files: BTreeSet<String> = entry
+            .commit_files
+            .iter()
+            .filter_map(|p| p.strip_prefix(&prefix).map(|s| s.to_string()))
+            .collect();
+        rerooted.entries.insert(
+            ticket,
+            TicketEntry {
+                commit_files,
+                ..entry
+            },
+        );
+    }
+    Some(rerooted)
+}
+
+#[cfg(not(feature = "temporal"))]
+fn history_index(_root: &Path, _full_history: bool) -> Option<TicketIndex> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_keyed_and_hash_tickets() {
+        let ids = extract_ticket_ids("Fix JIRA-123 and #456 (see PROJ2-7, also #456)");
+        assert_eq!(ids, vec!["JIRA-123", "#456", "PROJ2-7"]);
+    }
+
+    #[test]
+    fn test_extract_ignores_false_positives() {
+        assert!(extract_ticket_ids("UTF-8 SHA-256 ISO-8601 CVE-2021").is_empty());
+        assert!(extract_ticket_ids("color: #fff; &#123; a#12 ##5").is_empty());
+        assert!(extract_ticket_ids("lowercase jira-1").is_empty());
+    }
+
+    #[test]
+    fn test_normalize_ticket_id() {
+        assert_eq!(normalize_ticket_id("jira-12"), "JIRA-12");
+        assert_eq!(normalize_ticket_id("456"), "#456");
+        assert_eq!(normalize_ticket_id("#456"), "#456");
+    }
+
+    #[test]
+    fn test_scan_comments_only() {
+        let mut index = TicketIndex::new();
+        index.scan_comments(
+            "src/lib.rs",
+            "// TODO(ABC-9): remove\nlet s = \"DEF-1 in a string\";\nx = 1 # see #77\n",
+        );
+        assert_eq!(index.files_for("ABC-9"), vec!["src/lib.rs"]);
+        assert_eq!(index.files_for("77"), vec!["src/lib.rs"]);
+        assert!(index.get("DEF-1").is_none());
+    }
+
+    #[test]
+    fn test_record_commit_and_merge() {
+        let mut history = TicketIndex::new();
+        history.record_commit(
+            "JIRA-1: fix parser\n\nCloses #3",
+            &["src/parser.rs".to_string()],
+        );
+        history.record_commit("JIRA-1 follow-up", &["src/lexer.rs".to_string()]);
+
+        let mut index = TicketIndex::new();
+        index.scan_comments("src/ast.rs", "# JIRA-1 workaround");
+        index.merge(history);
+
+        let entry = index.get("jira-1").unwrap();
+        assert_eq!(entry.commit_count, 2);
+        assert_eq!(
+            entry.files(),
+            vec!["src/ast.rs", "src/lexer.rs", "src/parser.rs"]
+        );
+        assert_eq!(index.files_for("#3"), vec!["src/parser.rs"]);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_build_ticket_index_without_repo() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let files = vec![("a.py".to_string(), "# OPS-5 hotfix\n".to_string())];
+        let index = build_ticket_index(temp.path(), &files, false);
+        assert_eq!(index.files_for("OPS-5"), vec!["a.py"]);
+    }
+}
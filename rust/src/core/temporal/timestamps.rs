@@ -0,0 +1,147 @@
+//! Timestamp Census - temporal analysis without history
+//!
+//! Exported tarballs and other checkouts without a repository still carry
+//! file times. Each file then counts as a single observation at its mtime,
+//! first seen at the earlier of its ctime and mtime. The census this gives
+//! is degraded: ages and dormancy are approximate, there are no observers,
+//! and churn never exceeds one observation per file, so supernovas can't be
+//! told apart. Its state is [`ChronosState::FileTimestamps`], so reports can
+//! say where the numbers came from.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::metrics::{
+    AgeClassification, AncientStar, ChronosState, ChurnClassification, ConstellationChurn,
+    FileChurn, TemporalCensus,
+};
+use super::thresholds::TemporalThresholds;
+
+/// Build a census from `(path, mtime, ctime)` triples, times in seconds
+/// since the epoch
+///
+/// Files without an mtime (0) are left out.
+pub fn census_from_timestamps<'a>(
+    files: impl IntoIterator<Item = (&'a str, u64, u64)>,
+    now: DateTime<Utc>,
+    thresholds: &TemporalThresholds,
+) -> TemporalCensus {
+    let mut census = TemporalCensus::default();
+    let days_since = |t: DateTime<Utc>| (now - t).num_days().max(0) as u64;
+
+    for (path, mtime, ctime) in files {
+        let Some(modified) = DateTime::from_timestamp(mtime as i64, 0).filter(|_| mtime > 0) else {
+            continue;
+        };
+        let first = match ctime {
+            0 => mtime,
+            ctime => ctime.min(mtime),
+        };
+        let age_days = DateTime::from_timestamp(first as i64, 0).map_or(0, days_since);
+        let churn_30d = usize::from(modified > now - Duration::days(30));
+        let churn_90d = usize::from(modified > now - Duration::days(90));
+        let dormant_days = days_since(modified);
+
+        census.files.insert(
+            path.to_string(),
+            FileChurn {
+                path: path.to_string(),
+                churn_30d,
+                churn_90d,
+                age_days,
+                last_observation: Some(modified),
+                churn_classification: ChurnClassification::classify(
+                    churn_30d, churn_90d, thresholds,
+                ),
+                age_classification: AgeClassification::from_days(age_days),
+            },
+        );
+        if dormant_days > thresholds.ancient_dormant_days {
+            census.ancient_stars.push(AncientStar {
+                path: path.to_string(),
+                age_days,
+                dormant_days,
+                star_count: 0, // To be filled by caller with census data
+                is_core: false,
+            });
+        }
+    }
+
+    let mut constellation_map: HashMap<String, Vec<&FileChurn>> = HashMap::new();
+    for (path, file_churn) in &census.files {
+        let constellation = Path::new(path)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+        constellation_map
+            .entry(constellation)
+            .or_default()
+            .push(file_churn);
+    }
+    for (path, files) in constellation_map {
+        let file_count = files.len();
+        let churn_90d: usize = files.iter().map(|f| f.churn_90d).sum();
+        let avg_age_days = files.iter().map(|f| f.age_days).sum::<u64>() / file_count as u64;
+        let max_30d = files.iter().map(|f| f.churn_30d).max().unwrap_or(0);
+        let classification =
+            ChurnClassification::classify(max_30d, churn_90d / file_count, thresholds);
+        census.constellations.insert(
+            path.clone(),
+            ConstellationChurn {
+                path,
+                file_count,
+                churn_90d,
+                avg_age_days,
+                primary_observers: Vec::new(),
+                classification,
+            },
+        );
+    }
+
+    census.galaxy_age_days = census.files.values().map(|f| f.age_days).max().unwrap_or(0);
+    census.total_observations = census.files.len();
+    census.state = ChronosState::FileTimestamps {
+        file_count: census.files.len(),
+        galaxy_age_days: census.galaxy_age_days,
+    };
+    census
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_census_from_timestamps() {
+        let now = DateTime::from_timestamp(1_800_000_000, 0).unwrap();
+        let day = 86_400;
+        let secs = now.timestamp() as u64;
+        let files = [
+            // Touched last week, created a year ago
+            ("src/hot.rs", secs - 7 * day, secs - 365 * day),
+            // Untouched for three years
+            ("src/old.rs", secs - 3 * 365 * day, secs - 3 * 365 * day),
+            ("README.md", secs - 60 * day, 0),
+            // No timestamp
+            ("unknown.txt", 0, 0),
+        ];
+        let census = census_from_timestamps(files, now, &TemporalThresholds::default());
+
+        assert!(matches!(
+            census.state,
+            ChronosState::FileTimestamps { file_count: 3, .. }
+        ));
+        assert_eq!(census.galaxy_age_days, 3 * 365);
+        let hot = &census.files["src/hot.rs"];
+        assert_eq!((hot.churn_30d, hot.churn_90d, hot.age_days), (1, 1, 365));
+        assert_eq!(hot.churn_classification, ChurnClassification::Low);
+        assert_eq!(census.files["README.md"].churn_30d, 0);
+        assert_eq!(census.ancient_stars.len(), 1);
+        assert_eq!(census.ancient_stars[0].path, "src/old.rs");
+        assert_eq!(census.constellations["src"].churn_90d, 1);
+        assert_eq!(census.constellations["."].file_count, 1);
+        assert!(census.supernovas.is_empty());
+    }
+}
@@ -168,8 +168,10 @@ impl FileWalker for DefaultWalker {
                 &path.strip_prefix(root).unwrap_or(path).to_string_lossy(),
             );
 
-            // Skip ignored files
-            if self.should_ignore(&relative_path, &config.ignore_patterns) {
+            // Skip ignored files, and the processing cache's blobs
+            if self.should_ignore(&relative_path, &config.ignore_patterns)
+                || relative_path.starts_with(&format!("{}/", crate::cache::CACHE_DIR))
+            {
                 continue;
             }
 
@@ -195,7 +197,7 @@ impl FileWalker for DefaultWalker {
             };
 
             // Skip binary files
-            if is_binary(&bytes) {
+            if is_binary(&bytes) && crate::decode_utf16(&bytes).is_none() {
                 continue;
             }
 
@@ -206,6 +208,8 @@ impl FileWalker for DefaultWalker {
             };
 
             // Get timestamps and size
+            // Through the link, if it is one
+            let executable = std::fs::metadata(path).is_ok_and(|m| crate::is_executable(&m));
             let (mtime, ctime, size) = metadata
                 .map(|m| {
                     let mtime = m
@@ -228,7 +232,9 @@ impl FileWalker for DefaultWalker {
             entries.push(
                 FileEntry::new(&relative_path, content)
                     .with_timestamps(mtime, ctime)
-                    .with_size(size),
+                    .with_size(size)
+                    .with_executable(executable)
+                    .with_symlink_target(crate::symlink_target(path)),
             );
         }
 
@@ -254,6 +260,10 @@ pub fn is_binary(content: &[u8]) -> bool {
 
 /// Read file content, handling encoding
 pub fn read_file_content(bytes: &[u8]) -> Option<String> {
+    if let Some(s) = crate::decode_utf16(bytes) {
+        return Some(s.replace("\r\n", "\n"));
+    }
+
     // Try UTF-8 first
     if let Ok(s) = std::str::from_utf8(bytes) {
         // Normalize line endings
@@ -277,44 +287,6 @@ use crate::core::manifest::ProjectManifest;
 use ignore::{WalkBuilder, WalkState};
 use std::sync::mpsc;
 
-/// Hard-coded exclusion patterns (hygiene layer).
-/// These are ALWAYS excluded regardless of .gitignore.
-const HYGIENE_EXCLUSIONS: &[&str] = &[
-    // Version control
-    ".git",
-    ".hg",
-    ".svn",
-    // Package managers / dependencies
-    "node_modules",
-    ".npm",
-    ".yarn",
-    // Python environments
-    ".venv",
-    "venv",
-    "env",
-    "__pycache__",
-    ".pytest_cache",
-    ".mypy_cache",
-    ".ruff_cache",
-    ".eggs",
-    // Build artifacts
-    "target",
-    "dist",
-    "build",
-    "out",
-    "_build",
-    ".build",
-    // IDE / Editor
-    ".idea",
-    ".vscode",
-    // OS artifacts
-    ".DS_Store",
-    "Thumbs.db",
-];
-
-/// Wildcard exclusion patterns (matched by suffix).
-const HYGIENE_WILDCARDS: &[&str] = &[".egg-info", ".swp", ".swo", ".pyc"];
-
 /// Result of walking a directory with SmartWalker.
 #[derive(Debug, Clone)]
 pub struct WalkEntry {
@@ -402,26 +374,10 @@ impl SmartWalker {
         &self.root
     }
 
-    /// Check if a path should be excluded by hygiene rules.
+    /// Check if a path should be excluded by hygiene rules (see
+    /// [`project_walk::is_hygiene_excluded`]).
     pub fn is_hygiene_excluded(path: &Path) -> bool {
-        path.components().any(|c| {
-            let name = c.as_os_str().to_string_lossy();
-
-            // Check exact matches
-            if HYGIENE_EXCLUSIONS.iter().any(|&pattern| name == pattern) {
-                return true;
-            }
-
-            // Check wildcard patterns (suffix match)
-            if HYGIENE_WILDCARDS
-                .iter()
-                .any(|&pattern| name.ends_with(pattern))
-            {
-                return true;
-            }
-
-            false
-        })
+        project_walk::is_hygiene_excluded(path)
     }
 
     /// Walk the directory and collect file entries.
@@ -568,7 +524,7 @@ impl SmartWalker {
             };
 
             // Skip binary files
-            if is_binary(&bytes) {
+            if is_binary(&bytes) && crate::decode_utf16(&bytes).is_none() {
                 continue;
             }
 
@@ -579,7 +535,9 @@ impl SmartWalker {
             };
 
             // Get timestamps
-            let (mtime, ctime) = std::fs::metadata(&entry.path)
+            let metadata = std::fs::metadata(&entry.path);
+            let executable = metadata.as_ref().is_ok_and(crate::is_executable);
+            let (mtime, ctime) = metadata
                 .map(|m| {
                     let mtime = m
                         .modified()
@@ -602,7 +560,9 @@ impl SmartWalker {
                     normalize_path_separators(&entry.relative_path.to_string_lossy()),
                     content,
                 )
-                .with_timestamps(mtime, ctime),
+                .with_timestamps(mtime, ctime)
+                .with_executable(executable)
+                .with_symlink_target(crate::symlink_target(&entry.path)),
             );
         }
 
@@ -13,6 +13,7 @@
 //! The LLM can then request expansion via MCP or CLI.
 
 use crate::core::error::{EncoderError, Result};
+use crate::migrate::StateKind;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
@@ -567,7 +568,7 @@ impl ZoomSessionStore {
         let content =
             std::fs::read_to_string(path).map_err(|e| format!("Failed to read sessions: {}", e))?;
 
-        let mut store: Self = serde_json::from_str(&content)
+        let mut store: Self = crate::migrate::load_upgraded(StateKind::Sessions, &content)
             .map_err(|e| format!("Failed to parse sessions: {}", e))?;
 
         store.store_path = Some(path.to_path_buf());
@@ -0,0 +1,508 @@
+//! Reverse decoder for Plus/Minus output
+//!
+//! Parses the `++++++++++ path ++++++++++` / `---------- path md5 path ----------`
+//! framing back into files, verifies each checksum, and writes the files to
+//! disk. This closes the loop for "the LLM edits the context, we re-apply it":
+//! files whose checksum no longer matches are reported as modified.
+//!
+//! ## Checksums
+//!
+//! The serializer always terminates content with a newline, so the decoder
+//! tries the content with and without that final newline against the footer
+//! checksum. Truncated files can never be verified and are never written,
+//! since that would replace a full file with its truncated view.
+//!
+//! Content serialized with `--line-numbers` is recognised by its uniform
+//! `N | ` gutter, which is stripped before verification. A `--dedupe` stub
+//! (`duplicate of <path>`) is restored from the file it names when that
+//! file carries the same checksum.
+
+use std::fs;
+use std::path::{Component, Path};
+
+use crate::calculate_md5;
+
+pub(crate) const HEADER_MARK: &str = "++++++++++";
+pub(crate) const FOOTER_MARK: &str = "----------";
+
+/// Checksum verification result for a decoded file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// Content matches the footer checksum
+    Verified,
+    /// Content differs from the footer checksum (edited after encoding)
+    Modified,
+    /// The file was truncated when encoded; content is partial
+    Truncated,
+}
+
+/// A file reconstructed from Plus/Minus output
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedFile {
+    /// Relative path from the framing
+    pub path: String,
+    /// Reconstructed content
+    pub content: String,
+    /// Checksum recorded in the footer
+    pub md5: String,
+    /// Verification result
+    pub status: ChecksumStatus,
+    /// Original line numbers read from a `--line-numbers` gutter, one per
+    /// content line (`None` for marker lines); empty if there was no gutter
+    pub line_numbers: Vec<Option<usize>>,
+}
+
+/// Options for [`write_files`]
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// Refuse to write anything if any file fails verification
+    pub strict: bool,
+    /// Report what would be written without touching the disk
+    pub dry_run: bool,
+}
+
+/// Outcome of [`write_files`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodeReport {
+    /// Files written with a verified checksum
+    pub verified: Vec<String>,
+    /// Files written whose content differs from the recorded checksum
+    pub modified: Vec<String>,
+    /// Truncated files that were skipped
+    pub skipped: Vec<String>,
+}
+
+/// Parse a header line into its path, if it is a header
+pub(crate) fn parse_header(line: &str) -> Option<&str> {
+    let inner = line
+        .strip_prefix(HEADER_MARK)?
+        .strip_prefix(' ')?
+        .strip_suffix(HEADER_MARK)?
+        .strip_suffix(' ')?;
+
+    // Drop " [MERGE CONFLICTS: N]", " [TRUNCATED: N lines]" and then the
+    // metadata suffix " [S:.. M:..]" or header hints " [lang:.. tokens:..]"
+    let mut path = inner;
+    if let Some(pos) = path.rfind(" [MERGE CONFLICTS: ") {
+        if path.ends_with(']') {
+            path = &path[..pos];
+        }
+    }
+    if let Some(pos) = path.rfind(" [TRUNCATED: ") {
+        if path.ends_with(" lines]") {
+            path = &path[..pos];
+        }
+    }
+    if path.ends_with(']') {
+        if let Some(pos) = path.rfind(" [") {
+            let tag = &path[pos + 2..];
+            if tag.starts_with("S:") || tag.starts_with("M:") || tag.starts_with("lang:") {
+                path = &path[..pos];
+            }
+        }
+    }
+    if path.is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Parse a footer line for `path`: returns (md5, truncated)
+pub(crate) fn parse_footer<'a>(line: &'a str, path: &str) -> Option<(&'a str, bool)> {
+    let inner = line
+        .strip_prefix(FOOTER_MARK)?
+        .strip_prefix(' ')?
+        .strip_suffix(FOOTER_MARK)?
+        .strip_suffix(' ')?;
+
+    // "<path>[ [TRUNCATED:a→b]] <md5> <path>"
+    let rest = inner.strip_prefix(path)?.strip_prefix(' ')?;
+    let rest = rest.strip_suffix(path)?.strip_suffix(' ')?;
+    let (truncated, md5) = match rest.rsplit_once(' ') {
+        Some((marker, md5)) if marker.starts_with("[TRUNCATED:") && marker.ends_with(']') => {
+            (true, md5)
+        }
+        Some(_) => return None,
+        None => (false, rest),
+    };
+
+    if md5.len() == 32 && md5.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some((md5, truncated))
+    } else {
+        None
+    }
+}
+
+/// Decode Plus/Minus output into files
+///
+/// Text outside of file frames is ignored. A header without a matching
+/// footer is an error.
+pub fn decode(input: &str) -> Result<Vec<DecodedFile>, String> {
+    let mut files = Vec::new();
+    let mut lines = input.split_inclusive('\n').enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let path = match parse_header(line.trim_end_matches(['\n', '\r'])) {
+            Some(p) => p.to_string(),
+            None => continue,
+        };
+
+        let mut content = String::new();
+        let mut footer = None;
+        for (_, body_line) in lines.by_ref() {
+            if let Some(parsed) = parse_footer(body_line.trim_end_matches(['\n', '\r']), &path) {
+                footer = Some(parsed);
+                break;
+            }
+            content.push_str(body_line);
+        }
+
+        let (md5, truncated) = match footer {
+            Some(f) => f,
+            None => {
+                return Err(format!(
+                    "line {}: no footer found for '{}'",
+                    index + 1,
+                    path
+                ))
+            }
+        };
+
+        let (content, line_numbers) = match strip_line_numbers(&content) {
+            Some((stripped, numbers)) => (stripped, numbers),
+            None => (content, Vec::new()),
+        };
+        let (content, status) = if truncated {
+            (content, ChecksumStatus::Truncated)
+        } else if let Some(original) = checked_content(&content, md5) {
+            (original, ChecksumStatus::Verified)
+        } else if let Some(original) =
+            checked_content(&crate::unescape_control_chars(&content), md5)
+        {
+            // Control characters were escaped on the way out
+            (original, ChecksumStatus::Verified)
+        } else {
+            (content, ChecksumStatus::Modified)
+        };
+
+        files.push(DecodedFile {
+            path,
+            content,
+            md5: md5.to_string(),
+            status,
+            line_numbers,
+        });
+    }
+
+    restore_duplicates(&mut files);
+    Ok(files)
+}
+
+/// `content` if it hashes to `md5`, possibly after dropping the newline
+/// the encoder added at its end
+fn checked_content(content: &str, md5: &str) -> Option<String> {
+    if calculate_md5(content) == md5 {
+        Some(content.to_string())
+    } else {
+        content
+            .strip_suffix('\n')
+            .filter(|c| calculate_md5(c) == md5)
+            .map(str::to_string)
+    }
+}
+
+/// Put the first copy's content back into `--dedupe` stubs
+fn restore_duplicates(files: &mut [DecodedFile]) {
+    for i in 0..files.len() {
+        if files[i].status != ChecksumStatus::Modified {
+            continue;
+        }
+        let first = files[i]
+            .content
+            .strip_prefix("duplicate of ")
+            .map(|rest| rest.trim_end_matches('\n'))
+            .and_then(|path| {
+                files.iter().find(|f| {
+                    f.path == path && f.md5 == files[i].md5 && f.status == ChecksumStatus::Verified
+                })
+            })
+            .map(|f| f.content.clone());
+        if let Some(content) = first {
+            files[i].content = content;
+            files[i].status = ChecksumStatus::Verified;
+        }
+    }
+}
+
+/// Remove a `--line-numbers` gutter, if every line carries one
+///
+/// The gutter is a right-aligned number (blank for marker lines) followed by
+/// ` | `, all of the same width. Returns the stripped content and the number
+/// on each line.
+pub(crate) fn strip_line_numbers(content: &str) -> Option<(String, Vec<Option<usize>>)> {
+    let width = content.find(" | ")?;
+    if width == 0 {
+        return None;
+    }
+
+    let mut stripped = String::with_capacity(content.len());
+    let mut numbers = Vec::new();
+    for line in content.split_inclusive('\n') {
+        let (gutter, rest) = (line.get(..width)?, line.get(width..)?);
+        let rest = rest.strip_prefix(" | ")?;
+        let digits = gutter.trim_start_matches(' ');
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        numbers.push(digits.parse().ok());
+        stripped.push_str(rest);
+    }
+
+    numbers
+        .iter()
+        .any(Option::is_some)
+        .then_some((stripped, numbers))
+}
+
+/// Check that a decoded path stays below the output root
+fn validate_path(path: &str) -> Result<(), String> {
+    let p = Path::new(path);
+    let escapes = p.components().any(|c| {
+        matches!(
+            c,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_)
+        )
+    });
+    if escapes || path.is_empty() {
+        Err(format!(
+            "Refusing to write '{}': path escapes the output root",
+            path
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Write decoded files below `root`
+///
+/// Truncated files are skipped. With `strict`, any modified file aborts the
+/// whole write before anything touches the disk. Paths that are absolute or
+/// contain `..` are always rejected.
+pub fn write_files(
+    files: &[DecodedFile],
+    root: &Path,
+    options: &DecodeOptions,
+) -> Result<DecodeReport, String> {
+    for file in files {
+        validate_path(&file.path)?;
+    }
+
+    if options.strict {
+        if let Some(file) = files.iter().find(|f| f.status == ChecksumStatus::Modified) {
+            return Err(format!(
+                "Checksum mismatch for '{}' (expected {})",
+                file.path, file.md5
+            ));
+        }
+    }
+
+    let mut report = DecodeReport::default();
+    for file in files {
+        let bucket = match file.status {
+            ChecksumStatus::Truncated => {
+                report.skipped.push(file.path.clone());
+                continue;
+            }
+            ChecksumStatus::Verified => &mut report.verified,
+            ChecksumStatus::Modified => &mut report.modified,
+        };
+
+        if !options.dry_run {
+            let target = root.join(&file.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            fs::write(&target, &file.content)
+                .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        }
+        bucket.push(file.path.clone());
+    }
+
+    Ok(report)
+}
+
+/// Decode Plus/Minus output and write the files below `root`
+pub fn decode_to_dir(
+    input: &str,
+    root: &Path,
+    options: &DecodeOptions,
+) -> Result<DecodeReport, String> {
+    let files = decode(input)?;
+    write_files(&files, root, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{serialize_project_with_config, EncoderConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_round_trip_through_serializer() {
+        let src = TempDir::new().unwrap();
+        fs::create_dir_all(src.path().join("src")).unwrap();
+        fs::write(src.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(src.path().join("no newline.txt"), "last line").unwrap();
+
+        let config = EncoderConfig {
+            metadata_mode: crate::MetadataMode::All,
+            ..Default::default()
+        };
+        let output = serialize_project_with_config(src.path().to_str().unwrap(), &config).unwrap();
+
+        let files = decode(&output).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f.status == ChecksumStatus::Verified));
+
+        let dest = TempDir::new().unwrap();
+        let report = write_files(&files, dest.path(), &DecodeOptions::default()).unwrap();
+        assert_eq!(report.verified.len(), 2);
+        assert_eq!(
+            fs::read_to_string(dest.path().join("src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.path().join("no newline.txt")).unwrap(),
+            "last line"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_stubs_are_restored() {
+        let src = TempDir::new().unwrap();
+        let body = "fn shared() -> u32 {\n    42\n}\n";
+        fs::write(src.path().join("a.rs"), body).unwrap();
+        fs::write(src.path().join("b.rs"), body).unwrap();
+
+        let config = EncoderConfig {
+            dedupe: true,
+            ..Default::default()
+        };
+        let output = serialize_project_with_config(src.path().to_str().unwrap(), &config).unwrap();
+        assert_eq!(output.matches("fn shared()").count(), 1);
+        assert!(output.contains("duplicate of a.rs\n"));
+
+        let files = decode(&output).unwrap();
+        assert!(files.iter().all(|f| f.status == ChecksumStatus::Verified));
+        assert_eq!(files[1].content, body);
+    }
+
+    #[test]
+    fn test_edited_content_is_modified() {
+        let md5 = calculate_md5("a\n");
+        let input = format!(
+            "++++++++++ a.txt ++++++++++\nb\n---------- a.txt {} a.txt ----------\n",
+            md5
+        );
+        let files = decode(&input).unwrap();
+        assert_eq!(files[0].status, ChecksumStatus::Modified);
+        assert_eq!(files[0].content, "b\n");
+
+        let dest = TempDir::new().unwrap();
+        let strict = DecodeOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(write_files(&files, dest.path(), &strict).is_err());
+        assert!(!dest.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_truncated_files_are_skipped() {
+        let md5 = calculate_md5("full\ncontent\n");
+        let input = format!(
+            "++++++++++ t.rs [TRUNCATED: 2 lines] ++++++++++\nfull\n---------- t.rs [TRUNCATED:2→1] {} t.rs ----------\n",
+            md5
+        );
+        let files = decode(&input).unwrap();
+        assert_eq!(files[0].path, "t.rs");
+        assert_eq!(files[0].status, ChecksumStatus::Truncated);
+
+        let dest = TempDir::new().unwrap();
+        let report = write_files(&files, dest.path(), &DecodeOptions::default()).unwrap();
+        assert_eq!(report.skipped, vec!["t.rs".to_string()]);
+        assert!(!dest.path().join("t.rs").exists());
+    }
+
+    #[test]
+    fn test_nested_frames_inside_content() {
+        let inner = "++++++++++ other.txt ++++++++++\n";
+        let md5 = calculate_md5(inner);
+        let input = format!(
+            "++++++++++ doc.md ++++++++++\n{}---------- doc.md {} doc.md ----------\n",
+            inner, md5
+        );
+        let files = decode(&input).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].content, inner);
+        assert_eq!(files[0].status, ChecksumStatus::Verified);
+    }
+
+    #[test]
+    fn test_line_numbers_are_stripped() {
+        let md5 = calculate_md5("a\n\nb\n");
+        let input = format!(
+            "++++++++++ a.txt ++++++++++\n1 | a\n2 | \n3 | b\n---------- a.txt {} a.txt ----------\n",
+            md5
+        );
+        let files = decode(&input).unwrap();
+        assert_eq!(files[0].content, "a\n\nb\n");
+        assert_eq!(files[0].status, ChecksumStatus::Verified);
+        assert_eq!(files[0].line_numbers, vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_header_hints_are_ignored() {
+        let md5 = calculate_md5("x\n");
+        let input = format!(
+            "++++++++++ a.py [lang:python tokens:1] ++++++++++\nx\n---------- a.py {} a.py ----------\n",
+            md5
+        );
+        let files = decode(&input).unwrap();
+        assert_eq!(files[0].path, "a.py");
+        assert_eq!(files[0].status, ChecksumStatus::Verified);
+    }
+
+    #[test]
+    fn test_merge_conflict_flag_is_not_part_of_the_path() {
+        assert_eq!(
+            parse_header("++++++++++ a.rs [M:1m] [MERGE CONFLICTS: 2] ++++++++++"),
+            Some("a.rs")
+        );
+        assert_eq!(
+            parse_header("++++++++++ a.rs [TRUNCATED: 9 lines] [MERGE CONFLICTS: 1] ++++++++++"),
+            Some("a.rs")
+        );
+    }
+
+    #[test]
+    fn test_missing_footer_is_error() {
+        let err = decode("++++++++++ a.txt ++++++++++\ncontent\n").unwrap_err();
+        assert!(err.contains("line 1"));
+    }
+
+    #[test]
+    fn test_rejects_escaping_paths() {
+        let file = DecodedFile {
+            path: "../evil.txt".to_string(),
+            content: String::new(),
+            md5: String::new(),
+            status: ChecksumStatus::Modified,
+            line_numbers: Vec::new(),
+        };
+        let dest = TempDir::new().unwrap();
+        assert!(write_files(&[file], dest.path(), &DecodeOptions::default()).is_err());
+    }
+}
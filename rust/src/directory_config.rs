@@ -0,0 +1,318 @@
+//! Per-directory `.pm_encoder_config.json` files
+//!
+//! In a monorepo each package can carry its own config next to its sources.
+//! The root config (loaded by [`EncoderConfig::from_file`]) still decides
+//! what is walked; nested configs then refine it for their subtree:
+//!
+//! - `ignore_patterns` are matched relative to the config's directory and
+//!   layered outermost first, so with gitignore precedence a deeper `!pattern`
+//!   can re-include what a shallower nested config ignored. They can't
+//!   re-include what the root config ignored.
+//! - `truncate_lines` and `truncate_mode` come from the closest config that
+//!   sets them, falling back to the root settings.
+//!
+//! `include_patterns` and `templates` are only read from the root config.
+//!
+//! [`EncoderConfig::from_file`]: crate::EncoderConfig::from_file
+
+use std::path::Path;
+
+use crate::core::SmartWalker;
+use crate::discovery::pattern_syntax::{PatternStyle, CASE_INSENSITIVE_FS};
+use crate::Config;
+
+/// File name looked for in every directory
+pub const CONFIG_FILE_NAME: &str = ".pm_encoder_config.json";
+
+/// One nested config and the directory it applies to
+#[derive(Debug, Clone)]
+struct Layer {
+    /// Directory relative to the project root, `/`-separated
+    dir: String,
+    config: Config,
+}
+
+impl Layer {
+    /// `path` relative to this layer's directory, if the layer covers it
+    fn local_path<'a>(&self, path: &'a str) -> Option<&'a str> {
+        if self.dir.is_empty() {
+            return Some(path);
+        }
+        path.strip_prefix(self.dir.as_str())?.strip_prefix('/')
+    }
+}
+
+/// Nested configs found below a project root
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryConfigs {
+    /// Sorted outermost first
+    layers: Vec<Layer>,
+}
+
+impl DirectoryConfigs {
+    /// Find and parse every nested config below `root`
+    ///
+    /// The root's own config is not included. Hygiene-excluded and
+    /// gitignored directories are skipped; a malformed file is an error.
+    pub fn discover(root: &Path) -> Result<Self, String> {
+        let mut layers = Vec::new();
+        let walker = ignore::WalkBuilder::new(root)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(|entry| !SmartWalker::is_hygiene_excluded(entry.path()))
+            .build();
+
+        for entry in walker.flatten() {
+            if entry.file_name() != CONFIG_FILE_NAME
+                || !entry.file_type().is_some_and(|ft| ft.is_file())
+            {
+                continue;
+            }
+            let Some(dir) = entry.path().parent() else {
+                continue;
+            };
+            let Ok(rel) = dir.strip_prefix(root) else {
+                continue;
+            };
+            if rel.as_os_str().is_empty() {
+                continue;
+            }
+
+            let content = std::fs::read_to_string(entry.path())
+                .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+            let config: Config = serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", entry.path().display(), e))?;
+            layers.push(Layer {
+                dir: rel.to_string_lossy().replace('\\', "/"),
+                config,
+            });
+        }
+
+        let mut configs = Self { layers };
+        configs.sort();
+        Ok(configs)
+    }
+
+    /// Like [`Self::discover`], for a root whose paths are reported under
+    /// `prefix` (see [`EncoderConfig::prefix_roots`])
+    ///
+    /// With `with_root_config` the root's own config, if any, becomes a
+    /// layer for the whole prefix, as for the extra roots of a multi-root
+    /// run.
+    ///
+    /// [`EncoderConfig::prefix_roots`]: crate::EncoderConfig::prefix_roots
+    pub fn discover_prefixed(
+        root: &Path,
+        prefix: &str,
+        with_root_config: bool,
+    ) -> Result<Self, String> {
+        let mut configs = Self::discover(root)?;
+        for layer in &mut configs.layers {
+            layer.dir = format!("{}/{}", prefix, layer.dir);
+        }
+        let root_config = root.join(CONFIG_FILE_NAME);
+        if with_root_config && root_config.is_file() {
+            let content = std::fs::read_to_string(&root_config)
+                .map_err(|e| format!("Failed to read {}: {}", root_config.display(), e))?;
+            let config: Config = serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", root_config.display(), e))?;
+            configs.layers.push(Layer {
+                dir: prefix.to_string(),
+                config,
+            });
+        }
+        configs.sort();
+        Ok(configs)
+    }
+
+    /// Add the configs of another (prefixed) root
+    pub fn extend(&mut self, other: Self) {
+        self.layers.extend(other.layers);
+        self.sort();
+    }
+
+    /// The configs below `prefix`, relative to it, for walking that root
+    pub fn within(&self, prefix: &str) -> Self {
+        let layers = self
+            .layers
+            .iter()
+            .filter_map(|layer| {
+                let dir = if layer.dir == prefix {
+                    ""
+                } else {
+                    layer.dir.strip_prefix(prefix)?.strip_prefix('/')?
+                };
+                Some(Layer {
+                    dir: dir.to_string(),
+                    config: layer.config.clone(),
+                })
+            })
+            .collect();
+        Self { layers }
+    }
+
+    /// Outermost first, so closer configs win
+    fn sort(&mut self) {
+        self.layers.sort_by(|a, b| {
+            let depth = |l: &Layer| {
+                if l.dir.is_empty() {
+                    0
+                } else {
+                    l.dir.matches('/').count() + 1
+                }
+            };
+            depth(a).cmp(&depth(b)).then_with(|| a.dir.cmp(&b.dir))
+        });
+    }
+
+    /// Whether no nested configs were found
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Directories (relative to the root) that carry a config
+    pub fn directories(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|l| l.dir.as_str())
+    }
+
+    /// Whether a nested config ignores file `path` (relative to the root)
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.ignores(path, false)
+    }
+
+    /// Whether a nested config ignores `path`, each config matching its
+    /// patterns in its own `pattern_syntax` and case handling
+    fn ignores(&self, path: &str, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for layer in &self.layers {
+            if let Some(local) = layer.local_path(path) {
+                let style = PatternStyle {
+                    syntax: layer.config.pattern_syntax.unwrap_or_default(),
+                    case_insensitive: layer
+                        .config
+                        .case_insensitive_patterns
+                        .unwrap_or(CASE_INSENSITIVE_FS),
+                };
+                if let Some(verdict) = project_walk::pattern_verdict(
+                    local,
+                    &layer.config.ignore_patterns,
+                    style,
+                    is_dir,
+                ) {
+                    ignored = verdict;
+                }
+            }
+        }
+        ignored
+    }
+
+    /// Whether the walk can skip directory `dir` without missing a re-include
+    pub fn prunes(&self, dir: &str) -> bool {
+        self.ignores(dir, true)
+            && !self.layers.iter().any(|layer| {
+                layer.local_path(dir).is_some_and(|local| {
+                    project_walk::negation_reaches_into(local, &layer.config.ignore_patterns)
+                })
+            })
+    }
+
+    /// Closest `truncate_lines` and `truncate_mode` covering `path`
+    pub fn truncation_for(&self, path: &str) -> (Option<usize>, Option<&str>) {
+        let mut lines = None;
+        let mut mode = None;
+        for layer in self.layers.iter().filter(|l| l.local_path(path).is_some()) {
+            lines = layer.config.truncate_lines.or(lines);
+            mode = layer.config.truncate_mode.as_deref().or(mode);
+        }
+        (lines, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_discover_and_merge() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("packages/api/legacy")).unwrap();
+        fs::create_dir_all(root.join("packages/web")).unwrap();
+        fs::write(
+            root.join(CONFIG_FILE_NAME),
+            r#"{"ignore_patterns": ["*.log"]}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("packages/api").join(CONFIG_FILE_NAME),
+            r#"{"ignore_patterns": ["fixtures"], "truncate_lines": 50, "truncate_mode": "smart"}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("packages/api/legacy").join(CONFIG_FILE_NAME),
+            r#"{"ignore_patterns": ["!fixtures/keep.json"], "truncate_lines": 10}"#,
+        )
+        .unwrap();
+
+        let configs = DirectoryConfigs::discover(root).unwrap();
+        assert_eq!(
+            configs.directories().collect::<Vec<_>>(),
+            vec!["packages/api", "packages/api/legacy"]
+        );
+
+        assert!(configs.is_ignored("packages/api/fixtures/a.json"));
+        assert!(configs.is_ignored("packages/api/legacy/fixtures/a.json"));
+        assert!(!configs.is_ignored("packages/api/legacy/fixtures/keep.json"));
+        assert!(!configs.is_ignored("packages/web/fixtures/a.json"));
+        assert!(configs.prunes("packages/api/fixtures"));
+        assert!(!configs.prunes("packages/api/legacy/fixtures"));
+
+        assert_eq!(
+            configs.truncation_for("packages/api/legacy/old.rs"),
+            (Some(10), Some("smart"))
+        );
+        assert_eq!(
+            configs.truncation_for("packages/api/src/lib.rs"),
+            (Some(50), Some("smart"))
+        );
+        assert_eq!(configs.truncation_for("packages/web/app.ts"), (None, None));
+    }
+
+    #[test]
+    fn test_prefixed_configs() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("pkg")).unwrap();
+        fs::write(root.join(CONFIG_FILE_NAME), r#"{"truncate_lines": 10}"#).unwrap();
+        fs::write(
+            root.join("pkg").join(CONFIG_FILE_NAME),
+            r#"{"ignore_patterns": ["*.gen"]}"#,
+        )
+        .unwrap();
+
+        let configs = DirectoryConfigs::discover_prefixed(root, "proto", true).unwrap();
+        assert_eq!(
+            configs.directories().collect::<Vec<_>>(),
+            vec!["proto", "proto/pkg"]
+        );
+        assert_eq!(configs.truncation_for("proto/a.rs"), (Some(10), None));
+        assert_eq!(configs.truncation_for("service/a.rs"), (None, None));
+        assert!(configs.is_ignored("proto/pkg/x.gen"));
+
+        let within = configs.within("proto");
+        assert!(within.is_ignored("pkg/x.gen"));
+        assert_eq!(within.truncation_for("a.rs"), (Some(10), None));
+        assert!(configs.within("service").is_empty());
+    }
+
+    #[test]
+    fn test_discover_rejects_malformed_config() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("pkg")).unwrap();
+        fs::write(temp.path().join("pkg").join(CONFIG_FILE_NAME), "{not json").unwrap();
+
+        let err = DirectoryConfigs::discover(temp.path()).unwrap_err();
+        assert!(err.contains("Failed to parse"));
+    }
+}
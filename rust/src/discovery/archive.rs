@@ -0,0 +1,362 @@
+//! Archive input
+//!
+//! A `.zip`, `.tar`, `.tar.gz` or `.tgz` file given as the project root is
+//! walked as a virtual directory: members are read straight out of the
+//! archive, nothing is extracted to disk. Member paths are checked before
+//! use - absolute paths and `..` components are reported as skipped rather
+//! than trusted - and, as with `--remote` tarballs, a single top-level
+//! directory wrapping every file (`repo-main/`) is dropped from the paths.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::SkippedFile;
+
+/// Whether `path` is an archive file that can be walked as a project root
+pub fn is_archive(path: &Path) -> bool {
+    archive_stem(path).is_some() && path.is_file()
+}
+
+/// The file name of an archive without its archive extension
+pub fn archive_stem(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    ARCHIVE_EXTENSIONS
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .filter(|stem| !stem.is_empty())
+}
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tar.gz", ".tgz", ".tar", ".zip"];
+
+/// A regular file read from an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveMember {
+    /// Path relative to the archive root, `/`-separated
+    pub path: String,
+    pub bytes: Vec<u8>,
+    /// Modification time from the member header (seconds since the epoch)
+    pub mtime: u64,
+    /// Whether the member's Unix mode has an execute bit set
+    pub executable: bool,
+}
+
+/// Read the regular files of the archive at `path`, sorted by path
+///
+/// `admit` sees each member's path and uncompressed size before its data is
+/// read; members it rejects are passed over. Directories, symlinks and
+/// other special members are never returned. Members with unsafe paths, or
+/// whose data can't be read, come back as `Err`.
+pub fn read_archive(
+    path: &Path,
+    admit: impl Fn(&str, u64) -> bool,
+) -> Result<Vec<Result<ArchiveMember, SkippedFile>>, String> {
+    let name = path.to_string_lossy();
+    let error = |e: &dyn std::fmt::Display| format!("{}: {}", name, e);
+    let mut members = if name.ends_with(".zip") {
+        read_zip(path, &admit).map_err(|e| error(&e))?
+    } else {
+        read_tar(path, &admit).map_err(|e| error(&e))?
+    };
+    members.sort_by(|a, b| member_path(a).cmp(member_path(b)));
+    Ok(members)
+}
+
+fn member_path(member: &Result<ArchiveMember, SkippedFile>) -> &str {
+    match member {
+        Ok(member) => &member.path,
+        Err(skipped) => &skipped.path,
+    }
+}
+
+/// The member's path with `.` and empty components dropped, or `None` if it
+/// is absolute or climbs out of the archive with `..`
+fn safe_member_path(name: &str) -> Option<String> {
+    let name = name.replace('\\', "/");
+    if name.starts_with('/') || name.split('/').next().is_some_and(|c| c.contains(':')) {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for part in name.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn unsafe_path(name: &str) -> SkippedFile {
+    SkippedFile {
+        path: name.to_string(),
+        reason: "path leaves the archive root".to_string(),
+    }
+}
+
+/// The top-level directory that every file in `paths` sits in, if any
+fn wrapper_dir<'a>(mut paths: impl Iterator<Item = &'a str>) -> Option<String> {
+    let first = paths.next()?;
+    let (dir, _) = first.split_once('/')?;
+    let prefix = format!("{}/", dir);
+    paths.all(|p| p.starts_with(&prefix)).then_some(prefix)
+}
+
+/// Relative path for a member given the wrapper directory to strip
+fn unwrapped(path: String, wrapper: Option<&str>) -> String {
+    match wrapper.and_then(|w| path.strip_prefix(w)) {
+        Some(rest) => rest.to_string(),
+        None => path,
+    }
+}
+
+/// Seconds since the epoch for a zip (MS-DOS) timestamp
+fn zip_mtime(time: zip::DateTime) -> u64 {
+    chrono::NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())
+        .and_then(|date| {
+            date.and_hms_opt(
+                time.hour().into(),
+                time.minute().into(),
+                time.second().into(),
+            )
+        })
+        .map_or(0, |t| t.and_utc().timestamp().max(0) as u64)
+}
+
+fn read_zip(
+    path: &Path,
+    admit: &dyn Fn(&str, u64) -> bool,
+) -> Result<Vec<Result<ArchiveMember, SkippedFile>>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    // The central directory lists every member up front
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let member = archive.by_index_raw(index).map_err(|e| e.to_string())?;
+        if member.is_file() {
+            let name = member.name().map_err(|e| e.to_string())?.into_owned();
+            files.push((index, safe_member_path(&name), name));
+        }
+    }
+    let wrapper = wrapper_dir(files.iter().filter_map(|(_, safe, _)| safe.as_deref()));
+
+    let mut members = Vec::new();
+    for (index, safe, name) in files {
+        let Some(safe) = safe else {
+            members.push(Err(unsafe_path(&name)));
+            continue;
+        };
+        let rel = unwrapped(safe, wrapper.as_deref());
+        let mut member = match archive.by_index(index) {
+            Ok(member) => member,
+            Err(e) => {
+                members.push(Err(SkippedFile {
+                    path: rel,
+                    reason: e.to_string(),
+                }));
+                continue;
+            }
+        };
+        if !admit(&rel, member.size()) {
+            continue;
+        }
+        let mtime = member.last_modified().map_or(0, zip_mtime);
+        let executable = member.unix_mode().is_some_and(|mode| mode & 0o111 != 0);
+        let mut bytes = Vec::new();
+        members.push(match member.read_to_end(&mut bytes) {
+            Ok(_) => Ok(ArchiveMember {
+                path: rel,
+                bytes,
+                mtime,
+                executable,
+            }),
+            Err(e) => Err(SkippedFile {
+                path: rel,
+                reason: e.to_string(),
+            }),
+        });
+    }
+    Ok(members)
+}
+
+/// Open a plain or gzipped tar archive
+fn open_tar(path: &Path) -> std::io::Result<tar::Archive<Box<dyn Read>>> {
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 2];
+    let gzipped = file.read(&mut magic)? == 2 && magic == [0x1f, 0x8b];
+    let file = File::open(path)?;
+    let reader: Box<dyn Read> = if gzipped {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn read_tar(
+    path: &Path,
+    admit: &dyn Fn(&str, u64) -> bool,
+) -> Result<Vec<Result<ArchiveMember, SkippedFile>>, String> {
+    // A tar has no index: one pass over the headers to find the wrapper
+    // directory, a second to read the admitted members
+    let mut paths = Vec::new();
+    let mut archive = open_tar(path).map_err(|e| e.to_string())?;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if entry.header().entry_type().is_file() {
+            let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            paths.push(safe_member_path(&name));
+        }
+    }
+    let wrapper = wrapper_dir(paths.iter().filter_map(|p| p.as_deref()));
+
+    archive = open_tar(path).map_err(|e| e.to_string())?;
+    let mut members = Vec::new();
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let Some(safe) = safe_member_path(&name) else {
+            members.push(Err(unsafe_path(&name)));
+            continue;
+        };
+        let rel = unwrapped(safe, wrapper.as_deref());
+        if !admit(&rel, entry.size()) {
+            continue;
+        }
+        let mtime = entry.header().mtime().unwrap_or(0);
+        let executable = entry.header().mode().is_ok_and(|mode| mode & 0o111 != 0);
+        let mut bytes = Vec::new();
+        members.push(match entry.read_to_end(&mut bytes) {
+            Ok(_) => Ok(ArchiveMember {
+                path: rel,
+                bytes,
+                mtime,
+                executable,
+            }),
+            Err(e) => Err(SkippedFile {
+                path: rel,
+                reason: e.to_string(),
+            }),
+        });
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn admit_all(_: &str, _: u64) -> bool {
+        true
+    }
+
+    fn paths(members: &[Result<ArchiveMember, SkippedFile>]) -> Vec<&str> {
+        members.iter().map(member_path).collect()
+    }
+
+    #[test]
+    fn test_safe_member_path() {
+        assert_eq!(safe_member_path("./src//a.rs").as_deref(), Some("src/a.rs"));
+        assert_eq!(safe_member_path("src\\a.rs").as_deref(), Some("src/a.rs"));
+        assert_eq!(safe_member_path("../a.rs"), None);
+        assert_eq!(safe_member_path("src/../../a.rs"), None);
+        assert_eq!(safe_member_path("/etc/passwd"), None);
+        assert_eq!(safe_member_path("C:/a.rs"), None);
+        assert_eq!(safe_member_path("./"), None);
+    }
+
+    #[test]
+    fn test_archive_stem() {
+        assert_eq!(
+            archive_stem(Path::new("out/repo-main.tar.gz")),
+            Some("repo-main")
+        );
+        assert_eq!(archive_stem(Path::new("repo.tgz")), Some("repo"));
+        assert_eq!(archive_stem(Path::new("repo.zip")), Some("repo"));
+        assert_eq!(archive_stem(Path::new("repo.rs")), None);
+        assert_eq!(archive_stem(Path::new(".zip")), None);
+    }
+
+    #[test]
+    fn test_read_zip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("repo.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        zip.add_directory("repo-main/src/", options).unwrap();
+        for (name, content) in [
+            ("repo-main/src/lib.rs", "fn a() {}"),
+            ("repo-main/README.md", "# repo\n"),
+            ("repo-main/big.txt", "0123456789"),
+            ("../escape.rs", "evil"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        // Unsafe members don't count against the wrapper directory
+        let members = read_archive(&path, |_, size| size < 10).unwrap();
+        assert_eq!(
+            paths(&members),
+            vec!["../escape.rs", "README.md", "src/lib.rs"]
+        );
+        assert!(members[0].is_err());
+        let lib = members[2].as_ref().unwrap();
+        assert_eq!(lib.bytes, b"fn a() {}");
+        assert!(lib.mtime > 0);
+    }
+
+    #[test]
+    fn test_read_tar_strips_wrapper_and_rejects_traversal() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut append = |name: &[u8], content: &[u8]| {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name);
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(1_700_000_000);
+            header.set_cksum();
+            builder.append(&header, content).unwrap();
+        };
+        append(b"repo-main/src/lib.rs", b"pub fn a() {}\n");
+        append(b"repo-main/../../escape.rs", b"evil");
+        append(b"repo-main/README.md", b"# repo\n");
+        let bytes = builder.into_inner().unwrap();
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("repo.tar.gz");
+        let mut gz = flate2::write::GzEncoder::new(
+            File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(&bytes).unwrap();
+        gz.finish().unwrap();
+
+        let members = read_archive(&path, admit_all).unwrap();
+        assert_eq!(
+            paths(&members),
+            vec!["README.md", "repo-main/../../escape.rs", "src/lib.rs"]
+        );
+        assert_eq!(
+            members[1].as_ref().unwrap_err().reason,
+            "path leaves the archive root"
+        );
+        assert_eq!(members[2].as_ref().unwrap().mtime, 1_700_000_000);
+        assert!(!temp.path().join("escape.rs").exists());
+    }
+
+    #[test]
+    fn test_read_archive_reports_corrupt_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("broken.zip");
+        std::fs::write(&path, "not a zip").unwrap();
+        assert!(read_archive(&path, admit_all).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! Binary file placeholders
+//!
+//! Binary files are left out by default, so an LLM reading about
+//! `logo.png` in the code finds no such file. With [`BinaryPolicy::Stub`]
+//! each one is kept as a one-line placeholder giving its size and media
+//! type; [`BinaryPolicy::Base64`] also inlines the bytes of small ones.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Largest binary whose bytes `base64` inlines; bigger ones get a stub
+pub const BASE64_MAX_BYTES: usize = 16 * 1024;
+
+/// Base64 line width, as in MIME
+const BASE64_LINE: usize = 76;
+
+/// Media types by lowercased extension
+const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("ico", "image/x-icon"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("jar", "application/java-archive"),
+    ("wasm", "application/wasm"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("sqlite", "application/vnd.sqlite3"),
+    ("db", "application/vnd.sqlite3"),
+];
+
+/// What the walk does with binary files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryPolicy {
+    /// Leave them out (recorded as excluded)
+    #[default]
+    Skip,
+    /// Keep a placeholder line with size and media type
+    Stub,
+    /// Like `Stub`, with the bytes of files up to [`BASE64_MAX_BYTES`]
+    /// inlined as base64
+    Base64,
+}
+
+impl BinaryPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Stub => "stub",
+            Self::Base64 => "base64",
+        }
+    }
+}
+
+/// The media type of `path` from its extension
+pub fn media_type(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    MEDIA_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map_or("application/octet-stream", |(_, media)| media)
+}
+
+/// `bytes` as a short size: `512B`, `3.4KB`, `1.2MB`
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", size, UNITS[unit])
+}
+
+/// The content `policy` gives the binary file at `path`; `None` to skip it
+pub fn binary_stub(path: &str, bytes: &[u8], policy: BinaryPolicy) -> Option<String> {
+    let header = format!(
+        "[binary: {}, {}",
+        human_size(bytes.len() as u64),
+        media_type(path)
+    );
+    match policy {
+        BinaryPolicy::Skip => None,
+        BinaryPolicy::Base64 if bytes.len() <= BASE64_MAX_BYTES => {
+            let encoded = base64(bytes);
+            let mut stub = format!("{}, base64]\n", header);
+            for line in encoded.as_bytes().chunks(BASE64_LINE) {
+                stub.push_str(std::str::from_utf8(line).unwrap_or_default());
+                stub.push('\n');
+            }
+            Some(stub)
+        }
+        BinaryPolicy::Stub | BinaryPolicy::Base64 => Some(format!("{}]\n", header)),
+    }
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stub_names_size_and_media_type() {
+        let png = vec![0u8; 1_300_000];
+        assert_eq!(
+            binary_stub("assets/logo.PNG", &png, BinaryPolicy::Stub).unwrap(),
+            "[binary: 1.2MB, image/png]\n"
+        );
+        assert_eq!(
+            binary_stub("data.bin", &[0, 1, 2], BinaryPolicy::Stub).unwrap(),
+            "[binary: 3B, application/octet-stream]\n"
+        );
+        assert!(binary_stub("data.bin", &[0], BinaryPolicy::Skip).is_none());
+        assert_eq!(human_size(3 * 1024 + 512), "3.5KB");
+    }
+
+    #[test]
+    fn test_base64_only_for_small_files() {
+        let small = binary_stub("a.bin", b"\0hi\xff", BinaryPolicy::Base64).unwrap();
+        assert_eq!(
+            small,
+            "[binary: 4B, application/octet-stream, base64]\nAGhp/w==\n"
+        );
+        let big = vec![0u8; BASE64_MAX_BYTES + 1];
+        let stub = binary_stub("a.bin", &big, BinaryPolicy::Base64).unwrap();
+        assert_eq!(stub.lines().count(), 1);
+
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,169 @@
+//! Exclusion ledger
+//!
+//! Records why the walk left each candidate out - a pattern, the size
+//! limit, binary content, an unreadable file - so `--show-excluded` can
+//! answer "why isn't this file in my context?". Pruned directories are
+//! recorded once, with a trailing `/`, rather than file by file.
+
+use std::fmt;
+use std::sync::Mutex;
+
+use super::generated::Generated;
+
+/// Why a path was left out of the context
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExclusionReason {
+    /// Built-in hygiene exclusion (`node_modules`, `target`, `.venv`, ...)
+    Hygiene,
+    /// Matched an ignore pattern
+    IgnorePattern(String),
+    /// Include patterns are set and none matched
+    NotIncluded,
+    /// Ignored by a nested `.pm_encoder_config.json`
+    NestedConfig,
+    /// Ignored by `.gitignore`, `.ignore` or a git exclude file
+    Gitignored,
+    /// Not tracked by git (`--git-only`)
+    Untracked,
+    /// Larger than the size limit
+    TooLarge { size: u64, limit: u64 },
+    /// Binary content
+    Binary,
+    /// Not in the `--files-from` list
+    NotListed,
+    /// Unchanged since the `--since` / `--changed-since` point
+    Unchanged,
+    /// Nested deeper below the root than `max_depth` allows
+    TooDeep { limit: usize },
+    /// Came after `max_total_files` files had been taken
+    FileLimit { limit: usize },
+    /// Would have taken the total past `max_total_bytes`
+    ByteLimit { limit: u64 },
+    /// A symbolic link the `--follow-symlinks` policy doesn't follow
+    Symlink(String),
+    /// Generated or vendored, with `--generated skip`
+    Generated(Generated),
+    /// The content matches this `exclude_content_patterns` regex
+    ContentPattern(String),
+    /// The file could not be read
+    Unreadable(String),
+}
+
+impl ExclusionReason {
+    /// Short machine-readable kind, for porcelain output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Hygiene => "hygiene",
+            Self::IgnorePattern(_) => "pattern",
+            Self::NotIncluded => "not-included",
+            Self::NestedConfig => "nested-config",
+            Self::Gitignored => "gitignore",
+            Self::Untracked => "untracked",
+            Self::TooLarge { .. } => "size",
+            Self::Binary => "binary",
+            Self::NotListed => "not-listed",
+            Self::Unchanged => "unchanged",
+            Self::TooDeep { .. } => "depth",
+            Self::FileLimit { .. } => "file-limit",
+            Self::ByteLimit { .. } => "byte-limit",
+            Self::Symlink(_) => "symlink",
+            Self::Generated(_) => "generated",
+            Self::ContentPattern(_) => "content",
+            Self::Unreadable(_) => "error",
+        }
+    }
+}
+
+impl fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hygiene => write!(f, "hygiene exclusion"),
+            Self::IgnorePattern(pattern) => write!(f, "ignore pattern '{}'", pattern),
+            Self::NotIncluded => write!(f, "no include pattern matches"),
+            Self::NestedConfig => write!(f, "ignored by a nested config"),
+            Self::Gitignored => write!(f, "gitignored"),
+            Self::Untracked => write!(f, "not tracked by git"),
+            Self::TooLarge { size, limit } => {
+                write!(f, "too large ({} bytes, limit {})", size, limit)
+            }
+            Self::Binary => write!(f, "binary content"),
+            Self::NotListed => write!(f, "not in the file list"),
+            Self::Unchanged => write!(f, "unchanged"),
+            Self::TooDeep { limit } => write!(f, "deeper than max depth {}", limit),
+            Self::FileLimit { limit } => write!(f, "past the {}-file limit", limit),
+            Self::ByteLimit { limit } => write!(f, "past the {}-byte limit", limit),
+            Self::Symlink(why) => write!(f, "symlink {}", why),
+            Self::Generated(generated) => write!(f, "{}", generated),
+            Self::ContentPattern(pattern) => write!(f, "content matches '{}'", pattern),
+            Self::Unreadable(reason) => write!(f, "unreadable: {}", reason),
+        }
+    }
+}
+
+/// A path the walk left out, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcludedFile {
+    /// Relative path; directories end with `/`
+    pub path: String,
+    pub reason: ExclusionReason,
+}
+
+/// Collects exclusions from the (possibly parallel) walk
+#[derive(Debug, Default)]
+pub struct ExclusionLedger {
+    excluded: Mutex<Vec<ExcludedFile>>,
+}
+
+impl ExclusionLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `path` was left out
+    pub fn record(&self, path: impl Into<String>, reason: ExclusionReason) {
+        self.excluded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(ExcludedFile {
+                path: path.into(),
+                reason,
+            });
+    }
+
+    /// The recorded exclusions, sorted by path
+    pub fn into_sorted(self) -> Vec<ExcludedFile> {
+        let mut excluded = self
+            .excluded
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        excluded.sort_by(|a, b| a.path.cmp(&b.path));
+        excluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_display_and_kind() {
+        let reason = ExclusionReason::IgnorePattern("*.log".to_string());
+        assert_eq!(reason.to_string(), "ignore pattern '*.log'");
+        assert_eq!(reason.kind(), "pattern");
+        let reason = ExclusionReason::TooLarge {
+            size: 2048,
+            limit: 1024,
+        };
+        assert_eq!(reason.to_string(), "too large (2048 bytes, limit 1024)");
+        assert_eq!(reason.kind(), "size");
+    }
+
+    #[test]
+    fn test_ledger_sorts_by_path() {
+        let ledger = ExclusionLedger::new();
+        ledger.record("b.bin", ExclusionReason::Binary);
+        ledger.record("a/", ExclusionReason::Hygiene);
+        let paths: Vec<String> = ledger.into_sorted().into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec!["a/", "b.bin"]);
+    }
+}
@@ -0,0 +1,98 @@
+//! Explicit file lists
+//!
+//! `--files-from <FILE|->` restricts a run to the listed files, one per
+//! line, e.g. `git diff --name-only | vo . --files-from -`. Like rsync's
+//! `--files-from`, relative paths are taken relative to the project root;
+//! absolute paths must point inside it. Listed files still go through the
+//! usual ignore, size and binary checks.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Parse a newline-separated list, skipping blank lines
+pub fn parse_file_list(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Turn `paths` into project-relative paths as the walk reports them
+///
+/// Errors on paths that lead outside `root`.
+pub fn relative_paths(root: &Path, paths: &[PathBuf]) -> Result<Vec<String>, String> {
+    let mut canonical_root = None;
+    paths
+        .iter()
+        .map(|path| {
+            let outside = || format!("{} is outside {}", path.display(), root.display());
+            let relative = if path.is_absolute() {
+                let root = match &canonical_root {
+                    Some(root) => root,
+                    None => canonical_root.insert(
+                        root.canonicalize()
+                            .map_err(|e| format!("{}: {}", root.display(), e))?,
+                    ),
+                };
+                // A listed file may be gone (deleted since the diff), so only
+                // canonicalize it when the plain prefix doesn't match
+                match path.strip_prefix(root) {
+                    Ok(rel) => rel.to_path_buf(),
+                    Err(_) => path
+                        .canonicalize()
+                        .ok()
+                        .and_then(|p| p.strip_prefix(root).ok().map(Path::to_path_buf))
+                        .ok_or_else(outside)?,
+                }
+            } else {
+                path.clone()
+            };
+
+            let mut normalized = PathBuf::new();
+            for component in relative.components() {
+                match component {
+                    Component::Normal(part) => normalized.push(part),
+                    Component::CurDir => {}
+                    Component::ParentDir => {
+                        if !normalized.pop() {
+                            return Err(outside());
+                        }
+                    }
+                    Component::RootDir | Component::Prefix(_) => return Err(outside()),
+                }
+            }
+            Ok(normalized.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_list() {
+        let paths = parse_file_list("src/a.rs\r\n\n  \n./b.rs\n");
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("src/a.rs"), PathBuf::from("./b.rs")]
+        );
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::write(root.join("a.rs"), "").unwrap();
+
+        let listed = vec![
+            PathBuf::from("./src/../b.rs"),
+            root.canonicalize().unwrap().join("a.rs"),
+        ];
+        assert_eq!(relative_paths(root, &listed).unwrap(), vec!["b.rs", "a.rs"]);
+
+        let err = relative_paths(root, &[PathBuf::from("../elsewhere.rs")]).unwrap_err();
+        assert!(err.contains("is outside"));
+        assert!(relative_paths(root, &[PathBuf::from("/etc/passwd")]).is_err());
+    }
+}
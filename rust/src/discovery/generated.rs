@@ -0,0 +1,206 @@
+//! Generated and vendored file detection
+//!
+//! A linguist-style heuristic: vendored directories and well-known
+//! generated file names are recognised by path, everything else by a
+//! generator's marker near the top of the file. [`GeneratedPolicy`] decides
+//! what the run does with them.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Directories holding third-party code copied into the project
+const VENDORED_DIRS: &[&str] = &[
+    "vendor",
+    "vendors",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "bower_components",
+    "Godeps",
+];
+
+/// File name endings of common code generators' output
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".g.dart",
+    ".freezed.dart",
+    ".Designer.cs",
+    ".designer.cs",
+    ".g.cs",
+    ".generated.cs",
+    "_generated.go",
+    ".min.js",
+    ".min.css",
+    ".js.map",
+    ".css.map",
+];
+
+/// Files that package managers write
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "Pipfile.lock",
+    "composer.lock",
+    "Gemfile.lock",
+    "go.sum",
+    "flake.lock",
+];
+
+/// Generator markers, matched case-insensitively
+const MARKERS: &[&str] = &[
+    "@generated",
+    "do not edit",
+    "code generated by",
+    "automatically generated",
+    "autogenerated",
+    "auto-generated",
+];
+
+/// Lines searched for a marker; generators put theirs in the header
+const MARKER_LINES: usize = 5;
+
+/// Whether a file was generated or copied in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratedKind {
+    /// Written by a tool (code generator, bundler, package manager)
+    Generated,
+    /// Third-party code kept in the tree
+    Vendored,
+}
+
+impl GeneratedKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Generated => "generated",
+            Self::Vendored => "vendored",
+        }
+    }
+}
+
+/// A file classified as generated or vendored, and what gave it away
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    pub kind: GeneratedKind,
+    pub reason: String,
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.kind.name(), self.reason)
+    }
+}
+
+/// Classify the file at relative `path` with `content`
+pub fn detect_generated(path: &str, content: &str) -> Option<Generated> {
+    let path = Path::new(path);
+    let mut dirs = path.parent().into_iter().flat_map(|p| p.components());
+    if let Some(dir) = dirs.find_map(|c| {
+        let name = c.as_os_str().to_str()?;
+        VENDORED_DIRS.contains(&name).then_some(name)
+    }) {
+        return Some(Generated {
+            kind: GeneratedKind::Vendored,
+            reason: format!("in {}/", dir),
+        });
+    }
+
+    let name = path.file_name()?.to_str()?;
+    if LOCKFILES.contains(&name) {
+        return Some(Generated {
+            kind: GeneratedKind::Generated,
+            reason: "lockfile".to_string(),
+        });
+    }
+    if let Some(suffix) = GENERATED_SUFFIXES.iter().find(|s| name.ends_with(*s)) {
+        return Some(Generated {
+            kind: GeneratedKind::Generated,
+            reason: format!("*{} file", suffix),
+        });
+    }
+
+    content.lines().take(MARKER_LINES).find_map(|line| {
+        let line = line.to_lowercase();
+        let marker = MARKERS.iter().find(|m| line.contains(*m))?;
+        Some(Generated {
+            kind: GeneratedKind::Generated,
+            reason: format!("'{}' marker", marker),
+        })
+    })
+}
+
+/// What the run does with generated and vendored files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneratedPolicy {
+    /// Treat them like any other file
+    #[default]
+    Keep,
+    /// Reduce them to their structure (signatures only)
+    Structure,
+    /// Leave them out
+    Skip,
+}
+
+impl GeneratedPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Keep => "keep",
+            Self::Structure => "structure",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_path() {
+        let vendored = detect_generated("third_party/zlib/inflate.c", "int x;\n").unwrap();
+        assert_eq!(vendored.kind, GeneratedKind::Vendored);
+        assert_eq!(vendored.to_string(), "vendored (in third_party/)");
+
+        let proto = detect_generated("api/user.pb.go", "package api\n").unwrap();
+        assert_eq!(proto.kind, GeneratedKind::Generated);
+        assert_eq!(proto.reason, "*.pb.go file");
+
+        assert_eq!(
+            detect_generated("Cargo.lock", "").unwrap().reason,
+            "lockfile"
+        );
+        // A file merely named after a vendored directory is not vendored
+        assert!(detect_generated("src/vendor.rs", "fn f() {}\n").is_none());
+    }
+
+    #[test]
+    fn test_detect_by_marker() {
+        let go = "// Code generated by stringer. DO NOT EDIT.\n\npackage color\n";
+        let generated = detect_generated("color_string.go", go).unwrap();
+        assert_eq!(generated.reason, "'do not edit' marker");
+
+        let hack = "<?hh\n/**\n * @generated SignedSource<<abc>>\n */\n";
+        assert!(detect_generated("schema.php", hack).is_some());
+
+        // Markers further down are prose, not a generator header
+        let prose = "fn a() {}\n\n\n\n\n\n// do not edit by hand\n";
+        assert!(detect_generated("src/lib.rs", prose).is_none());
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let policy: GeneratedPolicy = serde_json::from_str(r#""structure""#).unwrap();
+        assert_eq!(policy, GeneratedPolicy::Structure);
+        assert_eq!(policy.name(), "structure");
+        assert!(serde_json::from_str::<GeneratedPolicy>(r#""drop""#).is_err());
+    }
+}
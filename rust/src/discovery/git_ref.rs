@@ -0,0 +1,166 @@
+//! Serializing a git revision without checking it out
+//!
+//! `--git-ref <REF>` reads the project as it was at a branch, tag or commit
+//! straight from the object database: `git archive` writes the tree into a
+//! temporary tar file, which is then walked like any other archive root.
+//! The working tree, index and stash are never touched, so uncommitted
+//! changes can stay where they are.
+
+use super::scratch::ScratchDir;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A directory's contents at a git revision, archived to a temporary file
+///
+/// The file is removed on drop.
+#[derive(Debug)]
+pub struct GitRefSnapshot {
+    /// Temporary directory holding the archive
+    temp: ScratchDir,
+    /// `<name>.tar` inside `temp`
+    archive: PathBuf,
+}
+
+impl GitRefSnapshot {
+    /// Archive the directory `root` (inside a git repository) as of `rev`
+    ///
+    /// Only the part of the tree under `root` is taken, and the archive is
+    /// named after `root` so it keeps the directory's name as a root prefix.
+    pub fn take(root: &Path, rev: &str) -> Result<Self, String> {
+        if rev.is_empty() || rev.starts_with('-') {
+            return Err(format!("'{}' is not a git revision", rev));
+        }
+        if !root.is_dir() {
+            return Err(format!(
+                "'{}' is not a directory (--git-ref needs a directory in a git repository)",
+                root.display()
+            ));
+        }
+        let git = || {
+            let mut command = Command::new("git");
+            command.arg("-C").arg(root);
+            command
+        };
+        let verified = git()
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(format!("{}^{{tree}}", rev))
+            .output()
+            .map_err(|e| format!("Running git failed: {}", e))?;
+        if !verified.status.success() {
+            return Err(format!(
+                "'{}' is not a revision of a git repository at '{}'",
+                rev,
+                root.display()
+            ));
+        }
+
+        // From here on, failures clean up through Drop
+        let temp = ScratchDir::new("vo-git-ref-")?;
+        let name = root_name(root);
+        let snapshot = Self {
+            archive: temp.path().join(format!("{}.tar", name)),
+            temp,
+        };
+        // Run from `root`, git archive takes just that subtree. The prefix
+        // wraps every member in one directory, which the archive walk drops
+        let output = git()
+            .args(["archive", "--format=tar"])
+            .arg(format!("--prefix={}/", name))
+            .arg("-o")
+            .arg(&snapshot.archive)
+            .args([rev, "--"])
+            .output()
+            .map_err(|e| format!("Running git failed: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Reading {} at {} failed: {}",
+                root.display(),
+                rev,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// The archive to serialize in place of the directory
+    pub fn path(&self) -> &Path {
+        &self.archive
+    }
+}
+
+/// The directory's own name, resolving `.` and the like
+fn root_name(root: &Path) -> String {
+    root.canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "project".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::archive::read_archive;
+    use std::fs;
+
+    #[test]
+    fn test_snapshot_at_tag() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repo = temp.path().join("widget");
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("src/lib.rs"), "fn v1() {}\n").unwrap();
+        fs::write(repo.join("README.md"), "# widget\n").unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .status()
+                .is_ok_and(|s| s.success())
+        };
+        if !git(&["init", "-q"]) {
+            // git not installed
+            return;
+        }
+        assert!(git(&["add", "."]));
+        assert!(git(&["commit", "-q", "-m", "v1"]));
+        assert!(git(&["tag", "v1.4.0"]));
+        fs::write(repo.join("src/lib.rs"), "fn v2() {}\n").unwrap();
+        assert!(git(&["commit", "-q", "-am", "v2"]));
+        fs::write(repo.join("src/lib.rs"), "fn uncommitted() {}\n").unwrap();
+
+        let snapshot = GitRefSnapshot::take(&repo, "v1.4.0").unwrap();
+        assert!(snapshot.path().ends_with("widget.tar"));
+        let members: Vec<_> = read_archive(snapshot.path(), |_, _| true)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let paths: Vec<_> = members.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["README.md", "src/lib.rs"]);
+        assert_eq!(members[1].bytes, b"fn v1() {}\n");
+        assert!(members[1].mtime > 0);
+
+        // Only the subtree under a subdirectory root
+        let sub = GitRefSnapshot::take(&repo.join("src"), "HEAD").unwrap();
+        assert!(sub.path().ends_with("src.tar"));
+        let members = read_archive(sub.path(), |_, _| true).unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].as_ref().unwrap().bytes, b"fn v2() {}\n");
+
+        // The working tree is left alone
+        assert_eq!(
+            fs::read_to_string(repo.join("src/lib.rs")).unwrap(),
+            "fn uncommitted() {}\n"
+        );
+
+        let err = GitRefSnapshot::take(&repo, "v9").unwrap_err();
+        assert!(err.contains("'v9' is not a revision"), "{}", err);
+        assert!(GitRefSnapshot::take(&repo, "--output=x").is_err());
+
+        let temp_dir = snapshot.temp.path().to_path_buf();
+        drop(snapshot);
+        assert!(!temp_dir.exists());
+    }
+}
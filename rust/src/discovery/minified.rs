@@ -0,0 +1,102 @@
+//! Minified asset detection
+//!
+//! A bundler's output packs a whole program into a handful of lines, so a
+//! single `bundle.js` can take most of a context budget while carrying
+//! nothing a reader can follow. JavaScript and CSS files whose lines
+//! average more than [`MIN_AVERAGE_LINE_LEN`] bytes are treated as
+//! minified, whatever their name; [`minified_stub`] stands in for them.
+
+use std::fmt;
+use std::path::Path;
+
+use super::binary::human_size;
+
+/// Extensions of the assets bundlers minify
+const MINIFIED_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "css"];
+
+/// Average line length (in bytes) above which a file is minified;
+/// hand-written code rarely averages a third of this
+pub const MIN_AVERAGE_LINE_LEN: usize = 300;
+
+/// Files smaller than this are left alone, minified or not
+const MIN_SIZE: usize = 2048;
+
+/// A file recognised as minified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minified {
+    pub bytes: usize,
+    /// Non-blank lines
+    pub lines: usize,
+    pub average_line_len: usize,
+}
+
+impl fmt::Display for Minified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} lines, avg {} chars/line",
+            human_size(self.bytes as u64),
+            self.lines,
+            self.average_line_len
+        )
+    }
+}
+
+/// Whether `path` has an extension minified assets have
+pub fn is_candidate(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MINIFIED_EXTENSIONS.contains(&ext))
+}
+
+/// Classify the file at `path` with `content` by its average line length
+pub fn detect_minified(path: &str, content: &str) -> Option<Minified> {
+    if !is_candidate(path) || content.len() < MIN_SIZE {
+        return None;
+    }
+    let (lines, bytes) = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .fold((0, 0), |(lines, bytes), line| {
+            (lines + 1, bytes + line.len())
+        });
+    let average_line_len = bytes / lines.max(1);
+    (average_line_len > MIN_AVERAGE_LINE_LEN).then_some(Minified {
+        bytes: content.len(),
+        lines,
+        average_line_len,
+    })
+}
+
+/// The placeholder a minified file is emitted as when its structure
+/// doesn't read any better: `[minified: 812.3KB in 3 lines, avg 277265 chars/line]`
+pub fn minified_stub(minified: &Minified) -> String {
+    format!("[minified: {}]\n", minified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_minified() {
+        let bundle = format!(
+            "/*! lib v1 */\n{}\n",
+            "var a=1;function b(){return a}".repeat(200)
+        );
+        let minified = detect_minified("dist/bundle.js", &bundle).unwrap();
+        assert_eq!(minified.lines, 2);
+        assert_eq!(minified.average_line_len, 3006);
+        assert_eq!(
+            minified_stub(&minified),
+            "[minified: 5.9KB in 2 lines, avg 3006 chars/line]\n"
+        );
+
+        // Only scripts and stylesheets, and only long lines
+        assert!(detect_minified("data/bundle.json", &bundle).is_none());
+        let readable = "function b() {\n    return a;\n}\n".repeat(200);
+        assert!(detect_minified("src/app.js", &readable).is_none());
+        assert!(detect_minified("a.css", "a{color:red}").is_none());
+    }
+}
@@ -0,0 +1,39 @@
+//! Discovery filters and inputs
+//!
+//! Narrow the set of files the walk yields, for both batch and streaming
+//! output. Each filter is resolved once up front and then checked per entry.
+//! [`remote`] fetches a repository to walk in the first place,
+//! [`archive`] walks a `.zip` or `.tar` file in place of a directory, and
+//! [`git_ref`] archives a directory as of a git revision; both land in a
+//! [`scratch`] directory.
+//! [`exclusions`] keeps track of what the walk left out, [`generated`]
+//! recognises generated and vendored files, [`minified`] bundled assets,
+//! [`binary`] stands in for binary ones, and [`content`] drops files by
+//! what they contain.
+//! [`pattern_syntax`] holds the gitignore-style `v2` pattern matcher, shared
+//! with voyager-ast through `project_walk`.
+
+pub mod archive;
+pub mod binary;
+pub mod content;
+pub mod exclusions;
+pub mod file_list;
+pub mod generated;
+pub mod git_ref;
+pub mod minified;
+pub mod remote;
+pub mod scratch;
+pub mod since;
+
+pub use binary::{binary_stub, BinaryPolicy};
+pub use content::ContentFilter;
+pub use exclusions::{ExcludedFile, ExclusionLedger, ExclusionReason};
+pub use file_list::{parse_file_list, relative_paths};
+pub use generated::{detect_generated, Generated, GeneratedKind, GeneratedPolicy};
+pub use git_ref::GitRefSnapshot;
+pub use minified::{detect_minified, Minified};
+pub use project_walk::pattern_syntax;
+pub use project_walk::{PatternStyle, PatternSyntax};
+pub use remote::{RemoteCheckout, RemoteSpec};
+pub use scratch::{remove_scratch_dirs, ScratchDir};
+pub use since::{Since, SinceFilter};
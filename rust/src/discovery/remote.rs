@@ -0,0 +1,294 @@
+//! Remote repository input
+//!
+//! `--remote <URL[#REV]>` serializes a repository without cloning it by
+//! hand. Git URLs are shallow-cloned (`git clone --depth 1`, or a depth-1
+//! fetch of `REV`); `.tar.gz`, `.tgz` and `.tar` URLs are downloaded with
+//! `curl` and unpacked. Either way the files land in a [`ScratchDir`] that
+//! is removed with the [`RemoteCheckout`], and the run then proceeds as for
+//! a local project, config file and lens included.
+
+use super::scratch::ScratchDir;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A remote repository and the revision to take
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSpec {
+    pub url: String,
+    /// Branch, tag or commit (`None` = the default branch)
+    pub rev: Option<String>,
+}
+
+impl RemoteSpec {
+    /// Parse `URL[#REV]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (url, rev) = match spec.rsplit_once('#') {
+            Some((url, rev)) => (url, Some(rev)),
+            None => (spec, None),
+        };
+        if url.is_empty() {
+            return Err(format!("'{}' has no URL", spec));
+        }
+        if rev == Some("") {
+            return Err(format!("'{}' has an empty revision after '#'", spec));
+        }
+        let spec = Self {
+            url: url.to_string(),
+            rev: rev.map(str::to_string),
+        };
+        if spec.is_tarball() && spec.rev.is_some() {
+            return Err(format!(
+                "'{}': a revision can't be selected from a tarball",
+                url
+            ));
+        }
+        Ok(spec)
+    }
+
+    /// Whether the URL points at a tarball rather than a git repository
+    pub fn is_tarball(&self) -> bool {
+        let path = self.url.split(['?', '#']).next().unwrap_or(&self.url);
+        [".tar.gz", ".tgz", ".tar"]
+            .iter()
+            .any(|ext| path.ends_with(ext))
+    }
+
+    /// Directory name for the checkout: the repository or archive name
+    pub fn name(&self) -> String {
+        let path = self.url.split(['?', '#']).next().unwrap_or(&self.url);
+        let last = path
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or("");
+        let name = [".tar.gz", ".tgz", ".tar", ".git"]
+            .iter()
+            .find_map(|ext| last.strip_suffix(ext))
+            .unwrap_or(last);
+        if name.is_empty() || name == "." || name == ".." {
+            "remote".to_string()
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// A remote repository fetched into a temporary directory
+///
+/// The directory is removed on drop.
+#[derive(Debug)]
+pub struct RemoteCheckout {
+    /// Temporary directory holding the checkout
+    temp: ScratchDir,
+    /// Project root inside `temp`
+    root: PathBuf,
+}
+
+impl RemoteCheckout {
+    /// Clone or download `spec`
+    pub fn fetch(spec: &RemoteSpec) -> Result<Self, String> {
+        // From here on, failures clean up through Drop
+        let temp = ScratchDir::new("vo-remote-")?;
+        let mut checkout = Self {
+            root: temp.path().join(spec.name()),
+            temp,
+        };
+        if spec.is_tarball() {
+            checkout.root = download_tarball(&spec.url, &checkout.root)?;
+        } else {
+            clone(spec, &checkout.root)?;
+        }
+        Ok(checkout)
+    }
+
+    /// The project root to serialize
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+}
+
+fn run(command: &mut Command, what: &str) -> Result<Vec<u8>, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("{} failed: {}", what, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Shallow clone of the default branch, or a depth-1 fetch of `rev`
+/// (which also works for commit ids, unlike `clone --branch`)
+fn clone(spec: &RemoteSpec, dest: &Path) -> Result<(), String> {
+    let what = format!("Cloning {}", spec.url);
+    match &spec.rev {
+        None => run(
+            Command::new("git")
+                .args(["clone", "--quiet", "--depth", "1", "--", &spec.url])
+                .arg(dest),
+            &what,
+        )?,
+        Some(rev) => {
+            fs::create_dir_all(dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+            let git = |args: &[&str]| {
+                let mut command = Command::new("git");
+                command.arg("-C").arg(dest).args(args);
+                command
+            };
+            run(&mut git(&["init", "--quiet"]), &what)?;
+            run(
+                &mut git(&["fetch", "--quiet", "--depth", "1", "--", &spec.url, rev]),
+                &format!("Fetching {} at {}", spec.url, rev),
+            )?;
+            run(
+                &mut git(&["checkout", "--quiet", "--detach", "FETCH_HEAD"]),
+                &what,
+            )?
+        }
+    };
+    Ok(())
+}
+
+/// Download and unpack a tarball into `dest`, returning the project root
+///
+/// Archives from code hosts wrap everything in one top-level directory
+/// (`repo-main/`); that directory becomes the root.
+fn download_tarball(url: &str, dest: &Path) -> Result<PathBuf, String> {
+    let bytes = run(
+        Command::new("curl").args([
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--location",
+            "--",
+            url,
+        ]),
+        &format!("Downloading {}", url),
+    )?;
+    unpack_tarball(&bytes, dest).map_err(|e| format!("Unpacking {}: {}", url, e))?;
+
+    let mut children = fs::read_dir(dest)
+        .map_err(|e| format!("{}: {}", dest.display(), e))?
+        .filter_map(Result::ok)
+        .map(|e| e.path());
+    match (children.next(), children.next()) {
+        (Some(only), None) if only.is_dir() => Ok(only),
+        _ => Ok(dest.to_path_buf()),
+    }
+}
+
+/// Unpack a plain or gzipped tar archive
+///
+/// Entries that would land outside `dest` (absolute paths, `..`) are
+/// skipped by [`tar::Entry::unpack_in`].
+fn unpack_tarball(bytes: &[u8], dest: &Path) -> std::io::Result<()> {
+    let reader: Box<dyn Read> = if bytes.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::read::GzDecoder::new(bytes))
+    } else {
+        Box::new(bytes)
+    };
+    fs::create_dir_all(dest)?;
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        entry?.unpack_in(dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = RemoteSpec::parse("https://github.com/a/b.git#v1.2").unwrap();
+        assert_eq!(spec.url, "https://github.com/a/b.git");
+        assert_eq!(spec.rev.as_deref(), Some("v1.2"));
+        assert_eq!(spec.name(), "b");
+        assert!(!spec.is_tarball());
+
+        let spec = RemoteSpec::parse("git@github.com:a/widget").unwrap();
+        assert_eq!(spec.rev, None);
+        assert_eq!(spec.name(), "widget");
+
+        let spec = RemoteSpec::parse("https://example.com/b-main.tar.gz").unwrap();
+        assert!(spec.is_tarball());
+        assert_eq!(spec.name(), "b-main");
+
+        assert!(RemoteSpec::parse("https://example.com/b.tgz#main").is_err());
+        assert!(RemoteSpec::parse("https://github.com/a/b#").is_err());
+        assert!(RemoteSpec::parse("#main").is_err());
+    }
+
+    #[test]
+    fn test_unpack_tarball() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "repo-main/a.rs", &b"a()"[..])
+            .unwrap();
+        let bytes = builder.into_inner().unwrap();
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let dest = temp.path().join("out");
+        unpack_tarball(&bytes, &dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("repo-main/a.rs")).unwrap(),
+            "a()"
+        );
+    }
+
+    #[test]
+    fn test_fetch_local_git_repository() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repo = temp.path().join("widget");
+        fs::create_dir_all(&repo).unwrap();
+        fs::write(repo.join("lib.rs"), "fn v1() {}\n").unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .status()
+                .is_ok_and(|s| s.success())
+        };
+        if !git(&["init", "-q"]) {
+            // git not installed
+            return;
+        }
+        assert!(git(&["add", "."]));
+        assert!(git(&["commit", "-q", "-m", "v1"]));
+        assert!(git(&["tag", "v1"]));
+        fs::write(repo.join("lib.rs"), "fn v2() {}\n").unwrap();
+        assert!(git(&["commit", "-q", "-am", "v2"]));
+
+        let url = format!("file://{}", repo.display());
+        let latest = RemoteCheckout::fetch(&RemoteSpec::parse(&url).unwrap()).unwrap();
+        assert!(latest.path().ends_with("widget"));
+        assert_eq!(
+            fs::read_to_string(latest.path().join("lib.rs")).unwrap(),
+            "fn v2() {}\n"
+        );
+
+        let tagged =
+            RemoteCheckout::fetch(&RemoteSpec::parse(&format!("{}#v1", url)).unwrap()).unwrap();
+        assert_eq!(
+            fs::read_to_string(tagged.path().join("lib.rs")).unwrap(),
+            "fn v1() {}\n"
+        );
+
+        let temp_dir = latest.temp.path().to_path_buf();
+        drop(latest);
+        assert!(!temp_dir.exists());
+    }
+}
@@ -0,0 +1,76 @@
+//! Temporary directories for fetched and archived inputs
+//!
+//! A [`ScratchDir`] is a [`tempfile::TempDir`]: a fresh directory with an
+//! unpredictable name, created with owner-only permissions. It is removed on
+//! drop, but `std::process::exit` skips destructors, so every live scratch
+//! directory is also recorded here and [`remove_scratch_dirs`] clears them
+//! before a process exits early.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static LIVE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// A temporary directory, removed on drop or by [`remove_scratch_dirs`]
+#[derive(Debug)]
+pub struct ScratchDir {
+    dir: tempfile::TempDir,
+}
+
+impl ScratchDir {
+    /// Create a directory named `<prefix><random>` under the system temp dir
+    pub fn new(prefix: &str) -> Result<Self, String> {
+        let dir = tempfile::Builder::new()
+            .prefix(prefix)
+            .tempdir()
+            .map_err(|e| format!("Creating a temporary directory failed: {}", e))?;
+        live().push(dir.path().to_path_buf());
+        Ok(Self { dir })
+    }
+
+    /// The directory's path
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        live().retain(|p| p != self.dir.path());
+    }
+}
+
+/// Remove every scratch directory still alive, for use before
+/// `std::process::exit`
+pub fn remove_scratch_dirs() {
+    for path in live().drain(..) {
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
+
+fn live() -> std::sync::MutexGuard<'static, Vec<PathBuf>> {
+    LIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_dir_removed_on_drop() {
+        let scratch = ScratchDir::new("vo-test-").unwrap();
+        let path = scratch.path().to_path_buf();
+        assert!(path.is_dir());
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("vo-test-"));
+        assert!(live().contains(&path));
+
+        std::fs::write(path.join("a.txt"), "a").unwrap();
+        drop(scratch);
+        assert!(!path.exists());
+        assert!(!live().contains(&path));
+    }
+}
@@ -0,0 +1,169 @@
+//! Changed-since filter
+//!
+//! `--changed-since` keeps only files changed since a git revision, or
+//! modified within a recent time window:
+//!
+//! - `HEAD~3`, `main`, `v1.2.0`: files that differ between the revision and
+//!   the working tree (committed, staged or unstaged), plus untracked files
+//! - `30m`, `2h`, `7d`, `2w`: files whose mtime falls inside the window
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use crate::FileEntry;
+
+/// What "changed" is measured against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Since {
+    /// A git revision
+    Revision(String),
+    /// A time window ending now
+    Window(Duration),
+}
+
+impl Since {
+    /// Parse a duration (`90s`, `30m`, `2h`, `7d`, `2w`), else a revision
+    pub fn parse(spec: &str) -> Self {
+        parse_window(spec).map_or_else(|| Self::Revision(spec.to_string()), Self::Window)
+    }
+}
+
+/// `<number><unit>` with unit s, m, h, d or w
+fn parse_window(spec: &str) -> Option<Duration> {
+    let unit = spec.chars().last()?;
+    let amount: u64 = spec[..spec.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount.checked_mul(seconds)?))
+}
+
+/// A resolved [`Since`], ready to test entries against
+#[derive(Debug, Clone)]
+pub enum SinceFilter {
+    /// Paths (relative to the project root) changed since the revision
+    Paths(HashSet<String>),
+    /// Oldest mtime (seconds since epoch) still inside the window
+    ModifiedAfter(u64),
+}
+
+impl SinceFilter {
+    /// Resolve `since` for the project at `root`
+    ///
+    /// A revision needs `root` to be inside a git work tree.
+    pub fn resolve(since: &Since, root: &Path) -> Result<Self, String> {
+        match since {
+            Since::Revision(rev) => changed_paths(rev, root).map(Self::Paths),
+            Since::Window(window) => {
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                Ok(Self::ModifiedAfter(now.saturating_sub(window.as_secs())))
+            }
+        }
+    }
+
+    /// Resolve `since` for several roots whose paths are reported under
+    /// the given prefixes (see [`crate::EncoderConfig::prefix_roots`])
+    pub fn resolve_prefixed(since: &Since, roots: &[(&Path, String)]) -> Result<Self, String> {
+        match since {
+            Since::Revision(rev) => {
+                let mut paths = HashSet::new();
+                for (root, prefix) in roots {
+                    let changed = changed_paths(rev, root)?;
+                    paths.extend(changed.into_iter().map(|p| format!("{}/{}", prefix, p)));
+                }
+                Ok(Self::Paths(paths))
+            }
+            Since::Window(_) => Self::resolve(since, Path::new(".")),
+        }
+    }
+
+    /// Whether `entry` changed
+    pub fn admits(&self, entry: &FileEntry) -> bool {
+        match self {
+            Self::Paths(paths) => paths.contains(&entry.path.replace('\\', "/")),
+            Self::ModifiedAfter(cutoff) => entry.mtime >= *cutoff,
+        }
+    }
+}
+
+/// Files below `root` that differ from `rev`, plus untracked files
+fn changed_paths(rev: &str, root: &Path) -> Result<HashSet<String>, String> {
+    let run = |args: &[&str]| -> Result<Vec<u8>, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "--changed-since {}: {}",
+                rev,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    };
+
+    // `--` keeps a revision that looks like a path from being read as one
+    let diffed = run(&["diff", "--name-only", "-z", "--relative", rev, "--"])?;
+    let untracked = run(&["ls-files", "-z", "--others", "--exclude-standard"])?;
+
+    Ok(diffed
+        .split(|&b| b == 0)
+        .chain(untracked.split(|&b| b == 0))
+        .filter(|p| !p.is_empty())
+        .map(|p| String::from_utf8_lossy(p).into_owned())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, mtime: u64) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            content: String::new(),
+            md5: String::new(),
+            mtime,
+            ctime: mtime,
+            size: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(Since::parse("2h"), Since::Window(Duration::from_secs(7200)));
+        assert_eq!(
+            Since::parse("7d"),
+            Since::Window(Duration::from_secs(7 * 86400))
+        );
+        assert_eq!(Since::parse("HEAD~3"), Since::Revision("HEAD~3".into()));
+        // Not a duration: a branch that happens to end in a unit letter
+        assert_eq!(Since::parse("fixes"), Since::Revision("fixes".into()));
+        assert_eq!(Since::parse("d"), Since::Revision("d".into()));
+    }
+
+    #[test]
+    fn test_filter_admits() {
+        let window = SinceFilter::ModifiedAfter(1_000);
+        assert!(window.admits(&entry("a.rs", 1_000)));
+        assert!(!window.admits(&entry("b.rs", 999)));
+
+        let paths = SinceFilter::Paths(["src/a.rs".to_string()].into_iter().collect());
+        assert!(paths.admits(&entry("src/a.rs", 0)));
+        assert!(!paths.admits(&entry("src/b.rs", 0)));
+    }
+}
@@ -0,0 +1,352 @@
+//! Self-test: `vo --doctor`
+//!
+//! Checks what a run depends on besides the source tree: config files,
+//! the learning store, journal and zoom sessions under `.pm_encoder/`, the
+//! Chronos cache, the voyager-ast grammars and write access to the state
+//! directories. Every problem comes with a fix the user can apply.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::presenter::{Palette, Style};
+use crate::directory_config::{DirectoryConfigs, CONFIG_FILE_NAME};
+use crate::migrate::{self, StateKind};
+use crate::EncoderConfig;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but something is degraded
+    Warn,
+    /// Runs will fail or silently lose data
+    Fail,
+}
+
+/// One line of the doctor report
+#[derive(Debug, Clone)]
+pub struct Check {
+    /// Short name of what was checked
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// What was found
+    pub detail: String,
+    /// What to do about it (for warnings and failures)
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// All checks for a project
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// Number of failed checks
+    pub fn failures(&self) -> usize {
+        self.count(CheckStatus::Fail)
+    }
+
+    /// Number of checks with warnings
+    pub fn warnings(&self) -> usize {
+        self.count(CheckStatus::Warn)
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    /// Human-readable report, one line per check plus fixes
+    pub fn render(&self, palette: Palette) -> String {
+        let mut out = format!(
+            "{}\n",
+            palette.paint(Style::Heading, "🩺 OBSERVATORY DOCTOR")
+        );
+        for check in &self.checks {
+            let (mark, style) = match check.status {
+                CheckStatus::Ok => ("ok  ", Style::Good),
+                CheckStatus::Warn => ("warn", Style::Warn),
+                CheckStatus::Fail => ("FAIL", Style::Bad),
+            };
+            out.push_str(&format!(
+                "  {} {:<10} {}\n",
+                palette.paint(style, mark),
+                check.name,
+                check.detail
+            ));
+            if let Some(fix) = &check.fix {
+                out.push_str(&format!(
+                    "       {} {}\n",
+                    palette.paint(Style::Dim, "fix:"),
+                    fix
+                ));
+            }
+        }
+        out.push_str(&format!(
+            "{} checks, {} warnings, {} failures\n",
+            self.checks.len(),
+            self.warnings(),
+            self.failures()
+        ));
+        out
+    }
+}
+
+/// Run every check for the project at `root`
+///
+/// `config_path` is the `--config` file, if one was given; otherwise the
+/// root's own `.pm_encoder_config.json` is checked when present.
+pub fn diagnose(root: &Path, config_path: Option<&Path>) -> DoctorReport {
+    let mut checks = vec![check_root_config(root, config_path)];
+    checks.push(check_nested_configs(root));
+    for kind in StateKind::ALL {
+        checks.push(check_state(root, kind));
+    }
+    #[cfg(feature = "temporal")]
+    checks.push(check_chronos_cache(root));
+    checks.push(check_grammars());
+    for dir in [root.join(".pm_encoder"), root.join(".voyager")] {
+        checks.push(check_writable(&dir));
+    }
+    DoctorReport { checks }
+}
+
+fn check_root_config(root: &Path, config_path: Option<&Path>) -> Check {
+    let path = match config_path {
+        Some(p) => p.to_path_buf(),
+        None => root.join(CONFIG_FILE_NAME),
+    };
+    if !path.exists() {
+        return if config_path.is_some() {
+            Check::fail(
+                "config",
+                format!("{} does not exist", path.display()),
+                "check the --config path",
+            )
+        } else {
+            Check::ok("config", "no config file, using defaults")
+        };
+    }
+    match EncoderConfig::from_file(&path) {
+        Ok(_) => Check::ok("config", format!("{} is valid", path.display())),
+        Err(e) => Check::fail(
+            "config",
+            e,
+            format!(
+                "fix the JSON in {} (runs fall back to defaults)",
+                path.display()
+            ),
+        ),
+    }
+}
+
+fn check_nested_configs(root: &Path) -> Check {
+    match DirectoryConfigs::discover(root) {
+        Ok(configs) if configs.is_empty() => Check::ok("nested", "no nested config files"),
+        Ok(configs) => Check::ok(
+            "nested",
+            format!(
+                "{} nested config files are valid",
+                configs.directories().count()
+            ),
+        ),
+        Err(e) => Check::fail(
+            "nested",
+            e,
+            "fix or remove the file; nested configs are ignored until then",
+        ),
+    }
+}
+
+/// A state file under `.pm_encoder/`, read as empty when it is corrupt
+fn check_state(root: &Path, kind: StateKind) -> Check {
+    let name = kind.name();
+    let path = kind.path(root);
+    if !path.exists() {
+        return Check::ok(name, format!("{} not created yet", path.display()));
+    }
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            return Check::fail(
+                name,
+                format!("cannot read {}: {}", path.display(), e),
+                format!("check the permissions of {}", path.display()),
+            )
+        }
+    };
+    let corrupt = |e: String| {
+        Check::fail(
+            name,
+            format!("{} is corrupt: {}", path.display(), e),
+            format!(
+                "restore {} from a backup or delete it (it is read as empty meanwhile)",
+                path.display()
+            ),
+        )
+    };
+    let (from, value) = match migrate::upgrade_json(kind, &content) {
+        Ok(upgraded) => upgraded,
+        Err(e) => return corrupt(e),
+    };
+    if let Err(e) = kind.validate(&value) {
+        return corrupt(e);
+    }
+    if from != kind.current_version() {
+        return Check::warn(
+            name,
+            format!(
+                "{} uses the {} format (current is {})",
+                path.display(),
+                from,
+                kind.current_version()
+            ),
+            "run vo --migrate to rewrite it (it is upgraded in memory meanwhile)",
+        );
+    }
+    Check::ok(name, format!("{} is intact", path.display()))
+}
+
+#[cfg(feature = "temporal")]
+fn check_chronos_cache(root: &Path) -> Check {
+    use crate::core::ChronosCacheManager;
+
+    // The cache lives at the repository root, not the project root
+    let repo_root = git2::Repository::discover(root)
+        .ok()
+        .and_then(|repo| repo.workdir().map(Path::to_path_buf))
+        .unwrap_or_else(|| root.to_path_buf());
+    let manager = ChronosCacheManager::new(&repo_root);
+    let path = manager.cache_path();
+    if !path.exists() {
+        Check::ok("cache", "no Chronos cache yet")
+    } else if manager.warp_engaged() {
+        Check::ok("cache", format!("{} is current", path.display()))
+    } else {
+        Check::warn(
+            "cache",
+            format!("{} is stale or unreadable", path.display()),
+            "nothing to do: it is rebuilt on the next scan (or pass --no-cache)",
+        )
+    }
+}
+
+/// Every registered voyager-ast grammar must load into a parser
+fn check_grammars() -> Check {
+    let registry = voyager_ast::AdapterRegistry::new();
+    let languages = registry.supported_languages();
+    let broken: Vec<String> = languages
+        .iter()
+        .filter_map(|&lang| {
+            registry
+                .parse("", lang)
+                .err()
+                .map(|e| format!("{:?} ({})", lang, e))
+        })
+        .collect();
+    if broken.is_empty() {
+        Check::ok(
+            "grammars",
+            format!("{} tree-sitter grammars load", languages.len()),
+        )
+    } else {
+        Check::fail(
+            "grammars",
+            format!("failed to load: {}", broken.join(", ")),
+            "rebuild vo; the tree-sitter grammar crates are out of step with tree-sitter",
+        )
+    }
+}
+
+/// Whether `dir` (or, before it exists, its parent) accepts new files
+fn check_writable(dir: &Path) -> Check {
+    let name = "writable";
+    let target: PathBuf = if dir.exists() {
+        dir.to_path_buf()
+    } else {
+        match dir.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => dir.to_path_buf(),
+        }
+    };
+    let probe = target.join(format!(".vo-doctor-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Check::ok(name, format!("{} can be written", dir.display()))
+        }
+        Err(e) => Check::fail(
+            name,
+            format!("cannot write to {}: {}", target.display(), e),
+            format!(
+                "make {} writable; journal, store and cache updates are lost until then",
+                target.display()
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_clean_project() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let report = diagnose(temp.path(), None);
+        assert_eq!(report.failures(), 0, "{}", report.render(Palette::plain()));
+        assert!(report.checks.iter().any(|c| c.name == "grammars"));
+    }
+
+    #[test]
+    fn test_diagnose_reports_corrupt_state() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join(".pm_encoder")).unwrap();
+        fs::write(root.join(CONFIG_FILE_NAME), "{oops").unwrap();
+        fs::write(root.join(".pm_encoder/journal.json"), "[]").unwrap();
+
+        let report = diagnose(root, None);
+        let status = |name: &str| {
+            report
+                .checks
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.status)
+        };
+        assert_eq!(status("config"), Some(CheckStatus::Fail));
+        assert_eq!(status("journal"), Some(CheckStatus::Fail));
+        assert_eq!(status("store"), Some(CheckStatus::Ok));
+        assert_eq!(report.failures(), 2);
+        assert!(report.render(Palette::plain()).contains("fix: restore"));
+    }
+}
@@ -0,0 +1,389 @@
+//! One code path for every output format
+//!
+//! A [`BundleFormatter`] writes a whole bundle: [`BundleFormatter::begin`]
+//! before the first file, [`BundleFormatter::file`] for each file and
+//! [`BundleFormatter::end`] after the last. Batch serialization, `--stream`
+//! mode and the token-budget path all drive a formatter the same way, so no
+//! caller special-cases a format.
+//!
+//! Every [`OutputWriter`] becomes a formatter through [`WriterFormatter`].
+//! Claude-XML, whose `<context>` envelope carries lens and budget metadata,
+//! is [`ClaudeXmlFormatter`]. [`formatter_for`] picks the one a config asks
+//! for.
+//!
+//! When streaming, files aren't known up front: `begin` and `end` get `None`
+//! instead of the bundle's files.
+
+use std::io::{self, Write};
+
+use rayon::prelude::*;
+
+use crate::budgeting::BudgetReport;
+use crate::formats::{writer_for, AttentionEntry, OutputFile, OutputWriter, TemplateOutput};
+use crate::formats::{XmlConfig, XmlError, XmlWriter};
+use crate::{EncoderConfig, FileEntry, LensManager, OutputFormat};
+
+/// Writes a bundle of files in one output format
+pub trait BundleFormatter: Send + Sync {
+    /// Turn an entry into the file to write (truncation, line numbers and
+    /// the run's transforms)
+    fn prepare(&self, entry: &FileEntry) -> OutputFile;
+
+    /// Write anything that precedes the first file
+    fn begin(&mut self, out: &mut dyn Write, files: Option<&[OutputFile]>) -> io::Result<()>;
+
+    /// Write one file
+    fn file(&mut self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()>;
+
+    /// Write anything that follows the last file
+    fn end(&mut self, out: &mut dyn Write, files: Option<&[OutputFile]>) -> io::Result<()>;
+}
+
+/// Write `entries` as one bundle
+///
+/// Entries are prepared in parallel, then written in order.
+pub fn format_entries(
+    formatter: &mut dyn BundleFormatter,
+    out: &mut dyn Write,
+    entries: &[FileEntry],
+) -> io::Result<()> {
+    let files: Vec<OutputFile> = {
+        let formatter = &*formatter;
+        entries.par_iter().map(|e| formatter.prepare(e)).collect()
+    };
+
+    formatter.begin(out, Some(&files))?;
+    for file in &files {
+        formatter.file(out, file)?;
+    }
+    formatter.end(out, Some(&files))
+}
+
+/// The formatter `config` asks for: its output format, with Plus/Minus
+/// templates when the config has them
+pub fn formatter_for(config: &EncoderConfig) -> Result<Box<dyn BundleFormatter + '_>, String> {
+    Ok(match (config.output_format, &config.templates) {
+        (OutputFormat::ClaudeXml, _) => Box::new(ClaudeXmlFormatter::new(config)),
+        (OutputFormat::PlusMinus, Some(templates)) => {
+            let writer = TemplateOutput::new(templates)?;
+            // A custom truncation marker replaces the built-in summary
+            let summary = !writer.replaces_truncation_marker();
+            Box::new(WriterFormatter::new(config, Box::new(writer)).with_summary(summary))
+        }
+        (format, _) => Box::new(WriterFormatter::new(config, Box::new(writer_for(format)))),
+    })
+}
+
+/// Any [`OutputWriter`] as a formatter
+///
+/// The writer's header and footer see every file, so they are only written
+/// when the files are known up front (not when streaming).
+pub struct WriterFormatter<'a> {
+    config: &'a EncoderConfig,
+    writer: Box<dyn OutputWriter + 'a>,
+    summary: bool,
+}
+
+impl<'a> WriterFormatter<'a> {
+    pub fn new(config: &'a EncoderConfig, writer: Box<dyn OutputWriter + 'a>) -> Self {
+        Self {
+            config,
+            writer,
+            summary: true,
+        }
+    }
+
+    /// Whether truncated files end with the built-in summary marker
+    pub fn with_summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+}
+
+impl BundleFormatter for WriterFormatter<'_> {
+    fn prepare(&self, entry: &FileEntry) -> OutputFile {
+        let mut file = crate::prepare_output_file_with_transforms(
+            entry,
+            &self.config.transforms_for(&entry.path, self.summary),
+            self.config.metadata_mode,
+        );
+        file.hints = self.config.header_hints_for(&file);
+        file
+    }
+
+    fn begin(&mut self, out: &mut dyn Write, files: Option<&[OutputFile]>) -> io::Result<()> {
+        match files {
+            Some(files) => self.writer.write_header(out, files),
+            None => Ok(()),
+        }
+    }
+
+    fn file(&mut self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        self.writer.write_file(out, file)
+    }
+
+    fn end(&mut self, out: &mut dyn Write, files: Option<&[OutputFile]>) -> io::Result<()> {
+        match files {
+            Some(files) => self.writer.write_footer(out, files),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Claude-XML: files inside a `<context>` envelope with an attention map
+///
+/// With a [`BudgetReport`] (see [`ClaudeXmlFormatter::with_report`]) the
+/// envelope shows the budget actually used, the attention map lists dropped
+/// files as coldspots, and files the budget strategy truncated are cut to
+/// their structure.
+pub struct ClaudeXmlFormatter<'a> {
+    config: &'a EncoderConfig,
+    xml_config: XmlConfig,
+    lens_manager: LensManager,
+    report: Option<&'a BudgetReport>,
+}
+
+fn to_io(e: XmlError) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+impl<'a> ClaudeXmlFormatter<'a> {
+    pub fn new(config: &'a EncoderConfig) -> Self {
+        let xml_config = XmlConfig {
+            package: "pm_encoder".to_string(),
+            version: crate::VERSION.to_string(),
+            lens: config.active_lens.clone(),
+            token_budget: config.token_budget,
+            utilized_tokens: None,
+            frozen: config.frozen,
+            allow_sensitive: config.allow_sensitive,
+            snapshot_id: if config.frozen {
+                Some("FROZEN_SNAPSHOT".to_string())
+            } else {
+                None
+            },
+        };
+
+        // Apply the active lens for priority calculation
+        let mut lens_manager = LensManager::new();
+        if let Some(ref lens_name) = config.active_lens {
+            let _ = lens_manager.apply_lens(lens_name);
+        }
+
+        Self {
+            config,
+            xml_config,
+            lens_manager,
+            report: None,
+        }
+    }
+
+    /// Describe a token-budgeted selection
+    pub fn with_report(mut self, report: &'a BudgetReport) -> Self {
+        self.xml_config.token_budget = Some(report.budget);
+        self.xml_config.utilized_tokens = Some(report.used);
+        self.report = Some(report);
+        self
+    }
+
+    fn attention_entries(&self, files: &[OutputFile]) -> Vec<AttentionEntry> {
+        let Some(report) = self.report else {
+            return files
+                .iter()
+                .map(|f| AttentionEntry {
+                    path: f.path.clone(),
+                    priority: self
+                        .lens_manager
+                        .get_file_priority(std::path::Path::new(&f.path)),
+                    tokens: f.size as usize / 4,
+                    truncated: f.was_truncated,
+                    dropped: false,
+                    utility_score: None,
+                })
+                .collect();
+        };
+
+        // TODO: Integrate with ContextStore for utility scores
+        let mut entries: Vec<AttentionEntry> = report
+            .included_files
+            .iter()
+            .map(|(path, priority, tokens, method)| AttentionEntry {
+                path: path.clone(),
+                priority: *priority,
+                tokens: *tokens,
+                truncated: method == "truncated",
+                dropped: false,
+                utility_score: None,
+            })
+            .collect();
+
+        // Dropped files are the coldspots
+        entries.extend(report.dropped_files.iter().map(|(path, priority, tokens)| {
+            AttentionEntry {
+                path: path.clone(),
+                priority: *priority,
+                tokens: *tokens,
+                truncated: false,
+                dropped: true,
+                utility_score: None,
+            }
+        }));
+
+        // Highest priority first
+        entries.sort_by(|a, b| b.priority.cmp(&a.priority));
+        entries
+    }
+}
+
+impl BundleFormatter for ClaudeXmlFormatter<'_> {
+    fn prepare(&self, entry: &FileEntry) -> OutputFile {
+        let budget_truncated = self.report.is_some_and(|report| {
+            report
+                .included_files
+                .iter()
+                .any(|(p, _, _, m)| p == &entry.path && m == "truncated")
+        });
+        let (lines, mode) = self.config.truncation_for(&entry.path);
+        let transforms = self.config.transforms_with(
+            &entry.path,
+            crate::XmlTruncation {
+                lines,
+                mode: mode.to_string(),
+                budget_truncated,
+            },
+        );
+        let mut file = crate::prepare_output_file_with_transforms(
+            entry,
+            &transforms,
+            self.config.metadata_mode,
+        );
+        file.hints = self.config.header_hints_for(&file);
+        file
+    }
+
+    fn begin(&mut self, out: &mut dyn Write, files: Option<&[OutputFile]>) -> io::Result<()> {
+        // Without the files (streaming) the attention map stays empty
+        let files = files.unwrap_or_default();
+        if self.report.is_none() && !files.is_empty() {
+            self.xml_config.utilized_tokens = Some(files.iter().map(|f| f.size as usize / 4).sum());
+        }
+        let attention_entries = self.attention_entries(files);
+
+        let mut writer = XmlWriter::new(out, self.xml_config.clone());
+        writer.write_context_start().map_err(to_io)?;
+        writer.write_metadata(&attention_entries).map_err(to_io)?;
+        writer.write_files_start().map_err(to_io)
+    }
+
+    fn file(&mut self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        let priority = self
+            .lens_manager
+            .get_static_priority(std::path::Path::new(&file.path));
+        let original_tokens = file.was_truncated.then_some(file.size as usize / 4);
+
+        // Zoom affordance for truncated files (Phase 4: Fractal affordances)
+        let zoom_cmd = file.was_truncated.then(|| match self.report {
+            Some(_) => format!("pm_encoder --zoom file={}", file.path),
+            None => format!("--include {} --truncate 0", file.path),
+        });
+
+        let mut block = Vec::new();
+        XmlWriter::in_files_section(&mut block, self.xml_config.clone())
+            .write_file(
+                &file.path,
+                file.language,
+                &file.md5,
+                priority,
+                &file.content,
+                file.was_truncated,
+                original_tokens,
+                zoom_cmd.as_deref(),
+                file.merge_conflicts,
+            )
+            .map_err(to_io)?;
+
+        // Hints go after the tag's other attributes (language is already there)
+        let mut block = String::from_utf8_lossy(&block).into_owned();
+        if let Some(ref hints) = file.hints {
+            block = block.replacen(
+                ">\n",
+                &format!("{}>\n", hints.xml_attrs(false, "\n      ")),
+                1,
+            );
+        }
+        out.write_all(block.as_bytes())
+    }
+
+    fn end(&mut self, out: &mut dyn Write, _files: Option<&[OutputFile]>) -> io::Result<()> {
+        let mut writer = XmlWriter::in_files_section(out, self.xml_config.clone());
+        writer.write_files_end().map_err(to_io)?;
+        writer.write_context_end().map_err(to_io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, content: &str) -> FileEntry {
+        FileEntry::new(path, content)
+    }
+
+    fn render(formatter: &mut dyn BundleFormatter, entries: &[FileEntry]) -> String {
+        let mut output = Vec::new();
+        format_entries(formatter, &mut output, entries).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_streaming_skips_writer_header() {
+        let config = EncoderConfig {
+            output_format: OutputFormat::Gemini,
+            ..Default::default()
+        };
+        let a = entry("a.rs", "fn a() {}\n");
+        let mut formatter = formatter_for(&config).unwrap();
+
+        let mut output = Vec::new();
+        formatter.begin(&mut output, None).unwrap();
+        let file = formatter.prepare(&a);
+        formatter.file(&mut output, &file).unwrap();
+        formatter.end(&mut output, None).unwrap();
+        let streamed = String::from_utf8(output).unwrap();
+
+        let batch = render(formatter.as_mut(), std::slice::from_ref(&a));
+        assert!(batch.starts_with("# Project Files (1)"));
+        assert!(batch.ends_with(&streamed));
+        assert!(streamed.starts_with("<file path=\"a.rs\""));
+    }
+
+    #[test]
+    fn test_claude_xml_report_lists_dropped_files() {
+        let config = EncoderConfig {
+            output_format: OutputFormat::ClaudeXml,
+            ..Default::default()
+        };
+        let report = BudgetReport {
+            budget: 100,
+            used: 3,
+            selected_count: 1,
+            dropped_count: 1,
+            dropped_files: vec![("big.rs".to_string(), 10, 900)],
+            estimation_method: "heuristic".to_string(),
+            strategy: "drop".to_string(),
+            included_files: vec![("a.rs".to_string(), 50, 3, "full".to_string())],
+            truncated_count: 0,
+            framing: Default::default(),
+        };
+        let output = render(
+            &mut ClaudeXmlFormatter::new(&config).with_report(&report),
+            &[entry("a.rs", "fn a() {}\n")],
+        );
+
+        assert!(output.contains("token_budget=\"100\""));
+        assert!(output.contains("utilized=\"3\""));
+        assert!(output.contains("big.rs"));
+        assert_eq!(output.matches("<file\n").count(), 1);
+        assert!(output.trim_end().ends_with("</context>"));
+    }
+}
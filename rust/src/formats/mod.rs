@@ -1,8 +1,25 @@
 //! Output format modules for pm_encoder
 //!
-//! This module provides streaming formatters for various output formats.
+//! This module provides streaming formatters for various output formats,
+//! the [`OutputWriter`] trait every format implements, and the
+//! [`BundleFormatter`] every serialization path drives.
 //! All formatters use the `std::io::Write` trait for WASM compatibility.
 
+pub mod bundle_formatter;
+pub mod output_writer;
+pub mod stream_writer;
+pub mod template;
+pub mod validate;
 pub mod xml_writer;
 
+pub use bundle_formatter::{
+    format_entries, formatter_for, BundleFormatter, ClaudeXmlFormatter, WriterFormatter,
+};
+pub use output_writer::{
+    writer_for, ClaudeXmlOutput, GeminiOutput, HeaderHints, MarkdownOutput, OutputFile,
+    OutputWriter, OutputWriterRegistry, PlusMinusOutput, XmlOutput,
+};
+pub use stream_writer::StreamWriter;
+pub use template::{Template, TemplateOutput, Templates};
+pub use validate::validate_output;
 pub use xml_writer::{escape_cdata, AttentionEntry, XmlConfig, XmlError, XmlWriter};
@@ -0,0 +1,505 @@
+//! Pluggable output writers
+//!
+//! Every output format is an [`OutputWriter`]: an optional header before the
+//! first file, one block per file, and an optional footer. The built-in
+//! formats are registered in [`OutputWriterRegistry::new`]; downstream crates
+//! add their own with [`OutputWriterRegistry::register`] and serialize with
+//! [`crate::serialize_entries_with_writer`], without touching the encoder.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pm_encoder::formats::{OutputFile, OutputWriter, OutputWriterRegistry};
+//! use std::io::{self, Write};
+//!
+//! struct PathList;
+//!
+//! impl OutputWriter for PathList {
+//!     fn name(&self) -> &str {
+//!         "paths"
+//!     }
+//!
+//!     fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+//!         writeln!(out, "{} ({} lines)", file.path, file.final_lines)
+//!     }
+//! }
+//!
+//! let mut registry = OutputWriterRegistry::new();
+//! registry.register(Box::new(PathList));
+//! assert!(registry.get("paths").is_some());
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::{MetadataMode, OutputFormat};
+
+/// A file ready to be written: truncation (and line numbering) already applied
+#[derive(Debug, Clone)]
+pub struct OutputFile {
+    /// Relative path
+    pub path: String,
+    /// Content to emit
+    pub content: String,
+    /// MD5 of the ORIGINAL content
+    pub md5: String,
+    /// Original size in bytes
+    pub size: u64,
+    /// Modification time (seconds since epoch, 0 if unknown)
+    pub mtime: u64,
+    /// Language detected by the walker (empty when unknown)
+    pub language: &'static str,
+    /// Whether the file is executable
+    pub executable: bool,
+    /// Symlink target, if the file was reached through one
+    pub symlink_target: Option<String>,
+    /// Whether `content` is a truncated view
+    pub was_truncated: bool,
+    /// Line count before truncation
+    pub original_lines: usize,
+    /// Line count after truncation
+    pub final_lines: usize,
+    /// Unresolved merge conflicts in the original content, flagged in the header
+    pub merge_conflicts: usize,
+    /// How much size/time metadata to show
+    pub metadata_mode: MetadataMode,
+    /// Language, token and utility hints for the header (see
+    /// [`crate::EncoderConfig::header_hints`])
+    pub hints: Option<HeaderHints>,
+}
+
+/// Per-file hints added to headers when `EncoderConfig::header_hints` is set
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderHints {
+    /// Language detected from the file extension
+    pub language: &'static str,
+    /// Estimated tokens in the emitted content
+    pub tokens: usize,
+    /// Learned utility score, when a context store is loaded
+    pub utility: Option<f64>,
+}
+
+impl HeaderHints {
+    /// Plus/Minus and Markdown form: ` [lang:rust tokens:120 utility:0.82]`
+    pub fn bracket(&self) -> String {
+        let mut hints = format!(" [lang:{} tokens:{}", self.language, self.tokens);
+        if let Some(utility) = self.utility {
+            hints.push_str(&format!(" utility:{:.2}", utility));
+        }
+        hints.push(']');
+        hints
+    }
+
+    /// XML attribute form, each attribute preceded by `separator`, optionally
+    /// without `language` (formats that already carry it)
+    pub fn xml_attrs(&self, with_language: bool, separator: &str) -> String {
+        let mut attrs = String::new();
+        if with_language {
+            attrs.push_str(&format!("{}language=\"{}\"", separator, self.language));
+        }
+        attrs.push_str(&format!("{}tokens=\"{}\"", separator, self.tokens));
+        if let Some(utility) = self.utility {
+            attrs.push_str(&format!("{}utility=\"{:.2}\"", separator, utility));
+        }
+        attrs
+    }
+}
+
+/// An output format
+pub trait OutputWriter: Send + Sync {
+    /// Name the writer is registered under
+    fn name(&self) -> &str;
+
+    /// Write anything that precedes the first file
+    fn write_header(&self, _out: &mut dyn Write, _files: &[OutputFile]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Write one file
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()>;
+
+    /// Write anything that follows the last file
+    fn write_footer(&self, _out: &mut dyn Write, _files: &[OutputFile]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: OutputWriter + ?Sized> OutputWriter for &T {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn write_header(&self, out: &mut dyn Write, files: &[OutputFile]) -> io::Result<()> {
+        (**self).write_header(out, files)
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        (**self).write_file(out, file)
+    }
+
+    fn write_footer(&self, out: &mut dyn Write, files: &[OutputFile]) -> io::Result<()> {
+        (**self).write_footer(out, files)
+    }
+}
+
+/// Signature shared by the per-entry serializers in the crate root
+type EntryFn = fn(&str, &str, &str, u64, u64, bool, usize, usize, MetadataMode) -> String;
+
+/// Where a format's file header is, for flagging merge conflicts and
+/// adding hints in it
+#[derive(Clone, Copy)]
+enum Header {
+    /// `++++++++++ path ++++++++++`
+    PlusMinus,
+    /// `### path`
+    Markdown,
+    /// `<file ...>`, which may already name the language
+    XmlTag { language: bool },
+}
+
+impl Header {
+    /// `block` with `hints` added to its header
+    fn add_hints(self, block: String, path: &str, hints: Option<&HeaderHints>) -> String {
+        let Some(hints) = hints else {
+            return block;
+        };
+        match self {
+            Header::PlusMinus => {
+                let header = format!("++++++++++ {}", path);
+                block.replacen(&header, &format!("{}{}", header, hints.bracket()), 1)
+            }
+            Header::Markdown => {
+                let header = format!("### {}", path);
+                block.replacen(&header, &format!("{}{}", header, hints.bracket()), 1)
+            }
+            Header::XmlTag { language } => {
+                block.replacen(">\n", &format!("{}>\n", hints.xml_attrs(!language, " ")), 1)
+            }
+        }
+    }
+
+    /// `block` with its header flagging `conflicts` merge conflicts
+    fn flag_conflicts(self, block: String, conflicts: usize) -> String {
+        if conflicts == 0 {
+            return block;
+        }
+        let flag = format!(" [MERGE CONFLICTS: {}]", conflicts);
+        match self {
+            Header::PlusMinus => {
+                block.replacen(" ++++++++++\n", &format!("{} ++++++++++\n", flag), 1)
+            }
+            Header::Markdown => block.replacen('\n', &format!("{}\n", flag), 1),
+            Header::XmlTag { .. } => {
+                block.replacen(">\n", &format!(" merge_conflicts=\"{}\">\n", conflicts), 1)
+            }
+        }
+    }
+}
+
+fn write_entry(
+    out: &mut dyn Write,
+    file: &OutputFile,
+    entry: EntryFn,
+    header: Header,
+) -> io::Result<()> {
+    let block = entry(
+        &file.path,
+        &file.content,
+        &file.md5,
+        file.size,
+        file.mtime,
+        file.was_truncated,
+        file.original_lines,
+        file.final_lines,
+        file.metadata_mode,
+    );
+    let block = header.add_hints(block, &file.path, file.hints.as_ref());
+    let block = header.flag_conflicts(block, file.merge_conflicts);
+    out.write_all(block.as_bytes())
+}
+
+/// Plus/Minus format (default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlusMinusOutput;
+
+/// Plain XML format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlOutput;
+
+/// Markdown format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownOutput;
+
+/// Claude-XML file blocks
+///
+/// Writes per-file `<file>` elements only; the full document with context
+/// and attention metadata comes from [`crate::formats::ClaudeXmlFormatter`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaudeXmlOutput;
+
+/// Gemini long-context format (file list preamble + fenced blocks)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GeminiOutput;
+
+impl OutputWriter for PlusMinusOutput {
+    fn name(&self) -> &str {
+        "plus_minus"
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        write_entry(
+            out,
+            file,
+            crate::serialize_plus_minus_entry,
+            Header::PlusMinus,
+        )
+    }
+}
+
+impl OutputWriter for XmlOutput {
+    fn name(&self) -> &str {
+        "xml"
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        write_entry(
+            out,
+            file,
+            crate::serialize_xml_entry,
+            Header::XmlTag { language: false },
+        )
+    }
+}
+
+impl OutputWriter for MarkdownOutput {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        write_entry(out, file, crate::serialize_markdown_entry, Header::Markdown)
+    }
+}
+
+impl OutputWriter for ClaudeXmlOutput {
+    fn name(&self) -> &str {
+        "claude-xml"
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        write_entry(
+            out,
+            file,
+            crate::serialize_claude_xml_entry,
+            Header::XmlTag { language: true },
+        )
+    }
+}
+
+impl OutputWriter for GeminiOutput {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn write_header(&self, out: &mut dyn Write, files: &[OutputFile]) -> io::Result<()> {
+        let listing: Vec<(&str, &str)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.content.as_str()))
+            .collect();
+        out.write_all(crate::gemini_preamble(&listing).as_bytes())
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        let block = crate::serialize_gemini_entry(
+            &file.path,
+            &file.content,
+            &file.md5,
+            file.size,
+            file.mtime,
+            file.was_truncated,
+            file.original_lines,
+            file.metadata_mode,
+        );
+        let header = Header::XmlTag { language: true };
+        let block = header.add_hints(block, &file.path, file.hints.as_ref());
+        let block = header.flag_conflicts(block, file.merge_conflicts);
+        out.write_all(block.as_bytes())
+    }
+}
+
+/// The built-in writer for a format
+pub fn writer_for(format: OutputFormat) -> &'static dyn OutputWriter {
+    match format {
+        OutputFormat::PlusMinus => &PlusMinusOutput,
+        OutputFormat::Xml => &XmlOutput,
+        OutputFormat::Markdown => &MarkdownOutput,
+        OutputFormat::ClaudeXml => &ClaudeXmlOutput,
+        OutputFormat::Gemini => &GeminiOutput,
+    }
+}
+
+/// Output writers by name
+pub struct OutputWriterRegistry {
+    writers: BTreeMap<String, Box<dyn OutputWriter>>,
+}
+
+impl OutputWriterRegistry {
+    /// Create a registry with the built-in formats
+    pub fn new() -> Self {
+        let mut registry = Self {
+            writers: BTreeMap::new(),
+        };
+        registry.register(Box::new(PlusMinusOutput));
+        registry.register(Box::new(XmlOutput));
+        registry.register(Box::new(MarkdownOutput));
+        registry.register(Box::new(ClaudeXmlOutput));
+        registry.register(Box::new(GeminiOutput));
+        registry
+    }
+
+    /// Register a writer, replacing any writer with the same name
+    pub fn register(&mut self, writer: Box<dyn OutputWriter>) {
+        self.writers.insert(writer.name().to_string(), writer);
+    }
+
+    /// Get a writer by name; built-in formats also accept their aliases
+    pub fn get(&self, name: &str) -> Option<&dyn OutputWriter> {
+        if let Some(writer) = self.writers.get(name) {
+            return Some(writer.as_ref());
+        }
+        let canonical = OutputFormat::parse(name).ok()?.name();
+        self.writers.get(canonical).map(|w| w.as_ref())
+    }
+
+    /// Registered writer names
+    pub fn names(&self) -> Vec<&str> {
+        self.writers.keys().map(String::as_str).collect()
+    }
+}
+
+impl Default for OutputWriterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{serialize_entries_with_writer, EncoderConfig, FileEntry};
+
+    struct PathList;
+
+    impl OutputWriter for PathList {
+        fn name(&self) -> &str {
+            "paths"
+        }
+
+        fn write_header(&self, out: &mut dyn Write, files: &[OutputFile]) -> io::Result<()> {
+            writeln!(out, "{} files", files.len())
+        }
+
+        fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+            writeln!(out, "- {}", file.path)
+        }
+
+        fn write_footer(&self, out: &mut dyn Write, _files: &[OutputFile]) -> io::Result<()> {
+            writeln!(out, "end")
+        }
+    }
+
+    fn entry(path: &str, content: &str) -> FileEntry {
+        FileEntry::new(path, content)
+    }
+
+    #[test]
+    fn test_registry_builtins_and_aliases() {
+        let registry = OutputWriterRegistry::new();
+        assert_eq!(registry.names().len(), 5);
+        assert_eq!(registry.get("md").unwrap().name(), "markdown");
+        assert_eq!(registry.get("claude_xml").unwrap().name(), "claude-xml");
+        assert!(registry.get("paths").is_none());
+    }
+
+    #[test]
+    fn test_custom_writer() {
+        let mut registry = OutputWriterRegistry::new();
+        registry.register(Box::new(PathList));
+
+        let entries = vec![entry("a.rs", "fn a() {}\n"), entry("b.rs", "fn b() {}\n")];
+        let writer = registry.get("paths").unwrap();
+        let output =
+            serialize_entries_with_writer(&EncoderConfig::default(), &entries, writer).unwrap();
+        assert_eq!(output, "2 files\n- a.rs\n- b.rs\nend\n");
+    }
+
+    #[test]
+    fn test_builtin_writer_matches_format_serialization() {
+        let entries = vec![entry("a.rs", "fn a() {}\n")];
+        for format in [
+            OutputFormat::PlusMinus,
+            OutputFormat::Xml,
+            OutputFormat::Markdown,
+            OutputFormat::Gemini,
+        ] {
+            let config = EncoderConfig {
+                output_format: format,
+                ..Default::default()
+            };
+            assert_eq!(
+                serialize_entries_with_writer(&config, &entries, writer_for(format)).unwrap(),
+                crate::serialize_sorted_entries(&config, &entries).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_conflicts_flagged_in_header() {
+        let entries = vec![entry(
+            "a.rs",
+            "<<<<<<< HEAD\nfn a() {}\n=======\nfn b() {}\n>>>>>>> topic\n",
+        )];
+        for (format, flag) in [
+            (
+                OutputFormat::PlusMinus,
+                "++++++++++ a.rs [MERGE CONFLICTS: 1] ++++++++++\n",
+            ),
+            (OutputFormat::Markdown, "### a.rs [MERGE CONFLICTS: 1]\n"),
+            (OutputFormat::Xml, " merge_conflicts=\"1\">\n"),
+            (OutputFormat::ClaudeXml, "\n      merge_conflicts=\"1\"\n"),
+            (OutputFormat::Gemini, " merge_conflicts=\"1\">\n"),
+        ] {
+            let config = EncoderConfig {
+                output_format: format,
+                metadata_mode: crate::MetadataMode::None,
+                ..Default::default()
+            };
+            let output = crate::serialize_sorted_entries(&config, &entries).unwrap();
+            assert!(output.contains(flag), "{:?}: {}", format, output);
+        }
+    }
+
+    #[test]
+    fn test_header_hints_in_every_format() {
+        let entries = vec![entry("a.rs", "fn a() {}\n")];
+        for (format, hint) in [
+            (
+                OutputFormat::PlusMinus,
+                "++++++++++ a.rs [lang:rust tokens:",
+            ),
+            (OutputFormat::Markdown, "### a.rs [lang:rust tokens:"),
+            (OutputFormat::Xml, " language=\"rust\" tokens=\""),
+            (OutputFormat::ClaudeXml, "\n      tokens=\""),
+            (OutputFormat::Gemini, " tokens=\""),
+        ] {
+            let config = EncoderConfig {
+                output_format: format,
+                metadata_mode: crate::MetadataMode::None,
+                header_hints: true,
+                ..Default::default()
+            };
+            let output = crate::serialize_sorted_entries(&config, &entries).unwrap();
+            assert!(output.contains(hint), "{:?}: {}", format, output);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Incremental writer for `--stream` mode
+//!
+//! [`StreamWriter`] writes each file as soon as it is discovered, in any
+//! output format. Formats with an envelope (Claude-XML's `<context>` and
+//! `<files>`) open it on construction and close it in [`StreamWriter::finish`];
+//! if the writer is dropped early (an error, or the caller stopping the walk)
+//! the envelope is still closed, so the output stays well-formed.
+//!
+//! Gemini's file listing needs every file up front, so in streaming mode only
+//! the fenced file blocks are written.
+
+use std::io::{self, Write};
+
+use crate::budgeting::TokenEstimator;
+use crate::formats::{formatter_for, BundleFormatter};
+use crate::{EncoderConfig, FileEntry};
+
+/// Writes files one at a time in the configured output format
+pub struct StreamWriter<'a, W: Write> {
+    out: W,
+    formatter: Box<dyn BundleFormatter + 'a>,
+    finished: bool,
+}
+
+impl<'a, W: Write> StreamWriter<'a, W> {
+    /// Create a writer and emit the opening envelope, if the format has one
+    pub fn new(mut out: W, config: &'a EncoderConfig) -> io::Result<Self> {
+        let mut formatter = formatter_for(config).map_err(io::Error::other)?;
+        formatter.begin(&mut out, None)?;
+        out.flush()?;
+
+        Ok(Self {
+            out,
+            formatter,
+            finished: false,
+        })
+    }
+
+    /// Write one file and flush it
+    pub fn write_entry(&mut self, entry: &FileEntry) -> io::Result<()> {
+        let file = self.formatter.prepare(entry);
+        self.formatter.file(&mut self.out, &file)?;
+        self.out.flush()
+    }
+
+    /// Write one file if it comes to at most `tokens_left` tokens as
+    /// written; returns its tokens, or `None` (writing nothing) if it
+    /// doesn't fit
+    pub fn write_entry_within(
+        &mut self,
+        entry: &FileEntry,
+        tokens_left: usize,
+    ) -> io::Result<Option<usize>> {
+        let file = self.formatter.prepare(entry);
+        let mut block = Vec::new();
+        self.formatter.file(&mut block, &file)?;
+        let tokens = TokenEstimator::estimate_tokens(&String::from_utf8_lossy(&block));
+        if tokens > tokens_left {
+            return Ok(None);
+        }
+        self.out.write_all(&block)?;
+        self.out.flush()?;
+        Ok(Some(tokens))
+    }
+
+    /// Close the envelope and flush
+    pub fn finish(mut self) -> io::Result<()> {
+        self.close()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.formatter.end(&mut self.out, None)?;
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for StreamWriter<'_, W> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputFormat;
+
+    fn entry(path: &str, content: &str) -> FileEntry {
+        FileEntry::new(path, content)
+    }
+
+    fn config(format: OutputFormat) -> EncoderConfig {
+        EncoderConfig {
+            output_format: format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plain_formats_match_batch_blocks() {
+        let entries = vec![entry("a.rs", "fn a() {}\n"), entry("b.rs", "fn b() {}\n")];
+        for format in [
+            OutputFormat::PlusMinus,
+            OutputFormat::Xml,
+            OutputFormat::Markdown,
+        ] {
+            let config = config(format);
+            let mut buffer = Vec::new();
+            let mut writer = StreamWriter::new(&mut buffer, &config).unwrap();
+            for e in &entries {
+                writer.write_entry(e).unwrap();
+            }
+            writer.finish().unwrap();
+            assert_eq!(
+                String::from_utf8(buffer).unwrap(),
+                crate::serialize_sorted_entries(&config, &entries).unwrap(),
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_templates_match_batch_output() {
+        let config = EncoderConfig {
+            truncate_lines: 1,
+            templates: Some(crate::formats::Templates {
+                header: Some("# {{ path }}".to_string()),
+                footer: Some("# end {{ md5 }}".to_string()),
+                truncation_marker: Some("# ({{ omitted_lines }} more)".to_string()),
+            }),
+            ..Default::default()
+        };
+        let entries = vec![entry("a.rs", "fn a() {}\n"), entry("b.rs", "fn b() {}\n")];
+        let mut buffer = Vec::new();
+        let mut writer = StreamWriter::new(&mut buffer, &config).unwrap();
+        for e in &entries {
+            writer.write_entry(e).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            crate::serialize_sorted_entries(&config, &entries).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_within_tokens() {
+        let config = config(OutputFormat::PlusMinus);
+        let mut buffer = Vec::new();
+        let mut writer = StreamWriter::new(&mut buffer, &config).unwrap();
+        let small = entry("a.rs", "fn a() {}\n");
+        let tokens = writer.write_entry_within(&small, 1000).unwrap().unwrap();
+        assert_eq!(
+            writer
+                .write_entry_within(&entry("b.rs", &"x".repeat(400)), 50)
+                .unwrap(),
+            None
+        );
+        writer.finish().unwrap();
+
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(tokens, TokenEstimator::estimate_tokens(&output));
+        assert!(output.contains("a.rs"));
+        assert!(!output.contains("b.rs"));
+    }
+
+    #[test]
+    fn test_claude_xml_envelope() {
+        let config = config(OutputFormat::ClaudeXml);
+        let mut buffer = Vec::new();
+        let mut writer = StreamWriter::new(&mut buffer, &config).unwrap();
+        writer.write_entry(&entry("a.rs", "fn a() {}\n")).unwrap();
+        writer.finish().unwrap();
+
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("<context"));
+        assert!(output.contains("<files>"));
+        assert!(output.contains("a.rs"));
+        assert!(output.trim_end().ends_with("</context>"));
+        assert_eq!(output.matches("</files>").count(), 1);
+    }
+
+    #[test]
+    fn test_claude_xml_closed_on_drop() {
+        let config = config(OutputFormat::ClaudeXml);
+        let mut buffer = Vec::new();
+        {
+            let mut writer = StreamWriter::new(&mut buffer, &config).unwrap();
+            writer.write_entry(&entry("a.rs", "fn a() {}\n")).unwrap();
+        }
+
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains("</files>"));
+        assert!(output.trim_end().ends_with("</context>"));
+    }
+}
@@ -0,0 +1,372 @@
+//! Custom Plus/Minus delimiters from `.pm_encoder_config.json`
+//!
+//! A `templates` section replaces the file header, footer and truncation
+//! marker with minijinja-style templates:
+//!
+//! ```json
+//! {
+//!   "templates": {
+//!     "header": "=== BEGIN {{ path }}{% if truncated %} (truncated){% endif %} ===",
+//!     "footer": "=== END {{ path }} md5={{ md5 }} ===",
+//!     "truncation_marker": "... {{ omitted_lines }} more lines in {{ path }} ..."
+//!   }
+//! }
+//! ```
+//!
+//! Supported syntax is `{{ var }}` and `{% if var %}...{% else %}...{% endif %}`
+//! (nestable); a variable is false when empty, `0` or `false`. Templates left
+//! out keep the built-in Plus/Minus form. See [`VARIABLES`] for the names.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+use super::{OutputFile, OutputWriter};
+
+/// Variables available to every template
+pub const VARIABLES: &[&str] = &[
+    "path",
+    "md5",
+    "size",
+    "mtime",
+    "language",
+    "executable",
+    "symlink_target",
+    "metadata",
+    "truncated",
+    "original_lines",
+    "final_lines",
+    "omitted_lines",
+    "merge_conflicts",
+];
+
+const DEFAULT_HEADER: &str = "++++++++++ {{ path }}{{ metadata }}{% if truncated %} [TRUNCATED: {{ original_lines }} lines]{% endif %}{% if merge_conflicts %} [MERGE CONFLICTS: {{ merge_conflicts }}]{% endif %} ++++++++++";
+const DEFAULT_FOOTER: &str = "---------- {{ path }}{% if truncated %} [TRUNCATED:{{ original_lines }}→{{ final_lines }}]{% endif %} {{ md5 }} {{ path }} ----------";
+
+/// The `templates` section of the config file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Templates {
+    /// Line before each file's content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    /// Line after each file's content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<String>,
+    /// Line appended to truncated content, replacing the built-in summary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncation_marker: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    If {
+        var: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parse a template, rejecting unknown variables and unbalanced blocks
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut rest = source;
+        let (nodes, end) = parse_nodes(&mut rest)?;
+        match end {
+            None => Ok(Self { nodes }),
+            Some(tag) => Err(format!("unexpected {{% {} %}}", tag)),
+        }
+    }
+
+    /// Render with `lookup` supplying variable values
+    pub fn render(&self, lookup: &dyn Fn(&str) -> String) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, lookup, &mut out);
+        out
+    }
+}
+
+fn check_variable(name: &str) -> Result<String, String> {
+    if VARIABLES.contains(&name) {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "unknown template variable '{}' (expected one of: {})",
+            name,
+            VARIABLES.join(", ")
+        ))
+    }
+}
+
+/// Parse until end of input or a closing tag (`else`/`endif`), which is returned
+fn parse_nodes(rest: &mut &str) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+    loop {
+        let next = [rest.find("{{"), rest.find("{%")]
+            .into_iter()
+            .flatten()
+            .min();
+        let Some(start) = next else {
+            if !rest.is_empty() {
+                nodes.push(Node::Text(rest.to_string()));
+            }
+            *rest = "";
+            return Ok((nodes, None));
+        };
+
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let is_var = rest[start..].starts_with("{{");
+        let close = if is_var { "}}" } else { "%}" };
+        let body_start = start + 2;
+        let end = rest[body_start..]
+            .find(close)
+            .map(|i| body_start + i)
+            .ok_or_else(|| format!("unclosed '{}'", &rest[start..start + 2]))?;
+        let body = rest[body_start..end].trim().to_string();
+        *rest = &rest[end + 2..];
+
+        if is_var {
+            nodes.push(Node::Var(check_variable(&body)?));
+            continue;
+        }
+
+        let mut words = body.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("if"), Some(var), None) => {
+                let var = check_variable(var)?;
+                let (then, end) = parse_nodes(rest)?;
+                let otherwise = match end.as_deref() {
+                    Some("endif") => Vec::new(),
+                    Some("else") => match parse_nodes(rest)? {
+                        (otherwise, Some(tag)) if tag == "endif" => otherwise,
+                        _ => return Err("{% else %} without {% endif %}".to_string()),
+                    },
+                    _ => return Err(format!("{{% if {} %}} without {{% endif %}}", var)),
+                };
+                nodes.push(Node::If {
+                    var,
+                    then,
+                    otherwise,
+                });
+            }
+            (Some(tag @ ("else" | "endif")), None, None) => {
+                return Ok((nodes, Some(tag.to_string())));
+            }
+            _ => return Err(format!("unsupported tag '{{% {} %}}'", body)),
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], lookup: &dyn Fn(&str) -> String, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => out.push_str(&lookup(name)),
+            Node::If {
+                var,
+                then,
+                otherwise,
+            } => {
+                let value = lookup(var);
+                let truthy = !(value.is_empty() || value == "0" || value == "false");
+                render_nodes(if truthy { then } else { otherwise }, lookup, out);
+            }
+        }
+    }
+}
+
+/// Plus/Minus output with templated delimiters
+#[derive(Debug, Clone)]
+pub struct TemplateOutput {
+    header: Template,
+    footer: Template,
+    truncation_marker: Option<Template>,
+}
+
+impl TemplateOutput {
+    /// Compile the configured templates; missing ones use the Plus/Minus defaults
+    pub fn new(templates: &Templates) -> Result<Self, String> {
+        let compile = |name: &str, source: &str| {
+            Template::parse(source).map_err(|e| format!("Invalid {} template: {}", name, e))
+        };
+        Ok(Self {
+            header: compile(
+                "header",
+                templates.header.as_deref().unwrap_or(DEFAULT_HEADER),
+            )?,
+            footer: compile(
+                "footer",
+                templates.footer.as_deref().unwrap_or(DEFAULT_FOOTER),
+            )?,
+            truncation_marker: templates
+                .truncation_marker
+                .as_deref()
+                .map(|t| compile("truncation_marker", t))
+                .transpose()?,
+        })
+    }
+
+    /// Whether the built-in truncation summary should be left out of the content
+    pub fn replaces_truncation_marker(&self) -> bool {
+        self.truncation_marker.is_some()
+    }
+}
+
+fn variable(file: &OutputFile, name: &str) -> String {
+    match name {
+        "path" => file.path.clone(),
+        "md5" => file.md5.clone(),
+        "size" => file.size.to_string(),
+        "mtime" => file.mtime.to_string(),
+        "language" => file.language.to_string(),
+        "executable" => file.executable.to_string(),
+        "symlink_target" => file.symlink_target.clone().unwrap_or_default(),
+        "metadata" => crate::core::serialization::format_metadata_suffix(
+            file.size,
+            file.mtime,
+            file.metadata_mode,
+        ),
+        "truncated" => file.was_truncated.to_string(),
+        "original_lines" => file.original_lines.to_string(),
+        "final_lines" => file.final_lines.to_string(),
+        "omitted_lines" => file
+            .original_lines
+            .saturating_sub(file.final_lines)
+            .to_string(),
+        "merge_conflicts" => file.merge_conflicts.to_string(),
+        _ => String::new(),
+    }
+}
+
+impl OutputWriter for TemplateOutput {
+    fn name(&self) -> &str {
+        "template"
+    }
+
+    fn write_file(&self, out: &mut dyn Write, file: &OutputFile) -> io::Result<()> {
+        let lookup = |name: &str| variable(file, name);
+        writeln!(out, "{}", self.header.render(&lookup))?;
+        out.write_all(file.content.as_bytes())?;
+        if !file.content.ends_with('\n') {
+            out.write_all(b"\n")?;
+        }
+        if file.was_truncated {
+            if let Some(marker) = &self.truncation_marker {
+                writeln!(out, "{}", marker.render(&lookup))?;
+            }
+        }
+        writeln!(out, "{}", self.footer.render(&lookup))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncoderConfig, FileEntry, OutputFormat};
+
+    fn entry(path: &str, content: &str) -> FileEntry {
+        FileEntry::new(path, content)
+    }
+
+    #[test]
+    fn test_parse_and_render() {
+        let t = Template::parse("{{ path }}{% if truncated %} (cut){% else %} (full){% endif %}")
+            .unwrap();
+        let render = |truncated: &'static str| {
+            t.render(&|name: &str| match name {
+                "path" => "a.rs".to_string(),
+                _ => truncated.to_string(),
+            })
+        };
+        assert_eq!(render("true"), "a.rs (cut)");
+        assert_eq!(render("false"), "a.rs (full)");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{ nope }}").unwrap_err().contains("nope"));
+        assert!(Template::parse("{{ path").is_err());
+        assert!(Template::parse("{% if truncated %}x").is_err());
+        assert!(Template::parse("x{% endif %}").is_err());
+        assert!(Template::parse("{% for x in y %}").is_err());
+    }
+
+    #[test]
+    fn test_defaults_match_plus_minus() {
+        let writer = TemplateOutput::new(&Templates::default()).unwrap();
+        let entries = vec![entry("a.rs", "fn a() {}\n")];
+        let config = EncoderConfig {
+            metadata_mode: crate::MetadataMode::None,
+            ..Default::default()
+        };
+        assert_eq!(
+            crate::serialize_entries_with_writer(&config, &entries, &writer).unwrap(),
+            crate::serialize_entries_with_writer(
+                &config,
+                &entries,
+                crate::formats::writer_for(OutputFormat::PlusMinus)
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_custom_delimiters_and_marker() {
+        let config = EncoderConfig {
+            truncate_lines: 2,
+            metadata_mode: crate::MetadataMode::None,
+            templates: Some(Templates {
+                header: Some("=== BEGIN {{ path }} ===".to_string()),
+                footer: Some("=== END {{ path }} {{ md5 }} ===".to_string()),
+                truncation_marker: Some("... {{ omitted_lines }} more lines ...".to_string()),
+            }),
+            ..Default::default()
+        };
+        let content = "a\nb\nc\nd\n";
+        let output = crate::serialize_sorted_entries(&config, &[entry("x.txt", content)]).unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "=== BEGIN x.txt ===\na\nb\n... 3 more lines ...\n=== END x.txt {} ===\n",
+                crate::calculate_md5(content)
+            )
+        );
+    }
+
+    #[test]
+    fn test_walker_metadata_variables() {
+        let config = EncoderConfig {
+            metadata_mode: crate::MetadataMode::None,
+            templates: Some(Templates {
+                header: Some(
+                    "# {{ path }} [{{ language }}]{% if executable %} +x{% endif %}\
+                     {% if symlink_target %} -> {{ symlink_target }}{% endif %}"
+                        .to_string(),
+                ),
+                footer: Some("# end".to_string()),
+                truncation_marker: None,
+            }),
+            ..Default::default()
+        };
+        let script = FileEntry {
+            executable: true,
+            symlink_target: Some("tools/run.sh".to_string()),
+            ..entry("run.sh", "echo hi\n")
+        };
+        let output =
+            crate::serialize_sorted_entries(&config, &[entry("a.rs", "fn a() {}\n"), script])
+                .unwrap();
+        assert_eq!(
+            output,
+            "# a.rs [rust]\nfn a() {}\n# end\n# run.sh [bash] +x -> tools/run.sh\necho hi\n# end\n"
+        );
+    }
+}
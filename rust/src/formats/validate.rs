@@ -0,0 +1,81 @@
+//! Checks on a finished bundle
+//!
+//! Bundles are often wrapped in JSON or XML on their way to a model, and
+//! both break on stray bytes: anything that isn't UTF-8, and control
+//! characters (XML 1.0 allows none but tab, newline and carriage return).
+//! [`validate_output`] looks for them, and checks that a Plus/Minus bundle
+//! decodes again.
+
+use crate::OutputFormat;
+
+/// Problems reported before the rest are summarized
+const MAX_PROBLEMS: usize = 10;
+
+/// What is wrong with `output`, a bundle in `format`; empty when nothing is
+pub fn validate_output(output: &[u8], format: OutputFormat) -> Vec<String> {
+    let text = match std::str::from_utf8(output) {
+        Ok(text) => text,
+        Err(e) => return vec![format!("not valid UTF-8: {}", e)],
+    };
+
+    let mut problems = Vec::new();
+    let mut controls = 0;
+    for (i, line) in text.lines().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            if c.is_control() && !matches!(c, '\t' | '\r') {
+                controls += 1;
+                if problems.len() < MAX_PROBLEMS {
+                    problems.push(format!(
+                        "line {}, column {}: control character U+{:04X}",
+                        i + 1,
+                        column + 1,
+                        c as u32
+                    ));
+                }
+            }
+        }
+    }
+    if controls > problems.len() {
+        problems.push(format!(
+            "... and {} more control characters",
+            controls - problems.len()
+        ));
+    }
+
+    if format == OutputFormat::PlusMinus {
+        if let Err(e) = crate::decode::decode(text) {
+            problems.push(format!("Plus/Minus framing: {}", e));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_output() {
+        let bundle = "++++++++++ a.txt ++++++++++\nhi\n---------- a.txt 764efa883dda1e11db47671c4a3bbd9e a.txt ----------\n";
+        assert!(validate_output(bundle.as_bytes(), OutputFormat::PlusMinus).is_empty());
+
+        assert_eq!(
+            validate_output(b"caf\xe9\n", OutputFormat::Markdown),
+            vec!["not valid UTF-8: invalid utf-8 sequence of 1 bytes from index 3"]
+        );
+        assert_eq!(
+            validate_output("ok\r\n\tx\u{1b}[0m\n".as_bytes(), OutputFormat::Xml),
+            vec!["line 2, column 3: control character U+001B"]
+        );
+
+        let noisy = "\u{0}".repeat(12);
+        let problems = validate_output(noisy.as_bytes(), OutputFormat::Xml);
+        assert_eq!(problems.len(), MAX_PROBLEMS + 1);
+        assert_eq!(problems[10], "... and 2 more control characters");
+
+        let unframed = "++++++++++ a.txt ++++++++++\nhi\n";
+        let problems = validate_output(unframed.as_bytes(), OutputFormat::PlusMinus);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Plus/Minus framing: "));
+    }
+}
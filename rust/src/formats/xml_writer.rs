@@ -97,6 +97,15 @@ impl<W: Write> XmlWriter<W> {
         }
     }
 
+    /// Create a writer that continues inside an already open `<files>` section
+    pub fn in_files_section(writer: W, config: XmlConfig) -> Self {
+        Self {
+            writer,
+            config,
+            in_files_section: true,
+        }
+    }
+
     /// Write the opening <context> tag with attributes
     pub fn write_context_start(&mut self) -> Result<()> {
         // Use BTreeMap for deterministic attribute ordering
@@ -256,6 +265,7 @@ impl<W: Write> XmlWriter<W> {
         truncated: bool,
         original_tokens: Option<usize>,
         zoom_command: Option<&str>,
+        merge_conflicts: usize,
     ) -> Result<()> {
         if !self.in_files_section {
             return Err(XmlError::InvalidState(
@@ -283,6 +293,9 @@ impl<W: Write> XmlWriter<W> {
                 attrs.insert("original_tokens".to_string(), orig.to_string());
             }
         }
+        if merge_conflicts > 0 {
+            attrs.insert("merge_conflicts".to_string(), merge_conflicts.to_string());
+        }
 
         // Write file tag with sorted attributes
         write!(self.writer, "    <file")?;
@@ -581,6 +594,7 @@ mod tests {
                 false,
                 None,
                 None,
+                0,
             )
             .unwrap();
         writer.write_files_end().unwrap();
@@ -613,6 +627,7 @@ mod tests {
                 true,
                 Some(5000),
                 Some("--include large.rs --truncate 0"),
+                0,
             )
             .unwrap();
         writer.write_files_end().unwrap();
@@ -687,7 +702,7 @@ mod tests {
         // Skip write_files_start
 
         let result = writer.write_file(
-            "test.rs", "rust", "abc123", 100, "content", false, None, None,
+            "test.rs", "rust", "abc123", 100, "content", false, None, None, 0,
         );
 
         assert!(result.is_err());
@@ -877,6 +892,7 @@ mod tests {
                 false,
                 None,
                 None,
+                0,
             )
             .unwrap();
 
@@ -962,6 +978,7 @@ mod tests {
                 false, // not truncated
                 None,
                 None,
+                0,
             )
             .unwrap();
         writer.write_files_end().unwrap();
@@ -990,6 +1007,7 @@ mod tests {
                 true, // truncated
                 Some(1000),
                 None, // no zoom command
+                0,
             )
             .unwrap();
         writer.write_files_end().unwrap();
@@ -0,0 +1,363 @@
+//! Glossary of a project's own vocabulary
+//!
+//! Models know what `HTTP` or `parse` mean, but not a team's `PLM` or
+//! `Manifold`. The glossary collects the acronyms and words that recur in a
+//! project's identifiers and comments but aren't common programming or
+//! English vocabulary, with how often and where they are used, so a context
+//! can end with an appendix pointing them out.
+//!
+//! Identifiers are split at underscores, digits and case changes
+//! (`PLMClientV2` is `PLM` and `Client`). An all-caps part is an acronym,
+//! unless it comes from a constant such as `MAX_RETRIES`; other parts are
+//! compared lowercased. Acronyms must be used at least twice, words in at
+//! least two files.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{escape_xml, escape_xml_attr, FileEntry, OutputFormat};
+
+/// Terms an appendix lists by default
+pub const MAX_TERMS: usize = 30;
+
+/// Identifiers quoted as examples of a term
+const MAX_EXAMPLES: usize = 3;
+
+/// Shortest word that can be a term
+const MIN_WORD_LEN: usize = 4;
+
+/// Acronyms every model knows, separated by whitespace
+const COMMON_ACRONYMS: &str = "
+    API ARG ASCII AST AWS CI CLI CPU CR CRLF CSS CSV DB DNS DOM EOF ERR FIFO FS GC GNU GPU
+    GUI HTML HTTP HTTPS ID IDE IDS IO IP JS JSON JWT LF LRU MD MIME MIT NOTE OK OS PDF PID
+    PNG PR RAM REST RPC SDK SHA SQL SSH SSL SVG TCP TLS TODO TOML TS UDP UI URI URL UTC UTF
+    UUID VM XML XXX YAML FIXME HACK
+";
+
+/// Words too common in code or English to explain anything, separated by
+/// whitespace
+const COMMON_WORDS: &str = "
+    about above absolute access action active actual added additional address advanced after
+    again against all allow already also always amount analysis analyze analyzer another
+    append apply arc aren args argument arguments argv array ascii assert async attribute
+    attributes auto available await back base based basic because been before begin being
+    below between block body bool boolean borrow both bound break buffer build builder byte
+    bytes cache calculate call called calls cannot captures case catch cell change changed
+    char chars check checks child children class classes clean clear cli client clone cloned
+    close code collect collections column command commands comment common compare complex
+    concat config configuration const contain contains content contents context continue
+    convert copied copy core could couldn count counts coverage crate create creation
+    current custom cwd data date debug dedup default define delete demo depth derive
+    description deserialize detect detection dict didn dir directory dirs display docs
+    documentation does doesn don done down during each either element else empty enable
+    enabled encode ends entries entry enum enumerate env environment eprintln equal equals
+    err error errors even event every example examples exist exists exit expect expected
+    export extend extension external extract fail failed fails failure false feature
+    features field file filename filepath files fill filter final find first fixture
+    fixtures flag flags float fn fold folder follow following format formatter found free
+    from full func function future generate generated generic given global golang handle
+    handler hash hashmap have head header height hello help helper helpers here hidden high
+    higher hold ignore impl import include index info init initial inner input inputs insert
+    inside instance integer interface internal into invalid isn item items iter iterator
+    java javascript join just keep kind kotlin label lambda language languages last later
+    layout left len length less let level like limit line lines link list load local lock
+    logger long look loop low lower lowercase main make many map match matches matching max
+    maximum memory message meta metadata method methods might min minimum missing mock mocks
+    mod mode model module more most move much multiple must mut mutable mutex name names
+    need needs never next node none normal note null number numbers object objects offset ok
+    once only open option optional options order ordering original other otherwise outer
+    output outputs over override owner package page pair param params parent parse parser
+    part partial pass passed path paths pattern point pointer position prefix previous
+    primary print println private process processing project properties property protected
+    provide provides pub public push python query range rc read reader ready real reason
+    record reduce ref reference regex relative remove render replace report request requests
+    require required reset resolve response rest result results retain return returns
+    reverse right root rows ruby rule rules rust same sample samples save scope script
+    search second secondary section see select self send separator serde serialization
+    serialize server service session setting settings setup shared should shouldn show side
+    signature simple since single size sized skip slice some sort sorted source space spec
+    special specific specs split start starts state static status stderr stdin stdout step
+    still stop store str stream string strings strip struct structure style success such
+    suffix sum summary super support swift switch symbol sync system table take target task
+    temp tempdir tempfile test tested testing tests text than that their them then there
+    these they thing this those through throw time timeout token tokens tool total trait
+    tree trim true tuple type types typescript undefined under unicode unit unknown unless
+    until unwrap unwrap_or update upper uppercase usage used user uses using usize utf util
+    utilities utility utils valid validate validation value values variable variant variants
+    vec vector verify version very view void wait want warn warning wasn were what when
+    where whether which while whole width will window with within without won word words
+    work world would wrap wrapper write writeln writer wrong yield your
+";
+
+lazy_static! {
+    static ref IDENTIFIER_PATTERN: Regex = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    static ref PART_PATTERN: Regex = Regex::new(r"[A-Z]+(?:[a-z]+)?|[a-z]+").unwrap();
+    static ref COMMON: BTreeSet<&'static str> = COMMON_ACRONYMS
+        .split_whitespace()
+        .chain(COMMON_WORDS.split_whitespace())
+        .collect();
+}
+
+/// What kind of term a [`GlossaryTerm`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TermKind {
+    /// An all-caps abbreviation, listed as written
+    Acronym,
+    /// A word, listed lowercased
+    Word,
+}
+
+impl TermKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TermKind::Acronym => "acronym",
+            TermKind::Word => "term",
+        }
+    }
+}
+
+/// A term of a project's vocabulary
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlossaryTerm {
+    pub term: String,
+    pub kind: TermKind,
+    /// How often it is used, across all files
+    pub uses: usize,
+    /// How many files use it
+    pub files: usize,
+    /// Identifiers it is part of, in order of appearance
+    pub examples: Vec<String>,
+}
+
+#[derive(Default)]
+struct Tally {
+    uses: usize,
+    files: BTreeSet<usize>,
+    examples: Vec<String>,
+}
+
+/// Split an identifier into its parts: `parse_PLMClient2` is `parse`,
+/// `PLM` and `Client`
+fn identifier_parts(identifier: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    for m in PART_PATTERN.find_iter(identifier) {
+        let part = m.as_str();
+        // `PLMClient` matches as one run; its last capital starts a word
+        let upper = part.chars().take_while(|c| c.is_ascii_uppercase()).count();
+        if upper > 1 && upper < part.len() {
+            parts.push(&part[..upper - 1]);
+            parts.push(&part[upper - 1..]);
+        } else {
+            parts.push(part);
+        }
+    }
+    parts
+}
+
+/// The term `part` of `identifier` counts as, if any
+fn term_of(part: &str, identifier: &str) -> Option<(String, TermKind)> {
+    let constant = identifier.contains('_') && !identifier.chars().any(|c| c.is_ascii_lowercase());
+    if part.len() > 1 && part.chars().all(|c| c.is_ascii_uppercase()) && !constant {
+        return (part.len() <= 6 && !COMMON.contains(part))
+            .then(|| (part.to_string(), TermKind::Acronym));
+    }
+    let word = part.to_ascii_lowercase();
+    let stem = word.strip_suffix('s').unwrap_or(&word);
+    let common = COMMON.contains(word.as_str())
+        || COMMON.contains(stem)
+        || COMMON.contains(part.to_ascii_uppercase().as_str());
+    (word.len() >= MIN_WORD_LEN && !common).then_some((word, TermKind::Word))
+}
+
+/// The `max_terms` terms most characteristic of `entries`, most widely used
+/// first
+pub fn extract_glossary(entries: &[FileEntry], max_terms: usize) -> Vec<GlossaryTerm> {
+    let mut tallies: BTreeMap<(String, TermKind), Tally> = BTreeMap::new();
+    for (file, entry) in entries.iter().enumerate() {
+        for m in IDENTIFIER_PATTERN.find_iter(&entry.content) {
+            let identifier = m.as_str();
+            for part in identifier_parts(identifier) {
+                let Some(key) = term_of(part, identifier) else {
+                    continue;
+                };
+                let tally = tallies.entry(key).or_default();
+                tally.uses += 1;
+                tally.files.insert(file);
+                if identifier.len() > part.len()
+                    && tally.examples.len() < MAX_EXAMPLES
+                    && !tally.examples.iter().any(|e| e == identifier)
+                {
+                    tally.examples.push(identifier.to_string());
+                }
+            }
+        }
+    }
+
+    let mut terms: Vec<GlossaryTerm> = tallies
+        .into_iter()
+        .filter(|((_, kind), tally)| match kind {
+            TermKind::Acronym => tally.uses >= 2,
+            TermKind::Word => tally.files.len() >= 2 && tally.uses >= 3,
+        })
+        .map(|((term, kind), tally)| GlossaryTerm {
+            term,
+            kind,
+            uses: tally.uses,
+            files: tally.files.len(),
+            examples: tally.examples,
+        })
+        .collect();
+    terms.sort_by(|a, b| {
+        b.files
+            .cmp(&a.files)
+            .then(b.uses.cmp(&a.uses))
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    terms.truncate(max_terms);
+    terms
+}
+
+/// Render `terms` as an appendix in `format`; empty when there are none
+///
+/// Plus/Minus gets framing-style lines outside the file frames, so the
+/// decoder skips the appendix.
+pub fn render_glossary(terms: &[GlossaryTerm], format: OutputFormat) -> String {
+    if terms.is_empty() {
+        return String::new();
+    }
+    let uses = |t: &GlossaryTerm| {
+        format!(
+            "{} use{} in {} file{}",
+            t.uses,
+            if t.uses == 1 { "" } else { "s" },
+            t.files,
+            if t.files == 1 { "" } else { "s" }
+        )
+    };
+
+    let mut output = String::new();
+    match format {
+        OutputFormat::PlusMinus => {
+            output.push_str("========== GLOSSARY ==========\n");
+            for t in terms {
+                output.push_str(&format!("{} ({}, {})", t.term, t.kind.as_str(), uses(t)));
+                if !t.examples.is_empty() {
+                    output.push_str(&format!(": {}", t.examples.join(", ")));
+                }
+                output.push('\n');
+            }
+            output.push_str("========== END GLOSSARY ==========\n");
+        }
+        OutputFormat::Xml | OutputFormat::ClaudeXml => {
+            output.push_str("<glossary>\n");
+            for t in terms {
+                output.push_str(&format!(
+                    "  <term name=\"{}\" kind=\"{}\" uses=\"{}\" files=\"{}\">{}</term>\n",
+                    escape_xml_attr(&t.term),
+                    t.kind.as_str(),
+                    t.uses,
+                    t.files,
+                    escape_xml(&t.examples.join(", "))
+                ));
+            }
+            output.push_str("</glossary>\n");
+        }
+        OutputFormat::Markdown | OutputFormat::Gemini => {
+            output.push_str("## Glossary\n\n");
+            for t in terms {
+                output.push_str(&format!(
+                    "- **{}** ({}, {})",
+                    t.term,
+                    t.kind.as_str(),
+                    uses(t)
+                ));
+                if !t.examples.is_empty() {
+                    let examples: Vec<String> =
+                        t.examples.iter().map(|e| format!("`{}`", e)).collect();
+                    output.push_str(&format!(": {}", examples.join(", ")));
+                }
+                output.push('\n');
+            }
+            output.push('\n');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<FileEntry> {
+        vec![
+            FileEntry::new(
+                "src/ledger.rs",
+                "// Posts a PLM journal to the ledger\n\
+                 const MAX_RETRIES: u32 = 3;\n\
+                 fn post_journal(ledger: &mut Ledger, plm: PLMClient) -> Result<(), String> {\n\
+                     ledger.journal_entries.push(plm.fetch()?);\n\
+                 }\n",
+            ),
+            FileEntry::new(
+                "src/reconcile.rs",
+                "/// Reconciles a ledger journal against the PLM feed over HTTP\n\
+                 fn reconcile(ledger: &Ledger, http: HttpClient) {}\n",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_identifier_parts() {
+        assert_eq!(
+            identifier_parts("parse_PLMClient2"),
+            vec!["parse", "PLM", "Client"]
+        );
+        assert_eq!(
+            identifier_parts("journalEntries"),
+            vec!["journal", "Entries"]
+        );
+        assert_eq!(identifier_parts("MAX_RETRIES"), vec!["MAX", "RETRIES"]);
+    }
+
+    #[test]
+    fn test_extract_glossary() {
+        let terms = extract_glossary(&entries(), MAX_TERMS);
+        let names: Vec<&str> = terms.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(names, vec!["ledger", "journal", "PLM"]);
+
+        assert_eq!(terms[0].kind, TermKind::Word);
+        assert_eq!((terms[0].uses, terms[0].files), (7, 2));
+        assert!(terms[0].examples.is_empty());
+        assert_eq!(terms[1].examples, vec!["post_journal", "journal_entries"]);
+        assert_eq!(terms[2].kind, TermKind::Acronym);
+        assert_eq!((terms[2].uses, terms[2].files), (3, 2));
+        assert_eq!(terms[2].examples, vec!["PLMClient"]);
+        // Single-file words, common acronyms and constants are left out
+        assert!(!names.contains(&"reconcile"));
+        assert!(!names.contains(&"HTTP"));
+        assert!(!names.contains(&"RETRIES"));
+
+        assert_eq!(extract_glossary(&entries(), 1).len(), 1);
+    }
+
+    #[test]
+    fn test_render_glossary() {
+        let terms = extract_glossary(&entries(), MAX_TERMS);
+        assert_eq!(
+            render_glossary(&terms, OutputFormat::PlusMinus),
+            "========== GLOSSARY ==========\n\
+             ledger (term, 7 uses in 2 files)\n\
+             journal (term, 4 uses in 2 files): post_journal, journal_entries\n\
+             PLM (acronym, 3 uses in 2 files): PLMClient\n\
+             ========== END GLOSSARY ==========\n"
+        );
+        assert!(render_glossary(&terms, OutputFormat::Xml).contains(
+            "<term name=\"PLM\" kind=\"acronym\" uses=\"3\" files=\"2\">PLMClient</term>"
+        ));
+        assert!(render_glossary(&terms, OutputFormat::Markdown)
+            .starts_with("## Glossary\n\n- **ledger** (term, 7 uses in 2 files)\n"));
+        assert_eq!(render_glossary(&[], OutputFormat::Markdown), "");
+    }
+}
@@ -416,6 +416,306 @@ fn generate_instruction_content(
     content
 }
 
+/// File `--init-llm-readme` writes in the project root
+pub const LLM_README_FILE: &str = "README.llm.md";
+
+/// Manifests named under "Layout", with what each marks
+const LLM_README_MANIFESTS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust"),
+    ("package.json", "Node.js"),
+    ("pyproject.toml", "Python"),
+    ("setup.py", "Python"),
+    ("requirements.txt", "Python"),
+    ("go.mod", "Go"),
+    ("Gemfile", "Ruby"),
+    ("composer.json", "PHP"),
+    ("pom.xml", "Java (Maven)"),
+    ("build.gradle", "Java (Gradle)"),
+    ("Makefile", "Make"),
+];
+
+/// Docs whose opening paragraph goes under "Conventions"
+const LLM_README_CONVENTION_DOCS: &[&str] = &[
+    "CONTRIBUTING.md",
+    "AGENTS.md",
+    "CLAUDE.md",
+    "ARCHITECTURE.md",
+    "CONVENTIONS.md",
+    "STYLE.md",
+    "docs/CONTRIBUTING.md",
+    "docs/ARCHITECTURE.md",
+];
+
+/// Tool configuration named under "Conventions"
+const LLM_README_TOOL_CONFIGS: &[&str] = &[
+    ".editorconfig",
+    "rustfmt.toml",
+    ".rustfmt.toml",
+    "clippy.toml",
+    ".prettierrc",
+    ".eslintrc.json",
+    ".eslintrc.js",
+    "ruff.toml",
+    ".pre-commit-config.yaml",
+];
+
+/// Longest opening paragraph quoted from a doc, in bytes
+const MAX_PARAGRAPH_BYTES: usize = 600;
+
+/// Entry points listed at most
+const MAX_ENTRY_POINTS: usize = 15;
+
+/// Languages listed at most
+const MAX_LANGUAGES: usize = 8;
+
+/// The opening paragraph of a Markdown document: the first run of prose
+/// lines, past headings, badges and HTML
+fn opening_paragraph(markdown: &str) -> Option<String> {
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if paragraph.is_empty() {
+                continue;
+            }
+            break;
+        }
+        let decoration = trimmed.starts_with('#')
+            || trimmed.starts_with("![")
+            || trimmed.starts_with("[![")
+            || trimmed.starts_with('<')
+            || trimmed.starts_with("---")
+            || trimmed.starts_with("```");
+        if decoration {
+            if paragraph.is_empty() {
+                continue;
+            }
+            break;
+        }
+        paragraph.push(trimmed);
+    }
+    if paragraph.is_empty() {
+        return None;
+    }
+    let mut text = paragraph.join(" ");
+    if text.len() > MAX_PARAGRAPH_BYTES {
+        let mut end = MAX_PARAGRAPH_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("...");
+    }
+    Some(text)
+}
+
+/// `npm run` commands for the scripts in `package.json`
+fn npm_script_commands(root: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+    manifest
+        .get("scripts")
+        .and_then(|scripts| scripts.as_object())
+        .map(|scripts| {
+            scripts
+                .keys()
+                .map(|name| format!("npm run {}", name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generate the content of `README.llm.md`
+///
+/// Drawn from what the project already says about itself: the opening of
+/// its README, its manifests, a census of its languages, the entry points
+/// the analyzers find, the commands its build files imply and the opening
+/// of its contributor docs.
+pub fn generate_llm_readme(root: &str) -> Result<String, String> {
+    use crate::{walk_project, EncoderConfig};
+    use std::collections::HashMap;
+
+    let root_path = Path::new(root);
+    if !root_path.exists() {
+        return Err(format!("Directory not found: {}", root));
+    }
+    let canonical_path = root_path
+        .canonicalize()
+        .unwrap_or_else(|_| root_path.to_path_buf());
+    let project_name = canonical_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("project");
+
+    let mut config = EncoderConfig::default();
+    config.ignore_patterns.push("node_modules".to_string());
+    config.ignore_patterns.push(LLM_README_FILE.to_string());
+    let entries = walk_project(root, &config)?;
+
+    let mut content = String::new();
+    content.push_str(&format!(
+        "# {}: notes for language models\n\n",
+        project_name
+    ));
+    content.push_str(&format!(
+        "*Generated by `vo --init-llm-readme`. Edit freely and commit: `{}` pins this file, so every bundle carries it.*\n\n",
+        crate::attributes::ATTRIBUTES_FILE_NAME
+    ));
+    if let Some(overview) = fs::read_to_string(root_path.join("README.md"))
+        .ok()
+        .and_then(|readme| opening_paragraph(&readme))
+    {
+        content.push_str(&overview);
+        content.push_str("\n\n");
+    }
+
+    // Layout: manifests, languages and the top of the tree
+    content.push_str("## Layout\n\n");
+    for (manifest, kind) in LLM_README_MANIFESTS {
+        if root_path.join(manifest).exists() {
+            content.push_str(&format!("- `{}`: {}\n", manifest, kind));
+        }
+    }
+    let mut languages: HashMap<&str, (usize, usize)> = HashMap::new();
+    for entry in &entries {
+        let ext = Path::new(&entry.path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        if let Some(signature) = crate::core::STELLAR_LIBRARY.get_by_extension(ext) {
+            let (_, code, _, _) = signature.count_lines(&entry.content);
+            let counts = languages.entry(signature.display_name).or_default();
+            counts.0 += 1;
+            counts.1 += code;
+        }
+    }
+    let mut languages: Vec<_> = languages.into_iter().collect();
+    languages.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then_with(|| a.0.cmp(b.0)));
+    for (language, (files, code)) in languages.iter().take(MAX_LANGUAGES) {
+        content.push_str(&format!(
+            "- {}: {} files, {} lines of code\n",
+            language,
+            files,
+            format_with_commas(*code)
+        ));
+    }
+    let tree_ignore: Vec<String> = config
+        .ignore_patterns
+        .iter()
+        .cloned()
+        .chain(["CONTEXT.txt".to_string()])
+        .collect();
+    content.push_str("\n```\n");
+    content.push_str(&format!("{}/\n", project_name));
+    for line in generate_directory_tree(root, &tree_ignore, 2) {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content.push_str("```\n\n");
+
+    // Entry points the analyzers recognize
+    let entry_points: Vec<String> = entries
+        .iter()
+        .filter_map(|entry| {
+            let analysis = crate::analyzers::analyze_within_budget(&entry.path, &entry.content)?;
+            (!analysis.entry_points.is_empty())
+                .then(|| format!("- `{}` ({})", entry.path, analysis.entry_points.join(", ")))
+        })
+        .take(MAX_ENTRY_POINTS)
+        .collect();
+    if !entry_points.is_empty() {
+        content.push_str("## Entry points\n\n");
+        for line in entry_points {
+            content.push_str(&line);
+            content.push('\n');
+        }
+        content.push('\n');
+    }
+
+    // Commands
+    let mut commands = detect_project_commands(root);
+    commands.extend(npm_script_commands(root_path));
+    commands.dedup();
+    if !commands.is_empty() {
+        content.push_str("## Key commands\n\n");
+        for cmd in &commands {
+            content.push_str(&format!("- `{}`\n", cmd));
+        }
+        content.push('\n');
+    }
+
+    // Conventions, from the docs and tool configuration already there
+    let mut conventions = Vec::new();
+    for doc in LLM_README_CONVENTION_DOCS {
+        if let Ok(text) = fs::read_to_string(root_path.join(doc)) {
+            match opening_paragraph(&text) {
+                Some(paragraph) => conventions.push(format!("- `{}`: {}", doc, paragraph)),
+                None => conventions.push(format!("- `{}`", doc)),
+            }
+        }
+    }
+    let tools: Vec<String> = LLM_README_TOOL_CONFIGS
+        .iter()
+        .filter(|name| root_path.join(name).exists())
+        .map(|name| format!("`{}`", name))
+        .collect();
+    if !tools.is_empty() {
+        conventions.push(format!("- Tooling configured in {}", tools.join(", ")));
+    }
+    if !conventions.is_empty() {
+        content.push_str("## Conventions\n\n");
+        for line in conventions {
+            content.push_str(&line);
+            content.push('\n');
+        }
+        content.push('\n');
+    }
+
+    content.push_str(&format!("*Generated by pm_encoder v{}*\n", crate::VERSION));
+    Ok(content)
+}
+
+/// Write `README.llm.md` in the project root and pin it in the root
+/// `.pmattributes`
+///
+/// Returns the path written and, when the pin was added, the attributes
+/// file's path.
+pub fn init_llm_readme(root: &str) -> Result<(String, Option<String>), String> {
+    let content = generate_llm_readme(root)?;
+    let root_path = Path::new(root);
+    let readme_path = root_path.join(LLM_README_FILE);
+    fs::write(&readme_path, content)
+        .map_err(|e| format!("Failed to write {}: {}", LLM_README_FILE, e))?;
+
+    let attributes_path = root_path.join(crate::attributes::ATTRIBUTES_FILE_NAME);
+    let mut attributes = fs::read_to_string(&attributes_path).unwrap_or_default();
+    let pinned = attributes
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(LLM_README_FILE));
+    let pin_path = if pinned {
+        None
+    } else {
+        if !attributes.is_empty() && !attributes.ends_with('\n') {
+            attributes.push('\n');
+        }
+        attributes.push_str(&format!("{} pin\n", LLM_README_FILE));
+        fs::write(&attributes_path, attributes).map_err(|e| {
+            format!(
+                "Failed to write {}: {}",
+                crate::attributes::ATTRIBUTES_FILE_NAME,
+                e
+            )
+        })?;
+        Some(attributes_path.to_string_lossy().to_string())
+    };
+
+    Ok((readme_path.to_string_lossy().to_string(), pin_path))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -898,4 +1198,55 @@ mod tests {
             "Nonexistent directory should produce empty tree"
         );
     }
+
+    #[test]
+    fn test_opening_paragraph() {
+        let readme = "# Shop\n\n[![CI](https://ci/badge.svg)](https://ci)\n\nA small shop\nwith carts.\n\nMore text.\n";
+        assert_eq!(
+            opening_paragraph(readme).as_deref(),
+            Some("A small shop with carts.")
+        );
+        assert_eq!(opening_paragraph("# Only a heading\n"), None);
+        let long = "x".repeat(MAX_PARAGRAPH_BYTES + 10);
+        assert!(opening_paragraph(&long).unwrap().ends_with("..."));
+    }
+
+    #[test]
+    fn test_init_llm_readme() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::write(root.join("README.md"), "# shop\n\nSells things.\n").unwrap();
+        fs::write(
+            root.join("CONTRIBUTING.md"),
+            "# Contributing\n\nErrors are Strings.\n",
+        )
+        .unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"shop\"\n").unwrap();
+        fs::write(root.join("rustfmt.toml"), "").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn sell() {}\n").unwrap();
+        fs::write(root.join("src/bin/shop.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join(".pmattributes"), "vendor/ linguist-vendored").unwrap();
+
+        let (readme_path, pin_path) = init_llm_readme(root.to_str().unwrap()).unwrap();
+        let readme = fs::read_to_string(&readme_path).unwrap();
+        assert!(readme.contains("\nSells things.\n"));
+        assert!(readme.contains("- `Cargo.toml`: Rust\n"));
+        assert!(readme.contains("- Rust: 2 files, 2 lines of code\n"));
+        assert!(readme.contains("- `src/bin/shop.rs` (fn main)\n"));
+        assert!(readme.contains("- `cargo build`\n"));
+        assert!(readme.contains("- `CONTRIBUTING.md`: Errors are Strings.\n"));
+        assert!(readme.contains("- Tooling configured in `rustfmt.toml`\n"));
+        assert_eq!(
+            fs::read_to_string(pin_path.unwrap()).unwrap(),
+            "vendor/ linguist-vendored\nREADME.llm.md pin\n"
+        );
+
+        // Regenerating leaves the pin alone and doesn't describe itself
+        let (_, pin_path) = init_llm_readme(root.to_str().unwrap()).unwrap();
+        assert_eq!(pin_path, None);
+        let readme = fs::read_to_string(&readme_path).unwrap();
+        assert!(!readme.contains("README.llm.md\n"));
+        assert!(readme.contains("- Rust: 2 files"));
+    }
 }
@@ -73,7 +73,7 @@ pub struct LensConfig {
     #[serde(default)]
     pub include: Vec<String>,
 
-    /// Sort by: "name", "mtime", "ctime"
+    /// Sort by: "name", "mtime", "ctime", "size", "ext", "utility"
     #[serde(default)]
     pub sort_by: Option<String>,
 
@@ -58,25 +58,48 @@
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
-use globset::Glob;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
-use std::time::SystemTime;
-use walkdir::WalkDir;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use analyzers::lockfile::LockfilePolicy;
+use discovery::{
+    BinaryPolicy, ExcludedFile, ExclusionLedger, ExclusionReason, GeneratedPolicy, PatternStyle,
+    PatternSyntax,
+};
+use project_walk::{deciding_pattern, pattern_verdict};
 
 pub mod analyzers;
+pub mod attributes;
+pub mod blobs;
 pub mod budgeting;
+pub mod cache;
+pub mod concat;
+pub mod config_builder;
 pub mod core;
+pub mod decode;
+pub mod directory_config;
+pub mod discovery;
+pub mod doctor;
 pub mod formats;
+pub mod glossary;
 pub mod init;
 pub mod lenses;
+pub mod locate;
+pub mod migrate;
 pub mod plugins;
 pub mod server;
+pub mod timing;
+pub mod transform;
 
 pub use budgeting::{
-    apply_token_budget, parse_token_budget, BudgetReport, FileData, TokenEstimator,
+    apply_token_budget, apply_token_budget_for_format, apply_token_budget_with_pins,
+    parse_token_budget, BudgetReport, FileData, FormatOverhead, FramingCost, TokenEstimator,
 };
+pub use config_builder::ConfigBuilder;
 pub use formats::{escape_cdata, AttentionEntry, XmlConfig, XmlError, XmlWriter};
 pub use lenses::{AppliedLens, LensConfig, LensManager};
 
@@ -113,7 +136,10 @@ pub use core::{
 };
 
 /// A file entry with its content and metadata
-#[derive(Debug, Clone)]
+///
+/// The walker fills in everything; entries built by hand can start from
+/// [`FileEntry::new`].
+#[derive(Debug, Clone, Default)]
 pub struct FileEntry {
     /// Relative path to the file
     pub path: String,
@@ -125,8 +151,43 @@ pub struct FileEntry {
     pub mtime: u64,
     /// Creation time (seconds since epoch, falls back to mtime on some systems)
     pub ctime: u64,
-    /// File size in bytes
+    /// File size in bytes, before decoding
     pub size: u64,
+    /// Language detected from the extension (empty when unknown)
+    pub language: &'static str,
+    /// Whether any execute permission bit is set (always false off Unix)
+    pub executable: bool,
+    /// Where the file points, if it was reached through a symlink
+    pub symlink_target: Option<String>,
+}
+
+impl FileEntry {
+    /// An entry for `content` at `path`, with checksum, size and language
+    /// derived from them and no timestamps
+    pub fn new(path: impl Into<String>, content: impl Into<String>) -> Self {
+        let path = path.into();
+        let content = content.into();
+        Self {
+            md5: calculate_md5(&content),
+            size: content.len() as u64,
+            language: detect_language(&path),
+            path,
+            content,
+            ..Default::default()
+        }
+    }
+}
+
+/// A file the walker found but could not read
+///
+/// Binary, oversized and ignored files are deliberate omissions and are not
+/// reported; these are the ones a run silently lost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    /// Relative path (or the absolute path when it can't be made relative)
+    pub path: String,
+    /// Why the file was skipped
+    pub reason: String,
 }
 
 /// Configuration loaded from .pm_encoder_config.json
@@ -138,6 +199,61 @@ pub struct Config {
     /// Patterns to include (overrides ignore)
     #[serde(default)]
     pub include_patterns: Vec<String>,
+    /// Regexes; files whose content matches any of them are left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_content_patterns: Vec<String>,
+    /// How this file's patterns are matched: "v1" (default) or "v2" (gitignore rules)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern_syntax: Option<PatternSyntax>,
+    /// Match this file's patterns ignoring case (default: on Windows and macOS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_insensitive_patterns: Option<bool>,
+    /// Custom Plus/Minus header, footer and truncation marker templates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<formats::Templates>,
+    /// Maximum lines before truncation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncate_lines: Option<usize>,
+    /// Truncation mode: "simple", "tail", "smart", "structure", "outline",
+    /// "tokens" or "strip-comments"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncate_mode: Option<String>,
+    /// Remove comment lines before truncating
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip_comments: Option<bool>,
+    /// Regexes; matching lines survive truncation in any mode
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncate_keep_patterns: Vec<String>,
+    /// Escape control characters in file content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escape_control_chars: Option<bool>,
+    /// Add language, token-estimate and utility hints to file headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_hints: Option<bool>,
+    /// Don't walk more than this many directories below the root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    /// Take at most this many files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_files: Option<usize>,
+    /// Stop taking files past this many bytes in total
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_bytes: Option<u64>,
+    /// Cutoffs for the temporal survey's classifications
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temporal: Option<core::TemporalThresholds>,
+    /// Rules giving framework declarations namespaced kinds (see [`core::kinds`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub declaration_kinds: Vec<core::KindRule>,
+    /// What to do with generated and vendored files: keep, structure or skip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated: Option<GeneratedPolicy>,
+    /// What to do with binary files: skip, stub or base64
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<BinaryPolicy>,
+    /// What to do with lockfiles: summary or keep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockfiles: Option<LockfilePolicy>,
 }
 
 /// Output format for serialization
@@ -152,6 +268,8 @@ pub enum OutputFormat {
     Markdown,
     /// Claude-optimized XML with semantic headers and attention hints
     ClaudeXml,
+    /// Gemini long-context layout: file list preamble, fenced blocks with path attributes
+    Gemini,
 }
 
 impl OutputFormat {
@@ -162,36 +280,198 @@ impl OutputFormat {
             "xml" => Ok(Self::Xml),
             "markdown" | "md" => Ok(Self::Markdown),
             "claude-xml" | "claude_xml" | "claudexml" => Ok(Self::ClaudeXml),
+            "gemini" => Ok(Self::Gemini),
+            _ => Err(format!(
+                "Unknown format '{}'. Valid options: plus_minus, xml, markdown, claude-xml, gemini",
+                s
+            )),
+        }
+    }
+
+    /// Canonical name of the format
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PlusMinus => "plus_minus",
+            Self::Xml => "xml",
+            Self::Markdown => "markdown",
+            Self::ClaudeXml => "claude-xml",
+            Self::Gemini => "gemini",
+        }
+    }
+
+    /// File extension for output written in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::PlusMinus => "txt",
+            Self::Xml | Self::ClaudeXml => "xml",
+            Self::Markdown | Self::Gemini => "md",
+        }
+    }
+}
+
+impl Serialize for OutputFormat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Self::parse(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+/// How the walk treats symbolic links
+///
+/// Whichever links are followed, a directory link that leads back to a
+/// directory enclosing it is never entered, so link cycles can't keep the
+/// walk going forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Follow every link (matches the Python encoder)
+    #[default]
+    On,
+    /// Leave links out of the walk
+    Off,
+    /// Follow links that resolve inside the project root
+    Safe,
+}
+
+impl SymlinkPolicy {
+    /// Parse a policy name
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "on" | "true" => Ok(Self::On),
+            "off" | "false" => Ok(Self::Off),
+            "safe" => Ok(Self::Safe),
             _ => Err(format!(
-                "Unknown format '{}'. Valid options: plus_minus, xml, markdown, claude-xml",
+                "Unknown symlink policy '{}'. Valid options: on, off, safe",
                 s
             )),
         }
     }
+
+    /// Policy name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::On => "on",
+            Self::Off => "off",
+            Self::Safe => "safe",
+        }
+    }
+}
+
+/// The order `--stream` emits files in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamOrder {
+    /// As the walk finds them, so the first file goes out at once
+    #[default]
+    Walk,
+    /// Lens priority (blended with learned utility when loaded), then
+    /// path; the tree is listed, paths only, before any file is read
+    Priority,
+}
+
+impl StreamOrder {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Walk => "walk",
+            Self::Priority => "priority",
+        }
+    }
+}
+
+impl Serialize for SymlinkPolicy {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for SymlinkPolicy {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Configs written before the policy existed hold a bool
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Flag(bool),
+            Name(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Flag(true) => Ok(Self::On),
+            Repr::Flag(false) => Ok(Self::Off),
+            Repr::Name(name) => Self::parse(&name).map_err(serde::de::Error::custom),
+        }
+    }
 }
 
 pub use core::models::MetadataMode;
 pub use core::SkeletonMode;
 
+/// Files larger than this are skipped unless the config raises the limit
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
 /// Configuration for the encoder (expanded for CLI parity)
-#[derive(Debug, Clone)]
+///
+/// Build one with [`EncoderConfig::builder`]; new fields are added in minor
+/// releases. Serializes to JSON with every setting, and missing fields
+/// deserialize to their defaults. The discovered nested configs and the
+/// resolved changed-since filter are run state and aren't serialized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
 pub struct EncoderConfig {
     /// Patterns to ignore (e.g., ["*.pyc", ".git"])
     pub ignore_patterns: Vec<String>,
     /// Patterns to include (overrides ignore)
     pub include_patterns: Vec<String>,
-    /// Sort by: "name", "mtime", or "ctime"
+    /// Sort by: "name", "mtime", "ctime", "size", "ext" or "utility"
     pub sort_by: String,
     /// Sort order: "asc" or "desc"
     pub sort_order: String,
     /// Maximum lines before truncation (0 = no truncation)
     pub truncate_lines: usize,
-    /// Truncation mode: "simple", "smart", or "structure"
+    /// Truncation mode: "simple", "tail", "smart", "structure", "outline",
+    /// "tokens" or "strip-comments"
     pub truncate_mode: String,
-    /// Maximum file size in bytes (default: 5MB)
+    /// Remove comment lines ahead of whichever truncation mode applies
+    /// (see [`strip_comments`])
+    pub strip_comments: bool,
+    /// Regexes; lines matching any of them survive truncation and comment
+    /// stripping (see [`KeepPatterns`])
+    pub truncate_keep_patterns: Vec<String>,
+    /// Escape control characters in file content (see
+    /// [`escape_control_chars`]; default: true)
+    pub escape_control_chars: bool,
+    /// Maximum file size in bytes (default: [`DEFAULT_MAX_FILE_SIZE`], 5MB)
     pub max_file_size: u64,
+    /// Don't walk more than this many directories below a root
+    pub max_depth: Option<usize>,
+    /// Take at most this many files, in path order
+    pub max_total_files: Option<usize>,
+    /// Stop taking files once their sizes would add up to more than this
+    pub max_total_bytes: Option<u64>,
+    /// Cutoffs for supernovas, volcanic churn, tectonic shifts and ancient stars
+    pub temporal_thresholds: core::TemporalThresholds,
+    /// Rules giving framework declarations namespaced kinds (see [`core::kinds`])
+    pub declaration_kinds: Vec<core::KindRule>,
+    /// What to do with generated and vendored files (see [`discovery::generated`])
+    pub generated: GeneratedPolicy,
+    /// What to do with binary files (see [`discovery::binary`])
+    pub binary: BinaryPolicy,
+    /// What to do with lockfiles (see [`analyzers::lockfile`])
+    pub lockfiles: LockfilePolicy,
+    /// Regexes; files whose content matches any of them are left out
+    pub exclude_content_patterns: Vec<String>,
+    /// How the ignore and include patterns are matched (see [`discovery::pattern_syntax`])
+    pub pattern_syntax: PatternSyntax,
+    /// Match the ignore and include patterns ignoring case
+    pub case_insensitive_patterns: bool,
     /// Enable streaming mode (immediate output, no global sort)
     pub stream: bool,
+    /// The order streaming mode emits files in
+    pub stream_order: StreamOrder,
     /// Include summary markers in truncated output (default: true)
     pub truncate_summary: bool,
     /// Patterns of files to skip truncation for
@@ -202,6 +482,9 @@ pub struct EncoderConfig {
     pub output_format: OutputFormat,
     /// Frozen mode: bypass context store for deterministic output (v2.0.0)
     pub frozen: bool,
+    /// End the output with a glossary of the project's own terms (see
+    /// [`glossary`])
+    pub glossary: bool,
     /// Allow sensitive metadata in output (v2.0.0)
     pub allow_sensitive: bool,
     /// Active lens name for metadata injection (v2.0.0)
@@ -212,8 +495,58 @@ pub struct EncoderConfig {
     pub skeleton_mode: SkeletonMode,
     /// Metadata display mode: 'auto', 'all', 'none', 'size-only' (v2.3.0 Chronos)
     pub metadata_mode: MetadataMode,
-    /// Follow symbolic links (default: false, skip broken symlinks silently)
-    pub follow_symlinks: bool,
+    /// Which symbolic links the walk follows (broken links are skipped silently)
+    pub follow_symlinks: SymlinkPolicy,
+    /// Restrict output to exactly these relative paths (None = no restriction)
+    pub only_paths: Option<Vec<String>>,
+    /// Prefix each content line with its 1-based line number
+    pub line_numbers: bool,
+    /// Emit repeated contents once, and later copies as a stub naming the
+    /// first (see [`dedupe_entries`])
+    pub dedupe: bool,
+    /// Add language, token-estimate and utility-score hints to file headers
+    /// (off by default to keep byte parity with the Python encoder)
+    pub header_hints: bool,
+    /// Custom Plus/Minus delimiters from the config file's `templates` section
+    pub templates: Option<formats::Templates>,
+    /// Honour `.gitignore`, `.ignore` and git exclude files while walking
+    pub respect_gitignore: bool,
+    /// Nested `.pm_encoder_config.json` files (see [`Self::discover_directory_configs`])
+    #[serde(skip)]
+    pub directory_configs: Option<directory_config::DirectoryConfigs>,
+    /// `.pmattributes` files (see [`attributes`]), found with the nested configs
+    #[serde(skip)]
+    pub attributes: Option<attributes::PathAttributes>,
+    /// Learned utility scores for `sort_by = "utility"`; without a store
+    /// every file scores the neutral 0.5
+    #[serde(skip)]
+    pub utility_store: Option<core::ContextStore>,
+    /// Where to record per-file read, analysis and truncation times
+    #[serde(skip)]
+    pub timings: Option<Arc<timing::FileTimings>>,
+    /// Where walks record unreadable files instead of warning about them
+    #[serde(skip)]
+    pub skipped: Option<Arc<std::sync::Mutex<Vec<SkippedFile>>>>,
+    /// Processed files kept from earlier runs (see [`cache`])
+    #[serde(skip)]
+    pub cache: Option<Arc<cache::ProcessingCache>>,
+    /// List files with `git ls-files` instead of walking (falls back outside a repo)
+    pub git_only: bool,
+    /// Keep only files changed since a revision or time window
+    #[serde(skip)]
+    pub changed_since: Option<discovery::SinceFilter>,
+    /// Further roots walked after the main one, into the same context
+    pub extra_roots: Vec<std::path::PathBuf>,
+    /// Report paths under their root's directory name (see [`root_prefix`]);
+    /// required when there are `extra_roots`, unless `path_prefixes` are given
+    pub prefix_roots: bool,
+    /// Prefixes to report each root's paths under instead, one per root with
+    /// the main root first (e.g. `["repo-name/"]`; see [`normalize_prefix`])
+    pub path_prefixes: Vec<String>,
+    /// Transforms of this run, besides truncation and line numbers (see
+    /// [`Self::transforms_for`])
+    #[serde(skip)]
+    pub transforms: transform::TransformPipeline,
 }
 
 impl Default for EncoderConfig {
@@ -233,19 +566,53 @@ impl Default for EncoderConfig {
             sort_order: "asc".to_string(),
             truncate_lines: 0,
             truncate_mode: "simple".to_string(),
-            max_file_size: 5 * 1024 * 1024,         // 5MB
-            stream: false,          // Default to batch mode for backward compatibility
-            truncate_summary: true, // Include summary markers by default
+            strip_comments: false,
+            truncate_keep_patterns: vec![],
+            escape_control_chars: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE, // 5MB
+            max_depth: None,                      // Walk the whole tree
+            max_total_files: None,                // No file count limit
+            max_total_bytes: None,                // No total size limit
+            temporal_thresholds: core::TemporalThresholds::default(),
+            declaration_kinds: vec![],
+            generated: GeneratedPolicy::Keep, // Emit generated files like any other
+            binary: BinaryPolicy::Skip,       // Leave binary files out, like the Python encoder
+            lockfiles: LockfilePolicy::Summary, // Lockfiles as their dependency lists
+            exclude_content_patterns: vec![], // No content-based exclusion
+            pattern_syntax: PatternSyntax::V1, // Python-compatible pattern matching
+            case_insensitive_patterns: discovery::pattern_syntax::CASE_INSENSITIVE_FS,
+            stream: false, // Default to batch mode for backward compatibility
+            stream_order: StreamOrder::Walk,
+            truncate_summary: true,   // Include summary markers by default
             truncate_exclude: vec![], // No files excluded by default
-            truncate_stats: false,  // Don't show stats report by default
+            truncate_stats: false,    // Don't show stats report by default
             output_format: OutputFormat::PlusMinus, // Default to Plus/Minus format
-            frozen: false,          // Default to dynamic mode with context store
-            allow_sensitive: false, // Default to privacy-safe mode
-            active_lens: None,      // No lens by default
-            token_budget: None,     // No budget by default
-            skeleton_mode: SkeletonMode::Auto, // Auto-enable if budget is set
-            metadata_mode: MetadataMode::Auto, // Smart metadata display (v2.3.0)
-            follow_symlinks: false, // Skip broken symlinks silently by default
+            frozen: false,            // Default to dynamic mode with context store
+            glossary: false,
+            allow_sensitive: false,             // Default to privacy-safe mode
+            active_lens: None,                  // No lens by default
+            token_budget: None,                 // No budget by default
+            skeleton_mode: SkeletonMode::Auto,  // Auto-enable if budget is set
+            metadata_mode: MetadataMode::Auto,  // Smart metadata display (v2.3.0)
+            follow_symlinks: SymlinkPolicy::On, // Follow links, cutting cycles
+            only_paths: None,                   // No explicit path restriction
+            line_numbers: false,                // Content is emitted verbatim by default
+            dedupe: false,                      // Every copy in full
+            header_hints: false,                // Headers match the Python encoder by default
+            templates: None,                    // Built-in Plus/Minus delimiters
+            respect_gitignore: true,            // Skip what git would skip
+            directory_configs: None,            // Root config only until discovered
+            attributes: None,
+            utility_store: None,
+            timings: None,
+            skipped: None, // Unreadable files are warned about
+            cache: None,
+            git_only: false,                                 // Walk the filesystem
+            changed_since: None,                             // Every file, changed or not
+            extra_roots: vec![],                             // A single project root
+            prefix_roots: false,                             // Paths relative to the root
+            path_prefixes: vec![],                           // Root prefixes are directory names
+            transforms: transform::TransformPipeline::new(), // Built-in transforms only
         }
     }
 }
@@ -259,13 +626,304 @@ impl EncoderConfig {
         let config: Config = serde_json::from_str(&content)
             .map_err(|e| format!("Failed to parse config file: {}", e))?;
 
+        // Reject bad templates here rather than on first use
+        if let Some(ref templates) = config.templates {
+            formats::TemplateOutput::new(templates)?;
+        }
+
+        if let Some(ref thresholds) = config.temporal {
+            thresholds.validate()?;
+        }
+
+        discovery::ContentFilter::new(&config.exclude_content_patterns)?;
+        KeepPatterns::new(&config.truncate_keep_patterns)?;
+        core::KindRules::new(&config.declaration_kinds)?;
+
+        let defaults = Self::default();
         Ok(Self {
             ignore_patterns: config.ignore_patterns,
             include_patterns: config.include_patterns,
+            templates: config.templates,
+            truncate_lines: config.truncate_lines.unwrap_or(defaults.truncate_lines),
+            truncate_mode: config.truncate_mode.unwrap_or(defaults.truncate_mode),
+            strip_comments: config.strip_comments.unwrap_or(defaults.strip_comments),
+            truncate_keep_patterns: config.truncate_keep_patterns,
+            escape_control_chars: config
+                .escape_control_chars
+                .unwrap_or(defaults.escape_control_chars),
+            header_hints: config.header_hints.unwrap_or(defaults.header_hints),
+            max_depth: config.max_depth,
+            max_total_files: config.max_total_files,
+            max_total_bytes: config.max_total_bytes,
+            temporal_thresholds: config.temporal.unwrap_or_default(),
+            declaration_kinds: config.declaration_kinds,
+            generated: config.generated.unwrap_or_default(),
+            binary: config.binary.unwrap_or_default(),
+            lockfiles: config.lockfiles.unwrap_or_default(),
+            exclude_content_patterns: config.exclude_content_patterns,
+            pattern_syntax: config.pattern_syntax.unwrap_or_default(),
+            case_insensitive_patterns: config
+                .case_insensitive_patterns
+                .unwrap_or(defaults.case_insensitive_patterns),
             stream: false, // Streaming is only enabled via CLI flag
-            ..Default::default()
+            ..defaults
+        })
+    }
+
+    /// How the walk matches the ignore and include patterns
+    pub fn pattern_style(&self) -> PatternStyle {
+        PatternStyle {
+            syntax: self.pattern_syntax,
+            case_insensitive: self.case_insensitive_patterns,
+        }
+    }
+
+    /// Pick up nested `.pm_encoder_config.json` files below `root`, and
+    /// `.pmattributes` files anywhere in its tree
+    ///
+    /// Their ignore patterns and truncation settings then apply to their own
+    /// subtree, closest config winning; see [`directory_config`] and
+    /// [`attributes`].
+    ///
+    /// With [`Self::prefix_roots`] or [`Self::path_prefixes`] the configs of
+    /// every root are kept under their prefixes, and each extra root's own
+    /// config applies to its whole prefix.
+    pub fn discover_directory_configs(&mut self, root: &Path) -> Result<(), String> {
+        let (configs, attributes) = if self.prefixes_paths() {
+            let prefix = self.prefix_of_root(0, root);
+            let mut configs =
+                directory_config::DirectoryConfigs::discover_prefixed(root, &prefix, false)?;
+            let mut attributes = attributes::PathAttributes::discover_prefixed(root, &prefix)?;
+            for (i, extra) in self.extra_roots.iter().enumerate() {
+                let prefix = self.prefix_of_root(i + 1, extra);
+                configs.extend(directory_config::DirectoryConfigs::discover_prefixed(
+                    extra, &prefix, true,
+                )?);
+                attributes.extend(attributes::PathAttributes::discover_prefixed(
+                    extra, &prefix,
+                )?);
+            }
+            (configs, attributes)
+        } else {
+            (
+                directory_config::DirectoryConfigs::discover(root)?,
+                attributes::PathAttributes::discover(root)?,
+            )
+        };
+        self.directory_configs = (!configs.is_empty()).then_some(configs);
+        self.attributes = (!attributes.is_empty()).then_some(attributes);
+        Ok(())
+    }
+
+    /// The `.pmattributes` attributes of `path`
+    pub fn attributes_for(&self, path: &str) -> attributes::FileAttributes {
+        self.attributes
+            .as_ref()
+            .map(|a| a.for_path(path))
+            .unwrap_or_default()
+    }
+
+    /// Whether `path` is generated or vendored: per its `linguist-generated`
+    /// and `linguist-vendored` attributes, else [`discovery::detect_generated`]
+    pub fn generated_status(&self, path: &str, content: &str) -> Option<discovery::Generated> {
+        self.attributes_for(path)
+            .generated()
+            .unwrap_or_else(|| discovery::detect_generated(path, content))
+    }
+
+    /// Whether paths are reported under a prefix per root
+    fn prefixes_paths(&self) -> bool {
+        self.prefix_roots || !self.path_prefixes.is_empty()
+    }
+
+    /// The prefix of the `index`th root (the main one is 0): its entry in
+    /// [`Self::path_prefixes`], else its directory name
+    fn prefix_of_root(&self, index: usize, root: &Path) -> String {
+        match self.path_prefixes.get(index) {
+            Some(prefix) => prefix.trim_end_matches('/').to_string(),
+            None => root_prefix(root),
+        }
+    }
+
+    /// Every root to walk with its path prefix, the main `root` first
+    fn roots<'a>(&'a self, root: &'a str) -> Result<Vec<(&'a Path, Option<String>)>, String> {
+        let roots: Vec<&Path> = std::iter::once(Path::new(root))
+            .chain(self.extra_roots.iter().map(|r| r.as_path()))
+            .collect();
+        if !self.path_prefixes.is_empty() && self.path_prefixes.len() != roots.len() {
+            return Err(format!(
+                "{} path prefixes for {} project roots; give one per root (--prefix)",
+                self.path_prefixes.len(),
+                roots.len()
+            ));
+        }
+        if roots.len() > 1 && !self.prefixes_paths() {
+            return Err(
+                "Several project roots need prefix_roots (--prefix-roots) or path prefixes (--prefix)"
+                    .to_string(),
+            );
+        }
+        for root in &roots {
+            if !root.exists() {
+                return Err(format!("Directory not found: {}", root.display()));
+            }
+        }
+        Ok(roots
+            .into_iter()
+            .enumerate()
+            .map(|(i, root)| {
+                (
+                    root,
+                    self.prefixes_paths().then(|| self.prefix_of_root(i, root)),
+                )
+            })
+            .collect())
+    }
+
+    /// The nested configs for walking the root reported under `prefix`
+    fn directory_configs_within(
+        &self,
+        prefix: Option<&str>,
+    ) -> Option<directory_config::DirectoryConfigs> {
+        match prefix {
+            Some(prefix) => self.directory_configs.as_ref().map(|d| d.within(prefix)),
+            None => self.directory_configs.clone(),
+        }
+    }
+
+    /// Restrict the run to `paths`, within any restriction already set
+    pub fn restrict_to(&mut self, paths: Vec<String>) {
+        self.only_paths = Some(match self.only_paths.take() {
+            Some(only) => paths.into_iter().filter(|p| only.contains(p)).collect(),
+            None => paths,
+        });
+    }
+
+    /// Record an unreadable file in [`Self::skipped`], or warn about it
+    /// on stderr when nothing collects them
+    pub fn report_skipped(&self, file: SkippedFile) {
+        match &self.skipped {
+            Some(skipped) => skipped.lock().unwrap().push(file),
+            None => eprintln!("Warning: skipped {}: {}", file.path, file.reason),
+        }
+    }
+
+    /// Header hints for a prepared file, if [`Self::header_hints`] is set
+    ///
+    /// Utility scores come from [`Self::utility_store`] when one is loaded.
+    pub fn header_hints_for(&self, file: &formats::OutputFile) -> Option<formats::HeaderHints> {
+        if !self.header_hints {
+            return None;
+        }
+        Some(formats::HeaderHints {
+            language: detect_language(&file.path),
+            tokens: TokenEstimator::estimate_tokens(&file.content),
+            utility: self
+                .utility_store
+                .as_ref()
+                .and_then(|store| store.get_utility(&file.path))
+                .map(|u| u.score),
         })
     }
+
+    /// The content transforms for `path`
+    ///
+    /// Truncation with the settings for that path (see
+    /// [`Self::truncation_for`]), line numbers when enabled, and the run's
+    /// own [`Self::transforms`], ordered by [`transform::Stage`].
+    pub fn transforms_for(
+        &self,
+        path: &str,
+        include_summary: bool,
+    ) -> transform::TransformPipeline {
+        let (lines, mode) = self.truncation_for(path);
+        self.transforms_with(
+            path,
+            transform::Truncation::new(lines, mode, include_summary).keeping(self.keep_patterns()),
+        )
+    }
+
+    /// Compiled [`Self::truncate_keep_patterns`]; invalid ones are rejected
+    /// when the config is built, so they are dropped here
+    pub fn keep_patterns(&self) -> KeepPatterns {
+        KeepPatterns::new(&self.truncate_keep_patterns).unwrap_or_default()
+    }
+
+    /// Like [`Self::transforms_for`], with a different truncation
+    ///
+    /// A `redact` attribute adds [`transform::Redaction`],
+    /// `linguist-generated` decides what [`transform::GeneratedStructure`]
+    /// reduces, lockfiles get [`transform::LockfileSummary`], and scripts
+    /// and stylesheets [`transform::MinifiedStructure`].
+    pub(crate) fn transforms_with(
+        &self,
+        path: &str,
+        truncation: impl transform::ContentTransform + 'static,
+    ) -> transform::TransformPipeline {
+        let mut pipeline = self.transforms.clone();
+        if let Some(timings) = &self.timings {
+            pipeline = pipeline.timed(timings.clone());
+        }
+        if let Some(cache) = &self.cache {
+            pipeline = pipeline.cached(cache.clone());
+        }
+        let attributes = self.attributes_for(path);
+        if attributes.redacted() {
+            pipeline = pipeline.with(transform::Redaction);
+        }
+        if self.strip_comments {
+            pipeline = pipeline.with(transform::StripComments {
+                keep: self.keep_patterns(),
+            });
+        }
+        if self.lockfiles == LockfilePolicy::Summary && analyzers::lockfile::is_lockfile(path) {
+            pipeline = pipeline.with(transform::LockfileSummary {
+                include_summary: self.truncate_summary,
+            });
+        } else if self.generated == GeneratedPolicy::Structure {
+            match attributes.generated() {
+                Some(Some(_)) => pipeline = pipeline.with(transform::GeneratedStructure::marked()),
+                Some(None) => {}
+                None => pipeline = pipeline.with(transform::GeneratedStructure::default()),
+            }
+        }
+        if self.reduces_minified(path) {
+            pipeline = pipeline.with(transform::MinifiedStructure);
+        }
+        pipeline = pipeline.with(truncation);
+        if self.line_numbers {
+            pipeline = pipeline.with(transform::LineNumbers);
+        }
+        if self.escape_control_chars {
+            pipeline = pipeline.with(transform::EscapeControlChars);
+        }
+        pipeline
+    }
+
+    /// Whether minified content at `path` is reduced by
+    /// [`transform::MinifiedStructure`]: unless `tokens` mode, which cuts
+    /// inside lines, or a `-truncate` attribute has it in full
+    pub fn reduces_minified(&self, path: &str) -> bool {
+        discovery::minified::is_candidate(path)
+            && self.attributes_for(path).truncate_mode() != Some(None)
+            && self.truncation_for(path).1 != "tokens"
+    }
+
+    /// Truncation line limit and mode for `path`, after nested overrides
+    /// and its `truncate` attribute
+    pub fn truncation_for(&self, path: &str) -> (usize, &str) {
+        let (lines, mode) = self
+            .directory_configs
+            .as_ref()
+            .map_or((None, None), |d| d.truncation_for(path));
+        let lines = lines.unwrap_or(self.truncate_lines);
+        let mode = mode.unwrap_or(&self.truncate_mode);
+        match self.attributes_for(path).truncate_mode() {
+            Some(Some(mode)) => (lines, mode),
+            Some(None) => (0, "simple"),
+            None => (lines, mode),
+        }
+    }
 }
 
 // ============================================================================
@@ -328,10 +986,18 @@ impl ContextEngine {
         }
     }
 
+    /// Attach a context store whose utility scores appear in header hints
+    pub fn with_store(mut self, store: core::ContextStore) -> Self {
+        self.config.utility_store = Some(store);
+        self
+    }
+
     /// Create a new context engine with a specific lens applied
     pub fn with_lens(config: EncoderConfig, lens_name: &str) -> Result<Self, String> {
         let mut engine = Self::new(config);
         engine.lens_manager.apply_lens(lens_name)?;
+        // Claude-XML names the lens and ranks files by it
+        engine.config.active_lens = Some(lens_name.to_string());
         Ok(engine)
     }
 
@@ -352,204 +1018,27 @@ impl ContextEngine {
         let original_lines = count_lines_python_style(content);
         let md5 = calculate_md5(content);
 
-        // Apply truncation if configured
-        let (processed_content, was_truncated) =
-            if self.config.truncate_lines > 0 || self.config.truncate_mode == "structure" {
-                match self.config.truncate_mode.as_str() {
-                    "simple" => truncate_simple_with_options(
-                        content,
-                        self.config.truncate_lines,
-                        path,
-                        self.config.truncate_summary,
-                    ),
-                    "smart" => truncate_smart_with_options(
-                        content,
-                        self.config.truncate_lines,
-                        path,
-                        self.config.truncate_summary,
-                    ),
-                    "structure" => truncate_structure_with_fallback(
-                        content,
-                        path,
-                        self.config.truncate_summary,
-                        self.config.truncate_lines,
-                    ),
-                    _ => (content.to_string(), false),
-                }
-            } else {
-                (content.to_string(), false)
-            };
+        // Truncation (nested configs may override it per path), line numbers
+        // and the run's own transforms
+        let processed = self
+            .config
+            .transforms_for(path, self.config.truncate_summary)
+            .run(path, content);
 
         ProcessedFile {
             path: path.to_string(),
-            content: processed_content,
+            content: processed.text,
             md5,
-            was_truncated,
+            was_truncated: processed.was_truncated,
             original_lines,
             mtime: 0, // Set by caller if needed
             ctime: 0, // Set by caller if needed
         }
     }
 
-    /// Serialize a single processed file to Plus/Minus format (PURE - no I/O)
+    /// Serialize a single processed file's block (PURE - no I/O)
     pub fn serialize_processed_file(&self, file: &ProcessedFile) -> String {
-        match self.config.output_format {
-            OutputFormat::PlusMinus => self.serialize_plus_minus(file),
-            OutputFormat::Xml => self.serialize_xml(file),
-            OutputFormat::Markdown => self.serialize_markdown(file),
-            OutputFormat::ClaudeXml => self.serialize_claude_xml(file),
-        }
-    }
-
-    /// Serialize file to Plus/Minus format
-    fn serialize_plus_minus(&self, file: &ProcessedFile) -> String {
-        let mut output = String::new();
-
-        // Header: ++++++++++ filename [TRUNCATED: N lines] ++++++++++
-        if file.was_truncated {
-            output.push_str(&format!(
-                "++++++++++ {} [TRUNCATED: {} lines] ++++++++++\n",
-                file.path, file.original_lines
-            ));
-        } else {
-            output.push_str(&format!("++++++++++ {} ++++++++++\n", file.path));
-        }
-
-        // Content
-        output.push_str(&file.content);
-
-        // Ensure content ends with newline
-        if !file.content.ends_with('\n') {
-            output.push('\n');
-        }
-
-        // Calculate final line count for footer
-        let final_lines = count_lines_python_style(&file.content);
-
-        // Footer: ---------- filename [TRUNCATED:orig→final] md5 filename ----------
-        if file.was_truncated {
-            output.push_str(&format!(
-                "---------- {} [TRUNCATED:{}→{}] {} {} ----------\n",
-                file.path, file.original_lines, final_lines, file.md5, file.path
-            ));
-        } else {
-            output.push_str(&format!(
-                "---------- {} {} {} ----------\n",
-                file.path, file.md5, file.path
-            ));
-        }
-
-        output
-    }
-
-    /// Serialize file to XML format
-    fn serialize_xml(&self, file: &ProcessedFile) -> String {
-        let mut output = String::new();
-
-        // Escape XML special characters in content
-        let escaped_content = escape_xml(&file.content);
-
-        if file.was_truncated {
-            let final_lines = count_lines_python_style(&file.content);
-            output.push_str(&format!(
-                "<file path=\"{}\" md5=\"{}\" truncated=\"true\" original_lines=\"{}\" final_lines=\"{}\">\n",
-                escape_xml_attr(&file.path), file.md5, file.original_lines, final_lines
-            ));
-        } else {
-            output.push_str(&format!(
-                "<file path=\"{}\" md5=\"{}\">\n",
-                escape_xml_attr(&file.path),
-                file.md5
-            ));
-        }
-
-        output.push_str(&escaped_content);
-
-        if !escaped_content.ends_with('\n') {
-            output.push('\n');
-        }
-
-        output.push_str("</file>\n");
-        output
-    }
-
-    /// Serialize file to Markdown format
-    fn serialize_markdown(&self, file: &ProcessedFile) -> String {
-        let mut output = String::new();
-
-        // Detect language from file extension for code block
-        let lang = detect_language(&file.path);
-
-        // Header
-        if file.was_truncated {
-            let final_lines = count_lines_python_style(&file.content);
-            output.push_str(&format!(
-                "### {} [TRUNCATED: {} → {} lines]\n\n",
-                file.path, file.original_lines, final_lines
-            ));
-        } else {
-            output.push_str(&format!("### {}\n\n", file.path));
-        }
-
-        // Code block
-        output.push_str(&format!("```{}\n", lang));
-        output.push_str(&file.content);
-
-        if !file.content.ends_with('\n') {
-            output.push('\n');
-        }
-
-        output.push_str("```\n\n");
-
-        // Footer with checksum
-        output.push_str(&format!("*MD5: {}*\n\n", file.md5));
-
-        output
-    }
-
-    /// Serialize file to Claude-optimized XML format
-    ///
-    /// Claude-optimized XML uses CDATA sections for code content to avoid escaping,
-    /// includes language hints, and provides semantic attributes for better understanding.
-    fn serialize_claude_xml(&self, file: &ProcessedFile) -> String {
-        let mut output = String::new();
-        let lang = detect_language(&file.path);
-        let priority = self.lens_manager.get_file_priority(Path::new(&file.path));
-
-        // Opening tag with semantic attributes
-        output.push_str("<file\n");
-        output.push_str(&format!("  path=\"{}\"\n", escape_xml_attr(&file.path)));
-        output.push_str(&format!("  language=\"{}\"\n", lang));
-        output.push_str(&format!("  md5=\"{}\"\n", file.md5));
-        output.push_str(&format!("  priority=\"{}\"\n", priority));
-
-        if file.was_truncated {
-            let final_lines = count_lines_python_style(&file.content);
-            output.push_str("  truncated=\"true\"\n");
-            output.push_str(&format!("  original_lines=\"{}\"\n", file.original_lines));
-            output.push_str(&format!("  final_lines=\"{}\"", final_lines));
-        }
-
-        output.push_str(">\n");
-
-        // Use CDATA to avoid escaping code content
-        // Handle content that might contain "]]>" by splitting CDATA sections
-        let content = if file.content.contains("]]>") {
-            // Split "]]>" into "]]" + ">" across CDATA boundaries
-            file.content.replace("]]>", "]]]]><![CDATA[>")
-        } else {
-            file.content.clone()
-        };
-
-        output.push_str("<![CDATA[\n");
-        output.push_str(&content);
-        if !content.ends_with('\n') {
-            output.push('\n');
-        }
-        output.push_str("]]>\n");
-        output.push_str("</file>\n");
-
-        output
+        self.render(std::slice::from_ref(file), false)
     }
 
     /// Serialize multiple processed files (PURE - no I/O)
@@ -557,96 +1046,58 @@ impl ContextEngine {
     /// Files are serialized in the order provided. Sorting should be done
     /// by the caller before passing to this function.
     pub fn serialize_processed_files(&self, files: &[ProcessedFile]) -> String {
-        let mut output = String::new();
+        self.render(files, true)
+    }
+
+    /// Write `files` with the config's [`formats::formatter_for`], with the
+    /// bundle's header and footer when `whole_bundle` is set
+    fn render(&self, files: &[ProcessedFile], whole_bundle: bool) -> String {
+        let files: Vec<formats::OutputFile> = files.iter().map(|f| self.output_file(f)).collect();
+        // Templates are checked when a config file is loaded; a bad one built
+        // in code falls back to the plain format
+        let mut formatter = formats::formatter_for(&self.config).unwrap_or_else(|_| {
+            Box::new(formats::WriterFormatter::new(
+                &self.config,
+                Box::new(formats::writer_for(self.config.output_format)),
+            ))
+        });
 
-        // For ClaudeXml format, wrap with context root and metadata
-        if self.config.output_format == OutputFormat::ClaudeXml {
-            output.push_str(&self.generate_claude_xml_header(files));
+        let mut output = Vec::new();
+        if whole_bundle {
+            let _ = formatter.begin(&mut output, Some(&files));
         }
-
-        for file in files {
-            output.push_str(&self.serialize_processed_file(file));
+        for file in &files {
+            let _ = formatter.file(&mut output, file);
         }
-
-        // Close ClaudeXml wrapper
-        if self.config.output_format == OutputFormat::ClaudeXml {
-            output.push_str("  </files>\n</context>\n");
+        if whole_bundle {
+            let _ = formatter.end(&mut output, Some(&files));
         }
-
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    /// A processed file as the formatters take it (its transforms already ran)
+    fn output_file(&self, file: &ProcessedFile) -> formats::OutputFile {
+        let mut output = formats::OutputFile {
+            path: file.path.clone(),
+            content: file.content.clone(),
+            md5: file.md5.clone(),
+            size: file.content.len() as u64,
+            mtime: file.mtime,
+            language: detect_language(&file.path),
+            executable: false,
+            symlink_target: None,
+            was_truncated: file.was_truncated,
+            original_lines: file.original_lines,
+            final_lines: count_lines_python_style(&file.content),
+            merge_conflicts: count_merge_conflicts(&file.content),
+            // A processed file has lost its original size, so no metadata
+            metadata_mode: MetadataMode::None,
+            hints: None,
+        };
+        output.hints = self.config.header_hints_for(&output);
         output
     }
 
-    /// Generate Claude-XML header with metadata
-    fn generate_claude_xml_header(&self, files: &[ProcessedFile]) -> String {
-        let mut header = String::new();
-
-        // Context root with attributes
-        header.push_str("<context\n");
-        header.push_str("  package=\"pm_encoder\"\n");
-
-        if let Some(ref lens) = self.config.active_lens {
-            header.push_str(&format!("  lens=\"{}\"\n", lens));
-        }
-
-        if let Some(budget) = self.config.token_budget {
-            let utilized: usize = files
-                .iter()
-                .map(|f| f.content.len() / 4) // Rough token estimate
-                .sum();
-            header.push_str(&format!("  token_budget=\"{}\"\n", budget));
-            header.push_str(&format!("  utilized=\"{}\"\n", utilized));
-        }
-
-        header.push_str(">\n");
-
-        // Metadata section
-        header.push_str("  <metadata>\n");
-        header.push_str(&format!("    <version>{}</version>\n", VERSION));
-        header.push_str(&format!("    <frozen>{}</frozen>\n", self.config.frozen));
-
-        if !self.config.frozen {
-            // Only include timestamp in non-frozen mode
-            header.push_str(&format!(
-                "    <timestamp>{}</timestamp>\n",
-                chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
-            ));
-        }
-
-        if let Some(ref lens) = self.config.active_lens {
-            header.push_str("    <lens_config>\n");
-            header.push_str(&format!("      <name>{}</name>\n", lens));
-            header.push_str("    </lens_config>\n");
-        }
-
-        // Attention map with file priorities
-        if !files.is_empty() {
-            header.push_str("    <attention_map>\n");
-            for file in files.iter().take(5) {
-                let priority = self.lens_manager.get_file_priority(Path::new(&file.path));
-                let tokens = file.content.len() / 4;
-                if file.was_truncated {
-                    header.push_str(&format!(
-                        "      <hotspot path=\"{}\" priority=\"{}\" tokens=\"{}\" truncated=\"true\" />\n",
-                        escape_xml_attr(&file.path), priority, tokens
-                    ));
-                } else {
-                    header.push_str(&format!(
-                        "      <hotspot path=\"{}\" priority=\"{}\" tokens=\"{}\" />\n",
-                        escape_xml_attr(&file.path),
-                        priority,
-                        tokens
-                    ));
-                }
-            }
-            header.push_str("    </attention_map>\n");
-        }
-
-        header.push_str("  </metadata>\n\n");
-        header.push_str("  <files>\n");
-
-        header
-    }
-
     /// Generate complete context from path-content pairs (PURE - no I/O)
     ///
     /// This is the main entry point for WASM usage. It takes a list of
@@ -782,6 +1233,61 @@ pub fn is_binary(content: &[u8]) -> bool {
     content[..check_len].contains(&0)
 }
 
+/// Decode UTF-16 text, without its byte order mark
+///
+/// Recognised by a byte order mark or, without one, by mostly-ASCII text
+/// having a zero in every other byte (as Windows tools write it). `None`
+/// when `bytes` doesn't look like UTF-16: an odd length, or text with NULs.
+pub fn decode_utf16(bytes: &[u8]) -> Option<String> {
+    let (big_endian, body) = match bytes {
+        [0xFF, 0xFE, rest @ ..] => (false, rest),
+        [0xFE, 0xFF, rest @ ..] => (true, rest),
+        _ => (utf16_byte_order(bytes)?, bytes),
+    };
+    if !body.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = body.chunks_exact(2).map(|pair| {
+        let pair = [pair[0], pair[1]];
+        if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    });
+    let text: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    (!text.contains('\0')).then_some(text)
+}
+
+/// Byte order of UTF-16 without a byte order mark (`true` for big-endian),
+/// judged from the first 8KB
+fn utf16_byte_order(bytes: &[u8]) -> Option<bool> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(8192)];
+    let units = sample.len() / 2;
+    let zeros = |offset: usize| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    // ASCII in UTF-16 has a zero high byte and a non-zero low byte
+    if even == 0 && odd * 10 >= units * 9 {
+        Some(false)
+    } else if odd == 0 && even * 10 >= units * 9 {
+        Some(true)
+    } else {
+        None
+    }
+}
+
 /// Check if file size exceeds the limit
 ///
 /// # Arguments
@@ -796,13 +1302,39 @@ pub fn is_too_large(size: u64, limit: u64) -> bool {
     size > limit
 }
 
+/// Whether `metadata` has any execute permission bit set
+///
+/// Always `false` on platforms without Unix permissions.
+pub fn is_executable(metadata: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// The target of `path` if it is a symlink, as written in the link
+pub fn symlink_target(path: &Path) -> Option<String> {
+    fs::symlink_metadata(path)
+        .ok()
+        .filter(|m| m.file_type().is_symlink())
+        .and_then(|_| fs::read_link(path).ok())
+        .map(|target| target.to_string_lossy().into_owned())
+}
+
 /// Read file content with binary detection and encoding fallback
 ///
-/// Matches Python's behavior:
+/// Matches Python's behavior, plus UTF-16:
 /// 1. Read file as bytes
-/// 2. Check for binary (null bytes) - return None if binary
-/// 3. Try UTF-8 decoding
-/// 4. Fallback to Latin-1 (ISO-8859-1) if UTF-8 fails
+/// 2. Decode UTF-16 (see [`decode_utf16`]), whose null bytes would read as binary
+/// 3. Check for binary (null bytes) - return None if binary
+/// 4. Try UTF-8 decoding (a UTF-8 byte order mark is kept, as Python keeps it)
+/// 5. Fallback to Latin-1 (ISO-8859-1) if UTF-8 fails
 ///
 /// # Arguments
 ///
@@ -813,19 +1345,19 @@ pub fn is_too_large(size: u64, limit: u64) -> bool {
 /// * `Some(String)` - Decoded content
 /// * `None` - File is binary (should be skipped)
 pub fn read_file_content(bytes: &[u8]) -> Option<String> {
-    // Check for binary content
-    if is_binary(bytes) {
-        return None;
-    }
-
-    // Try UTF-8 first
-    let content = match String::from_utf8(bytes.to_vec()) {
-        Ok(s) => s,
-        Err(_) => {
-            // Fallback: decode as Latin-1 (ISO-8859-1)
-            // Latin-1 is a 1:1 byte-to-char mapping, never fails
-            bytes.iter().map(|&b| b as char).collect()
-        }
+    let content = match decode_utf16(bytes) {
+        Some(s) => s,
+        // Check for binary content
+        None if is_binary(bytes) => return None,
+        // Try UTF-8 first
+        None => match String::from_utf8(bytes.to_vec()) {
+            Ok(s) => s,
+            Err(_) => {
+                // Fallback: decode as Latin-1 (ISO-8859-1)
+                // Latin-1 is a 1:1 byte-to-char mapping, never fails
+                bytes.iter().map(|&b| b as char).collect()
+            }
+        },
     };
 
     // Normalize line endings to \n (like Python's read_text())
@@ -833,48 +1365,24 @@ pub fn read_file_content(bytes: &[u8]) -> Option<String> {
     Some(content.replace("\r\n", "\n").replace('\r', "\n"))
 }
 
-/// Check if a path matches any of the given glob patterns
+/// Check if a path matches the given glob patterns
+///
+/// Patterns are applied in order with gitignore-style precedence: a pattern
+/// prefixed with `!` negates, and the last pattern that matches decides. So
+/// `["target", "!target/doc/*.md"]` matches `target/debug/app` but not
+/// `target/doc/index.md`.
 ///
 /// # Arguments
 ///
 /// * `path` - Path to check (relative path)
-/// * `patterns` - List of glob patterns
+/// * `patterns` - List of glob patterns, optionally `!`-negated
 ///
 /// # Returns
 ///
-/// * `true` if path matches any pattern, `false` otherwise
+/// * `true` if the last matching pattern is not negated, `false` otherwise
 fn matches_patterns(path: &str, patterns: &[String]) -> bool {
-    for pattern_str in patterns {
-        // Try to compile the pattern
-        if let Ok(glob) = Glob::new(pattern_str) {
-            let matcher = glob.compile_matcher();
-
-            // Match against the full path
-            if matcher.is_match(path) {
-                return true;
-            }
-
-            // Also check if any path component or parent path matches
-            // This handles patterns like ".git" matching ".git/config"
-            let parts: Vec<&str> = path.split('/').collect();
-            for i in 0..parts.len() {
-                let component = parts[i];
-                // Check individual component
-                if matcher.is_match(component) {
-                    return true;
-                }
-                // Check partial paths (e.g., ".git" for ".git/config")
-                if i > 0 {
-                    let partial = parts[..=i].join("/");
-                    if matcher.is_match(&partial) {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
-    false
-}
+    project_walk::matches_patterns(path, patterns, PatternStyle::default())
+}
 
 /// Determine if a file should be included based on ignore/include patterns
 ///
@@ -896,32 +1404,68 @@ fn matches_patterns(path: &str, patterns: &[String]) -> bool {
 /// 3. Hybrid mode: if both exist, file just needs to NOT match ignore patterns
 ///    (include patterns don't act as a filter, they're for explicit inclusion of ignored items)
 /// 4. If no patterns or only ignore patterns, include by default (if not ignored)
+///
+/// Within each list, `!pattern` negations follow gitignore precedence (see
+/// [`matches_patterns`]): `ignore_patterns = ["target", "!target/doc/*.md"]`
+/// keeps the docs, and `include_patterns = ["src/**", "!src/gen/**"]` keeps
+/// all of `src` but the generated code.
 fn should_include_file(
     path: &str,
     ignore_patterns: &[String],
     include_patterns: &[String],
 ) -> bool {
+    file_exclusion(
+        path,
+        ignore_patterns,
+        include_patterns,
+        PatternStyle::default(),
+    )
+    .is_none()
+}
+
+/// Why [`should_include_file`] leaves `path` out, if it does
+fn file_exclusion(
+    path: &str,
+    ignore_patterns: &[String],
+    include_patterns: &[String],
+    style: PatternStyle,
+) -> Option<ExclusionReason> {
     // Check ignore patterns FIRST (they take precedence over includes)
     // This matches Python behavior where directory-level ignores can't be overridden
-    if matches_patterns(path, ignore_patterns) {
-        return false; // Ignored paths are always excluded
+    if let Some(pattern) = deciding_pattern(path, ignore_patterns, style, false) {
+        if !pattern.starts_with('!') {
+            // Ignored paths are always excluded
+            return Some(ExclusionReason::IgnorePattern(pattern.to_string()));
+        }
     }
 
     // Pure whitelist mode: only when include_patterns exist AND no ignore_patterns
     // In this mode, files must match at least one include pattern
-    if !include_patterns.is_empty() && ignore_patterns.is_empty() {
-        return matches_patterns(path, include_patterns);
+    if !include_patterns.is_empty()
+        && ignore_patterns.is_empty()
+        && pattern_verdict(path, include_patterns, style, false) != Some(true)
+    {
+        return Some(ExclusionReason::NotIncluded);
     }
 
     // Hybrid mode (both patterns) or blacklist mode (only ignore):
     // If not ignored, include by default
-    true
+    None
+}
+
+/// Note an exclusion in the ledger, if one is being kept
+fn record_exclusion(ledger: Option<&ExclusionLedger>, path: &str, reason: ExclusionReason) {
+    if let Some(ledger) = ledger {
+        ledger.record(path, reason);
+    }
 }
 
 /// Walk directory and yield file entries as an iterator (streaming)
 ///
-/// Uses WalkDir with filter_entry for directory pruning - ignored directories
-/// are never entered, matching Python's behavior.
+/// Uses the `ignore` crate's walker with filter_entry for directory pruning -
+/// ignored directories are never entered, matching Python's behavior. Nested
+/// `.gitignore` and `.ignore` files, `.git/info/exclude` and the global git
+/// excludes file are honoured as well.
 ///
 /// This is the iterator-based version that enables streaming output.
 /// Files are yielded as they're discovered, enabling immediate output.
@@ -942,143 +1486,817 @@ pub fn walk_directory_iter(
     include_patterns: Vec<String>,
     max_size: u64,
 ) -> impl Iterator<Item = FileEntry> {
+    walk_directory_iter_with_options(root, ignore_patterns, include_patterns, max_size, true)
+}
+
+/// Like [`walk_directory_iter`], with `.gitignore`/`.ignore` handling optional
+///
+/// With `respect_gitignore` false only the hygiene exclusions and the given
+/// patterns decide what is walked.
+pub fn walk_directory_iter_with_options(
+    root: &str,
+    ignore_patterns: Vec<String>,
+    include_patterns: Vec<String>,
+    max_size: u64,
+    respect_gitignore: bool,
+) -> impl Iterator<Item = FileEntry> {
+    walk_directory_iter_inner(
+        root,
+        ignore_patterns,
+        include_patterns,
+        PatternStyle::default(),
+        max_size,
+        None,
+        respect_gitignore,
+        None,
+        false,
+        SymlinkPolicy::default(),
+        BinaryPolicy::default(),
+        None,
+        None,
+    )
+    .filter_map(warn_skipped)
+}
+
+/// The path a walk error is about, and the error without it
+fn split_walk_error(e: &ignore::Error) -> (Option<&Path>, String) {
+    match e {
+        ignore::Error::WithPath { path, err } => (Some(path), err.to_string()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            split_walk_error(err)
+        }
+        _ => (None, e.to_string()),
+    }
+}
+
+/// The link a walk error reports as a file system loop, if it is one
+fn symlink_loop(e: &ignore::Error) -> Option<&Path> {
+    match e {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        _ => None,
+    }
+}
+
+/// Pass entries through, warning about skipped files on stderr
+fn warn_skipped(result: Result<FileEntry, SkippedFile>) -> Option<FileEntry> {
+    match result {
+        Ok(entry) => Some(entry),
+        Err(skipped) => {
+            eprintln!("Warning: skipped {}: {}", skipped.path, skipped.reason);
+            None
+        }
+    }
+}
+
+/// Shared walker; `directory_configs` adds the nested configs' ignores
+///
+/// With `git_only` the files come from `git ls-files` instead of the
+/// filesystem; outside a git repository the normal walk is used.
+///
+/// Unreadable files come through as `Err` so callers can decide whether
+/// losing them is acceptable.
+fn walk_directory_iter_inner(
+    root: &str,
+    ignore_patterns: Vec<String>,
+    include_patterns: Vec<String>,
+    style: PatternStyle,
+    max_size: u64,
+    max_depth: Option<usize>,
+    respect_gitignore: bool,
+    directory_configs: Option<directory_config::DirectoryConfigs>,
+    git_only: bool,
+    symlinks: SymlinkPolicy,
+    binary: BinaryPolicy,
+    ledger: Option<Arc<ExclusionLedger>>,
+    timings: Option<Arc<timing::FileTimings>>,
+) -> Box<dyn Iterator<Item = Result<FileEntry, SkippedFile>>> {
     let root_path = Path::new(root).to_path_buf();
-    let root_path_clone = root_path.clone();
-    let ignore_patterns_clone = ignore_patterns.clone();
+    let directory_configs = directory_configs.filter(|d| !d.is_empty());
+
+    if discovery::archive::is_archive(&root_path) {
+        return Box::new(
+            walk_archive(
+                &root_path,
+                &ignore_patterns,
+                &include_patterns,
+                style,
+                max_size,
+                max_depth,
+                directory_configs.as_ref(),
+                binary,
+                ledger.as_deref(),
+            )
+            .into_iter(),
+        );
+    }
 
-    // Create walker with directory pruning via filter_entry
-    // filter_entry is called BEFORE descending into a directory
-    // follow_links(true) matches Python's default behavior
-    WalkDir::new(&root_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_entry(move |entry| {
-            // Get the path relative to root for pattern matching
-            let path = entry.path();
+    let candidates = walk_candidates(
+        &root_path,
+        &ignore_patterns,
+        style,
+        max_depth,
+        respect_gitignore,
+        directory_configs.clone(),
+        git_only,
+        symlinks,
+        ledger.clone(),
+    );
+
+    Box::new(candidates.filter_map(move |candidate| match candidate {
+        Ok((path, path_str)) => read_walked_file(
+            &path,
+            &path_str,
+            &ignore_patterns,
+            &include_patterns,
+            style,
+            max_size,
+            directory_configs.as_ref(),
+            binary,
+            ledger.as_deref(),
+            timings.as_deref(),
+        ),
+        Err(skipped) => Some(Err(skipped)),
+    }))
+}
 
-            // Always include the root directory itself
-            if path == root_path_clone {
-                return true;
-            }
+/// The files [`walk_directory_iter_inner`] would read below `root_path`,
+/// in walk order, without reading them
+fn walk_candidates(
+    root_path: &Path,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+    max_depth: Option<usize>,
+    respect_gitignore: bool,
+    directory_configs: Option<directory_config::DirectoryConfigs>,
+    git_only: bool,
+    symlinks: SymlinkPolicy,
+    ledger: Option<Arc<ExclusionLedger>>,
+) -> Box<dyn Iterator<Item = WalkCandidate>> {
+    match git_only.then(|| git_tracked_files(root_path)).flatten() {
+        Some(tracked) => Box::new(
+            git_candidates(
+                root_path,
+                tracked,
+                ignore_patterns,
+                style,
+                max_depth,
+                directory_configs.as_ref(),
+                symlinks,
+                ledger.as_deref(),
+            )
+            .into_iter()
+            .map(Ok),
+        ),
+        None => {
+            let walk_root = root_path.to_path_buf();
+            let walk_ledger = ledger.clone();
+            Box::new(
+                walk_builder(
+                    root_path,
+                    ignore_patterns,
+                    style,
+                    max_depth,
+                    respect_gitignore,
+                    directory_configs,
+                    symlinks,
+                    ledger,
+                )
+                .build()
+                .filter_map(move |result| {
+                    walk_candidate(result, &walk_root, walk_ledger.as_deref())
+                }),
+            )
+        }
+    }
+}
+
+/// Batch counterpart of [`walk_directory_iter_inner`]
+///
+/// Directories are walked on several threads, then files are read and
+/// hashed on the rayon pool. Results are sorted by path, so they don't
+/// depend on thread timing.
+fn walk_directory_parallel(
+    root: &str,
+    ignore_patterns: &[String],
+    include_patterns: &[String],
+    style: PatternStyle,
+    max_size: u64,
+    max_depth: Option<usize>,
+    respect_gitignore: bool,
+    directory_configs: Option<directory_config::DirectoryConfigs>,
+    git_only: bool,
+    symlinks: SymlinkPolicy,
+    binary: BinaryPolicy,
+    ledger: Option<Arc<ExclusionLedger>>,
+    timings: Option<Arc<timing::FileTimings>>,
+) -> Vec<Result<FileEntry, SkippedFile>> {
+    let root_path = Path::new(root).to_path_buf();
+    let directory_configs = directory_configs.filter(|d| !d.is_empty());
+
+    if discovery::archive::is_archive(&root_path) {
+        return walk_archive(
+            &root_path,
+            ignore_patterns,
+            include_patterns,
+            style,
+            max_size,
+            max_depth,
+            directory_configs.as_ref(),
+            binary,
+            ledger.as_deref(),
+        );
+    }
 
-            // Apply hygiene exclusions (SmartWalker's "Concentric Scope" model)
-            // These are ALWAYS excluded regardless of user patterns: .venv, node_modules, target, etc.
-            if SmartWalker::is_hygiene_excluded(path) {
-                return false;
+    let mut candidates: Vec<WalkCandidate> =
+        match git_only.then(|| git_tracked_files(&root_path)).flatten() {
+            Some(tracked) => git_candidates(
+                &root_path,
+                tracked,
+                ignore_patterns,
+                style,
+                max_depth,
+                directory_configs.as_ref(),
+                symlinks,
+                ledger.as_deref(),
+            )
+            .into_iter()
+            .map(Ok)
+            .collect(),
+            None => {
+                let found = std::sync::Mutex::new(Vec::new());
+                walk_builder(
+                    &root_path,
+                    ignore_patterns,
+                    style,
+                    max_depth,
+                    respect_gitignore,
+                    directory_configs.clone(),
+                    symlinks,
+                    ledger.clone(),
+                )
+                .build_parallel()
+                .run(|| {
+                    let found = &found;
+                    let root_path = &root_path;
+                    let ledger = ledger.as_deref();
+                    Box::new(move |result| {
+                        if let Some(candidate) = walk_candidate(result, root_path, ledger) {
+                            found
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .push(candidate);
+                        }
+                        ignore::WalkState::Continue
+                    })
+                });
+                found.into_inner().unwrap_or_else(|e| e.into_inner())
             }
+        };
 
-            // Get relative path for pattern matching
-            let rel_path = match path.strip_prefix(&root_path_clone) {
-                Ok(p) => p,
-                Err(_) => return false,
-            };
+    let sort_key = |candidate: &WalkCandidate| match candidate {
+        Ok((_, path_str)) => path_str.clone(),
+        Err(skipped) => skipped.path.clone(),
+    };
+    candidates.sort_by_cached_key(sort_key);
+
+    candidates
+        .into_par_iter()
+        .filter_map(|candidate| match candidate {
+            Ok((path, path_str)) => read_walked_file(
+                &path,
+                &path_str,
+                ignore_patterns,
+                include_patterns,
+                style,
+                max_size,
+                directory_configs.as_ref(),
+                binary,
+                ledger.as_deref(),
+                timings.as_deref(),
+            ),
+            Err(skipped) => Some(Err(skipped)),
+        })
+        .collect()
+}
 
-            let path_str = match rel_path.to_str() {
-                Some(s) => s,
-                None => return false,
-            };
+/// A file to read (its path and the path relative to the walk root), or a
+/// walk error
+type WalkCandidate = Result<(std::path::PathBuf, String), SkippedFile>;
+
+/// Walker over `root_path` that prunes with [`walk_admits`]
+fn walk_builder(
+    root_path: &Path,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+    max_depth: Option<usize>,
+    respect_gitignore: bool,
+    directory_configs: Option<directory_config::DirectoryConfigs>,
+    symlinks: SymlinkPolicy,
+    ledger: Option<Arc<ExclusionLedger>>,
+) -> ignore::WalkBuilder {
+    let root = root_path.to_path_buf();
+    let ignore_patterns = ignore_patterns.to_vec();
+
+    // Create walker with directory pruning via filter_entry
+    // filter_entry is called BEFORE descending into a directory
+    // Following links matches Python's default behavior; which links are
+    // followed is up to `symlinks`, checked per entry by symlink_exclusion
+    let mut builder = project_walk::walk_builder(
+        root_path,
+        &project_walk::WalkOptions {
+            respect_gitignore,
+            follow_links: symlinks != SymlinkPolicy::Off,
+            pattern_style: style,
+        },
+    );
+    builder.filter_entry(move |entry| {
+        // Get the path relative to root for pattern matching
+        let path = entry.path();
+
+        // Always include the root directory itself
+        if path == root {
+            return true;
+        }
+
+        // Get relative path for pattern matching
+        let rel_path = match path.strip_prefix(&root) {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+
+        let path_str = match rel_path.to_str() {
+            Some(s) => s,
+            None => return false,
+        };
 
-            // For directories: check if directory should be pruned (ignored)
-            // This prevents entering .git, .llm_archive, node_modules, etc.
-            if entry.file_type().is_dir() {
-                // Check if this directory matches any ignore pattern
-                // If so, skip the entire tree by returning false
-                !matches_patterns(path_str, &ignore_patterns_clone)
+        let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+        // A directory at depth N holds files N directories down
+        let exclusion = if entry.path_is_symlink() {
+            symlink_exclusion(&root, path, symlinks)
+        } else {
+            None
+        }
+        .or_else(|| {
+            let limit = max_depth.filter(|&limit| is_dir && entry.depth() > limit)?;
+            Some(ExclusionReason::TooDeep { limit })
+        });
+        let Some(reason) = exclusion.or_else(|| {
+            walk_exclusion(
+                path,
+                path_str,
+                is_dir,
+                &ignore_patterns,
+                style,
+                directory_configs.as_ref(),
+            )
+        }) else {
+            return true;
+        };
+        if let Some(ledger) = &ledger {
+            let path_str = if is_dir {
+                format!("{}/", path_str)
             } else {
-                // For files: always return true here, we'll filter later
-                // (filter_entry affects directory traversal, not file inclusion)
-                true
-            }
-        })
-        .filter_map(move |result| {
-            let entry = match result {
-                Ok(e) => e,
-                Err(e) => {
-                    // Check if this is a broken symlink (silently skip)
-                    let error_str = e.to_string();
-                    let is_not_found = error_str.contains("No such file or directory")
-                        || error_str.contains("cannot access")
-                        || e.io_error()
-                            .map_or(false, |io| io.kind() == std::io::ErrorKind::NotFound);
-
-                    // Only warn about real errors (not broken symlinks)
-                    if !is_not_found {
-                        eprintln!("Warning: {}", e);
-                    }
-                    return None;
-                }
+                path_str.to_string()
             };
+            ledger.record(path_str, reason);
+        }
+        false
+    });
+    builder
+}
 
-            // Skip directories (we only want files)
-            if entry.file_type().is_dir() {
+/// Turn one walk result into a [`WalkCandidate`]
+///
+/// `None` for directories, broken symlinks and symlink cycles. The walker
+/// compares each followed directory link with the directories enclosing it
+/// (by device and inode) and stops at a match; that's recorded in `ledger`
+/// rather than reported as an error.
+fn walk_candidate(
+    result: Result<ignore::DirEntry, ignore::Error>,
+    root_path: &Path,
+    ledger: Option<&ExclusionLedger>,
+) -> Option<WalkCandidate> {
+    let entry = match result {
+        Ok(e) => e,
+        Err(e) => {
+            if let Some(link) = symlink_loop(&e) {
+                let path = link.strip_prefix(root_path).unwrap_or(link);
+                record_exclusion(
+                    ledger,
+                    &format!("{}/", path.to_string_lossy()),
+                    ExclusionReason::Symlink("loops back to an enclosing directory".to_string()),
+                );
                 return None;
             }
 
-            let path = entry.path();
-
-            // Get relative path for pattern matching and output
-            let rel_path = path.strip_prefix(&root_path).ok()?;
-            let path_str = rel_path.to_str()?;
+            // Check if this is a broken symlink (silently skip)
+            let error_str = e.to_string();
+            let is_not_found = error_str.contains("No such file or directory")
+                || error_str.contains("cannot access")
+                || e.io_error()
+                    .map_or(false, |io| io.kind() == std::io::ErrorKind::NotFound);
 
-            // Check if this file should be included based on patterns
-            // Note: ignore patterns already handled by filter_entry for directories,
-            // but we still need to check file-level ignores and include patterns
-            if !should_include_file(path_str, &ignore_patterns, &include_patterns) {
+            // Only report real errors (not broken symlinks)
+            if is_not_found {
                 return None;
             }
+            let (path, reason) = split_walk_error(&e);
+            let path = path.map_or_else(String::new, |p| {
+                p.strip_prefix(root_path)
+                    .unwrap_or(p)
+                    .to_string_lossy()
+                    .into_owned()
+            });
+            return Some(Err(SkippedFile { path, reason }));
+        }
+    };
 
-            // Get file metadata
-            let metadata = fs::metadata(path).ok()?;
-            let file_size = metadata.len();
+    // Skip directories and stdin (we only want files)
+    if entry.file_type().is_none_or(|ft| ft.is_dir()) {
+        return None;
+    }
+
+    // Get relative path for pattern matching and output
+    let path_str = entry
+        .path()
+        .strip_prefix(root_path)
+        .ok()?
+        .to_str()?
+        .to_string();
+    Some(Ok((entry.into_path(), path_str)))
+}
 
-            // Skip files that are too large
-            if is_too_large(file_size, max_size) {
+/// Why `policy` keeps the walk from following the symlink at `path`, if it
+/// does
+///
+/// Broken links pass; the walk skips them anyway. Cycles are cut by the
+/// walker itself (see [`walk_candidate`]).
+fn symlink_exclusion(root: &Path, path: &Path, policy: SymlinkPolicy) -> Option<ExclusionReason> {
+    let why = match policy {
+        SymlinkPolicy::On => return None,
+        SymlinkPolicy::Off => "not followed",
+        SymlinkPolicy::Safe => {
+            let target = path.canonicalize().ok()?;
+            if target.starts_with(root.canonicalize().ok()?) {
                 return None;
             }
+            "points outside the project root"
+        }
+    };
+    Some(ExclusionReason::Symlink(why.to_string()))
+}
 
-            // Extract timestamps
-            let mtime = metadata
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs())
-                .unwrap_or(0);
-
-            // ctime: On Unix, use created(). Falls back to mtime if unavailable.
-            let ctime = metadata
-                .created()
-                .ok()
-                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs())
-                .unwrap_or(mtime);
-
-            // Read file content (bytes first, then decode)
-            let buffer = fs::read(path).ok()?;
-
-            // Use read_file_content helper (handles binary detection + encoding)
-            let content = read_file_content(&buffer)?;
-
-            // Calculate MD5
-            let md5 = calculate_md5(&content);
-
-            Some(FileEntry {
-                path: path_str.to_string(),
-                size: content.len() as u64,
-                content,
-                md5,
-                mtime,
-                ctime,
-            })
+/// Tracked files that survive the pruning the walk would have applied
+fn git_candidates(
+    root_path: &Path,
+    tracked: Vec<String>,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+    max_depth: Option<usize>,
+    directory_configs: Option<&directory_config::DirectoryConfigs>,
+    symlinks: SymlinkPolicy,
+    ledger: Option<&ExclusionLedger>,
+) -> Vec<(std::path::PathBuf, String)> {
+    tracked
+        .into_iter()
+        .filter(|path_str| {
+            // Apply the same pruning the walk would have done to each
+            // parent directory, then the file-level checks
+            let path = root_path.join(path_str);
+            let exclusion = if fs::symlink_metadata(&path).is_ok_and(|m| m.is_symlink()) {
+                symlink_exclusion(root_path, &path, symlinks)
+            } else {
+                None
+            };
+            match exclusion.or_else(|| {
+                path_exclusion(
+                    root_path,
+                    path_str,
+                    ignore_patterns,
+                    style,
+                    max_depth,
+                    directory_configs,
+                )
+            }) {
+                Some(reason) => {
+                    record_exclusion(ledger, path_str, reason);
+                    false
+                }
+                None => true,
+            }
+        })
+        .map(|path_str| (root_path.join(&path_str), path_str))
+        // Tracked but deleted from the work tree, or a submodule
+        .filter(|(path, _)| path.is_file())
+        .collect()
+}
+
+/// Whether the walk enters directory `path` or considers file `path`
+///
+/// `path_str` is the same path relative to the walk root.
+fn walk_admits(
+    path: &Path,
+    path_str: &str,
+    is_dir: bool,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+    directory_configs: Option<&directory_config::DirectoryConfigs>,
+) -> bool {
+    walk_exclusion(
+        path,
+        path_str,
+        is_dir,
+        ignore_patterns,
+        style,
+        directory_configs,
+    )
+    .is_none()
+}
+
+/// Why [`walk_admits`] turns `path` away, if it does
+fn walk_exclusion(
+    path: &Path,
+    path_str: &str,
+    is_dir: bool,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+    directory_configs: Option<&directory_config::DirectoryConfigs>,
+) -> Option<ExclusionReason> {
+    // Hygiene exclusions and pruned directories, as project_walk decides
+    // them (files are matched against the patterns once reached)
+    match project_walk::walk_exclusion(path, path_str, is_dir, ignore_patterns, style) {
+        Some(project_walk::Exclusion::Hygiene) => return Some(ExclusionReason::Hygiene),
+        Some(project_walk::Exclusion::IgnorePattern(pattern)) => {
+            return Some(ExclusionReason::IgnorePattern(pattern))
+        }
+        None => {}
+    }
+    // Nested configs can prune their own subdirectories
+    (is_dir && directory_configs.is_some_and(|d| d.prunes(path_str)))
+        .then_some(ExclusionReason::NestedConfig)
+}
+
+/// Why the walk would never reach `path_str`: the depth limit, the pruning
+/// of one of its parent directories, or of the file itself
+///
+/// For file lists that don't come from walking `root_path` (git, archives).
+fn path_exclusion(
+    root_path: &Path,
+    path_str: &str,
+    ignore_patterns: &[String],
+    style: PatternStyle,
+    max_depth: Option<usize>,
+    directory_configs: Option<&directory_config::DirectoryConfigs>,
+) -> Option<ExclusionReason> {
+    if let Some(limit) = max_depth.filter(|&limit| path_str.matches('/').count() > limit) {
+        return Some(ExclusionReason::TooDeep { limit });
+    }
+    path_str
+        .match_indices('/')
+        .map(|(i, _)| (&path_str[..i], true))
+        .chain(std::iter::once((path_str, false)))
+        .find_map(|(p, is_dir)| {
+            walk_exclusion(
+                &root_path.join(p),
+                p,
+                is_dir,
+                ignore_patterns,
+                style,
+                directory_configs,
+            )
+        })
+}
+
+/// Read a walked file into an entry
+///
+/// `None` for files the patterns leave out and for oversized or binary
+/// files; `Err` when the file can't be read.
+fn read_walked_file(
+    path: &Path,
+    path_str: &str,
+    ignore_patterns: &[String],
+    include_patterns: &[String],
+    style: PatternStyle,
+    max_size: u64,
+    directory_configs: Option<&directory_config::DirectoryConfigs>,
+    binary: BinaryPolicy,
+    ledger: Option<&ExclusionLedger>,
+    timings: Option<&timing::FileTimings>,
+) -> Option<Result<FileEntry, SkippedFile>> {
+    // Check if this file should be included based on patterns
+    // Note: ignore patterns already handled by filter_entry for directories,
+    // but we still need to check file-level ignores and include patterns
+    if let Some(reason) = file_exclusion(path_str, ignore_patterns, include_patterns, style) {
+        record_exclusion(ledger, path_str, reason);
+        return None;
+    }
+    if directory_configs.is_some_and(|d| d.is_ignored(path_str)) {
+        record_exclusion(ledger, path_str, ExclusionReason::NestedConfig);
+        return None;
+    }
+
+    let skipped = |e: std::io::Error| SkippedFile {
+        path: path_str.to_string(),
+        reason: e.to_string(),
+    };
+    let started = Instant::now();
+
+    // Get file metadata
+    let metadata = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(e) => return Some(Err(skipped(e))),
+    };
+    let file_size = metadata.len();
+
+    // Skip files that are too large
+    if is_too_large(file_size, max_size) {
+        let reason = ExclusionReason::TooLarge {
+            size: file_size,
+            limit: max_size,
+        };
+        record_exclusion(ledger, path_str, reason);
+        return None;
+    }
+
+    // Extract timestamps
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // ctime: On Unix, use created(). Falls back to mtime if unavailable.
+    let ctime = metadata
+        .created()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(mtime);
+
+    // Read file content (bytes first, then decode)
+    let buffer = match fs::read(path) {
+        Ok(b) => b,
+        Err(e) => return Some(Err(skipped(e))),
+    };
+
+    let Some(entry) = file_entry_from_bytes(path_str, &buffer, mtime, ctime, binary) else {
+        record_exclusion(ledger, path_str, ExclusionReason::Binary);
+        return None;
+    };
+    if let Some(timings) = timings {
+        timings.record_read(path_str, started.elapsed());
+    }
+    Some(Ok(FileEntry {
+        executable: is_executable(&metadata),
+        symlink_target: symlink_target(path),
+        ..entry
+    }))
+}
+
+/// Decode raw file bytes into an entry; binary content becomes the stub
+/// `binary` asks for, or `None` when it is skipped
+fn file_entry_from_bytes(
+    path_str: &str,
+    bytes: &[u8],
+    mtime: u64,
+    ctime: u64,
+    binary: BinaryPolicy,
+) -> Option<FileEntry> {
+    // Use read_file_content helper (handles binary detection + encoding)
+    let content =
+        read_file_content(bytes).or_else(|| discovery::binary_stub(path_str, bytes, binary))?;
+
+    // Calculate MD5
+    let md5 = calculate_md5(&content);
+
+    Some(FileEntry {
+        path: path_str.to_string(),
+        size: bytes.len() as u64,
+        content,
+        md5,
+        mtime,
+        ctime,
+        language: detect_language(path_str),
+        executable: false,
+        symlink_target: None,
+    })
+}
+
+/// Walk the members of an archive given as the project root
+///
+/// Members are filtered as walked files are: hygiene exclusions and ignore
+/// patterns on every parent directory, then the file patterns, nested
+/// configs and size limit. An archive that can't be opened is reported as
+/// a single skipped file.
+fn walk_archive(
+    root_path: &Path,
+    ignore_patterns: &[String],
+    include_patterns: &[String],
+    style: PatternStyle,
+    max_size: u64,
+    max_depth: Option<usize>,
+    directory_configs: Option<&directory_config::DirectoryConfigs>,
+    binary: BinaryPolicy,
+    ledger: Option<&ExclusionLedger>,
+) -> Vec<Result<FileEntry, SkippedFile>> {
+    let admit = |path_str: &str, size: u64| {
+        let reason = path_exclusion(
+            Path::new(""),
+            path_str,
+            ignore_patterns,
+            style,
+            max_depth,
+            directory_configs,
+        )
+        .or_else(|| file_exclusion(path_str, ignore_patterns, include_patterns, style))
+        .or_else(|| {
+            directory_configs
+                .is_some_and(|d| d.is_ignored(path_str))
+                .then_some(ExclusionReason::NestedConfig)
         })
+        .or_else(|| {
+            is_too_large(size, max_size).then_some(ExclusionReason::TooLarge {
+                size,
+                limit: max_size,
+            })
+        });
+        match reason {
+            Some(reason) => {
+                record_exclusion(ledger, path_str, reason);
+                false
+            }
+            None => true,
+        }
+    };
+    match discovery::archive::read_archive(root_path, admit) {
+        Ok(members) => members
+            .into_iter()
+            .filter_map(|member| match member {
+                Ok(member) => {
+                    match file_entry_from_bytes(
+                        &member.path,
+                        &member.bytes,
+                        member.mtime,
+                        member.mtime,
+                        binary,
+                    ) {
+                        Some(entry) => Some(Ok(FileEntry {
+                            executable: member.executable,
+                            ..entry
+                        })),
+                        None => {
+                            record_exclusion(ledger, &member.path, ExclusionReason::Binary);
+                            None
+                        }
+                    }
+                }
+                Err(skipped) => Some(Err(skipped)),
+            })
+            .collect(),
+        Err(reason) => vec![Err(SkippedFile {
+            path: root_path.to_string_lossy().into_owned(),
+            reason,
+        })],
+    }
+}
+
+/// Files tracked by git below `root`, relative to it
+///
+/// `None` when `root` isn't inside a git work tree or git isn't installed.
+fn git_tracked_files(root: &Path) -> Option<Vec<String>> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["ls-files", "-z", "--cached"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut files: Vec<String> = output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| std::str::from_utf8(p).ok())
+        .map(str::to_string)
+        .collect();
+    // Unmerged paths are listed once per conflict stage
+    files.dedup();
+    Some(files)
 }
 
 /// Walk directory and collect file entries (batch mode)
 ///
-/// Uses WalkDir with filter_entry for directory pruning - ignored directories
-/// are never entered, matching Python's behavior.
+/// Uses the same gitignore-aware walker as `walk_directory_iter` - ignored
+/// directories are never entered, matching Python's behavior.
 ///
-/// This is the batch version that collects all files into a Vec.
-/// For streaming output, use `walk_directory_iter` instead.
+/// This is the batch version that collects all files into a Vec. Files are
+/// read in parallel and returned sorted by path. For streaming output, use
+/// `walk_directory_iter` instead.
 ///
 /// # Arguments
 ///
@@ -1102,119 +2320,489 @@ pub fn walk_directory(
         return Err(format!("Directory not found: {}", root));
     }
 
-    // Use the iterator version and collect into Vec
-    let entries: Vec<FileEntry> = walk_directory_iter(
+    let entries: Vec<FileEntry> = walk_directory_parallel(
         root,
-        ignore_patterns.to_vec(),
-        include_patterns.to_vec(),
+        ignore_patterns,
+        include_patterns,
+        PatternStyle::default(),
         max_size,
+        None,
+        true,
+        None,
+        false,
+        SymlinkPolicy::default(),
+        BinaryPolicy::default(),
+        None,
+        None,
     )
+    .into_iter()
+    .filter_map(warn_skipped)
     .collect();
 
     Ok(entries)
 }
 
-/// Truncate content to a maximum number of lines (simple mode)
-///
-/// # Arguments
-///
-/// * `content` - The content to truncate
-/// * `max_lines` - Maximum number of lines to keep
-/// * `file_path` - File path for the truncation marker
-///
-/// # Returns
+/// Walk a project using the patterns and path restrictions in `config`
 ///
-/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
-pub fn truncate_simple(content: &str, max_lines: usize, file_path: &str) -> (String, bool) {
-    truncate_simple_with_options(content, max_lines, file_path, true)
+/// Like [`walk_directory`], but additionally honours `config.only_paths`,
+/// `config.respect_gitignore`, `config.directory_configs`, `config.git_only`
+/// and `config.changed_since`, and walks `config.extra_roots` after `root`.
+/// Unreadable files go to [`EncoderConfig::report_skipped`].
+pub fn walk_project(root: &str, config: &EncoderConfig) -> Result<Vec<FileEntry>, String> {
+    let (entries, skipped) = walk_project_with_skipped(root, config)?;
+    for file in skipped {
+        config.report_skipped(file);
+    }
+    Ok(entries)
 }
 
-/// Truncate content to a maximum number of lines with options
-///
-/// # Arguments
-///
-/// * `content` - The content to truncate
-/// * `max_lines` - Maximum number of lines to keep
-/// * `file_path` - File path for the truncation marker
-/// * `include_summary` - Whether to include the summary marker
-///
-/// # Returns
-///
-/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
-pub fn truncate_simple_with_options(
-    content: &str,
-    max_lines: usize,
-    file_path: &str,
-    include_summary: bool,
-) -> (String, bool) {
-    let lines: Vec<&str> = python_style_split(content);
-    let total_lines = lines.len();
+/// Like [`walk_project`], returning the unreadable files instead of warning
+pub fn walk_project_with_skipped(
+    root: &str,
+    config: &EncoderConfig,
+) -> Result<(Vec<FileEntry>, Vec<SkippedFile>), String> {
+    walk_roots(root, config, None)
+}
 
-    if max_lines == 0 || total_lines <= max_lines {
-        return (content.to_string(), false);
+/// Like [`walk_project`], also returning why every other file was left out
+///
+/// Unreadable files are listed among the exclusions rather than warned
+/// about. Files hidden by `.gitignore` (or left untracked with `git_only`)
+/// are found with a second walk that ignores them, so this costs more than
+/// a plain walk.
+pub fn walk_project_with_exclusions(
+    root: &str,
+    config: &EncoderConfig,
+) -> Result<(Vec<FileEntry>, Vec<ExcludedFile>), String> {
+    let ledger = ExclusionLedger::new();
+    let (entries, skipped) = walk_roots(root, config, Some(&ledger))?;
+    for file in skipped {
+        ledger.record(file.path, ExclusionReason::Unreadable(file.reason));
     }
+    Ok((entries, ledger.into_sorted()))
+}
 
-    // Keep first N lines
-    let kept_lines: Vec<&str> = lines.into_iter().take(max_lines).collect();
-    let mut truncated = kept_lines.join("\n");
-
-    // Add truncation marker (matching Python format) only if include_summary is true
-    if include_summary {
-        let reduced_pct = (total_lines - max_lines) * 100 / total_lines;
-        let marker = format!(
-            "\n\n{}\nTRUNCATED at line {}/{} ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
-            "=".repeat(70),
-            max_lines,
-            total_lines,
-            reduced_pct,
-            file_path,
-            file_path,
-            "=".repeat(70)
+/// Walk every root in `config`, recording exclusions in `ledger` if given
+fn walk_roots(
+    root: &str,
+    config: &EncoderConfig,
+    ledger: Option<&ExclusionLedger>,
+) -> Result<(Vec<FileEntry>, Vec<SkippedFile>), String> {
+    let content_filter = discovery::ContentFilter::new(&config.exclude_content_patterns)?;
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    let mut limits = WalkLimits::new(config);
+    for (root_path, prefix) in config.roots(root)? {
+        let root_ledger = Arc::new(ExclusionLedger::new());
+        let directory_configs = config.directory_configs_within(prefix.as_deref());
+        let results = walk_directory_parallel(
+            &root_path.to_string_lossy(),
+            &config.ignore_patterns,
+            &config.include_patterns,
+            config.pattern_style(),
+            config.max_file_size,
+            config.max_depth,
+            config.respect_gitignore,
+            directory_configs.clone(),
+            config.git_only,
+            config.follow_symlinks,
+            config.binary,
+            Some(root_ledger.clone()),
+            config.timings.clone(),
         );
-        truncated.push_str(&marker);
+        let prefixed = |path: &str| match &prefix {
+            Some(prefix) => prefixed_path(prefix, path),
+            None => path.to_string(),
+        };
+        let mut excluded = Arc::try_unwrap(root_ledger)
+            .unwrap_or_default()
+            .into_sorted();
+        limits.count_pruned(&excluded);
+        if let Some(ledger) = ledger {
+            excluded.extend(hidden_by_git(
+                root_path,
+                config,
+                directory_configs,
+                &results,
+                &excluded,
+            ));
+            for file in excluded {
+                ledger.record(prefixed(&file.path), file.reason);
+            }
+        }
+        for result in results {
+            match result {
+                Ok(mut entry) => {
+                    entry.path = prefixed(&entry.path);
+                    entries.push(entry)
+                }
+                Err(mut file) => {
+                    file.path = prefixed(&file.path);
+                    skipped.push(file)
+                }
+            }
+        }
+    }
+    if let Some(only) = &config.only_paths {
+        let allowed: std::collections::HashSet<&str> = only.iter().map(|p| p.as_str()).collect();
+        entries.retain(|e| {
+            let listed = allowed.contains(e.path.as_str());
+            if !listed {
+                record_exclusion(ledger, &e.path, ExclusionReason::NotListed);
+            }
+            listed
+        });
+        skipped.retain(|f| allowed.contains(f.path.as_str()));
+    }
+    if let Some(since) = &config.changed_since {
+        entries.retain(|e| {
+            let changed = since.admits(e);
+            if !changed {
+                record_exclusion(ledger, &e.path, ExclusionReason::Unchanged);
+            }
+            changed
+        });
+    }
+    if config.generated == GeneratedPolicy::Skip {
+        entries.retain(|e| match config.generated_status(&e.path, &e.content) {
+            Some(generated) => {
+                record_exclusion(ledger, &e.path, ExclusionReason::Generated(generated));
+                false
+            }
+            None => true,
+        });
+    }
+    if !content_filter.is_empty() {
+        entries.retain(|e| match content_filter.matching(&e.content) {
+            Some(pattern) => {
+                let reason = ExclusionReason::ContentPattern(pattern.to_string());
+                record_exclusion(ledger, &e.path, reason);
+                false
+            }
+            None => true,
+        });
+    }
+    entries.retain(|e| match limits.admit(e) {
+        Some(reason) => {
+            record_exclusion(ledger, &e.path, reason);
+            false
+        }
+        None => true,
+    });
+    // With a ledger the caller lists the cut files itself
+    if ledger.is_none() {
+        limits.warn();
+    }
+    if config.frozen {
+        canonicalize_entries(&mut entries);
+    }
+    if config.dedupe {
+        dedupe_entries(&mut entries);
     }
+    Ok((entries, skipped))
+}
 
-    (truncated, true)
+/// The walk limits in an [`EncoderConfig`] and what they have cut so far
+#[derive(Debug, Default)]
+struct WalkLimits {
+    max_depth: Option<usize>,
+    max_files: Option<usize>,
+    max_bytes: Option<u64>,
+    files: usize,
+    bytes: u64,
+    /// The limit reached; every later file is cut for the same reason
+    reached: Option<ExclusionReason>,
+    too_deep: usize,
+    cut: usize,
 }
 
-/// Check if a file should skip truncation based on exclude patterns
+impl WalkLimits {
+    fn new(config: &EncoderConfig) -> Self {
+        Self {
+            max_depth: config.max_depth,
+            max_files: config.max_total_files,
+            max_bytes: config.max_total_bytes,
+            ..Default::default()
+        }
+    }
+
+    /// Count the paths among `excluded` that the depth limit pruned
+    fn count_pruned(&mut self, excluded: &[ExcludedFile]) {
+        self.too_deep += excluded
+            .iter()
+            .filter(|file| matches!(file.reason, ExclusionReason::TooDeep { .. }))
+            .count();
+    }
+
+    /// Take `entry`, or say which total limit it comes after
+    fn admit(&mut self, entry: &FileEntry) -> Option<ExclusionReason> {
+        if self.reached.is_none() {
+            if let Some(limit) = self.max_files.filter(|&limit| self.files >= limit) {
+                self.reached = Some(ExclusionReason::FileLimit { limit });
+            } else if let Some(limit) = self
+                .max_bytes
+                .filter(|&limit| self.bytes + entry.size > limit)
+            {
+                self.reached = Some(ExclusionReason::ByteLimit { limit });
+            } else {
+                self.files += 1;
+                self.bytes += entry.size;
+                return None;
+            }
+        }
+        self.cut += 1;
+        self.reached.clone()
+    }
+
+    /// Summarize on stderr what the limits cut, if anything
+    fn warn(&self) {
+        let count = |n: usize, what: &str| match n {
+            1 => format!("1 {}", what),
+            n => format!("{} {}s", n, what),
+        };
+        let mut cut = Vec::new();
+        if let Some(limit) = self.max_depth.filter(|_| self.too_deep > 0) {
+            cut.push(format!(
+                "{} deeper than max depth {}",
+                count(self.too_deep, "path"),
+                limit
+            ));
+        }
+        if let Some(reason) = &self.reached {
+            cut.push(format!("{} {}", count(self.cut, "file"), reason));
+        }
+        if !cut.is_empty() {
+            eprintln!(
+                "Warning: walk limits reached, skipped {}",
+                cut.join(" and ")
+            );
+        }
+    }
+}
+
+/// Files the walk of `root_path` never saw because `.gitignore` files hid
+/// them, or, with `git_only`, because git doesn't track them
+///
+/// Walks the root again with gitignore handling off; whatever turns up that
+/// the first walk neither returned nor excluded for another reason was
+/// hidden.
+fn hidden_by_git(
+    root_path: &Path,
+    config: &EncoderConfig,
+    directory_configs: Option<directory_config::DirectoryConfigs>,
+    results: &[Result<FileEntry, SkippedFile>],
+    excluded: &[ExcludedFile],
+) -> Vec<ExcludedFile> {
+    let reason = if config.git_only && git_tracked_files(root_path).is_some() {
+        ExclusionReason::Untracked
+    } else if config.respect_gitignore {
+        ExclusionReason::Gitignored
+    } else {
+        return Vec::new();
+    };
+    if !root_path.is_dir() {
+        return Vec::new();
+    }
+
+    let seen: std::collections::HashSet<&str> = results
+        .iter()
+        .map(|result| match result {
+            Ok(entry) => entry.path.as_str(),
+            Err(file) => file.path.as_str(),
+        })
+        .chain(excluded.iter().map(|file| file.path.as_str()))
+        .collect();
+    // Directories this walk prunes were hidden before the first walk could
+    // prune them too; they keep their own reason
+    let pruned = Arc::new(ExclusionLedger::new());
+    let mut hidden: Vec<ExcludedFile> = walk_builder(
+        root_path,
+        &config.ignore_patterns,
+        config.pattern_style(),
+        config.max_depth,
+        false,
+        directory_configs.filter(|d| !d.is_empty()),
+        config.follow_symlinks,
+        Some(pruned.clone()),
+    )
+    .build()
+    .filter_map(|result| walk_candidate(result, root_path, None)?.ok())
+    .map(|(_, path)| ExcludedFile {
+        path,
+        reason: reason.clone(),
+    })
+    .collect();
+    hidden.extend(Arc::try_unwrap(pruned).unwrap_or_default().into_sorted());
+    hidden.retain(|file| !seen.contains(file.path.as_str()));
+    hidden.sort_by(|a, b| a.path.cmp(&b.path));
+    hidden
+}
+
+/// The prefix a root's paths get with [`EncoderConfig::prefix_roots`]: the
+/// name of its directory, or of an archive without its extension
+pub fn root_prefix(root: &Path) -> String {
+    let canonical = root.canonicalize().ok();
+    let root = canonical.as_deref().unwrap_or(root);
+    if let Some(stem) = discovery::archive::archive_stem(root).filter(|_| root.is_file()) {
+        return stem.to_string();
+    }
+    root.file_name()
+        .map_or_else(|| "root".to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// Check a path prefix such as `repo-name/` and return it without the
+/// trailing slash
+///
+/// A prefix must be a relative path without `..`, so prefixed paths still
+/// decode inside the target directory.
+pub fn normalize_prefix(prefix: &str) -> Result<String, String> {
+    let trimmed = prefix.trim_end_matches('/');
+    let valid = !trimmed.is_empty()
+        && Path::new(trimmed)
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if valid {
+        Ok(trimmed.to_string())
+    } else {
+        Err(format!(
+            "Invalid path prefix '{}': expected a relative path such as repo-name/",
+            prefix
+        ))
+    }
+}
+
+fn prefixed_path(prefix: &str, path: &str) -> String {
+    Path::new(prefix).join(path).to_string_lossy().into_owned()
+}
+
+/// Canonicalize entries for frozen (byte-identical) output
+///
+/// Path separators become `/` and mtime/ctime are cleared, so nothing that
+/// depends on the host filesystem reaches the serialized output.
+pub fn canonicalize_entries(entries: &mut [FileEntry]) {
+    for entry in entries {
+        if entry.path.contains('\\') {
+            entry.path = entry.path.replace('\\', "/");
+        }
+        entry.mtime = 0;
+        entry.ctime = 0;
+    }
+}
+
+/// Content emitted in place of a file identical to the one at `first`
+pub fn duplicate_stub(first: &str) -> String {
+    format!("duplicate of {}\n", first)
+}
+
+/// Replace the content of every entry identical to an earlier one with a
+/// [`duplicate_stub`], returning how many were stubbed
+///
+/// Identity is by md5; the entries keep the checksum and size of their real
+/// content, so the decoder can restore them from the first copy. Files too
+/// small to gain from a stub are left alone.
+pub fn dedupe_entries(entries: &mut [FileEntry]) -> usize {
+    let mut dedupe = Dedupe::default();
+    entries
+        .iter_mut()
+        .map(|entry| dedupe.stub(entry))
+        .filter(|&stubbed| stubbed)
+        .count()
+}
+
+/// The first path seen for each content, for [`EncoderConfig::dedupe`]
+#[derive(Debug, Default)]
+struct Dedupe {
+    first: std::collections::HashMap<String, String>,
+}
+
+impl Dedupe {
+    /// Stub `entry` if an earlier entry had the same content
+    fn stub(&mut self, entry: &mut FileEntry) -> bool {
+        match self.first.get(&entry.md5) {
+            Some(first) => {
+                let stub = duplicate_stub(first);
+                if stub.len() >= entry.content.len() {
+                    return false;
+                }
+                entry.content = stub;
+                true
+            }
+            None => {
+                self.first.insert(entry.md5.clone(), entry.path.clone());
+                false
+            }
+        }
+    }
+}
+
+/// SHA-256 of a whole serialized context, as lowercase hex
+pub fn context_hash(output: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(output.as_bytes()))
+}
+
+/// Append the frozen-mode trailer carrying the hash of everything before it
+///
+/// Plus/Minus gets a framing-style line (ignored by the decoder); the other
+/// formats get a comment.
+pub fn append_context_hash(output: &mut String, format: OutputFormat) {
+    let hash = context_hash(output);
+    match format {
+        OutputFormat::PlusMinus => {
+            output.push_str(&format!("========== CONTEXT sha256:{} ==========\n", hash))
+        }
+        _ => output.push_str(&format!("<!-- context sha256:{} -->\n", hash)),
+    }
+}
+
+/// Truncate content to a maximum number of lines (simple mode)
 ///
 /// # Arguments
 ///
-/// * `path` - File path to check
-/// * `patterns` - Patterns to match against
+/// * `content` - The content to truncate
+/// * `max_lines` - Maximum number of lines to keep
+/// * `file_path` - File path for the truncation marker
 ///
 /// # Returns
 ///
-/// * `true` if the file should skip truncation
-pub fn should_skip_truncation(path: &str, patterns: &[String]) -> bool {
-    matches_patterns(path, patterns)
+/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
+pub fn truncate_simple(content: &str, max_lines: usize, file_path: &str) -> (String, bool) {
+    truncate_simple_with_options(content, max_lines, file_path, true)
 }
 
-/// Serialize a file entry into Plus/Minus format
+/// Truncate content to a maximum number of lines with options
 ///
 /// # Arguments
 ///
-/// * `entry` - The file entry to serialize
+/// * `content` - The content to truncate
+/// * `max_lines` - Maximum number of lines to keep
+/// * `file_path` - File path for the truncation marker
+/// * `include_summary` - Whether to include the summary marker
 ///
 /// # Returns
 ///
-/// * Serialized string in Plus/Minus format
-pub fn serialize_file(entry: &FileEntry) -> String {
-    serialize_file_with_truncation(entry, 0, "simple")
+/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
+pub fn truncate_simple_with_options(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    truncate_simple_keeping(
+        content,
+        max_lines,
+        file_path,
+        include_summary,
+        &KeepPatterns::default(),
+    )
 }
 
-/// Truncate content using Python's default strategy: keep first 40%, gap, keep last 10%
-///
-/// This matches Python's LanguageAnalyzer.get_truncate_ranges() default behavior
-/// for files without specialized analyzers. The output includes gap markers
-/// to show where content was omitted.
-fn truncate_with_gap_markers(
+fn truncate_simple_keeping(
     content: &str,
     max_lines: usize,
     file_path: &str,
     include_summary: bool,
-    language: Option<&str>,
+    keep: &KeepPatterns,
 ) -> (String, bool) {
     let lines: Vec<&str> = python_style_split(content);
     let total_lines = lines.len();
@@ -1223,519 +2811,2063 @@ fn truncate_with_gap_markers(
         return (content.to_string(), false);
     }
 
-    // Python's default strategy: keep first 40% and last 10% of max_lines
-    let keep_first = (max_lines as f64 * 0.4) as usize;
-    let keep_last = (max_lines as f64 * 0.1) as usize;
+    // Keep first N lines
+    let mut truncated = head_keeping(&lines, max_lines, keep);
 
-    // Calculate range boundaries (1-indexed like Python)
-    let first_end = keep_first.min(total_lines);
-    // Use saturating subtraction to avoid overflow
-    let last_start = total_lines
-        .saturating_sub(keep_last)
-        .saturating_add(1)
-        .max(first_end + 1);
+    // Add truncation marker (matching Python format) only if include_summary is true
+    if include_summary {
+        let reduced_pct = (total_lines - max_lines) * 100 / total_lines;
+        let marker = format!(
+            "\n\n{}\nTRUNCATED at line {}/{} ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            "=".repeat(70),
+            max_lines,
+            total_lines,
+            reduced_pct,
+            file_path,
+            file_path,
+            "=".repeat(70)
+        );
+        truncated.push_str(&marker);
+    }
 
-    let mut result = String::new();
+    (truncated, true)
+}
 
-    // Keep first section
-    for line in lines.iter().take(first_end) {
-        result.push_str(line);
-        result.push('\n');
+/// Truncate content to its last `max_lines` lines
+///
+/// For logs and changelogs, where the newest lines come last. A gap marker
+/// stands in for the head that was dropped.
+pub fn truncate_tail(content: &str, max_lines: usize, file_path: &str) -> (String, bool) {
+    truncate_tail_with_options(content, max_lines, file_path, true)
+}
+
+/// Truncate content to its last lines, with or without the summary marker
+/// (see [`truncate_tail`])
+pub fn truncate_tail_with_options(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    truncate_tail_keeping(
+        content,
+        max_lines,
+        file_path,
+        include_summary,
+        &KeepPatterns::default(),
+    )
+}
+
+fn truncate_tail_keeping(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    // The empty string after a final newline is not a line to keep
+    let mut lines: Vec<&str> = python_style_split(content);
+    let newline_terminated = lines.len() > 1 && lines.last() == Some(&"");
+    if newline_terminated {
+        lines.pop();
     }
+    let total_lines = lines.len();
 
-    // Add gap marker if there's a gap
-    if last_start > first_end + 1 {
-        let gap_size = last_start - first_end - 1;
-        result.push_str(&format!("\n... [{} lines omitted] ...\n\n", gap_size));
+    if max_lines == 0 || total_lines <= max_lines {
+        return (content.to_string(), false);
     }
 
-    // Keep last section
-    for line in lines.iter().skip(last_start - 1) {
-        result.push_str(line);
+    // Lines up to `head_end` (1-indexed) are dropped, except keep matches;
+    // gap markers after a kept line get a blank line above them too
+    let head_end = total_lines - max_lines;
+    let gap = |last: usize, gap_size: usize| {
+        let framing = if last > 0 { "\n" } else { "" };
+        format!("{}... [{} lines omitted] ...\n\n", framing, gap_size)
+    };
+    let mut result = String::new();
+    let mut last = 0;
+    for line_num in keep.matching_lines(&lines) {
+        if line_num > head_end {
+            break;
+        }
+        if line_num > last + 1 {
+            result.push_str(&gap(last, line_num - last - 1));
+        }
+        result.push_str(lines[line_num - 1]);
+        result.push('\n');
+        last = line_num;
+    }
+    if head_end > last {
+        result.push_str(&gap(last, head_end - last));
+    }
+    result.push_str(&lines[head_end..].join("\n"));
+    if newline_terminated {
         result.push('\n');
     }
 
-    // Calculate kept lines (excluding the gap marker line itself)
-    let kept_count = first_end + total_lines.saturating_sub(last_start).saturating_add(1);
-
-    // Add truncation marker
     if include_summary {
-        let omitted = total_lines.saturating_sub(kept_count);
-        let mut marker = format!(
-            "\n{}\nTRUNCATED at line {}/{} ({}% reduction)",
+        let marker = format!(
+            "\n{}\nTRUNCATED to the last {} of {} lines ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
             "=".repeat(70),
             max_lines,
             total_lines,
-            omitted * 100 / total_lines,
-        );
-
-        // Add Language line if provided (matches Python's smart mode marker)
-        if let Some(lang) = language {
-            marker.push_str(&format!("\nLanguage: {}", lang));
-        }
-
-        marker.push_str(&format!(
-            "\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            head_end * 100 / total_lines,
             file_path,
             file_path,
             "=".repeat(70)
-        ));
+        );
         result.push_str(&marker);
     }
 
     (result, true)
 }
 
-/// Truncate markdown content matching Python's MarkdownAnalyzer.get_truncate_ranges()
+/// Share of a `tokens` truncation budget kept from the head of the file;
+/// the rest goes to the tail (the 4:1 split of gap truncation's 40%/10%)
+const TOKEN_HEAD_SHARE: f64 = 0.8;
+
+/// Truncate content to a maximum number of estimated tokens
+pub fn truncate_tokens(content: &str, max_tokens: usize, file_path: &str) -> (String, bool) {
+    truncate_tokens_with_options(content, max_tokens, file_path, true)
+}
+
+/// Truncate content to a maximum number of estimated tokens, with options
 ///
-/// Python's markdown truncation keeps most of the file:
-/// - Allocates budget for H1/H2 header sections (10 lines each, up to 10% of max per section)
-/// - Fills remaining budget with beginning of file
+/// The budget (estimated with [`TokenEstimator`]) is shared between the
+/// head and the tail of the file, which are cut at line boundaries where a
+/// whole line fits and mid-line otherwise, so a minified file with one huge
+/// line shrinks as much as one with many short ones.
 ///
-/// This effectively keeps first ~max_lines with header supplements
-fn truncate_markdown(
+/// # Returns
+///
+/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
+pub fn truncate_tokens_with_options(
     content: &str,
-    max_lines: usize,
+    max_tokens: usize,
     file_path: &str,
     include_summary: bool,
 ) -> (String, bool) {
-    let lines: Vec<&str> = python_style_split(content);
-    let total_lines = lines.len();
+    truncate_tokens_keeping(
+        content,
+        max_tokens,
+        file_path,
+        include_summary,
+        &KeepPatterns::default(),
+    )
+}
 
-    if max_lines == 0 || total_lines <= max_lines {
+fn truncate_tokens_keeping(
+    content: &str,
+    max_tokens: usize,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    let total_tokens = TokenEstimator::estimate_tokens(content);
+    if max_tokens == 0 || total_tokens <= max_tokens {
         return (content.to_string(), false);
     }
 
-    // Python behavior: keep first max_lines (budget filled with beginning)
-    // This matches Python's MarkdownAnalyzer.get_truncate_ranges() which adds (1, budget)
-    let kept_lines: Vec<&str> = lines.iter().take(max_lines).copied().collect();
-    let mut truncated = kept_lines.join("\n");
+    let budget = max_tokens * budgeting::BYTES_PER_TOKEN;
+    let head_budget = (budget as f64 * TOKEN_HEAD_SHARE) as usize;
+    let tail_budget = budget - head_budget;
 
-    // Add smart mode marker with Language: Markdown (matches Python's smart mode output)
-    if include_summary {
-        let reduced_pct = (total_lines - max_lines) * 100 / total_lines;
+    // Head: whole lines if at least one fits
+    let mut head_end = head_budget;
+    while !content.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    if let Some(newline) = content[..head_end].rfind('\n') {
+        head_end = newline + 1;
+    }
 
-        // Extract links from markdown (Python's "imports" field for markdown)
-        // Python iterates LINE BY LINE, so multi-line links are not found
-        let link_pattern = regex::Regex::new(r"\[([^\]]+)\]\(([^\)]+)\)").unwrap();
-        let mut links: Vec<&str> = Vec::new();
-        for line in content.lines() {
-            for cap in link_pattern.captures_iter(line) {
-                if let Some(url) = cap.get(2) {
-                    links.push(url.as_str());
-                    if links.len() >= 10 {
-                        break;
-                    }
-                }
-            }
-            if links.len() >= 10 {
-                break;
+    // Tail: whole lines if at least one fits, never overlapping the head
+    let mut tail_start = (content.len() - tail_budget).max(head_end);
+    while !content.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    let at_line_start = |i: usize| i == 0 || content.as_bytes()[i - 1] == b'\n';
+    if !at_line_start(tail_start) {
+        if let Some(newline) = content[tail_start..].find('\n') {
+            if tail_start + newline + 1 < content.len() {
+                tail_start += newline + 1;
             }
         }
+    }
 
-        let mut marker = format!(
-            "\n\n{}\nTRUNCATED at line {}/{} ({}% reduction)\nLanguage: Markdown\nCategory: documentation",
-            "=".repeat(70),
-            max_lines,
-            total_lines,
-            reduced_pct,
-        );
-
-        // Add Key imports if links found (Python shows first 8 + "...")
-        if !links.is_empty() {
-            let imports_str = if links.len() > 8 {
-                format!("{}, ...", links[..8].join(", "))
+    // Whole lines in between that match a keep pattern split the gap
+    let mut kept_lines = Vec::new();
+    let mut line_start = head_end;
+    if !at_line_start(line_start) {
+        line_start = content[line_start..tail_start]
+            .find('\n')
+            .map_or(tail_start, |newline| line_start + newline + 1);
+    }
+    while line_start < tail_start {
+        let Some(newline) = content[line_start..tail_start].find('\n') else {
+            break;
+        };
+        let line_end = line_start + newline + 1;
+        if keep.is_match(&content[line_start..line_end - 1]) {
+            kept_lines.push((line_start, line_end));
+        }
+        line_start = line_end;
+    }
+
+    let mut result = content[..head_end].to_string();
+    let mut omitted_tokens = 0;
+    let mut gap_start = head_end;
+    for (start, end) in kept_lines.into_iter().chain([(tail_start, tail_start)]) {
+        if start > gap_start {
+            let omitted = &content[gap_start..start];
+            omitted_tokens += TokenEstimator::estimate_tokens(omitted);
+            if at_line_start(gap_start) && at_line_start(start) {
+                let lines = omitted.matches('\n').count();
+                result.push_str(&format!("\n... [{} lines omitted] ...\n\n", lines));
             } else {
-                links.join(", ")
-            };
-            marker.push_str(&format!("\nKey imports: {}", imports_str));
+                result.push_str(&format!(
+                    "\n... [~{} tokens omitted] ...\n\n",
+                    TokenEstimator::estimate_tokens(omitted)
+                ));
+            }
         }
+        result.push_str(&content[start..end]);
+        gap_start = end;
+    }
+    result.push_str(&content[tail_start..]);
 
-        // Empty line before "To get full content" (matches Python's marker format)
-        marker.push_str(&format!(
-            "\n\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+    if include_summary {
+        let kept_tokens = total_tokens.saturating_sub(omitted_tokens);
+        if !result.ends_with('\n') {
+            result.push('\n');
+        }
+        result.push_str(&format!(
+            "\n{}\nTOKEN TRUNCATED: kept ~{}/{} tokens ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            "=".repeat(70),
+            kept_tokens,
+            total_tokens,
+            (total_tokens - kept_tokens) * 100 / total_tokens,
             file_path,
             file_path,
             "=".repeat(70)
         ));
-        truncated.push_str(&marker);
     }
 
-    (truncated, true)
+    (result, true)
 }
 
-/// Truncate content using smart mode (language-aware)
+/// Remove the comment lines of the file at `path`, as its language's
+/// [`core::SpectralSignature`] recognizes them
 ///
-/// Smart mode uses language analyzers to identify important sections
-/// and keeps them while truncating less important parts.
-pub fn truncate_smart(content: &str, max_lines: usize, file_path: &str) -> (String, bool) {
-    truncate_smart_with_options(content, max_lines, file_path, true)
+/// Returns the content and whether any line was removed; files in a
+/// language the spectrograph doesn't know are left alone.
+pub fn strip_comments(content: &str, path: &str) -> (String, bool) {
+    strip_comments_keeping(content, path, &KeepPatterns::default())
 }
 
-/// Truncate content using smart mode with options
-///
-/// # Arguments
-///
-/// * `content` - The content to truncate
-/// * `max_lines` - Maximum number of lines to keep
-/// * `file_path` - File path for the truncation marker
-/// * `include_summary` - Whether to include the summary marker
-///
-/// # Returns
+/// Like [`strip_comments`], keeping the comment lines `keep` matches
+pub fn strip_comments_keeping(content: &str, path: &str, keep: &KeepPatterns) -> (String, bool) {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    match core::STELLAR_LIBRARY.get_by_extension(ext) {
+        Some(signature) => {
+            let (stripped, removed) =
+                signature.strip_comments_keeping(content, |line| keep.is_match(line));
+            (stripped, removed > 0)
+        }
+        None => (content.to_string(), false),
+    }
+}
+
+/// Compiled `truncate_keep_patterns`: lines no truncation mode drops
 ///
-/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
-pub fn truncate_smart_with_options(
-    content: &str,
-    max_lines: usize,
-    file_path: &str,
-    include_summary: bool,
-) -> (String, bool) {
-    let lines: Vec<&str> = python_style_split(content);
-    let total_lines = lines.len();
+/// Matched lines are kept on top of what the mode keeps, with gap markers
+/// around them where the mode uses those, and comment stripping leaves them
+/// in too. Useful for `TODO`, `FIXME` and `SAFETY` comments.
+#[derive(Debug, Clone, Default)]
+pub struct KeepPatterns {
+    patterns: Vec<regex::Regex>,
+}
 
-    if max_lines == 0 || total_lines <= max_lines {
-        return (content.to_string(), false);
+impl KeepPatterns {
+    /// Compile `patterns`, naming the first one that isn't a valid regex
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                regex::Regex::new(p)
+                    .map_err(|e| format!("Invalid truncate keep pattern '{}': {}", p, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
     }
 
-    // Try to get an analyzer for this file type
-    if let Some(analyzer) = analyzers::get_analyzer_for_file(file_path) {
-        let analysis = analyzer.analyze(content, file_path);
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
 
-        // Collect important line ranges (imports, class/function definitions)
-        let mut important_lines: Vec<usize> = Vec::new();
+    /// Whether `line` matches any of the patterns
+    pub fn is_match(&self, line: &str) -> bool {
+        self.patterns.iter().any(|p| p.is_match(line))
+    }
 
-        // Always keep first few lines (often contain shebang, docstring, imports)
-        for i in 1..=10.min(total_lines) {
-            important_lines.push(i);
-        }
+    /// The patterns, as given
+    pub fn as_strs(&self) -> Vec<&str> {
+        self.patterns.iter().map(|p| p.as_str()).collect()
+    }
 
-        // Add lines around class/function definitions
-        for (i, line) in lines.iter().enumerate() {
-            let line_num = i + 1;
-            // Check if this line starts a class or function
-            if line.trim_start().starts_with("class ")
-                || line.trim_start().starts_with("def ")
-                || line.trim_start().starts_with("fn ")
-                || line.trim_start().starts_with("pub fn ")
-                || line.trim_start().starts_with("function ")
-                || line.trim_start().starts_with("const ")
-                || line.trim_start().starts_with("struct ")
-                || line.trim_start().starts_with("impl ")
-                || line.trim_start().starts_with("enum ")
-            {
-                // Add this line and a few lines after (signature + docstring)
-                for j in line_num..=(line_num + 5).min(total_lines) {
-                    important_lines.push(j);
-                }
-            }
+    /// Lines (1-indexed) of `lines` that match
+    fn matching_lines(&self, lines: &[&str]) -> Vec<usize> {
+        if self.is_empty() {
+            return Vec::new();
         }
+        (1..=lines.len())
+            .filter(|&i| self.is_match(lines[i - 1]))
+            .collect()
+    }
+}
 
-        // Add critical sections from analysis
-        for (start, end) in &analysis.critical_sections {
-            for line_num in *start..=*end {
-                important_lines.push(line_num);
-            }
-        }
+impl PartialEq for KeepPatterns {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_strs() == other.as_strs()
+    }
+}
 
-        // Deduplicate and sort
-        important_lines.sort();
-        important_lines.dedup();
+impl Eq for KeepPatterns {}
 
-        // If we have more important lines than max_lines, or found very few important lines
-        // (non-code file), use Python's default truncation strategy: keep first 40%, gap, keep last 10%
-        if important_lines.len() > max_lines
-            || (important_lines.len() < 50 && total_lines > max_lines)
-        {
-            return truncate_with_gap_markers(
-                content,
-                max_lines,
-                file_path,
-                include_summary,
-                Some(&analysis.language),
-            );
+/// The first `max_lines` of `lines` joined, then every later line `keep`
+/// matches, after a gap marker
+fn head_keeping(lines: &[&str], max_lines: usize, keep: &KeepPatterns) -> String {
+    let mut result = lines[..max_lines.min(lines.len())].join("\n");
+    let mut last = max_lines;
+    for line_num in keep.matching_lines(lines) {
+        if line_num <= last {
+            continue;
         }
-
-        // If file is smaller than max_lines after finding important sections,
-        // just return the original content
-        if total_lines <= max_lines {
-            return (content.to_string(), false);
+        result.push('\n');
+        if line_num > last + 1 {
+            let gap_size = line_num - last - 1;
+            result.push_str(&format!("\n... [{} lines omitted] ...\n\n", gap_size));
         }
+        result.push_str(lines[line_num - 1]);
+        last = line_num;
+    }
+    result
+}
 
-        // Build output with kept lines and gap markers
-        let mut result = String::new();
-        let mut last_line = 0;
-
-        for &line_num in &important_lines {
-            // Add gap marker if there's a gap
-            if line_num > last_line + 1 && last_line > 0 {
-                let gap_size = line_num - last_line - 1;
-                result.push_str(&format!("\n... [{} lines omitted] ...\n\n", gap_size));
-            }
+/// Whether `c` is a control character [`escape_control_chars`] escapes:
+/// any but tab, newline and carriage return
+fn is_escaped_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
 
-            if line_num <= total_lines {
-                result.push_str(lines[line_num - 1]);
-                result.push('\n');
-            }
-            last_line = line_num;
+/// Number of unresolved merge conflicts in `content`
+///
+/// A conflict is a `<<<<<<<` line followed by a `=======` line and then a
+/// `>>>>>>>` line, git's seven-character markers at the start of the line.
+pub fn count_merge_conflicts(content: &str) -> usize {
+    fn marker(line: &str, c: char) -> bool {
+        let rest = line.trim_end_matches('\r');
+        rest.len() >= 7
+            && rest.chars().take(7).all(|x| x == c)
+            && rest[7..].chars().next().is_none_or(|x| x == ' ')
+    }
+
+    let mut conflicts = 0;
+    // 0: outside a conflict, 1: in "ours", 2: in "theirs"
+    let mut state = 0;
+    for line in content.lines() {
+        if marker(line, '<') {
+            state = 1;
+        } else if state == 1 && line.trim_end_matches('\r') == "=======" {
+            state = 2;
+        } else if state == 2 && marker(line, '>') {
+            conflicts += 1;
+            state = 0;
         }
+    }
+    conflicts
+}
 
-        // Add final truncation marker only if include_summary is true
-        if include_summary {
-            let kept_count = important_lines.len();
-            let omitted = total_lines - kept_count;
-            if omitted > 0 {
-                result.push_str(&format!(
-                    "\n{}\nSMART TRUNCATED: kept {}/{} lines ({}% reduction)\nLanguage: {} | Category: {}\n{}\n",
-                    "=".repeat(70),
-                    kept_count,
-                    total_lines,
-                    omitted * 100 / total_lines,
-                    analysis.language,
-                    analysis.category,
-                    "=".repeat(70)
-                ));
-            }
+/// Escape control characters as `\xNN`
+///
+/// Tab, newline and carriage return are kept. Returns the content and
+/// whether anything was escaped.
+pub fn escape_control_chars(content: &str) -> (String, bool) {
+    if !content.chars().any(is_escaped_control) {
+        return (content.to_string(), false);
+    }
+    let mut escaped = String::with_capacity(content.len() + 8);
+    for c in content.chars() {
+        if is_escaped_control(c) {
+            escaped.push_str(&format!("\\x{:02x}", c as u32));
+        } else {
+            escaped.push(c);
         }
-
-        return (result, true);
     }
-
-    // Fall back to gap-based truncation if no analyzer available (Python behavior)
-    truncate_with_gap_markers(content, max_lines, file_path, include_summary, None)
+    (escaped, true)
 }
 
-/// Truncate content using structure mode (signatures only)
+/// Undo [`escape_control_chars`]
 ///
-/// Structure mode extracts only class/function signatures, removing all bodies.
-pub fn truncate_structure(content: &str, file_path: &str) -> (String, bool) {
-    truncate_structure_with_options(content, file_path, true)
+/// A `\xNN` the file contained literally is turned into a control character
+/// too, so callers should prefer the content as it is when it checks out.
+pub fn unescape_control_chars(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(at) = rest.find("\\x") {
+        result.push_str(&rest[..at]);
+        let control = rest
+            .get(at + 2..at + 4)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32)
+            .filter(|&c| is_escaped_control(c));
+        match control {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[at + 4..];
+            }
+            None => {
+                result.push_str("\\x");
+                rest = &rest[at + 2..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
 }
 
-/// Truncate content using structure mode with options
+/// Check if a file should skip truncation based on exclude patterns
 ///
 /// # Arguments
 ///
-/// * `content` - The content to truncate
-/// * `file_path` - File path for the truncation marker
-/// * `include_summary` - Whether to include the summary marker
+/// * `path` - File path to check
+/// * `patterns` - Patterns to match against
 ///
 /// # Returns
 ///
-/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
-pub fn truncate_structure_with_options(
-    content: &str,
-    file_path: &str,
-    include_summary: bool,
-) -> (String, bool) {
-    // Use 0 for max_lines to disable smart fallback (backward compatible)
-    truncate_structure_with_fallback(content, file_path, include_summary, 0)
+/// * `true` if the file should skip truncation
+pub fn should_skip_truncation(path: &str, patterns: &[String]) -> bool {
+    matches_patterns(path, patterns)
 }
 
-/// Truncate content using structure mode with smart fallback (matches Python behavior)
+/// Serialize a file entry into Plus/Minus format
 ///
 /// # Arguments
 ///
-/// * `content` - The content to truncate
-/// * `file_path` - File path for the truncation marker
-/// * `include_summary` - Whether to include the summary marker
-/// * `max_lines` - Maximum lines for smart fallback when no signatures found (0 = no fallback)
+/// * `entry` - The file entry to serialize
 ///
 /// # Returns
 ///
-/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
-pub fn truncate_structure_with_fallback(
+/// * Serialized string in Plus/Minus format
+pub fn serialize_file(entry: &FileEntry) -> String {
+    serialize_file_with_truncation(entry, 0, "simple")
+}
+
+/// Truncate content using Python's default strategy: keep first 40%, gap, keep last 10%
+///
+/// This matches Python's LanguageAnalyzer.get_truncate_ranges() default behavior
+/// for files without specialized analyzers. The output includes gap markers
+/// to show where content was omitted.
+fn truncate_with_gap_markers(
     content: &str,
+    max_lines: usize,
     file_path: &str,
     include_summary: bool,
-    max_lines: usize,
+    language: Option<&str>,
+    keep: &KeepPatterns,
 ) -> (String, bool) {
     let lines: Vec<&str> = python_style_split(content);
     let total_lines = lines.len();
 
-    if total_lines == 0 {
+    if max_lines == 0 || total_lines <= max_lines {
         return (content.to_string(), false);
     }
 
-    // Try to get an analyzer for this file type
-    if let Some(analyzer) = analyzers::get_analyzer_for_file(file_path) {
-        let analysis = analyzer.analyze(content, file_path);
-
-        // Python behavior: Markdown files use specialized get_truncate_ranges()
-        // that keeps most of the file (beginning + header sections)
-        // This prevents false positives from code examples in markdown
-        let path_lower = file_path.to_lowercase();
-        if path_lower.ends_with(".md") || path_lower.ends_with(".markdown") {
-            if max_lines > 0 {
-                return truncate_markdown(content, max_lines, file_path, include_summary);
-            }
-            return truncate_markdown(content, 2000, file_path, include_summary);
-        }
+    // Python's default strategy: keep first 40% and last 10% of max_lines
+    let keep_first = (max_lines as f64 * 0.4) as usize;
+    let keep_last = (max_lines as f64 * 0.1) as usize;
 
-        // Collect signature lines matching Python's get_structure_ranges behavior
-        let mut signature_lines: Vec<usize> = Vec::new();
+    // Calculate range boundaries (1-indexed like Python)
+    let first_end = keep_first.min(total_lines);
+    // Use saturating subtraction to avoid overflow
+    let last_start = total_lines
+        .saturating_sub(keep_last)
+        .saturating_add(1)
+        .max(first_end + 1);
 
-        // Iterate through ALL lines (matching Python behavior)
-        for (i, line) in lines.iter().enumerate() {
-            let line_num = i + 1;
-            let trimmed = line.trim_start();
+    let mut result = String::new();
 
-            // Skip empty lines and pure comments (but keep docstrings)
-            if trimmed.is_empty() {
-                continue;
-            }
+    // Keep first section
+    for line in lines.iter().take(first_end) {
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    // Add gap markers around the lines in between that match a keep pattern
+    let mut last = first_end;
+    let mut kept_between = 0;
+    for line_num in keep.matching_lines(&lines) {
+        if line_num <= first_end || line_num >= last_start {
+            continue;
+        }
+        if line_num > last + 1 {
+            let gap_size = line_num - last - 1;
+            result.push_str(&format!("\n... [{} lines omitted] ...\n\n", gap_size));
+        }
+        result.push_str(lines[line_num - 1]);
+        result.push('\n');
+        last = line_num;
+        kept_between += 1;
+    }
+    if last_start > last + 1 {
+        let gap_size = last_start - last - 1;
+        result.push_str(&format!("\n... [{} lines omitted] ...\n\n", gap_size));
+    }
+
+    // Keep last section
+    for line in lines.iter().skip(last_start - 1) {
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    // Calculate kept lines (excluding the gap marker line itself)
+    let kept_count =
+        first_end + kept_between + total_lines.saturating_sub(last_start).saturating_add(1);
+
+    // Add truncation marker
+    if include_summary {
+        let omitted = total_lines.saturating_sub(kept_count);
+        let mut marker = format!(
+            "\n{}\nTRUNCATED at line {}/{} ({}% reduction)",
+            "=".repeat(70),
+            max_lines,
+            total_lines,
+            omitted * 100 / total_lines,
+        );
+
+        // Add Language line if provided (matches Python's smart mode marker)
+        if let Some(lang) = language {
+            marker.push_str(&format!("\nLanguage: {}", lang));
+        }
+
+        marker.push_str(&format!(
+            "\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            file_path,
+            file_path,
+            "=".repeat(70)
+        ));
+        result.push_str(&marker);
+    }
+
+    (result, true)
+}
+
+/// Truncate markdown content matching Python's MarkdownAnalyzer.get_truncate_ranges()
+///
+/// Python's markdown truncation keeps most of the file:
+/// - Allocates budget for H1/H2 header sections (10 lines each, up to 10% of max per section)
+/// - Fills remaining budget with beginning of file
+///
+/// This effectively keeps first ~max_lines with header supplements
+fn truncate_markdown(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    let lines: Vec<&str> = python_style_split(content);
+    let total_lines = lines.len();
+
+    if max_lines == 0 || total_lines <= max_lines {
+        return (content.to_string(), false);
+    }
+
+    // Python behavior: keep first max_lines (budget filled with beginning)
+    // This matches Python's MarkdownAnalyzer.get_truncate_ranges() which adds (1, budget)
+    let mut truncated = head_keeping(&lines, max_lines, keep);
+
+    // Add smart mode marker with Language: Markdown (matches Python's smart mode output)
+    if include_summary {
+        let reduced_pct = (total_lines - max_lines) * 100 / total_lines;
+
+        // Extract links from markdown (Python's "imports" field for markdown)
+        // Python iterates LINE BY LINE, so multi-line links are not found
+        let link_pattern = regex::Regex::new(r"\[([^\]]+)\]\(([^\)]+)\)").unwrap();
+        let mut links: Vec<&str> = Vec::new();
+        for line in content.lines() {
+            for cap in link_pattern.captures_iter(line) {
+                if let Some(url) = cap.get(2) {
+                    links.push(url.as_str());
+                    if links.len() >= 10 {
+                        break;
+                    }
+                }
+            }
+            if links.len() >= 10 {
+                break;
+            }
+        }
+
+        let mut marker = format!(
+            "\n\n{}\nTRUNCATED at line {}/{} ({}% reduction)\nLanguage: Markdown\nCategory: documentation",
+            "=".repeat(70),
+            max_lines,
+            total_lines,
+            reduced_pct,
+        );
+
+        // Add Key imports if links found (Python shows first 8 + "...")
+        if !links.is_empty() {
+            let imports_str = if links.len() > 8 {
+                format!("{}, ...", links[..8].join(", "))
+            } else {
+                links.join(", ")
+            };
+            marker.push_str(&format!("\nKey imports: {}", imports_str));
+        }
+
+        // Empty line before "To get full content" (matches Python's marker format)
+        marker.push_str(&format!(
+            "\n\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            file_path,
+            file_path,
+            "=".repeat(70)
+        ));
+        truncated.push_str(&marker);
+    }
+
+    (truncated, true)
+}
+
+/// Data rows a CSV or TSV sample keeps when no line limit is given
+const TABLE_SAMPLE_ROWS: usize = 10;
+
+/// Truncate a CSV or TSV file to its header and first rows, then column
+/// statistics for the whole table
+///
+/// Records are kept whole, quoted line breaks included. `max_lines` bounds
+/// the header and sampled rows; 0 samples [`TABLE_SAMPLE_ROWS`] rows. The
+/// gap marker says how many rows it stands for. `None` when the content
+/// doesn't parse as a table.
+fn truncate_table(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> Option<(String, bool)> {
+    let table = analyzers::tabular::Table::parse(content, file_path)?;
+    let header_lines = table.header.end_line;
+    let sample_rows = match max_lines {
+        0 => TABLE_SAMPLE_ROWS,
+        n => n.saturating_sub(header_lines).max(1),
+    };
+    if table.rows.len() <= sample_rows {
+        return Some((content.to_string(), false));
+    }
+
+    let lines: Vec<&str> = python_style_split(content);
+    let record_lines = |start: usize, end: usize| lines[start - 1..end].join("\n");
+    let keeps = |start: usize, end: usize| (start..=end).any(|i| keep.is_match(lines[i - 1]));
+
+    let mut result = record_lines(1, header_lines);
+    result.push('\n');
+    let (mut last_line, mut skipped_rows, mut kept_rows) = (header_lines, 0, 0);
+    let gap = |result: &mut String, omitted_lines: usize, rows: usize| {
+        result.push_str(&format!(
+            "\n... [{} lines omitted: {} rows] ...\n\n",
+            omitted_lines, rows
+        ));
+    };
+    for (i, row) in table.rows.iter().enumerate() {
+        if i >= sample_rows && !keeps(row.start_line, row.end_line) {
+            skipped_rows += 1;
+            continue;
+        }
+        if skipped_rows > 0 {
+            gap(&mut result, row.start_line - last_line - 1, skipped_rows);
+            skipped_rows = 0;
+        }
+        result.push_str(&record_lines(row.start_line, row.end_line));
+        result.push('\n');
+        last_line = row.end_line;
+        kept_rows += 1;
+    }
+    if skipped_rows > 0 {
+        let end = table.rows.last().map_or(last_line, |row| row.end_line);
+        gap(&mut result, end - last_line, skipped_rows);
+    }
+
+    if !result.ends_with("\n\n") {
+        result.push('\n');
+    }
+    result.push_str(&format!("Columns ({} rows):\n", table.rows.len()));
+    for stats in table.column_stats() {
+        result.push_str(&format!("  {}\n", stats));
+    }
+
+    if include_summary {
+        let total_rows = table.rows.len();
+        result.push_str(&format!(
+            "\n{}\nTABLE SAMPLED: kept {}/{} rows ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            "=".repeat(70),
+            kept_rows,
+            total_rows,
+            (total_rows - kept_rows) * 100 / total_rows,
+            file_path,
+            file_path,
+            "=".repeat(70)
+        ));
+    }
+
+    Some((result, true))
+}
+
+/// Lines a JSON or YAML file needs before `structure` mode outlines it when
+/// no line limit is given
+const SCHEMA_MIN_LINES: usize = 200;
+
+/// Replace a JSON or YAML file longer than `max_lines` (0:
+/// [`SCHEMA_MIN_LINES`]) with an outline of its schema: keys, value types
+/// and array lengths (see [`analyzers::schema`])
+///
+/// Lines `keep` matches stay, with gap markers between them, ahead of the
+/// outline. `None` when the content doesn't parse, or the outline wouldn't
+/// be shorter.
+fn truncate_schema(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> Option<(String, bool)> {
+    let mut lines: Vec<&str> = python_style_split(content);
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+    let limit = match max_lines {
+        0 => SCHEMA_MIN_LINES,
+        n => n,
+    };
+    if lines.len() <= limit {
+        return None;
+    }
+    let schema = analyzers::schema::Schema::parse(content, file_path)?;
+    let outline = schema.outline();
+    if outline.len() >= lines.len() {
+        return None;
+    }
+
+    let mut result = String::new();
+    let gap = |result: &mut String, omitted: usize| {
+        let lead = if result.is_empty() { "" } else { "\n" };
+        result.push_str(&format!("{}... [{} lines omitted] ...\n\n", lead, omitted));
+    };
+    let mut next = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if keep.is_match(line) {
+            if i > next {
+                gap(&mut result, i - next);
+            }
+            result.push_str(line);
+            result.push('\n');
+            next = i + 1;
+        }
+    }
+    if next < lines.len() {
+        gap(&mut result, lines.len() - next);
+    }
+    if !result.ends_with("\n\n") {
+        result.push('\n');
+    }
+    result.push_str(&format!("Schema ({}):\n", schema.describe()));
+    for line in &outline {
+        result.push_str(&format!("  {}\n", line));
+    }
+
+    if include_summary {
+        result.push_str(&format!(
+            "\n{}\nSCHEMA OUTLINE: {} lines as {} lines of keys, types and array lengths ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            "=".repeat(70),
+            lines.len(),
+            outline.len(),
+            (lines.len() - outline.len()) * 100 / lines.len(),
+            file_path,
+            file_path,
+            "=".repeat(70)
+        ));
+    }
+
+    Some((result, true))
+}
+
+/// Tables a SQL gap marker names before it counts the rest
+const SQL_MARKER_TABLES: usize = 3;
+
+/// Replace a SQL file's data statements, and with `bodies` its routines'
+/// bodies, with gap markers saying what they held (see [`analyzers::sql`])
+///
+/// Definitions, comments and other statements stay, and so do lines `keep`
+/// matches. Data statements with only blank lines between them share a
+/// marker. `None` when there is nothing to collapse.
+fn truncate_sql(
+    content: &str,
+    file_path: &str,
+    include_summary: bool,
+    bodies: bool,
+    keep: &KeepPatterns,
+) -> Option<(String, bool)> {
+    use analyzers::sql::StatementKind;
+
+    let mut lines: Vec<&str> = python_style_split(content);
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+    let statements = analyzers::sql::parse(content);
+
+    // Omitted line ranges and what they held
+    let mut runs: Vec<(usize, usize, Vec<&analyzers::sql::Statement>)> = Vec::new();
+    for statement in &statements {
+        match (statement.kind, statement.body) {
+            (StatementKind::Data, _) => match runs.last_mut() {
+                Some((_, end, held))
+                    if held[0].kind == StatementKind::Data
+                        && lines[*end..statement.start_line - 1]
+                            .iter()
+                            .all(|l| l.trim().is_empty()) =>
+                {
+                    *end = statement.end_line;
+                    held.push(statement);
+                }
+                _ => runs.push((statement.start_line, statement.end_line, vec![statement])),
+            },
+            (StatementKind::Routine, Some((first, last))) if bodies => {
+                runs.push((first, last, vec![statement]))
+            }
+            _ => {}
+        }
+    }
+    if runs.is_empty() {
+        return None;
+    }
+
+    let note = |held: &[&analyzers::sql::Statement]| {
+        if held[0].kind == StatementKind::Routine {
+            return match &held[0].name {
+                Some(name) => format!("body of {}", name),
+                None => "routine body".to_string(),
+            };
+        }
+        let mut tables: Vec<&str> = Vec::new();
+        for name in held.iter().filter_map(|s| s.name.as_deref()) {
+            if !tables.contains(&name) {
+                tables.push(name);
+            }
+        }
+        let mut into = tables[..tables.len().min(SQL_MARKER_TABLES)].join(", ");
+        if tables.len() > SQL_MARKER_TABLES {
+            into.push_str(&format!(" and {} more", tables.len() - SQL_MARKER_TABLES));
+        }
+        let rows: usize = held.iter().map(|s| s.rows).sum();
+        let mut note = format!(
+            "{} data statement{}",
+            held.len(),
+            if held.len() == 1 { "" } else { "s" }
+        );
+        if rows > 0 {
+            note.push_str(&format!(", {} rows", rows));
+        }
+        if !into.is_empty() {
+            note.push_str(&format!(" into {}", into));
+        }
+        note
+    };
+
+    let mut result = String::new();
+    let mut kept = 0;
+    let mut next = 1;
+    let emit_gap = |result: &mut String, from: usize, to: usize, note: Option<String>| {
+        if to >= from {
+            let note = note.map_or(String::new(), |n| format!(": {}", n));
+            result.push_str(&format!(
+                "... [{} lines omitted{}] ...\n",
+                to - from + 1,
+                note
+            ));
+        }
+    };
+    for (start, end, held) in &runs {
+        for line in &lines[next - 1..start - 1] {
+            result.push_str(line);
+            result.push('\n');
+            kept += 1;
+        }
+        let mut note = Some(note(held));
+        let mut from = *start;
+        for i in *start..=*end {
+            if keep.is_match(lines[i - 1]) {
+                if i > from {
+                    emit_gap(&mut result, from, i - 1, note.take());
+                }
+                result.push_str(lines[i - 1]);
+                result.push('\n');
+                kept += 1;
+                from = i + 1;
+            }
+        }
+        emit_gap(&mut result, from, *end, note);
+        next = end + 1;
+    }
+    for line in &lines[next - 1..] {
+        result.push_str(line);
+        result.push('\n');
+        kept += 1;
+    }
+
+    if include_summary {
+        let data = statements.iter().filter(|s| s.kind == StatementKind::Data);
+        let (count, rows) = data.fold((0, 0), |(n, rows), s| (n + 1, rows + s.rows));
+        let routines = runs
+            .iter()
+            .filter(|(_, _, held)| held[0].kind == StatementKind::Routine)
+            .count();
+        let total = lines.len().max(1);
+        result.push_str(&format!(
+            "\n{}\nSQL STRUCTURE: kept {}/{} lines; omitted {} data statements ({} rows) and {} routine bodies ({}% reduction)\nTo get full content: --include \"{}\" --truncate 0\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            "=".repeat(70),
+            kept,
+            lines.len(),
+            count,
+            rows,
+            routines,
+            (lines.len() - kept) * 100 / total,
+            file_path,
+            file_path,
+            "=".repeat(70)
+        ));
+    }
+
+    Some((result, true))
+}
+
+/// Replace a lockfile with the packages it pins, one `name version` per line
+///
+/// `None` when `file_path` isn't a lockfile [`analyzers::lockfile::Lockfile`]
+/// reads, or its content doesn't parse.
+pub fn summarize_lockfile(content: &str, file_path: &str, include_summary: bool) -> Option<String> {
+    let lockfile = analyzers::lockfile::Lockfile::parse(content, file_path)?;
+    let count = lockfile.dependencies.len();
+    let mut result = format!("Dependencies ({}, {} packages):\n", lockfile.manager, count);
+    for dependency in &lockfile.dependencies {
+        let line = format!("  {} {}", dependency.name, dependency.version);
+        result.push_str(line.trim_end());
+        result.push('\n');
+    }
+    if include_summary {
+        result.push_str(&format!(
+            "\n{}\nLOCKFILE SUMMARY: {} lines as {} dependencies (name and version)\nTo get full content: --lockfiles keep\n/* ZOOM_AFFORDANCE: pm_encoder --zoom file={} */\n{}\n",
+            "=".repeat(70),
+            content.lines().count(),
+            count,
+            file_path,
+            "=".repeat(70)
+        ));
+    }
+    Some(result)
+}
+
+/// Truncate content using smart mode (language-aware)
+///
+/// Smart mode uses language analyzers to identify important sections
+/// and keeps them while truncating less important parts.
+pub fn truncate_smart(content: &str, max_lines: usize, file_path: &str) -> (String, bool) {
+    truncate_smart_with_options(content, max_lines, file_path, true)
+}
+
+/// Truncate content using smart mode with options
+///
+/// # Arguments
+///
+/// * `content` - The content to truncate
+/// * `max_lines` - Maximum number of lines to keep
+/// * `file_path` - File path for the truncation marker
+/// * `include_summary` - Whether to include the summary marker
+///
+/// # Returns
+///
+/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
+pub fn truncate_smart_with_options(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    truncate_smart_keeping(
+        content,
+        max_lines,
+        file_path,
+        include_summary,
+        &KeepPatterns::default(),
+    )
+}
+
+fn truncate_smart_keeping(
+    content: &str,
+    max_lines: usize,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    let lines: Vec<&str> = python_style_split(content);
+    let total_lines = lines.len();
+
+    if max_lines == 0 || total_lines <= max_lines {
+        return (content.to_string(), false);
+    }
+
+    // Data files keep whole records rather than a head and tail of lines
+    if analyzers::tabular::is_table(file_path) {
+        if let Some(result) = truncate_table(content, max_lines, file_path, include_summary, keep) {
+            return result;
+        }
+    }
+    if analyzers::schema::is_structured(file_path) {
+        if let Some(result) = truncate_schema(content, max_lines, file_path, include_summary, keep)
+        {
+            return result;
+        }
+    }
+    if analyzers::sql::is_sql(file_path) {
+        if let Some(result) = truncate_sql(content, file_path, include_summary, false, keep) {
+            return result;
+        }
+    }
+
+    // Try to get an analyzer for this file type
+    if let Some(analysis) = analyzers::analyze_within_budget(file_path, content) {
+        // Collect important line ranges (imports, class/function definitions)
+        let mut important_lines: Vec<usize> = Vec::new();
+
+        // Always keep first few lines (often contain shebang, docstring, imports)
+        for i in 1..=10.min(total_lines) {
+            important_lines.push(i);
+        }
+
+        // Add lines around class/function definitions
+        for (i, line) in lines.iter().enumerate() {
+            let line_num = i + 1;
+            // Check if this line starts a class or function
+            if line.trim_start().starts_with("class ")
+                || line.trim_start().starts_with("def ")
+                || line.trim_start().starts_with("fn ")
+                || line.trim_start().starts_with("pub fn ")
+                || line.trim_start().starts_with("function ")
+                || line.trim_start().starts_with("const ")
+                || line.trim_start().starts_with("struct ")
+                || line.trim_start().starts_with("impl ")
+                || line.trim_start().starts_with("enum ")
+            {
+                // Add this line and a few lines after (signature + docstring)
+                for j in line_num..=(line_num + 5).min(total_lines) {
+                    important_lines.push(j);
+                }
+            }
+        }
+
+        // Add the declarations the analyzer located
+        for (start, end) in &analysis.structure_ranges {
+            important_lines.extend(*start..=*end);
+        }
+
+        // Add critical sections from analysis
+        for (start, end) in &analysis.critical_sections {
+            for line_num in *start..=*end {
+                important_lines.push(line_num);
+            }
+        }
+
+        // Deduplicate and sort
+        important_lines.sort();
+        important_lines.dedup();
+
+        // If we have more important lines than max_lines, or found very few important lines
+        // (non-code file), use Python's default truncation strategy: keep first 40%, gap, keep last 10%
+        if important_lines.len() > max_lines
+            || (important_lines.len() < 50 && total_lines > max_lines)
+        {
+            return truncate_with_gap_markers(
+                content,
+                max_lines,
+                file_path,
+                include_summary,
+                Some(&analysis.language),
+                keep,
+            );
+        }
+
+        // If file is smaller than max_lines after finding important sections,
+        // just return the original content
+        if total_lines <= max_lines {
+            return (content.to_string(), false);
+        }
 
-            // IMPORT STATEMENTS (Python: import, from; Rust: use; JS: import, export)
-            if trimmed.starts_with("import ")
-                || trimmed.starts_with("from ")
-                || trimmed.starts_with("use ")
-                || trimmed.starts_with("export ")
-            {
-                signature_lines.push(line_num);
+        // Lines matching a keep pattern come on top of the budget
+        important_lines.extend(keep.matching_lines(&lines));
+        important_lines.sort();
+        important_lines.dedup();
+
+        // Build output with kept lines and gap markers
+        let mut result = String::new();
+        let mut last_line = 0;
+
+        for &line_num in &important_lines {
+            // Add gap marker if there's a gap
+            if line_num > last_line + 1 && last_line > 0 {
+                let gap_size = line_num - last_line - 1;
+                result.push_str(&format!("\n... [{} lines omitted] ...\n\n", gap_size));
+            }
+
+            if line_num <= total_lines {
+                result.push_str(lines[line_num - 1]);
+                result.push('\n');
+            }
+            last_line = line_num;
+        }
+
+        // Add final truncation marker only if include_summary is true
+        if include_summary {
+            let kept_count = important_lines.len();
+            let omitted = total_lines - kept_count;
+            if omitted > 0 {
+                result.push_str(&format!(
+                    "\n{}\nSMART TRUNCATED: kept {}/{} lines ({}% reduction)\nLanguage: {} | Category: {}\n{}\n",
+                    "=".repeat(70),
+                    kept_count,
+                    total_lines,
+                    omitted * 100 / total_lines,
+                    analysis.language,
+                    analysis.category,
+                    "=".repeat(70)
+                ));
+            }
+        }
+
+        return (result, true);
+    }
+
+    // Fall back to gap-based truncation if no analyzer available (Python behavior)
+    truncate_with_gap_markers(content, max_lines, file_path, include_summary, None, keep)
+}
+
+/// Truncate content using structure mode (signatures only)
+///
+/// Structure mode extracts only class/function signatures, removing all bodies.
+pub fn truncate_structure(content: &str, file_path: &str) -> (String, bool) {
+    truncate_structure_with_options(content, file_path, true)
+}
+
+/// Truncate content using structure mode with options
+///
+/// # Arguments
+///
+/// * `content` - The content to truncate
+/// * `file_path` - File path for the truncation marker
+/// * `include_summary` - Whether to include the summary marker
+///
+/// # Returns
+///
+/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
+pub fn truncate_structure_with_options(
+    content: &str,
+    file_path: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    // Use 0 for max_lines to disable smart fallback (backward compatible)
+    truncate_structure_with_fallback(content, file_path, include_summary, 0)
+}
+
+/// Truncate content using structure mode with smart fallback (matches Python behavior)
+///
+/// # Arguments
+///
+/// * `content` - The content to truncate
+/// * `file_path` - File path for the truncation marker
+/// * `include_summary` - Whether to include the summary marker
+/// * `max_lines` - Maximum lines for smart fallback when no signatures found (0 = no fallback)
+///
+/// # Returns
+///
+/// * `(truncated_content, was_truncated)` - The truncated content and whether truncation occurred
+pub fn truncate_structure_with_fallback(
+    content: &str,
+    file_path: &str,
+    include_summary: bool,
+    max_lines: usize,
+) -> (String, bool) {
+    truncate_structure_keeping(
+        content,
+        file_path,
+        include_summary,
+        max_lines,
+        &KeepPatterns::default(),
+    )
+}
+
+fn truncate_structure_keeping(
+    content: &str,
+    file_path: &str,
+    include_summary: bool,
+    max_lines: usize,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    let lines: Vec<&str> = python_style_split(content);
+    let total_lines = lines.len();
+
+    if total_lines == 0 {
+        return (content.to_string(), false);
+    }
+
+    // A table's structure is its header, a sample and column statistics
+    if analyzers::tabular::is_table(file_path) {
+        if let Some(result) = truncate_table(content, max_lines, file_path, include_summary, keep) {
+            return result;
+        }
+    }
+    // A config file's is its schema
+    if analyzers::schema::is_structured(file_path) {
+        if let Some(result) = truncate_schema(content, max_lines, file_path, include_summary, keep)
+        {
+            return result;
+        }
+    }
+    // A database dump's is its definitions and routine signatures
+    if analyzers::sql::is_sql(file_path) {
+        if let Some(result) = truncate_sql(content, file_path, include_summary, true, keep) {
+            return result;
+        }
+    }
+
+    // Try to get an analyzer for this file type
+    if let Some(analysis) = analyzers::analyze_within_budget(file_path, content) {
+        // Python behavior: Markdown files use specialized get_truncate_ranges()
+        // that keeps most of the file (beginning + header sections)
+        // This prevents false positives from code examples in markdown
+        let path_lower = file_path.to_lowercase();
+        if path_lower.ends_with(".md") || path_lower.ends_with(".markdown") {
+            if max_lines > 0 {
+                return truncate_markdown(content, max_lines, file_path, include_summary, keep);
+            }
+            return truncate_markdown(content, 2000, file_path, include_summary, keep);
+        }
+
+        let mut signature_lines = structure_signature_lines(&lines, content, file_path, &analysis);
+
+        if signature_lines.is_empty() {
+            // No structure found - fall back to smart mode if max_lines > 0 (Python behavior)
+            if max_lines > 0 {
+                return truncate_smart_keeping(
+                    content,
+                    max_lines,
+                    file_path,
+                    include_summary,
+                    keep,
+                );
+            }
+            // Otherwise return first 20 lines (backward compatible)
+            let mut result = head_keeping(&lines, 20, keep);
+            if total_lines > 20 && include_summary {
+                result.push_str(&format!(
+                    "\n\n{}\nSTRUCTURE MODE: No signatures found, showing first 20/{} lines\n{}\n",
+                    "=".repeat(70),
+                    total_lines,
+                    "=".repeat(70)
+                ));
+            }
+            return (result, total_lines > 20);
+        }
+
+        signature_lines.extend(keep.matching_lines(&lines));
+        let result = signature_lines_only(
+            &lines,
+            signature_lines,
+            &analysis.language,
+            file_path,
+            include_summary,
+            &[],
+        );
+        return (result, true);
+    }
+
+    // Without an analyzer (Go, Java), voyager-ast may still find the imports
+    // and signatures
+    if let Some((mut signature_lines, fidelity_notes)) =
+        ast_signature_lines(content, file_path, true).filter(|(l, _)| !l.is_empty())
+    {
+        signature_lines.extend(keep.matching_lines(&lines));
+        let language = core::AstBridge::detect_language(Path::new(file_path));
+        let result = signature_lines_only(
+            &lines,
+            signature_lines,
+            language.name(),
+            file_path,
+            include_summary,
+            &fidelity_notes,
+        );
+        return (result, true);
+    }
+
+    // No analyzer - fall back to smart mode if max_lines > 0 (Python behavior)
+    if max_lines > 0 {
+        return truncate_smart_keeping(content, max_lines, file_path, include_summary, keep);
+    }
+
+    // Otherwise fall back to first 30 lines (backward compatible)
+    let mut result = head_keeping(&lines, 30, keep);
+    if total_lines > 30 && include_summary {
+        result.push_str(&format!(
+            "\n\n{}\nSTRUCTURE MODE: Unknown language, showing first 30/{} lines\n{}\n",
+            "=".repeat(70),
+            total_lines,
+            "=".repeat(70)
+        ));
+    }
+    (result, total_lines > 30)
+}
+
+/// Truncate content to an outline (an API reference view)
+///
+/// Keeps the comments and docstring the file opens with, then each public
+/// declaration's signature with the doc comments and attributes right above
+/// it (and, in Python, the docstring below it). Bodies and private
+/// declarations are dropped: `_name` in Python, anything without `pub` in
+/// Rust (trait members aside), top-level declarations without `export` in
+/// JavaScript, and `private` members elsewhere. Imports are kept, as in every
+/// mode: [`truncate_content`] adds them back with the file's minimum context,
+/// which this function alone leaves out. Markdown, files without an analyzer
+/// and files without any of the above get structure mode.
+pub fn truncate_outline(content: &str, file_path: &str) -> (String, bool) {
+    truncate_outline_with_options(content, file_path, true)
+}
+
+/// Truncate content to an outline, with or without the summary marker
+/// (see [`truncate_outline`])
+pub fn truncate_outline_with_options(
+    content: &str,
+    file_path: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    truncate_outline_keeping(
+        content,
+        file_path,
+        include_summary,
+        &KeepPatterns::default(),
+    )
+}
+
+fn truncate_outline_keeping(
+    content: &str,
+    file_path: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    let lines: Vec<&str> = python_style_split(content);
+    let total_lines = lines.len();
+    let path_lower = file_path.to_lowercase();
+    let analysis = match analyzers::analyze_within_budget(file_path, content) {
+        Some(analysis) if !path_lower.ends_with(".md") && !path_lower.ends_with(".markdown") => {
+            analysis
+        }
+        _ => return truncate_structure_keeping(content, file_path, include_summary, 0, keep),
+    };
+
+    let extension = Path::new(&path_lower)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let signature_lines = structure_signature_lines(&lines, content, file_path, &analysis);
+    let mut kept = module_doc_lines(&lines);
+    kept.extend(outline_declaration_lines(
+        &lines,
+        &signature_lines,
+        extension,
+    ));
+    kept.sort();
+    kept.dedup();
+
+    if kept.is_empty() {
+        return truncate_structure_keeping(content, file_path, include_summary, 0, keep);
+    }
+    kept.extend(keep.matching_lines(&lines));
+    kept.sort();
+    kept.dedup();
+    if kept.len() == total_lines {
+        return (content.to_string(), false);
+    }
+
+    let mut result = String::new();
+    for &line_num in &kept {
+        result.push_str(lines[line_num - 1]);
+        result.push('\n');
+    }
+    if include_summary {
+        result.push_str(&format!(
+            "\n{}\nOUTLINE MODE: Showing docs and public signatures ({}/{} lines)\nLanguage: {}\n\nIncluded: module docs, imports, public signatures and their doc comments\nExcluded: bodies, private declarations\n\nTo get full content: --include \"{}\" --truncate 0\n{}\n",
+            "=".repeat(70),
+            kept.len(),
+            total_lines,
+            analysis.language,
+            file_path,
+            "=".repeat(70)
+        ));
+    }
+    (result, true)
+}
+
+/// Lines (1-indexed) of the comments and docstring a file opens with
+fn module_doc_lines(lines: &[&str]) -> Vec<usize> {
+    let mut kept = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        if let Some(end) = docstring_end(lines, i) {
+            kept.extend(i + 1..=end + 1);
+            i = end + 1;
+            continue;
+        }
+        if is_comment_line(trimmed) {
+            kept.push(i + 1);
+        } else if !trimmed.is_empty() && trimmed != "<?php" {
+            break;
+        }
+        i += 1;
+    }
+    kept
+}
+
+/// Lines (1-indexed) of a source file's module docs and import block, with
+/// the blank lines, comments and package lines between them
+///
+/// Empty for files without a language analyzer, and for Markdown and data
+/// files, whose leading lines are content rather than context.
+fn minimum_context_lines(lines: &[&str], file_path: &str) -> Vec<usize> {
+    let Some(analyzer) = analyzers::get_analyzer_for_file(file_path) else {
+        return Vec::new();
+    };
+    if matches!(
+        analyzer.language_name(),
+        "Markdown" | "JSON" | "YAML" | "csv" | "SQL"
+    ) {
+        return Vec::new();
+    }
+
+    let docs_end = module_doc_lines(lines).last().copied().unwrap_or(0);
+    let mut end = docs_end;
+    let mut depth = 0;
+    for (i, line) in lines.iter().enumerate().skip(docs_end) {
+        let trimmed = line.trim();
+        if depth > 0 || is_import_line(trimmed) {
+            depth = (depth + bracket_balance(trimmed) + brace_balance(trimmed)).max(0);
+            end = i + 1;
+        } else if !(trimmed.is_empty()
+            || is_comment_line(trimmed)
+            || trimmed.starts_with("#![")
+            || ["package ", "namespace ", "\"use strict\"", "'use strict'"]
+                .iter()
+                .any(|p| trimmed.starts_with(p)))
+        {
+            break;
+        }
+    }
+    (1..=end).collect()
+}
+
+/// Opening minus closing braces on a line
+fn brace_balance(trimmed: &str) -> i32 {
+    trimmed.matches('{').count() as i32 - trimmed.matches('}').count() as i32
+}
+
+/// `truncated`, with the module docs and imports of `original` it dropped
+/// put back (see [`minimum_context_lines`])
+///
+/// Every truncation goes through this, whatever the mode, so no file is
+/// left as a fragment that doesn't say what it is or what it uses. Lines
+/// put back inside a gap split its marker; lines past the last kept one go
+/// before whatever the mode appended (the summary marker). Token gaps can
+/// start or end mid-line: a cut inside the first line is replaced by the
+/// whole line, and a token marker after lines put back only counts what is
+/// still left out.
+fn with_minimum_context(original: &str, file_path: &str, truncated: String) -> String {
+    use std::collections::HashSet;
+
+    let original_lines = python_style_split(original);
+    let map = line_map(&truncated, original);
+    let present: HashSet<usize> = map.iter().flatten().copied().collect();
+    let mut missing: Vec<usize> = minimum_context_lines(&original_lines, file_path)
+        .into_iter()
+        .filter(|n| !present.contains(n))
+        .collect();
+    if missing.is_empty() {
+        return truncated;
+    }
+    missing.reverse();
+
+    let lines = python_style_split(&truncated);
+    // The first line cut off by a token gap, not kept whole
+    let head_fragment = lines.len() > 1
+        && map.first() == Some(&None)
+        && !lines[0].is_empty()
+        && token_gap_size(lines[1]).is_some()
+        && original_lines[0].starts_with(lines[0]);
+    if head_fragment && missing.last() != Some(&1) {
+        missing.push(1);
+    }
+    fn gap(n: usize) -> String {
+        format!("... [{} lines omitted] ...", n)
+    }
+    // The rest of `missing` below `until`, after original line `previous`;
+    // returns the tokens put back
+    let put_back =
+        |result: &mut Vec<String>, missing: &mut Vec<usize>, mut previous: usize, until: usize| {
+            let mut tokens = 0;
+            while let Some(n) = missing.pop_if(|n| *n < until) {
+                if previous > 0 && previous + 1 < n {
+                    result.extend([String::new(), gap(n - previous - 1), String::new()]);
+                }
+                result.push(original_lines[n - 1].to_string());
+                tokens += TokenEstimator::estimate_tokens(original_lines[n - 1]);
+                previous = n;
+            }
+            tokens
+        };
+    // Output lines after the last kept line or marker
+    let tail = map
+        .iter()
+        .enumerate()
+        .rposition(|(i, index)| index.is_some() || gap_marker_size(lines[i]).is_some())
+        .map_or(0, |i| i + 1);
+
+    let mut result: Vec<String> = Vec::with_capacity(lines.len() + missing.len());
+    // Next original line (1-indexed) the output reaches
+    let mut cursor = 1;
+    let mut skip_blank = false;
+    // Tokens put back since the last line kept from `truncated`
+    let mut put_back_tokens = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if skip_blank && line.trim().is_empty() {
+            skip_blank = false;
+            continue;
+        }
+        skip_blank = false;
+        if i == 0 && head_fragment {
+            // Whole lines up to the first one kept replace the fragment
+            let until = map.iter().flatten().next().copied().unwrap_or(usize::MAX);
+            put_back_tokens = put_back(&mut result, &mut missing, 0, until)
+                .saturating_sub(TokenEstimator::estimate_tokens(line));
+            result.push(String::new());
+            cursor = 2;
+            continue;
+        }
+        if i == tail {
+            // Dropped without a marker after the last kept line
+            put_back_tokens += put_back(&mut result, &mut missing, cursor - 1, usize::MAX);
+        }
+        if let Some(omitted) = token_gap_size(line) {
+            if put_back_tokens > 0 {
+                if result.last().is_some_and(|l| !l.trim().is_empty()) {
+                    result.push(String::new());
+                }
+                match omitted.saturating_sub(put_back_tokens) {
+                    0 => {
+                        result.pop();
+                        skip_blank = true;
+                    }
+                    left => result.push(format!("... [~{} tokens omitted] ...", left)),
+                }
+                put_back_tokens = 0;
                 continue;
             }
+        }
+        if let Some(omitted) = gap_marker_size(line) {
+            let end = cursor + omitted;
+            if missing.last().is_some_and(|&n| n < end) {
+                // Split the marker around the lines put back
+                if result.last().is_some_and(|l| l.trim().is_empty())
+                    && missing.last() == Some(&cursor)
+                {
+                    result.pop();
+                }
+                let mut from = cursor;
+                while let Some(n) = missing.pop_if(|n| *n < end) {
+                    if n > from {
+                        if from > cursor {
+                            result.push(String::new());
+                        }
+                        result.extend([gap(n - from), String::new()]);
+                    }
+                    result.push(original_lines[n - 1].to_string());
+                    from = n + 1;
+                }
+                if from < end {
+                    result.extend([String::new(), gap(end - from)]);
+                } else {
+                    skip_blank = true;
+                }
+            } else {
+                result.push(line.to_string());
+            }
+            cursor = end;
+            put_back_tokens = 0;
+            continue;
+        }
+        if let Some(index) = map.get(i).copied().flatten() {
+            // Dropped without a marker before a kept line
+            while let Some(n) = missing.pop_if(|n| *n < index) {
+                result.push(original_lines[n - 1].to_string());
+            }
+            cursor = index + 1;
+            put_back_tokens = 0;
+        }
+        result.push(line.to_string());
+    }
+    put_back(&mut result, &mut missing, cursor - 1, usize::MAX);
+    result.join("\n")
+}
+
+/// Lines (1-indexed) outline mode keeps of the declarations among
+/// `signature_lines` (see [`truncate_outline`])
+fn outline_declaration_lines(
+    lines: &[&str],
+    signature_lines: &[usize],
+    extension: &str,
+) -> Vec<usize> {
+    /// A declaration whose members are indented below it
+    struct Enclosing {
+        indent: usize,
+        dropped: bool,
+        members_public: bool,
+    }
+
+    let mut kept = Vec::new();
+    let mut enclosing: Vec<Enclosing> = Vec::new();
+    // Whether the declaration being read is kept, and its open brackets
+    let mut keep = false;
+    let mut depth = 0i32;
+    let mut previous: Option<usize> = None;
+    // First line of the attributes right above the next declaration
+    let mut attributes: Option<usize> = None;
+
+    for &line_num in signature_lines {
+        let line = lines[line_num - 1];
+        let trimmed = line.trim();
+        let adjacent = previous == Some(line_num - 1);
+        previous = Some(line_num);
+
+        // The rest of a multi-line signature (or attribute)
+        if adjacent && (depth > 0 || continues_signature(trimmed, lines[line_num - 2].trim())) {
+            depth += bracket_balance(trimmed);
+            if keep {
+                kept.push(line_num);
+            }
+            continue;
+        }
+        depth = bracket_balance(trimmed);
+
+        if trimmed.starts_with("#[") || trimmed.starts_with('@') {
+            if !(adjacent && attributes.is_some()) {
+                attributes = Some(line_num);
+            }
+            keep = false;
+            continue;
+        }
+        let first = match attributes.take() {
+            Some(start) if adjacent => start,
+            _ => line_num,
+        };
+        if is_import_line(trimmed) {
+            keep = false;
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        while enclosing.last().is_some_and(|e| e.indent >= indent) {
+            enclosing.pop();
+        }
+        let parent = enclosing.last();
+        keep = !parent.is_some_and(|p| p.dropped)
+            && !is_private_declaration(
+                trimmed,
+                extension,
+                parent.is_some_and(|p| p.members_public),
+                parent.is_none(),
+            );
+        enclosing.push(Enclosing {
+            indent,
+            dropped: !keep,
+            members_public: extension == "rs"
+                && (trimmed.contains("trait ")
+                    || (trimmed.starts_with("impl") && trimmed.contains(" for "))),
+        });
 
-            // SHEBANG / MODULE DOCS (first few lines)
-            if line_num <= 5 && (trimmed.starts_with("#!") || trimmed.starts_with("//!")) {
-                signature_lines.push(line_num);
-                continue;
+        if keep {
+            // Doc comments and attributes right above
+            let mut top = first;
+            while top > 1 && {
+                let above = lines[top - 2].trim();
+                is_comment_line(above) || above.starts_with("#[") || above.starts_with('@')
+            } {
+                top -= 1;
             }
+            kept.extend(top..=line_num);
+        }
+    }
 
-            // MODULE-LEVEL DOCSTRINGS (first 10 lines)
-            if line_num <= 10 && (trimmed.starts_with("\"\"\"") || trimmed.starts_with("'''")) {
-                signature_lines.push(line_num);
-                continue;
+    // Python docstrings follow the signature
+    if matches!(extension, "py" | "pyw") {
+        let signature_ends: Vec<usize> = kept
+            .iter()
+            .copied()
+            .filter(|&n| lines[n - 1].trim_end().ends_with(':'))
+            .collect();
+        for line_num in signature_ends {
+            let next = (line_num..lines.len()).find(|&i| !lines[i].trim().is_empty());
+            if let Some(end) = next.and_then(|i| docstring_end(lines, i)) {
+                kept.extend(next.unwrap_or(line_num) + 1..=end + 1);
             }
+        }
+    }
+    kept
+}
 
-            // DECORATORS (Python @decorator)
-            if trimmed.starts_with("@") {
-                signature_lines.push(line_num);
-                continue;
-            }
+/// The index of the line a docstring opening on `lines[start]` closes on;
+/// the last line when it never closes
+fn docstring_end(lines: &[&str], start: usize) -> Option<usize> {
+    let trimmed = lines[start].trim_start();
+    let trimmed = trimmed
+        .strip_prefix(['r', 'u', 'b', 'R', 'U', 'B'])
+        .filter(|rest| rest.starts_with(['"', '\'']))
+        .unwrap_or(trimmed);
+    let delimiter = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|d| trimmed.starts_with(d))?;
+    if trimmed[3..].contains(delimiter) {
+        return Some(start);
+    }
+    Some(
+        (start + 1..lines.len())
+            .find(|&i| lines[i].contains(delimiter))
+            .unwrap_or(lines.len() - 1),
+    )
+}
 
-            // CLASS DEFINITIONS
-            if trimmed.starts_with("class ")
-                || trimmed.starts_with("pub struct ")
-                || trimmed.starts_with("struct ")
-            {
-                signature_lines.push(line_num);
-                continue;
-            }
+/// Whether `trimmed` is a comment line (attributes aside)
+fn is_comment_line(trimmed: &str) -> bool {
+    (trimmed.starts_with('#') && !trimmed.starts_with("#[") && !trimmed.starts_with("#!["))
+        || ["//", "/*", "*", "--"]
+            .iter()
+            .any(|p| trimmed.starts_with(p))
+}
 
-            // FUNCTION DEFINITIONS
-            if trimmed.starts_with("def ")
-                || trimmed.starts_with("async def ")
-                || trimmed.starts_with("fn ")
-                || trimmed.starts_with("pub fn ")
-                || trimmed.starts_with("async fn ")
-                || trimmed.starts_with("pub async fn ")
-                || trimmed.starts_with("function ")
-                || trimmed.starts_with("export function ")
-                || trimmed.starts_with("async function ")
-            {
-                signature_lines.push(line_num);
-                continue;
-            }
+/// Whether `trimmed` imports or includes other code
+fn is_import_line(trimmed: &str) -> bool {
+    [
+        "import ", "from ", "use ", "using ", "require", "include", "load ", "#include",
+    ]
+    .iter()
+    .any(|p| trimmed.starts_with(p))
+}
 
-            // OTHER STRUCTURAL ELEMENTS (Rust: impl, trait, enum, const)
-            if trimmed.starts_with("impl ")
-                || trimmed.starts_with("trait ")
-                || trimmed.starts_with("pub trait ")
-                || trimmed.starts_with("enum ")
-                || trimmed.starts_with("pub enum ")
-                || trimmed.starts_with("const ")
-                || trimmed.starts_with("pub const ")
-                || trimmed.starts_with("pub mod ")
-                || trimmed.starts_with("mod ")
-            {
-                signature_lines.push(line_num);
-                continue;
-            }
+/// Whether `trimmed`, following `previous`, carries on the signature
+/// started above it
+fn continues_signature(trimmed: &str, previous: &str) -> bool {
+    trimmed.starts_with([')', ']', '>', '{', '|', '+', ':'])
+        || ["->", "where", "extends ", "implements ", "throws "]
+            .iter()
+            .any(|p| trimmed.starts_with(p))
+        || previous.ends_with([',', '(', '[', '<', '=', '|', '+'])
+        || previous.ends_with("->")
+        || previous.ends_with("where")
+}
 
-            // JS/TS: interface, type definitions, arrow functions
-            if trimmed.starts_with("interface ")
-                || trimmed.starts_with("export interface ")
-                || trimmed.starts_with("type ")
-                || trimmed.starts_with("export type ")
-                || (trimmed.starts_with("const ") && trimmed.contains("=>"))
-            {
-                signature_lines.push(line_num);
-                continue;
-            }
+/// Parentheses and square brackets `trimmed` opens, less those it closes
+fn bracket_balance(trimmed: &str) -> i32 {
+    trimmed
+        .chars()
+        .map(|c| match c {
+            '(' | '[' => 1,
+            ')' | ']' => -1,
+            _ => 0,
+        })
+        .sum()
+}
 
-            // Rust attributes (#[...])
-            if trimmed.starts_with("#[") {
-                signature_lines.push(line_num);
-                continue;
+/// Whether the declaration on `trimmed` is private: see
+/// [`truncate_outline`]. `members_public` is set inside Rust traits and
+/// trait impls, whose members are as public as the trait.
+fn is_private_declaration(
+    trimmed: &str,
+    extension: &str,
+    members_public: bool,
+    top_level: bool,
+) -> bool {
+    let first_word = |s: &str| s.split_whitespace().next().unwrap_or("").to_string();
+    match extension {
+        "rs" => {
+            const KEYWORDS: &[&str] = &[
+                "fn", "async", "unsafe", "const", "static", "struct", "enum", "union", "trait",
+                "type", "mod", "extern",
+            ];
+            let unqualified = match trimmed.strip_prefix("pub(") {
+                Some(rest) => rest.split_once(')').map_or(rest, |(_, r)| r).trim_start(),
+                None => trimmed,
+            };
+            KEYWORDS.contains(&first_word(unqualified).as_str())
+                && !members_public
+                && !trimmed.starts_with("pub ")
+        }
+        "py" | "pyw" => {
+            let declaration = trimmed.strip_prefix("async ").unwrap_or(trimmed);
+            let name = declaration
+                .strip_prefix("def ")
+                .or_else(|| declaration.strip_prefix("class "))
+                .unwrap_or("");
+            let name = name.split(['(', ':']).next().unwrap_or("").trim();
+            name.starts_with('_') && !(name.starts_with("__") && name.ends_with("__"))
+        }
+        "js" | "jsx" | "mjs" | "ts" | "tsx" => {
+            const KEYWORDS: &[&str] = &[
+                "function",
+                "async",
+                "class",
+                "abstract",
+                "const",
+                "let",
+                "var",
+                "interface",
+                "type",
+                "enum",
+                "declare",
+            ];
+            if top_level {
+                KEYWORDS.contains(&first_word(trimmed).as_str())
+            } else {
+                trimmed.starts_with("private ") || trimmed.starts_with('#')
             }
         }
+        _ => trimmed.starts_with("private "),
+    }
+}
 
-        // Deduplicate and sort
-        signature_lines.sort();
-        signature_lines.dedup();
+/// Lines (1-indexed) structure mode keeps: imports, module docs,
+/// decorators and attributes, and declaration signatures, sorted
+fn structure_signature_lines(
+    lines: &[&str],
+    content: &str,
+    file_path: &str,
+    analysis: &analyzers::AnalysisResult,
+) -> Vec<usize> {
+    let mut signature_lines: Vec<usize> = Vec::new();
+
+    // Iterate through ALL lines (matching Python behavior)
+    for (i, line) in lines.iter().enumerate() {
+        let line_num = i + 1;
+        let trimmed = line.trim_start();
+
+        // Skip empty lines and pure comments (but keep docstrings)
+        if trimmed.is_empty() {
+            continue;
+        }
 
-        if signature_lines.is_empty() {
-            // No structure found - fall back to smart mode if max_lines > 0 (Python behavior)
-            if max_lines > 0 {
-                return truncate_smart_with_options(content, max_lines, file_path, include_summary);
-            }
-            // Otherwise return first 20 lines (backward compatible)
-            let kept: Vec<&str> = lines.iter().take(20).copied().collect();
-            let mut result = kept.join("\n");
-            if total_lines > 20 && include_summary {
-                result.push_str(&format!(
-                    "\n\n{}\nSTRUCTURE MODE: No signatures found, showing first 20/{} lines\n{}\n",
-                    "=".repeat(70),
-                    total_lines,
-                    "=".repeat(70)
-                ));
-            }
-            return (result, total_lines > 20);
+        // IMPORT STATEMENTS (Python: import, from; Rust: use; JS: import, export)
+        if trimmed.starts_with("import ")
+            || trimmed.starts_with("from ")
+            || trimmed.starts_with("use ")
+            || trimmed.starts_with("export ")
+        {
+            signature_lines.push(line_num);
+            continue;
         }
 
-        // Build output with signature lines only
-        let mut result = String::new();
-        for &line_num in &signature_lines {
-            if line_num <= total_lines {
-                result.push_str(lines[line_num - 1]);
-                result.push('\n');
-            }
+        // SHEBANG / MODULE DOCS (first few lines)
+        if line_num <= 5 && (trimmed.starts_with("#!") || trimmed.starts_with("//!")) {
+            signature_lines.push(line_num);
+            continue;
         }
 
-        // Add structure marker only if include_summary is true
-        // Format matches Python's structure mode output exactly
-        if include_summary {
-            let kept_count = signature_lines.len();
-            result.push_str(&format!(
-                "\n{}\nSTRUCTURE MODE: Showing only signatures ({}/{} lines)\nLanguage: {}\n\nIncluded: imports, class/function signatures, type definitions\nExcluded: function bodies, implementation details\n\nTo get full content: --include \"{}\" --truncate 0\n{}\n",
-                "=".repeat(70),
-                kept_count,
-                total_lines,
-                analysis.language,
-                file_path,
-                "=".repeat(70)
-            ));
+        // MODULE-LEVEL DOCSTRINGS (first 10 lines)
+        if line_num <= 10 && (trimmed.starts_with("\"\"\"") || trimmed.starts_with("'''")) {
+            signature_lines.push(line_num);
+            continue;
         }
 
-        return (result, true);
+        // DECORATORS (Python @decorator)
+        if trimmed.starts_with("@") {
+            signature_lines.push(line_num);
+            continue;
+        }
+
+        // CLASS DEFINITIONS
+        if trimmed.starts_with("class ")
+            || trimmed.starts_with("pub struct ")
+            || trimmed.starts_with("struct ")
+        {
+            signature_lines.push(line_num);
+            continue;
+        }
+
+        // FUNCTION DEFINITIONS
+        if trimmed.starts_with("def ")
+            || trimmed.starts_with("async def ")
+            || trimmed.starts_with("fn ")
+            || trimmed.starts_with("pub fn ")
+            || trimmed.starts_with("async fn ")
+            || trimmed.starts_with("pub async fn ")
+            || trimmed.starts_with("function ")
+            || trimmed.starts_with("export function ")
+            || trimmed.starts_with("async function ")
+        {
+            signature_lines.push(line_num);
+            continue;
+        }
+
+        // OTHER STRUCTURAL ELEMENTS (Rust: impl, trait, enum, const)
+        if trimmed.starts_with("impl ")
+            || trimmed.starts_with("trait ")
+            || trimmed.starts_with("pub trait ")
+            || trimmed.starts_with("enum ")
+            || trimmed.starts_with("pub enum ")
+            || trimmed.starts_with("const ")
+            || trimmed.starts_with("pub const ")
+            || trimmed.starts_with("pub mod ")
+            || trimmed.starts_with("mod ")
+        {
+            signature_lines.push(line_num);
+            continue;
+        }
+
+        // JS/TS: interface, type definitions, arrow functions
+        if trimmed.starts_with("interface ")
+            || trimmed.starts_with("export interface ")
+            || trimmed.starts_with("type ")
+            || trimmed.starts_with("export type ")
+            || (trimmed.starts_with("const ") && trimmed.contains("=>"))
+        {
+            signature_lines.push(line_num);
+            continue;
+        }
+
+        // Rust attributes (#[...])
+        if trimmed.starts_with("#[") {
+            signature_lines.push(line_num);
+            continue;
+        }
     }
 
-    // No analyzer - fall back to smart mode if max_lines > 0 (Python behavior)
-    if max_lines > 0 {
-        return truncate_smart_with_options(content, max_lines, file_path, include_summary);
+    // Declarations the analyzer located (C#'s attributes and properties)
+    for (start, end) in &analysis.structure_ranges {
+        signature_lines.extend(*start..=*end);
     }
 
-    // Otherwise fall back to first 30 lines (backward compatible)
-    let kept: Vec<&str> = lines.iter().take(30).copied().collect();
-    let mut result = kept.join("\n");
-    if total_lines > 30 && include_summary {
+    // Where voyager-ast has an adapter, tree-sitter adds what prefixes
+    // miss: the rest of multi-line signatures and decorated declarations
+    if let Some((ast_lines, _)) = ast_signature_lines(content, file_path, false) {
+        signature_lines.extend(ast_lines);
+    }
+
+    // Deduplicate and sort
+    signature_lines.sort();
+    signature_lines.dedup();
+    signature_lines
+}
+
+/// The structure mode output: just `signature_lines` (1-indexed) of `lines`
+///
+/// `fidelity_notes` say what the parser that found the signatures could
+/// not extract; the summary marker lists them.
+fn signature_lines_only(
+    lines: &[&str],
+    mut signature_lines: Vec<usize>,
+    language: &str,
+    file_path: &str,
+    include_summary: bool,
+    fidelity_notes: &[String],
+) -> String {
+    signature_lines.sort();
+    signature_lines.dedup();
+
+    // Build output with signature lines only
+    let mut result = String::new();
+    for &line_num in &signature_lines {
+        if line_num <= lines.len() {
+            result.push_str(lines[line_num - 1]);
+            result.push('\n');
+        }
+    }
+
+    // Add structure marker only if include_summary is true
+    // Format matches Python's structure mode output exactly
+    if include_summary {
+        let fidelity: String = fidelity_notes
+            .iter()
+            .map(|note| format!("Not extracted: {}\n", note))
+            .collect();
         result.push_str(&format!(
-            "\n\n{}\nSTRUCTURE MODE: Unknown language, showing first 30/{} lines\n{}\n",
+            "\n{}\nSTRUCTURE MODE: Showing only signatures ({}/{} lines)\nLanguage: {}\n\nIncluded: imports, class/function signatures, type definitions\nExcluded: function bodies, implementation details\n{}\nTo get full content: --include \"{}\" --truncate 0\n{}\n",
             "=".repeat(70),
-            total_lines,
+            signature_lines.len(),
+            lines.len(),
+            language,
+            fidelity,
+            file_path,
             "=".repeat(70)
         ));
     }
-    (result, total_lines > 30)
+    result
+}
+
+/// Lines (1-indexed) of the declaration signatures in `content`, parsed by
+/// voyager-ast, and what its adapter can't extract (see
+/// [`core::FileSummary::fidelity_notes`]); `None` when it has no adapter
+/// for `file_path`'s language
+///
+/// A signature runs from the declaration's first line to the line its body
+/// opens on, nested declarations (methods) included; declarations without a
+/// body (fields, constants) have none. `with_imports` adds the import lines,
+/// for languages without an analyzer to find them.
+fn ast_signature_lines(
+    content: &str,
+    file_path: &str,
+    with_imports: bool,
+) -> Option<(Vec<usize>, Vec<String>)> {
+    static BRIDGE: std::sync::OnceLock<core::AstBridge> = std::sync::OnceLock::new();
+    let bridge = BRIDGE.get_or_init(core::AstBridge::new);
+    let language = core::AstBridge::detect_language(Path::new(file_path));
+    let file = bridge.analyze_file(content, language)?;
+
+    // The line of the last non-blank text before `byte`
+    let line_before = |byte: usize| {
+        let before = content.get(..byte).unwrap_or(content);
+        before.trim_end().matches('\n').count() + 1
+    };
+    let mut lines = Vec::new();
+    let mut pending: Vec<&voyager_ast::Declaration> = file.declarations.iter().collect();
+    while let Some(declaration) = pending.pop() {
+        pending.extend(&declaration.children);
+        let Some(signature) = &declaration.signature_span else {
+            continue;
+        };
+        let start = declaration.span.start_line;
+        lines.extend(start..=line_before(signature.end).max(start));
+    }
+    if with_imports {
+        for import in &file.imports {
+            lines.extend(import.span.start_line..=import.span.end_line);
+        }
+    }
+    Some((lines, bridge.get_file_summary(&file).fidelity_notes))
 }
 
 /// Count lines matching Python's split('\n') behavior.
@@ -1752,110 +4884,469 @@ pub fn python_style_split(content: &str) -> Vec<&str> {
     content.split('\n').collect()
 }
 
-pub fn serialize_file_with_truncation(
+pub fn serialize_file_with_truncation(
+    entry: &FileEntry,
+    truncate_lines: usize,
+    truncate_mode: &str,
+) -> String {
+    // Default to Plus/Minus format for backward compatibility
+    serialize_file_with_format(
+        entry,
+        truncate_lines,
+        truncate_mode,
+        OutputFormat::PlusMinus,
+    )
+}
+
+/// Serialize a file entry with format support
+pub fn serialize_file_with_format(
+    entry: &FileEntry,
+    truncate_lines: usize,
+    truncate_mode: &str,
+    format: OutputFormat,
+) -> String {
+    // Use default metadata mode (None for backwards compatibility)
+    serialize_file_with_format_and_metadata(
+        entry,
+        truncate_lines,
+        truncate_mode,
+        format,
+        MetadataMode::None,
+    )
+}
+
+/// Apply the configured truncation mode to a file entry
+///
+/// Returns the (possibly truncated) content and whether truncation occurred.
+pub fn apply_truncation(
+    entry: &FileEntry,
+    truncate_lines: usize,
+    truncate_mode: &str,
+) -> (String, bool) {
+    apply_truncation_with_options(entry, truncate_lines, truncate_mode, true)
+}
+
+/// Apply the configured truncation mode, optionally without the summary marker
+pub fn apply_truncation_with_options(
+    entry: &FileEntry,
+    truncate_lines: usize,
+    truncate_mode: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    truncate_content(
+        &entry.content,
+        &entry.path,
+        truncate_lines,
+        truncate_mode,
+        include_summary,
+    )
+}
+
+/// Truncate `content` of the file at `path` with the given mode
+///
+/// `truncate_lines` 0 leaves the content alone, except in `structure`,
+/// `outline` and `strip-comments` modes; in `tokens` mode it counts estimated tokens
+/// rather than lines. `strip-comments` removes comment lines, then keeps at
+/// most `truncate_lines` of what is left.
+pub fn truncate_content(
+    content: &str,
+    path: &str,
+    truncate_lines: usize,
+    truncate_mode: &str,
+    include_summary: bool,
+) -> (String, bool) {
+    truncate_content_keeping(
+        content,
+        path,
+        truncate_lines,
+        truncate_mode,
+        include_summary,
+        &KeepPatterns::default(),
+    )
+}
+
+/// Like [`truncate_content`], never dropping a line `keep` matches
+pub fn truncate_content_keeping(
+    content: &str,
+    path: &str,
+    truncate_lines: usize,
+    truncate_mode: &str,
+    include_summary: bool,
+    keep: &KeepPatterns,
+) -> (String, bool) {
+    if truncate_lines == 0 && !matches!(truncate_mode, "structure" | "outline" | "strip-comments") {
+        return (content.to_string(), false);
+    }
+
+    let result = match truncate_mode {
+        "simple" => truncate_simple_keeping(content, truncate_lines, path, include_summary, keep),
+        "tail" => truncate_tail_keeping(content, truncate_lines, path, include_summary, keep),
+        "smart" => truncate_smart_keeping(content, truncate_lines, path, include_summary, keep),
+        "tokens" => truncate_tokens_keeping(content, truncate_lines, path, include_summary, keep),
+        "strip-comments" => {
+            let (stripped, removed) = strip_comments_keeping(content, path, keep);
+            let (mut text, truncated) =
+                truncate_simple_keeping(&stripped, truncate_lines, path, include_summary, keep);
+            // The comments a file opens with are gone on purpose
+            if truncated {
+                text = with_minimum_context(&stripped, path, text);
+            }
+            return (text, removed || truncated);
+        }
+        "outline" => truncate_outline_keeping(content, path, include_summary, keep),
+        "structure" => {
+            // Use fallback version that falls back to smart mode when no signatures (Python behavior)
+            truncate_structure_keeping(content, path, include_summary, truncate_lines, keep)
+        }
+        _ => (content.to_string(), false),
+    };
+    match result {
+        (text, true) if !analyzers::tabular::is_table(path) => {
+            (with_minimum_context(content, path, text), true)
+        }
+        result => result,
+    }
+}
+
+/// Truncation figures for `--truncate-stats`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TruncationStats {
+    /// Files emitted
+    pub files: usize,
+    /// Files whose content was shortened
+    pub truncated: usize,
+    /// Lines before truncation, over all files
+    pub original_lines: usize,
+    /// Lines after truncation
+    pub final_lines: usize,
+    /// Generated and vendored files, emitted or skipped, sorted by path
+    pub generated: Vec<(String, discovery::Generated)>,
+    /// What the run did with them
+    pub generated_policy: GeneratedPolicy,
+    /// Minified scripts and stylesheets, reduced to structure or a stub,
+    /// sorted by path
+    pub minified: Vec<(String, discovery::Minified)>,
+}
+
+impl TruncationStats {
+    /// Run the configured transforms over `entries` and tally the result
+    ///
+    /// The generated files `--generated skip` left out come from `excluded`
+    /// (see [`walk_project_with_exclusions`]).
+    pub fn collect(
+        config: &EncoderConfig,
+        entries: &[FileEntry],
+        excluded: &[ExcludedFile],
+    ) -> Self {
+        let mut stats = Self {
+            generated_policy: config.generated,
+            ..Default::default()
+        };
+        for entry in entries {
+            let content = config
+                .transforms_for(&entry.path, config.truncate_summary)
+                .run_entry(entry);
+            stats.files += 1;
+            stats.truncated += usize::from(content.was_truncated);
+            stats.original_lines += count_lines_python_style(&entry.content);
+            stats.final_lines += content.final_lines;
+            if let Some(generated) = config.generated_status(&entry.path, &entry.content) {
+                stats.generated.push((entry.path.clone(), generated));
+            }
+            if let Some(minified) = discovery::detect_minified(&entry.path, &entry.content)
+                .filter(|_| config.reduces_minified(&entry.path))
+            {
+                stats.minified.push((entry.path.clone(), minified));
+            }
+        }
+        for file in excluded {
+            if let ExclusionReason::Generated(generated) = &file.reason {
+                stats.generated.push((file.path.clone(), generated.clone()));
+            }
+        }
+        stats.generated.sort_by(|a, b| a.0.cmp(&b.0));
+        stats.minified.sort_by(|a, b| a.0.cmp(&b.0));
+        stats
+    }
+
+    /// Multi-line report for stderr
+    pub fn report(&self) -> String {
+        let reduction = match self.original_lines {
+            0 => 0,
+            n => n.saturating_sub(self.final_lines) * 100 / n,
+        };
+        let mut report = format!(
+            "Truncation: {} of {} files truncated, {} -> {} lines ({}% reduction)\n",
+            self.truncated, self.files, self.original_lines, self.final_lines, reduction
+        );
+        if !self.generated.is_empty() {
+            let handling = match self.generated_policy {
+                GeneratedPolicy::Keep => "kept in full",
+                GeneratedPolicy::Structure => "reduced to structure",
+                GeneratedPolicy::Skip => "left out",
+            };
+            report.push_str(&format!(
+                "Generated or vendored: {} files, {}\n",
+                self.generated.len(),
+                handling
+            ));
+            for (path, generated) in &self.generated {
+                report.push_str(&format!("  {}  {}\n", path, generated));
+            }
+        }
+        if !self.minified.is_empty() {
+            report.push_str(&format!(
+                "Minified: {} files, reduced to structure or a stub\n",
+                self.minified.len()
+            ));
+            for (path, minified) in &self.minified {
+                report.push_str(&format!("  {}  {}\n", path, minified));
+            }
+        }
+        report
+    }
+}
+
+/// Map each line of `content` to its 1-based line number in `original`
+///
+/// `content` is `original` after truncation: kept lines appear in their
+/// original order, so they are matched against `original` with a forward
+/// scan; `... [N lines omitted] ...` gap markers (which may add a note after
+/// a colon, as in `... [N lines omitted: R rows] ...`) skip ahead by N. Lines that
+/// were not in the original (truncation and gap markers) map to `None`.
+pub fn line_map(content: &str, original: &str) -> Vec<Option<usize>> {
+    let original_lines = python_style_split(original);
+    let mut cursor = 0;
+
+    let mut lines = python_style_split(content);
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+
+    let mut map = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        // The blank lines framing a gap marker are not original content
+        let framing = [i.checked_sub(1), Some(i + 1)]
+            .into_iter()
+            .flatten()
+            .any(|j| lines.get(j).and_then(|l| gap_marker_size(l)).is_some());
+
+        let index = if let Some(omitted) = gap_marker_size(line) {
+            cursor += omitted;
+            None
+        } else if line.trim().is_empty() {
+            (!framing && original_lines.get(cursor) == Some(line)).then_some(cursor)
+        } else {
+            original_lines
+                .get(cursor..)
+                .and_then(|rest| rest.iter().position(|l| l == line))
+                .map(|offset| cursor + offset)
+        };
+
+        if let Some(index) = index {
+            cursor = index + 1;
+        }
+        map.push(index.map(|i| i + 1));
+    }
+
+    map
+}
+
+/// The number of lines a `... [N lines omitted] ...` marker stands for
+fn gap_marker_size(line: &str) -> Option<usize> {
+    let inner = line.strip_prefix("... [")?.strip_suffix("] ...")?;
+    let (count, note) = inner.split_once(" lines omitted")?;
+    if !note.is_empty() && !note.starts_with(": ") {
+        return None;
+    }
+    count.parse().ok()
+}
+
+/// The tokens a `... [~N tokens omitted] ...` marker stands for
+fn token_gap_size(line: &str) -> Option<usize> {
+    line.strip_prefix("... [~")?
+        .strip_suffix(" tokens omitted] ...")?
+        .parse()
+        .ok()
+}
+
+/// Compact source map for truncated content: runs of consecutive lines
+///
+/// Each range says that `lines` output lines starting at `output` (1-based,
+/// counted from the first content line of the file) are original lines
+/// starting at `original`. Marker lines belong to no range.
+pub fn source_map(content: &str, original: &str) -> Vec<core::LineMapRange> {
+    let mut ranges: Vec<core::LineMapRange> = Vec::new();
+    for (i, number) in line_map(content, original).into_iter().enumerate() {
+        let Some(number) = number else { continue };
+        let output = i + 1;
+        match ranges.last_mut() {
+            Some(r) if r.output + r.lines == output && r.original + r.lines == number => {
+                r.lines += 1;
+            }
+            _ => ranges.push(core::LineMapRange {
+                output,
+                original: number,
+                lines: 1,
+            }),
+        }
+    }
+    ranges
+}
+
+/// Prefix each line of `content` with its 1-based line number in `original`
+///
+/// Numbers come from [`line_map`]; lines that were not in the original
+/// (truncation and gap markers) are left unnumbered but keep the alignment.
+pub fn number_lines(content: &str, original: &str) -> String {
+    let width = python_style_split(original).len().to_string().len();
+    let mut output = String::with_capacity(content.len() + content.len() / 8);
+
+    let mut lines = python_style_split(content);
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+
+    for (line, number) in lines.iter().zip(line_map(content, original)) {
+        match number {
+            Some(n) => output.push_str(&format!("{:>width$} | {}\n", n, line)),
+            None => output.push_str(&format!("{:>width$} | {}\n", "", line)),
+        }
+    }
+
+    output
+}
+
+/// Serialize a file entry with format and metadata support (Chronos v2.3)
+pub fn serialize_file_with_format_and_metadata(
     entry: &FileEntry,
     truncate_lines: usize,
     truncate_mode: &str,
+    format: OutputFormat,
+    metadata_mode: MetadataMode,
 ) -> String {
-    // Default to Plus/Minus format for backward compatibility
-    serialize_file_with_format(
+    serialize_file_with_options(
         entry,
         truncate_lines,
         truncate_mode,
-        OutputFormat::PlusMinus,
+        format,
+        metadata_mode,
+        false,
     )
 }
 
-/// Serialize a file entry with format support
-pub fn serialize_file_with_format(
+/// Serialize a file entry with format, metadata and line-number support
+///
+/// With `line_numbers`, each content line is prefixed with its line number
+/// in the original file. The checksum still covers the original content.
+pub fn serialize_file_with_options(
     entry: &FileEntry,
     truncate_lines: usize,
     truncate_mode: &str,
     format: OutputFormat,
+    metadata_mode: MetadataMode,
+    line_numbers: bool,
 ) -> String {
-    // Use default metadata mode (None for backwards compatibility)
-    serialize_file_with_format_and_metadata(
+    let file = prepare_output_file(
         entry,
         truncate_lines,
         truncate_mode,
-        format,
-        MetadataMode::None,
-    )
+        metadata_mode,
+        line_numbers,
+    );
+    let mut output = Vec::new();
+    // Writing into a Vec cannot fail
+    let _ = formats::writer_for(format).write_file(&mut output, &file);
+    String::from_utf8_lossy(&output).into_owned()
 }
 
-/// Serialize a file entry with format and metadata support (Chronos v2.3)
-pub fn serialize_file_with_format_and_metadata(
+/// Apply truncation (and optional line numbering) to get a writer-ready file
+pub fn prepare_output_file(
     entry: &FileEntry,
     truncate_lines: usize,
     truncate_mode: &str,
-    format: OutputFormat,
     metadata_mode: MetadataMode,
-) -> String {
-    let original_lines = count_lines_python_style(&entry.content);
+    line_numbers: bool,
+) -> formats::OutputFile {
+    let mut transforms = transform::TransformPipeline::new().with(transform::Truncation::new(
+        truncate_lines,
+        truncate_mode,
+        true,
+    ));
+    if line_numbers {
+        transforms = transforms.with(transform::LineNumbers);
+    }
+    prepare_output_file_with_transforms(entry, &transforms, metadata_mode)
+}
 
-    // Apply truncation and track if file was truncated
-    let (content, was_truncated) = if truncate_lines > 0 || truncate_mode == "structure" {
-        match truncate_mode {
-            "simple" => truncate_simple(&entry.content, truncate_lines, &entry.path),
-            "smart" => truncate_smart(&entry.content, truncate_lines, &entry.path),
-            "structure" => {
-                // Use fallback version that falls back to smart mode when no signatures (Python behavior)
-                truncate_structure_with_fallback(&entry.content, &entry.path, true, truncate_lines)
-            }
-            _ => (entry.content.clone(), false),
-        }
-    } else {
-        (entry.content.clone(), false)
-    };
+/// Run `transforms` over an entry to get a writer-ready file
+pub fn prepare_output_file_with_transforms(
+    entry: &FileEntry,
+    transforms: &transform::TransformPipeline,
+    metadata_mode: MetadataMode,
+) -> formats::OutputFile {
+    let original_lines = count_lines_python_style(&entry.content);
+    let content = transforms.run_entry(entry);
+
+    formats::OutputFile {
+        path: entry.path.clone(),
+        final_lines: content.final_lines,
+        content: content.text,
+        md5: entry.md5.clone(),
+        size: entry.size,
+        mtime: entry.mtime,
+        language: entry.language,
+        executable: entry.executable,
+        symlink_target: entry.symlink_target.clone(),
+        was_truncated: content.was_truncated,
+        original_lines,
+        merge_conflicts: count_merge_conflicts(&entry.content),
+        metadata_mode,
+        hints: None,
+    }
+}
 
-    let final_lines = count_lines_python_style(&content);
+/// Serialize entries with any [`formats::OutputWriter`], built-in or custom
+///
+/// Truncation, metadata and line numbers come from `config`; its output
+/// format is ignored in favour of `writer`. No frozen context hash is
+/// appended, since its syntax depends on the format.
+pub fn serialize_entries_with_writer(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    writer: &dyn formats::OutputWriter,
+) -> Result<String, String> {
+    render_entries(
+        &mut formats::WriterFormatter::new(config, Box::new(writer)),
+        entries,
+    )
+}
 
-    match format {
-        OutputFormat::PlusMinus => serialize_plus_minus_entry(
-            &entry.path,
-            &content,
-            &entry.md5,
-            entry.size,
-            entry.mtime,
-            was_truncated,
-            original_lines,
-            final_lines,
-            metadata_mode,
-        ),
-        OutputFormat::Xml => serialize_xml_entry(
-            &entry.path,
-            &content,
-            &entry.md5,
-            entry.size,
-            entry.mtime,
-            was_truncated,
-            original_lines,
-            final_lines,
-            metadata_mode,
-        ),
-        OutputFormat::Markdown => serialize_markdown_entry(
-            &entry.path,
-            &content,
-            &entry.md5,
-            entry.size,
-            entry.mtime,
-            was_truncated,
-            original_lines,
-            final_lines,
-            metadata_mode,
-        ),
-        OutputFormat::ClaudeXml => serialize_claude_xml_entry(
-            &entry.path,
-            &content,
-            &entry.md5,
-            entry.size,
-            entry.mtime,
-            was_truncated,
-            original_lines,
-            final_lines,
-            metadata_mode,
-        ),
+/// Serialize entries with any [`formats::BundleFormatter`]
+///
+/// Appends the glossary when `config.glossary` is set and the context hash
+/// when `config.frozen` is.
+pub fn serialize_entries_with_formatter(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    formatter: &mut dyn formats::BundleFormatter,
+) -> Result<String, String> {
+    let mut output = render_entries(formatter, entries)?;
+    append_glossary(&mut output, config, entries);
+    if config.frozen {
+        append_context_hash(&mut output, config.output_format);
     }
+    Ok(output)
+}
+
+fn render_entries(
+    formatter: &mut dyn formats::BundleFormatter,
+    entries: &[FileEntry],
+) -> Result<String, String> {
+    let mut output = Vec::new();
+    formats::format_entries(formatter, &mut output, entries)
+        .map_err(|e| format!("Failed to write output: {}", e))?;
+    String::from_utf8(output).map_err(|e| format!("Output is not UTF-8: {}", e))
 }
 
 /// Serialize to Plus/Minus format (with Chronos metadata support)
@@ -2001,6 +5492,90 @@ fn serialize_markdown_entry(
     output
 }
 
+/// Serialize to Gemini long-context format (with Chronos metadata support)
+///
+/// Each file is a `<file>` element carrying its path as an explicit attribute,
+/// with the content in a fenced code block. Line counts are the reader's
+/// (see [`gemini_line_count`]). The fence is lengthened when the content
+/// itself contains backtick runs, so files are never cut short.
+/// `original_lines` is shown for truncated files when known (non-zero).
+pub(crate) fn serialize_gemini_entry(
+    path: &str,
+    content: &str,
+    md5: &str,
+    size: u64,
+    mtime: u64,
+    was_truncated: bool,
+    original_lines: usize,
+    metadata_mode: MetadataMode,
+) -> String {
+    use core::serialization::format_xml_header_attrs;
+    let mut output = String::new();
+    let lang = detect_language(path);
+    let metadata_attrs = format_xml_header_attrs(size, mtime, metadata_mode);
+
+    output.push_str(&format!(
+        "<file path=\"{}\" language=\"{}\" md5=\"{}\" lines=\"{}\"",
+        escape_xml_attr(path),
+        lang,
+        md5,
+        gemini_line_count(content)
+    ));
+    if was_truncated {
+        output.push_str(" truncated=\"true\"");
+        if original_lines > 0 {
+            output.push_str(&format!(" original_lines=\"{}\"", original_lines));
+        }
+    }
+    output.push_str(&metadata_attrs);
+    output.push_str(">\n");
+
+    let fence = code_fence(content);
+    output.push_str(&format!("{}{}\n", fence, lang));
+    output.push_str(content);
+    if !content.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str(&format!("{}\n", fence));
+    output.push_str("</file>\n\n");
+
+    output
+}
+
+/// Backtick fence longer than any backtick run in `content`
+fn code_fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Lines of `content` as a reader counts them: no phantom line after a
+/// trailing newline
+fn gemini_line_count(content: &str) -> usize {
+    content.lines().count()
+}
+
+/// Gemini preamble: list every file up front, given `(path, content)` pairs
+/// of the content each entry will show
+pub(crate) fn gemini_preamble(files: &[(&str, &str)]) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("# Project Files ({})\n\n", files.len()));
+    output.push_str(
+        "Each file below is enclosed in a <file> element whose path attribute \
+         identifies it, followed by its content in a fenced code block.\n\n",
+    );
+    for (path, content) in files {
+        let lines = gemini_line_count(content);
+        let lang = detect_language(path);
+        if lang.is_empty() {
+            output.push_str(&format!("- {} ({} lines)\n", path, lines));
+        } else {
+            output.push_str(&format!("- {} ({}, {} lines)\n", path, lang, lines));
+        }
+    }
+    output.push('\n');
+    output
+}
+
 /// Serialize to Claude-optimized XML format (with Chronos metadata support)
 /// Uses CDATA sections for code content with semantic attributes
 fn serialize_claude_xml_entry(
@@ -2084,7 +5659,7 @@ fn serialize_claude_xml_entry(
 /// Serialize a project directory into the Plus/Minus format
 ///
 /// This function automatically loads configuration from `.pm_encoder_config.json`
-/// if it exists in the root directory.
+/// if it exists in the root directory, plus any nested ones in subdirectories.
 ///
 /// # Arguments
 ///
@@ -2104,13 +5679,44 @@ fn serialize_claude_xml_entry(
 /// assert!(result.is_ok());
 /// ```
 pub fn serialize_project(root: &str) -> Result<String, String> {
+    let config = project_config(root)?;
+    serialize_project_with_config(root, &config)
+}
+
+/// The project's own config (defaults if it has none), with nested configs
+fn project_config(root: &str) -> Result<EncoderConfig, String> {
     // Try to load config from the project directory
     let config_path = Path::new(root).join(".pm_encoder_config.json");
-    let config = if config_path.exists() {
+    let mut config = if config_path.exists() {
         EncoderConfig::from_file(&config_path).unwrap_or_default()
     } else {
         EncoderConfig::default()
     };
+    config.discover_directory_configs(Path::new(root))?;
+    Ok(config)
+}
+
+/// Serialize only the listed files of the project in the current directory
+///
+/// Relative paths are taken from the current directory, whose
+/// `.pm_encoder_config.json` applies as in [`serialize_project`].
+pub fn serialize_files(paths: &[std::path::PathBuf]) -> Result<String, String> {
+    let config = project_config(".")?;
+    serialize_files_with_config(".", paths, &config)
+}
+
+/// Serialize only the listed files below `root`
+///
+/// Relative paths are taken from `root`; see [`discovery::file_list`].
+/// Listed files that don't exist or are excluded by `config` are left out.
+pub fn serialize_files_with_config(
+    root: &str,
+    paths: &[std::path::PathBuf],
+    config: &EncoderConfig,
+) -> Result<String, String> {
+    let listed = discovery::relative_paths(Path::new(root), paths)?;
+    let mut config = config.clone();
+    config.restrict_to(listed);
     serialize_project_with_config(root, &config)
 }
 
@@ -2123,73 +5729,595 @@ pub fn serialize_project(root: &str) -> Result<String, String> {
 ///
 /// # Returns
 ///
-/// * `Ok(String)` - The serialized output (empty string in streaming mode)
-/// * `Err(String)` - Error message if serialization fails
-pub fn serialize_project_with_config(root: &str, config: &EncoderConfig) -> Result<String, String> {
-    // Streaming mode: use iterator, write directly, return empty string
-    if config.stream {
-        return serialize_project_streaming(root, config);
-    }
+/// * `Ok(String)` - The serialized output (empty string in streaming mode)
+/// * `Err(String)` - Error message if serialization fails
+pub fn serialize_project_with_config(root: &str, config: &EncoderConfig) -> Result<String, String> {
+    // Streaming mode: use iterator, write directly, return empty string
+    if config.stream {
+        return serialize_project_streaming(root, config);
+    }
+
+    // Batch mode: collect, sort, return complete string
+    let entries = walk_project(root, config)?;
+
+    // Sort entries based on config
+    let mut sorted_entries = entries;
+    sort_entries(&mut sorted_entries, config);
+
+    serialize_sorted_entries(config, &sorted_entries)
+}
+
+/// Serialize already-sorted entries in the configured output format
+pub fn serialize_sorted_entries(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+) -> Result<String, String> {
+    let mut formatter = formats::formatter_for(config)?;
+    serialize_entries_with_formatter(config, entries, formatter.as_mut())
+}
+
+/// Serialize entries selected by a token budget
+///
+/// Like [`serialize_sorted_entries`], except that Claude-XML describes the
+/// selection (see [`formats::ClaudeXmlFormatter::with_report`]).
+pub fn serialize_budgeted_entries(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    report: &budgeting::BudgetReport,
+) -> Result<String, String> {
+    let mut formatter = match config.output_format {
+        OutputFormat::ClaudeXml => {
+            Box::new(formats::ClaudeXmlFormatter::new(config).with_report(report))
+        }
+        _ => formats::formatter_for(config)?,
+    };
+    serialize_entries_with_formatter(config, entries, formatter.as_mut())
+}
+
+/// Serialize sorted entries relative to a git revision (diff-embedding mode)
+///
+/// `changes` maps project-relative paths to their unified diff since `rev`.
+/// Changed files are emitted in full followed by their diff; unchanged files
+/// are reduced to structure-only stubs. Deleted files get a diff section of
+/// their own at the end.
+pub fn serialize_sorted_entries_since(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    rev: &str,
+    changes: &std::collections::BTreeMap<String, String>,
+) -> Result<String, String> {
+    if config.output_format == OutputFormat::ClaudeXml {
+        return Err("--since is not supported with the claude-xml format".to_string());
+    }
+
+    let mut output = String::new();
+    for entry in entries {
+        let (truncate_lines, truncate_mode) = config.truncation_for(&entry.path);
+        match changes.get(&entry.path) {
+            Some(diff) => {
+                output.push_str(&serialize_file_with_options(
+                    entry,
+                    truncate_lines,
+                    truncate_mode,
+                    config.output_format,
+                    config.metadata_mode,
+                    config.line_numbers,
+                ));
+                output.push_str(&serialize_diff_section(
+                    &entry.path,
+                    rev,
+                    diff,
+                    config.output_format,
+                ));
+            }
+            None => output.push_str(&serialize_file_with_options(
+                entry,
+                truncate_lines,
+                "structure",
+                config.output_format,
+                config.metadata_mode,
+                config.line_numbers,
+            )),
+        }
+    }
+
+    // Deleted files; other unmatched paths were filtered out on purpose
+    for (path, diff) in changes {
+        let deleted = diff
+            .lines()
+            .take_while(|l| !l.starts_with("@@"))
+            .any(|l| l.starts_with("deleted file mode"));
+        if deleted && !entries.iter().any(|e| &e.path == path) {
+            output.push_str(&serialize_diff_section(
+                path,
+                rev,
+                diff,
+                config.output_format,
+            ));
+        }
+    }
+
+    // Files here skip the transform pipeline, and diffs carry control
+    // characters too
+    if config.escape_control_chars {
+        output = escape_control_chars(&output).0;
+    }
+    append_glossary(&mut output, config, entries);
+    if config.frozen {
+        append_context_hash(&mut output, config.output_format);
+    }
+    Ok(output)
+}
+
+/// Append the glossary of `entries` when `config.glossary` is set
+fn append_glossary(output: &mut String, config: &EncoderConfig, entries: &[FileEntry]) {
+    if config.glossary {
+        let terms = glossary::extract_glossary(entries, glossary::MAX_TERMS);
+        output.push_str(&glossary::render_glossary(&terms, config.output_format));
+    }
+}
+
+/// Render a unified diff section for diff-embedding mode
+///
+/// Plus/Minus sections sit outside the file frames, so the decoder skips them.
+fn serialize_diff_section(path: &str, rev: &str, diff: &str, format: OutputFormat) -> String {
+    let mut output = String::new();
+    match format {
+        OutputFormat::PlusMinus => {
+            output.push_str(&format!(
+                "~~~~~~~~~~ {} [DIFF since {}] ~~~~~~~~~~\n",
+                path, rev
+            ));
+            output.push_str(diff);
+            if !diff.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(&format!("~~~~~~~~~~ {} [END DIFF] ~~~~~~~~~~\n", path));
+        }
+        OutputFormat::Xml | OutputFormat::ClaudeXml => {
+            output.push_str(&format!(
+                "<diff path=\"{}\" since=\"{}\">\n",
+                escape_xml_attr(path),
+                escape_xml_attr(rev)
+            ));
+            output.push_str(&escape_xml(diff));
+            if !diff.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str("</diff>\n");
+        }
+        OutputFormat::Markdown | OutputFormat::Gemini => {
+            let fence = code_fence(diff);
+            output.push_str(&format!("#### Diff since {}: {}\n\n", rev, path));
+            output.push_str(&format!("{}diff\n", fence));
+            output.push_str(diff);
+            if !diff.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(&format!("{}\n\n", fence));
+        }
+    }
+    output
+}
+
+/// Measure the markup `config`'s output format wraps around files
+///
+/// Renders no file, then one file under a short and a long path: the
+/// first is the envelope, the difference between the others how often the
+/// path repeats.
+pub fn format_overhead(config: &EncoderConfig) -> Result<FormatOverhead, String> {
+    const CONTENT: &str = "x\n";
+    const SHORT: &str = "a.txt";
+    const LONGER_BY: usize = 40;
+
+    let size = |entries: &[FileEntry]| serialize_sorted_entries(config, entries).map(|s| s.len());
+    let envelope = size(&[])?;
+    let frame = |path: &str| -> Result<usize, String> {
+        let size = size(&[FileEntry::new(path, CONTENT)])?;
+        Ok(size.saturating_sub(envelope + CONTENT.len()))
+    };
+    let short = frame(SHORT)?;
+    let long = frame(&format!("{}{}", "a".repeat(LONGER_BY), SHORT))?;
+    let path_repeats = long.saturating_sub(short) / LONGER_BY;
+    Ok(FormatOverhead {
+        format: config.output_format.name().to_string(),
+        envelope_bytes: envelope,
+        file_bytes: short.saturating_sub(path_repeats * SHORT.len()),
+        path_repeats,
+    })
+}
+
+/// Partition sorted entries into serialized parts of at most `max_tokens` each
+///
+/// Parts are cut only at file boundaries, so every file keeps its header and
+/// footer intact. Each part is serialized independently (Claude-XML parts get
+/// their own wrapper). A single file larger than `max_tokens` becomes a part
+/// of its own.
+pub fn split_entries_by_tokens(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    max_tokens: usize,
+) -> Result<Vec<String>, String> {
+    if max_tokens == 0 {
+        return Err("--split-by-tokens must be greater than zero".to_string());
+    }
+
+    // Fixed per-part overhead (e.g. the Claude-XML wrapper)
+    let overhead = TokenEstimator::estimate_tokens(&serialize_sorted_entries(config, &[])?);
+
+    let mut groups: Vec<Vec<FileEntry>> = Vec::new();
+    let mut current: Vec<FileEntry> = Vec::new();
+    let mut current_tokens = overhead;
+
+    for entry in entries {
+        let (truncate_lines, truncate_mode) = config.truncation_for(&entry.path);
+        let entry_tokens = TokenEstimator::estimate_tokens(&serialize_file_with_options(
+            entry,
+            truncate_lines,
+            truncate_mode,
+            config.output_format,
+            config.metadata_mode,
+            config.line_numbers,
+        ));
+
+        if !current.is_empty() && current_tokens + entry_tokens > max_tokens {
+            groups.push(std::mem::take(&mut current));
+            current_tokens = overhead;
+        }
+        current.push(entry.clone());
+        current_tokens += entry_tokens;
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    groups
+        .iter()
+        .map(|group| serialize_sorted_entries(config, group))
+        .collect()
+}
+
+/// Serialize a project into token-bounded parts (see [`split_entries_by_tokens`])
+pub fn serialize_project_split(
+    root: &str,
+    config: &EncoderConfig,
+    max_tokens: usize,
+) -> Result<Vec<String>, String> {
+    let mut entries = walk_project(root, config)?;
+    sort_entries(&mut entries, config);
+    split_entries_by_tokens(config, &entries, max_tokens)
+}
+
+/// Path of the `index`-th (1-based) split part: `context.txt` -> `context.part1.txt`
+pub fn split_part_path(base: &Path, index: usize) -> std::path::PathBuf {
+    suffixed_path(base, &format!("part{}", index))
+}
+
+/// Path of a `--split roles` section: `context.txt` -> `context.system.txt`
+pub fn role_section_path(base: &Path, role: &str) -> std::path::PathBuf {
+    suffixed_path(base, role)
+}
+
+/// `base` with `suffix` before its extension
+fn suffixed_path(base: &Path, suffix: &str) -> std::path::PathBuf {
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "context".to_string());
+    let file_name = match base.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, suffix),
+    };
+    base.with_file_name(file_name)
+}
+
+/// Context split the way chat APIs take it (`--split roles`): a stable
+/// system prompt, cached across turns, and a volatile user message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoleSections {
+    /// The project tree, the file index and the pinned files
+    pub system: String,
+    /// The files of the turn
+    pub user: String,
+}
+
+/// Split sorted `entries` into [`RoleSections`]
+///
+/// The system section opens with the project tree and an index of every
+/// file, then carries the files pinned in `.pmattributes`. The user section
+/// carries the other files `volatile` selects (changed files, zoom
+/// targets); the rest are only listed in the system section.
+pub fn serialize_roles(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    volatile: impl Fn(&FileEntry) -> bool,
+) -> Result<RoleSections, String> {
+    let (pinned, rest): (Vec<&FileEntry>, Vec<&FileEntry>) = entries
+        .iter()
+        .partition(|entry| config.attributes_for(&entry.path).pinned());
+    let pinned: Vec<FileEntry> = pinned.into_iter().cloned().collect();
+    let user: Vec<FileEntry> = rest.into_iter().filter(|e| volatile(e)).cloned().collect();
+
+    let mut system = project_overview(entries);
+    system.push_str(&serialize_sorted_entries(config, &pinned)?);
+    Ok(RoleSections {
+        system,
+        user: serialize_sorted_entries(config, &user)?,
+    })
+}
+
+/// The project tree and file index opening the system section
+fn project_overview(entries: &[FileEntry]) -> String {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+
+    #[derive(Default)]
+    struct Dir {
+        dirs: BTreeMap<String, Dir>,
+        files: Vec<String>,
+    }
+
+    fn render(dir: &Dir, prefix: &str, out: &mut String) {
+        let mut files: Vec<&String> = dir.files.iter().collect();
+        files.sort();
+        let children: Vec<(&String, Option<&Dir>)> = dir
+            .dirs
+            .iter()
+            .map(|(name, sub)| (name, Some(sub)))
+            .chain(files.into_iter().map(|name| (name, None)))
+            .collect();
+        for (i, (name, sub)) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let connector = if last { "└── " } else { "├── " };
+            match sub {
+                Some(sub) => {
+                    writeln!(out, "{}{}{}/", prefix, connector, name).ok();
+                    let child_prefix = if last { "    " } else { "│   " };
+                    render(sub, &format!("{}{}", prefix, child_prefix), out);
+                }
+                None => {
+                    writeln!(out, "{}{}{}", prefix, connector, name).ok();
+                }
+            }
+        }
+    }
+
+    let mut root = Dir::default();
+    for entry in entries {
+        let mut parts: Vec<&str> = entry.path.split('/').collect();
+        let file = parts.pop().unwrap_or_default();
+        let mut dir = &mut root;
+        for part in parts {
+            dir = dir.dirs.entry(part.to_string()).or_default();
+        }
+        dir.files.push(file.to_string());
+    }
+
+    let mut out = String::from("Project tree:\n");
+    render(&root, "", &mut out);
+    let tokens: Vec<usize> = entries
+        .iter()
+        .map(|entry| TokenEstimator::estimate_tokens(&entry.content))
+        .collect();
+    writeln!(
+        out,
+        "\nFile index ({} files, ~{} tokens):",
+        entries.len(),
+        tokens.iter().sum::<usize>()
+    )
+    .ok();
+    for (entry, tokens) in entries.iter().zip(tokens) {
+        writeln!(out, "  {}  ~{} tokens", entry.path, tokens).ok();
+    }
+    out.push('\n');
+    out
+}
+
+/// Write a tarball bundle of already-selected entries and their serialized context
+///
+/// Each entry is stored after truncation under `files/`, alongside the
+/// serialized `context` and a `manifest.json`.
+pub fn write_bundle(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    context: &str,
+    bundle_path: &Path,
+) -> Result<core::BundleManifest, String> {
+    let mut bundle = core::Bundle::new(
+        config.output_format.name(),
+        config.output_format.extension(),
+        config.frozen,
+    );
+
+    let keep = config.keep_patterns();
+    for entry in entries {
+        let (truncate_lines, truncate_mode) = config.truncation_for(&entry.path);
+        let (content, truncated) = truncate_content_keeping(
+            &entry.content,
+            &entry.path,
+            truncate_lines,
+            truncate_mode,
+            true,
+            &keep,
+        );
+        bundle.add_file(
+            core::BundleFileEntry {
+                path: entry.path.clone(),
+                md5: entry.md5.clone(),
+                size: entry.size,
+                truncated,
+                original_lines: count_lines_python_style(&entry.content),
+                final_lines: count_lines_python_style(&content),
+            },
+            content,
+        );
+    }
+    bundle.set_context(context.to_string());
+
+    bundle
+        .write_to(bundle_path)
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    Ok(bundle.manifest)
+}
+
+/// Serialize a project into a tarball bundle
+///
+/// Returns the bundle manifest. Streaming is not used: the bundle needs the
+/// full, sorted file list.
+pub fn serialize_project_to_bundle(
+    root: &str,
+    config: &EncoderConfig,
+    bundle_path: &Path,
+) -> Result<core::BundleManifest, String> {
+    let mut entries = walk_project(root, config)?;
+    sort_entries(&mut entries, config);
+
+    let context = serialize_sorted_entries(config, &entries)?;
+    write_bundle(config, &entries, &context, bundle_path)
+}
+
+/// Build the sidecar manifest for already-selected entries and their serialized output
+///
+/// Token estimates cover each file's content after truncation; the total
+/// covers the complete serialized output (all parts when split).
+pub fn build_sidecar_manifest(
+    config: &EncoderConfig,
+    entries: &[FileEntry],
+    output: &str,
+) -> core::SidecarManifest {
+    let keep = config.keep_patterns();
+    let files = entries
+        .iter()
+        .map(|entry| {
+            let (truncate_lines, truncate_mode) = config.truncation_for(&entry.path);
+            let (content, truncated) = truncate_content_keeping(
+                &entry.content,
+                &entry.path,
+                truncate_lines,
+                truncate_mode,
+                true,
+                &keep,
+            );
+            core::SidecarFileEntry {
+                path: entry.path.clone(),
+                md5: entry.md5.clone(),
+                size: entry.size,
+                tokens: TokenEstimator::estimate_tokens(&content),
+                truncation: if truncated {
+                    truncate_mode.to_string()
+                } else {
+                    "none".to_string()
+                },
+                original_lines: count_lines_python_style(&entry.content),
+                final_lines: count_lines_python_style(&content),
+                line_map: if truncated {
+                    source_map(&content, &entry.content)
+                } else {
+                    Vec::new()
+                },
+            }
+        })
+        .collect();
 
-    // Batch mode: collect, sort, return complete string
-    let entries = walk_directory(
-        root,
-        &config.ignore_patterns,
-        &config.include_patterns,
-        config.max_file_size,
-    )?;
+    core::SidecarManifest {
+        encoder_version: VERSION.to_string(),
+        format: config.output_format.name().to_string(),
+        generated_at: if config.frozen {
+            None
+        } else {
+            Some(chrono::Utc::now().to_rfc3339())
+        },
+        lens: config.active_lens.clone(),
+        token_budget: config.token_budget,
+        truncate_lines: config.truncate_lines,
+        truncate_mode: config.truncate_mode.clone(),
+        total_tokens: TokenEstimator::estimate_tokens(output),
+        parts: Vec::new(),
+        files,
+    }
+}
 
-    // Sort entries based on config
-    let mut sorted_entries = entries;
+/// Sort file entries according to `config.sort_by` / `config.sort_order`
+pub fn sort_entries(entries: &mut [FileEntry], config: &EncoderConfig) {
     let is_desc = config.sort_order == "desc";
 
     match config.sort_by.as_str() {
         "name" => {
             if is_desc {
-                sorted_entries.sort_by(|a, b| b.path.cmp(&a.path));
+                entries.sort_by(|a, b| b.path.cmp(&a.path));
             } else {
-                sorted_entries.sort_by(|a, b| a.path.cmp(&b.path));
+                entries.sort_by(|a, b| a.path.cmp(&b.path));
             }
         }
+        // Ties fall back to path order so equal timestamps (and frozen
+        // mode, which clears them) don't depend on walk order
         "mtime" => {
             if is_desc {
-                sorted_entries.sort_by(|a, b| b.mtime.cmp(&a.mtime));
+                entries.sort_by(|a, b| b.mtime.cmp(&a.mtime).then_with(|| a.path.cmp(&b.path)));
             } else {
-                sorted_entries.sort_by(|a, b| a.mtime.cmp(&b.mtime));
+                entries.sort_by(|a, b| a.mtime.cmp(&b.mtime).then_with(|| a.path.cmp(&b.path)));
             }
         }
         "ctime" => {
             if is_desc {
-                sorted_entries.sort_by(|a, b| b.ctime.cmp(&a.ctime));
+                entries.sort_by(|a, b| b.ctime.cmp(&a.ctime).then_with(|| a.path.cmp(&b.path)));
+            } else {
+                entries.sort_by(|a, b| a.ctime.cmp(&b.ctime).then_with(|| a.path.cmp(&b.path)));
+            }
+        }
+        // `size desc` puts the budget hogs first
+        "size" => {
+            if is_desc {
+                entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+            } else {
+                entries.sort_by(|a, b| a.size.cmp(&b.size).then_with(|| a.path.cmp(&b.path)));
+            }
+        }
+        // A ranking: `asc` puts the most useful files first, so a budget
+        // cutting from the tail loses the least useful
+        "utility" => {
+            let score = |e: &FileEntry| {
+                config
+                    .utility_store
+                    .as_ref()
+                    .map_or(0.5, |store| store.get_utility_score(&e.path))
+            };
+            if is_desc {
+                entries.sort_by(|a, b| {
+                    score(a)
+                        .total_cmp(&score(b))
+                        .then_with(|| a.path.cmp(&b.path))
+                });
+            } else {
+                entries.sort_by(|a, b| {
+                    score(b)
+                        .total_cmp(&score(a))
+                        .then_with(|| a.path.cmp(&b.path))
+                });
+            }
+        }
+        // Files without an extension sort as the empty one
+        "ext" => {
+            let ext = |e: &FileEntry| sort_extension(&e.path);
+            if is_desc {
+                entries.sort_by_cached_key(|e| (std::cmp::Reverse(ext(e)), e.path.clone()));
             } else {
-                sorted_entries.sort_by(|a, b| a.ctime.cmp(&b.ctime));
+                entries.sort_by_cached_key(|e| (ext(e), e.path.clone()));
             }
         }
         // Default to name sorting
         _ => {
-            sorted_entries.sort_by(|a, b| a.path.cmp(&b.path));
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
         }
     }
+}
 
-    // Use streaming XmlWriter for ClaudeXml format (Phase 2 refactor)
-    if config.output_format == OutputFormat::ClaudeXml {
-        return serialize_entries_claude_xml(config, &sorted_entries);
-    }
-
-    // Serialize each file entry with optional truncation and format (non-XML formats)
-    let mut output = String::new();
-
-    for entry in sorted_entries {
-        output.push_str(&serialize_file_with_format_and_metadata(
-            &entry,
-            config.truncate_lines,
-            &config.truncate_mode,
-            config.output_format,
-            config.metadata_mode,
-        ));
-    }
-
-    Ok(output)
+/// Lowercased extension of `path` for `sort_by = "ext"`; empty without one
+fn sort_extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
 }
 
 /// Serialize files to Claude-XML format using streaming XmlWriter
@@ -2210,103 +6338,7 @@ pub fn serialize_entries_claude_xml(
     config: &EncoderConfig,
     files: &[FileEntry],
 ) -> Result<String, String> {
-    use crate::formats::{AttentionEntry, XmlConfig, XmlWriter};
-
-    let mut buffer = Vec::new();
-
-    // Build XmlConfig from EncoderConfig
-    let xml_config = XmlConfig {
-        package: "pm_encoder".to_string(),
-        version: VERSION.to_string(),
-        lens: config.active_lens.clone(),
-        token_budget: config.token_budget,
-        utilized_tokens: Some(files.iter().map(|f| f.content.len() / 4).sum()),
-        frozen: config.frozen,
-        allow_sensitive: config.allow_sensitive,
-        snapshot_id: if config.frozen {
-            Some("FROZEN_SNAPSHOT".to_string())
-        } else {
-            None
-        },
-    };
-
-    let mut writer = XmlWriter::new(&mut buffer, xml_config);
-
-    // Build attention entries for metadata
-    // Apply active lens for accurate priority calculation
-    let mut lens_manager = LensManager::new();
-    if let Some(ref lens_name) = config.active_lens {
-        let _ = lens_manager.apply_lens(lens_name);
-    }
-
-    let attention_entries: Vec<AttentionEntry> = files
-        .iter()
-        .map(|f| {
-            let priority = lens_manager.get_file_priority(std::path::Path::new(&f.path));
-            let tokens = f.content.len() / 4;
-            let truncated =
-                config.truncate_lines > 0 && f.content.lines().count() > config.truncate_lines;
-            AttentionEntry {
-                path: f.path.clone(),
-                priority,
-                tokens,
-                truncated,
-                dropped: false,
-                utility_score: None,
-            }
-        })
-        .collect();
-
-    // Write XML structure
-    writer.write_context_start().map_err(|e| e.to_string())?;
-    writer
-        .write_metadata(&attention_entries)
-        .map_err(|e| e.to_string())?;
-    writer.write_files_start().map_err(|e| e.to_string())?;
-
-    for entry in files {
-        let language = detect_language(&entry.path);
-        let priority = lens_manager.get_static_priority(std::path::Path::new(&entry.path));
-
-        // Apply truncation if configured
-        let (content, truncated) = if config.truncate_lines > 0 {
-            truncate_for_xml(&entry.content, config.truncate_lines, &config.truncate_mode)
-        } else {
-            (entry.content.clone(), false)
-        };
-
-        let original_tokens = if truncated {
-            Some(entry.content.len() / 4)
-        } else {
-            None
-        };
-
-        // Build zoom command for truncated files (Phase 4: Fractal affordances)
-        let zoom_cmd = if truncated {
-            Some(format!("--include {} --truncate 0", entry.path))
-        } else {
-            None
-        };
-
-        writer
-            .write_file(
-                &entry.path,
-                language,
-                &entry.md5,
-                priority,
-                &content,
-                truncated,
-                original_tokens,
-                zoom_cmd.as_deref(),
-            )
-            .map_err(|e| e.to_string())?;
-    }
-
-    writer.write_files_end().map_err(|e| e.to_string())?;
-    writer.write_context_end().map_err(|e| e.to_string())?;
-    writer.flush().map_err(|e| e.to_string())?;
-
-    String::from_utf8(buffer).map_err(|e| e.to_string())
+    serialize_entries_with_formatter(config, files, &mut formats::ClaudeXmlFormatter::new(config))
 }
 
 /// Serialize file entries to Claude-XML format with budget report for dropped files
@@ -2329,129 +6361,53 @@ pub fn serialize_entries_claude_xml_with_report(
     files: &[FileEntry],
     report: &crate::budgeting::BudgetReport,
 ) -> Result<String, String> {
-    use crate::formats::{AttentionEntry, XmlConfig, XmlWriter};
-
-    let mut buffer = Vec::new();
-
-    // Build XmlConfig from EncoderConfig - use report.used for accurate utilized count
-    let xml_config = XmlConfig {
-        package: "pm_encoder".to_string(),
-        version: VERSION.to_string(),
-        lens: config.active_lens.clone(),
-        token_budget: Some(report.budget),
-        utilized_tokens: Some(report.used),
-        frozen: config.frozen,
-        allow_sensitive: config.allow_sensitive,
-        snapshot_id: if config.frozen {
-            Some("FROZEN_SNAPSHOT".to_string())
-        } else {
-            None
-        },
-    };
-
-    let mut writer = XmlWriter::new(&mut buffer, xml_config);
+    serialize_entries_with_formatter(
+        config,
+        files,
+        &mut formats::ClaudeXmlFormatter::new(config).with_report(report),
+    )
+}
 
-    // Build attention entries from included files
-    // TODO: Integrate with ContextStore for utility scores
-    let mut attention_entries: Vec<AttentionEntry> = report
-        .included_files
-        .iter()
-        .map(|(path, priority, tokens, method)| {
-            AttentionEntry {
-                path: path.clone(),
-                priority: *priority,
-                tokens: *tokens,
-                truncated: method == "truncated",
-                dropped: false,
-                utility_score: None, // Will be populated from ContextStore when available
-            }
-        })
-        .collect();
+/// Claude-XML truncation (see [`truncate_for_xml`])
+pub(crate) struct XmlTruncation {
+    pub(crate) lines: usize,
+    pub(crate) mode: String,
+    /// The budget strategy truncated the file: cut it to its structure
+    pub(crate) budget_truncated: bool,
+}
 
-    // Add dropped files as coldspots
-    for (path, priority, tokens) in &report.dropped_files {
-        attention_entries.push(AttentionEntry {
-            path: path.clone(),
-            priority: *priority,
-            tokens: *tokens,
-            truncated: false,
-            dropped: true,
-            utility_score: None,
-        });
+impl transform::ContentTransform for XmlTruncation {
+    fn name(&self) -> &str {
+        "truncate"
     }
 
-    // Sort by priority descending for better attention_map ordering
-    attention_entries.sort_by(|a, b| b.priority.cmp(&a.priority));
-
-    // Apply active lens for priority calculation in file loop
-    let mut lens_manager = LensManager::new();
-    if let Some(ref lens_name) = config.active_lens {
-        let _ = lens_manager.apply_lens(lens_name);
+    fn stage(&self) -> transform::Stage {
+        transform::Stage::Truncate
     }
 
-    // Write XML structure
-    writer.write_context_start().map_err(|e| e.to_string())?;
-    writer
-        .write_metadata(&attention_entries)
-        .map_err(|e| e.to_string())?;
-    writer.write_files_start().map_err(|e| e.to_string())?;
-
-    for entry in files {
-        let language = detect_language(&entry.path);
-        let priority = lens_manager.get_static_priority(std::path::Path::new(&entry.path));
-
-        // Check if this file was truncated by the budget strategy
-        let was_truncated = report
-            .included_files
-            .iter()
-            .any(|(p, _, _, m)| p == &entry.path && m == "truncated");
-
-        // Apply truncation if configured or if budget strategy truncated it
-        let (content, truncated) = if was_truncated {
-            // Already truncated by budget strategy - use structure mode
-            let (trunc, _) = truncate_structure(&entry.content, &entry.path);
-            (trunc, true)
-        } else if config.truncate_lines > 0 {
-            truncate_for_xml(&entry.content, config.truncate_lines, &config.truncate_mode)
-        } else {
-            (entry.content.clone(), false)
-        };
-
-        let original_tokens = if truncated {
-            Some(entry.content.len() / 4)
-        } else {
-            None
-        };
+    fn fingerprint(&self) -> Option<String> {
+        Some(format!(
+            "xml-truncate:{}:{}:{}",
+            self.lines, self.mode, self.budget_truncated
+        ))
+    }
 
-        // Build zoom command for truncated files
-        let zoom_cmd = if truncated {
-            Some(format!("pm_encoder --zoom file={}", entry.path))
+    fn apply(&self, file: &transform::SourceFile<'_>, content: &mut transform::Content) {
+        let (text, truncated) = if self.budget_truncated {
+            (truncate_structure(&content.text, file.path).0, true)
+        } else if self.lines > 0 {
+            match truncate_for_xml(&content.text, self.lines, &self.mode) {
+                (text, true) => (with_minimum_context(&content.text, file.path, text), true),
+                result => result,
+            }
         } else {
-            None
+            return;
         };
-
-        writer
-            .write_file(
-                &entry.path,
-                language,
-                &entry.md5,
-                priority,
-                &content,
-                truncated,
-                original_tokens,
-                zoom_cmd.as_deref(),
-            )
-            .map_err(|e| e.to_string())?;
+        content.text = text;
+        content.was_truncated |= truncated;
     }
-
-    writer.write_files_end().map_err(|e| e.to_string())?;
-    writer.write_context_end().map_err(|e| e.to_string())?;
-    writer.flush().map_err(|e| e.to_string())?;
-
-    String::from_utf8(buffer).map_err(|e| e.to_string())
 }
 
-/// Truncate content for XML output
 fn truncate_for_xml(content: &str, max_lines: usize, mode: &str) -> (String, bool) {
     let lines: Vec<&str> = content.lines().collect();
     if lines.len() <= max_lines {
@@ -2467,6 +6423,7 @@ fn truncate_for_xml(content: &str, max_lines: usize, mode: &str) -> (String, boo
             let (truncated, was_truncated) = truncate_smart(content, max_lines, "file");
             (truncated, was_truncated)
         }
+        "tail" => truncate_tail_with_options(content, max_lines, "file", false),
         _ => {
             // Simple truncation
             let truncated: String = lines[..max_lines].join("\n");
@@ -2489,52 +6446,6 @@ fn detect_language_from_content(content: &str) -> String {
     }
 }
 
-/// Generate Claude-XML wrapper start with metadata
-///
-/// This function generates the opening `<context>` tag with metadata
-/// for Claude-XML format output.
-pub fn generate_claude_xml_header(config: &EncoderConfig, files: &[FileEntry]) -> String {
-    let mut header = String::new();
-    header.push_str("<context\n");
-    header.push_str("  package=\"pm_encoder\"\n");
-
-    if let Some(ref lens) = config.active_lens {
-        header.push_str(&format!("  lens=\"{}\"\n", lens));
-    }
-
-    if let Some(budget) = config.token_budget {
-        let utilized: usize = files
-            .iter()
-            .map(|f| f.content.len() / 4) // Rough token estimate
-            .sum();
-        header.push_str(&format!("  token_budget=\"{}\"\n", budget));
-        header.push_str(&format!("  utilized=\"{}\"\n", utilized));
-    }
-
-    header.push_str(">\n");
-    header.push_str("  <metadata>\n");
-    header.push_str(&format!("    <version>{}</version>\n", VERSION));
-    header.push_str(&format!("    <frozen>{}</frozen>\n", config.frozen));
-
-    if !config.frozen {
-        // Only include timestamp in non-frozen mode
-        header.push_str(&format!(
-            "    <timestamp>{}</timestamp>\n",
-            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
-        ));
-    }
-
-    if let Some(ref lens) = config.active_lens {
-        header.push_str("    <lens_config>\n");
-        header.push_str(&format!("      <name>{}</name>\n", lens));
-        header.push_str("    </lens_config>\n");
-    }
-
-    header.push_str("  </metadata>\n\n");
-    header.push_str("  <files>\n");
-    header
-}
-
 /// Serialize a project in streaming mode (immediate output)
 ///
 /// Writes each file to stdout as it's discovered, enabling immediate output
@@ -2550,12 +6461,31 @@ pub fn generate_claude_xml_header(config: &EncoderConfig, files: &[FileEntry]) -
 /// * `Ok(String)` - Always returns empty string (output goes to stdout)
 /// * `Err(String)` - Error message if serialization fails
 pub fn serialize_project_streaming(root: &str, config: &EncoderConfig) -> Result<String, String> {
-    use std::io::{self, Write};
+    stream_project(root, config)?;
+    // Output was written directly
+    Ok(String::new())
+}
 
-    let root_path = Path::new(root);
-    if !root_path.exists() {
-        return Err(format!("Directory not found: {}", root));
-    }
+/// Stream the project to stdout, like [`serialize_project_streaming`]
+///
+/// With `config.token_budget` the tokens written are counted, and once a
+/// file doesn't fit in what's left nothing more is written: that file and
+/// every one after it is omitted. Returns the budget's report (strategy
+/// `stream`), listing the files written and omitted.
+pub fn stream_project(root: &str, config: &EncoderConfig) -> Result<Option<BudgetReport>, String> {
+    stream_project_to(root, config, &mut std::io::stdout().lock())
+}
+
+/// Stream the project into `out`, like [`stream_project`] does to stdout
+///
+/// Each file is flushed to `out` once written (see [`formats::StreamWriter`]).
+pub fn stream_project_to(
+    root: &str,
+    config: &EncoderConfig,
+    out: &mut dyn std::io::Write,
+) -> Result<Option<BudgetReport>, String> {
+    let roots = config.roots(root)?;
+    let content_filter = discovery::ContentFilter::new(&config.exclude_content_patterns)?;
 
     // Warn if sorting options are specified (they're ignored in streaming mode)
     if config.sort_by != "name" || config.sort_order != "asc" {
@@ -2564,62 +6494,537 @@ pub fn serialize_project_streaming(root: &str, config: &EncoderConfig) -> Result
         );
     }
 
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
-
-    // Stream files as they're discovered
-    for entry in walk_directory_iter(
-        root,
-        config.ignore_patterns.clone(),
-        config.include_patterns.clone(),
-        config.max_file_size,
-    ) {
-        let serialized = serialize_file_with_format_and_metadata(
-            &entry,
-            config.truncate_lines,
-            &config.truncate_mode,
-            config.output_format,
-            config.metadata_mode,
-        );
-        // Write immediately to stdout
-        if handle.write_all(serialized.as_bytes()).is_err() {
-            break; // Broken pipe or similar, stop gracefully
-        }
-        // Flush to ensure immediate output
-        let _ = handle.flush();
+    let mut writer = formats::StreamWriter::new(out, config).map_err(|e| e.to_string())?;
+
+    // Stream files as they're discovered, root by root, or as ranked; the
+    // depth limit's pruning is only counted, for the summary
+    let pruned = Arc::new(ExclusionLedger::new());
+    let entries: Box<dyn Iterator<Item = Result<FileEntry, SkippedFile>>> = match config
+        .stream_order
+    {
+        StreamOrder::Priority => Box::new(walk_roots_by_priority(roots, config, pruned.clone())?),
+        StreamOrder::Walk => Box::new(roots.into_iter().flat_map(|(root_path, prefix)| {
+            walk_directory_iter_inner(
+                &root_path.to_string_lossy(),
+                config.ignore_patterns.clone(),
+                config.include_patterns.clone(),
+                config.pattern_style(),
+                config.max_file_size,
+                config.max_depth,
+                config.respect_gitignore,
+                config.directory_configs_within(prefix.as_deref()),
+                config.git_only,
+                config.follow_symlinks,
+                config.binary,
+                Some(pruned.clone()),
+                config.timings.clone(),
+            )
+            .map(move |result| prefix_walk_result(prefix.as_deref(), result))
+        })),
+    };
+    let mut limits = WalkLimits::new(config);
+    let mut dedupe = Dedupe::default();
+    let priority = file_priority(config)?;
+    let overhead = match config.token_budget {
+        Some(_) => format_overhead(config)?,
+        None => FormatOverhead::default(),
+    };
+    // The envelope is already written
+    let mut report = config.token_budget.map(|budget| BudgetReport {
+        budget,
+        used: overhead.envelope_tokens(),
+        selected_count: 0,
+        dropped_count: 0,
+        dropped_files: Vec::new(),
+        estimation_method: TokenEstimator::method().to_string(),
+        strategy: "stream".to_string(),
+        included_files: Vec::new(),
+        truncated_count: 0,
+        framing: FramingCost {
+            overhead: overhead.clone(),
+            tokens: overhead.envelope_tokens(),
+        },
+    });
+    let mut budget_reached = false;
+    for mut entry in entries.filter_map(|result| result.map_err(|f| config.report_skipped(f)).ok())
+    {
+        if let Some(only) = &config.only_paths {
+            if !only.contains(&entry.path) {
+                continue;
+            }
+        }
+        if config
+            .changed_since
+            .as_ref()
+            .is_some_and(|since| !since.admits(&entry))
+        {
+            continue;
+        }
+        if config.generated == GeneratedPolicy::Skip
+            && config
+                .generated_status(&entry.path, &entry.content)
+                .is_some()
+        {
+            continue;
+        }
+        if content_filter.matching(&entry.content).is_some() {
+            continue;
+        }
+        if limits.admit(&entry).is_some() {
+            continue;
+        }
+        if config.dedupe {
+            dedupe.stub(&mut entry);
+        }
+        let Some(report) = report.as_mut() else {
+            // Write immediately
+            if writer.write_entry(&entry).is_err() {
+                break; // Broken pipe or similar, stop gracefully
+            }
+            continue;
+        };
+        let written = if budget_reached {
+            None
+        } else {
+            match writer.write_entry_within(&entry, report.remaining()) {
+                Ok(written) => written,
+                Err(_) => break,
+            }
+        };
+        match written {
+            Some(tokens) => {
+                report.used += tokens;
+                report.framing.tokens += overhead.frame_tokens(&entry.path);
+                let priority = priority(&entry.path);
+                report
+                    .included_files
+                    .push((entry.path, priority, tokens, "full".to_string()));
+            }
+            None => {
+                // The rest is still walked, so the report lists it
+                budget_reached = true;
+                let tokens = overhead.file_tokens(&entry.path, &entry.content);
+                let priority = priority(&entry.path);
+                report.dropped_files.push((entry.path, priority, tokens));
+            }
+        }
+    }
+    limits.count_pruned(&Arc::try_unwrap(pruned).unwrap_or_default().into_sorted());
+    limits.warn();
+    // Close the format's envelope (also done on drop if we bailed out early)
+    let _ = writer.finish();
+
+    Ok(report.map(|mut report| {
+        report.selected_count = report.included_files.len();
+        report.dropped_count = report.dropped_files.len();
+        report
+    }))
+}
+
+/// `result` with its path under `prefix`, if any
+fn prefix_walk_result(
+    prefix: Option<&str>,
+    result: Result<FileEntry, SkippedFile>,
+) -> Result<FileEntry, SkippedFile> {
+    let Some(prefix) = prefix else {
+        return result;
+    };
+    result
+        .map(|entry| FileEntry {
+            path: prefixed_path(prefix, &entry.path),
+            ..entry
+        })
+        .map_err(|file| SkippedFile {
+            path: prefixed_path(prefix, &file.path),
+            ..file
+        })
+}
+
+/// The priority of a path under `config.active_lens`, blended with the
+/// utility store's if one is loaded
+fn file_priority(config: &EncoderConfig) -> Result<impl Fn(&str) -> i32 + '_, String> {
+    let mut lenses = LensManager::new();
+    if let Some(lens) = &config.active_lens {
+        lenses.apply_lens(lens)?;
+    }
+    Ok(move |path: &str| {
+        let priority = lenses.get_static_priority(Path::new(path));
+        config
+            .utility_store
+            .as_ref()
+            .map_or(priority, |store| store.blend_priority(path, priority))
+    })
+}
+
+/// The files of `roots` in [`StreamOrder::Priority`] order
+///
+/// Every root is listed first, without reading a file; files are then read
+/// one at a time, highest priority first. Archives can't be listed without
+/// reading them, so theirs are read while listing.
+fn walk_roots_by_priority<'a>(
+    roots: Vec<(&'a Path, Option<String>)>,
+    config: &'a EncoderConfig,
+    pruned: Arc<ExclusionLedger>,
+) -> Result<impl Iterator<Item = Result<FileEntry, SkippedFile>> + 'a, String> {
+    let priority = file_priority(config)?;
+
+    enum Listed {
+        /// Path on disk, path within its root, root index
+        Unread(std::path::PathBuf, String, usize),
+        Read(Result<FileEntry, SkippedFile>),
+    }
+    let mut listed: Vec<(String, Listed)> = Vec::new();
+    let mut root_configs = Vec::new();
+    for (index, (root_path, prefix)) in roots.into_iter().enumerate() {
+        let directory_configs = config
+            .directory_configs_within(prefix.as_deref())
+            .filter(|d| !d.is_empty());
+        if discovery::archive::is_archive(root_path) {
+            let results = walk_directory_iter_inner(
+                &root_path.to_string_lossy(),
+                config.ignore_patterns.clone(),
+                config.include_patterns.clone(),
+                config.pattern_style(),
+                config.max_file_size,
+                config.max_depth,
+                config.respect_gitignore,
+                directory_configs.clone(),
+                config.git_only,
+                config.follow_symlinks,
+                config.binary,
+                Some(pruned.clone()),
+                config.timings.clone(),
+            );
+            for result in results {
+                let result = prefix_walk_result(prefix.as_deref(), result);
+                let path = match &result {
+                    Ok(entry) => entry.path.clone(),
+                    Err(file) => file.path.clone(),
+                };
+                listed.push((path, Listed::Read(result)));
+            }
+        } else {
+            let candidates = walk_candidates(
+                root_path,
+                &config.ignore_patterns,
+                config.pattern_style(),
+                config.max_depth,
+                config.respect_gitignore,
+                directory_configs.clone(),
+                config.git_only,
+                config.follow_symlinks,
+                Some(pruned.clone()),
+            );
+            for candidate in candidates {
+                let reported = |path: &str| match &prefix {
+                    Some(prefix) => prefixed_path(prefix, path),
+                    None => path.to_string(),
+                };
+                match candidate {
+                    Ok((path, path_str)) => {
+                        listed.push((reported(&path_str), Listed::Unread(path, path_str, index)))
+                    }
+                    Err(mut file) => {
+                        file.path = reported(&file.path);
+                        listed.push((file.path.clone(), Listed::Read(Err(file))));
+                    }
+                }
+            }
+        }
+        root_configs.push((prefix, directory_configs));
+    }
+    listed.sort_by_cached_key(|(path, _)| (std::cmp::Reverse(priority(path)), path.clone()));
+
+    Ok(listed.into_iter().filter_map(move |(_, item)| match item {
+        Listed::Read(result) => Some(result),
+        Listed::Unread(path, path_str, root) => {
+            let (prefix, directory_configs) = &root_configs[root];
+            read_walked_file(
+                &path,
+                &path_str,
+                &config.ignore_patterns,
+                &config.include_patterns,
+                config.pattern_style(),
+                config.max_file_size,
+                directory_configs.as_ref(),
+                config.binary,
+                Some(&pruned),
+                config.timings.as_deref(),
+            )
+            .map(|result| prefix_walk_result(prefix.as_deref(), result))
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version() {
+        assert_eq!(version(), "1.0.0");
+    }
+
+    #[test]
+    fn test_serialize_project() {
+        let result = serialize_project(".");
+        assert!(result.is_ok());
+        // Result should be in Plus/Minus format
+        let output = result.unwrap();
+        assert!(output.contains("++++++++++")); // Plus/Minus format header
+    }
+
+    #[test]
+    fn test_serialize_with_config() {
+        let config = EncoderConfig {
+            truncate_lines: 100,
+            max_file_size: 1024,
+            ..Default::default()
+        };
+        let result = serialize_project_with_config(".", &config);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_split_entries_by_tokens_cuts_at_file_boundaries() {
+        let entries: Vec<FileEntry> = (0..5)
+            .map(|i| {
+                let content = format!("{}\n", "x".repeat(400));
+                FileEntry {
+                    path: format!("file{}.txt", i),
+                    md5: calculate_md5(&content),
+                    size: content.len() as u64,
+                    content,
+                    mtime: 0,
+                    ctime: 0,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let config = EncoderConfig::default();
+
+        let parts = split_entries_by_tokens(&config, &entries, 250).unwrap();
+        assert!(parts.len() > 1);
+        for part in &parts {
+            assert!(TokenEstimator::estimate_tokens(part) <= 250);
+            assert_eq!(
+                part.matches("++++++++++ ").count(),
+                part.matches("---------- ").count()
+            );
+        }
+        let total: usize = parts.iter().map(|p| p.matches("++++++++++ ").count()).sum();
+        assert_eq!(total, 5);
+    }
+
+    #[test]
+    fn test_split_entries_oversized_file_gets_own_part() {
+        let big = FileEntry {
+            path: "big.txt".to_string(),
+            content: "y".repeat(4000),
+            md5: String::new(),
+            mtime: 0,
+            ctime: 0,
+            size: 4000,
+            ..Default::default()
+        };
+        let config = EncoderConfig::default();
+        let parts = split_entries_by_tokens(&config, &[big.clone(), big], 100).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(split_entries_by_tokens(&config, &[], 0).is_err());
+    }
+
+    #[test]
+    fn test_split_part_path() {
+        assert_eq!(
+            split_part_path(Path::new("out/context.txt"), 2),
+            Path::new("out/context.part2.txt")
+        );
+        assert_eq!(
+            split_part_path(Path::new("bundle"), 1),
+            Path::new("bundle.part1")
+        );
+        assert_eq!(
+            role_section_path(Path::new("out/context.xml"), "system"),
+            Path::new("out/context.system.xml")
+        );
+    }
+
+    #[test]
+    fn test_format_overhead() {
+        let overhead = |format| {
+            format_overhead(&EncoderConfig {
+                output_format: format,
+                metadata_mode: MetadataMode::None,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        // Header path, then path, checksum and path again in the footer
+        let plus_minus = overhead(OutputFormat::PlusMinus);
+        assert_eq!(plus_minus.format, "plus_minus");
+        assert_eq!(plus_minus.envelope_bytes, 0);
+        assert_eq!(plus_minus.path_repeats, 3);
+
+        let claude_xml = overhead(OutputFormat::ClaudeXml);
+        assert!(claude_xml.envelope_bytes > 0);
+        assert!(claude_xml.file_bytes > plus_minus.file_bytes);
+
+        // The measure matches what serializing costs, give or take
+        // attributes of varying width (token counts, language names)
+        let config = EncoderConfig {
+            output_format: OutputFormat::ClaudeXml,
+            metadata_mode: MetadataMode::None,
+            ..Default::default()
+        };
+        let entry = FileEntry::new("docs/deeply/nested/notes.txt", "note\n".repeat(20));
+        let output = serialize_sorted_entries(&config, std::slice::from_ref(&entry)).unwrap();
+        let measured = claude_xml.envelope_bytes
+            + claude_xml.file_bytes
+            + claude_xml.path_repeats * entry.path.len()
+            + entry.content.len();
+        assert!(measured.abs_diff(output.len()) <= 8);
     }
 
-    // Return empty string - output was written directly
-    Ok(String::new())
-}
+    #[test]
+    fn test_serialize_roles() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join(".pmattributes"), "docs/*.md pin\n").unwrap();
+        let config = EncoderConfig {
+            attributes: Some(attributes::PathAttributes::discover(temp.path()).unwrap()),
+            ..Default::default()
+        };
+        let entries = vec![
+            FileEntry::new("docs/guide.md", "# Guide\n"),
+            FileEntry::new("src/lib.rs", "pub fn a() {}\n"),
+            FileEntry::new("src/util/mod.rs", "pub fn b() {}\n"),
+            FileEntry::new("Cargo.toml", "[package]\n"),
+        ];
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let sections = serialize_roles(&config, &entries, |e| e.path.starts_with("src/")).unwrap();
+        assert!(sections.system.starts_with(
+            "Project tree:\n\
+             ├── docs/\n\
+             │   └── guide.md\n\
+             ├── src/\n\
+             │   ├── util/\n\
+             │   │   └── mod.rs\n\
+             │   └── lib.rs\n\
+             └── Cargo.toml\n\
+             \n\
+             File index (4 files, ~10 tokens):\n  docs/guide.md  ~2 tokens\n"
+        ));
+        // Pinned docs are stable, the selected files volatile, the rest listed only
+        assert!(sections.system.contains("++++++++++ docs/guide.md"));
+        assert!(!sections.system.contains("++++++++++ src/lib.rs"));
+        assert!(sections.user.contains("++++++++++ src/lib.rs"));
+        assert!(sections.user.contains("++++++++++ src/util/mod.rs"));
+        assert!(!sections.user.contains("guide.md"));
+        assert!(!sections.user.contains("Cargo.toml"));
+        assert!(!sections.system.contains("++++++++++ Cargo.toml"));
+    }
 
     #[test]
-    fn test_version() {
-        assert_eq!(version(), "1.0.0");
+    fn test_serialize_project_to_bundle() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::write(temp.path().join("a.py"), "print('a')\n").unwrap();
+        fs::write(temp.path().join("b.rs"), "fn b() {}\n").unwrap();
+        let bundle_path = temp.path().join("out.tar.gz");
+
+        let config = EncoderConfig {
+            frozen: true,
+            ..Default::default()
+        };
+        let manifest =
+            serialize_project_to_bundle(temp.path().to_str().unwrap(), &config, &bundle_path)
+                .unwrap();
+
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.py", "b.rs"]);
+        assert_eq!(manifest.context_file, "context.txt");
+        assert_eq!(
+            crate::core::Bundle::read_manifest(&bundle_path).unwrap(),
+            manifest
+        );
     }
 
     #[test]
-    fn test_serialize_project() {
-        let result = serialize_project(".");
-        assert!(result.is_ok());
-        // Result should be in Plus/Minus format
-        let output = result.unwrap();
-        assert!(output.contains("++++++++++")); // Plus/Minus format header
+    fn test_build_sidecar_manifest() {
+        let entries: Vec<FileEntry> = [("a.py", "l1\nl2\nl3\nl4\n"), ("b.rs", "fn b() {}\n")]
+            .iter()
+            .map(|(path, content)| FileEntry {
+                path: path.to_string(),
+                md5: calculate_md5(content),
+                size: content.len() as u64,
+                content: content.to_string(),
+                mtime: 0,
+                ctime: 0,
+                ..Default::default()
+            })
+            .collect();
+        let config = EncoderConfig {
+            truncate_lines: 2,
+            frozen: true,
+            active_lens: Some("debug".to_string()),
+            token_budget: Some(500),
+            ..Default::default()
+        };
+        let output = serialize_sorted_entries(&config, &entries).unwrap();
+        let manifest = build_sidecar_manifest(&config, &entries, &output);
+
+        assert!(manifest.generated_at.is_none());
+        assert_eq!(manifest.lens.as_deref(), Some("debug"));
+        assert_eq!(manifest.token_budget, Some(500));
+        assert_eq!(
+            manifest.total_tokens,
+            TokenEstimator::estimate_tokens(&output)
+        );
+        assert_eq!(manifest.files[0].truncation, "simple");
+        assert_eq!(manifest.files[0].original_lines, 5);
+        assert_eq!(manifest.files[0].md5, entries[0].md5);
+        assert_eq!(manifest.files[1].truncation, "none");
+        assert!(manifest.files[1].tokens > 0);
     }
 
     #[test]
-    fn test_serialize_with_config() {
-        let config = EncoderConfig {
-            truncate_lines: 100,
-            max_file_size: 1024,
+    fn test_serialize_sorted_entries_since() {
+        let changed = "def changed():\n    return 1\n";
+        let stable = "def stable():\n    x = 1\n    y = 2\n    return x + y\n";
+        let entries: Vec<FileEntry> = [("changed.py", changed), ("stable.py", stable)]
+            .iter()
+            .map(|(path, content)| FileEntry {
+                path: path.to_string(),
+                md5: calculate_md5(content),
+                size: content.len() as u64,
+                content: content.to_string(),
+                mtime: 0,
+                ctime: 0,
+                ..Default::default()
+            })
+            .collect();
+        let mut changes = std::collections::BTreeMap::new();
+        changes.insert(
+            "changed.py".to_string(),
+            "@@ -1,2 +1,2 @@\n def changed():\n-    return 0\n+    return 1\n".to_string(),
+        );
+        changes.insert(
+            "gone.py".to_string(),
+            "diff --git a/gone.py b/gone.py\ndeleted file mode 100644\n@@ -1 +0,0 @@\n-x = 1\n"
+                .to_string(),
+        );
+        let config = EncoderConfig::default();
+
+        let output = serialize_sorted_entries_since(&config, &entries, "HEAD~1", &changes).unwrap();
+        assert!(output.contains("~~~~~~~~~~ changed.py [DIFF since HEAD~1] ~~~~~~~~~~"));
+        assert!(output.contains("+    return 1"));
+        assert!(!output.contains("    return x + y"));
+        assert!(output.contains("++++++++++ stable.py [TRUNCATED:"));
+        assert!(output.contains("~~~~~~~~~~ gone.py [DIFF since HEAD~1] ~~~~~~~~~~"));
+
+        let claude = EncoderConfig {
+            output_format: OutputFormat::ClaudeXml,
             ..Default::default()
         };
-        let result = serialize_project_with_config(".", &config);
-        assert!(result.is_ok());
+        assert!(serialize_sorted_entries_since(&claude, &entries, "HEAD", &changes).is_err());
     }
 
     #[test]
@@ -2833,6 +7238,136 @@ impl Config {
         }
     }
 
+    #[test]
+    fn test_truncate_structure_multiline_signatures() {
+        let python = "class Store:\n    def put(\n        self,\n        key: str,\n    ) -> None:\n        self.items[key] = 1\n";
+        let (result, truncated) = truncate_structure_with_options(python, "store.py", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "class Store:\n    def put(\n        self,\n        key: str,\n    ) -> None:\n"
+        );
+
+        let rust =
+            "pub fn load(\n    path: &Path,\n) -> Result<Config, String> {\n    todo!()\n}\n";
+        let (result, _) = truncate_structure_with_options(rust, "config.rs", false);
+        assert_eq!(
+            result,
+            "pub fn load(\n    path: &Path,\n) -> Result<Config, String> {\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_structure_multiline_signatures_ast() {
+        // Go and Java have no analyzer; voyager-ast finds imports and signatures
+        let go = "package store\n\nimport \"fmt\"\n\n// Put stores a value.\nfunc (s *Store) Put(\n\tkey string,\n\tvalue []byte,\n) error {\n\treturn fmt.Errorf(\"%s\", key)\n}\n";
+        let (result, truncated) = truncate_structure_with_options(go, "store.go", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "import \"fmt\"\nfunc (s *Store) Put(\n\tkey string,\n\tvalue []byte,\n) error {\n"
+        );
+
+        let java = "package com.example;\n\nimport java.io.IOException;\n\npublic class Store\n        implements Cache {\n    public byte[] put(\n            String key,\n            byte[] value)\n            throws IOException {\n        return value;\n    }\n}\n";
+        let (result, truncated) = truncate_structure_with_options(java, "Store.java", true);
+        assert!(truncated);
+        assert!(result.starts_with(
+            "import java.io.IOException;\npublic class Store\n        implements Cache {\n    public byte[] put(\n            String key,\n            byte[] value)\n            throws IOException {\n\n"
+        ));
+        assert!(result.contains("Language: Java"));
+
+        // C# has an analyzer; voyager-ast adds the rest of a multi-line header
+        let csharp = "namespace Shop;\n\npublic class Store\n    : Base,\n      IStore\n{\n    public async Task<int> PutAsync(\n        string key,\n        byte[] value)\n    {\n        return await Save(key, value);\n    }\n}\n";
+        let (result, _) = truncate_structure_with_options(csharp, "Store.cs", false);
+        assert_eq!(
+            result,
+            "namespace Shop;\npublic class Store\n    : Base,\n      IStore\n    public async Task<int> PutAsync(\n        string key,\n        byte[] value)\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_structure_csharp() {
+        let csharp = "using System;\n\nnamespace Shop;\n\n[Serializable]\npublic class Cart\n{\n    public int Count { get; private set; }\n\n    public decimal Total => Lines.Sum(l => l.Price);\n\n    public void Add(Line line)\n    {\n        Lines.Add(line);\n        Count++;\n    }\n}\n";
+        let (result, truncated) = truncate_structure_with_options(csharp, "Cart.cs", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "using System;\nnamespace Shop;\n[Serializable]\npublic class Cart\n    public int Count { get; private set; }\n    public decimal Total => Lines.Sum(l => l.Price);\n    public void Add(Line line)\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_structure_ruby_and_php() {
+        let ruby = "require \"json\"\n\nmodule Shop\n  class Cart\n    attr_reader :items\n\n    def add(item)\n      items << item\n    end\n  end\nend\n";
+        let (result, truncated) = truncate_structure_with_options(ruby, "cart.rb", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "require \"json\"\nmodule Shop\n  class Cart\n    attr_reader :items\n    def add(item)\n"
+        );
+
+        let php = "<?php\nnamespace App;\n\nuse App\\Models\\Item;\n\nclass Cart\n{\n    private array $items = [];\n\n    public function add(Item $item): void\n    {\n        $this->items[] = $item;\n    }\n}\n";
+        let (result, truncated) = truncate_structure_with_options(php, "Cart.php", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "namespace App;\nuse App\\Models\\Item;\nclass Cart\n    private array $items = [];\n    public function add(Item $item): void\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_outline_rust() {
+        let rust = "//! Ledger postings\n\nuse std::fmt;\n\n/// A posting\n#[derive(Debug)]\npub struct Posting {\n    amount: i64,\n}\n\nimpl Posting {\n    /// A new posting\n    pub fn new(\n        amount: i64,\n    ) -> Self {\n        Self { amount }\n    }\n\n    fn check(&self) -> bool {\n        true\n    }\n}\n\nimpl fmt::Display for Posting {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n        write!(f, \"{}\", self.amount)\n    }\n}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn test_new() {}\n}\n";
+        let (result, truncated) = truncate_outline_with_options(rust, "src/ledger.rs", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "//! Ledger postings\n\
+             /// A posting\n\
+             #[derive(Debug)]\n\
+             pub struct Posting {\n\
+             impl Posting {\n    \
+             /// A new posting\n    \
+             pub fn new(\n        \
+             amount: i64,\n    \
+             ) -> Self {\n\
+             impl fmt::Display for Posting {\n    \
+             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_outline_python_and_javascript() {
+        let python = "\"\"\"Ledger postings.\"\"\"\nimport os\n\n\nclass Ledger:\n    \"\"\"A set of accounts.\n\n    Balanced.\n    \"\"\"\n\n    def post(self, amount):\n        '''Post `amount`.'''\n        self.total += amount\n\n    def _check(self):\n        \"\"\"Internal.\"\"\"\n        return True\n\n\ndef _helper():\n    pass\n";
+        let (result, _) = truncate_outline_with_options(python, "ledger.py", false);
+        assert_eq!(
+            result,
+            "\"\"\"Ledger postings.\"\"\"\n\
+             class Ledger:\n    \
+             \"\"\"A set of accounts.\n\n    \
+             Balanced.\n    \
+             \"\"\"\n    \
+             def post(self, amount):\n        \
+             '''Post `amount`.'''\n"
+        );
+
+        let js = "import fs from 'fs';\n\n/** Reads the ledger */\nexport function read(path) {\n  return fs.readFileSync(path);\n}\n\nfunction parse(text) {\n  return text;\n}\n";
+        let (result, truncated) = truncate_outline(js, "ledger.js");
+        assert!(truncated);
+        assert!(result.starts_with("/** Reads the ledger */\nexport function read(path) {\n\n"));
+        assert!(result.contains("OUTLINE MODE: Showing docs and public signatures (2/11 lines)"));
+
+        // Content without declarations gets structure mode
+        let text = "This is just some plain text.\nNothing special here.";
+        assert_eq!(
+            truncate_outline(text, "readme.txt"),
+            (text.to_string(), false)
+        );
+        // Through truncate_content, imports are kept like in every mode
+        let (result, _) = truncate_content(python, "ledger.py", 0, "outline", false);
+        assert!(result.starts_with("\"\"\"Ledger postings.\"\"\"\nimport os\nclass Ledger:\n"));
+    }
+
     #[test]
     fn test_truncate_structure_non_code_file() {
         let text = "This is just some plain text.\nNothing special here.\nJust text.";
@@ -2852,6 +7387,7 @@ impl Config {
             mtime: 1234567890,
             ctime: 1234567890,
             size: 14,
+            ..Default::default()
         };
 
         let serialized = serialize_file(&entry);
@@ -2906,22 +7442,56 @@ impl Config {
             ignore_patterns: vec!["*.log".to_string()],
             include_patterns: vec!["*.rs".to_string()],
             max_file_size: 1_000_000,
+            max_depth: Some(8),
+            max_total_files: Some(1_000),
+            max_total_bytes: None,
+            temporal_thresholds: core::TemporalThresholds::default(),
+            declaration_kinds: vec![],
+            generated: GeneratedPolicy::Keep,
+            binary: BinaryPolicy::Skip,
+            lockfiles: LockfilePolicy::Summary,
+            exclude_content_patterns: vec![],
+            pattern_syntax: PatternSyntax::V2,
+            case_insensitive_patterns: true,
             truncate_lines: 500,
             truncate_mode: "smart".to_string(),
+            strip_comments: false,
+            truncate_keep_patterns: vec![],
+            escape_control_chars: false,
             sort_by: "mtime".to_string(),
             sort_order: "desc".to_string(),
             stream: true,
+            stream_order: StreamOrder::Priority,
             truncate_summary: true,
             truncate_exclude: vec![],
             truncate_stats: false,
             output_format: OutputFormat::PlusMinus,
             frozen: true,
+            glossary: false,
             allow_sensitive: false,
             active_lens: Some("architecture".to_string()),
             token_budget: Some(100_000),
             skeleton_mode: SkeletonMode::Auto,
             metadata_mode: MetadataMode::Auto,
-            follow_symlinks: false,
+            follow_symlinks: SymlinkPolicy::Off,
+            only_paths: None,
+            line_numbers: false,
+            dedupe: false,
+            header_hints: false,
+            templates: None,
+            respect_gitignore: true,
+            directory_configs: None,
+            attributes: None,
+            utility_store: None,
+            timings: None,
+            skipped: None,
+            cache: None,
+            git_only: false,
+            changed_since: None,
+            extra_roots: vec![],
+            prefix_roots: false,
+            path_prefixes: vec![],
+            transforms: transform::TransformPipeline::new(),
         };
 
         assert_eq!(config.truncate_lines, 500);
@@ -2958,20 +7528,490 @@ impl Config {
     }
 
     #[test]
-    fn test_truncate_modes_all() {
-        let python = "def foo():\n    pass\n";
-
-        // Simple mode - no truncation needed for short content
-        let (result, _) = truncate_simple(python, 100, "test.py");
-        assert!(result.contains("def foo"));
+    fn test_truncate_modes_all() {
+        let python = "def foo():\n    pass\n";
+
+        // Simple mode - no truncation needed for short content
+        let (result, _) = truncate_simple(python, 100, "test.py");
+        assert!(result.contains("def foo"));
+
+        // Smart mode - no truncation needed for short content
+        let (result, _) = truncate_smart(python, 100, "test.py");
+        assert!(result.contains("def foo"));
+
+        // Structure mode
+        let (result, _) = truncate_structure(python, "test.py");
+        assert!(result.contains("def foo"));
+    }
+
+    #[test]
+    fn test_truncate_tokens() {
+        // 100 lines of 20 bytes: 500 tokens
+        let content: String = (0..100)
+            .map(|i| format!("line {:02} ...........\n", i))
+            .collect();
+        let (result, truncated) = truncate_tokens_with_options(&content, 100, "a.txt", false);
+        assert!(truncated);
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[0], "line 00 ...........");
+        assert_eq!(lines[15], "line 15 ...........");
+        assert_eq!(lines[17], "... [80 lines omitted] ...");
+        assert_eq!(lines[19], "line 96 ...........");
+        assert_eq!(lines.last(), Some(&"line 99 ..........."));
+
+        // One minified line is cut inside it
+        let minified = format!("{}{}", "a".repeat(2000), "z".repeat(2000));
+        let (result, truncated) = truncate_tokens(&minified, 100, "app.min.js");
+        assert!(truncated);
+        assert!(result.starts_with(&format!(
+            "{}\n... [~900 tokens omitted] ...\n\n",
+            "a".repeat(320)
+        )));
+        assert!(result.contains(&format!("\n\n{}\n", "z".repeat(80))));
+        assert!(result.contains("TOKEN TRUNCATED: kept ~100/1000 tokens (90% reduction)"));
+
+        // Under the limit, or through truncate_content with no limit
+        assert_eq!(
+            truncate_content(&content, "a.txt", 500, "tokens", true),
+            (content.clone(), false)
+        );
+        assert!(truncate_content(&content, "a.txt", 50, "tokens", true).1);
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let rust =
+            "// Licensed under MIT\n// Copyright\nfn a() {}\n/* old\n   code */\nfn b() {}\n";
+        assert_eq!(
+            strip_comments(rust, "src/a.rs"),
+            ("fn a() {}\nfn b() {}\n".to_string(), true)
+        );
+        assert_eq!(
+            strip_comments("// kept\n", "notes.txt"),
+            ("// kept\n".to_string(), false)
+        );
+
+        // As a mode, it applies without a line limit and composes with one
+        assert_eq!(
+            truncate_content(rust, "src/a.rs", 0, "strip-comments", false),
+            ("fn a() {}\nfn b() {}\n".to_string(), true)
+        );
+        assert_eq!(
+            truncate_content(rust, "src/a.rs", 1, "strip-comments", false).0,
+            "fn a() {}"
+        );
+
+        // As a pre-pass, ahead of the configured mode
+        let config = EncoderConfig::builder()
+            .strip_comments(true)
+            .truncate(1, "simple")
+            .build()
+            .unwrap();
+        let pipeline = config.transforms_for("src/a.rs", false);
+        assert_eq!(
+            pipeline.names(),
+            ["strip-comments", "truncate", "escape-control-chars"]
+        );
+        assert_eq!(pipeline.run("src/a.rs", rust).text, "fn a() {}");
+    }
+
+    #[test]
+    fn test_truncate_table() {
+        let mut csv = String::from("id,city,note\n");
+        for i in 1..=30 {
+            csv.push_str(&format!("{},Oslo,\"line one\nline two\"\n", i));
+        }
+
+        let (result, truncated) = truncate_content(&csv, "trips.csv", 7, "smart", false);
+        assert!(truncated);
+        let expected_head = "id,city,note\n\
+                             1,Oslo,\"line one\nline two\"\n\
+                             2,Oslo,\"line one\nline two\"\n\
+                             3,Oslo,\"line one\nline two\"\n\
+                             4,Oslo,\"line one\nline two\"\n\
+                             5,Oslo,\"line one\nline two\"\n\
+                             6,Oslo,\"line one\nline two\"\n\
+                             \n... [48 lines omitted: 24 rows] ...\n\n\
+                             Columns (30 rows):\n\
+                             \x20 id: integer, 1 to 30, mean 15.50\n\
+                             \x20 city: text, 1 distinct\n\
+                             \x20 note: text, 1 distinct\n";
+        assert_eq!(result, expected_head);
+        let map = line_map(&result, &csv);
+        assert_eq!(map[12], Some(13));
+        assert_eq!(map[13], None);
+
+        // Structure mode samples a fixed number of rows, with the summary
+        let (result, _) = truncate_content(&csv, "trips.csv", 0, "structure", true);
+        assert!(result.contains("\n10,Oslo,"));
+        assert!(!result.contains("\n11,Oslo,"));
+        assert!(result.contains("[40 lines omitted: 20 rows]"));
+        assert!(result.contains("TABLE SAMPLED: kept 10/30 rows (66% reduction)"));
+
+        // Rows matching a keep pattern survive, each gap counting its rows
+        let keep = KeepPatterns::new(&["^20,".to_string()]).unwrap();
+        let (result, _) = truncate_content_keeping(&csv, "trips.csv", 3, "smart", false, &keep);
+        assert!(result.contains("[34 lines omitted: 17 rows] ...\n\n20,Oslo,"));
+        assert!(result.contains("[20 lines omitted: 10 rows]"));
+    }
+
+    #[test]
+    fn test_truncate_schema() {
+        let mut yaml = String::from("version: 2\nservices:\n");
+        for i in 0..20 {
+            yaml.push_str(&format!(
+                "  - name: svc{}\n    port: {}\n    debug: false\n",
+                i, i
+            ));
+        }
+        let (result, truncated) = truncate_content(&yaml, "compose.yml", 10, "smart", true);
+        assert!(truncated);
+        assert!(result.starts_with(
+            "... [62 lines omitted] ...\n\n\
+             Schema (object):\n  \
+             version: integer\n  \
+             services: array of object, 20 items\n    \
+             name: string\n    \
+             port: integer\n    \
+             debug: boolean\n"
+        ));
+        assert!(result.contains("SCHEMA OUTLINE: 62 lines as 5 lines"));
+
+        // Kept lines come first; structure mode needs a long file
+        let keep = KeepPatterns::new(&["svc7$".to_string()]).unwrap();
+        let (result, _) =
+            truncate_content_keeping(&yaml, "compose.yml", 10, "structure", false, &keep);
+        assert!(result.starts_with(
+            "... [23 lines omitted] ...\n\n  - name: svc7\n\n... [38 lines omitted] ...\n\nSchema"
+        ));
+        assert_eq!(line_map(&result, &yaml)[2], Some(24));
+        assert!(
+            !truncate_content(&yaml, "compose.yml", 0, "structure", false)
+                .0
+                .contains("Schema")
+        );
+
+        // Content that doesn't parse is truncated by lines
+        let broken = format!("{{\"a\": [\n{}", "1,\n".repeat(50));
+        let (result, _) = truncate_content(&broken, "x.json", 10, "smart", false);
+        assert!(!result.contains("Schema"));
+    }
+
+    #[test]
+    fn test_summarize_lockfile() {
+        let lock = "version = 3\n\n[[package]]\nname = \"zip\"\nversion = \"2.1.0\"\n\n\
+                    [[package]]\nname = \"adler\"\nversion = \"1.0.2\"\n";
+        let summary = summarize_lockfile(lock, "Cargo.lock", true).unwrap();
+        assert!(summary
+            .starts_with("Dependencies (Cargo, 2 packages):\n  adler 1.0.2\n  zip 2.1.0\n\n===="));
+        assert!(summary.contains("LOCKFILE SUMMARY: 9 lines as 2 dependencies"));
+        assert!(summarize_lockfile(lock, "Cargo.toml", true).is_none());
+
+        // Summarized ahead of truncation, unless the policy keeps them
+        let config = EncoderConfig::default();
+        assert_eq!(
+            config.transforms_for("Cargo.lock", true).names(),
+            vec!["lockfile-summary", "truncate", "escape-control-chars"]
+        );
+        let config = EncoderConfig::builder()
+            .lockfiles(LockfilePolicy::Keep)
+            .build()
+            .unwrap();
+        assert!(!config
+            .transforms_for("Cargo.lock", true)
+            .names()
+            .contains(&"lockfile-summary"));
+    }
+
+    #[test]
+    fn test_truncate_sql() {
+        let mut dump = String::from(
+            "-- Dump of shop\n\
+             CREATE TABLE users (\n  id integer,\n  name text\n);\n\
+             CREATE FUNCTION touch() RETURNS trigger AS $$\nBEGIN\n  RETURN NEW;\nEND;\n$$ LANGUAGE plpgsql;\n\
+             COPY users (id, name) FROM stdin;\n",
+        );
+        for i in 0..30 {
+            dump.push_str(&format!("{}\tuser{}\n", i, i));
+        }
+        dump.push_str("\\.\n\nINSERT INTO orders VALUES (1, 'a;b'), (2, 'c');\n\n");
+        dump.push_str(
+            "INSERT INTO orders VALUES (3, 'd');\nALTER TABLE users ADD PRIMARY KEY (id);\n",
+        );
+
+        let (result, truncated) = truncate_content(&dump, "shop.sql", 0, "structure", true);
+        assert!(truncated);
+        assert!(result.starts_with(
+            "-- Dump of shop\n\
+             CREATE TABLE users (\n  id integer,\n  name text\n);\n\
+             CREATE FUNCTION touch() RETURNS trigger AS $$\n\
+             ... [3 lines omitted: body of touch] ...\n\
+             $$ LANGUAGE plpgsql;\n\
+             ... [36 lines omitted: 3 data statements, 33 rows into users, orders] ...\n\
+             ALTER TABLE users ADD PRIMARY KEY (id);\n"
+        ));
+        assert!(result.contains(
+            "SQL STRUCTURE: kept 8/47 lines; omitted 3 data statements (33 rows) and 1 routine bodies"
+        ));
+        assert_eq!(line_map(&result, &dump)[7], Some(10));
+
+        // Smart mode keeps routine bodies; kept lines split a marker
+        let keep = KeepPatterns::new(&["user7$".to_string()]).unwrap();
+        let (result, _) = truncate_content_keeping(&dump, "shop.sql", 20, "smart", false, &keep);
+        assert!(result.contains("BEGIN\n  RETURN NEW;\nEND;\n"));
+        assert!(result.contains(
+            "$$ LANGUAGE plpgsql;\n\
+             ... [8 lines omitted: 3 data statements, 33 rows into users, orders] ...\n\
+             7\tuser7\n\
+             ... [27 lines omitted] ...\n\
+             ALTER TABLE"
+        ));
+
+        // A schema without data is left to structure mode's signatures
+        let schema = "CREATE TABLE t (\n  id int\n);\nSELECT 1;\n";
+        let (result, _) = truncate_content(schema, "t.sql", 0, "structure", false);
+        assert!(result.starts_with("CREATE TABLE t (\n  id int\n);\n"));
+    }
+
+    #[test]
+    fn test_minimum_context_survives_every_mode() {
+        let mut py = String::from(
+            "\"\"\"Billing jobs.\"\"\"\n\nimport os\nfrom decimal import (\n    Decimal,\n)\n\n",
+        );
+        for i in 0..40 {
+            py.push_str(&format!("def job_{}():\n    return {}\n\n", i, i));
+        }
+        for mode in ["simple", "tail", "smart", "tokens", "structure", "outline"] {
+            let (text, truncated) = truncate_content(&py, "jobs.py", 5, mode, true);
+            assert!(truncated, "{}", mode);
+            for line in ["\"\"\"Billing jobs.\"\"\"", "import os", "    Decimal,"] {
+                assert!(text.contains(line), "{} lost {}:\n{}", mode, line, text);
+            }
+            // Still maps back to the original
+            let map = line_map(&text, &py);
+            assert!(map.contains(&Some(1)) && map.contains(&Some(5)), "{}", mode);
+        }
+
+        // Put back into a gap, splitting its marker
+        let (text, _) = truncate_content(&py, "jobs.py", 3, "tail", false);
+        assert_eq!(
+            text,
+            "\"\"\"Billing jobs.\"\"\"\n\nimport os\nfrom decimal import (\n    Decimal,\n)\n\n\
+             ... [118 lines omitted] ...\n\ndef job_39():\n    return 39\n\n"
+        );
+        assert_eq!(line_map(&text, &py)[9], Some(125));
+
+        // Token gaps cut mid-line: the cut first line is completed, once
+        let module = "\"\"\"Module docs.\"\"\"\nimport os\n\ndef f():\n    return 1\n\n\
+                      print(f() + len(os.sep) + 12345678901234567890)\n";
+        let (text, _) = truncate_content(module, "m.py", 5, "tokens", false);
+        assert!(
+            text.starts_with("\"\"\"Module docs.\"\"\"\nimport os\n\n... [~"),
+            "{}",
+            text
+        );
+        assert_eq!(text.matches("Module docs").count(), 1);
+        assert_eq!(&line_map(&text, module)[..2], [Some(1), Some(2)]);
+        // ...and the marker no longer counts the lines put back
+        let (cut, _) = truncate_tokens_keeping(module, 5, "m.py", false, &KeepPatterns::default());
+        let omitted = |text: &str| text.lines().find_map(token_gap_size).unwrap();
+        assert!(omitted(&text) < omitted(&cut));
+
+        // Already there: untouched
+        assert_eq!(
+            truncate_content(&py, "jobs.py", 10, "simple", false),
+            truncate_simple_with_options(&py, 10, "jobs.py", false)
+        );
+        // Not code
+        let md = format!("# Title\n{}", "text\n".repeat(20));
+        let (text, _) = truncate_content(&md, "notes.md", 3, "tail", false);
+        assert!(!text.contains("# Title"));
+    }
+
+    #[test]
+    fn test_truncate_tail() {
+        let log: String = (1..=10).map(|i| format!("entry {}\n", i)).collect();
+        let (result, truncated) = truncate_tail_with_options(&log, 3, "app.log", false);
+        assert!(truncated);
+        assert_eq!(
+            result,
+            "... [7 lines omitted] ...\n\nentry 8\nentry 9\nentry 10\n"
+        );
+        assert_eq!(
+            line_map(&result, &log),
+            [None, None, Some(8), Some(9), Some(10)]
+        );
+
+        let (result, _) = truncate_tail(&log, 3, "app.log");
+        assert!(result.contains("TRUNCATED to the last 3 of 10 lines (70% reduction)"));
+        assert!(result.contains("--zoom file=app.log"));
+
+        assert_eq!(
+            truncate_content(&log, "app.log", 20, "tail", false),
+            (log.clone(), false)
+        );
+
+        let keep = KeepPatterns::new(&["entry 2$".to_string()]).unwrap();
+        let (result, _) = truncate_content_keeping(&log, "app.log", 2, "tail", false, &keep);
+        assert_eq!(
+            result,
+            "... [1 lines omitted] ...\n\nentry 2\n\n... [6 lines omitted] ...\n\nentry 9\nentry 10\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_keep_patterns() {
+        let keep = KeepPatterns::new(&["TODO|SAFETY".to_string()]).unwrap();
+        let text: String = (1..=30)
+            .map(|i| match i {
+                12 => "// TODO: drop the retry\n".to_string(),
+                20 => "// SAFETY: checked above\n".to_string(),
+                _ => format!("line {}\n", i),
+            })
+            .collect();
+        let gaps = "\n... [6 lines omitted] ...\n\n// TODO: drop the retry\n\
+                    \n... [7 lines omitted] ...\n\n// SAFETY: checked above";
+        assert_eq!(
+            truncate_content_keeping(&text, "notes.txt", 5, "simple", false, &keep),
+            (
+                format!("line 1\nline 2\nline 3\nline 4\nline 5\n{}", gaps),
+                true
+            )
+        );
+        // Without patterns, nothing changes
+        assert_eq!(
+            truncate_content_keeping(
+                &text,
+                "notes.txt",
+                5,
+                "simple",
+                false,
+                &KeepPatterns::default()
+            ),
+            truncate_content(&text, "notes.txt", 5, "simple", false)
+        );
+
+        // Gap truncation and tokens split their gap around kept lines
+        let (smart, _) = truncate_content_keeping(&text, "notes.txt", 10, "smart", false, &keep);
+        assert!(smart.contains("line 4\n\n... [7 lines omitted] ...\n\n// TODO: drop the retry\n"));
+        assert!(smart.contains("// SAFETY: checked above\n\n... [10 lines omitted] ...\n"));
+        let (tokens, _) = truncate_content_keeping(&text, "notes.txt", 20, "tokens", false, &keep);
+        assert!(tokens.contains("\n... [2 lines omitted] ...\n\n// TODO: drop the retry\n"));
+        assert!(
+            tokens.contains("// SAFETY: checked above\n\n... [8 lines omitted] ...\n\nline 29\n")
+        );
+
+        // Structure and outline keep them among the signatures
+        let rust = "fn a() {\n    // TODO: remove\n    body();\n}\n";
+        for mode in ["structure", "outline"] {
+            assert_eq!(
+                truncate_content_keeping(rust, "src/a.rs", 0, mode, false, &keep).0,
+                "fn a() {\n    // TODO: remove\n"
+            );
+        }
+
+        // Comment stripping leaves them in, as a mode or a pre-pass
+        assert_eq!(
+            truncate_content_keeping(rust, "src/a.rs", 0, "strip-comments", false, &keep).0,
+            rust
+        );
+        let config = EncoderConfig::builder()
+            .strip_comments(true)
+            .truncate_keep_patterns(["TODO"])
+            .build()
+            .unwrap();
+        assert_eq!(
+            config
+                .transforms_for("src/a.rs", false)
+                .run("src/a.rs", rust)
+                .text,
+            rust
+        );
+
+        let err = EncoderConfig::builder()
+            .truncate_keep_patterns(["(unclosed"])
+            .build()
+            .unwrap_err();
+        assert!(err.starts_with("Invalid truncate keep pattern '(unclosed'"));
+    }
+
+    #[test]
+    fn test_count_merge_conflicts() {
+        let merged = "fn a() {}\n<<<<<<< HEAD\nlet x = 1;\n=======\nlet x = 2;\n>>>>>>> feature\n\
+                      <<<<<<< HEAD\r\nb\r\n=======\r\nc\r\n>>>>>>> 1a2b3c\r\n";
+        assert_eq!(count_merge_conflicts(merged), 2);
+
+        // Underlines, longer runs and unmatched markers are not conflicts
+        assert_eq!(count_merge_conflicts("Title\n=======\n"), 0);
+        assert_eq!(
+            count_merge_conflicts("<<<<<<<< x\n=======\n>>>>>>>> y\n"),
+            0
+        );
+        assert_eq!(
+            count_merge_conflicts("<<<<<<< HEAD\nours\n>>>>>>> theirs\n"),
+            0
+        );
+    }
+
+    #[test]
+    fn test_escape_control_chars() {
+        let content = "say \u{1b}[1mhi\u{1b}[0m\tnow\r\n\\x41 \u{85}\n";
+        let (escaped, changed) = escape_control_chars(content);
+        assert!(changed);
+        assert_eq!(escaped, "say \\x1b[1mhi\\x1b[0m\tnow\r\n\\x41 \\x85\n");
+        // A literal `\x41` isn't a control character, so it stays
+        assert_eq!(unescape_control_chars(&escaped), content);
+        assert_eq!(
+            escape_control_chars("plain\n"),
+            ("plain\n".to_string(), false)
+        );
 
-        // Smart mode - no truncation needed for short content
-        let (result, _) = truncate_smart(python, 100, "test.py");
-        assert!(result.contains("def foo"));
+        // On by default; the decoder still verifies the file
+        let entries = vec![FileEntry::new("log.txt", content)];
+        let output = serialize_sorted_entries(&EncoderConfig::default(), &entries).unwrap();
+        assert!(output.contains("say \\x1b[1mhi"));
+        assert!(formats::validate_output(output.as_bytes(), OutputFormat::PlusMinus).is_empty());
+        let decoded = decode::decode(&output).unwrap();
+        assert_eq!(decoded[0].status, decode::ChecksumStatus::Verified);
+        assert_eq!(decoded[0].content, content);
+
+        let config = EncoderConfig::builder()
+            .escape_control_chars(false)
+            .build()
+            .unwrap();
+        let output = serialize_sorted_entries(&config, &entries).unwrap();
+        assert!(output.contains("say \u{1b}[1mhi"));
+    }
+
+    #[test]
+    fn test_glossary_appendix() {
+        let entries = vec![
+            FileEntry::new(
+                "src/a.rs",
+                "fn post(ledger: &Ledger) {}
+",
+            ),
+            FileEntry::new(
+                "src/b.rs",
+                "fn close(ledger: &Ledger) {}
+",
+            ),
+        ];
+        let config = EncoderConfig::builder()
+            .glossary(true)
+            .frozen(true)
+            .build()
+            .unwrap();
+        let output = serialize_sorted_entries(&config, &entries).unwrap();
+        let glossary = output.find("========== GLOSSARY ==========\n").unwrap();
+        assert!(output[glossary..].contains("ledger (term, 4 uses in 2 files)\n"));
+        // The hash covers the glossary
+        assert!(output.find("========== CONTEXT sha256:").unwrap() > glossary);
+        assert_eq!(decode::decode(&output).unwrap().len(), 2);
 
-        // Structure mode
-        let (result, _) = truncate_structure(python, "test.py");
-        assert!(result.contains("def foo"));
+        let config = EncoderConfig::default();
+        let output = serialize_sorted_entries(&config, &entries).unwrap();
+        assert!(!output.contains("GLOSSARY"));
     }
 
     #[test]
@@ -2983,6 +8023,7 @@ impl Config {
             mtime: 1702000000,
             ctime: 1701000000,
             size: 12,
+            ..Default::default()
         };
 
         assert_eq!(entry.path, "/path/to/file.rs");
@@ -3105,6 +8146,760 @@ if __name__ == "__main__":
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_walk_directory_respects_gitignore_hierarchy() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("sub/deep")).unwrap();
+        fs::write(root.join(".gitignore"), "*.tmp\n").unwrap();
+        fs::write(root.join("sub/.gitignore"), "secret.txt\n").unwrap();
+        fs::write(root.join("sub/.ignore"), "deep/\n").unwrap();
+        fs::write(root.join("keep.rs"), "fn a() {}").unwrap();
+        fs::write(root.join("scratch.tmp"), "x").unwrap();
+        fs::write(root.join("sub/secret.txt"), "x").unwrap();
+        fs::write(root.join("secret.txt"), "kept: only ignored under sub/").unwrap();
+        fs::write(root.join("sub/deep/gone.rs"), "fn b() {}").unwrap();
+
+        let paths = |respect: bool| {
+            let mut paths: Vec<String> = walk_directory_iter_with_options(
+                root.to_str().unwrap(),
+                vec![],
+                vec![],
+                5_000_000,
+                respect,
+            )
+            .map(|e| e.path.replace('\\', "/"))
+            .filter(|p| !p.ends_with("ignore"))
+            .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(paths(true), vec!["keep.rs", "secret.txt"]);
+        assert_eq!(
+            paths(false),
+            vec![
+                "keep.rs",
+                "scratch.tmp",
+                "secret.txt",
+                "sub/deep/gone.rs",
+                "sub/secret.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_directory_parallel_matches_streaming() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        for dir in ["a", "b/c", "b/d", "node_modules/pkg"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            for i in 0..5 {
+                fs::write(root.join(dir).join(format!("f{}.rs", i)), "fn f() {}").unwrap();
+            }
+        }
+        let root = root.to_str().unwrap();
+
+        let batch: Vec<String> = walk_directory(root, &[], &[], 5_000_000)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        let mut streamed: Vec<String> = walk_directory_iter(root, vec![], vec![], 5_000_000)
+            .map(|e| e.path)
+            .collect();
+        streamed.sort();
+
+        // Batch results come back sorted, whatever the thread timing
+        assert_eq!(batch, streamed);
+        assert_eq!(batch.len(), 15);
+    }
+
+    #[test]
+    fn test_walk_project_prefixes_roots() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let service = temp.path().join("service");
+        let proto = temp.path().join("proto");
+        fs::create_dir_all(service.join("src")).unwrap();
+        fs::create_dir_all(proto.join("gen")).unwrap();
+        fs::write(service.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(proto.join("api.proto"), "syntax = \"proto3\";").unwrap();
+        fs::write(proto.join("gen/api.rs"), "// generated").unwrap();
+        fs::write(
+            proto.join(directory_config::CONFIG_FILE_NAME),
+            r#"{"ignore_patterns": ["gen", ".pm_encoder_config.json"]}"#,
+        )
+        .unwrap();
+
+        let mut config = EncoderConfig::builder()
+            .extra_roots([&proto])
+            .prefix_roots(true)
+            .build()
+            .unwrap();
+        config.discover_directory_configs(&service).unwrap();
+        let mut paths: Vec<String> = walk_project(service.to_str().unwrap(), &config)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path.replace('\\', "/"))
+            .collect();
+        paths.sort();
+        // The extra root's own config applies to its prefix
+        assert_eq!(paths, vec!["proto/api.proto", "service/src/main.rs"]);
+
+        config.prefix_roots = false;
+        assert!(walk_project(service.to_str().unwrap(), &config).is_err());
+    }
+
+    #[test]
+    fn test_walk_project_path_prefixes() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let service = temp.path().join("service");
+        let proto = temp.path().join("proto");
+        fs::create_dir_all(proto.join("gen")).unwrap();
+        fs::create_dir_all(&service).unwrap();
+        fs::write(service.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(proto.join("gen/api.rs"), "// generated").unwrap();
+        fs::write(
+            proto.join(attributes::ATTRIBUTES_FILE_NAME),
+            "gen/** redact\n",
+        )
+        .unwrap();
+
+        let mut config = EncoderConfig::builder()
+            .extra_roots([&proto])
+            .path_prefixes(["acme-service/", "acme-proto"])
+            .build()
+            .unwrap();
+        config.discover_directory_configs(&service).unwrap();
+        let mut entries = walk_project(service.to_str().unwrap(), &config).unwrap();
+        entries.retain(|e| !e.path.ends_with(attributes::ATTRIBUTES_FILE_NAME));
+        let mut paths: Vec<String> = entries.iter().map(|e| e.path.replace('\\', "/")).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["acme-proto/gen/api.rs", "acme-service/main.rs"]);
+        // Attributes follow their root to its prefix
+        assert!(config.attributes_for("acme-proto/gen/api.rs").redacted());
+
+        assert_eq!(normalize_prefix("repo/").unwrap(), "repo");
+        for bad in ["", "/", "/abs", "../up", "a/../b"] {
+            assert!(normalize_prefix(bad).is_err(), "{}", bad);
+        }
+        assert!(EncoderConfig::builder()
+            .path_prefixes(["a", "b"])
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_walk_records_file_metadata() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("run.sh"), "echo hi\n").unwrap();
+        fs::set_permissions(root.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        // Latin-1: one byte on disk, two once decoded
+        fs::write(root.join("notes.txt"), b"caf\xe9\n").unwrap();
+        std::os::unix::fs::symlink("run.sh", root.join("start.sh")).unwrap();
+
+        let entries = walk_project(root.to_str().unwrap(), &EncoderConfig::default()).unwrap();
+        let entry = |path: &str| entries.iter().find(|e| e.path == path).unwrap();
+        assert!(entry("run.sh").executable);
+        assert_eq!(entry("run.sh").language, "bash");
+        assert_eq!(entry("run.sh").symlink_target, None);
+        assert_eq!(entry("start.sh").symlink_target.as_deref(), Some("run.sh"));
+        assert!(entry("start.sh").executable);
+        assert!(!entry("notes.txt").executable);
+        assert_eq!(entry("notes.txt").language, "");
+        assert_eq!(entry("notes.txt").size, 5);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_walk_symlink_policies() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(temp.path().join("shared")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn a() {}").unwrap();
+        fs::write(temp.path().join("shared/util.rs"), "pub fn u() {}").unwrap();
+        symlink("src/lib.rs", root.join("alias.rs")).unwrap();
+        symlink("../shared", root.join("shared")).unwrap();
+        // A cycle: src/up/src/up/... would never end
+        symlink("..", root.join("src/up")).unwrap();
+
+        let walk = |policy: SymlinkPolicy| {
+            let config = EncoderConfig {
+                follow_symlinks: policy,
+                ..Default::default()
+            };
+            let (entries, excluded) =
+                walk_project_with_exclusions(root.to_str().unwrap(), &config).unwrap();
+            let paths: Vec<String> = entries.into_iter().map(|e| e.path).collect();
+            let excluded: Vec<(String, String)> = excluded
+                .into_iter()
+                .map(|e| (e.path, e.reason.to_string()))
+                .collect();
+            (paths, excluded)
+        };
+        let cycle = (
+            "src/up/".to_string(),
+            "symlink loops back to an enclosing directory".to_string(),
+        );
+
+        let (paths, excluded) = walk(SymlinkPolicy::On);
+        assert_eq!(paths, vec!["alias.rs", "shared/util.rs", "src/lib.rs"]);
+        assert_eq!(excluded, vec![cycle.clone()]);
+
+        let (paths, excluded) = walk(SymlinkPolicy::Safe);
+        assert_eq!(paths, vec!["alias.rs", "src/lib.rs"]);
+        assert_eq!(
+            excluded,
+            vec![
+                (
+                    "shared/".to_string(),
+                    "symlink points outside the project root".to_string()
+                ),
+                cycle
+            ]
+        );
+
+        let (paths, excluded) = walk(SymlinkPolicy::Off);
+        assert_eq!(paths, vec!["src/lib.rs"]);
+        let excluded: Vec<&str> = excluded.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(excluded, vec!["alias.rs", "shared", "src/up"]);
+    }
+
+    #[test]
+    fn test_symlink_policy_parse() {
+        assert_eq!(SymlinkPolicy::parse("SAFE"), Ok(SymlinkPolicy::Safe));
+        assert_eq!(SymlinkPolicy::parse("off"), Ok(SymlinkPolicy::Off));
+        assert!(SymlinkPolicy::parse("sometimes").is_err());
+        let policy: SymlinkPolicy = serde_json::from_str("true").unwrap();
+        assert_eq!(policy, SymlinkPolicy::On);
+        let policy: SymlinkPolicy = serde_json::from_str("\"safe\"").unwrap();
+        assert_eq!(policy, SymlinkPolicy::Safe);
+        assert_eq!(serde_json::to_string(&policy).unwrap(), "\"safe\"");
+    }
+
+    #[test]
+    fn test_generated_policies() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("main.py"), "def main():\n    pass\n").unwrap();
+        fs::write(
+            root.join("models.py"),
+            "# @generated by schema-gen\nclass User:\n    name = 'x'\n",
+        )
+        .unwrap();
+        let root = root.to_str().unwrap();
+
+        let config = EncoderConfig {
+            generated: GeneratedPolicy::Skip,
+            ..Default::default()
+        };
+        let (entries, excluded) = walk_project_with_exclusions(root, &config).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(excluded[0].path, "models.py");
+        assert_eq!(excluded[0].reason.kind(), "generated");
+        let stats = TruncationStats::collect(&config, &entries, &excluded);
+        assert_eq!(stats.generated.len(), 1);
+        assert!(stats
+            .report()
+            .contains("models.py  generated ('@generated' marker)"));
+
+        let config = EncoderConfig {
+            generated: GeneratedPolicy::Structure,
+            ..Default::default()
+        };
+        let entries = walk_project(root, &config).unwrap();
+        let stats = TruncationStats::collect(&config, &entries, &[]);
+        assert_eq!((stats.files, stats.truncated), (2, 1));
+        let output = serialize_project_with_config(root, &config).unwrap();
+        assert!(output.contains("class User:"));
+        assert!(!output.contains("name = 'x'"));
+        assert!(output.contains("    pass"));
+    }
+
+    #[test]
+    fn test_walk_agrees_with_project_walk() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["src", "node_modules/pkg", "target/doc", "logs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/main.rs",
+            "src/scratch.rs",
+            ".env.example",
+            "node_modules/pkg/index.js",
+            "target/doc/index.md",
+            "target/doc/app.js",
+            "logs/a.log",
+        ] {
+            fs::write(root.join(file), "x\n").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "scratch.rs\n").unwrap();
+
+        // voyager-ast indexes what project_walk::walk_files yields
+        let ignore = vec!["*.log".to_string(), "!target/doc/*.md".to_string()];
+        let shared: Vec<String> =
+            project_walk::walk_files(root, &project_walk::WalkOptions::default(), &ignore)
+                .unwrap()
+                .iter()
+                .filter_map(|path| project_walk::relative_path(root, path))
+                .collect();
+        let walked: Vec<String> = walk_directory(root.to_str().unwrap(), &ignore, &[], 1 << 20)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+        assert_eq!(walked, shared);
+        assert_eq!(
+            walked,
+            [
+                ".env.example",
+                ".gitignore",
+                "src/main.rs",
+                "target/doc/index.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minified_assets() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let bundle = format!("{}\n", "function a(b){return b+1}var c=a(2);".repeat(100));
+        fs::write(root.join("app.js"), &bundle).unwrap();
+        fs::write(root.join("main.js"), "function main() {\n  return 1;\n}\n").unwrap();
+        let root = root.to_str().unwrap();
+
+        let config = EncoderConfig::default();
+        let entries = walk_project(root, &config).unwrap();
+        let stats = TruncationStats::collect(&config, &entries, &[]);
+        assert_eq!((stats.files, stats.truncated), (2, 1));
+        assert!(stats.report().contains(
+            "Minified: 1 files, reduced to structure or a stub\n  \
+             app.js  3.5KB in 1 lines, avg 3600 chars/line\n"
+        ));
+        let output = serialize_project_with_config(root, &config).unwrap();
+        assert!(output.contains("[minified: 3.5KB in 1 lines, avg 3600 chars/line]\n"));
+        assert!(!output.contains("var c=a(2)"));
+        assert!(output.contains("  return 1;"));
+    }
+
+    #[test]
+    fn test_exclude_content_patterns() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("main.py"), "def main():\n    pass\n").unwrap();
+        fs::write(
+            root.join("fixture.py"),
+            "# PM_ENCODER: SKIP\nTOKEN = 'hunter2'\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(directory_config::CONFIG_FILE_NAME),
+            r#"{"exclude_content_patterns": ["PM_ENCODER: SKIP"]}"#,
+        )
+        .unwrap();
+
+        let config =
+            EncoderConfig::from_file(&root.join(directory_config::CONFIG_FILE_NAME)).unwrap();
+        let root = root.to_str().unwrap();
+        let (entries, excluded) = walk_project_with_exclusions(root, &config).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert!(!paths.contains(&"fixture.py"));
+        assert!(paths.contains(&"main.py"));
+        let fixture = excluded.iter().find(|f| f.path == "fixture.py").unwrap();
+        assert_eq!(
+            fixture.reason.to_string(),
+            "content matches 'PM_ENCODER: SKIP'"
+        );
+
+        let config = EncoderConfig {
+            exclude_content_patterns: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(walk_project(root, &config).is_err());
+    }
+
+    #[test]
+    fn test_pattern_syntax_v2_walk() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["src", "lib/src", "mysrc", "gen", "tools"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/main.rs",
+            "lib/src/util.rs",
+            "mysrc/x.rs",
+            "gen/out.rs",
+            "tools/gen",
+        ] {
+            fs::write(root.join(file), "x\n").unwrap();
+        }
+        let root = root.to_str().unwrap();
+        let paths = |config: &EncoderConfig| -> Vec<String> {
+            walk_project(root, config)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.path)
+                .collect()
+        };
+
+        // An anchored include takes src/ at the root only
+        let config = EncoderConfig {
+            ignore_patterns: vec![],
+            include_patterns: vec!["/src/**".to_string()],
+            pattern_syntax: PatternSyntax::V2,
+            ..Default::default()
+        };
+        assert_eq!(paths(&config), vec!["src/main.rs"]);
+
+        // A directory-only ignore leaves a file of the same name alone
+        let config = EncoderConfig {
+            ignore_patterns: vec!["gen/".to_string()],
+            pattern_syntax: PatternSyntax::V2,
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&config),
+            vec!["lib/src/util.rs", "mysrc/x.rs", "src/main.rs", "tools/gen"]
+        );
+
+        // v1 stays as it was
+        let config = EncoderConfig {
+            ignore_patterns: vec!["gen".to_string()],
+            ..Default::default()
+        };
+        assert!(!paths(&config).contains(&"tools/gen".to_string()));
+    }
+
+    #[test]
+    fn test_case_insensitive_patterns() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("README.MD"), "# Hi\n").unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        let root = root.to_str().unwrap();
+        let paths = |case_insensitive_patterns| -> Vec<String> {
+            let config = EncoderConfig {
+                ignore_patterns: vec![],
+                include_patterns: vec!["*.md".to_string()],
+                case_insensitive_patterns,
+                ..Default::default()
+            };
+            walk_project(root, &config)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.path)
+                .collect()
+        };
+        assert_eq!(paths(true), vec!["README.MD"]);
+        assert!(paths(false).is_empty());
+    }
+
+    #[test]
+    fn test_binary_policies() {
+        use std::fs;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("main.py"), "print('logo.png')\n").unwrap();
+        fs::write(root.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        let root = root.to_str().unwrap();
+
+        let (entries, excluded) =
+            walk_project_with_exclusions(root, &EncoderConfig::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(excluded[0].reason, ExclusionReason::Binary);
+
+        let config = EncoderConfig {
+            binary: BinaryPolicy::Stub,
+            ..Default::default()
+        };
+        let (entries, excluded) = walk_project_with_exclusions(root, &config).unwrap();
+        assert!(excluded.is_empty());
+        let logo = entries.iter().find(|e| e.path == "logo.png").unwrap();
+        assert_eq!(logo.content, "[binary: 10B, image/png]\n");
+        assert_eq!(logo.size, 10);
+
+        let config = EncoderConfig {
+            binary: BinaryPolicy::Base64,
+            ..Default::default()
+        };
+        let output = serialize_project_with_config(root, &config).unwrap();
+        assert!(output.contains("[binary: 10B, image/png, base64]\niVBORw0KGgoAAA==\n"));
+    }
+
+    #[test]
+    fn test_dedupe_entries() {
+        let body = "pub const TABLE: [u8; 4] = [1, 2, 3, 4];\n";
+        let mut entries = vec![
+            FileEntry::new("a/table.rs", body),
+            FileEntry::new("b/table.rs", body),
+            FileEntry::new("c.txt", "x\n"),
+            FileEntry::new("d.txt", "x\n"),
+        ];
+        assert_eq!(dedupe_entries(&mut entries), 1);
+        assert_eq!(entries[0].content, body);
+        assert_eq!(entries[1].content, "duplicate of a/table.rs\n");
+        assert_eq!(entries[1].md5, entries[0].md5);
+        // A stub wouldn't be shorter
+        assert_eq!(entries[3].content, "x\n");
+    }
+
+    #[test]
+    fn test_walk_project_limits() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::write(root.join("top.rs"), "1").unwrap();
+        fs::write(root.join("a/x.rs"), "22").unwrap();
+        fs::write(root.join("a/b/y.rs"), "333").unwrap();
+        fs::write(root.join("a/b/c/z.rs"), "4444").unwrap();
+        let walk = |config: EncoderConfig| {
+            let (entries, excluded) =
+                walk_project_with_exclusions(root.to_str().unwrap(), &config).unwrap();
+            let paths: Vec<String> = entries
+                .into_iter()
+                .map(|e| e.path.replace('\\', "/"))
+                .collect();
+            let excluded: Vec<(String, ExclusionReason)> = excluded
+                .into_iter()
+                .map(|e| (e.path.replace('\\', "/"), e.reason))
+                .collect();
+            (paths, excluded)
+        };
+
+        let (paths, excluded) = walk(EncoderConfig {
+            max_depth: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(paths, vec!["a/x.rs", "top.rs"]);
+        assert_eq!(
+            excluded,
+            vec![("a/b/".to_string(), ExclusionReason::TooDeep { limit: 1 })]
+        );
+
+        let (paths, excluded) = walk(EncoderConfig {
+            max_total_files: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(paths, vec!["a/b/c/z.rs", "a/b/y.rs", "a/x.rs"]);
+        assert_eq!(
+            excluded,
+            vec![(
+                "top.rs".to_string(),
+                ExclusionReason::FileLimit { limit: 3 }
+            )]
+        );
+
+        // Once a file doesn't fit, the smaller ones after it don't either
+        let (paths, excluded) = walk(EncoderConfig {
+            max_total_bytes: Some(6),
+            ..Default::default()
+        });
+        assert_eq!(paths, vec!["a/b/c/z.rs"]);
+        assert_eq!(excluded.len(), 3);
+        assert!(excluded
+            .iter()
+            .all(|(_, reason)| *reason == ExclusionReason::ByteLimit { limit: 6 }));
+    }
+
+    #[test]
+    fn test_walk_project_with_exclusions() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("node_modules/x")).unwrap();
+        fs::create_dir_all(root.join("secret")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn a() {}").unwrap();
+        fs::write(root.join("src/blob.bin"), b"\0\x01").unwrap();
+        fs::write(root.join("src/big.rs"), "x".repeat(200)).unwrap();
+        fs::write(root.join("debug.log"), "log").unwrap();
+        fs::write(root.join("node_modules/x/index.js"), "x").unwrap();
+        fs::write(root.join("secret/key.txt"), "k").unwrap();
+        fs::write(root.join(".gitignore"), "secret\n").unwrap();
+
+        let config = EncoderConfig {
+            ignore_patterns: vec!["*.log".to_string(), ".gitignore".to_string()],
+            max_file_size: 100,
+            ..Default::default()
+        };
+        let (entries, excluded) =
+            walk_project_with_exclusions(root.to_str().unwrap(), &config).unwrap();
+        assert_eq!(entries.len(), 1);
+        let excluded: Vec<(String, ExclusionReason)> = excluded
+            .into_iter()
+            .map(|e| (e.path.replace('\\', "/"), e.reason))
+            .collect();
+        assert_eq!(
+            excluded,
+            vec![
+                (
+                    ".gitignore".to_string(),
+                    ExclusionReason::IgnorePattern(".gitignore".to_string())
+                ),
+                (
+                    "debug.log".to_string(),
+                    ExclusionReason::IgnorePattern("*.log".to_string())
+                ),
+                ("node_modules/".to_string(), ExclusionReason::Hygiene),
+                ("secret/key.txt".to_string(), ExclusionReason::Gitignored),
+                (
+                    "src/big.rs".to_string(),
+                    ExclusionReason::TooLarge {
+                        size: 200,
+                        limit: 100
+                    }
+                ),
+                ("src/blob.bin".to_string(), ExclusionReason::Binary),
+            ]
+        );
+
+        // Include patterns and nothing to ignore: the rest isn't included
+        let config = EncoderConfig {
+            ignore_patterns: vec![],
+            include_patterns: vec!["src/**".to_string()],
+            respect_gitignore: false,
+            ..Default::default()
+        };
+        let (_, excluded) = walk_project_with_exclusions(root.to_str().unwrap(), &config).unwrap();
+        assert!(excluded
+            .iter()
+            .any(|e| e.path == "debug.log" && e.reason == ExclusionReason::NotIncluded));
+        assert!(!excluded
+            .iter()
+            .any(|e| e.reason == ExclusionReason::Gitignored));
+    }
+
+    #[test]
+    fn test_walk_project_archive_root() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in [
+            ("repo-main/src/lib.rs", "pub fn a() {}"),
+            ("repo-main/node_modules/x/index.js", "x"),
+            ("repo-main/target/debug/out.rs", "x"),
+            ("repo-main/logo.png", "\0PNG"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        let archive = temp.path().join("repo-main.tar");
+        fs::write(&archive, builder.into_inner().unwrap()).unwrap();
+
+        let config = EncoderConfig {
+            ignore_patterns: vec!["target".to_string()],
+            ..Default::default()
+        };
+        let entries = walk_project(archive.to_str().unwrap(), &config).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/lib.rs"]);
+        assert_eq!(entries[0].content, "pub fn a() {}");
+
+        // The archive's stem names its prefix
+        assert_eq!(root_prefix(&archive), "repo-main");
+    }
+
+    #[test]
+    fn test_walk_project_git_only() {
+        use std::fs;
+        use std::process::Command;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/tracked.rs"), "fn a() {}").unwrap();
+        fs::write(root.join("untracked.rs"), "fn b() {}").unwrap();
+
+        let config = EncoderConfig {
+            git_only: true,
+            ..Default::default()
+        };
+        let paths = || {
+            let mut paths: Vec<String> = walk_project(root.to_str().unwrap(), &config)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.path.replace('\\', "/"))
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        // Outside a repository the normal walk is used
+        assert_eq!(paths(), vec!["src/tracked.rs", "untracked.rs"]);
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(args)
+                .status()
+                .is_ok_and(|s| s.success())
+        };
+        if !git(&["init", "-q"]) {
+            // git not installed
+            return;
+        }
+        assert!(git(&["add", "src/tracked.rs"]));
+        assert_eq!(paths(), vec!["src/tracked.rs"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_project_reports_unreadable_files() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("ok.rs"), "fn a() {}").unwrap();
+        fs::write(root.join("locked.rs"), "fn b() {}").unwrap();
+        fs::write(root.join("image.bin"), [0u8, 159, 146, 150]).unwrap();
+        fs::set_permissions(root.join("locked.rs"), fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read(root.join("locked.rs")).is_ok() {
+            // Running as root: permissions don't apply
+            return;
+        }
+
+        let (entries, skipped) =
+            walk_project_with_skipped(root.to_str().unwrap(), &EncoderConfig::default()).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["ok.rs"]);
+        // The binary file is a deliberate omission, not a skip
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, "locked.rs");
+    }
+
     #[test]
     fn test_walk_directory_nonexistent() {
         // Test walk_directory with non-existent directory
@@ -3128,6 +8923,34 @@ if __name__ == "__main__":
         assert!(content.starts_with("Hello"));
     }
 
+    #[test]
+    fn test_read_file_content_utf16() {
+        let utf16 = |text: &str, big_endian: bool, bom: bool| -> Vec<u8> {
+            let mut bytes = Vec::new();
+            for unit in (bom.then_some('\u{feff}').into_iter().chain(text.chars()))
+                .collect::<String>()
+                .encode_utf16()
+            {
+                bytes.extend(if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                });
+            }
+            bytes
+        };
+        let source = "// Größe\r\nclass Program {}\r\n";
+        let expected = Some("// Größe\nclass Program {}\n".to_string());
+        assert_eq!(read_file_content(&utf16(source, false, true)), expected);
+        assert_eq!(read_file_content(&utf16(source, true, true)), expected);
+        assert_eq!(read_file_content(&utf16(source, false, false)), expected);
+        assert_eq!(read_file_content(&utf16(source, true, false)), expected);
+
+        // Null-padded binary is not UTF-16 text
+        assert_eq!(read_file_content(&[0xFF, 0xFE, 0, 0, 0, 0]), None);
+        assert_eq!(read_file_content(&[1, 0, 0, 0, 2, 0]), None);
+    }
+
     #[test]
     fn test_read_file_content_crlf_normalization() {
         // Test CRLF to LF normalization
@@ -3270,6 +9093,105 @@ if __name__ == "__main__":
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_sort_by_size_and_ext() {
+        let mut entries = vec![
+            FileEntry::new("b.rs", "fn b() {}\n"),
+            FileEntry::new("big.md", "# Notes\n".repeat(100)),
+            FileEntry::new("Makefile", "all:\n"),
+            FileEntry::new("a.RS", "fn a() {}\n"),
+        ];
+        let paths = |entries: &[FileEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.path.clone()).collect()
+        };
+
+        let mut config = EncoderConfig {
+            sort_by: "size".to_string(),
+            sort_order: "desc".to_string(),
+            ..Default::default()
+        };
+        sort_entries(&mut entries, &config);
+        // Equal sizes fall back to path order
+        assert_eq!(paths(&entries), ["big.md", "a.RS", "b.rs", "Makefile"]);
+
+        config.sort_by = "ext".to_string();
+        config.sort_order = "asc".to_string();
+        sort_entries(&mut entries, &config);
+        assert_eq!(paths(&entries), ["Makefile", "big.md", "a.RS", "b.rs"]);
+
+        config.sort_order = "desc".to_string();
+        sort_entries(&mut entries, &config);
+        assert_eq!(paths(&entries), ["a.RS", "b.rs", "big.md", "Makefile"]);
+    }
+
+    #[test]
+    fn test_sort_by_utility() {
+        let mut entries = vec![
+            FileEntry::new("a.rs", "fn a() {}\n"),
+            FileEntry::new("b.rs", "fn b() {}\n"),
+            FileEntry::new("c.rs", "fn c() {}\n"),
+            FileEntry::new("d.rs", "fn d() {}\n"),
+        ];
+        let paths = |entries: &[FileEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.path.clone()).collect()
+        };
+
+        let mut store = core::ContextStore::new();
+        store.report_utility("c.rs", 1.0, 1.0);
+        store.report_utility("a.rs", 0.1, 1.0);
+        let mut config = EncoderConfig {
+            sort_by: "utility".to_string(),
+            utility_store: Some(store),
+            ..Default::default()
+        };
+        sort_entries(&mut entries, &config);
+        // Unscored files sit at 0.5, in path order
+        assert_eq!(paths(&entries), ["c.rs", "b.rs", "d.rs", "a.rs"]);
+
+        config.sort_order = "desc".to_string();
+        sort_entries(&mut entries, &config);
+        assert_eq!(paths(&entries), ["a.rs", "b.rs", "d.rs", "c.rs"]);
+
+        // No store: path order
+        config.utility_store = None;
+        config.sort_order = "asc".to_string();
+        sort_entries(&mut entries, &config);
+        assert_eq!(paths(&entries), ["a.rs", "b.rs", "c.rs", "d.rs"]);
+    }
+
+    #[test]
+    fn test_walk_roots_by_priority() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            std::fs::write(temp_dir.path().join(name), "fn f() {}\n").unwrap();
+        }
+        std::fs::write(temp_dir.path().join("skip.log"), "noise\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let mut store = core::ContextStore::new();
+        store.report_utility("c.rs", 1.0, 1.0);
+        store.report_utility("a.rs", 0.0, 1.0);
+        let config = EncoderConfig {
+            ignore_patterns: vec!["*.log".to_string()],
+            utility_store: Some(store),
+            ..Default::default()
+        };
+        let pruned = Arc::new(ExclusionLedger::new());
+        let paths: Vec<String> =
+            walk_roots_by_priority(config.roots(root).unwrap(), &config, pruned)
+                .unwrap()
+                .map(|result| result.unwrap().path)
+                .collect();
+        assert_eq!(paths, ["c.rs", "b.rs", "a.rs"]);
+
+        let config = EncoderConfig {
+            active_lens: Some("no-such-lens".to_string()),
+            ..Default::default()
+        };
+        let pruned = Arc::new(ExclusionLedger::new());
+        assert!(walk_roots_by_priority(config.roots(root).unwrap(), &config, pruned).is_err());
+    }
+
     #[test]
     fn test_serialize_with_unknown_sort() {
         // Test fallback to name sorting for unknown sort_by
@@ -3345,6 +9267,7 @@ if __name__ == "__main__":
             md5: "abc123".to_string(),
             mtime: 0,
             ctime: 0,
+            ..Default::default()
         };
 
         // Simple truncation
@@ -3613,6 +9536,7 @@ pub fn main() {
             mtime: 0,
             ctime: 0,
             size: 12,
+            ..Default::default()
         };
 
         let output = serialize_file_with_truncation(&entry, 0, "simple");
@@ -3805,6 +9729,79 @@ def bar():
         assert!(config.truncate_stats);
     }
 
+    #[test]
+    fn test_encoder_config_serde_round_trip() {
+        let config = EncoderConfig::builder()
+            .truncate(50, "structure")
+            .output_format(OutputFormat::ClaudeXml)
+            .skeleton_mode(SkeletonMode::Disabled)
+            .only_paths(["src/lib.rs"])
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""output_format":"claude-xml""#));
+
+        let back: EncoderConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+        // Missing fields take their defaults
+        let partial: EncoderConfig = serde_json::from_str(r#"{"truncate_lines": 9}"#).unwrap();
+        assert_eq!(partial.truncate_lines, 9);
+        assert_eq!(
+            partial.ignore_patterns,
+            EncoderConfig::default().ignore_patterns
+        );
+        assert!(serde_json::from_str::<EncoderConfig>(r#"{"output_format": "pdf"}"#).is_err());
+    }
+
+    #[test]
+    fn test_custom_transform_reaches_every_format() {
+        use transform::{Content, ContentTransform, SourceFile, Stage};
+
+        struct MaskDigits;
+        impl ContentTransform for MaskDigits {
+            fn name(&self) -> &str {
+                "mask-digits"
+            }
+            fn stage(&self) -> Stage {
+                Stage::Redact
+            }
+            fn apply(&self, _file: &SourceFile<'_>, content: &mut Content) {
+                content.text = content.text.replace(|c: char| c.is_ascii_digit(), "#");
+            }
+        }
+
+        let entry = FileEntry {
+            path: "key.txt".to_string(),
+            content: "token=1234\n".to_string(),
+            md5: calculate_md5("token=1234\n"),
+            mtime: 0,
+            ctime: 0,
+            size: 11,
+            ..Default::default()
+        };
+        for format in [OutputFormat::PlusMinus, OutputFormat::ClaudeXml] {
+            let config = EncoderConfig::builder()
+                .transform(MaskDigits)
+                .output_format(format)
+                .line_numbers(true)
+                .build()
+                .unwrap();
+            assert_eq!(
+                config.transforms_for("key.txt", true).names(),
+                vec![
+                    "mask-digits",
+                    "truncate",
+                    "line-numbers",
+                    "escape-control-chars"
+                ]
+            );
+            let output = serialize_sorted_entries(&config, std::slice::from_ref(&entry)).unwrap();
+            assert!(output.contains("1 | token=####"), "{}", output);
+            assert!(!output.contains("1234"));
+        }
+    }
+
     #[test]
     fn test_encoder_config_truncate_defaults() {
         // Test default values for truncation control fields
@@ -3902,6 +9899,77 @@ def bar():
         );
     }
 
+    #[test]
+    fn test_number_lines_plain() {
+        let content = "fn a() {}\n\nfn b() {}\n";
+        assert_eq!(
+            number_lines(content, content),
+            "1 | fn a() {}\n2 | \n3 | fn b() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_number_lines_keeps_original_numbers_across_gaps() {
+        let content: String = (1..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (truncated, _) = truncate_smart_with_options(&content, 20, "data.ai", true);
+        let numbered = number_lines(&truncated, &content);
+
+        assert!(numbered.starts_with("  1 | line 1\n"));
+        assert!(numbered.contains("100 | line 100\n"));
+        // Markers are not original lines and stay unnumbered
+        assert!(numbered.contains("    | ... ["));
+        assert!(numbered.contains(&format!("    | {}\n", "=".repeat(70))));
+    }
+
+    #[test]
+    fn test_source_map_ranges_across_gap() {
+        let content: String = (1..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (truncated, _) = truncate_smart_with_options(&content, 20, "data.ai", true);
+        let map = source_map(&truncated, &content);
+
+        assert!(map.len() >= 2, "gap should split the map: {:?}", map);
+        assert_eq!(map[0].output, 1);
+        assert_eq!(map[0].original, 1);
+        let last = map.last().unwrap();
+        assert_eq!(last.original + last.lines - 1, 100);
+        // Every mapped output line points at the same text in the original
+        let out: Vec<&str> = truncated.split('\n').collect();
+        for r in &map {
+            for k in 0..r.lines {
+                assert_eq!(out[r.output - 1 + k], format!("line {}", r.original + k));
+            }
+        }
+    }
+
+    #[test]
+    fn test_serialize_with_line_numbers_keeps_checksum() {
+        let entry = FileEntry {
+            path: "a.py".to_string(),
+            content: "x = 1\ny = 2\n".to_string(),
+            md5: calculate_md5("x = 1\ny = 2\n"),
+            mtime: 0,
+            ctime: 0,
+            size: 12,
+            ..Default::default()
+        };
+        let output = serialize_file_with_options(
+            &entry,
+            0,
+            "simple",
+            OutputFormat::PlusMinus,
+            MetadataMode::None,
+            true,
+        );
+        assert!(output.contains("1 | x = 1\n2 | y = 2\n"));
+        assert!(output.contains(&entry.md5));
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // TDD TESTS FOR PYTHON PARITY (Gap #2: Structure mode keeps ALL imports)
     // ═══════════════════════════════════════════════════════════════════════════
@@ -4192,6 +10260,10 @@ This is the last section.
             OutputFormat::parse("claudexml"),
             Ok(OutputFormat::ClaudeXml)
         ));
+        assert!(matches!(
+            OutputFormat::parse("gemini"),
+            Ok(OutputFormat::Gemini)
+        ));
     }
 
     #[test]
@@ -4314,6 +10386,7 @@ This is the last section.
             mtime: 12345,
             ctime: 12340,
             size: 14,
+            ..Default::default()
         };
         assert_eq!(entry.path, "test.py");
         assert_eq!(entry.content, "print('hello')");
@@ -4354,6 +10427,57 @@ This is the last section.
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_frozen_output_is_canonical_and_hashed() {
+        let mut entries = vec![
+            FileEntry {
+                path: "src\\b.py".to_string(),
+                content: "b = 2\n".to_string(),
+                md5: calculate_md5("b = 2\n"),
+                mtime: 1_700_000_000,
+                ctime: 1_700_000_000,
+                size: 6,
+                ..Default::default()
+            },
+            FileEntry {
+                path: "src/a.py".to_string(),
+                content: "a = 1\n".to_string(),
+                md5: calculate_md5("a = 1\n"),
+                mtime: 1_700_000_500,
+                ctime: 1_700_000_500,
+                size: 6,
+                ..Default::default()
+            },
+        ];
+        canonicalize_entries(&mut entries);
+        assert_eq!(entries[0].path, "src/b.py");
+        assert!(entries.iter().all(|e| e.mtime == 0 && e.ctime == 0));
+
+        // Cleared timestamps sort by path
+        let config = EncoderConfig {
+            frozen: true,
+            sort_by: "mtime".to_string(),
+            metadata_mode: MetadataMode::SizeOnly,
+            ..Default::default()
+        };
+        sort_entries(&mut entries, &config);
+        assert_eq!(entries[0].path, "src/a.py");
+
+        let output = serialize_sorted_entries(&config, &entries).unwrap();
+        let (body, trailer) = output.rsplit_once("========== CONTEXT sha256:").unwrap();
+        assert_eq!(trailer, format!("{} ==========\n", context_hash(body)));
+
+        let xml = EncoderConfig {
+            output_format: OutputFormat::ClaudeXml,
+            ..config
+        };
+        let output = serialize_sorted_entries(&xml, &entries).unwrap();
+        assert!(output.ends_with(&format!(
+            "<!-- context sha256:{} -->\n",
+            context_hash(output.rsplit_once("<!-- context").unwrap().0)
+        )));
+    }
+
     #[test]
     fn test_serialize_with_token_budget() {
         use std::fs;
@@ -4413,6 +10537,7 @@ This is the last section.
             original_tokens: None,
             compression_level: CompressionLevel::Full,
             utility: None,
+            ..Default::default()
         };
 
         assert_eq!(pf.path, "test.py");
@@ -4426,6 +10551,24 @@ This is the last section.
         let config = Config {
             ignore_patterns: vec!["*.log".to_string()],
             include_patterns: vec!["*.py".to_string()],
+            templates: None,
+            truncate_lines: None,
+            truncate_mode: None,
+            strip_comments: None,
+            truncate_keep_patterns: vec![],
+            escape_control_chars: None,
+            header_hints: None,
+            max_depth: None,
+            max_total_files: None,
+            max_total_bytes: None,
+            temporal: None,
+            declaration_kinds: vec![],
+            generated: None,
+            binary: None,
+            lockfiles: None,
+            exclude_content_patterns: vec![],
+            pattern_syntax: None,
+            case_insensitive_patterns: None,
         };
 
         assert_eq!(config.ignore_patterns.len(), 1);
@@ -4779,6 +10922,100 @@ class MyClass:
         }
     }
 
+    #[test]
+    fn test_serialize_processed_files_gemini_line_counts() {
+        let file = ProcessedFile {
+            path: "test.py".to_string(),
+            content: "print('hi')\n".to_string(),
+            md5: "abc".to_string(),
+            was_truncated: false,
+            original_lines: 1,
+            mtime: 0,
+            ctime: 0,
+        };
+        let engine = ContextEngine::new(EncoderConfig {
+            output_format: OutputFormat::Gemini,
+            ..Default::default()
+        });
+
+        // The preamble and the entry agree on the count
+        let output = engine.serialize_processed_files(&[file]);
+        assert!(output.contains("- test.py (python, 1 lines)\n"));
+        assert!(output.contains("md5=\"abc\" lines=\"1\">"));
+    }
+
+    #[test]
+    fn test_header_hints() {
+        let file = ProcessedFile {
+            path: "test.py".to_string(),
+            content: "print('hi')\n".to_string(),
+            md5: "abc".to_string(),
+            was_truncated: false,
+            original_lines: 1,
+            mtime: 0,
+            ctime: 0,
+        };
+
+        // Off by default: headers stay byte-identical to the Python encoder
+        let engine = ContextEngine::new(EncoderConfig::default());
+        assert!(engine
+            .serialize_processed_file(&file)
+            .starts_with("++++++++++ test.py ++++++++++\n"));
+
+        let config = EncoderConfig {
+            header_hints: true,
+            ..Default::default()
+        };
+        let mut store = core::ContextStore::new();
+        store.report_utility("test.py", 0.9, 1.0);
+        let engine = ContextEngine::new(config.clone()).with_store(store);
+        let tokens = TokenEstimator::estimate_tokens(&file.content);
+        assert!(engine.serialize_processed_file(&file).starts_with(&format!(
+            "++++++++++ test.py [lang:python tokens:{} utility:0.90] ++++++++++\n",
+            tokens
+        )));
+
+        let xml = ContextEngine::new(EncoderConfig {
+            output_format: OutputFormat::Xml,
+            ..config
+        })
+        .serialize_processed_file(&file);
+        assert!(xml.starts_with(&format!(
+            "<file path=\"test.py\" md5=\"abc\" language=\"python\" tokens=\"{}\">",
+            tokens
+        )));
+    }
+
+    #[test]
+    fn test_context_engine_matches_shared_formatters() {
+        let files = vec![
+            ("a.py".to_string(), "print('a')\n".to_string()),
+            ("b.rs".to_string(), "fn b() {}\n".to_string()),
+        ];
+        let entries: Vec<FileEntry> = files
+            .iter()
+            .map(|(path, content)| FileEntry::new(path, content))
+            .collect();
+        for format in [
+            OutputFormat::PlusMinus,
+            OutputFormat::Xml,
+            OutputFormat::Markdown,
+            OutputFormat::Gemini,
+        ] {
+            let config = EncoderConfig {
+                output_format: format,
+                metadata_mode: MetadataMode::None,
+                ..Default::default()
+            };
+            assert_eq!(
+                ContextEngine::new(config.clone()).generate_context(&files),
+                serialize_sorted_entries(&config, &entries).unwrap(),
+                "{:?}",
+                format
+            );
+        }
+    }
+
     #[test]
     fn test_truncate_simple_no_summary() {
         let (result, truncated) = truncate_simple_with_options(
@@ -4926,6 +11163,166 @@ class MyClass:
         ));
     }
 
+    #[test]
+    fn test_matches_patterns_negation() {
+        let patterns = vec!["target".to_string(), "!target/doc/*.md".to_string()];
+        assert!(matches_patterns("target/debug/app", &patterns));
+        assert!(!matches_patterns("target/doc/index.md", &patterns));
+        assert!(matches_patterns("target/doc/index.html", &patterns));
+
+        // Last match wins: a later positive pattern re-ignores
+        let patterns = vec![
+            "*.log".to_string(),
+            "!keep.log".to_string(),
+            "keep.log".to_string(),
+        ];
+        assert!(matches_patterns("keep.log", &patterns));
+
+        // A negation alone never matches
+        assert!(!matches_patterns("a.md", &vec!["!*.md".to_string()]));
+    }
+
+    #[test]
+    fn test_should_include_file_negated_include() {
+        let include = vec!["src/**".to_string(), "!src/gen/**".to_string()];
+        assert!(should_include_file("src/lib.rs", &[], &include));
+        assert!(!should_include_file("src/gen/api.rs", &[], &include));
+    }
+
+    #[test]
+    fn test_negation_reaches_into() {
+        let patterns = vec!["target".to_string(), "!target/doc/*.md".to_string()];
+        assert!(project_walk::negation_reaches_into("target", &patterns));
+        assert!(project_walk::negation_reaches_into("target/doc", &patterns));
+        assert!(!project_walk::negation_reaches_into(
+            "target/debug",
+            &patterns
+        ));
+        assert!(!project_walk::negation_reaches_into(
+            "target",
+            &vec!["!*.md".to_string()]
+        ));
+    }
+
+    #[test]
+    fn test_walk_directory_negation_reincludes_under_ignored_dir() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("target/doc")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("target/doc/guide.md"), "# Guide").unwrap();
+        fs::write(root.join("target/doc/index.html"), "<html>").unwrap();
+        fs::write(root.join("target/debug/app.d"), "deps").unwrap();
+
+        let mut paths: Vec<String> = walk_directory(
+            root.to_str().unwrap(),
+            &["target".to_string(), "!target/doc/*.md".to_string()],
+            &[],
+            5_000_000,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|e| e.path.replace('\\', "/"))
+        .collect();
+        paths.sort();
+
+        assert_eq!(paths, vec!["main.rs", "target/doc/guide.md"]);
+    }
+
+    #[test]
+    fn test_nested_directory_configs() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("pkg/fixtures")).unwrap();
+        fs::write(root.join("top.txt"), "1\n2\n3\n4\n").unwrap();
+        fs::write(root.join("pkg/lib.txt"), "1\n2\n3\n4\n").unwrap();
+        fs::write(root.join("pkg/fixtures/big.json"), "{}").unwrap();
+        fs::write(
+            root.join("pkg/.pm_encoder_config.json"),
+            r#"{"ignore_patterns": ["fixtures", ".pm_encoder_config.json"], "truncate_lines": 2}"#,
+        )
+        .unwrap();
+
+        let mut config = EncoderConfig {
+            metadata_mode: MetadataMode::None,
+            ..Default::default()
+        };
+        config.discover_directory_configs(root).unwrap();
+        let output = serialize_project_with_config(root.to_str().unwrap(), &config).unwrap();
+
+        assert!(!output.contains("big.json"));
+        assert!(!output.contains("pkg/.pm_encoder_config.json"));
+        assert!(output.contains("top.txt ++++++++++\n1\n2\n3\n4\n"));
+        assert!(output.contains("pkg/lib.txt [TRUNCATED: 5 lines] ++++++++++\n1\n2\n"));
+    }
+
+    #[test]
+    fn test_pmattributes() {
+        use std::fs;
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("api")).unwrap();
+        fs::write(root.join("notes.txt"), "1\n2\n3\n4\n").unwrap();
+        fs::write(root.join("keep.txt"), "1\n2\n3\n4\n").unwrap();
+        fs::write(root.join("secrets.txt"), "token=abc\nuser=me\n").unwrap();
+        fs::write(root.join("api/schema.py"), "class User:\n    name = 'x'\n").unwrap();
+        fs::write(
+            root.join("api/client.py"),
+            "# @generated by schema-gen\ndef get():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(".pmattributes"),
+            "keep.txt -truncate pin\nsecrets.txt redact\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("api/.pmattributes"),
+            "schema.py linguist-generated\nclient.py -linguist-generated\n",
+        )
+        .unwrap();
+
+        let mut config = EncoderConfig {
+            metadata_mode: MetadataMode::None,
+            truncate_lines: 2,
+            generated: GeneratedPolicy::Skip,
+            ignore_patterns: vec![".pmattributes".to_string()],
+            ..Default::default()
+        };
+        config.discover_directory_configs(root).unwrap();
+        let (entries, excluded) =
+            walk_project_with_exclusions(root.to_str().unwrap(), &config).unwrap();
+        let generated: Vec<&str> = excluded
+            .iter()
+            .filter(|e| e.reason.kind() == "generated")
+            .map(|e| e.path.as_str())
+            .collect();
+        assert_eq!(generated, vec!["api/schema.py"]);
+        assert!(entries.iter().any(|e| e.path == "api/client.py"));
+
+        config.generated = GeneratedPolicy::Keep;
+        let output = serialize_project_with_config(root.to_str().unwrap(), &config).unwrap();
+        assert!(output.contains("keep.txt ++++++++++\n1\n2\n3\n4\n"));
+        assert!(output.contains("notes.txt [TRUNCATED: 5 lines]"));
+        assert!(output.contains("[redacted by .pmattributes: 2 lines]"));
+        assert!(!output.contains("token=abc"));
+
+        let files = vec![
+            ("keep.txt".to_string(), "x".repeat(400)),
+            ("notes.txt".to_string(), "y".repeat(40)),
+        ];
+        let pinned = |path: &str| config.attributes_for(path).pinned();
+        let (selected, report) =
+            apply_token_budget_with_pins(files, 50, &LensManager::new(), "drop", &pinned);
+        // The pinned file goes first and stays, though it alone exceeds the budget
+        let paths: Vec<&str> = selected.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["keep.txt"]);
+        assert_eq!(report.dropped_files[0].0, "notes.txt");
+    }
+
     #[test]
     fn test_should_skip_truncation() {
         // Test should_skip_truncation function
@@ -4959,6 +11356,7 @@ class MyClass:
             mtime: 1700000000,
             ctime: 1699000000,
             size: 42,
+            ..Default::default()
         };
 
         let output = serialize_file(&entry);
@@ -4980,6 +11378,7 @@ class MyClass:
             mtime: 0,
             ctime: 0,
             size: 14,
+            ..Default::default()
         };
 
         let output = serialize_file_with_format(&entry, 0, "simple", OutputFormat::Xml);
@@ -4996,6 +11395,7 @@ class MyClass:
             mtime: 0,
             ctime: 0,
             size: 12,
+            ..Default::default()
         };
 
         let output = serialize_file_with_format(&entry, 0, "simple", OutputFormat::PlusMinus);
@@ -5003,6 +11403,62 @@ class MyClass:
         assert!(output.contains("----------"));
     }
 
+    #[test]
+    fn test_serialize_file_with_format_gemini() {
+        let entry = FileEntry {
+            path: "doc.md".to_string(),
+            content: "Example:\n```rust\nfn main() {}\n```\n".to_string(),
+            md5: "def456".to_string(),
+            mtime: 0,
+            ctime: 0,
+            size: 34,
+            ..Default::default()
+        };
+
+        let output = serialize_file_with_format(&entry, 0, "simple", OutputFormat::Gemini);
+        assert!(output.starts_with(
+            "<file path=\"doc.md\" language=\"markdown\" md5=\"def456\" lines=\"4\">"
+        ));
+        // Fence must be longer than the backtick run inside the content
+        assert!(output.contains("````markdown\n"));
+        assert!(output.trim_end().ends_with("````\n</file>"));
+    }
+
+    #[test]
+    fn test_gemini_output_starts_with_file_list() {
+        let entries = vec![
+            FileEntry {
+                path: "src/a.rs".to_string(),
+                content: "fn a() {}\n".to_string(),
+                md5: "1".to_string(),
+                mtime: 0,
+                ctime: 0,
+                size: 10,
+                ..Default::default()
+            },
+            FileEntry {
+                path: "Makefile".to_string(),
+                content: "all:\n\techo hi\n".to_string(),
+                md5: "2".to_string(),
+                mtime: 0,
+                ctime: 0,
+                size: 15,
+                ..Default::default()
+            },
+        ];
+        let config = EncoderConfig {
+            output_format: OutputFormat::Gemini,
+            ..Default::default()
+        };
+
+        let output = serialize_sorted_entries(&config, &entries).unwrap();
+        assert!(output.starts_with("# Project Files (2)\n"));
+        let list_end = output.find("<file ").unwrap();
+        assert!(output[..list_end].contains("- src/a.rs (rust, 1 lines)"));
+        assert!(output[..list_end].contains("- Makefile (2 lines)"));
+        assert_eq!(output.matches("</file>").count(), 2);
+    }
+
     #[test]
     fn test_encoder_config_from_file_valid() {
         use std::fs;
@@ -5014,7 +11470,10 @@ class MyClass:
         // EncoderConfig::from_file reads a Config struct (ignore_patterns, include_patterns)
         let config_json = r#"{
             "ignore_patterns": ["*.tmp", "*.bak"],
-            "include_patterns": ["*.rs"]
+            "include_patterns": ["*.rs"],
+            "max_depth": 6,
+            "max_total_bytes": 20000000,
+            "temporal": { "supernova_30d": 80, "ancient_dormant_days": 365 }
         }"#;
         let config_path = temp_dir.join("encoder_config.json");
         fs::write(&config_path, config_json).unwrap();
@@ -5025,6 +11484,22 @@ class MyClass:
         assert!(config.ignore_patterns.contains(&"*.tmp".to_string()));
         assert!(config.ignore_patterns.contains(&"*.bak".to_string()));
         assert!(config.include_patterns.contains(&"*.rs".to_string()));
+        assert_eq!(config.max_depth, Some(6));
+        assert_eq!(config.max_total_files, None);
+        assert_eq!(config.max_total_bytes, Some(20_000_000));
+        assert_eq!(config.temporal_thresholds.supernova_30d, 80);
+        assert_eq!(config.temporal_thresholds.ancient_dormant_days, 365);
+        assert_eq!(config.temporal_thresholds.high_churn_90d, 10);
+
+        let bad_path = temp_dir.join("bad_temporal.json");
+        fs::write(
+            &bad_path,
+            r#"{"temporal": {"moderate_churn_90d": 50, "high_churn_90d": 20}}"#,
+        )
+        .unwrap();
+        assert!(EncoderConfig::from_file(&bad_path)
+            .unwrap_err()
+            .contains("moderate_churn_90d"));
 
         let _ = fs::remove_dir_all(&temp_dir);
     }
@@ -5131,6 +11606,7 @@ class MyClass:
             mtime: 100,
             ctime: 50,
             size: 12,
+            ..Default::default()
         };
 
         let cloned = entry.clone();
@@ -5169,6 +11645,7 @@ class MyClass:
             mtime: 0,
             ctime: 0,
             size: 14,
+            ..Default::default()
         }];
 
         let config = EncoderConfig::default();
@@ -5190,6 +11667,7 @@ class MyClass:
             mtime: 0,
             ctime: 0,
             size: 12,
+            ..Default::default()
         }];
 
         let config = EncoderConfig {
@@ -5213,6 +11691,7 @@ class MyClass:
             mtime: 0,
             ctime: 0,
             size: 1000,
+            ..Default::default()
         }];
 
         let config = EncoderConfig {
@@ -5257,6 +11736,8 @@ pub mod wasm {
         truncate_lines: Option<usize>,
         #[serde(default)]
         truncate_mode: Option<String>,
+        #[serde(default)]
+        header_hints: bool,
     }
 
     /// Serialize files to Plus/Minus format (WASM entry point)
@@ -5305,6 +11786,7 @@ pub mod wasm {
         if let Some(mode) = wasm_config.truncate_mode {
             config.truncate_mode = mode;
         }
+        config.header_hints = wasm_config.header_hints;
 
         // Create engine (with optional lens)
         let engine = if let Some(lens_name) = wasm_config.lens {
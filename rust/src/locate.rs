@@ -0,0 +1,226 @@
+//! Reverse lookup: where did a quoted excerpt come from?
+//!
+//! Given a Plus/Minus context and a snippet a model quoted from it, finds
+//! the file and line range the snippet was taken from. Output lines are
+//! mapped back to original lines through, in order of preference:
+//!
+//! 1. the `--line-numbers` gutter, when the context has one;
+//! 2. the sidecar manifest's `line_map`, when its checksum matches the file;
+//! 3. identity, for files emitted in full.
+//!
+//! Truncated files without either source of numbering still report their
+//! output lines, but no original range.
+//!
+//! Matching ignores indentation and trailing whitespace, and the first and
+//! last excerpt lines may be partial (a quote can start or end mid-line).
+
+use crate::core::SidecarManifest;
+use crate::decode::{decode, strip_line_numbers, ChecksumStatus, DecodedFile};
+
+/// A place an excerpt was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    /// Project-relative path
+    pub path: String,
+    /// First and last line within the file's block in the context (1-based)
+    pub output_lines: (usize, usize),
+    /// First and last line in the original file, if known
+    pub original_lines: Option<(usize, usize)>,
+    /// Checksum status of the file's block; `Modified` means the context was
+    /// edited after encoding, so the citation may not match the source
+    pub status: ChecksumStatus,
+}
+
+/// Find every place `excerpt` occurs in a Plus/Minus `context`
+pub fn locate(
+    context: &str,
+    excerpt: &str,
+    manifest: Option<&SidecarManifest>,
+) -> Result<Vec<Location>, String> {
+    let needle = excerpt_lines(excerpt);
+    if needle.is_empty() {
+        return Err("excerpt is empty".to_string());
+    }
+
+    let mut locations = Vec::new();
+    for file in decode(context)? {
+        let lines: Vec<&str> = file.content.lines().collect();
+        if lines.len() < needle.len() {
+            continue;
+        }
+        for start in 0..=lines.len() - needle.len() {
+            if !matches_at(&lines[start..start + needle.len()], &needle) {
+                continue;
+            }
+            let first = start + 1;
+            let last = start + needle.len();
+            let original_lines = match (
+                original_line(&file, manifest, first),
+                original_line(&file, manifest, last),
+            ) {
+                (Some(a), Some(b)) => Some((a, b)),
+                _ => None,
+            };
+            locations.push(Location {
+                path: file.path.clone(),
+                output_lines: (first, last),
+                original_lines,
+                status: file.status,
+            });
+        }
+    }
+
+    Ok(locations)
+}
+
+/// Excerpt lines without surrounding blank lines or a line-number gutter
+fn excerpt_lines(excerpt: &str) -> Vec<String> {
+    let mut text = excerpt.to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    let text = strip_line_numbers(&text)
+        .map(|(stripped, _)| stripped)
+        .unwrap_or(text);
+
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.iter().position(|l| !l.trim().is_empty());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty());
+    match (start, end) {
+        (Some(s), Some(e)) => lines[s..=e].iter().map(|l| l.trim().to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn matches_at(lines: &[&str], needle: &[String]) -> bool {
+    let last = needle.len() - 1;
+    lines
+        .iter()
+        .zip(needle)
+        .enumerate()
+        .all(|(i, (line, want))| {
+            let line = line.trim();
+            match (i == 0, i == last) {
+                (true, true) => line.contains(want.as_str()),
+                (true, false) => line.ends_with(want.as_str()),
+                (false, true) => line.starts_with(want.as_str()),
+                (false, false) => line == want.as_str(),
+            }
+        })
+}
+
+/// Original line for a 1-based output line of `file`
+fn original_line(
+    file: &DecodedFile,
+    manifest: Option<&SidecarManifest>,
+    output_line: usize,
+) -> Option<usize> {
+    if !file.line_numbers.is_empty() {
+        return file.line_numbers.get(output_line - 1).copied().flatten();
+    }
+
+    let entry = manifest.and_then(|m| {
+        m.files
+            .iter()
+            .find(|e| e.path == file.path && e.md5 == file.md5)
+    });
+    if let Some(entry) = entry {
+        return entry.original_line(output_line);
+    }
+
+    (file.status != ChecksumStatus::Truncated).then_some(output_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{LineMapRange, SidecarFileEntry};
+    use crate::{calculate_md5, serialize_file_with_options, FileEntry, OutputFormat};
+
+    fn frame(path: &str, content: &str, line_numbers: bool) -> String {
+        let entry = FileEntry::new(path, content);
+        serialize_file_with_options(
+            &entry,
+            0,
+            "simple",
+            OutputFormat::PlusMinus,
+            crate::MetadataMode::None,
+            line_numbers,
+        )
+    }
+
+    const SOURCE: &str = "fn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n";
+
+    #[test]
+    fn test_locates_multi_line_excerpt() {
+        let context = format!(
+            "{}{}",
+            frame("x.rs", "fn x() {}\n", false),
+            frame("src/lib.rs", SOURCE, false)
+        );
+        let found = locate(&context, "fn b() {\n  two();\n", None).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, "src/lib.rs");
+        assert_eq!(found[0].output_lines, (5, 6));
+        assert_eq!(found[0].original_lines, Some((5, 6)));
+        assert_eq!(found[0].status, ChecksumStatus::Verified);
+    }
+
+    #[test]
+    fn test_partial_first_and_last_lines() {
+        let context = frame("src/lib.rs", SOURCE, false);
+        let found = locate(&context, "one();\n}\n\nfn b", None).unwrap();
+        assert_eq!(found[0].original_lines, Some((2, 5)));
+        assert!(locate(&context, "three();", None).unwrap().is_empty());
+        assert!(locate(&context, "\n  \n", None).is_err());
+    }
+
+    #[test]
+    fn test_uses_line_number_gutter() {
+        let context = frame("src/lib.rs", SOURCE, true);
+        let found = locate(&context, "6 |     two();\n", None).unwrap();
+        assert_eq!(found[0].original_lines, Some((6, 6)));
+    }
+
+    #[test]
+    fn test_truncated_file_uses_manifest_line_map() {
+        let md5 = calculate_md5(SOURCE);
+        let context = format!(
+            "++++++++++ src/lib.rs [TRUNCATED: 7 lines] ++++++++++\nfn b() {{\n    two();\n}}\n---------- src/lib.rs [TRUNCATED:7→3] {} src/lib.rs ----------\n",
+            md5
+        );
+        assert_eq!(
+            locate(&context, "two();", None).unwrap()[0].original_lines,
+            None
+        );
+
+        let manifest = SidecarManifest {
+            encoder_version: crate::VERSION.to_string(),
+            format: "plus_minus".to_string(),
+            generated_at: None,
+            lens: None,
+            token_budget: None,
+            truncate_lines: 3,
+            truncate_mode: "smart".to_string(),
+            total_tokens: 0,
+            parts: Vec::new(),
+            files: vec![SidecarFileEntry {
+                path: "src/lib.rs".to_string(),
+                md5: md5.clone(),
+                size: SOURCE.len() as u64,
+                tokens: 0,
+                truncation: "smart".to_string(),
+                original_lines: 7,
+                final_lines: 3,
+                line_map: vec![LineMapRange {
+                    output: 1,
+                    original: 5,
+                    lines: 3,
+                }],
+            }],
+        };
+        let found = locate(&context, "two();", Some(&manifest)).unwrap();
+        assert_eq!(found[0].output_lines, (2, 2));
+        assert_eq!(found[0].original_lines, Some((6, 6)));
+    }
+}
@@ -0,0 +1,319 @@
+//! State format migrations
+//!
+//! The learning store, the Observer's Journal and the zoom sessions under
+//! `.pm_encoder/` each carry a `version`. Every format lists its versions in
+//! order, with one upgrade step between neighbours. Loaders run the steps in
+//! memory, so a file written by an older release loads instead of being reset.
+//! `vo --migrate` rewrites the files in the current format and keeps a
+//! backup of each original.
+//!
+//! Files written before versioning have no `version` field and count as
+//! [`LEGACY`]. The Chronos cache has no migrations: it is derived from git
+//! history and rebuilt whenever its version changes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::core::orchestrator::ObserversJournal;
+use crate::core::{ContextStore, ZoomSessionStore};
+
+/// Version of files written before formats were versioned
+pub const LEGACY: &str = "legacy";
+
+/// Upgrades a file's fields from one version to the next
+type Step = fn(&mut Map<String, Value>);
+
+/// Versions of one format, oldest first; `steps[i]` upgrades `versions[i]`
+struct Schema {
+    versions: &'static [&'static str],
+    steps: &'static [Step],
+}
+
+/// A versioned state file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKind {
+    /// `.pm_encoder/context_store.json`
+    Store,
+    /// `.pm_encoder/journal.json`
+    Journal,
+    /// `.pm_encoder/sessions.json`
+    Sessions,
+}
+
+impl StateKind {
+    pub const ALL: [StateKind; 3] = [Self::Store, Self::Journal, Self::Sessions];
+
+    /// Short name for reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Store => "store",
+            Self::Journal => "journal",
+            Self::Sessions => "sessions",
+        }
+    }
+
+    /// Where the file lives in a project
+    pub fn path(self, project_root: &Path) -> PathBuf {
+        match self {
+            Self::Store => ContextStore::default_path(project_root),
+            Self::Journal => ObserversJournal::default_path(project_root),
+            Self::Sessions => ZoomSessionStore::default_path(project_root),
+        }
+    }
+
+    /// The version this release writes
+    pub fn current_version(self) -> &'static str {
+        let versions = self.schema().versions;
+        versions[versions.len() - 1]
+    }
+
+    fn schema(self) -> Schema {
+        match self {
+            Self::Store => Schema {
+                versions: &[LEGACY, "2.0.0"],
+                steps: &[store_legacy_to_2_0_0],
+            },
+            Self::Journal => Schema {
+                versions: &[LEGACY, "1.0.0"],
+                steps: &[journal_legacy_to_1_0_0],
+            },
+            Self::Sessions => Schema {
+                versions: &[LEGACY, "1.0"],
+                steps: &[sessions_legacy_to_1_0],
+            },
+        }
+    }
+
+    /// Check that upgraded JSON deserializes into the current type
+    pub(crate) fn validate(self, value: &Value) -> Result<(), String> {
+        fn check<T: DeserializeOwned>(value: &Value) -> Result<(), String> {
+            serde_json::from_value::<T>(value.clone())
+                .map(drop)
+                .map_err(|e| e.to_string())
+        }
+        match self {
+            Self::Store => check::<ContextStore>(value),
+            Self::Journal => check::<ObserversJournal>(value),
+            Self::Sessions => check::<ZoomSessionStore>(value),
+        }
+    }
+}
+
+// =============================================================================
+// Steps
+// =============================================================================
+
+/// Unversioned stores lacked `version`, which made them fail to load
+fn store_legacy_to_2_0_0(fields: &mut Map<String, Value>) {
+    fields
+        .entry("files")
+        .or_insert_with(|| Value::Object(Map::new()));
+}
+
+/// Unversioned journals could lack any of the later-added sections
+fn journal_legacy_to_1_0_0(fields: &mut Map<String, Value>) {
+    for key in ["bright_stars", "faded_nebulae"] {
+        fields
+            .entry(key)
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    let explorations = fields
+        .entry("explorations")
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array()
+        .map_or(0, Vec::len);
+    fields
+        .entry("total_explorations")
+        .or_insert_with(|| Value::from(explorations));
+    for key in ["created_at", "updated_at"] {
+        fields
+            .entry(key)
+            .or_insert_with(|| Value::String(String::new()));
+    }
+}
+
+/// Unversioned session files only lacked `version`; every field has a default
+fn sessions_legacy_to_1_0(_fields: &mut Map<String, Value>) {}
+
+// =============================================================================
+// Upgrading
+// =============================================================================
+
+/// Bring a state file's JSON up to the current version
+///
+/// Returns the version the file was written with and the upgraded JSON.
+/// Files from a newer release are an error rather than being downgraded.
+pub fn upgrade_json(kind: StateKind, content: &str) -> Result<(String, Value), String> {
+    let mut value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let fields = value
+        .as_object_mut()
+        .ok_or_else(|| "expected a JSON object".to_string())?;
+    let from = fields
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or(LEGACY)
+        .to_string();
+
+    let schema = kind.schema();
+    let start = schema
+        .versions
+        .iter()
+        .position(|v| *v == from)
+        .ok_or_else(|| {
+            format!(
+                "unknown version {} (this release writes {}); upgrade vo",
+                from,
+                kind.current_version()
+            )
+        })?;
+    for (i, step) in schema.steps.iter().enumerate().skip(start) {
+        step(fields);
+        fields.insert(
+            "version".to_string(),
+            Value::String(schema.versions[i + 1].to_string()),
+        );
+    }
+    Ok((from, value))
+}
+
+/// Upgrade and deserialize a state file in one go
+pub fn load_upgraded<T: DeserializeOwned>(kind: StateKind, content: &str) -> Result<T, String> {
+    let (_, value) = upgrade_json(kind, content)?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// What `--migrate` did (or would do) with one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// No such file in this project
+    Missing,
+    /// Already in the current format
+    UpToDate,
+    /// Upgraded from `from`; the original was kept at `backup`
+    Migrated { from: String, backup: PathBuf },
+    /// Left untouched
+    Failed(String),
+}
+
+/// Migration result for one state file
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub kind: StateKind,
+    pub path: PathBuf,
+    pub outcome: Outcome,
+}
+
+/// Rewrite every outdated state file in the current format
+///
+/// Each original is copied to `<file>.<old version>.bak` first. With
+/// `dry_run` nothing is written, but the reports are the same.
+pub fn migrate_state(project_root: &Path, dry_run: bool) -> Vec<FileReport> {
+    StateKind::ALL
+        .iter()
+        .map(|&kind| {
+            let path = kind.path(project_root);
+            let outcome = migrate_file(kind, &path, dry_run);
+            FileReport {
+                kind,
+                path,
+                outcome,
+            }
+        })
+        .collect()
+}
+
+fn migrate_file(kind: StateKind, path: &Path, dry_run: bool) -> Outcome {
+    if !path.exists() {
+        return Outcome::Missing;
+    }
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+    let (from, value) = match upgrade_json(kind, &content) {
+        Ok(upgraded) => upgraded,
+        Err(e) => return Outcome::Failed(e),
+    };
+    if from == kind.current_version() {
+        return Outcome::UpToDate;
+    }
+    if let Err(e) = kind.validate(&value) {
+        return Outcome::Failed(format!("upgraded file does not load: {}", e));
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{}.bak", from));
+    let backup = PathBuf::from(backup);
+    if !dry_run {
+        let written = serde_json::to_string_pretty(&value)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                fs::copy(path, &backup)
+                    .and_then(|_| fs::write(path, json))
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = written {
+            return Outcome::Failed(e);
+        }
+    }
+    Outcome::Migrated { from, backup }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_legacy_files() {
+        let (from, value) = upgrade_json(StateKind::Store, r#"{"files": {}}"#).unwrap();
+        assert_eq!(from, LEGACY);
+        assert_eq!(value["version"], "2.0.0");
+
+        let journal: ObserversJournal = load_upgraded(
+            StateKind::Journal,
+            r#"{"bright_stars": {}, "explorations": [{"intent": "x", "explored_at": "t", "files_analyzed": 1, "key_insights": []}]}"#,
+        )
+        .unwrap();
+        assert_eq!(journal.version, "1.0.0");
+        assert_eq!(journal.total_explorations, 1);
+    }
+
+    #[test]
+    fn test_upgrade_rejects_newer_versions() {
+        let err = upgrade_json(StateKind::Sessions, r#"{"version": "9.0"}"#).unwrap_err();
+        assert!(err.contains("unknown version 9.0"));
+        let (from, _) = upgrade_json(StateKind::Sessions, r#"{"version": "1.0"}"#).unwrap();
+        assert_eq!(from, "1.0");
+    }
+
+    #[test]
+    fn test_migrate_state_writes_backups() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let store = StateKind::Store.path(temp.path());
+        fs::create_dir_all(store.parent().unwrap()).unwrap();
+        let legacy = r#"{"files": {"a.rs": {"score": 0.9, "access_count": 3}}}"#;
+        fs::write(&store, legacy).unwrap();
+
+        let reports = migrate_state(temp.path(), true);
+        assert!(matches!(reports[0].outcome, Outcome::Migrated { .. }));
+        assert_eq!(reports[1].outcome, Outcome::Missing);
+        assert_eq!(fs::read_to_string(&store).unwrap(), legacy);
+
+        let reports = migrate_state(temp.path(), false);
+        let Outcome::Migrated { backup, .. } = &reports[0].outcome else {
+            panic!("store not migrated: {:?}", reports[0].outcome);
+        };
+        assert_eq!(fs::read_to_string(backup).unwrap(), legacy);
+        let migrated = ContextStore::load_from_file(&store);
+        assert_eq!(migrated.version, "2.0.0");
+        assert_eq!(migrated.file_count(), 1);
+
+        assert_eq!(
+            migrate_state(temp.path(), false)[0].outcome,
+            Outcome::UpToDate
+        );
+    }
+}
@@ -853,14 +853,13 @@ impl LanguagePlugin for AblPlugin {
                     boost += 0.15;
                 }
             }
-            "security" | "security-review" => {
-                // Boost validation procedures
+            // Boost validation procedures
+            "security" | "security-review"
                 if symbol.name.to_lowercase().contains("valid")
                     || symbol.name.to_lowercase().contains("auth")
-                    || symbol.name.to_lowercase().contains("check")
-                {
-                    boost += 0.25;
-                }
+                    || symbol.name.to_lowercase().contains("check") =>
+            {
+                boost += 0.25;
             }
             // Boost infrastructure procedures
             "migration" | "migration-assessment"
@@ -0,0 +1,388 @@
+//! Server Access Control - path allowlist/denylist and audit log
+//!
+//! Every path a tool call reads is canonicalized (symlinks resolved) and must
+//! stay inside its project root. An [`AccessPolicy`] narrows that further with
+//! glob patterns relative to the root: deny patterns always win, and a
+//! non-empty allowlist admits only matching files. What was served (or
+//! refused) is appended to an optional JSON-lines [`AuditLog`].
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::core::walker::WalkConfig;
+use crate::core::{DefaultWalker, FileEntry, FileWalker, Result as CoreResult};
+
+/// Server-side allowlist/denylist for served files
+#[derive(Debug, Clone, Default)]
+pub struct AccessPolicy {
+    allow: Vec<String>,
+    deny: Vec<String>,
+    allow_set: Option<GlobSet>,
+    deny_set: Option<GlobSet>,
+}
+
+/// Compile glob patterns (None when empty)
+fn build_globset(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob =
+            Glob::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| e.to_string())
+}
+
+impl AccessPolicy {
+    /// Create a policy from allow and deny glob patterns
+    ///
+    /// An empty allowlist admits every file not denied.
+    pub fn new(allow: Vec<String>, deny: Vec<String>) -> Result<Self, String> {
+        Ok(Self {
+            allow_set: build_globset(&allow)?,
+            deny_set: build_globset(&deny)?,
+            allow,
+            deny,
+        })
+    }
+
+    /// Whether any allow or deny pattern is configured
+    pub fn is_restricted(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    /// Whether a root-relative file path may be served
+    pub fn is_allowed(&self, relative: &str) -> bool {
+        if let Some(deny) = &self.deny_set {
+            if deny.is_match(relative) {
+                return false;
+            }
+        }
+        match &self.allow_set {
+            Some(allow) => allow.is_match(relative),
+            None => true,
+        }
+    }
+
+    /// Resolve `requested` and check it against `root` and the policy
+    ///
+    /// Rejects anything that escapes the root after canonicalization
+    /// (absolute paths, `../` traversal, or a symlink pointing outside).
+    /// Files must be permitted under both their requested and resolved
+    /// paths, so a symlink cannot launder a denied file. Directories only
+    /// need to be contained; their files are filtered by [`PolicyWalker`].
+    pub fn resolve(&self, root: &Path, requested: &Path) -> Result<PathBuf, String> {
+        let canonical_root = fs::canonicalize(root)
+            .map_err(|e| format!("Invalid project root '{}': {}", root.display(), e))?;
+        let target = fs::canonicalize(requested)
+            .map_err(|e| format!("Invalid path '{}': {}", requested.display(), e))?;
+
+        if !target.starts_with(&canonical_root) {
+            return Err(format!(
+                "Path '{}' escapes project root '{}'",
+                requested.display(),
+                canonical_root.display()
+            ));
+        }
+
+        if target.is_file() {
+            let resolved = relative_path(&target, &canonical_root);
+            let logical = requested
+                .strip_prefix(root)
+                .ok()
+                .map(|p| normalize(&p.to_string_lossy()));
+            let denied = !self.is_allowed(&resolved)
+                || logical.as_deref().is_some_and(|l| !self.is_allowed(l));
+            if denied {
+                return Err(format!(
+                    "Access denied: '{}' is not permitted by the server policy",
+                    logical.unwrap_or(resolved)
+                ));
+            }
+        }
+
+        Ok(target)
+    }
+}
+
+/// Root-relative path with forward slashes
+fn relative_path(path: &Path, root: &Path) -> String {
+    normalize(&path.strip_prefix(root).unwrap_or(path).to_string_lossy())
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// File walker that drops entries escaping the project root or the policy
+///
+/// Wraps [`DefaultWalker`] and records the root-relative paths it returns,
+/// so the server can audit which files went into a response.
+pub struct PolicyWalker {
+    inner: DefaultWalker,
+    root: PathBuf,
+    policy: AccessPolicy,
+    served: Arc<Mutex<Vec<String>>>,
+}
+
+impl PolicyWalker {
+    /// Create a walker confined to `root`
+    pub fn new(root: &Path, policy: AccessPolicy) -> Self {
+        Self {
+            inner: DefaultWalker::new(),
+            root: fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()),
+            policy,
+            served: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Handle to the files returned by walks (root-relative)
+    pub fn served(&self) -> Arc<Mutex<Vec<String>>> {
+        Arc::clone(&self.served)
+    }
+}
+
+impl FileWalker for PolicyWalker {
+    fn walk(&self, root: &str, config: &WalkConfig) -> CoreResult<Vec<FileEntry>> {
+        let walk_root = Path::new(root);
+        let entries: Vec<FileEntry> = self
+            .inner
+            .walk(root, config)?
+            .into_iter()
+            .filter(|entry| {
+                self.policy
+                    .resolve(&self.root, &walk_root.join(&entry.path))
+                    .is_ok()
+            })
+            .collect();
+
+        let walk_root = fs::canonicalize(walk_root).unwrap_or_else(|_| walk_root.to_path_buf());
+        self.served
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(
+                entries
+                    .iter()
+                    .map(|e| relative_path(&walk_root.join(&e.path), &self.root)),
+            );
+
+        Ok(entries)
+    }
+
+    fn should_ignore(&self, path: &str, patterns: &[String]) -> bool {
+        self.inner.should_ignore(path, patterns)
+    }
+}
+
+/// Result of an audited request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    /// Content was returned to the client
+    Served,
+    /// The request was refused by the root boundary or the policy
+    Denied,
+}
+
+/// One audit log record (serialized as a JSON line)
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    /// Tool name (`get_context`, `zoom`, ...)
+    pub tool: String,
+    /// Selected project name
+    pub project: String,
+    /// Requested path or zoom target
+    pub target: String,
+    /// Served or denied
+    pub outcome: AuditOutcome,
+    /// Root-relative files read for the response
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Response size in bytes
+    pub bytes: usize,
+    /// Why the request was denied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl AuditEntry {
+    /// Record served content
+    pub fn served(
+        tool: &str,
+        project: &str,
+        target: impl Into<String>,
+        files: Vec<String>,
+        bytes: usize,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tool: tool.to_string(),
+            project: project.to_string(),
+            target: target.into(),
+            outcome: AuditOutcome::Served,
+            files,
+            bytes,
+            reason: None,
+        }
+    }
+
+    /// Record a refused request
+    pub fn denied(
+        tool: &str,
+        project: &str,
+        target: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tool: tool.to_string(),
+            project: project.to_string(),
+            target: target.into(),
+            outcome: AuditOutcome::Denied,
+            files: Vec::new(),
+            bytes: 0,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// Append-only JSON-lines audit log
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open (or create) the log for appending
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Log file location
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry; write failures are reported on stderr
+    pub fn record(&self, entry: &AuditEntry) {
+        let line = match serde_json::to_string(entry) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("[MCP] Audit serialization failed: {}", e);
+                return;
+            }
+        };
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            eprintln!("[MCP] Audit write to {} failed: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_policy_deny_wins_over_allow() {
+        let policy =
+            AccessPolicy::new(vec!["src/**".to_string()], vec!["**/*.pem".to_string()]).unwrap();
+        assert!(policy.is_restricted());
+        assert!(!AccessPolicy::default().is_restricted());
+        assert!(policy.is_allowed("src/lib.rs"));
+        assert!(!policy.is_allowed("src/keys/server.pem"));
+        assert!(!policy.is_allowed("README.md"));
+    }
+
+    #[test]
+    fn test_policy_rejects_invalid_pattern() {
+        assert!(AccessPolicy::new(vec!["src/[".to_string()], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_denies_file_by_policy() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join(".env"), "SECRET=1").unwrap();
+        fs::write(temp.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let policy = AccessPolicy::new(vec![], vec![".env".to_string()]).unwrap();
+        assert!(policy
+            .resolve(temp.path(), &temp.path().join("main.rs"))
+            .is_ok());
+        let err = policy
+            .resolve(temp.path(), &temp.path().join(".env"))
+            .unwrap_err();
+        assert!(err.contains("Access denied"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_policy_walker_drops_symlinks_escaping_root() {
+        let base = TempDir::new().unwrap();
+        let root = base.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(base.path().join("secret.txt"), "TOP SECRET").unwrap();
+        fs::write(root.join("ok.txt"), "fine").unwrap();
+        std::os::unix::fs::symlink(base.path().join("secret.txt"), root.join("link.txt")).unwrap();
+
+        let walker = PolicyWalker::new(&root, AccessPolicy::default());
+        let served = walker.served();
+        let config = WalkConfig {
+            ignore_patterns: vec![],
+            include_patterns: vec![],
+            max_file_size: 1_048_576,
+        };
+        let entries = walker.walk(root.to_str().unwrap(), &config).unwrap();
+
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["ok.txt"]);
+        assert_eq!(*served.lock().unwrap(), vec!["ok.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_audit_log_appends_json_lines() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("audit.jsonl");
+        let log = AuditLog::open(&path).unwrap();
+
+        log.record(&AuditEntry::served(
+            "get_context",
+            "api",
+            ".",
+            vec!["src/lib.rs".to_string()],
+            42,
+        ));
+        log.record(&AuditEntry::denied(
+            "zoom",
+            "api",
+            "file=../x",
+            "escapes project root",
+        ));
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["outcome"], "served");
+        assert_eq!(lines[0]["files"][0], "src/lib.rs");
+        assert_eq!(lines[1]["outcome"], "denied");
+        assert!(lines[1].get("files").is_none());
+    }
+}
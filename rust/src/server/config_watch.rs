@@ -0,0 +1,321 @@
+//! Hot reload of project config files
+//!
+//! The server lives as long as the client's session, so a project's config
+//! is rebuilt whenever one of its files changes (modification time or
+//! size), on the next tool call for that project, rather than at startup
+//! only. The files are the root `.pm_encoder_config.json`, the nested
+//! configs below it and the `.pmattributes` files in its tree, loaded as
+//! the CLI loads them ([`EncoderConfig::from_file`] and
+//! [`EncoderConfig::discover_directory_configs`]), so every setting
+//! applies. Lenses are built in and have no files to watch. Each reload
+//! that changes something is logged on stderr with the settings and files
+//! that changed. A config that no longer loads is reported and the last
+//! good one is kept, so a half-saved edit can't take the server down.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde_json::{Map, Value};
+
+use crate::attributes::ATTRIBUTES_FILE_NAME;
+use crate::core::SmartWalker;
+use crate::directory_config::CONFIG_FILE_NAME;
+use crate::EncoderConfig;
+
+/// A config file with its modification time and size
+type Stamp = (PathBuf, SystemTime, u64);
+
+/// A project's config files and the config last loaded from them
+#[derive(Debug)]
+pub(crate) struct ConfigWatch {
+    root: PathBuf,
+    /// Stamps at the last check, sorted by path; `None` before the first one
+    stamps: Option<Vec<Stamp>>,
+    config: EncoderConfig,
+}
+
+impl ConfigWatch {
+    /// Watch `root`'s config files; nothing is read until [`Self::refresh`]
+    pub(crate) fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            stamps: None,
+            config: EncoderConfig::default(),
+        }
+    }
+
+    /// Reload the config if any of its files changed since the last check
+    ///
+    /// Returns a diagnostic describing the change, or the reason the new
+    /// config was rejected. The first load is silent.
+    pub(crate) fn refresh(&mut self) -> Option<String> {
+        let stamps = config_files(&self.root);
+        let previous = self.stamps.replace(stamps.clone());
+        if previous.as_ref() == Some(&stamps) {
+            return None;
+        }
+
+        let config = match load(&self.root) {
+            Ok(config) => config,
+            Err(e) => {
+                return Some(format!(
+                    "Config reload failed for {}: {}; keeping the previous config",
+                    self.root.display(),
+                    e
+                ))
+            }
+        };
+        let mut changed = changed_settings(&self.config, &config);
+        self.config = config;
+        let previous = previous?;
+        changed.extend(
+            changed_files(&previous, &stamps)
+                .into_iter()
+                .filter(|path| *path != self.root.join(CONFIG_FILE_NAME))
+                .map(|path| {
+                    path.strip_prefix(&self.root)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string()
+                }),
+        );
+        if changed.is_empty() {
+            return None;
+        }
+        let root_config = self.root.join(CONFIG_FILE_NAME);
+        let had_root_config = previous.iter().any(|(path, ..)| *path == root_config);
+        let has_root_config = stamps.iter().any(|(path, ..)| *path == root_config);
+        Some(if had_root_config && !has_root_config {
+            format!(
+                "Config {} removed; back to defaults ({} reset)",
+                root_config.display(),
+                changed.join(", ")
+            )
+        } else {
+            format!(
+                "Config for {} reloaded: {} changed",
+                self.root.display(),
+                changed.join(", ")
+            )
+        })
+    }
+
+    /// The loaded config with the server's own settings, those of `base`
+    /// that differ from the defaults, laid over it
+    ///
+    /// As with the CLI's flags, the server's ignore patterns add to the
+    /// file's and everything else replaces it. Run state that isn't part
+    /// of a config file (the cache, timings and the like) comes from `base`.
+    pub(crate) fn apply(&self, base: &EncoderConfig) -> EncoderConfig {
+        let defaults = settings(&EncoderConfig::default());
+        let mut merged = settings(&self.config);
+        for (key, value) in settings(base) {
+            if defaults.get(&key) == Some(&value) {
+                continue;
+            }
+            if key == "ignore_patterns" {
+                if let (Some(Value::Array(patterns)), Value::Array(extra)) =
+                    (merged.get_mut(&key), value)
+                {
+                    let default_patterns = &EncoderConfig::default().ignore_patterns;
+                    for pattern in extra {
+                        let is_default = pattern
+                            .as_str()
+                            .is_some_and(|p| default_patterns.iter().any(|d| d == p));
+                        if !is_default && !patterns.contains(&pattern) {
+                            patterns.push(pattern);
+                        }
+                    }
+                }
+                continue;
+            }
+            merged.insert(key, value);
+        }
+
+        let mut config: EncoderConfig =
+            serde_json::from_value(Value::Object(merged)).unwrap_or_else(|_| self.config.clone());
+        config.directory_configs = self.config.directory_configs.clone();
+        config.attributes = self.config.attributes.clone();
+        config.utility_store = base.utility_store.clone();
+        config.timings = base.timings.clone();
+        config.skipped = base.skipped.clone();
+        config.cache = base.cache.clone();
+        config.changed_since = base.changed_since.clone();
+        config.transforms = base.transforms.clone();
+        config
+    }
+}
+
+/// `root`'s config, as the CLI loads it
+fn load(root: &Path) -> Result<EncoderConfig, String> {
+    let path = root.join(CONFIG_FILE_NAME);
+    let mut config = if path.is_file() {
+        EncoderConfig::from_file(&path)?
+    } else {
+        EncoderConfig::default()
+    };
+    config.discover_directory_configs(root)?;
+    Ok(config)
+}
+
+/// Stamps of the config and attributes files in `root`'s tree, found as
+/// [`EncoderConfig::discover_directory_configs`] finds them
+fn config_files(root: &Path) -> Vec<Stamp> {
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| !SmartWalker::is_hygiene_excluded(entry.path()))
+        .build();
+    let mut stamps: Vec<Stamp> = walker
+        .flatten()
+        .filter(|entry| {
+            (entry.file_name() == CONFIG_FILE_NAME || entry.file_name() == ATTRIBUTES_FILE_NAME)
+                && entry.file_type().is_some_and(|ft| ft.is_file())
+        })
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((entry.into_path(), modified, meta.len()))
+        })
+        .collect();
+    stamps.sort();
+    stamps
+}
+
+/// Files added, changed or removed between two sets of stamps
+fn changed_files<'a>(old: &'a [Stamp], new: &'a [Stamp]) -> Vec<&'a Path> {
+    let mut paths: Vec<&Path> = old
+        .iter()
+        .filter(|stamp| !new.contains(stamp))
+        .chain(new.iter().filter(|stamp| !old.contains(stamp)))
+        .map(|(path, ..)| path.as_path())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// A config's settings as they'd be written to a config file
+fn settings(config: &EncoderConfig) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Settings whose values differ between `old` and `new`
+fn changed_settings(old: &EncoderConfig, new: &EncoderConfig) -> Vec<String> {
+    let (old, new) = (settings(old), settings(new));
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rewrite the config with a size that differs from the last write, so
+    /// the change shows even within the file system's mtime granularity
+    fn write(root: &Path, json: &str) {
+        fs::write(root.join(CONFIG_FILE_NAME), json).unwrap();
+    }
+
+    #[test]
+    fn test_reload_reports_changed_settings() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        write(root, r#"{"ignore_patterns": ["*.log"]}"#);
+
+        let mut watch = ConfigWatch::new(root);
+        assert_eq!(watch.refresh(), None);
+        assert_eq!(watch.refresh(), None);
+
+        write(
+            root,
+            r#"{"ignore_patterns": ["*.log"], "truncate_lines": 40}"#,
+        );
+        let message = watch.refresh().unwrap();
+        assert!(message.ends_with("reloaded: truncate_lines changed"));
+        let config = watch.apply(&EncoderConfig::default());
+        assert_eq!(config.truncate_lines, 40);
+        assert!(config.ignore_patterns.contains(&"*.log".to_string()));
+
+        // Every setting of the file applies, as on the CLI
+        write(
+            root,
+            r#"{"ignore_patterns": ["*.log"], "truncate_lines": 40, "max_depth": 3, "binary": "stub"}"#,
+        );
+        let message = watch.refresh().unwrap();
+        assert!(message.ends_with("reloaded: binary, max_depth changed"));
+        let config = watch.apply(&EncoderConfig::default());
+        assert_eq!(config.max_depth, Some(3));
+        assert_eq!(config.binary, crate::BinaryPolicy::Stub);
+
+        write(root, r#"{"ignore_patterns": ["#);
+        assert!(watch
+            .refresh()
+            .unwrap()
+            .contains("keeping the previous config"));
+        assert_eq!(watch.apply(&EncoderConfig::default()).truncate_lines, 40);
+
+        fs::remove_file(root.join(CONFIG_FILE_NAME)).unwrap();
+        let message = watch.refresh().unwrap();
+        assert!(message.contains("removed"));
+        assert!(message.contains("binary, ignore_patterns, max_depth, truncate_lines"));
+    }
+
+    #[test]
+    fn test_reload_watches_nested_configs_and_attributes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir(root.join("pkg")).unwrap();
+        let mut watch = ConfigWatch::new(root);
+        assert_eq!(watch.refresh(), None);
+
+        fs::write(
+            root.join("pkg").join(CONFIG_FILE_NAME),
+            r#"{"truncate_lines": 5}"#,
+        )
+        .unwrap();
+        let message = watch.refresh().unwrap();
+        assert!(
+            message.contains("pkg/.pm_encoder_config.json"),
+            "{}",
+            message
+        );
+        assert!(watch
+            .apply(&EncoderConfig::default())
+            .directory_configs
+            .is_some());
+
+        fs::write(root.join(ATTRIBUTES_FILE_NAME), "*.rs pin\n").unwrap();
+        assert!(watch.refresh().unwrap().contains(".pmattributes"));
+        assert!(watch.apply(&EncoderConfig::default()).attributes.is_some());
+    }
+
+    #[test]
+    fn test_server_settings_override_the_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            r#"{"ignore_patterns": ["*.log"], "truncate_lines": 40, "truncate_mode": "smart"}"#,
+        );
+        let mut watch = ConfigWatch::new(root);
+        watch.refresh();
+
+        let mut base = EncoderConfig::default();
+        base.truncate_lines = 10;
+        base.ignore_patterns.push("dist".to_string());
+        let config = watch.apply(&base);
+        assert_eq!(config.truncate_lines, 10);
+        assert_eq!(config.truncate_mode, "smart");
+        assert_eq!(config.ignore_patterns, ["*.log", "dist"]);
+    }
+}
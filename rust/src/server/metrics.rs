@@ -0,0 +1,360 @@
+//! Server Metrics - request counts, latencies, bytes served and cache hit rates
+//!
+//! Counters are always kept in-process (exposed through [`ServerMetrics::snapshot`]
+//! and summarized on shutdown). With the `otel` feature enabled and
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` set, the same measurements are exported via
+//! OTLP/HTTP so operators can monitor the server like any other backend.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Per-method request statistics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MethodStats {
+    /// Requests handled
+    pub requests: u64,
+    /// Requests that returned a JSON-RPC or tool error
+    pub errors: u64,
+    /// Cumulative handling time in milliseconds
+    pub total_ms: f64,
+    /// Slowest request in milliseconds
+    pub max_ms: f64,
+}
+
+impl MethodStats {
+    /// Mean handling time in milliseconds
+    pub fn mean_ms(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.total_ms / self.requests as f64
+        }
+    }
+}
+
+/// Point-in-time copy of the server metrics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Stats keyed by method (`tools/call` is keyed as `tools/call:<tool>`)
+    pub methods: BTreeMap<String, MethodStats>,
+    /// Response bytes written to the client
+    pub bytes_served: u64,
+    /// Processed files reused from the `--cache` processing cache
+    pub cache_hits: u64,
+    /// Processed files the cache didn't have
+    pub cache_misses: u64,
+}
+
+impl MetricsSnapshot {
+    /// Total requests across all methods
+    pub fn total_requests(&self) -> u64 {
+        self.methods.values().map(|m| m.requests).sum()
+    }
+
+    /// Share of cache lookups that hit, if there were any
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+        (lookups > 0).then(|| self.cache_hits as f64 / lookups as f64)
+    }
+}
+
+/// Metrics recorder for the MCP server
+pub struct ServerMetrics {
+    methods: Mutex<BTreeMap<String, MethodStats>>,
+    bytes_served: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    #[cfg(feature = "otel")]
+    otlp: Option<otlp::OtlpExporter>,
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerMetrics {
+    /// Create an in-process recorder (no export)
+    pub fn new() -> Self {
+        Self {
+            methods: Mutex::new(BTreeMap::new()),
+            bytes_served: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            #[cfg(feature = "otel")]
+            otlp: None,
+        }
+    }
+
+    /// Create a recorder, enabling OTLP export when configured
+    ///
+    /// Export requires the `otel` feature and `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    /// Exporter setup failures are reported on stderr and fall back to
+    /// in-process metrics only.
+    pub fn from_env() -> Self {
+        #[allow(unused_mut)]
+        let mut metrics = Self::new();
+
+        #[cfg(feature = "otel")]
+        if let Ok(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            match otlp::OtlpExporter::new(&endpoint) {
+                Ok(exporter) => metrics.otlp = Some(exporter),
+                Err(e) => eprintln!("[MCP] OTLP metrics disabled: {}", e),
+            }
+        }
+
+        metrics
+    }
+
+    /// Whether measurements are exported via OTLP
+    pub fn is_exporting(&self) -> bool {
+        #[cfg(feature = "otel")]
+        {
+            self.otlp.is_some()
+        }
+        #[cfg(not(feature = "otel"))]
+        {
+            false
+        }
+    }
+
+    /// Record a handled request
+    pub fn record_request(&self, method: &str, elapsed: Duration, is_error: bool) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        {
+            let mut methods = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+            let stats = methods.entry(method.to_string()).or_default();
+            stats.requests += 1;
+            if is_error {
+                stats.errors += 1;
+            }
+            stats.total_ms += ms;
+            stats.max_ms = stats.max_ms.max(ms);
+        }
+
+        #[cfg(feature = "otel")]
+        if let Some(otlp) = &self.otlp {
+            otlp.record_request(method, ms, is_error);
+        }
+    }
+
+    /// Record response bytes written to the client
+    pub fn record_bytes(&self, bytes: usize) {
+        self.bytes_served.fetch_add(bytes as u64, Ordering::Relaxed);
+
+        #[cfg(feature = "otel")]
+        if let Some(otlp) = &self.otlp {
+            otlp.record_bytes(bytes as u64);
+        }
+    }
+
+    /// Record processing cache lookups made while handling a request
+    pub fn record_cache(&self, hits: u64, misses: u64) {
+        if hits + misses == 0 {
+            return;
+        }
+        let total_hits = self.cache_hits.fetch_add(hits, Ordering::Relaxed) + hits;
+        let total_misses = self.cache_misses.fetch_add(misses, Ordering::Relaxed) + misses;
+
+        #[cfg(feature = "otel")]
+        if let Some(otlp) = &self.otlp {
+            otlp.record_cache(
+                hits,
+                misses,
+                total_hits as f64 / (total_hits + total_misses) as f64,
+            );
+        }
+        #[cfg(not(feature = "otel"))]
+        let _ = (total_hits, total_misses);
+    }
+
+    /// Copy the current counters
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            methods: self
+                .methods
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            bytes_served: self.bytes_served.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// One-line summary for the shutdown log
+    pub fn summary(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut summary = format!(
+            "{} requests, {} bytes served",
+            snapshot.total_requests(),
+            snapshot.bytes_served
+        );
+        if let Some(rate) = snapshot.cache_hit_rate() {
+            summary.push_str(&format!(", {:.0}% cache hits", rate * 100.0));
+        }
+        summary
+    }
+
+    /// Flush pending exports (no-op without OTLP)
+    pub fn shutdown(&self) {
+        #[cfg(feature = "otel")]
+        if let Some(otlp) = &self.otlp {
+            otlp.shutdown();
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otlp {
+    use opentelemetry::metrics::{Counter, Gauge, Histogram, MeterProvider as _};
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{MetricExporter, WithExportConfig};
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::Resource;
+
+    /// OTLP/HTTP instruments backed by a periodic reader
+    pub(super) struct OtlpExporter {
+        provider: SdkMeterProvider,
+        requests: Counter<u64>,
+        errors: Counter<u64>,
+        duration: Histogram<f64>,
+        bytes: Counter<u64>,
+        cache_lookups: Counter<u64>,
+        cache_hit_rate: Gauge<f64>,
+    }
+
+    impl OtlpExporter {
+        pub(super) fn new(endpoint: &str) -> Result<Self, String> {
+            let exporter = MetricExporter::builder()
+                .with_http()
+                .with_endpoint(endpoint)
+                .build()
+                .map_err(|e| e.to_string())?;
+
+            let provider = SdkMeterProvider::builder()
+                .with_reader(PeriodicReader::builder(exporter).build())
+                .with_resource(
+                    Resource::builder()
+                        .with_service_name("voyager-observatory")
+                        .build(),
+                )
+                .build();
+
+            let meter = provider.meter("pm_encoder.server");
+            Ok(Self {
+                requests: meter
+                    .u64_counter("vo.server.requests")
+                    .with_description("JSON-RPC requests handled")
+                    .build(),
+                errors: meter
+                    .u64_counter("vo.server.errors")
+                    .with_description("JSON-RPC requests that returned an error")
+                    .build(),
+                duration: meter
+                    .f64_histogram("vo.server.request.duration")
+                    .with_unit("ms")
+                    .with_description("Request handling time")
+                    .build(),
+                bytes: meter
+                    .u64_counter("vo.server.bytes_served")
+                    .with_unit("By")
+                    .with_description("Response bytes written to the client")
+                    .build(),
+                cache_lookups: meter
+                    .u64_counter("vo.server.cache.lookups")
+                    .with_description("Processing cache lookups, by result (hit or miss)")
+                    .build(),
+                cache_hit_rate: meter
+                    .f64_gauge("vo.server.cache.hit_rate")
+                    .with_unit("1")
+                    .with_description("Share of processing cache lookups that hit since start")
+                    .build(),
+                provider,
+            })
+        }
+
+        pub(super) fn record_request(&self, method: &str, ms: f64, is_error: bool) {
+            let attrs = [KeyValue::new("method", method.to_string())];
+            self.requests.add(1, &attrs);
+            self.duration.record(ms, &attrs);
+            if is_error {
+                self.errors.add(1, &attrs);
+            }
+        }
+
+        pub(super) fn record_bytes(&self, bytes: u64) {
+            self.bytes.add(bytes, &[]);
+        }
+
+        pub(super) fn record_cache(&self, hits: u64, misses: u64, hit_rate: f64) {
+            self.cache_lookups
+                .add(hits, &[KeyValue::new("result", "hit")]);
+            self.cache_lookups
+                .add(misses, &[KeyValue::new("result", "miss")]);
+            self.cache_hit_rate.record(hit_rate, &[]);
+        }
+
+        pub(super) fn shutdown(&self) {
+            if let Err(e) = self.provider.shutdown() {
+                eprintln!("[MCP] OTLP metrics shutdown failed: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_request_aggregates_per_method() {
+        let metrics = ServerMetrics::new();
+        metrics.record_request("tools/list", Duration::from_millis(2), false);
+        metrics.record_request("tools/list", Duration::from_millis(6), true);
+        metrics.record_request("initialize", Duration::from_millis(1), false);
+
+        let snapshot = metrics.snapshot();
+        let list = &snapshot.methods["tools/list"];
+        assert_eq!(list.requests, 2);
+        assert_eq!(list.errors, 1);
+        assert!((list.mean_ms() - 4.0).abs() < 0.5);
+        assert!(list.max_ms >= 6.0);
+        assert_eq!(snapshot.total_requests(), 3);
+    }
+
+    #[test]
+    fn test_bytes_served() {
+        let metrics = ServerMetrics::new();
+        metrics.record_bytes(100);
+        metrics.record_bytes(50);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.bytes_served, 150);
+        assert!(metrics.summary().contains("150 bytes served"));
+    }
+
+    #[test]
+    fn test_cache_hit_rate() {
+        let metrics = ServerMetrics::new();
+        assert_eq!(metrics.snapshot().cache_hit_rate(), None);
+        assert!(!metrics.summary().contains("cache"));
+
+        metrics.record_cache(0, 4);
+        metrics.record_cache(0, 0);
+        metrics.record_cache(4, 0);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!((snapshot.cache_hits, snapshot.cache_misses), (4, 4));
+        assert_eq!(snapshot.cache_hit_rate(), Some(0.5));
+        assert!(metrics.summary().ends_with(", 50% cache hits"));
+    }
+
+    #[test]
+    fn test_new_does_not_export() {
+        assert!(!ServerMetrics::new().is_exporting());
+    }
+}
@@ -6,29 +6,49 @@
 //! # Protocol
 //! - JSON-RPC 2.0 over stdio (line-delimited JSON)
 //! - MCP initialize handshake
-//! - Tools: get_context, zoom, session_list, report_utility
+//! - Tools: get_context, zoom, session_list, report_utility, project_list
+//! - Multiple project roots, selected per call with `select_project`
+//! - Root confinement, allow/deny path policy and an audit log (see [`access`])
+//! - Oversized tool results are paginated with `next_cursor`/`cursor`
+//! - `server/status` and `server/warmup` for readiness probes
+//! - Project config files are reloaded when they change (see [`config_watch`])
+//! - Request, byte and processing cache metrics, exported via OTLP with the
+//!   `otel` feature (see [`ServerMetrics`])
 //!
 //! # Usage
 //! ```bash
 //! pm_encoder --server
+//! vo --server --roots api/ web/
 //! ```
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
 use std::fs;
 use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
-
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+pub mod access;
+mod config_watch;
+mod metrics;
+mod pagination;
+pub use access::{AccessPolicy, AuditEntry, AuditLog, AuditOutcome, PolicyWalker};
+use config_watch::ConfigWatch;
+pub use metrics::{MethodStats, MetricsSnapshot, ServerMetrics};
+pub use pagination::{split_pages, DEFAULT_MAX_RESPONSE_BYTES};
+use pagination::{Page, PageStore};
+
+use crate::cache::ProcessingCache;
 use crate::core::{
     CallGraphAnalyzer,
     ContextEngine,
     ContextStore,
-    EncoderConfig,
     ExplorationIntent,
     ExplorerConfig,
     // Phase 2 Week 2: Intent-Driven Exploration
     IntentExplorer,
-    OutputFormat,
     RelatedContext,
     SkeletonMode,
     SymbolResolver,
@@ -41,7 +61,7 @@ use crate::core::{
     ZoomTarget,
     DEFAULT_ALPHA,
 };
-use crate::{parse_token_budget, LensManager};
+use crate::{parse_token_budget, EncoderConfig, LensManager, OutputFormat};
 
 // ============================================================================
 // JSON-RPC 2.0 Types
@@ -126,6 +146,27 @@ fn tool_success(id: Value, text: String) -> JsonRpcResponse {
     )
 }
 
+/// Create a paginated MCP tool response
+///
+/// The continuation cursor is exposed both as `next_cursor` and as a short
+/// text note, so clients that only read `content` still see it.
+fn tool_page(id: Value, tool: &str, page: Page) -> JsonRpcResponse {
+    let mut content = vec![json!({ "type": "text", "text": page.text })];
+    let mut result = json!({ "isError": false });
+    if let Some(cursor) = &page.next_cursor {
+        content.push(json!({
+            "type": "text",
+            "text": format!(
+                "[page {}/{}: call {} with {{\"cursor\": \"{}\"}} for the next page]",
+                page.number, page.total, tool, cursor
+            )
+        }));
+        result["next_cursor"] = json!(cursor);
+    }
+    result["content"] = json!(content);
+    JsonRpcResponse::success(id, result)
+}
+
 /// Create an error MCP tool response with isError: true
 fn tool_error(id: Value, message: String) -> JsonRpcResponse {
     JsonRpcResponse::success(
@@ -144,56 +185,369 @@ fn tool_error(id: Value, message: String) -> JsonRpcResponse {
 // MCP Server
 // ============================================================================
 
+/// Most recent failed request, reported by `server/status`
+struct LastError {
+    method: String,
+    message: String,
+    at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Per-request configuration overrides, merged over the server's base config
+///
+/// Accepted both as top-level tool arguments (`lens`, `token_budget`,
+/// `format`, `skeleton`) and as a nested `config` object, which wins.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigOverrides {
+    lens: Option<String>,
+    token_budget: Option<String>,
+    format: Option<String>,
+    skeleton: Option<String>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    truncate_lines: Option<usize>,
+}
+
+impl ConfigOverrides {
+    /// Collect overrides from tool arguments
+    fn from_args(args: &Value) -> Result<Self, String> {
+        let field = |key: &str| args.get(key).and_then(|v| v.as_str()).map(String::from);
+        let mut overrides = Self {
+            lens: field("lens"),
+            token_budget: field("token_budget"),
+            format: field("format"),
+            skeleton: field("skeleton"),
+            ..Default::default()
+        };
+
+        if let Some(nested) = args.get("config") {
+            let nested: ConfigOverrides = serde_json::from_value(nested.clone())
+                .map_err(|e| format!("Invalid config override: {}", e))?;
+            overrides.lens = nested.lens.or(overrides.lens);
+            overrides.token_budget = nested.token_budget.or(overrides.token_budget);
+            overrides.format = nested.format.or(overrides.format);
+            overrides.skeleton = nested.skeleton.or(overrides.skeleton);
+            overrides.include = nested.include;
+            overrides.exclude = nested.exclude;
+            overrides.truncate_lines = nested.truncate_lines;
+        }
+
+        Ok(overrides)
+    }
+}
+
+/// A registered project root
+///
+/// Each project keeps its own config file; zoom sessions and the context
+/// store already live under the root (`.pm_encoder/`), so selecting a
+/// project selects its stores too.
+struct Project {
+    name: String,
+    root: PathBuf,
+    config_watch: RefCell<ConfigWatch>,
+    /// When a context was last built for the project
+    last_built: Cell<Option<SystemTime>>,
+    /// The project's processing cache, when the server runs with one
+    cache: Option<Arc<ProcessingCache>>,
+}
+
+impl Project {
+    fn new(name: String, root: PathBuf, cached: bool) -> Self {
+        Self {
+            name,
+            config_watch: RefCell::new(ConfigWatch::new(&root)),
+            cache: cached.then(|| Arc::new(ProcessingCache::open(&root))),
+            root,
+            last_built: Cell::new(None),
+        }
+    }
+}
+
+/// Project name derived from the root's directory name
+fn project_name(root: &Path) -> String {
+    fs::canonicalize(root)
+        .ok()
+        .as_deref()
+        .unwrap_or(root)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| root.display().to_string())
+}
+
 /// MCP Server state
 pub struct McpServer {
     initialized: bool,
-    project_root: PathBuf,
+    /// Registered projects; the first one is the default
+    projects: Vec<Project>,
+    base_config: EncoderConfig,
+    metrics: ServerMetrics,
+    policy: AccessPolicy,
+    audit: Option<AuditLog>,
+    /// Largest tool result text returned in one response (0 = unlimited)
+    max_response_bytes: usize,
+    pages: RefCell<PageStore>,
+    started: Instant,
+    last_error: Option<LastError>,
+    /// Whether projects keep a processing cache (`--cache`)
+    cached: bool,
 }
 
 impl McpServer {
     /// Create a new MCP server
+    ///
+    /// Metrics are exported via OTLP when built with the `otel` feature and
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
     pub fn new(project_root: PathBuf) -> Self {
         Self {
             initialized: false,
-            project_root,
+            projects: vec![Project::new(
+                project_name(&project_root),
+                project_root,
+                false,
+            )],
+            base_config: EncoderConfig::default(),
+            metrics: ServerMetrics::from_env(),
+            policy: AccessPolicy::default(),
+            audit: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            pages: RefCell::new(PageStore::default()),
+            started: Instant::now(),
+            last_error: None,
+            cached: false,
+        }
+    }
+
+    /// Reuse processed files across calls through each project's
+    /// processing cache (see [`crate::cache`]); lookups are counted in the
+    /// cache hit rate of [`Self::metrics`]
+    pub fn with_processing_cache(mut self) -> Self {
+        self.cached = true;
+        for project in &mut self.projects {
+            if project.cache.is_none() {
+                project.cache = Some(Arc::new(ProcessingCache::open(&project.root)));
+            }
+        }
+        self
+    }
+
+    /// Processing cache (hits, misses) summed over the projects
+    fn cache_lookups(&self) -> (u64, u64) {
+        self.projects
+            .iter()
+            .filter_map(|p| p.cache.as_deref())
+            .fold((0, 0), |(hits, misses), cache| {
+                (hits + cache.hits() as u64, misses + cache.misses() as u64)
+            })
+    }
+
+    /// Set the response size limit; larger results are paginated (0 = unlimited)
+    pub fn with_max_response_bytes(mut self, bytes: usize) -> Self {
+        self.max_response_bytes = bytes;
+        self
+    }
+
+    /// Restrict served files with an allow/deny policy
+    pub fn with_access_policy(mut self, policy: AccessPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Record served and refused content to an audit log
+    pub fn with_audit_log(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    fn audit(&self, entry: AuditEntry) {
+        if let Some(log) = &self.audit {
+            log.record(&entry);
+        }
+    }
+
+    /// Register additional project roots, selectable with `select_project`
+    ///
+    /// Projects are named after their directory; clashing names get a
+    /// numeric suffix (`app`, `app-2`).
+    pub fn with_roots(mut self, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        for root in roots {
+            let base = project_name(&root);
+            let mut name = base.clone();
+            let mut n = 2;
+            while self.projects.iter().any(|p| p.name == name) {
+                name = format!("{}-{}", base, n);
+                n += 1;
+            }
+            self.projects.push(Project::new(name, root, self.cached));
         }
+        self
     }
 
-    /// Resolve a client-supplied `path` argument against `project_root`,
-    /// rejecting anything that escapes it after canonicalization (absolute
-    /// paths, `../` traversal, or a symlink pointing outside the root).
-    fn resolve_contained_path(&self, raw: Option<&str>) -> Result<PathBuf, String> {
+    /// Names of the registered projects, default first
+    pub fn project_names(&self) -> Vec<&str> {
+        self.projects.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// Pick the project named by the `select_project` argument
+    ///
+    /// Matches a project name or its root path; without the argument the
+    /// default (first) project is used.
+    fn select_project(&self, args: &Value) -> Result<&Project, String> {
+        let selector = match args.get("select_project").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return Ok(&self.projects[0]),
+        };
+
+        let canonical = fs::canonicalize(selector).ok();
+        self.projects
+            .iter()
+            .find(|p| {
+                p.name == selector
+                    || p.root == Path::new(selector)
+                    || (canonical.is_some() && fs::canonicalize(&p.root).ok() == canonical)
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Unknown project '{}'. Available: {}",
+                    selector,
+                    self.project_names().join(", ")
+                )
+            })
+    }
+
+    /// Set the base configuration that per-request overrides are merged over
+    pub fn with_base_config(mut self, config: EncoderConfig) -> Self {
+        self.base_config = config;
+        self
+    }
+
+    /// The base config with `project`'s config file merged in, reloading
+    /// the file first if it changed
+    fn project_config(&self, project: &Project) -> EncoderConfig {
+        let mut watch = project.config_watch.borrow_mut();
+        if let Some(message) = watch.refresh() {
+            eprintln!("[MCP] {}", message);
+        }
+        let mut config = watch.apply(&self.base_config);
+        if let Some(cache) = &project.cache {
+            config.cache = Some(cache.clone());
+        }
+        config
+    }
+
+    /// Build the effective config for a tool call: base config + the
+    /// project's config file + overrides
+    ///
+    /// The lens (from the request or the base config) is applied first, so
+    /// explicit `include`/`exclude` overrides take precedence over it.
+    #[allow(clippy::result_large_err)]
+    fn request_config(
+        &self,
+        project: &Project,
+        id: &Value,
+        args: &Value,
+    ) -> Result<EncoderConfig, JsonRpcResponse> {
+        let overrides = ConfigOverrides::from_args(args)
+            .map_err(|e| JsonRpcResponse::error(id.clone(), INVALID_PARAMS, e))?;
+        let mut config = self.project_config(project);
+
+        if let Some(format) = &overrides.format {
+            config.output_format = OutputFormat::parse(format)
+                .map_err(|e| JsonRpcResponse::error(id.clone(), INVALID_PARAMS, e))?;
+        }
+        if let Some(skeleton) = &overrides.skeleton {
+            config.skeleton_mode = SkeletonMode::parse(skeleton).ok_or_else(|| {
+                JsonRpcResponse::error(
+                    id.clone(),
+                    INVALID_PARAMS,
+                    format!(
+                        "Unknown skeleton mode '{}'. Valid options: auto, true, false",
+                        skeleton
+                    ),
+                )
+            })?;
+        }
+        if let Some(lines) = overrides.truncate_lines {
+            config.truncate_lines = lines;
+        }
+        if let Some(budget_str) = &overrides.token_budget {
+            config.token_budget = Some(parse_token_budget(budget_str).map_err(|e| {
+                JsonRpcResponse::error(
+                    id.clone(),
+                    INVALID_PARAMS,
+                    format!("Invalid token budget: {}", e),
+                )
+            })?);
+        }
+
+        // Apply lens and merge patterns into config
+        let requested_lens = overrides.lens.is_some();
+        if let Some(lens_name) = overrides.lens.or_else(|| config.active_lens.clone()) {
+            let mut lens_manager = LensManager::new();
+            let applied = lens_manager.apply_lens(&lens_name).map_err(|e| {
+                let message = format!("Invalid lens '{}': {}", lens_name, e);
+                // The server's own --lens is not the client's mistake
+                if requested_lens {
+                    JsonRpcResponse::error(id.clone(), INVALID_PARAMS, message)
+                } else {
+                    tool_error(id.clone(), message)
+                }
+            })?;
+            config.ignore_patterns.extend(applied.ignore_patterns);
+            if !applied.include_patterns.is_empty() {
+                config.include_patterns = applied.include_patterns;
+            }
+            config.active_lens = Some(lens_name);
+        }
+
+        if let Some(include) = overrides.include {
+            config.include_patterns = include;
+        }
+        if let Some(exclude) = overrides.exclude {
+            config.ignore_patterns.extend(exclude);
+        }
+
+        Ok(config)
+    }
+
+    /// Request, latency and bytes metrics recorded so far
+    pub fn metrics(&self) -> &ServerMetrics {
+        &self.metrics
+    }
+
+    /// Resolve a client-supplied `path` argument against the project root,
+    /// rejecting anything that escapes it or is refused by the access policy.
+    fn resolve_contained_path(
+        &self,
+        project: &Project,
+        raw: Option<&str>,
+    ) -> Result<PathBuf, String> {
         let requested = match raw {
-            None => self.project_root.clone(),
+            None => project.root.clone(),
             Some(p) => {
                 let p = Path::new(p);
                 if p.is_absolute() {
                     p.to_path_buf()
                 } else {
-                    self.project_root.join(p)
+                    project.root.join(p)
                 }
             }
         };
 
-        let root = fs::canonicalize(&self.project_root).map_err(|e| {
-            format!(
-                "Invalid project root '{}': {}",
-                self.project_root.display(),
-                e
-            )
-        })?;
-        let target = fs::canonicalize(&requested)
-            .map_err(|e| format!("Invalid path '{}': {}", requested.display(), e))?;
-
-        if !target.starts_with(&root) {
-            return Err(format!(
-                "Path '{}' escapes project root '{}'",
-                requested.display(),
-                root.display()
-            ));
-        }
+        self.policy.resolve(&project.root, &requested)
+    }
 
-        Ok(target)
+    /// Context engine whose walker is confined to the project and policy
+    fn confined_engine(
+        &self,
+        project: &Project,
+        config: EncoderConfig,
+    ) -> (ContextEngine, Arc<Mutex<Vec<String>>>) {
+        let walker = PolicyWalker::new(&project.root, self.policy.clone());
+        let served = walker.served();
+        (
+            ContextEngine::with_encoder_config(config).with_walker(walker),
+            served,
+        )
     }
 
     /// Run the server loop (blocking)
@@ -222,10 +576,12 @@ impl McpServer {
 
                 writeln!(stdout, "{}", response_str)?;
                 stdout.flush()?;
+                self.metrics.record_bytes(response_str.len() + 1);
             }
         }
 
-        eprintln!("[MCP] Server shutting down");
+        eprintln!("[MCP] Server shutting down ({})", self.metrics.summary());
+        self.metrics.shutdown();
         Ok(())
     }
 
@@ -255,6 +611,15 @@ impl McpServer {
         // Check if this is a notification (no id = no response expected)
         let is_notification = request.id.is_none();
         let id = request.id.clone().unwrap_or(Value::Null);
+        let started = Instant::now();
+        let (hits_before, misses_before) = self.cache_lookups();
+        let tool_name = request
+            .params
+            .as_ref()
+            .and_then(|p| p.get("name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
 
         // Route method
         let response = match request.method.as_str() {
@@ -269,6 +634,10 @@ impl McpServer {
                 JsonRpcResponse::success(id, json!({}))
             }
 
+            // Health checks
+            "server/status" => self.handle_server_status(id),
+            "server/warmup" => self.handle_server_warmup(id, request.params),
+
             // MCP discovery
             "tools/list" => self.handle_tools_list(id),
 
@@ -282,6 +651,33 @@ impl McpServer {
             ),
         };
 
+        let metric_name = match request.method.as_str() {
+            "tools/call" => format!("tools/call:{}", tool_name),
+            method => method.to_string(),
+        };
+        let error_message = match (&response.error, &response.result) {
+            (Some(error), _) => Some(error.message.clone()),
+            (None, Some(result)) if result["isError"] == true => Some(
+                result["content"][0]["text"]
+                    .as_str()
+                    .unwrap_or("Tool error")
+                    .to_string(),
+            ),
+            _ => None,
+        };
+        self.metrics
+            .record_request(&metric_name, started.elapsed(), error_message.is_some());
+        let (hits, misses) = self.cache_lookups();
+        self.metrics
+            .record_cache(hits - hits_before, misses - misses_before);
+        if let Some(message) = error_message {
+            self.last_error = Some(LastError {
+                method: metric_name,
+                message,
+                at: chrono::Utc::now(),
+            });
+        }
+
         // Don't respond to notifications
         if is_notification {
             None
@@ -309,6 +705,108 @@ impl McpServer {
         )
     }
 
+    /// Readiness probe: index freshness and the last error
+    ///
+    /// `last_indexed_secs_ago` is the time since a context was last built
+    /// for the project.
+    fn handle_server_status(&self, id: Value) -> JsonRpcResponse {
+        let projects: Vec<Value> = self
+            .projects
+            .iter()
+            .map(|project| {
+                json!({
+                    "name": project.name,
+                    "root": project.root.display().to_string(),
+                    "last_indexed_secs_ago": project
+                        .last_built
+                        .get()
+                        .and_then(|t| t.elapsed().ok())
+                        .map(|d| d.as_secs()),
+                })
+            })
+            .collect();
+
+        let snapshot = self.metrics.snapshot();
+        JsonRpcResponse::success(
+            id,
+            json!({
+                "status": "ok",
+                "initialized": self.initialized,
+                "version": crate::version(),
+                "uptime_secs": self.started.elapsed().as_secs(),
+                "projects": projects,
+                "pending_pages": self.pages.borrow().len(),
+                "requests": snapshot.total_requests(),
+                "bytes_served": snapshot.bytes_served,
+                "cache_hit_rate": snapshot.cache_hit_rate(),
+                "last_error": self.last_error.as_ref().map(|e| json!({
+                    "method": e.method,
+                    "message": e.message,
+                    "at": e.at.to_rfc3339(),
+                })),
+            }),
+        )
+    }
+
+    /// Pre-build the default get_context output for each project
+    ///
+    /// Warms every project unless `select_project` names one: the walk and
+    /// the reads prime the OS file cache, and a project that can't be
+    /// serialized is reported before the first real call.
+    fn handle_server_warmup(&self, id: Value, params: Option<Value>) -> JsonRpcResponse {
+        let params = params.unwrap_or(json!({}));
+        let projects: Vec<&Project> = if params.get("select_project").is_some() {
+            match self.select_project(&params) {
+                Ok(p) => vec![p],
+                Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+            }
+        } else {
+            self.projects.iter().collect()
+        };
+
+        let mut results = Vec::new();
+        let mut failed = false;
+        for project in projects {
+            let started = Instant::now();
+            let config = self.project_config(project);
+            let result = self.build_context(project, &project.root, config);
+            let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+            results.push(match result {
+                Ok((context, files)) => json!({
+                    "name": project.name,
+                    "status": "warmed",
+                    "files": files.len(),
+                    "bytes": context.len(),
+                    "elapsed_ms": elapsed_ms,
+                }),
+                Err(e) => {
+                    failed = true;
+                    json!({
+                        "name": project.name,
+                        "status": "error",
+                        "error": e,
+                        "elapsed_ms": elapsed_ms,
+                    })
+                }
+            });
+        }
+
+        if failed {
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id,
+                result: None,
+                error: Some(JsonRpcError {
+                    code: INTERNAL_ERROR,
+                    message: "Warmup failed".to_string(),
+                    data: Some(json!({ "projects": results })),
+                }),
+            }
+        } else {
+            JsonRpcResponse::success(id, json!({ "projects": results }))
+        }
+    }
+
     fn handle_tools_list(&self, id: Value) -> JsonRpcResponse {
         let tools = json!({
             "tools": [
@@ -332,11 +830,24 @@ impl McpServer {
                             },
                             "format": {
                                 "type": "string",
-                                "description": "Output format: plusminus, xml, markdown, claude-xml"
+                                "description": "Output format: plusminus, xml, markdown, claude-xml, gemini"
                             },
                             "skeleton": {
                                 "type": "string",
                                 "description": "Skeleton mode: 'auto' (enable if budget set), 'true', 'false'. Extracts signatures, strips bodies."
+                            },
+                            "config": {
+                                "type": "object",
+                                "description": "Per-request overrides merged over the server's base config",
+                                "properties": {
+                                    "lens": { "type": "string" },
+                                    "token_budget": { "type": "string" },
+                                    "format": { "type": "string" },
+                                    "skeleton": { "type": "string" },
+                                    "include": { "type": "array", "items": { "type": "string" } },
+                                    "exclude": { "type": "array", "items": { "type": "string" } },
+                                    "truncate_lines": { "type": "integer" }
+                                }
                             }
                         }
                     }
@@ -358,6 +869,19 @@ impl McpServer {
                             "session_id": {
                                 "type": "string",
                                 "description": "Optional session ID to track zoom history"
+                            },
+                            "config": {
+                                "type": "object",
+                                "description": "Per-request overrides merged over the server's base config",
+                                "properties": {
+                                    "lens": { "type": "string" },
+                                    "token_budget": { "type": "string" },
+                                    "format": { "type": "string" },
+                                    "skeleton": { "type": "string" },
+                                    "include": { "type": "array", "items": { "type": "string" } },
+                                    "exclude": { "type": "array", "items": { "type": "string" } },
+                                    "truncate_lines": { "type": "integer" }
+                                }
                             }
                         },
                         "required": ["target"]
@@ -411,6 +935,14 @@ impl McpServer {
                         "required": ["path", "utility"]
                     }
                 },
+                {
+                    "name": "project_list",
+                    "description": "List the project roots served by this server (the first is the default)",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {}
+                    }
+                },
                 {
                     "name": "explore_with_intent",
                     "description": "Explore a codebase with a specific intent (business-logic, debugging, onboarding, security, migration). Returns a prioritized exploration path with read/skim/skip decisions for each code element.",
@@ -444,6 +976,26 @@ impl McpServer {
             ]
         });
 
+        // Every project-scoped tool accepts `select_project`; every tool
+        // accepts `cursor` to continue a paginated result
+        let mut tools = tools;
+        if let Some(list) = tools["tools"].as_array_mut() {
+            for tool in list.iter_mut() {
+                let project_scoped = tool["name"] != "project_list";
+                let properties = &mut tool["inputSchema"]["properties"];
+                if project_scoped {
+                    properties["select_project"] = json!({
+                        "type": "string",
+                        "description": "Optional: Project name or root path (default: first registered root)"
+                    });
+                }
+                properties["cursor"] = json!({
+                    "type": "string",
+                    "description": "Optional: next_cursor from a previous paginated result of this tool"
+                });
+            }
+        }
+
         JsonRpcResponse::success(id, tools)
     }
 
@@ -458,94 +1010,108 @@ impl McpServer {
         let tool_name = params.get("name").and_then(|v| v.as_str()).unwrap_or("");
         let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
 
-        match tool_name {
+        // Continuation of an earlier paginated result
+        if let Some(cursor) = arguments.get("cursor").and_then(|v| v.as_str()) {
+            return match self.pages.borrow_mut().next(tool_name, cursor) {
+                Ok(page) => tool_page(id, tool_name, page),
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e),
+            };
+        }
+
+        let response = match tool_name {
             "get_context" => self.tool_get_context(id, arguments),
             "zoom" => self.tool_zoom(id, arguments),
-            "session_list" => self.tool_session_list(id),
+            "session_list" => self.tool_session_list(id, arguments),
             "session_create" => self.tool_session_create(id, arguments),
             "report_utility" => self.tool_report_utility(id, arguments),
             "explore_with_intent" => self.tool_explore_with_intent(id, arguments),
+            "project_list" => self.tool_project_list(id),
             _ => {
                 JsonRpcResponse::error(id, METHOD_NOT_FOUND, format!("Unknown tool: {}", tool_name))
             }
-        }
+        };
+
+        self.paginate(tool_name, response)
+    }
+
+    /// Split a successful tool result that exceeds the response size limit
+    fn paginate(&self, tool_name: &str, response: JsonRpcResponse) -> JsonRpcResponse {
+        let text = match response
+            .result
+            .as_ref()
+            .filter(|r| r["isError"] == false)
+            .and_then(|r| r["content"][0]["text"].as_str())
+        {
+            Some(t) if self.max_response_bytes > 0 && t.len() > self.max_response_bytes => t,
+            _ => return response,
+        };
+
+        let pages = split_pages(text, self.max_response_bytes);
+        let page = self.pages.borrow_mut().start(tool_name, pages);
+        tool_page(response.id, tool_name, page)
     }
 
     // ========================================================================
     // Tool Implementations
     // ========================================================================
 
-    #[allow(clippy::field_reassign_with_default)]
     fn tool_get_context(&self, id: Value, args: Value) -> JsonRpcResponse {
-        let path = match self.resolve_contained_path(args.get("path").and_then(|v| v.as_str())) {
+        let project = match self.select_project(&args) {
             Ok(p) => p,
             Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
         };
-
-        let lens = args.get("lens").and_then(|v| v.as_str());
-        let token_budget = args.get("token_budget").and_then(|v| v.as_str());
-        let format = args
-            .get("format")
-            .and_then(|v| v.as_str())
-            .unwrap_or("plusminus");
-        let skeleton = args
-            .get("skeleton")
-            .and_then(|v| v.as_str())
-            .unwrap_or("auto");
-
-        // TODO: Load project .pm_encoder_config.json when core::EncoderConfig supports Deserialize
-        // For now, use defaults - the lens will override patterns anyway
-        let mut config = EncoderConfig::default();
-        config.output_format = match format {
-            "xml" => OutputFormat::Xml,
-            "markdown" => OutputFormat::Markdown,
-            "claude-xml" => OutputFormat::ClaudeXml,
-            _ => OutputFormat::PlusMinus,
+        let raw_path = args.get("path").and_then(|v| v.as_str());
+        let path = match self.resolve_contained_path(project, raw_path) {
+            Ok(p) => p,
+            Err(e) => {
+                self.audit(AuditEntry::denied(
+                    "get_context",
+                    &project.name,
+                    raw_path.unwrap_or("."),
+                    e.as_str(),
+                ));
+                return JsonRpcResponse::error(id, INVALID_PARAMS, e);
+            }
         };
 
-        // Apply skeleton mode (v2.2.0)
-        config.skeleton_mode = SkeletonMode::parse(skeleton).unwrap_or(SkeletonMode::Auto);
-
-        // Apply lens and merge patterns into config
-        let mut lens_manager = LensManager::new();
-        if let Some(lens_name) = lens {
-            match lens_manager.apply_lens(lens_name) {
-                Ok(applied) => {
-                    // Merge lens patterns into config
-                    config.ignore_patterns.extend(applied.ignore_patterns);
-                    if !applied.include_patterns.is_empty() {
-                        config.include_patterns = applied.include_patterns;
-                    }
-                    config.active_lens = Some(lens_name.to_string());
-                }
-                Err(e) => {
-                    return tool_error(id, format!("Invalid lens '{}': {}", lens_name, e));
-                }
-            }
-        }
+        let config = match self.request_config(project, &id, &args) {
+            Ok(c) => c,
+            Err(resp) => return resp,
+        };
 
-        // Parse token budget
-        if let Some(budget_str) = token_budget {
-            match parse_token_budget(budget_str) {
-                Ok(budget) => config.token_budget = Some(budget),
-                Err(e) => {
-                    return JsonRpcResponse::error(
-                        id,
-                        INVALID_PARAMS,
-                        format!("Invalid token budget: {}", e),
-                    );
-                }
+        match self.build_context(project, &path, config) {
+            Ok((context, files)) => {
+                self.audit(AuditEntry::served(
+                    "get_context",
+                    &project.name,
+                    raw_path.unwrap_or("."),
+                    files,
+                    context.len(),
+                ));
+                tool_success(id, context)
             }
-        }
-
-        // Generate context
-        let engine = ContextEngine::with_config(config);
-        match engine.serialize(path.to_str().unwrap_or(".")) {
-            Ok(context) => tool_success(id, context),
             Err(e) => tool_error(id, format!("Serialization failed: {}", e)),
         }
     }
 
+    /// Build the get_context output for `path`
+    ///
+    /// Returns the context and the files it contains.
+    fn build_context(
+        &self,
+        project: &Project,
+        path: &Path,
+        config: EncoderConfig,
+    ) -> Result<(String, Vec<String>), String> {
+        let (engine, served) = self.confined_engine(project, config);
+        let context = engine
+            .serialize(path.to_str().unwrap_or("."))
+            .map_err(|e| e.to_string())?;
+        let files = std::mem::take(&mut *served.lock().unwrap_or_else(|e| e.into_inner()));
+        project.last_built.set(Some(SystemTime::now()));
+        Ok((context, files))
+    }
+
     fn tool_zoom(&self, id: Value, args: Value) -> JsonRpcResponse {
         let target_str = match args.get("target").and_then(|v| v.as_str()) {
             Some(t) => t,
@@ -558,11 +1124,23 @@ impl McpServer {
             }
         };
 
-        // Parse optional path override (default: server's project_root)
+        // Parse optional path override (default: the selected project's root)
+        let project = match self.select_project(&args) {
+            Ok(p) => p,
+            Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+        };
         let project_root =
-            match self.resolve_contained_path(args.get("path").and_then(|v| v.as_str())) {
+            match self.resolve_contained_path(project, args.get("path").and_then(|v| v.as_str())) {
                 Ok(p) => p,
-                Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                Err(e) => {
+                    self.audit(AuditEntry::denied(
+                        "zoom",
+                        &project.name,
+                        target_str,
+                        e.as_str(),
+                    ));
+                    return JsonRpcResponse::error(id, INVALID_PARAMS, e);
+                }
             };
 
         // Parse target (e.g., "function=main", "file=src/lib.rs:10-50")
@@ -661,17 +1239,50 @@ impl McpServer {
             }
         }
 
+        // Resolved files must stay inside the root and pass the access policy
+        let permitted = |rel: &str| {
+            self.policy
+                .resolve(&project.root, &project_root.join(rel))
+                .is_ok()
+        };
+        let mut zoomed_files = Vec::new();
+        if let ZoomTarget::File { path, .. } = &target {
+            match self.policy.resolve(&project.root, &project_root.join(path)) {
+                Ok(resolved) => zoomed_files.push(
+                    resolved
+                        .strip_prefix(&project_root)
+                        .unwrap_or(&resolved)
+                        .to_string_lossy()
+                        .into_owned(),
+                ),
+                Err(e) => {
+                    self.audit(AuditEntry::denied(
+                        "zoom",
+                        &project.name,
+                        target_str,
+                        e.as_str(),
+                    ));
+                    return JsonRpcResponse::error(id, INVALID_PARAMS, e);
+                }
+            }
+        }
+
+        let config = match self.request_config(project, &id, &args) {
+            Ok(c) => c,
+            Err(resp) => return resp,
+        };
+
         // Build zoom config
         let zoom_config = ZoomConfig {
             target,
-            budget: None,
+            budget: config.token_budget,
             depth: ZoomDepth::Full,
             include_tests: false,
             context_lines: 5,
         };
 
         // Execute zoom
-        let engine = ContextEngine::new();
+        let (engine, _) = self.confined_engine(project, config);
         match engine.zoom(project_root.to_str().unwrap_or("."), &zoom_config) {
             Ok(mut output) => {
                 // Add zoom menu with call graph analysis (callees)
@@ -684,7 +1295,10 @@ impl McpServer {
                     let mut seen = std::collections::HashSet::new();
                     callees = valid_calls
                         .iter()
-                        .filter(|(call, _)| {
+                        .filter(|(call, loc)| {
+                            if !permitted(&loc.path) {
+                                return false;
+                            }
                             if let Some(ref orig) = resolved_name {
                                 if &call.name == orig {
                                     return false;
@@ -710,12 +1324,13 @@ impl McpServer {
                 // Phase 2: Add related_context with callers (reverse call graph)
                 if let Some(ref name) = resolved_name {
                     let usage_finder = UsageFinder::new().with_max_results(10);
-                    let callers = usage_finder.find_usages(
+                    let mut callers = usage_finder.find_usages(
                         name,
                         &project_root,
                         None, // definition_path - let it search everywhere
                         None, // definition_line
                     );
+                    callers.retain(|usage| permitted(&usage.path));
 
                     if !callers.is_empty() || !callees.is_empty() {
                         let related = RelatedContext {
@@ -727,14 +1342,25 @@ impl McpServer {
                     }
                 }
 
+                self.audit(AuditEntry::served(
+                    "zoom",
+                    &project.name,
+                    target_str,
+                    zoomed_files,
+                    output.len(),
+                ));
                 tool_success(id, output)
             }
             Err(e) => tool_error(id, format!("Zoom failed: {}", e)),
         }
     }
 
-    fn tool_session_list(&self, id: Value) -> JsonRpcResponse {
-        let session_path = ZoomSessionStore::default_path(&self.project_root);
+    fn tool_session_list(&self, id: Value, args: Value) -> JsonRpcResponse {
+        let project = match self.select_project(&args) {
+            Ok(p) => p,
+            Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+        };
+        let session_path = ZoomSessionStore::default_path(&project.root);
 
         match ZoomSessionStore::load(&session_path) {
             Ok(store) => {
@@ -772,7 +1398,11 @@ impl McpServer {
         };
 
         let description = args.get("description").and_then(|v| v.as_str());
-        let session_path = ZoomSessionStore::default_path(&self.project_root);
+        let project = match self.select_project(&args) {
+            Ok(p) => p,
+            Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+        };
+        let session_path = ZoomSessionStore::default_path(&project.root);
 
         match ZoomSessionStore::with_persistence(&session_path, |store| {
             if let Some(desc) = description {
@@ -825,7 +1455,11 @@ impl McpServer {
             .and_then(|v| v.as_str())
             .unwrap_or("MCP feedback");
 
-        let store_path = ContextStore::default_path(&self.project_root);
+        let project = match self.select_project(&args) {
+            Ok(p) => p,
+            Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+        };
+        let store_path = ContextStore::default_path(&project.root);
         let mut store = ContextStore::load_from_file(&store_path);
 
         store.report_utility(path, utility, DEFAULT_ALPHA);
@@ -844,6 +1478,26 @@ impl McpServer {
         )
     }
 
+    fn tool_project_list(&self, id: Value) -> JsonRpcResponse {
+        let projects: Vec<Value> = self
+            .projects
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                json!({
+                    "name": p.name,
+                    "root": p.root.display().to_string(),
+                    "default": i == 0
+                })
+            })
+            .collect();
+
+        tool_success(
+            id,
+            serde_json::to_string_pretty(&projects).unwrap_or_default(),
+        )
+    }
+
     fn tool_explore_with_intent(&self, id: Value, args: Value) -> JsonRpcResponse {
         // Parse intent (required)
         let intent_str = match args.get("intent").and_then(|v| v.as_str()) {
@@ -866,11 +1520,32 @@ impl McpServer {
         };
 
         // Parse optional path override
-        let project_root =
-            match self.resolve_contained_path(args.get("path").and_then(|v| v.as_str())) {
-                Ok(p) => p,
-                Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
-            };
+        let project = match self.select_project(&args) {
+            Ok(p) => p,
+            Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+        };
+        let raw_path = args.get("path").and_then(|v| v.as_str());
+        let project_root = match self.resolve_contained_path(project, raw_path) {
+            Ok(p) => p,
+            Err(e) => {
+                self.audit(AuditEntry::denied(
+                    "explore_with_intent",
+                    &project.name,
+                    raw_path.unwrap_or("."),
+                    e.as_str(),
+                ));
+                return JsonRpcResponse::error(id, INVALID_PARAMS, e);
+            }
+        };
+
+        // The explorer walks on its own and cannot honour path patterns
+        if self.policy.is_restricted() {
+            return tool_error(
+                id,
+                "explore_with_intent is unavailable while a server access policy is configured"
+                    .to_string(),
+            );
+        }
 
         // Parse optional parameters
         let include_tests = args
@@ -902,6 +1577,13 @@ impl McpServer {
                     "text" => result.to_text(),
                     _ => result.to_xml(), // Default to XML for MCP/Claude
                 };
+                self.audit(AuditEntry::served(
+                    "explore_with_intent",
+                    &project.name,
+                    raw_path.unwrap_or("."),
+                    Vec::new(),
+                    output.len(),
+                ));
                 tool_success(id, output)
             }
             Err(e) => tool_error(id, format!("Exploration failed: {}", e)),
@@ -941,7 +1623,8 @@ mod tests {
     fn test_mcp_server_new() {
         let server = McpServer::new(PathBuf::from("/tmp"));
         assert!(!server.initialized);
-        assert_eq!(server.project_root, PathBuf::from("/tmp"));
+        assert_eq!(server.projects[0].root, PathBuf::from("/tmp"));
+        assert_eq!(server.project_names(), vec!["tmp"]);
     }
 
     #[test]
@@ -970,8 +1653,8 @@ mod tests {
         let result = resp.result.unwrap();
         let tools = result["tools"].as_array().unwrap();
 
-        // Should have 6 tools
-        assert_eq!(tools.len(), 6);
+        // Should have 7 tools
+        assert_eq!(tools.len(), 7);
 
         // Check tool names
         let tool_names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
@@ -981,6 +1664,8 @@ mod tests {
         assert!(tool_names.contains(&"session_create"));
         assert!(tool_names.contains(&"report_utility"));
         assert!(tool_names.contains(&"explore_with_intent"));
+        assert!(tool_names.contains(&"project_list"));
+        assert!(tools[0]["inputSchema"]["properties"]["select_project"].is_object());
     }
 
     #[test]
@@ -1093,6 +1778,426 @@ mod tests {
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_tool_get_context_metrics() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "first").unwrap();
+
+        let mut server = McpServer::new(temp_dir.path().to_path_buf());
+        let call = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{}}}"#;
+
+        server.handle_request(call).unwrap();
+        server.handle_request(call).unwrap();
+
+        // Every call reflects the tree as it is now
+        fs::write(temp_dir.path().join("b.txt"), "second").unwrap();
+        let resp = server.handle_request(call).unwrap();
+        let text = resp.result.unwrap()["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(text.contains("b.txt"));
+
+        let snapshot = server.metrics().snapshot();
+        assert_eq!(snapshot.methods["tools/call:get_context"].requests, 3);
+        assert_eq!(snapshot.methods["tools/call:get_context"].errors, 0);
+    }
+
+    #[test]
+    fn test_processing_cache_hit_rate() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "first\nsecond\n").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "third\n").unwrap();
+
+        let mut server = McpServer::new(temp_dir.path().to_path_buf()).with_processing_cache();
+        let call = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{"config":{"truncate_lines":1}}}}"#;
+
+        server.handle_request(call).unwrap();
+        let snapshot = server.metrics().snapshot();
+        assert_eq!((snapshot.cache_hits, snapshot.cache_misses), (0, 2));
+
+        let resp = server.handle_request(call).unwrap();
+        let text = resp.result.unwrap()["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(text.contains("first") && !text.contains("second"));
+        let snapshot = server.metrics().snapshot();
+        assert_eq!((snapshot.cache_hits, snapshot.cache_misses), (2, 2));
+
+        let status = r#"{"jsonrpc":"2.0","id":2,"method":"server/status"}"#;
+        let resp = server.handle_request(status).unwrap();
+        assert_eq!(resp.result.unwrap()["cache_hit_rate"], 0.5);
+
+        // Without --cache there is no rate to report
+        let mut server = McpServer::new(temp_dir.path().to_path_buf());
+        server.handle_request(call).unwrap();
+        assert_eq!(server.metrics().snapshot().cache_hit_rate(), None);
+    }
+
+    #[test]
+    fn test_server_warmup_builds_each_project() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "first").unwrap();
+        let mut server = McpServer::new(temp_dir.path().to_path_buf());
+
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"server/warmup"}"#)
+            .unwrap();
+        let result = resp.result.unwrap();
+        assert_eq!(result["projects"][0]["status"], "warmed");
+        assert_eq!(result["projects"][0]["files"], 1);
+        assert!(result["projects"][0]["bytes"].as_u64().unwrap() > 0);
+
+        let resp = server
+            .handle_request(
+                r#"{"jsonrpc":"2.0","id":2,"method":"server/warmup","params":{"select_project":"nope"}}"#,
+            )
+            .unwrap();
+        assert!(resp.error.is_some());
+    }
+
+    #[test]
+    fn test_server_status_reports_freshness_and_last_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "first").unwrap();
+        let mut server = McpServer::new(temp_dir.path().to_path_buf());
+        let status = r#"{"jsonrpc":"2.0","id":1,"method":"server/status"}"#;
+
+        let result = server.handle_request(status).unwrap().result.unwrap();
+        assert_eq!(result["status"], "ok");
+        assert!(result["projects"][0]["last_indexed_secs_ago"].is_null());
+        assert!(result["last_error"].is_null());
+
+        server.handle_request(r#"{"jsonrpc":"2.0","id":2,"method":"server/warmup"}"#);
+        let result = server.handle_request(status).unwrap().result.unwrap();
+        assert_eq!(result["projects"][0]["last_indexed_secs_ago"], 0);
+
+        server.handle_request(r#"{"jsonrpc":"2.0","id":3,"method":"nope"}"#);
+        let result = server.handle_request(status).unwrap().result.unwrap();
+        assert_eq!(result["last_error"]["method"], "nope");
+        assert!(result["last_error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Unknown method"));
+    }
+
+    #[test]
+    fn test_with_roots_names_and_dedupes_projects() {
+        let base = tempfile::TempDir::new().unwrap();
+        for dir in ["api", "web", "other/api"] {
+            fs::create_dir_all(base.path().join(dir)).unwrap();
+        }
+
+        let server = McpServer::new(base.path().join("api"))
+            .with_roots(vec![base.path().join("web"), base.path().join("other/api")]);
+        assert_eq!(server.project_names(), vec!["api", "web", "api-2"]);
+    }
+
+    #[test]
+    fn test_select_project_routes_tool_calls() {
+        let base = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(base.path().join("api")).unwrap();
+        fs::create_dir_all(base.path().join("web")).unwrap();
+        fs::write(base.path().join("api/server.rs"), "fn serve() {}").unwrap();
+        fs::write(base.path().join("web/app.js"), "function app() {}").unwrap();
+
+        let mut server =
+            McpServer::new(base.path().join("api")).with_roots(vec![base.path().join("web")]);
+
+        let text = |resp: JsonRpcResponse| {
+            resp.result.unwrap()["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // Default project is the first root
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{}}}"#)
+            .unwrap();
+        let default_ctx = text(resp);
+        assert!(default_ctx.contains("server.rs"));
+        assert!(!default_ctx.contains("app.js"));
+
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"get_context","arguments":{"select_project":"web"}}}"#)
+            .unwrap();
+        let web_ctx = text(resp);
+        assert!(web_ctx.contains("app.js"));
+        assert!(!web_ctx.contains("server.rs"));
+
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"project_list","arguments":{}}}"#)
+            .unwrap();
+        let projects: Value = serde_json::from_str(&text(resp)).unwrap();
+        assert_eq!(projects[0]["name"], "api");
+        assert_eq!(projects[0]["default"], true);
+        assert_eq!(projects[1]["name"], "web");
+    }
+
+    #[test]
+    fn test_select_project_unknown() {
+        let mut server = McpServer::new(PathBuf::from("/tmp"));
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"session_list","arguments":{"select_project":"missing"}}}"#)
+            .unwrap();
+        let error = resp.error.unwrap();
+        assert_eq!(error.code, INVALID_PARAMS);
+        assert!(error.message.contains("Unknown project 'missing'"));
+    }
+
+    #[test]
+    fn test_access_policy_filters_context_and_audits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::write(temp_dir.path().join("secrets.env"), "TOKEN=abc").unwrap();
+        let audit_path = temp_dir.path().join("audit.jsonl");
+
+        let policy =
+            AccessPolicy::new(vec![], vec!["*.env".to_string(), "*.jsonl".to_string()]).unwrap();
+        let mut server = McpServer::new(temp_dir.path().to_path_buf())
+            .with_access_policy(policy)
+            .with_audit_log(AuditLog::open(&audit_path).unwrap());
+
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{}}}"#)
+            .unwrap();
+        let text = resp.result.unwrap()["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(text.contains("main.rs"));
+        assert!(!text.contains("TOKEN=abc"));
+
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"zoom","arguments":{"target":"file=secrets.env"}}}"#)
+            .unwrap();
+        let err = resp.error.unwrap();
+        assert_eq!(err.code, INVALID_PARAMS);
+        assert!(err.message.contains("Access denied"));
+
+        let audit: Vec<Value> = fs::read_to_string(&audit_path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(audit.len(), 2);
+        assert_eq!(audit[0]["tool"], "get_context");
+        assert_eq!(audit[0]["outcome"], "served");
+        assert_eq!(audit[0]["files"], json!(["main.rs"]));
+        assert_eq!(audit[1]["tool"], "zoom");
+        assert_eq!(audit[1]["outcome"], "denied");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_tool_zoom_rejects_file_symlink_escape() {
+        let base = tempfile::TempDir::new().unwrap();
+        let root = base.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(base.path().join("secret.rs"), "fn secret_fn() {}").unwrap();
+        std::os::unix::fs::symlink(base.path().join("secret.rs"), root.join("link.rs")).unwrap();
+
+        let mut server = McpServer::new(root);
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"zoom","arguments":{"target":"file=link.rs"}}}"#)
+            .unwrap();
+        let err = resp.error.unwrap();
+        assert!(err.message.contains("escapes project root"));
+    }
+
+    #[test]
+    fn test_large_results_paginate_with_cursor() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for i in 0..20 {
+            fs::write(
+                temp_dir.path().join(format!("file{:02}.txt", i)),
+                "line of content\n".repeat(20),
+            )
+            .unwrap();
+        }
+
+        let full = {
+            let mut server =
+                McpServer::new(temp_dir.path().to_path_buf()).with_max_response_bytes(0);
+            let resp = server
+                .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{}}}"#)
+                .unwrap();
+            resp.result.unwrap()["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let mut server =
+            McpServer::new(temp_dir.path().to_path_buf()).with_max_response_bytes(1024);
+        let mut request = json!({
+            "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+            "params": {"name": "get_context", "arguments": {}}
+        });
+        let mut pages = Vec::new();
+        loop {
+            let resp = server.handle_request(&request.to_string()).unwrap();
+            let result = resp.result.unwrap();
+            let text = result["content"][0]["text"].as_str().unwrap().to_string();
+            assert!(text.len() <= 1024);
+            pages.push(text);
+            match result.get("next_cursor").and_then(|c| c.as_str()) {
+                Some(cursor) => {
+                    assert!(result["content"][1]["text"]
+                        .as_str()
+                        .unwrap()
+                        .contains(cursor));
+                    request["params"]["arguments"] = json!({ "cursor": cursor });
+                }
+                None => break,
+            }
+        }
+
+        assert!(pages.len() > 1);
+        assert_eq!(pages.concat(), full);
+    }
+
+    #[test]
+    fn test_unknown_cursor_is_invalid_params() {
+        let mut server = McpServer::new(PathBuf::from("/tmp"));
+        let resp = server
+            .handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{"cursor":"99:1"}}}"#)
+            .unwrap();
+        assert_eq!(resp.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_handle_request_records_errors() {
+        let mut server = McpServer::new(PathBuf::from("/tmp"));
+        server.handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"nope"}"#);
+        let snapshot = server.metrics().snapshot();
+        assert_eq!(snapshot.methods["nope"].errors, 1);
+    }
+
+    #[test]
+    fn test_request_config_merges_overrides_over_base() {
+        let base = EncoderConfig {
+            output_format: OutputFormat::Xml,
+            truncate_lines: 50,
+            ..Default::default()
+        };
+        let server = McpServer::new(PathBuf::from("/tmp")).with_base_config(base);
+
+        // No overrides: base config is used as-is
+        let config = server
+            .request_config(&server.projects[0], &json!(1), &json!({}))
+            .unwrap();
+        assert_eq!(config.output_format, OutputFormat::Xml);
+        assert_eq!(config.truncate_lines, 50);
+
+        // Nested config wins over top-level arguments
+        let args = json!({
+            "format": "markdown",
+            "config": {
+                "format": "gemini",
+                "token_budget": "10k",
+                "include": ["*.rs"],
+                "exclude": ["vendor"]
+            }
+        });
+        let config = server
+            .request_config(&server.projects[0], &json!(1), &args)
+            .unwrap();
+        assert_eq!(config.output_format, OutputFormat::Gemini);
+        assert_eq!(config.token_budget, Some(10_000));
+        assert_eq!(config.include_patterns, vec!["*.rs"]);
+        assert!(config.ignore_patterns.contains(&"vendor".to_string()));
+        assert_eq!(config.truncate_lines, 50);
+    }
+
+    #[test]
+    fn test_request_config_reloads_project_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir
+            .path()
+            .join(crate::directory_config::CONFIG_FILE_NAME);
+        fs::write(&config_path, r#"{"ignore_patterns": ["*.log"]}"#).unwrap();
+        let server = McpServer::new(temp_dir.path().to_path_buf());
+        let project = &server.projects[0];
+
+        let config = server
+            .request_config(project, &json!(1), &json!({}))
+            .unwrap();
+        assert!(config.ignore_patterns.contains(&"*.log".to_string()));
+
+        fs::write(&config_path, r#"{"ignore_patterns": ["*.tmp", "*.bak"]}"#).unwrap();
+        let config = server
+            .request_config(project, &json!(1), &json!({}))
+            .unwrap();
+        assert!(config.ignore_patterns.contains(&"*.tmp".to_string()));
+        assert!(!config.ignore_patterns.contains(&"*.log".to_string()));
+    }
+
+    #[test]
+    fn test_request_config_rejects_unknown_override() {
+        let server = McpServer::new(PathBuf::from("/tmp"));
+        let err = server
+            .request_config(
+                &server.projects[0],
+                &json!(1),
+                &json!({"config": {"colour": "blue"}}),
+            )
+            .unwrap_err();
+        assert_eq!(err.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_request_config_rejects_unknown_format_skeleton_and_lens() {
+        let server = McpServer::new(PathBuf::from("/tmp"));
+        let project = &server.projects[0];
+
+        let config = server
+            .request_config(
+                project,
+                &json!(1),
+                &json!({"format": "claude-xml", "skeleton": "off"}),
+            )
+            .unwrap();
+        assert_eq!(config.output_format, OutputFormat::ClaudeXml);
+        assert_eq!(config.skeleton_mode, SkeletonMode::Disabled);
+
+        for (args, bad) in [
+            (json!({"format": "xmll"}), "'xmll'"),
+            (json!({"config": {"format": "yaml"}}), "'yaml'"),
+            (json!({"skeleton": "sometimes"}), "'sometimes'"),
+            (json!({"lens": "nope"}), "'nope'"),
+            (json!({"config": {"lens": "nope"}}), "'nope'"),
+        ] {
+            let err = server
+                .request_config(project, &json!(1), &args)
+                .unwrap_err()
+                .error
+                .unwrap();
+            assert_eq!(err.code, INVALID_PARAMS);
+            assert!(err.message.contains(bad), "{}", err.message);
+        }
+    }
+
+    #[test]
+    fn test_tool_get_context_with_config_override() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("keep.rs"), "fn keep() {}").unwrap();
+        fs::write(temp_dir.path().join("skip.py"), "def skip(): pass").unwrap();
+
+        let mut server = McpServer::new(temp_dir.path().to_path_buf());
+        let resp = server.handle_request(
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{"config":{"include":["*.rs"],"format":"xml"}}}}"#
+        ).unwrap();
+
+        let result = resp.result.unwrap();
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("keep.rs"));
+        assert!(!text.contains("skip.py"));
+        assert!(text.contains("<file"));
+    }
+
     #[test]
     fn test_tool_report_utility_missing_params() {
         let mut server = McpServer::new(PathBuf::from("/tmp"));
@@ -0,0 +1,225 @@
+//! Response Pagination - splits oversized tool results across calls
+//!
+//! Tool results larger than the server's response limit are cut at line
+//! boundaries. The first page is returned with a `next_cursor`; calling the
+//! same tool with `cursor` set returns the following page, until the last
+//! page comes back without one.
+
+use std::collections::BTreeMap;
+
+/// Default response size limit in bytes
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 512 * 1024;
+
+/// Maximum number of paginated results kept for continuation
+const PAGE_STORE_CAPACITY: usize = 32;
+
+/// Split `text` into pages of at most `max_bytes`, preferring line breaks
+///
+/// A single line longer than the limit is cut at a UTF-8 character
+/// boundary. `max_bytes == 0` disables splitting.
+pub fn split_pages(text: &str, max_bytes: usize) -> Vec<String> {
+    if max_bytes == 0 || text.len() <= max_bytes {
+        return vec![text.to_string()];
+    }
+
+    let mut pages = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_bytes {
+        let mut end = max_bytes;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(newline) = rest[..end].rfind('\n') {
+            end = newline + 1;
+        }
+        if end == 0 {
+            // First character alone exceeds the limit
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        pages.push(rest[..end].to_string());
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        pages.push(rest.to_string());
+    }
+    pages
+}
+
+/// A page handed out for a cursor
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Page {
+    pub text: String,
+    /// 1-based page number
+    pub number: usize,
+    pub total: usize,
+    pub next_cursor: Option<String>,
+}
+
+/// Remaining pages of one paginated result
+struct PagedResult {
+    tool: String,
+    /// Pages after the first, in order
+    pages: Vec<String>,
+}
+
+/// Pending continuations, keyed by result id (oldest evicted first)
+#[derive(Default)]
+pub(super) struct PageStore {
+    next_id: u64,
+    results: BTreeMap<u64, PagedResult>,
+}
+
+fn cursor(id: u64, page: usize) -> String {
+    format!("{}:{}", id, page)
+}
+
+impl PageStore {
+    /// Store `pages` for `tool` and return the first page
+    pub fn start(&mut self, tool: &str, mut pages: Vec<String>) -> Page {
+        let total = pages.len();
+        let first = if pages.is_empty() {
+            String::new()
+        } else {
+            pages.remove(0)
+        };
+        if pages.is_empty() {
+            return Page {
+                text: first,
+                number: 1,
+                total: 1,
+                next_cursor: None,
+            };
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.results.insert(
+            id,
+            PagedResult {
+                tool: tool.to_string(),
+                pages,
+            },
+        );
+        while self.results.len() > PAGE_STORE_CAPACITY {
+            self.results.pop_first();
+        }
+
+        Page {
+            text: first,
+            number: 1,
+            total,
+            next_cursor: Some(cursor(id, 1)),
+        }
+    }
+
+    /// Number of paginated results awaiting continuation
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Return the page a cursor points at
+    ///
+    /// The result is dropped once its last page has been served.
+    pub fn next(&mut self, tool: &str, cursor_str: &str) -> Result<Page, String> {
+        let invalid = || format!("Unknown or expired cursor '{}'", cursor_str);
+        let (id, index) = cursor_str
+            .split_once(':')
+            .and_then(|(id, index)| Some((id.parse::<u64>().ok()?, index.parse::<usize>().ok()?)))
+            .ok_or_else(invalid)?;
+
+        let result = self.results.get(&id).ok_or_else(invalid)?;
+        if result.tool != tool {
+            return Err(format!(
+                "Cursor '{}' belongs to tool '{}'",
+                cursor_str, result.tool
+            ));
+        }
+        let text = result
+            .pages
+            .get(index.wrapping_sub(1))
+            .cloned()
+            .ok_or_else(invalid)?;
+
+        let total = result.pages.len() + 1;
+        let next_cursor = if index + 1 < total {
+            Some(cursor(id, index + 1))
+        } else {
+            self.results.remove(&id);
+            None
+        };
+
+        Ok(Page {
+            text,
+            number: index + 1,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pages_prefers_line_breaks() {
+        let text = "aaaa\nbbbb\ncccc\n";
+        let pages = split_pages(text, 11);
+        assert_eq!(pages, vec!["aaaa\nbbbb\n", "cccc\n"]);
+        assert_eq!(pages.concat(), text);
+    }
+
+    #[test]
+    fn test_split_pages_long_line_and_utf8() {
+        let text = "é".repeat(10); // 20 bytes, no newlines
+        let pages = split_pages(&text, 5);
+        assert!(pages.iter().all(|p| p.len() <= 5));
+        assert_eq!(pages.concat(), text);
+    }
+
+    #[test]
+    fn test_split_pages_disabled_or_small() {
+        assert_eq!(split_pages("abc", 0), vec!["abc"]);
+        assert_eq!(split_pages("abc", 10), vec!["abc"]);
+    }
+
+    #[test]
+    fn test_page_store_walks_cursors() {
+        let mut store = PageStore::default();
+        let pages = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+
+        let first = store.start("get_context", pages);
+        assert_eq!(first.text, "one");
+        assert_eq!(first.total, 3);
+        let cursor = first.next_cursor.unwrap();
+
+        let second = store.next("get_context", &cursor).unwrap();
+        assert_eq!((second.text.as_str(), second.number), ("two", 2));
+        let third = store
+            .next("get_context", &second.next_cursor.unwrap())
+            .unwrap();
+        assert_eq!(third.text, "three");
+        assert!(third.next_cursor.is_none());
+
+        // Finished results are dropped
+        assert!(store.next("get_context", &cursor).is_err());
+    }
+
+    #[test]
+    fn test_page_store_rejects_foreign_and_bogus_cursors() {
+        let mut store = PageStore::default();
+        let first = store.start("zoom", vec!["a".to_string(), "b".to_string()]);
+        let cursor = first.next_cursor.unwrap();
+
+        assert!(store
+            .next("get_context", &cursor)
+            .unwrap_err()
+            .contains("belongs to tool 'zoom'"));
+        assert!(store.next("zoom", "nope").is_err());
+        assert!(store.next("zoom", "0:0").is_err());
+        assert!(store
+            .start("zoom", vec!["only".to_string()])
+            .next_cursor
+            .is_none());
+    }
+}
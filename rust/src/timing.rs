@@ -0,0 +1,179 @@
+//! Per-file processing times
+//!
+//! With `-vv` the CLI records how long each file took to read, to analyze
+//! (the language analyzers smart and structure truncation run) and to
+//! truncate, then lists the slowest, so a file that sends an analyzer's
+//! regexes into backtracking stands out.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Wall time spent on one file, by stage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileTiming {
+    pub path: String,
+    pub read: Duration,
+    /// Language analysis, as part of truncation
+    pub analyze: Duration,
+    /// Truncation, without the analysis
+    pub truncate: Duration,
+}
+
+impl FileTiming {
+    pub fn total(&self) -> Duration {
+        self.read + self.analyze + self.truncate
+    }
+}
+
+/// Times recorded during a run, shared by the walker threads
+#[derive(Debug, Default)]
+pub struct FileTimings {
+    files: Mutex<HashMap<String, FileTiming>>,
+}
+
+impl FileTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record_read(&self, path: &str, elapsed: Duration) {
+        self.update(path, |t| t.read += elapsed);
+    }
+
+    pub(crate) fn record_truncation(&self, path: &str, analyze: Duration, truncate: Duration) {
+        self.update(path, |t| {
+            t.analyze += analyze;
+            t.truncate += truncate;
+        });
+    }
+
+    fn update(&self, path: &str, f: impl FnOnce(&mut FileTiming)) {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let timing = files.entry(path.to_string()).or_insert_with(|| FileTiming {
+            path: path.to_string(),
+            ..Default::default()
+        });
+        f(timing);
+    }
+
+    /// Every file's times, in path order
+    pub fn files(&self) -> Vec<FileTiming> {
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let mut files: Vec<FileTiming> = files.values().cloned().collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    /// The `n` slowest files, slowest first
+    pub fn slowest(&self, n: usize) -> Vec<FileTiming> {
+        let mut files = self.files();
+        files.sort_by(|a, b| b.total().cmp(&a.total()).then_with(|| a.path.cmp(&b.path)));
+        files.truncate(n);
+        files
+    }
+
+    /// One line per file: `path  read / analyze / truncate`
+    pub fn report(&self) -> String {
+        let mut out = String::from("Per-file timings (read / analyze / truncate):\n");
+        for t in self.files() {
+            writeln!(
+                out,
+                "  {}  {:.1?} / {:.1?} / {:.1?}",
+                t.path, t.read, t.analyze, t.truncate
+            )
+            .ok();
+        }
+        out
+    }
+
+    /// The `n` slowest files with their totals and breakdown
+    pub fn slowest_report(&self, n: usize) -> String {
+        let slowest = self.slowest(n);
+        let mut out = format!(
+            "Slowest {} file{}:\n",
+            slowest.len(),
+            if slowest.len() == 1 { "" } else { "s" }
+        );
+        for (i, t) in slowest.iter().enumerate() {
+            writeln!(
+                out,
+                "  {:>2}. {}  {:.1?} (read {:.1?}, analyze {:.1?}, truncate {:.1?})",
+                i + 1,
+                t.path,
+                t.total(),
+                t.read,
+                t.analyze,
+                t.truncate
+            )
+            .ok();
+        }
+        out
+    }
+}
+
+thread_local! {
+    /// Analysis time of the timed truncation running on this thread
+    static ANALYSIS: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Run the language analysis `f`, counting its time toward the timed
+/// truncation around it, if any
+pub(crate) fn analysis<T>(f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    ANALYSIS.with(|a| {
+        if let Some(total) = a.get() {
+            a.set(Some(total + start.elapsed()));
+        }
+    });
+    result
+}
+
+/// Run the truncation `f`; returns its result, the analysis time within it
+/// and its whole wall time
+pub(crate) fn truncation<T>(f: impl FnOnce() -> T) -> (T, Duration, Duration) {
+    let outer = ANALYSIS.with(|a| a.replace(Some(Duration::ZERO)));
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let analysis = ANALYSIS.with(|a| a.replace(outer)).unwrap_or_default();
+    (result, analysis, elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slowest_first() {
+        let timings = FileTimings::new();
+        timings.record_read("a.rs", Duration::from_millis(1));
+        timings.record_read("b.rs", Duration::from_millis(2));
+        timings.record_truncation("a.rs", Duration::from_millis(5), Duration::from_millis(1));
+        timings.record_read("c.rs", Duration::from_millis(3));
+
+        let slowest: Vec<String> = timings.slowest(2).into_iter().map(|t| t.path).collect();
+        assert_eq!(slowest, ["a.rs", "c.rs"]);
+        assert_eq!(timings.files()[0].total(), Duration::from_millis(7));
+        assert!(timings
+            .slowest_report(10)
+            .contains("1. a.rs  7.0ms (read 1.0ms, analyze 5.0ms, truncate 1.0ms)"));
+        assert!(timings.report().contains("  b.rs  2.0ms / 0.0ns / 0.0ns\n"));
+        assert!(timings.slowest_report(1).starts_with("Slowest 1 file:\n"));
+    }
+
+    #[test]
+    fn test_analysis_counts_inside_truncation_only() {
+        let ((), analyzed, elapsed) = truncation(|| {
+            analysis(|| std::thread::sleep(Duration::from_millis(5)));
+        });
+        assert!(analyzed >= Duration::from_millis(5));
+        assert!(elapsed >= analyzed);
+        // Outside a timed truncation nothing is counted
+        analysis(|| ());
+        assert_eq!(ANALYSIS.with(|a| a.get()), None);
+    }
+}
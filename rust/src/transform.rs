@@ -0,0 +1,576 @@
+//! Content transform pipeline
+//!
+//! Between reading a file and writing it out, its content passes through an
+//! ordered list of [`ContentTransform`]s. Each transform belongs to a
+//! [`Stage`], and the pipeline runs the stages in order: redaction, then
+//! stripping, truncation and annotation. Transforms of the same stage run in
+//! the order they were added.
+//!
+//! The built-in truncation and line numbering are transforms like any other;
+//! [`EncoderConfig::transforms_for`] puts them together with the run's own
+//! [`EncoderConfig::transforms`], so library users and plugins add
+//! behaviour without touching the serializers:
+//!
+//! ```
+//! use pm_encoder::transform::{Content, ContentTransform, SourceFile, Stage};
+//! use pm_encoder::EncoderConfig;
+//!
+//! struct Shout;
+//!
+//! impl ContentTransform for Shout {
+//!     fn name(&self) -> &str {
+//!         "shout"
+//!     }
+//!     fn stage(&self) -> Stage {
+//!         Stage::Annotate
+//!     }
+//!     fn apply(&self, _file: &SourceFile<'_>, content: &mut Content) {
+//!         content.text = content.text.to_uppercase();
+//!     }
+//! }
+//!
+//! let config = EncoderConfig::builder().transform(Shout).build().unwrap();
+//! let content = config.transforms_for("a.txt", true).run("a.txt", "hi\n");
+//! assert_eq!(content.text, "HI\n");
+//! ```
+//!
+//! [`EncoderConfig::transforms_for`]: crate::EncoderConfig::transforms_for
+//! [`EncoderConfig::transforms`]: crate::EncoderConfig::transforms
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::cache::ProcessingCache;
+use crate::timing::FileTimings;
+use crate::FileEntry;
+
+/// When a transform runs, in pipeline order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Remove or mask sensitive content
+    Redact,
+    /// Drop content that carries no meaning for the reader (comments, noise)
+    Strip,
+    /// Shorten the file
+    Truncate,
+    /// Add information for the reader (line numbers, markers)
+    Annotate,
+}
+
+/// The file a transform works on
+#[derive(Debug, Clone, Copy)]
+pub struct SourceFile<'a> {
+    /// Path relative to the project root
+    pub path: &'a str,
+    /// Content as read, before any transform
+    pub original: &'a str,
+    /// Content as it entered the truncation stage, after redaction and
+    /// stripping; the same as `original` until then
+    pub untruncated: &'a str,
+}
+
+/// Content on its way through the pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Content {
+    pub text: String,
+    /// Whether a transform shortened the file
+    pub was_truncated: bool,
+}
+
+/// One step of the pipeline
+pub trait ContentTransform: Send + Sync {
+    /// Short name for diagnostics
+    fn name(&self) -> &str;
+
+    /// Where in the pipeline the transform runs
+    fn stage(&self) -> Stage;
+
+    /// Rewrite `content`, which earlier transforms may already have changed
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content);
+
+    /// Everything the output depends on besides the file, for the
+    /// persistent cache; `None` (the default) keeps the file out of it
+    fn fingerprint(&self) -> Option<String> {
+        None
+    }
+}
+
+/// What comes out of the pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transformed {
+    pub text: String,
+    pub was_truncated: bool,
+    /// Line count before the annotation stage, as truncation left it
+    pub final_lines: usize,
+}
+
+/// Ordered list of transforms
+#[derive(Clone, Default)]
+pub struct TransformPipeline {
+    transforms: Vec<Arc<dyn ContentTransform>>,
+    /// Where the truncation stage's times go, if anywhere
+    timings: Option<Arc<FileTimings>>,
+    cache: Option<Arc<ProcessingCache>>,
+}
+
+impl fmt::Debug for TransformPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl TransformPipeline {
+    /// An empty pipeline
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `transform` after the others of its stage
+    pub fn with(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.push(Arc::new(transform));
+        self
+    }
+
+    /// Add `transform` after the others of its stage
+    pub fn push(&mut self, transform: Arc<dyn ContentTransform>) {
+        let at = self
+            .transforms
+            .iter()
+            .position(|t| t.stage() > transform.stage())
+            .unwrap_or(self.transforms.len());
+        self.transforms.insert(at, transform);
+    }
+
+    /// Record the time each file spends in the truncation stage, split into
+    /// language analysis and the rest
+    pub fn timed(mut self, timings: Arc<FileTimings>) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// Reuse and store results in `cache` (see [`Self::run_entry`])
+    pub fn cached(mut self, cache: Arc<ProcessingCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The transforms' fingerprints together, if they all have one
+    pub fn fingerprint(&self) -> Option<String> {
+        let parts: Option<Vec<String>> = self.transforms.iter().map(|t| t.fingerprint()).collect();
+        Some(parts?.join(";"))
+    }
+
+    /// Names of the transforms, in the order they run
+    pub fn names(&self) -> Vec<&str> {
+        self.transforms.iter().map(|t| t.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    /// Like [`Self::run`] for a walked file, going through the cache when
+    /// there is one and every transform has a fingerprint
+    pub fn run_entry(&self, entry: &FileEntry) -> Transformed {
+        let Some((cache, fingerprint)) = self.cache.as_ref().zip(self.fingerprint()) else {
+            return self.run(&entry.path, &entry.content);
+        };
+        let md5 = crate::calculate_md5(&entry.content);
+        if let Some(transformed) = cache.get(&entry.path, &md5, &fingerprint) {
+            return transformed;
+        }
+        let transformed = self.run(&entry.path, &entry.content);
+        cache.put(&entry.path, &md5, &fingerprint, &transformed);
+        transformed
+    }
+
+    /// Run every transform over the content of `path`
+    pub fn run(&self, path: &str, original: &str) -> Transformed {
+        // Lines are counted like Python's `split('\n')`
+        let count = |text: &str| text.split('\n').count();
+        let mut content = Content {
+            text: original.to_string(),
+            was_truncated: false,
+        };
+        let mut untruncated = None;
+        let mut final_lines = None;
+        for transform in &self.transforms {
+            if transform.stage() >= Stage::Truncate && untruncated.is_none() {
+                untruncated = Some(content.text.clone());
+            }
+            if transform.stage() == Stage::Annotate && final_lines.is_none() {
+                final_lines = Some(count(&content.text));
+            }
+            let file = SourceFile {
+                path,
+                original,
+                untruncated: untruncated.as_deref().unwrap_or(original),
+            };
+            match &self.timings {
+                Some(timings) if transform.stage() == Stage::Truncate => {
+                    let ((), analysis, elapsed) =
+                        crate::timing::truncation(|| transform.apply(&file, &mut content));
+                    timings.record_truncation(path, analysis, elapsed.saturating_sub(analysis));
+                }
+                _ => transform.apply(&file, &mut content),
+            }
+        }
+        Transformed {
+            final_lines: final_lines.unwrap_or_else(|| count(&content.text)),
+            text: content.text,
+            was_truncated: content.was_truncated,
+        }
+    }
+}
+
+// =============================================================================
+// Built-in transforms
+// =============================================================================
+
+/// Truncation with one of the modes of [`crate::truncate_content`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncation {
+    /// Line limit (0 = none, except that `structure` always applies)
+    pub lines: usize,
+    pub mode: String,
+    /// Append the summary marker to truncated content
+    pub summary: bool,
+    /// Lines kept whatever the mode
+    pub keep: crate::KeepPatterns,
+}
+
+impl Truncation {
+    pub fn new(lines: usize, mode: &str, summary: bool) -> Self {
+        Self {
+            lines,
+            mode: mode.to_string(),
+            summary,
+            keep: crate::KeepPatterns::default(),
+        }
+    }
+
+    /// Never drop the lines `keep` matches
+    pub fn keeping(mut self, keep: crate::KeepPatterns) -> Self {
+        self.keep = keep;
+        self
+    }
+}
+
+/// `base`, followed by the keep patterns when there are any
+fn keep_fingerprint(base: String, keep: &crate::KeepPatterns) -> String {
+    if keep.is_empty() {
+        base
+    } else {
+        format!("{}:keep={}", base, keep.as_strs().join("\u{1f}"))
+    }
+}
+
+impl ContentTransform for Truncation {
+    fn name(&self) -> &str {
+        "truncate"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Truncate
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some(keep_fingerprint(
+            format!("truncate:{}:{}:{}", self.lines, self.mode, self.summary),
+            &self.keep,
+        ))
+    }
+
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content) {
+        let (text, truncated) = crate::truncate_content_keeping(
+            &content.text,
+            file.path,
+            self.lines,
+            &self.mode,
+            self.summary,
+            &self.keep,
+        );
+        content.text = text;
+        content.was_truncated |= truncated;
+    }
+}
+
+/// Replace the content of a file marked `redact` in `.pmattributes` with a
+/// placeholder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Redaction;
+
+impl ContentTransform for Redaction {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Redact
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some("redact".to_string())
+    }
+
+    fn apply(&self, _file: &SourceFile<'_>, content: &mut Content) {
+        content.text = format!(
+            "[redacted by {}: {} lines]\n",
+            crate::attributes::ATTRIBUTES_FILE_NAME,
+            content.text.lines().count()
+        );
+    }
+}
+
+/// Remove comment lines ahead of truncation; see [`crate::strip_comments`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StripComments {
+    /// Comment lines kept anyway
+    pub keep: crate::KeepPatterns,
+}
+
+impl ContentTransform for StripComments {
+    fn name(&self) -> &str {
+        "strip-comments"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Strip
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some(keep_fingerprint("strip-comments".to_string(), &self.keep))
+    }
+
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content) {
+        let (text, removed) = crate::strip_comments_keeping(&content.text, file.path, &self.keep);
+        content.text = text;
+        content.was_truncated |= removed;
+    }
+}
+
+/// Reduce generated and vendored files to their structure
+///
+/// Runs ahead of the configured truncation; see
+/// [`crate::discovery::detect_generated`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GeneratedStructure {
+    /// The file is known to be generated (`linguist-generated`), so it
+    /// isn't detected again
+    pub marked: bool,
+}
+
+impl GeneratedStructure {
+    pub fn marked() -> Self {
+        Self { marked: true }
+    }
+}
+
+impl ContentTransform for GeneratedStructure {
+    fn name(&self) -> &str {
+        "generated-structure"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Truncate
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some(format!("generated-structure:{}", self.marked))
+    }
+
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content) {
+        if !self.marked && crate::discovery::detect_generated(file.path, file.original).is_none() {
+            return;
+        }
+        let (text, truncated) = crate::truncate_structure(&content.text, file.path);
+        content.text = text;
+        content.was_truncated |= truncated;
+    }
+}
+
+/// Reduce minified scripts and stylesheets to their structure, or to a stub
+///
+/// Runs ahead of the configured truncation; see
+/// [`crate::discovery::minified`]. The structure is kept when it reads
+/// better than the bundle, which a minified file's few huge lines rarely
+/// allow, and [`crate::discovery::minified::minified_stub`] otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MinifiedStructure;
+
+impl ContentTransform for MinifiedStructure {
+    fn name(&self) -> &str {
+        "minified-structure"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Truncate
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some("minified-structure".to_string())
+    }
+
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content) {
+        use crate::discovery::minified;
+
+        let Some(detected) = minified::detect_minified(file.path, &content.text) else {
+            return;
+        };
+        let (structure, _) =
+            crate::truncate_structure_with_options(&content.text, file.path, false);
+        let readable = !structure.trim().is_empty()
+            && structure.len() < content.text.len() / 2
+            && minified::detect_minified(file.path, &structure).is_none();
+        content.text = if readable {
+            structure
+        } else {
+            minified::minified_stub(&detected)
+        };
+        content.was_truncated = true;
+    }
+}
+
+/// Replace lockfiles with the packages they pin
+///
+/// Runs ahead of the configured truncation, which then only shortens the
+/// list if it is still over the limit; see [`crate::summarize_lockfile`]. Lockfiles it can't parse pass
+/// through unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LockfileSummary {
+    /// Follow the list with the `LOCKFILE SUMMARY` marker
+    pub include_summary: bool,
+}
+
+impl ContentTransform for LockfileSummary {
+    fn name(&self) -> &str {
+        "lockfile-summary"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Truncate
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some(format!("lockfile-summary:{}", self.include_summary))
+    }
+
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content) {
+        if let Some(text) =
+            crate::summarize_lockfile(&content.text, file.path, self.include_summary)
+        {
+            content.text = text;
+            content.was_truncated = true;
+        }
+    }
+}
+
+/// Prefix each line with its line number in the original file
+///
+/// See [`crate::number_lines`]; lines are matched against the content as it
+/// entered truncation, so redacted lines keep their numbers, and lines added
+/// by earlier transforms are left unnumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineNumbers;
+
+impl ContentTransform for LineNumbers {
+    fn name(&self) -> &str {
+        "line-numbers"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Annotate
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some("line-numbers".to_string())
+    }
+
+    fn apply(&self, file: &SourceFile<'_>, content: &mut Content) {
+        content.text = crate::number_lines(&content.text, file.untruncated);
+    }
+}
+
+/// Escape control characters; see [`crate::escape_control_chars`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EscapeControlChars;
+
+impl ContentTransform for EscapeControlChars {
+    fn name(&self) -> &str {
+        "escape-control-chars"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Annotate
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some("escape-control-chars".to_string())
+    }
+
+    fn apply(&self, _file: &SourceFile<'_>, content: &mut Content) {
+        content.text = crate::escape_control_chars(&content.text).0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends its name, to show the order transforms ran in
+    struct Mark(&'static str, Stage);
+
+    impl ContentTransform for Mark {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn stage(&self) -> Stage {
+            self.1
+        }
+        fn apply(&self, _file: &SourceFile<'_>, content: &mut Content) {
+            content.text.push_str(self.0);
+        }
+    }
+
+    #[test]
+    fn test_pipeline_orders_by_stage() {
+        let pipeline = TransformPipeline::new()
+            .with(Mark("annotate", Stage::Annotate))
+            .with(Mark("strip", Stage::Strip))
+            .with(Mark("redact", Stage::Redact))
+            .with(Mark("strip-2", Stage::Strip));
+        assert_eq!(
+            pipeline.names(),
+            vec!["redact", "strip", "strip-2", "annotate"]
+        );
+        assert_eq!(pipeline.run("a.rs", "").text, "redactstripstrip-2annotate");
+    }
+
+    #[test]
+    fn test_builtin_transforms() {
+        let original = "a\nb\nc\nd\n";
+        let pipeline = TransformPipeline::new()
+            .with(LineNumbers)
+            .with(Truncation::new(2, "simple", false));
+        assert_eq!(pipeline.names(), vec!["truncate", "line-numbers"]);
+
+        let content = pipeline.run("a.txt", original);
+        assert!(content.was_truncated);
+        assert!(content.text.starts_with("1 | a\n2 | b\n"));
+        // Counted before the numbers were added
+        let truncated = TransformPipeline::new()
+            .with(Truncation::new(2, "simple", false))
+            .run("a.txt", original);
+        assert_eq!(content.final_lines, truncated.text.split('\n').count());
+
+        let untouched = TransformPipeline::new()
+            .with(Truncation::new(0, "simple", true))
+            .run("a.txt", original);
+        assert_eq!(untouched.text, original);
+        assert!(!untouched.was_truncated);
+    }
+}
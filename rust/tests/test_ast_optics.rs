@@ -534,9 +534,9 @@ fn test_voyager_ast_graceful_fallback() {
 
     let bridge = AstBridge::new();
 
-    // Go is not yet supported - should return None gracefully, not panic
-    let result = bridge.analyze_file("package main\nfunc main() {}", LanguageId::Go);
-    assert!(result.is_none(), "Go should not be supported yet");
+    // Ruby is not yet supported - should return None gracefully, not panic
+    let result = bridge.analyze_file("def main; end", LanguageId::Ruby);
+    assert!(result.is_none(), "Ruby should not be supported yet");
 
     // Python is now supported (Phase 1B Core Fleet) - should return valid AST
     let result = bridge.analyze_file("def foo(): pass", LanguageId::Python);
@@ -205,262 +205,1167 @@ fn test_frozen_mode_no_timestamp_variation() {
         .stdout(predicate::str::contains("FROZEN_SNAPSHOT"));
 }
 
-// ============================================================================
-// Zoom Tests
-// ============================================================================
-
 #[test]
-fn test_zoom_file_basic() {
+fn test_frozen_mode_strips_timestamps_and_appends_hash() {
     let temp_dir = create_test_project();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path()).arg("--zoom").arg("file=main.py");
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--frozen")
+        .arg("--metadata")
+        .arg("all")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
 
-    cmd.assert()
-        .success()
-        .stdout(predicate::str::contains("main.py"))
-        .stdout(predicate::str::contains("def main()"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains(" M:"));
+    let trailer = stdout.lines().last().unwrap();
+    assert!(trailer.starts_with("========== CONTEXT sha256:"));
 }
 
 #[test]
-fn test_zoom_file_with_line_range() {
+fn test_line_numbers_flag() {
     let temp_dir = create_test_project();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
     cmd.arg(temp_dir.path())
-        .arg("--zoom")
-        .arg("file=main.py:1-10");
+        .arg("--line-numbers")
+        .arg("--include")
+        .arg("*.py");
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("main.py"))
-        .stdout(predicate::str::contains("Main module"))
-        // Line 1-10 should include imports but not the Calculator class (which starts around line 15)
-        .stdout(predicate::str::contains("import"));
+        .stdout(predicate::str::contains(" 1 | #!/usr/bin/env python3\n"))
+        .stdout(predicate::str::contains(" 4 | import os\n"));
 }
 
 #[test]
-fn test_zoom_function() {
+fn test_header_hints_flag_and_config() {
     let temp_dir = create_test_project();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path()).arg("--zoom").arg("fn=main");
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--header-hints")
+        .arg("--include")
+        .arg("*.py");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "++++++++++ main.py [lang:python tokens:",
+    ));
+
+    let config_path = temp_dir.path().join("hints.json");
+    fs::write(&config_path, r#"{"header_hints": true}"#).unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--format")
+        .arg("xml")
+        .arg("--include")
+        .arg("*.py");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(" language=\"python\" tokens=\""));
 
+    // Off by default
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--include").arg("*.py");
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("def main()"));
+        .stdout(predicate::str::contains("[lang:").not());
 }
 
 #[test]
-fn test_zoom_class() {
+fn test_compress_gzip_output() {
+    use std::io::Read;
+
     let temp_dir = create_test_project();
+    let out_dir = TempDir::new().unwrap();
+    let out_path = out_dir.path().join("context.txt.gz");
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
     cmd.arg(temp_dir.path())
-        .arg("--zoom")
-        .arg("class=Calculator");
-
-    cmd.assert()
-        .success()
-        .stdout(predicate::str::contains("class Calculator"));
+        .arg("-o")
+        .arg(&out_path)
+        .arg("--compress")
+        .arg("gzip");
+    cmd.assert().success();
+
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(&out_path).unwrap())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert!(decoded.contains("def main()"));
 }
 
-// ============================================================================
-// Zoom Error Handling Tests
-// ============================================================================
-
 #[test]
-fn test_zoom_invalid_target_type() {
+fn test_stream_compress_writes_output_file() {
+    use std::io::Read;
+
     let temp_dir = create_test_project();
+    let out_dir = TempDir::new().unwrap();
+    let out_path = out_dir.path().join("context.txt.gz");
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
     cmd.arg(temp_dir.path())
-        .arg("--zoom")
-        .arg("invalid=something");
+        .arg("--stream")
+        .arg("-o")
+        .arg(&out_path)
+        .arg("--compress")
+        .arg("gzip");
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(&out_path).unwrap())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    let streamed = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--stream")
+        .output()
+        .unwrap();
+    assert!(decoded.contains("def main()"));
+    assert_eq!(decoded, String::from_utf8(streamed.stdout).unwrap());
+}
+
+#[test]
+fn test_config_templates() {
+    let temp_dir = create_test_project();
+    fs::write(
+        temp_dir.path().join(".pm_encoder_config.json"),
+        r#"{"templates": {"header": "=== BEGIN {{ path }} ===", "footer": "=== END {{ path }} ==="}}"#,
+    )
+    .unwrap();
 
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path());
     cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("Unknown zoom type"))
-        .stderr(predicate::str::contains("fn, class, mod, file"));
+        .success()
+        .stdout(predicate::str::contains("=== BEGIN main.py ==="))
+        .stdout(predicate::str::contains("=== END main.py ==="))
+        .stdout(predicate::str::contains("++++++++++").not());
 }
 
 #[test]
-fn test_zoom_malformed_target() {
+fn test_quiet_and_porcelain_status() {
     let temp_dir = create_test_project();
+    let out_dir = TempDir::new().unwrap();
+    let context = out_dir.path().join("context.txt");
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
     cmd.arg(temp_dir.path())
-        .arg("--zoom")
-        .arg("notavalidformat");
+        .arg("-o")
+        .arg(&context)
+        .arg("--quiet");
+    cmd.assert().success().stderr(predicate::str::is_empty());
 
-    cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("Invalid zoom format"))
-        .stderr(predicate::str::contains("Expected <TYPE>=<TARGET>"));
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("-o")
+        .arg(&context)
+        .arg("--porcelain");
+    let output = cmd.assert().success().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    let records: Vec<Vec<&str>> = stderr.lines().map(|l| l.split('\t').collect()).collect();
+    assert!(records.contains(&vec!["output", context.to_str().unwrap()]));
+    assert!(records.iter().any(|r| r[0] == "files" && r.len() == 2));
+    assert!(records.iter().any(|r| r[0] == "tokens" && r.len() == 2));
+    assert!(!stderr.contains("Observatory"));
 }
 
 #[test]
-fn test_zoom_nonexistent_target() {
+fn test_quiet_silences_zoom_mark_and_auto_focus() {
     let temp_dir = create_test_project();
+    let root = temp_dir.path();
+
+    let runs: [Vec<std::ffi::OsString>; 4] = [
+        vec![root.into(), "--zoom".into(), "function=main".into()],
+        vec![root.into(), "--zoom-session".into(), "create:review".into()],
+        vec![root.into(), "--mark".into(), "main.py".into()],
+        vec![root.join("main.py").into()],
+    ];
+    for args in runs {
+        let mut cmd = Command::cargo_bin("vo").unwrap();
+        cmd.args(&args).arg("--quiet");
+        cmd.assert().success().stderr(predicate::str::is_empty());
+    }
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path())
-        .arg("--zoom")
-        .arg("fn=nonexistent_function_xyz");
+    // --porcelain keeps them to records
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(root.join("main.py")).arg("--porcelain");
+    let output = cmd.assert().success().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.starts_with("focus\tmain.py\t"));
+    assert!(!stderr.contains("Auto-Focus"));
+}
 
-    cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("Symbol resolution failed"))
-        .stderr(predicate::str::contains("not found"));
+#[test]
+fn test_cache_reuses_processed_files() {
+    let temp_dir = create_test_project();
+    let run = || {
+        let mut cmd = Command::cargo_bin("vo").unwrap();
+        cmd.arg(temp_dir.path())
+            .arg("--cache")
+            .arg("--quiet")
+            .arg("--truncate")
+            .arg("5");
+        cmd.assert().success().get_output().stdout.clone()
+    };
+
+    let first = run();
+    assert!(temp_dir.path().join(".pm_encoder_cache/blobs").is_dir());
+    let second = run();
+    assert_eq!(first, second);
+    // The cache itself stays out of the context
+    assert!(!String::from_utf8_lossy(&second).contains(".pm_encoder_cache"));
 }
 
 #[test]
-fn test_zoom_invalid_line_range() {
+fn test_verbose_timings() {
     let temp_dir = create_test_project();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path())
-        .arg("--zoom")
-        .arg("file=main.py:abc-def");
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("-v").arg("--quiet");
+    let output = cmd.assert().success().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.starts_with("Slowest 3 files:\n   1. "));
+    assert!(!stderr.contains("Per-file timings"));
 
-    cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("Invalid"));
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("-vv")
+        .arg("--quiet")
+        .arg("--truncate")
+        .arg("5")
+        .arg("--truncate-mode")
+        .arg("structure");
+    let output = cmd.assert().success().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.contains("Per-file timings (read / analyze / truncate):\n  config.json  "));
+    assert!(stderr.contains("  main.py  "));
+
+    // Porcelain: one record per file, nothing human-readable in between
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("-v").arg("--porcelain");
+    let output = cmd.assert().success().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(!stderr.contains("Slowest"));
+    let timings: Vec<&str> = stderr
+        .lines()
+        .filter(|line| line.starts_with("timing\t"))
+        .collect();
+    assert_eq!(timings.len(), 3);
+    for record in timings {
+        let fields: Vec<&str> = record.split('\t').collect();
+        assert_eq!(fields.len(), 6, "{}", record);
+        assert!(fields[2..].iter().all(|f| f.parse::<u128>().is_ok()));
+    }
+    assert!(stderr.lines().all(|line| line.contains('\t')), "{}", stderr);
 }
 
-// ============================================================================
-// Truncation Tests
-// ============================================================================
+#[test]
+fn test_structure_footer_notes_ast_fidelity() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("main.go"),
+        "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn a() {\n    1;\n}\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .args(["--truncate-mode", "structure"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    let (rust, go) = stdout.split_once("main.go").unwrap();
+    assert!(go.contains(
+        "Excluded: function bodies, implementation details\n\
+         Not extracted: function bodies are not available for zoom\n"
+    ));
+    // Nothing missing for Rust
+    assert!(!rust.contains("Not extracted"));
+}
 
 #[test]
-fn test_truncation_with_zoom_affordance() {
+fn test_changed_since_filter() {
     let temp_dir = create_test_project();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(temp_dir.path())
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    if !git(&["init", "-q"]) {
+        // git not installed
+        return;
+    }
+    assert!(git(&["add", "."]));
+    assert!(git(&["commit", "-q", "-m", "init"]));
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn changed() {}\n").unwrap();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path()).arg("--truncate").arg("5");
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--changed-since").arg("HEAD");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("pub fn changed()"))
+        .stdout(predicate::str::contains("main.py").not());
 
+    // Everything was written within the last hour
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--changed-since").arg("1h");
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("TRUNCATED"))
-        .stdout(predicate::str::contains("ZOOM_AFFORDANCE"));
+        .stdout(predicate::str::contains("main.py"));
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--changed-since")
+        .arg("no-such-rev");
+    cmd.assert().code(1);
 }
 
 #[test]
-fn test_truncation_no_summary() {
+fn test_files_from() {
     let temp_dir = create_test_project();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
     cmd.arg(temp_dir.path())
-        .arg("--truncate")
-        .arg("5")
-        .arg("--no-truncate-summary");
-
-    let output = cmd.output().unwrap();
-    let stdout = String::from_utf8_lossy(&output.stdout);
+        .arg("--files-from")
+        .arg("-")
+        .write_stdin("./lib.rs\ndeleted.rs\n\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("lib.rs"))
+        .stdout(predicate::str::contains("main.py").not());
 
-    assert!(output.status.success());
-    // Files should still be present
-    assert!(stdout.contains("main.py") || stdout.contains("lib.rs"));
-    // With --no-truncate-summary, we should have fewer "TRUNCATED" markers or
-    // reduced detail. The [TRUNCATED: X lines] header should still appear
-    // but the detailed block may be suppressed.
+    let list = temp_dir.path().join("list.txt");
+    fs::write(&list, "../outside.rs\n").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--files-from").arg(&list);
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("is outside"));
 }
 
-// ============================================================================
-// Help and Version Tests
-// ============================================================================
-
 #[test]
-fn test_help_output() {
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg("--help");
+fn test_prefix_roots() {
+    let service = create_test_project();
+    let proto = TempDir::new().unwrap();
+    fs::write(proto.path().join("api.proto"), "syntax = \"proto3\";\n").unwrap();
+    let name = |dir: &TempDir| {
+        dir.path()
+            .canonicalize()
+            .unwrap()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    };
 
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(service.path())
+        .arg(proto.path())
+        .arg("--prefix-roots");
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("--zoom"))
-        .stdout(predicate::str::contains("--frozen"))
-        .stdout(predicate::str::contains("--format"))
-        .stdout(predicate::str::contains("--truncate"))
-        .stdout(predicate::str::contains("--lens"));
+        .stdout(predicate::str::contains(format!(
+            "{}/api.proto",
+            name(&proto)
+        )))
+        .stdout(predicate::str::contains(format!(
+            "{}/main.py",
+            name(&service)
+        )));
+
+    // Several roots without --prefix-roots is a usage error
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(service.path()).arg(proto.path());
+    cmd.assert().code(5);
 }
 
 #[test]
-fn test_version_output() {
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg("--version");
+fn test_prefix() {
+    let service = create_test_project();
+    let proto = TempDir::new().unwrap();
+    fs::write(proto.path().join("api.proto"), "syntax = \"proto3\";\n").unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(service.path())
+        .arg(proto.path())
+        .args(["--prefix", "acme-service/", "--prefix", "acme-proto/"])
+        .args(["--truncate", "5"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("++++++++++ acme-proto/api.proto "));
+    assert!(stdout.contains(" acme-service/main.py ----------"));
+    assert!(stdout.contains("--zoom file=acme-service/main.py "));
+
+    // One prefix per root
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(service.path())
+        .arg(proto.path())
+        .args(["--prefix", "acme-service/"]);
+    cmd.assert().code(5);
+
+    // And per --concat bundle
+    let bundle = service.path().join("service.ctx");
+    fs::write(&bundle, stdout.as_bytes()).unwrap();
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg("--concat")
+        .arg(&bundle)
+        .args(["--prefix", "mono/"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let merged = String::from_utf8_lossy(&output.stdout);
+    assert!(merged.contains("++++++++++ mono/acme-proto/api.proto "));
+    assert!(merged.contains("--zoom file=mono/acme-service/main.py "));
+}
 
+#[test]
+fn test_remote_git_repository() {
+    let repo = create_test_project();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    if !git(&["init", "-q"]) {
+        // git not installed
+        return;
+    }
+    assert!(git(&["add", "."]));
+    assert!(git(&["commit", "-q", "-m", "init"]));
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg("--remote")
+        .arg(format!("file://{}", repo.path().display()));
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("1.0.0"));
-}
+        .stdout(predicate::str::contains("main.py"))
+        .stdout(predicate::str::contains("lib.rs"));
 
-// ============================================================================
-// Error Handling Tests
-// ============================================================================
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg("--remote")
+        .arg(format!("file://{}#no-such-branch", repo.path().display()));
+    cmd.assert().code(1);
+}
 
 #[test]
-fn test_missing_project_root() {
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    // No arguments - should fail with an error about missing PATH
+fn test_git_ref() {
+    let repo = create_test_project();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo.path())
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    if !git(&["init", "-q"]) {
+        // git not installed
+        return;
+    }
+    fs::write(repo.path().join("lib.rs"), "fn released() {}\n").unwrap();
+    assert!(git(&["add", "."]));
+    assert!(git(&["commit", "-q", "-m", "release"]));
+    assert!(git(&["tag", "v1.4.0"]));
+    fs::write(repo.path().join("lib.rs"), "fn work_in_progress() {}\n").unwrap();
+    fs::write(repo.path().join("notes.txt"), "untracked\n").unwrap();
 
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(repo.path()).args(["--git-ref", "v1.4.0"]);
     cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("PATH").or(predicate::str::contains("PROJECT_ROOT")));
+        .success()
+        .stdout(predicate::str::contains("fn released() {}"))
+        .stdout(predicate::str::contains("++++++++++ main.py "))
+        .stdout(predicate::str::contains("work_in_progress").not())
+        .stdout(predicate::str::contains("notes.txt").not());
+    assert_eq!(
+        fs::read_to_string(repo.path().join("lib.rs")).unwrap(),
+        "fn work_in_progress() {}\n"
+    );
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(repo.path()).args(["--git-ref", "v9.9.9"]);
+    cmd.assert().code(1);
+
+    // The snapshot is removed even when the run exits early after taking it
+    let tmp = TempDir::new().unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.env("TMPDIR", tmp.path()).arg(repo.path()).args([
+        "--git-ref",
+        "v1.4.0",
+        "--prefix",
+        "a",
+        "--prefix",
+        "b",
+    ]);
+    cmd.assert().failure();
+    assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 0);
 }
 
 #[test]
-fn test_nonexistent_directory() {
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg("/nonexistent/path/that/does/not/exist");
+fn test_archive_project_root() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive = temp_dir.path().join("project.tar");
+    let mut builder = tar::Builder::new(fs::File::create(&archive).unwrap());
+    for (name, content) in [
+        ("project/main.py", "print('hi')\n"),
+        ("project/lib.rs", "fn f() {}\n"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, content.as_bytes())
+            .unwrap();
+    }
+    builder.finish().unwrap();
+    drop(builder);
 
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(&archive);
     cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("does not exist"));
+        .success()
+        .stdout(predicate::str::contains("main.py"))
+        .stdout(predicate::str::contains("fn f() {}"))
+        .stdout(predicate::str::contains("project/lib.rs").not());
 }
 
 #[test]
-fn test_invalid_format() {
+#[cfg(unix)]
+fn test_follow_symlinks_off() {
     let temp_dir = create_test_project();
+    std::os::unix::fs::symlink("main.py", temp_dir.path().join("alias.py")).unwrap();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path())
-        .arg("--format")
-        .arg("invalid_format");
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--follow-symlinks=off");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("main.py"))
+        .stdout(predicate::str::contains("alias.py").not());
 
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--follow-symlinks");
     cmd.assert()
-        .failure()
-        .stderr(predicate::str::contains("invalid"));
+        .success()
+        .stdout(predicate::str::contains("alias.py"));
 }
 
-// ============================================================================
-// Token Budget Tests
-// ============================================================================
-
 #[test]
-fn test_token_budget_basic() {
+fn test_walk_limits_summary() {
     let temp_dir = create_test_project();
+    fs::create_dir_all(temp_dir.path().join("deep/er")).unwrap();
+    fs::write(temp_dir.path().join("deep/er/buried.py"), "x = 1\n").unwrap();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path()).arg("--token-budget").arg("1000");
-
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .args(["--max-depth", "1", "--max-total-files", "1"]);
     cmd.assert()
         .success()
-        .stderr(predicate::str::contains("Budget"));
+        .stdout(predicate::str::contains("buried.py").not())
+        .stderr(predicate::str::contains(
+            "Warning: walk limits reached, skipped 1 path deeper than max depth 1 and",
+        ))
+        .stderr(predicate::str::contains("past the 1-file limit"));
 }
 
 #[test]
-fn test_token_budget_shorthand() {
+fn test_show_excluded() {
     let temp_dir = create_test_project();
+    fs::write(temp_dir.path().join("debug.log"), "log line\n").unwrap();
 
-    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
-    cmd.arg(temp_dir.path()).arg("--token-budget").arg("1k");
-
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .args(["--exclude", "*.log", "--show-excluded", "--porcelain"]);
     cmd.assert()
         .success()
-        .stderr(predicate::str::contains("Budget"));
+        .stderr(predicate::str::contains(
+            "excluded\tdebug.log\tpattern\tignore pattern '*.log'",
+        ))
+        .stdout(predicate::str::contains("debug.log").not());
 }
 
-// ============================================================================
+#[test]
+fn test_generated_files() {
+    let temp_dir = create_test_project();
+    let vendor = temp_dir.path().join("vendor/lib");
+    fs::create_dir_all(&vendor).unwrap();
+    fs::write(vendor.join("dep.py"), "def vendored_fn():\n    return 1\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .args(["--generated", "skip", "--truncate-stats", "--porcelain"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "generated\tvendor/lib/dep.py\tvendored\tin vendor/\tskip",
+        ))
+        .stdout(predicate::str::contains("vendored_fn").not());
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).args(["--generated", "structure"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("def vendored_fn():"))
+        .stdout(predicate::str::contains("return 1").not());
+}
+
+#[test]
+fn test_minified_assets_stubbed() {
+    let temp_dir = create_test_project();
+    let bundle = "!function(e){var t={};function n(r){return t[r]}n.m=e}([]);".repeat(2000);
+    fs::write(temp_dir.path().join("bundle.js"), bundle).unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .args(["--truncate-stats", "--porcelain"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "minified\tbundle.js\t118000\t1\t118000",
+        ))
+        .stdout(predicate::str::contains(
+            "[minified: 115.2KB in 1 lines, avg 118000 chars/line]",
+        ))
+        .stdout(predicate::str::contains("n.m=e").not());
+}
+
+#[test]
+fn test_binary_stubs() {
+    let temp_dir = create_test_project();
+    fs::write(
+        temp_dir.path().join("icon.png"),
+        [0x89, b'P', b'N', b'G', 0, 0],
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("icon.png").not());
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).args(["--binary", "stub"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[binary: 6B, image/png]"));
+}
+
+#[test]
+fn test_exclude_content() {
+    let temp_dir = create_test_project();
+    fs::write(
+        temp_dir.path().join("secrets_fixture.py"),
+        "# PM_ENCODER: SKIP\nTOKEN = 'hunter2'\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .args(["--exclude-content", "PM_ENCODER: SKIP"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hunter2").not());
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).args(["--exclude-content", "("]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_doctor() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--doctor");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("grammars"))
+        .stdout(predicate::str::contains("0 failures"));
+
+    fs::write(temp_dir.path().join(".pm_encoder_config.json"), "{oops").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--doctor");
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains("FAIL config"))
+        .stdout(predicate::str::contains("fix: fix the JSON"));
+}
+
+#[test]
+fn test_migrate_state_files() {
+    let temp_dir = create_test_project();
+    let store = temp_dir.path().join(".pm_encoder/context_store.json");
+    fs::create_dir_all(store.parent().unwrap()).unwrap();
+    fs::write(&store, r#"{"files": {}}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--migrate").arg("--dry-run");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("would migrate legacy -> 2.0.0"));
+    assert_eq!(fs::read_to_string(&store).unwrap(), r#"{"files": {}}"#);
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--migrate");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("migrated legacy -> 2.0.0"));
+    assert!(fs::read_to_string(&store).unwrap().contains("\"2.0.0\""));
+    assert!(temp_dir
+        .path()
+        .join(".pm_encoder/context_store.json.legacy.bak")
+        .exists());
+
+    // --dry-run only makes sense with --decode or --migrate
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--dry-run");
+    cmd.assert().code(5);
+}
+
+// /proc/self/mem fails to read even as root, so it stands in for a file
+// the walk can't read
+#[cfg(target_os = "linux")]
+#[test]
+fn test_strict_fails_on_files_the_walk_cannot_read() {
+    let temp_dir = create_test_project();
+    std::os::unix::fs::symlink("/proc/self/mem", temp_dir.path().join("mem.py")).unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: skipped mem.py"));
+
+    // Batch output is withheld
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--strict");
+    cmd.assert()
+        .code(2)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("Error: cannot read mem.py"));
+
+    // Streamed output is already out when the run fails
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--strict").arg("--stream");
+    cmd.assert()
+        .code(2)
+        .stdout(predicate::str::contains("++++++++++ main.py"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_non_utf8_project_root_is_rejected() {
+    use std::os::unix::ffi::OsStrExt;
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9"));
+    fs::create_dir(&root).unwrap();
+    fs::write(root.join("main.py"), "x = 1\n").unwrap();
+
+    for args in [
+        vec![],
+        vec!["--survey", "health"],
+        vec!["--stats"],
+        vec!["--stream"],
+    ] {
+        Command::cargo_bin("vo")
+            .unwrap()
+            .arg(&root)
+            .args(args)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("path is not valid UTF-8"));
+    }
+}
+
+#[test]
+fn test_strict_exit_codes() {
+    let temp_dir = create_test_project();
+
+    // Dropping files to fit the budget only fails under --strict
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--token-budget").arg("10");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--token-budget")
+        .arg("10")
+        .arg("--strict");
+    cmd.assert().code(3);
+
+    // A broken config is a warning, or exit 4 under --strict
+    fs::write(temp_dir.path().join(".pm_encoder_config.json"), "{not json").unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Could not load config file"));
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--strict");
+    cmd.assert().code(4);
+
+    // Bad arguments don't collide with the partial-run code
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg("--no-such-flag");
+    cmd.assert().code(5);
+}
+
+#[test]
+fn test_locate_excerpt() {
+    let temp_dir = create_test_project();
+    let out_dir = TempDir::new().unwrap();
+    let context = out_dir.path().join("context.txt");
+    let excerpt = out_dir.path().join("quote.txt");
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("-o").arg(&context);
+    cmd.assert().success();
+
+    fs::write(
+        &excerpt,
+        "def main():\n    \"\"\"Entry point for the application.\"\"\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg("--locate")
+        .arg(&context)
+        .arg("--excerpt-file")
+        .arg(&excerpt);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("main.py:7-8"));
+}
+
+#[test]
+fn test_check_claims() {
+    let temp_dir = create_test_project();
+    let claims_dir = TempDir::new().unwrap();
+    let claims = claims_dir.path().join("claims.json");
+
+    fs::write(
+        &claims,
+        r#"[{"path": "main.py", "symbol": "main", "line": 7}, {"symbol": "Calculator"}]"#,
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--check-claims").arg(&claims);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("2 checked, 0 fabricated"));
+
+    fs::write(
+        &claims,
+        r#"[{"path": "parser.py"}, {"path": "main.py", "symbol": "multiply"}]"#,
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--check-claims").arg(&claims);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("missing path"))
+        .stdout(predicate::str::contains("missing symbol"));
+}
+
+// ============================================================================
+// Zoom Tests
+// ============================================================================
+
+#[test]
+fn test_zoom_file_basic() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path()).arg("--zoom").arg("file=main.py");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("main.py"))
+        .stdout(predicate::str::contains("def main()"));
+}
+
+#[test]
+fn test_zoom_file_with_line_range() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--zoom")
+        .arg("file=main.py:1-10");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("main.py"))
+        .stdout(predicate::str::contains("Main module"))
+        // Line 1-10 should include imports but not the Calculator class (which starts around line 15)
+        .stdout(predicate::str::contains("import"));
+}
+
+#[test]
+fn test_zoom_function() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path()).arg("--zoom").arg("fn=main");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("def main()"));
+}
+
+#[test]
+fn test_zoom_class() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--zoom")
+        .arg("class=Calculator");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("class Calculator"));
+}
+
+// ============================================================================
+// Zoom Error Handling Tests
+// ============================================================================
+
+#[test]
+fn test_zoom_invalid_target_type() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--zoom")
+        .arg("invalid=something");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown zoom type"))
+        .stderr(predicate::str::contains("fn, class, mod, file"));
+}
+
+#[test]
+fn test_zoom_malformed_target() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--zoom")
+        .arg("notavalidformat");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid zoom format"))
+        .stderr(predicate::str::contains("Expected <TYPE>=<TARGET>"));
+}
+
+#[test]
+fn test_zoom_nonexistent_target() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--zoom")
+        .arg("fn=nonexistent_function_xyz");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Symbol resolution failed"))
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_zoom_invalid_line_range() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--zoom")
+        .arg("file=main.py:abc-def");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid"));
+}
+
+// ============================================================================
+// Truncation Tests
+// ============================================================================
+
+#[test]
+fn test_truncation_with_zoom_affordance() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path()).arg("--truncate").arg("5");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("TRUNCATED"))
+        .stdout(predicate::str::contains("ZOOM_AFFORDANCE"));
+}
+
+#[test]
+fn test_truncation_no_summary() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--truncate")
+        .arg("5")
+        .arg("--no-truncate-summary");
+
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    // Files should still be present
+    assert!(stdout.contains("main.py") || stdout.contains("lib.rs"));
+    // With --no-truncate-summary, we should have fewer "TRUNCATED" markers or
+    // reduced detail. The [TRUNCATED: X lines] header should still appear
+    // but the detailed block may be suppressed.
+}
+
+// ============================================================================
+// Help and Version Tests
+// ============================================================================
+
+#[test]
+fn test_help_output() {
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--zoom"))
+        .stdout(predicate::str::contains("--frozen"))
+        .stdout(predicate::str::contains("--format"))
+        .stdout(predicate::str::contains("--truncate"))
+        .stdout(predicate::str::contains("--lens"));
+}
+
+#[test]
+fn test_version_output() {
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg("--version");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("1.0.0"));
+}
+
+// ============================================================================
+// Error Handling Tests
+// ============================================================================
+
+#[test]
+fn test_missing_project_root() {
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    // No arguments - should fail with an error about missing PATH
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("PATH").or(predicate::str::contains("PROJECT_ROOT")));
+}
+
+#[test]
+fn test_nonexistent_directory() {
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg("/nonexistent/path/that/does/not/exist");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("does not exist"));
+}
+
+#[test]
+fn test_invalid_format() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--format")
+        .arg("invalid_format");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+// ============================================================================
+// Token Budget Tests
+// ============================================================================
+
+#[test]
+fn test_token_budget_basic() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path()).arg("--token-budget").arg("1000");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Budget"));
+}
+
+#[test]
+fn test_token_budget_shorthand() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path()).arg("--token-budget").arg("1k");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Budget"));
+}
+
+#[test]
+fn test_budget_report_color() {
+    let temp_dir = create_test_project();
+
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path())
+        .arg("--token-budget")
+        .arg("1k")
+        .arg("--color")
+        .arg("always");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "\x1b[1;36mTOKEN BUDGET REPORT\x1b[0m",
+    ));
+
+    // Piped stderr stays plain under the default `auto`
+    let mut cmd = Command::cargo_bin("pm_encoder").unwrap();
+    cmd.arg(temp_dir.path()).arg("--token-budget").arg("1k");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("\x1b[").not());
+}
+
+// ============================================================================
 // Lens Tests
 // ============================================================================
 
@@ -556,19 +1461,263 @@ fn test_sort_by_name_desc() {
         .arg("--sort-order")
         .arg("desc");
 
-    let output = cmd.output().unwrap();
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // main.py should come before lib.rs which comes before config.json
+    let config_pos = stdout.find("config.json");
+    let lib_pos = stdout.find("lib.rs");
+    let main_pos = stdout.find("main.py");
+
+    assert!(config_pos.is_some());
+    assert!(lib_pos.is_some());
+    assert!(main_pos.is_some());
+    assert!(main_pos < lib_pos);
+    assert!(lib_pos < config_pos);
+}
+
+#[test]
+fn test_sort_by_utility() {
+    let temp_dir = create_test_project();
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--report-utility")
+        .arg("main.py:1.0:entry point")
+        .assert()
+        .success();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--sort-by")
+        .arg("utility")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // The reported file comes first, the rest in path order
+    let main_pos = stdout.find("main.py").unwrap();
+    let config_pos = stdout.find("config.json").unwrap();
+    let lib_pos = stdout.find("lib.rs").unwrap();
+    assert!(main_pos < config_pos);
+    assert!(config_pos < lib_pos);
+}
+
+#[test]
+fn test_stream_order_priority() {
+    let temp_dir = create_test_project();
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--report-utility")
+        .arg("main.py:1.0:entry point")
+        .assert()
+        .success();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--stream")
+        .arg("--stream-order")
+        .arg("priority")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // The most useful file streams first, the rest in path order
+    let main_pos = stdout.find("main.py").unwrap();
+    let config_pos = stdout.find("config.json").unwrap();
+    let lib_pos = stdout.find("lib.rs").unwrap();
+    assert!(main_pos < config_pos);
+    assert!(config_pos < lib_pos);
+
+    // The order only applies to streaming
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--stream-order")
+        .arg("priority")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_stream_stops_at_token_budget() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.txt"), "first\n").unwrap();
+    fs::write(temp_dir.path().join("b.txt"), "x".repeat(4000)).unwrap();
+    fs::write(temp_dir.path().join("c.txt"), "third\n").unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--stream", "--stream-order", "priority"])
+        .args(["--token-budget", "200", "--porcelain"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Writing stops at the first file that doesn't fit, in path order here;
+    // the rest is omitted
+    assert!(stdout.contains("a.txt"));
+    assert!(!stdout.contains("b.txt"));
+    assert!(!stdout.contains("c.txt"));
+    let records: Vec<Vec<&str>> = stderr.lines().map(|l| l.split('\t').collect()).collect();
+    assert!(records
+        .iter()
+        .any(|r| r[..] == ["budget", r[1], "200", "stream"]));
+    let dropped: Vec<&str> = records
+        .iter()
+        .filter(|r| r[0] == "dropped")
+        .map(|r| r[1])
+        .collect();
+    assert_eq!(dropped, ["b.txt", "c.txt"]);
+
+    // The narrated report doesn't claim a priority order streaming never used
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--stream", "--token-budget", "200"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("(in stream order)"))
+        .stderr(predicate::str::contains("lowest priority first").not());
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--stream", "--token-budget", "200", "--strict", "--quiet"])
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn test_split_roles() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    fs::create_dir(&src).unwrap();
+    fs::write(temp_dir.path().join("README.md"), "# Shop\n").unwrap();
+    fs::write(temp_dir.path().join(".pmattributes"), "README.md pin\n").unwrap();
+    fs::write(src.join("cart.py"), "def total():\n    return 0\n").unwrap();
+    fs::write(src.join("order.py"), "def place():\n    pass\n").unwrap();
+    let out_dir = TempDir::new().unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--split", "roles", "--zoom", "fn=place", "--porcelain"])
+        .arg("-o")
+        .arg(out_dir.path().join("context.txt"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("section\tsystem\t"));
+    assert!(stderr.contains("section\tuser\t"));
+
+    // The tree, index and pinned docs are stable; the zoomed file is the turn's
+    let system = fs::read_to_string(out_dir.path().join("context.system.txt")).unwrap();
+    let user = fs::read_to_string(out_dir.path().join("context.user.txt")).unwrap();
+    assert!(system.contains("│   ├── cart.py"));
+    assert!(system.contains("  src/order.py  ~"));
+    assert!(system.contains("++++++++++ README.md"));
+    assert!(!system.contains("def place"));
+    assert!(user.contains("++++++++++ src/order.py"));
+    assert!(!user.contains("cart.py"));
+}
+
+#[test]
+fn test_truncate_mode_tokens() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.min.js"),
+        format!("var a={};", "1".repeat(8000)),
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate", "200", "--truncate-mode", "tokens"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("tokens omitted] ..."));
+    assert!(stdout.contains("TOKEN TRUNCATED: kept ~200/2001 tokens"));
+    assert!(stdout.len() < 2000);
+}
+
+#[test]
+fn test_strip_comments() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.py"),
+        "# Licensed under MIT\n# see LICENSE\nimport os\n\ndef main():\n    # say hi\n    print('hi')\n",
+    )
+    .unwrap();
+
+    for args in [
+        vec!["--truncate-mode", "strip-comments"],
+        vec![
+            "--strip-comments",
+            "--truncate",
+            "100",
+            "--truncate-mode",
+            "smart",
+        ],
+    ] {
+        let output = Command::cargo_bin("vo")
+            .unwrap()
+            .arg(temp_dir.path())
+            .args(&args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("import os\n\ndef main():\n    print('hi')\n"));
+        assert!(!stdout.contains("Licensed"));
+        assert!(!stdout.contains("say hi"));
+    }
+}
 
-    // main.py should come before lib.rs which comes before config.json
-    let config_pos = stdout.find("config.json");
-    let lib_pos = stdout.find("lib.rs");
-    let main_pos = stdout.find("main.py");
+#[test]
+fn test_init_llm_readme() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("README.md"),
+        "# Shop\n\nSells things.\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("main.py"),
+        "if __name__ == '__main__':\n    pass\n",
+    )
+    .unwrap();
 
-    assert!(config_pos.is_some());
-    assert!(lib_pos.is_some());
-    assert!(main_pos.is_some());
-    assert!(main_pos < lib_pos);
-    assert!(lib_pos < config_pos);
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--init-llm-readme", "--porcelain"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("generated\t") && stderr.contains("README.llm.md"));
+
+    let readme = fs::read_to_string(temp_dir.path().join("README.llm.md")).unwrap();
+    assert!(readme.contains("Sells things."));
+    assert!(readme.contains("- `main.py` (__main__ block)"));
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join(".pmattributes")).unwrap(),
+        "README.llm.md pin\n"
+    );
 }
 
 // ============================================================================
@@ -668,3 +1817,686 @@ fn test_vo_and_pm_encoder_produce_same_output() {
         "vo and pm_encoder should produce identical output"
     );
 }
+
+// ============================================================================
+// SARIF Ingestion Tests
+// ============================================================================
+
+#[test]
+fn test_sarif_findings_bundle() {
+    let temp_dir = create_test_project();
+    let sarif_path = temp_dir.path().join("results.sarif");
+    fs::write(
+        &sarif_path,
+        r#"{"version": "2.1.0", "runs": [{
+            "tool": {"driver": {"name": "pylint", "rules": [
+                {"id": "W0611", "shortDescription": {"text": "Unused import"}}
+            ]}},
+            "results": [{"ruleId": "W0611", "level": "warning",
+                "message": {"text": "Unused import os"},
+                "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": "main.py"},
+                    "region": {"startLine": 4}}}]}]
+        }]}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("vo").unwrap();
+    cmd.arg(temp_dir.path()).arg("--sarif").arg(&sarif_path);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("## WARNING (1)"))
+        .stdout(predicate::str::contains("# Description: Unused import"))
+        .stdout(predicate::str::contains(">    4 | import os"));
+}
+
+#[test]
+fn test_survey_census_sarif_output() {
+    let temp = TempDir::new().unwrap();
+    std::fs::write(
+        temp.path().join("lib.rs"),
+        "/// Adds numbers\npub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n",
+    )
+    .unwrap();
+    let sarif_path = temp.path().join("census.sarif");
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp.path())
+        .arg("--survey")
+        .arg("composition")
+        .arg("--census-sarif")
+        .arg(&sarif_path)
+        .assert()
+        .success();
+
+    let sarif = std::fs::read_to_string(&sarif_path).unwrap();
+    assert!(sarif.contains("\"version\": \"2.1.0\""));
+    assert!(sarif.contains("voyager-observatory"));
+}
+
+#[test]
+fn test_survey_custom_declaration_kinds() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("shop")).unwrap();
+    std::fs::write(
+        temp.path().join("shop/views.py"),
+        "def index(request):\n    return 1\n\ndef detail(request, pk):\n    return pk\n\ndef helper(x):\n    return x\n",
+    )
+    .unwrap();
+    std::fs::write(
+        temp.path().join(".pm_encoder_config.json"),
+        r#"{"declaration_kinds": [{"kind": "django:view", "base": "function", "parameter": "request"}]}"#,
+    )
+    .unwrap();
+
+    let survey = |kinds: Option<&str>| {
+        let mut cmd = Command::cargo_bin("vo").unwrap();
+        cmd.arg(temp.path()).arg("--survey").arg("composition");
+        if let Some(kinds) = kinds {
+            cmd.arg("--census-kinds").arg(kinds);
+        }
+        cmd.assert()
+    };
+    survey(None)
+        .success()
+        .stdout(predicate::str::contains("Custom Kinds:    2 django:view"));
+    survey(Some("django:*"))
+        .success()
+        .stdout(predicate::str::contains("Stellar Density: 250.0"));
+    survey(Some("widget"))
+        .code(5)
+        .stderr(predicate::str::contains(
+            "'widget' is not a declaration kind",
+        ));
+}
+
+#[cfg(feature = "temporal")]
+#[test]
+fn test_survey_without_git_uses_file_timestamps() {
+    let temp = TempDir::new().unwrap();
+    std::fs::write(temp.path().join("lib.rs"), "pub fn add() {}\n").unwrap();
+    let in_repo = std::process::Command::new("git")
+        .arg("-C")
+        .arg(temp.path())
+        .args(["rev-parse", "--git-dir"])
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if in_repo {
+        // The temp directory sits inside a repository
+        return;
+    }
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp.path())
+        .arg("--survey")
+        .arg("evolution")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Temporal source: file timestamps of 1 files",
+        ))
+        .stderr(predicate::str::contains("dated by file timestamps"));
+}
+
+#[test]
+fn test_callgraph_export_mermaid() {
+    let temp = TempDir::new().unwrap();
+    std::fs::write(
+        temp.path().join("lib.rs"),
+        "pub fn run() {\n    load();\n}\n\nfn load() {\n    parse();\n}\n\nfn parse() {\n}\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp.path())
+        .arg("--callgraph-export")
+        .arg("mermaid")
+        .arg("--callgraph-root")
+        .arg("run")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("flowchart LR"))
+        .stdout(predicate::str::contains("-->"))
+        .stdout(predicate::str::contains("parse"));
+}
+
+#[test]
+fn test_callgraph_export_requires_root() {
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(".")
+        .arg("--callgraph-export")
+        .arg("dot")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_manifest_sidecar_written_next_to_output() {
+    let temp = TempDir::new().unwrap();
+    let project = temp.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("main.rs"), "fn main() {}\n").unwrap();
+    let output = temp.path().join("context.txt");
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(&project)
+        .arg("-o")
+        .arg(&output)
+        .arg("--manifest")
+        .assert()
+        .success();
+
+    let manifest: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(temp.path().join("context.txt.manifest.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(manifest["files"][0]["path"], "main.rs");
+    assert_eq!(manifest["files"][0]["truncation"], "none");
+    assert!(manifest["files"][0]["md5"].is_string());
+    assert!(manifest["total_tokens"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_decode_round_trips_plus_minus_output() {
+    let temp = TempDir::new().unwrap();
+    let project = temp.path().join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(project.join("src/main.rs"), "fn main() {}\n").unwrap();
+    let output = temp.path().join("context.txt");
+    let restored = temp.path().join("restored");
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(&project)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg("--decode")
+        .arg(&output)
+        .arg(&restored)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 verified"));
+
+    assert_eq!(
+        fs::read_to_string(restored.join("src/main.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+}
+
+#[test]
+fn test_decode_writes_into_output_dir() {
+    let temp = TempDir::new().unwrap();
+    let project = temp.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("lib.rs"), "pub fn f() {}\n").unwrap();
+    let output = temp.path().join("context.txt");
+    let restored = temp.path().join("restored");
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(&project)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+
+    Command::cargo_bin("vo")
+        .unwrap()
+        .current_dir(temp.path())
+        .arg("--decode")
+        .arg(&output)
+        .arg("-o")
+        .arg(&restored)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 verified"));
+    assert_eq!(
+        fs::read_to_string(restored.join("lib.rs")).unwrap(),
+        "pub fn f() {}\n"
+    );
+    assert!(!temp.path().join("lib.rs").exists());
+
+    // Two target directories are a usage error
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg("--decode")
+        .arg(&output)
+        .arg(&restored)
+        .arg("-o")
+        .arg(temp.path().join("other"))
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn test_glossary() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("post.py"),
+        "# Post a PLM journal\ndef post_journal(ledger):\n    ledger.append(plm_fetch())\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("audit.py"),
+        "# Audit the PLM journal\ndef audit(ledger):\n    return ledger.journal\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--glossary", "--format", "markdown"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let glossary = &stdout[stdout.find("## Glossary").unwrap()..];
+    assert!(glossary.contains("- **ledger** (term, 4 uses in 2 files)\n"));
+    assert!(glossary.contains("- **journal** (term, 4 uses in 2 files): `post_journal`\n"));
+    assert!(glossary.contains("- **PLM** (acronym, 2 uses in 2 files)\n"));
+}
+
+#[test]
+fn test_truncate_mode_outline() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("ledger.py"),
+        "\"\"\"Ledger postings.\"\"\"\nimport os\n\n\ndef post(amount):\n    \"\"\"Post `amount`.\"\"\"\n    return amount\n\n\ndef _check():\n    return True\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate-mode", "outline"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "\"\"\"Ledger postings.\"\"\"\nimport os\ndef post(amount):\n    \"\"\"Post `amount`.\"\"\"\n"
+    ));
+    assert!(stdout.contains("OUTLINE MODE"));
+    assert!(!stdout.contains("_check"));
+}
+
+#[test]
+fn test_truncation_keeps_module_context() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut rust = String::from(
+        "//! Ledger storage.\n\nuse std::collections::{\n    BTreeMap,\n};\nuse std::fs;\n\n",
+    );
+    for i in 0..30 {
+        rust.push_str(&format!("fn step_{}() {{}}\n", i));
+    }
+    fs::write(temp_dir.path().join("ledger.rs"), rust).unwrap();
+    fs::write(temp_dir.path().join("app.log"), "started\n".repeat(30)).unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate", "2", "--truncate-mode", "tail"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "//! Ledger storage.\n\nuse std::collections::{\n    BTreeMap,\n};\nuse std::fs;\n\n\
+         ... [29 lines omitted] ...\n\nfn step_28() {}\nfn step_29() {}\n"
+    ));
+    // Logs have no module context
+    assert!(stdout.contains("++++++++++\n... [28 lines omitted] ...\n\nstarted\nstarted\n"));
+}
+
+#[test]
+fn test_merge_conflict_markers() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.py"),
+        "<<<<<<< HEAD\nTIMEOUT = 30\n=======\nTIMEOUT = 60\n>>>>>>> retry-tuning\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("clean.py"), "DEBUG = False\n").unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--health")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(" [MERGE CONFLICTS: 1] ++++++++++\n<<<<<<< HEAD\n"));
+    assert!(!stdout.contains("clean.py [MERGE"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning: 1 file contains merge conflict markers"));
+    assert!(stderr.contains("Merge Conflicts:  1 files"));
+}
+
+#[test]
+fn test_csv_sampling() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut csv = String::from("order_id,amount\n");
+    for i in 1..=200 {
+        csv.push_str(&format!("{},{}.50\n", i, i % 7));
+    }
+    fs::write(temp_dir.path().join("orders.csv"), csv).unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate", "6", "--truncate-mode", "smart"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("order_id,amount\n1,1.50\n2,2.50\n3,3.50\n4,4.50\n5,5.50\n"));
+    assert!(stdout.contains("... [195 lines omitted: 195 rows] ..."));
+    assert!(stdout.contains("  order_id: integer, 1 to 200, mean 100.50\n"));
+    assert!(stdout.contains("  amount: number, 0.5 to 6.5,"));
+    assert!(stdout.contains("TABLE SAMPLED: kept 5/200 rows"));
+}
+
+#[test]
+fn test_json_schema_outline() {
+    let temp_dir = TempDir::new().unwrap();
+    let paths: Vec<String> = (0..100)
+        .map(|i| {
+            format!(
+                "    \"/pets/{}\": {{\n      \"get\": {{\"summary\": \"Pet {}\"}}\n    }}",
+                i, i
+            )
+        })
+        .collect();
+    let spec = format!(
+        "{{\n  \"openapi\": \"3.0.0\",\n  \"paths\": {{\n{}\n  }}\n}}\n",
+        paths.join(",\n")
+    );
+    fs::write(temp_dir.path().join("openapi.json"), spec).unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate", "50", "--truncate-mode", "smart"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "... [305 lines omitted] ...\n\n\
+         Schema (object):\n  \
+         openapi: string\n  \
+         paths: object, 100 keys (\"/pets/0\", \"/pets/1\", \"/pets/2\", ...)\n    \
+         *: object\n      \
+         get: object\n        \
+         summary: string\n"
+    ));
+    assert!(stdout.contains("SCHEMA OUTLINE: 305 lines as 5 lines"));
+}
+
+#[test]
+fn test_sql_dump_keeps_ddl() {
+    let temp_dir = TempDir::new().unwrap();
+    let rows: Vec<String> = (0..500).map(|i| format!("({}, 'user{}')", i, i)).collect();
+    let dump = format!(
+        "CREATE TABLE `users` (\n  `id` int NOT NULL,\n  `name` varchar(40)\n);\n\
+         LOCK TABLES `users` WRITE;\n\
+         INSERT INTO `users` VALUES {};\n\
+         INSERT INTO `users` VALUES\n{};\n\
+         UNLOCK TABLES;\n",
+        rows.join(","),
+        rows.join(",\n")
+    );
+    fs::write(temp_dir.path().join("dump.sql"), dump).unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate-mode", "structure"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "CREATE TABLE `users` (\n  `id` int NOT NULL,\n  `name` varchar(40)\n);\n\
+         LOCK TABLES `users` WRITE;\n\
+         ... [502 lines omitted: 2 data statements, 1000 rows into users] ...\n\
+         UNLOCK TABLES;\n"
+    ));
+    assert!(stdout.contains("SQL STRUCTURE: kept 6/508 lines"));
+    assert!(!stdout.contains("user42"));
+}
+
+#[test]
+fn test_lockfiles_summarized() {
+    let temp_dir = TempDir::new().unwrap();
+    let packages: String = (0..200)
+        .map(|i| {
+            format!(
+                "[[package]]\nname = \"crate{}\"\nversion = \"0.{}.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"{:064}\"\n\n",
+                i, i, i
+            )
+        })
+        .collect();
+    fs::write(
+        temp_dir.path().join("Cargo.lock"),
+        format!("version = 3\n\n{}", packages),
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Dependencies (Cargo, 200 packages):\n  crate0 0.0.0\n  crate1 0.1.0\n")
+    );
+    assert!(stdout.contains("LOCKFILE SUMMARY: 1202 lines as 200 dependencies"));
+    assert!(!stdout.contains("checksum"));
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--lockfiles", "keep", "--truncate", "0"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("checksum"));
+}
+
+#[test]
+fn test_truncate_mode_tail() {
+    let temp_dir = TempDir::new().unwrap();
+    let log: String = (1..=50).map(|i| format!("request {}\n", i)).collect();
+    fs::write(temp_dir.path().join("server.log"), log).unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate", "5", "--truncate-mode", "tail"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("... [45 lines omitted] ...\n\nrequest 46\n"));
+    assert!(stdout.contains("request 50\n"));
+    assert!(!stdout.contains("request 45\n"));
+    assert!(stdout.contains("TRUNCATED to the last 5 of 50 lines"));
+}
+
+#[test]
+fn test_truncate_keep() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut content: String = (1..=40).map(|i| format!("step {}\n", i)).collect();
+    content.push_str("# FIXME: flaky on CI\n");
+    content.push_str("done\n");
+    fs::write(temp_dir.path().join("notes.txt"), content).unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate", "10", "--truncate-keep", "FIXME"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("step 10\n\n... [30 lines omitted] ...\n\n# FIXME: flaky on CI"));
+    assert!(!stdout.contains("step 11\n"));
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--truncate-keep", "(unclosed"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid truncate keep pattern"));
+}
+
+#[test]
+fn test_control_chars_escaped_and_validated() {
+    let temp_dir = TempDir::new().unwrap();
+    // Latin-1, with a terminal escape and a C1 control
+    fs::write(
+        temp_dir.path().join("log.txt"),
+        b"caf\xe9 \x1b[1mok\x1b[0m \x85\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .arg("--validate-output")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("café \\x1b[1mok\\x1b[0m \\x85\n"));
+
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg(temp_dir.path())
+        .args(["--validate-output", "--keep-control-chars"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("invalid output: line 2, column 6: control character U+001B"));
+}
+
+#[test]
+fn test_concat_and_append() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("project");
+    fs::create_dir(&project).unwrap();
+    fs::write(project.join("a.txt"), "one\n").unwrap();
+    fs::write(project.join("b.txt"), "two\n").unwrap();
+    let first = temp_dir.path().join("first.ctx");
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(&project)
+        .arg("-o")
+        .arg(&first)
+        .arg("--manifest")
+        .assert()
+        .success();
+
+    fs::write(project.join("a.txt"), "uno\n").unwrap();
+    fs::remove_file(project.join("b.txt")).unwrap();
+    let second = temp_dir.path().join("second.ctx");
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(&project)
+        .arg("-o")
+        .arg(&second)
+        .assert()
+        .success();
+
+    let merged = temp_dir.path().join("merged.ctx");
+    let output = Command::cargo_bin("vo")
+        .unwrap()
+        .arg("--concat")
+        .arg(&first)
+        .arg(&second)
+        .arg("-o")
+        .arg(&merged)
+        .arg("--porcelain")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("concat\t2\t2\t0\t1\n"));
+
+    let content = fs::read_to_string(&merged).unwrap();
+    assert!(content.contains("uno\n"));
+    assert!(!content.contains("one\n"));
+    assert!(content.find("a.txt").unwrap() < content.find("b.txt").unwrap());
+    let manifest = fs::read_to_string(temp_dir.path().join("merged.ctx.manifest.json")).unwrap();
+    assert!(manifest.contains("\"path\": \"b.txt\""));
+
+    // Appending the same run again changes nothing
+    Command::cargo_bin("vo")
+        .unwrap()
+        .arg(&project)
+        .arg("-o")
+        .arg(&merged)
+        .arg("--append")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&merged).unwrap(), content);
+}
+
+#[test]
+fn test_server_context_matches_cli_output() {
+    let temp_dir = create_test_project();
+    fs::write(temp_dir.path().join("bell.txt"), "ring\u{1}ring\n").unwrap();
+
+    for flags in [&[][..], &["--line-numbers", "--format", "markdown"][..]] {
+        let cli = Command::cargo_bin("vo")
+            .unwrap()
+            .arg(temp_dir.path())
+            .args(flags)
+            .output()
+            .unwrap();
+        assert!(cli.status.success());
+
+        let server = Command::cargo_bin("vo")
+            .unwrap()
+            .arg("--server")
+            .arg(temp_dir.path())
+            .args(flags)
+            .write_stdin(
+                r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_context","arguments":{}}}"#
+                    .to_string()
+                    + "\n",
+            )
+            .output()
+            .unwrap();
+        let response: serde_json::Value =
+            serde_json::from_slice(server.stdout.split(|&b| b == b'\n').next().unwrap()).unwrap();
+        let context = response["result"]["content"][0]["text"].as_str().unwrap();
+
+        assert!(context.contains("ring\\x01ring"));
+        assert_eq!(
+            context,
+            String::from_utf8(cli.stdout).unwrap(),
+            "{:?}",
+            flags
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! C# Language Adapter
+//!
+//! Extracts structural information from C# source files using Tree-sitter.
+//! Supports namespaces (block and file-scoped), classes, structs, interfaces,
+//! enums, records and delegates with their methods, constructors, properties,
+//! fields and events, XML doc comments and `using` directives.
+
+use super::{
+    collect_comments, leading_doc_comment, node_text, node_to_span, signature_span,
+    AdapterCapabilities, LanguageAdapter,
+};
+use crate::ir::{
+    Block, Comment, Declaration, DeclarationKind, ImportKind, ImportLike, LanguageId, Parameter,
+    Visibility,
+};
+
+/// C# language adapter using Tree-sitter
+pub struct CSharpTreeSitterAdapter {
+    language: tree_sitter::Language,
+}
+
+impl CSharpTreeSitterAdapter {
+    /// Create a new C# adapter
+    pub fn new() -> Self {
+        Self {
+            language: tree_sitter_c_sharp::LANGUAGE.into(),
+        }
+    }
+}
+
+impl Default for CSharpTreeSitterAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageAdapter for CSharpTreeSitterAdapter {
+    fn language(&self) -> LanguageId {
+        LanguageId::CSharp
+    }
+
+    fn tree_sitter_language(&self) -> tree_sitter::Language {
+        self.language.clone()
+    }
+
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities {
+            zoom_bodies: false,
+            visibility: true,
+            doc_comments: true,
+        }
+    }
+
+    fn extract_declarations(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Declaration> {
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        root.children(&mut cursor)
+            .flat_map(|child| self.extract_member(&child, source))
+            .collect()
+    }
+
+    fn extract_imports(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<ImportLike> {
+        let mut imports = Vec::new();
+        let mut pending = vec![tree.root_node()];
+
+        // Usings sit at the top of the file or of a namespace block
+        while let Some(node) = pending.pop() {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                match child.kind() {
+                    "using_directive" => imports.extend(self.extract_using(&child, source)),
+                    "namespace_declaration" => pending.extend(child.child_by_field_name("body")),
+                    _ => {}
+                }
+            }
+        }
+
+        imports.sort_by_key(|i| i.span.start);
+        imports
+    }
+
+    fn extract_comments(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Comment> {
+        collect_comments(&tree.root_node(), source, &["///", "/**"])
+    }
+
+    fn extract_body(
+        &self,
+        _tree: &tree_sitter::Tree,
+        _source: &str,
+        _declaration: &Declaration,
+    ) -> Option<Block> {
+        None
+    }
+
+    fn extract_visibility(&self, node: &tree_sitter::Node, source: &str) -> Visibility {
+        let mut cursor = node.walk();
+        let modifiers: Vec<_> = node
+            .children(&mut cursor)
+            .filter(|c| c.kind() == "modifier")
+            .map(|m| node_text(&m, source))
+            .collect();
+        if modifiers.contains(&"public") {
+            return Visibility::Public;
+        }
+        if modifiers.contains(&"protected") {
+            return Visibility::Protected;
+        }
+        if modifiers.contains(&"internal") {
+            return Visibility::Internal;
+        }
+        if modifiers.contains(&"private") {
+            return Visibility::Private;
+        }
+
+        // Defaults: interface members are public, types outside another type
+        // internal, and type members private
+        let container = node
+            .parent()
+            .filter(|p| p.kind() == "declaration_list")
+            .and_then(|list| list.parent());
+        match container.map(|c| c.kind()) {
+            Some("interface_declaration") => Visibility::Public,
+            Some("namespace_declaration") | None => Visibility::Internal,
+            _ => Visibility::Private,
+        }
+    }
+}
+
+impl CSharpTreeSitterAdapter {
+    /// Extract the declarations a namespace or type member introduces
+    fn extract_member(&self, node: &tree_sitter::Node, source: &str) -> Vec<Declaration> {
+        let kind = match node.kind() {
+            "namespace_declaration" | "file_scoped_namespace_declaration" => {
+                DeclarationKind::Namespace
+            }
+            "class_declaration" | "record_declaration" => DeclarationKind::Class,
+            "struct_declaration" | "record_struct_declaration" => DeclarationKind::Struct,
+            "interface_declaration" => DeclarationKind::Interface,
+            "enum_declaration" => DeclarationKind::Enum,
+            "delegate_declaration" => DeclarationKind::Type,
+            "method_declaration" | "constructor_declaration" => DeclarationKind::Method,
+            "property_declaration" | "enum_member_declaration" => DeclarationKind::Variable,
+            "field_declaration" | "event_field_declaration" => {
+                return self.extract_fields(node, source);
+            }
+            _ => return Vec::new(),
+        };
+        let Some(mut decl) = self.declaration(node, kind, source) else {
+            return Vec::new();
+        };
+
+        // Properties keep their accessors to themselves
+        if kind != DeclarationKind::Variable {
+            if let Some(body) = node.child_by_field_name("body") {
+                let mut cursor = body.walk();
+                for member in body.children(&mut cursor) {
+                    decl.children.extend(self.extract_member(&member, source));
+                }
+            }
+        }
+        vec![decl]
+    }
+
+    /// A named declaration with its visibility, doc comment, signature and body
+    fn declaration(
+        &self,
+        node: &tree_sitter::Node,
+        kind: DeclarationKind,
+        source: &str,
+    ) -> Option<Declaration> {
+        let name = node_text(&node.child_by_field_name("name")?, source).to_string();
+        let mut decl = Declaration::new(name, kind, node_to_span(node));
+        decl.visibility = if kind == DeclarationKind::Namespace {
+            Visibility::Public
+        } else {
+            self.extract_visibility(node, source)
+        };
+        decl.doc_comment = self.extract_doc_comment(node, source);
+
+        // Method bodies are blocks or `=> expr`; type bodies declaration lists
+        if kind != DeclarationKind::Variable {
+            if let Some(body) = node.child_by_field_name("body") {
+                decl.signature_span = Some(signature_span(node, &body));
+                decl.body_span = Some(node_to_span(&body));
+            }
+        }
+
+        let mut cursor = node.walk();
+        let params = node.child_by_field_name("parameters").or_else(|| {
+            node.children(&mut cursor)
+                .find(|c| c.kind() == "parameter_list")
+        });
+        if let Some(params) = params {
+            decl.parameters = self.extract_parameters(&params, source);
+        }
+        decl.return_type = node
+            .child_by_field_name("returns")
+            .or_else(|| node.child_by_field_name("type"))
+            .map(|t| node_text(&t, source).to_string());
+        Some(decl)
+    }
+
+    /// One declaration per variable of a field or event field
+    fn extract_fields(&self, node: &tree_sitter::Node, source: &str) -> Vec<Declaration> {
+        let mut cursor = node.walk();
+        let Some(variables) = node
+            .children(&mut cursor)
+            .find(|c| c.kind() == "variable_declaration")
+        else {
+            return Vec::new();
+        };
+        let mut modifiers = node.walk();
+        let constant = node
+            .children(&mut modifiers)
+            .any(|c| c.kind() == "modifier" && node_text(&c, source) == "const");
+        let kind = if constant {
+            DeclarationKind::Constant
+        } else {
+            DeclarationKind::Variable
+        };
+
+        let mut inner = variables.walk();
+        variables
+            .children(&mut inner)
+            .filter(|c| c.kind() == "variable_declarator")
+            .filter_map(|d| d.child_by_field_name("name"))
+            .map(|name| {
+                let mut decl = Declaration::new(
+                    node_text(&name, source).to_string(),
+                    kind,
+                    node_to_span(node),
+                );
+                decl.visibility = self.extract_visibility(node, source);
+                decl.doc_comment = self.extract_doc_comment(node, source);
+                decl
+            })
+            .collect()
+    }
+
+    /// Extract the parameters of a parameter_list
+    fn extract_parameters(&self, list: &tree_sitter::Node, source: &str) -> Vec<Parameter> {
+        let mut cursor = list.walk();
+        list.children(&mut cursor)
+            .filter(|p| p.kind() == "parameter")
+            .filter_map(|param| {
+                let name = param.child_by_field_name("name")?;
+                Some(Parameter {
+                    name: node_text(&name, source).to_string(),
+                    type_annotation: param
+                        .child_by_field_name("type")
+                        .map(|t| node_text(&t, source).to_string()),
+                    default_value: None,
+                    span: node_to_span(&param),
+                })
+            })
+            .collect()
+    }
+
+    /// Extract a `using` directive: `using A.B;`, `using static A.B;`, `using X = A.B;`
+    fn extract_using(&self, node: &tree_sitter::Node, source: &str) -> Option<ImportLike> {
+        let alias = node.child_by_field_name("name");
+        let mut cursor = node.walk();
+        let target = node
+            .named_children(&mut cursor)
+            .filter(|c| Some(*c) != alias)
+            .last()?;
+
+        Some(ImportLike {
+            source: node_text(&target, source).to_string(),
+            kind: ImportKind::Using,
+            items: Vec::new(),
+            alias: alias.map(|a| node_text(&a, source).to_string()),
+            type_only: false,
+            span: node_to_span(node),
+        })
+    }
+
+    /// The XML doc comment (`///` lines or `/** */`) right above a declaration
+    fn extract_doc_comment(&self, node: &tree_sitter::Node, source: &str) -> Option<Comment> {
+        leading_doc_comment(node, source, |text| {
+            text.starts_with("///") || text.starts_with("/**")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> (CSharpTreeSitterAdapter, tree_sitter::Tree) {
+        let adapter = CSharpTreeSitterAdapter::new();
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&adapter.tree_sitter_language())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        (adapter, tree)
+    }
+
+    #[test]
+    fn test_extract_namespace_and_class() {
+        let source = r#"namespace Acme.Tools
+{
+    /// <summary>A widget.</summary>
+    public class Widget<T> : Base, IRun
+        where T : class
+    {
+        private int count, other;
+        public const int Limit = 3;
+        public string Name { get; set; }
+        public Widget(int a) : base() { }
+        internal async Task<int> RunAsync(
+            int a,
+            string b)
+        {
+            return await Foo(a);
+        }
+        public int Twice(int x) => x * 2;
+        void Reset() { }
+    }
+}
+"#;
+        let (adapter, tree) = parse(source);
+        let decls = adapter.extract_declarations(&tree, source);
+
+        assert_eq!(decls.len(), 1);
+        assert_eq!(decls[0].name, "Acme.Tools");
+        assert_eq!(decls[0].kind, DeclarationKind::Namespace);
+
+        let widget = &decls[0].children[0];
+        assert_eq!(widget.name, "Widget");
+        assert_eq!(widget.kind, DeclarationKind::Class);
+        assert_eq!(widget.visibility, Visibility::Public);
+        assert_eq!(
+            widget.doc_comment.as_ref().unwrap().text,
+            "<summary>A widget.</summary>"
+        );
+        let signature = widget.signature_span.unwrap();
+        assert_eq!((signature.start_line, signature.end_line), (4, 6));
+
+        let members: Vec<_> = widget
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.kind, c.visibility))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("count", DeclarationKind::Variable, Visibility::Private),
+                ("other", DeclarationKind::Variable, Visibility::Private),
+                ("Limit", DeclarationKind::Constant, Visibility::Public),
+                ("Name", DeclarationKind::Variable, Visibility::Public),
+                ("Widget", DeclarationKind::Method, Visibility::Public),
+                ("RunAsync", DeclarationKind::Method, Visibility::Internal),
+                ("Twice", DeclarationKind::Method, Visibility::Public),
+                ("Reset", DeclarationKind::Method, Visibility::Private),
+            ]
+        );
+
+        // A multi-line signature ends where the body opens
+        let run = &widget.children[5];
+        assert_eq!(run.return_type.as_deref(), Some("Task<int>"));
+        assert_eq!(run.parameters.len(), 2);
+        let signature = run.signature_span.unwrap();
+        assert_eq!(
+            source[signature.start..signature.end].trim_end(),
+            "internal async Task<int> RunAsync(\n            int a,\n            string b)"
+        );
+        // Expression-bodied members stop before the arrow
+        let twice = widget.children[6].signature_span.unwrap();
+        assert_eq!(
+            source[twice.start..twice.end].trim_end(),
+            "public int Twice(int x)"
+        );
+        assert!(widget.children[3].signature_span.is_none());
+    }
+
+    #[test]
+    fn test_extract_file_scoped_namespace_and_types() {
+        let source = "namespace Acme;\n\ninterface IShape { double Area(); }\nstruct Point { public int X; }\nenum Color { Red, Green }\nrecord Person(string Name);\npublic delegate void Callback(int x);\n";
+        let (adapter, tree) = parse(source);
+        let decls = adapter.extract_declarations(&tree, source);
+
+        let names: Vec<_> = decls.iter().map(|d| (d.name.as_str(), d.kind)).collect();
+        assert_eq!(
+            names,
+            [
+                ("Acme", DeclarationKind::Namespace),
+                ("IShape", DeclarationKind::Interface),
+                ("Point", DeclarationKind::Struct),
+                ("Color", DeclarationKind::Enum),
+                ("Person", DeclarationKind::Class),
+                ("Callback", DeclarationKind::Type),
+            ]
+        );
+        assert_eq!(decls[1].visibility, Visibility::Internal);
+        assert_eq!(decls[1].children[0].visibility, Visibility::Public);
+        let colors: Vec<_> = decls[3].children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(colors, ["Red", "Green"]);
+        assert_eq!(decls[4].parameters.len(), 1);
+    }
+
+    #[test]
+    fn test_extract_usings_and_comments() {
+        let source = "using System;\nusing IO = System.IO;\nusing static System.Math;\n\nnamespace A\n{\n    using System.Linq;\n    // Line\n    /// Doc\n    class B {}\n}\n";
+        let (adapter, tree) = parse(source);
+
+        let imports = adapter.extract_imports(&tree, source);
+        let sources: Vec<_> = imports.iter().map(|i| i.source.as_str()).collect();
+        assert_eq!(
+            sources,
+            ["System", "System.IO", "System.Math", "System.Linq"]
+        );
+        assert_eq!(imports[1].alias.as_deref(), Some("IO"));
+
+        let comments = adapter.extract_comments(&tree, source);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].kind, crate::ir::CommentKind::Line);
+        assert_eq!(comments[1].kind, crate::ir::CommentKind::Doc);
+        assert_eq!(comments[1].text, "Doc");
+    }
+}
@@ -0,0 +1,478 @@
+//! Go Language Adapter
+//!
+//! Extracts structural information from Go source files using Tree-sitter.
+//! Supports functions, methods, struct and interface types, type aliases,
+//! constants, variables and imports. Exported (capitalized) names are public.
+
+use super::{
+    collect_comments, find_child_by_kind, leading_doc_comment, node_text, node_to_span,
+    signature_span, AdapterCapabilities, LanguageAdapter,
+};
+use crate::ir::{
+    Block, Comment, Declaration, DeclarationKind, ImportKind, ImportLike, LanguageId, Parameter,
+    Visibility,
+};
+
+/// Go language adapter using Tree-sitter
+pub struct GoTreeSitterAdapter {
+    language: tree_sitter::Language,
+}
+
+impl GoTreeSitterAdapter {
+    /// Create a new Go adapter
+    pub fn new() -> Self {
+        Self {
+            language: tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+impl Default for GoTreeSitterAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageAdapter for GoTreeSitterAdapter {
+    fn language(&self) -> LanguageId {
+        LanguageId::Go
+    }
+
+    fn tree_sitter_language(&self) -> tree_sitter::Language {
+        self.language.clone()
+    }
+
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities {
+            zoom_bodies: false,
+            visibility: true,
+            doc_comments: true,
+        }
+    }
+
+    fn extract_declarations(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Declaration> {
+        let mut declarations = Vec::new();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            match child.kind() {
+                "function_declaration" | "method_declaration" => {
+                    declarations.extend(self.extract_function(&child, source));
+                }
+                "type_declaration" => declarations.extend(self.extract_types(&child, source)),
+                "const_declaration" => declarations.extend(self.extract_values(
+                    &child,
+                    source,
+                    DeclarationKind::Constant,
+                )),
+                "var_declaration" => declarations.extend(self.extract_values(
+                    &child,
+                    source,
+                    DeclarationKind::Variable,
+                )),
+                _ => {}
+            }
+        }
+
+        declarations
+    }
+
+    fn extract_imports(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<ImportLike> {
+        let mut imports = Vec::new();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+
+        for declaration in root.children(&mut cursor) {
+            if declaration.kind() != "import_declaration" {
+                continue;
+            }
+            // `import "x"` or a parenthesized import_spec_list
+            let mut specs = Vec::new();
+            let mut inner = declaration.walk();
+            for child in declaration.children(&mut inner) {
+                match child.kind() {
+                    "import_spec" => specs.push(child),
+                    "import_spec_list" => {
+                        let mut list = child.walk();
+                        specs.extend(
+                            child
+                                .children(&mut list)
+                                .filter(|c| c.kind() == "import_spec"),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            for spec in specs {
+                let Some(path) = spec.child_by_field_name("path") else {
+                    continue;
+                };
+                imports.push(ImportLike {
+                    source: node_text(&path, source)
+                        .trim_matches(['"', '`'])
+                        .to_string(),
+                    kind: ImportKind::Import,
+                    items: Vec::new(),
+                    alias: spec
+                        .child_by_field_name("name")
+                        .map(|n| node_text(&n, source).to_string()),
+                    type_only: false,
+                    span: node_to_span(&spec),
+                });
+            }
+        }
+
+        imports
+    }
+
+    fn extract_comments(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Comment> {
+        // Go has no doc comment syntax; docs are plain comments above a declaration
+        collect_comments(&tree.root_node(), source, &[])
+    }
+
+    fn extract_body(
+        &self,
+        _tree: &tree_sitter::Tree,
+        _source: &str,
+        _declaration: &Declaration,
+    ) -> Option<Block> {
+        None
+    }
+
+    fn extract_visibility(&self, node: &tree_sitter::Node, source: &str) -> Visibility {
+        match node.child_by_field_name("name") {
+            Some(name) => exported(node_text(&name, source)),
+            None => Visibility::Unknown,
+        }
+    }
+}
+
+impl GoTreeSitterAdapter {
+    /// Extract a function or method
+    fn extract_function(&self, node: &tree_sitter::Node, source: &str) -> Option<Declaration> {
+        let name = node_text(&node.child_by_field_name("name")?, source).to_string();
+        let kind = if node.kind() == "method_declaration" {
+            DeclarationKind::Method
+        } else {
+            DeclarationKind::Function
+        };
+
+        let mut decl = Declaration::new(name, kind, node_to_span(node));
+        decl.visibility = self.extract_visibility(node, source);
+        decl.doc_comment = leading_doc_comment(node, source, |_| true);
+        if let Some(body) = node.child_by_field_name("body") {
+            decl.signature_span = Some(signature_span(node, &body));
+            decl.body_span = Some(node_to_span(&body));
+        }
+        if let Some(params) = node.child_by_field_name("parameters") {
+            decl.parameters = self.extract_parameters(&params, source);
+        }
+        decl.return_type = node
+            .child_by_field_name("result")
+            .map(|r| node_text(&r, source).to_string());
+
+        // The receiver's type, without the pointer: `func (s *Server)` -> Server
+        if let Some(receiver) = node.child_by_field_name("receiver") {
+            if let Some(ty) = find_child_by_kind(&receiver, "parameter_declaration")
+                .and_then(|p| p.child_by_field_name("type"))
+            {
+                let ty = node_text(&ty, source).trim_start_matches('*');
+                decl.metadata.insert("receiver".to_string(), ty.to_string());
+            }
+        }
+
+        Some(decl)
+    }
+
+    /// Extract the parameters of a parameter_list (`a, b int` gives two)
+    fn extract_parameters(&self, list: &tree_sitter::Node, source: &str) -> Vec<Parameter> {
+        let mut params = Vec::new();
+        let mut cursor = list.walk();
+        for param in list.children(&mut cursor) {
+            if !matches!(
+                param.kind(),
+                "parameter_declaration" | "variadic_parameter_declaration"
+            ) {
+                continue;
+            }
+            let type_annotation = param
+                .child_by_field_name("type")
+                .map(|t| node_text(&t, source).to_string());
+            let mut names = param.walk();
+            for name in param.children_by_field_name("name", &mut names) {
+                params.push(Parameter {
+                    name: node_text(&name, source).to_string(),
+                    type_annotation: type_annotation.clone(),
+                    default_value: None,
+                    span: node_to_span(&param),
+                });
+            }
+        }
+        params
+    }
+
+    /// Extract the types of a `type` declaration, one per spec
+    fn extract_types(&self, node: &tree_sitter::Node, source: &str) -> Vec<Declaration> {
+        let mut cursor = node.walk();
+        let specs: Vec<_> = node
+            .children(&mut cursor)
+            .filter(|c| matches!(c.kind(), "type_spec" | "type_alias"))
+            .collect();
+        // A single spec takes the whole declaration, `type` keyword and docs included
+        let single = specs.len() == 1;
+
+        specs
+            .iter()
+            .filter_map(|spec| {
+                let outer = if single { node } else { spec };
+                let name = node_text(&spec.child_by_field_name("name")?, source).to_string();
+                let ty = spec.child_by_field_name("type");
+                let kind = match ty.map(|t| t.kind()) {
+                    Some("struct_type") => DeclarationKind::Struct,
+                    Some("interface_type") => DeclarationKind::Interface,
+                    _ => DeclarationKind::Type,
+                };
+
+                let mut decl = Declaration::new(name, kind, node_to_span(outer));
+                decl.visibility = self.extract_visibility(spec, source);
+                decl.doc_comment = leading_doc_comment(outer, source, |_| true);
+
+                if let Some(ty) = ty {
+                    // The fields of a struct sit in a list; an interface's
+                    // elements follow its `{` directly
+                    let body = match ty.kind() {
+                        "struct_type" => find_child_by_kind(&ty, "field_declaration_list"),
+                        "interface_type" => Some(ty),
+                        _ => None,
+                    };
+                    if let Some(body) = body {
+                        if let Some(open) = find_child_by_kind(&body, "{") {
+                            decl.signature_span = Some(signature_span(outer, &open));
+                            decl.body_span = Some(node_to_span(&body));
+                        }
+                        decl.children = self.extract_members(&body, source);
+                    }
+                }
+                Some(decl)
+            })
+            .collect()
+    }
+
+    /// Extract struct fields and interface methods
+    fn extract_members(&self, body: &tree_sitter::Node, source: &str) -> Vec<Declaration> {
+        let mut members = Vec::new();
+        let mut cursor = body.walk();
+        for member in body.children(&mut cursor) {
+            match member.kind() {
+                "field_declaration" => {
+                    let mut names = member.walk();
+                    let mut found = false;
+                    for name in member.children_by_field_name("name", &mut names) {
+                        found = true;
+                        members.push(self.member(&member, node_text(&name, source), source));
+                    }
+                    // An embedded field is named after its type
+                    if !found {
+                        if let Some(ty) = member.child_by_field_name("type") {
+                            let name = node_text(&ty, source).trim_start_matches('*');
+                            members.push(self.member(&member, name, source));
+                        }
+                    }
+                }
+                "method_elem" | "method_spec" => {
+                    if let Some(name) = member.child_by_field_name("name") {
+                        let mut decl = Declaration::new(
+                            node_text(&name, source).to_string(),
+                            DeclarationKind::Method,
+                            node_to_span(&member),
+                        );
+                        decl.visibility = exported(&decl.name);
+                        if let Some(params) = member.child_by_field_name("parameters") {
+                            decl.parameters = self.extract_parameters(&params, source);
+                        }
+                        decl.return_type = member
+                            .child_by_field_name("result")
+                            .map(|r| node_text(&r, source).to_string());
+                        members.push(decl);
+                    }
+                }
+                _ => {}
+            }
+        }
+        members
+    }
+
+    /// A struct field
+    fn member(&self, node: &tree_sitter::Node, name: &str, source: &str) -> Declaration {
+        let mut decl = Declaration::new(
+            name.to_string(),
+            DeclarationKind::Variable,
+            node_to_span(node),
+        );
+        decl.visibility = exported(name);
+        decl.doc_comment = leading_doc_comment(node, source, |_| true);
+        decl
+    }
+
+    /// Extract the names of a `const` or `var` declaration, grouped or not
+    fn extract_values(
+        &self,
+        node: &tree_sitter::Node,
+        source: &str,
+        kind: DeclarationKind,
+    ) -> Vec<Declaration> {
+        let mut specs = Vec::new();
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "const_spec" | "var_spec" => specs.push(child),
+                "var_spec_list" => {
+                    let mut list = child.walk();
+                    specs.extend(child.children(&mut list).filter(|c| c.kind() == "var_spec"));
+                }
+                _ => {}
+            }
+        }
+        let single = specs.len() == 1;
+
+        let mut values = Vec::new();
+        for spec in specs {
+            let outer = if single { *node } else { spec };
+            let mut names = spec.walk();
+            for name in spec.children_by_field_name("name", &mut names) {
+                let mut decl = Declaration::new(
+                    node_text(&name, source).to_string(),
+                    kind,
+                    node_to_span(&outer),
+                );
+                decl.visibility = exported(&decl.name);
+                decl.doc_comment = leading_doc_comment(&outer, source, |_| true);
+                values.push(decl);
+            }
+        }
+        values
+    }
+}
+
+/// Go exports exactly the names that start with an upper-case letter
+fn exported(name: &str) -> Visibility {
+    if name.starts_with(char::is_uppercase) {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> (GoTreeSitterAdapter, tree_sitter::Tree) {
+        let adapter = GoTreeSitterAdapter::new();
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&adapter.tree_sitter_language())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        (adapter, tree)
+    }
+
+    #[test]
+    fn test_extract_functions_and_methods() {
+        let source = "package main\n\n// Run starts the server.\nfunc (s *Server) Run(\n\tctx Context,\n\taddr string,\n) error {\n\treturn nil\n}\n\nfunc helper(a, b int) {}\n";
+        let (adapter, tree) = parse(source);
+        let decls = adapter.extract_declarations(&tree, source);
+
+        assert_eq!(decls.len(), 2);
+        let run = &decls[0];
+        assert_eq!(run.name, "Run");
+        assert_eq!(run.kind, DeclarationKind::Method);
+        assert_eq!(run.visibility, Visibility::Public);
+        assert_eq!(run.metadata["receiver"], "Server");
+        assert_eq!(run.return_type.as_deref(), Some("error"));
+        assert_eq!(run.parameters.len(), 2);
+        assert_eq!(
+            run.doc_comment.as_ref().unwrap().text,
+            "Run starts the server."
+        );
+
+        // The signature runs over every parameter line up to the body
+        let signature = run.signature_span.unwrap();
+        assert_eq!((signature.start_line, signature.end_line), (4, 7));
+        assert_eq!(
+            source[signature.start..signature.end].trim_end(),
+            "func (s *Server) Run(\n\tctx Context,\n\taddr string,\n) error"
+        );
+
+        let helper = &decls[1];
+        assert_eq!(helper.kind, DeclarationKind::Function);
+        assert_eq!(helper.visibility, Visibility::Private);
+        assert_eq!(helper.parameters.len(), 2);
+        assert!(helper.doc_comment.is_none());
+    }
+
+    #[test]
+    fn test_extract_types() {
+        let source = "package main\n\ntype Server struct {\n\tName string\n\tport int\n\t*Logger\n}\n\ntype Handler interface {\n\tServe(w Writer) error\n}\n\ntype (\n\tID = string\n\tCount int\n)\n";
+        let (adapter, tree) = parse(source);
+        let decls = adapter.extract_declarations(&tree, source);
+        let names: Vec<_> = decls.iter().map(|d| (d.name.as_str(), &d.kind)).collect();
+        assert_eq!(
+            names,
+            [
+                ("Server", &DeclarationKind::Struct),
+                ("Handler", &DeclarationKind::Interface),
+                ("ID", &DeclarationKind::Type),
+                ("Count", &DeclarationKind::Type),
+            ]
+        );
+
+        let fields: Vec<_> = decls[0].children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(fields, ["Name", "port", "Logger"]);
+        assert_eq!(decls[0].children[1].visibility, Visibility::Private);
+        let signature = decls[0].signature_span.unwrap();
+        assert_eq!(
+            source[signature.start..signature.end].trim_end(),
+            "type Server struct"
+        );
+
+        assert_eq!(decls[1].children[0].name, "Serve");
+        assert_eq!(decls[1].children[0].kind, DeclarationKind::Method);
+        assert!(decls[1].signature_span.is_some());
+        assert!(decls[2].signature_span.is_none());
+    }
+
+    #[test]
+    fn test_extract_values_and_imports() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\tstr \"strings\"\n)\nimport \"os\"\n\nconst Limit = 10\n\nvar (\n\ta, b = 1, 2\n)\n";
+        let (adapter, tree) = parse(source);
+
+        let decls = adapter.extract_declarations(&tree, source);
+        let names: Vec<_> = decls.iter().map(|d| (d.name.as_str(), &d.kind)).collect();
+        assert_eq!(
+            names,
+            [
+                ("Limit", &DeclarationKind::Constant),
+                ("a", &DeclarationKind::Variable),
+                ("b", &DeclarationKind::Variable),
+            ]
+        );
+
+        let imports = adapter.extract_imports(&tree, source);
+        let sources: Vec<_> = imports.iter().map(|i| i.source.as_str()).collect();
+        assert_eq!(sources, ["fmt", "strings", "os"]);
+        assert_eq!(imports[1].alias.as_deref(), Some("str"));
+    }
+
+    #[test]
+    fn test_extract_comments() {
+        let source = "package main\n\n// Line\n/* Block */\nfunc f() {}\n";
+        let (adapter, tree) = parse(source);
+        let comments = adapter.extract_comments(&tree, source);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].text, "Line");
+        assert_eq!(comments[1].text, "Block");
+        assert_eq!(comments[1].kind, crate::ir::CommentKind::Block);
+    }
+}
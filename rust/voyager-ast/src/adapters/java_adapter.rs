@@ -0,0 +1,405 @@
+//! Java Language Adapter
+//!
+//! Extracts structural information from Java source files using Tree-sitter.
+//! Supports classes, interfaces, enums, records and annotation types with
+//! their methods, constructors and fields, Javadoc comments and imports.
+
+use super::{
+    collect_comments, leading_doc_comment, node_text, node_to_span, signature_span,
+    AdapterCapabilities, LanguageAdapter,
+};
+use crate::ir::{
+    Block, Comment, Declaration, DeclarationKind, ImportKind, ImportLike, LanguageId, Parameter,
+    Visibility,
+};
+
+/// Java language adapter using Tree-sitter
+pub struct JavaTreeSitterAdapter {
+    language: tree_sitter::Language,
+}
+
+impl JavaTreeSitterAdapter {
+    /// Create a new Java adapter
+    pub fn new() -> Self {
+        Self {
+            language: tree_sitter_java::LANGUAGE.into(),
+        }
+    }
+}
+
+impl Default for JavaTreeSitterAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageAdapter for JavaTreeSitterAdapter {
+    fn language(&self) -> LanguageId {
+        LanguageId::Java
+    }
+
+    fn tree_sitter_language(&self) -> tree_sitter::Language {
+        self.language.clone()
+    }
+
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities {
+            zoom_bodies: false,
+            visibility: true,
+            doc_comments: true,
+        }
+    }
+
+    fn extract_declarations(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Declaration> {
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        root.children(&mut cursor)
+            .filter_map(|child| self.extract_type(&child, source))
+            .collect()
+    }
+
+    fn extract_imports(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<ImportLike> {
+        let mut imports = Vec::new();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            if child.kind() != "import_declaration" {
+                continue;
+            }
+            // `import a.b.C;`, `import a.b.*;`, `import static a.B.c;`
+            let mut inner = child.walk();
+            let Some(name) = child
+                .children(&mut inner)
+                .find(|c| matches!(c.kind(), "scoped_identifier" | "identifier"))
+            else {
+                continue;
+            };
+            let mut path = node_text(&name, source).to_string();
+            let mut inner = child.walk();
+            if child.children(&mut inner).any(|c| c.kind() == "asterisk") {
+                path.push_str(".*");
+            }
+            imports.push(ImportLike {
+                source: path,
+                kind: ImportKind::Import,
+                items: Vec::new(),
+                alias: None,
+                type_only: false,
+                span: node_to_span(&child),
+            });
+        }
+
+        imports
+    }
+
+    fn extract_comments(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Comment> {
+        collect_comments(&tree.root_node(), source, &["/**"])
+    }
+
+    fn extract_body(
+        &self,
+        _tree: &tree_sitter::Tree,
+        _source: &str,
+        _declaration: &Declaration,
+    ) -> Option<Block> {
+        None
+    }
+
+    fn extract_visibility(&self, node: &tree_sitter::Node, source: &str) -> Visibility {
+        let mut cursor = node.walk();
+        if let Some(modifiers) = node.children(&mut cursor).find(|c| c.kind() == "modifiers") {
+            let mut inner = modifiers.walk();
+            for modifier in modifiers.children(&mut inner) {
+                match node_text(&modifier, source) {
+                    "public" => return Visibility::Public,
+                    "protected" => return Visibility::Protected,
+                    "private" => return Visibility::Private,
+                    _ => {}
+                }
+            }
+        }
+        // Interface members are public; everything else is package-private
+        match node.parent().map(|p| p.kind()) {
+            Some("interface_body" | "annotation_type_body") => Visibility::Public,
+            _ => Visibility::Internal,
+        }
+    }
+}
+
+impl JavaTreeSitterAdapter {
+    /// Extract a class, interface, enum, record or annotation type
+    fn extract_type(&self, node: &tree_sitter::Node, source: &str) -> Option<Declaration> {
+        let kind = match node.kind() {
+            "class_declaration" | "record_declaration" => DeclarationKind::Class,
+            "interface_declaration" | "annotation_type_declaration" => DeclarationKind::Interface,
+            "enum_declaration" => DeclarationKind::Enum,
+            _ => return None,
+        };
+        let mut decl = self.declaration(node, kind, source)?;
+
+        if let Some(body) = node.child_by_field_name("body") {
+            let mut cursor = body.walk();
+            for member in body.children(&mut cursor) {
+                if member.kind() == "enum_body_declarations" {
+                    let mut inner = member.walk();
+                    for member in member.children(&mut inner) {
+                        decl.children.extend(self.extract_member(&member, source));
+                    }
+                } else {
+                    decl.children.extend(self.extract_member(&member, source));
+                }
+            }
+        }
+        Some(decl)
+    }
+
+    /// Extract the declarations a class body member introduces
+    fn extract_member(&self, node: &tree_sitter::Node, source: &str) -> Vec<Declaration> {
+        match node.kind() {
+            "method_declaration"
+            | "constructor_declaration"
+            | "compact_constructor_declaration" => self
+                .declaration(node, DeclarationKind::Method, source)
+                .into_iter()
+                .collect(),
+            "field_declaration" | "constant_declaration" => {
+                let kind = if node.kind() == "constant_declaration"
+                    || (self.has_modifier(node, "static", source)
+                        && self.has_modifier(node, "final", source))
+                {
+                    DeclarationKind::Constant
+                } else {
+                    DeclarationKind::Variable
+                };
+                let mut cursor = node.walk();
+                node.children_by_field_name("declarator", &mut cursor)
+                    .filter_map(|d| d.child_by_field_name("name"))
+                    .map(|name| {
+                        let mut decl = Declaration::new(
+                            node_text(&name, source).to_string(),
+                            kind,
+                            node_to_span(node),
+                        );
+                        decl.visibility = self.extract_visibility(node, source);
+                        decl.doc_comment = self.extract_doc_comment(node, source);
+                        decl
+                    })
+                    .collect()
+            }
+            "enum_constant" => node
+                .child_by_field_name("name")
+                .map(|name| {
+                    let mut decl = Declaration::new(
+                        node_text(&name, source).to_string(),
+                        DeclarationKind::Variable,
+                        node_to_span(node),
+                    );
+                    decl.visibility = Visibility::Public;
+                    decl
+                })
+                .into_iter()
+                .collect(),
+            _ => self.extract_type(node, source).into_iter().collect(),
+        }
+    }
+
+    /// A named declaration with its visibility, Javadoc, signature and body
+    fn declaration(
+        &self,
+        node: &tree_sitter::Node,
+        kind: DeclarationKind,
+        source: &str,
+    ) -> Option<Declaration> {
+        let name = node_text(&node.child_by_field_name("name")?, source).to_string();
+        let mut decl = Declaration::new(name, kind, node_to_span(node));
+        decl.visibility = self.extract_visibility(node, source);
+        decl.doc_comment = self.extract_doc_comment(node, source);
+
+        if let Some(body) = node.child_by_field_name("body") {
+            decl.signature_span = Some(signature_span(node, &body));
+            decl.body_span = Some(node_to_span(&body));
+        }
+        if let Some(params) = node.child_by_field_name("parameters") {
+            decl.parameters = self.extract_parameters(&params, source);
+        }
+        if kind == DeclarationKind::Method {
+            decl.return_type = node
+                .child_by_field_name("type")
+                .map(|t| node_text(&t, source).to_string());
+        }
+        Some(decl)
+    }
+
+    /// Extract the parameters of formal_parameters (record components too)
+    fn extract_parameters(&self, list: &tree_sitter::Node, source: &str) -> Vec<Parameter> {
+        let mut cursor = list.walk();
+        list.children(&mut cursor)
+            .filter(|p| matches!(p.kind(), "formal_parameter" | "spread_parameter"))
+            .filter_map(|param| {
+                // A spread parameter keeps its name in a variable_declarator
+                let name = param.child_by_field_name("name").or_else(|| {
+                    let mut inner = param.walk();
+                    let declarator = param
+                        .children(&mut inner)
+                        .find(|c| c.kind() == "variable_declarator");
+                    declarator.and_then(|d| d.child_by_field_name("name"))
+                })?;
+                Some(Parameter {
+                    name: node_text(&name, source).to_string(),
+                    type_annotation: param
+                        .child_by_field_name("type")
+                        .map(|t| node_text(&t, source).to_string()),
+                    default_value: None,
+                    span: node_to_span(&param),
+                })
+            })
+            .collect()
+    }
+
+    /// The Javadoc (`/** ... */`) right above a declaration
+    fn extract_doc_comment(&self, node: &tree_sitter::Node, source: &str) -> Option<Comment> {
+        leading_doc_comment(node, source, |text| text.starts_with("/**"))
+    }
+
+    /// Whether the declaration carries `modifier` (`static`, `final`, ...)
+    fn has_modifier(&self, node: &tree_sitter::Node, modifier: &str, source: &str) -> bool {
+        let mut cursor = node.walk();
+        let modifiers = node.children(&mut cursor).find(|c| c.kind() == "modifiers");
+        modifiers.is_some_and(|m| {
+            let mut inner = m.walk();
+            let found = m
+                .children(&mut inner)
+                .any(|c| node_text(&c, source) == modifier);
+            found
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> (JavaTreeSitterAdapter, tree_sitter::Tree) {
+        let adapter = JavaTreeSitterAdapter::new();
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&adapter.tree_sitter_language())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        (adapter, tree)
+    }
+
+    #[test]
+    fn test_extract_class_members() {
+        let source = r#"package com.example;
+
+/** A widget. */
+@Deprecated
+public class Widget<T>
+        extends Base
+        implements Runnable {
+    private static final int LIMIT = 10, OTHER = 2;
+    String label;
+
+    /** Build one. */
+    public Widget(int a) { super(); }
+
+    protected List<T> items(
+            int a,
+            String b)
+            throws Exception {
+        return null;
+    }
+
+    abstract void run();
+
+    private class Inner {}
+}
+"#;
+        let (adapter, tree) = parse(source);
+        let decls = adapter.extract_declarations(&tree, source);
+
+        assert_eq!(decls.len(), 1);
+        let widget = &decls[0];
+        assert_eq!(widget.name, "Widget");
+        assert_eq!(widget.kind, DeclarationKind::Class);
+        assert_eq!(widget.visibility, Visibility::Public);
+        assert_eq!(widget.doc_comment.as_ref().unwrap().text, "A widget.");
+        let signature = widget.signature_span.unwrap();
+        assert_eq!((signature.start_line, signature.end_line), (4, 7));
+
+        let members: Vec<_> = widget
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.kind, c.visibility))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("LIMIT", DeclarationKind::Constant, Visibility::Private),
+                ("OTHER", DeclarationKind::Constant, Visibility::Private),
+                ("label", DeclarationKind::Variable, Visibility::Internal),
+                ("Widget", DeclarationKind::Method, Visibility::Public),
+                ("items", DeclarationKind::Method, Visibility::Protected),
+                ("run", DeclarationKind::Method, Visibility::Internal),
+                ("Inner", DeclarationKind::Class, Visibility::Private),
+            ]
+        );
+        assert_eq!(
+            widget.children[3].doc_comment.as_ref().unwrap().text,
+            "Build one."
+        );
+
+        // A multi-line signature ends where the body opens
+        let items = &widget.children[4];
+        assert_eq!(items.return_type.as_deref(), Some("List<T>"));
+        assert_eq!(items.parameters.len(), 2);
+        let signature = items.signature_span.unwrap();
+        assert_eq!(
+            source[signature.start..signature.end].trim_end(),
+            "protected List<T> items(\n            int a,\n            String b)\n            throws Exception"
+        );
+        // Abstract methods have no body, so no signature span
+        assert!(widget.children[5].signature_span.is_none());
+    }
+
+    #[test]
+    fn test_extract_interface_enum_record() {
+        let source = "interface Shape { double area(); }\nenum Color { RED, GREEN; void x() {} }\nrecord Point(int x, int y) { }\n@interface Marker {}\n";
+        let (adapter, tree) = parse(source);
+        let decls = adapter.extract_declarations(&tree, source);
+
+        let names: Vec<_> = decls.iter().map(|d| (d.name.as_str(), d.kind)).collect();
+        assert_eq!(
+            names,
+            [
+                ("Shape", DeclarationKind::Interface),
+                ("Color", DeclarationKind::Enum),
+                ("Point", DeclarationKind::Class),
+                ("Marker", DeclarationKind::Interface),
+            ]
+        );
+        assert_eq!(decls[0].visibility, Visibility::Internal);
+        assert_eq!(decls[0].children[0].visibility, Visibility::Public);
+        let constants: Vec<_> = decls[1].children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(constants, ["RED", "GREEN", "x"]);
+        assert_eq!(decls[2].parameters.len(), 2);
+    }
+
+    #[test]
+    fn test_extract_imports_and_comments() {
+        let source = "import java.util.List;\nimport static java.lang.Math.*;\n\n// Line\n/** Doc */\nclass A {}\n";
+        let (adapter, tree) = parse(source);
+
+        let imports = adapter.extract_imports(&tree, source);
+        let sources: Vec<_> = imports.iter().map(|i| i.source.as_str()).collect();
+        assert_eq!(sources, ["java.util.List", "java.lang.Math.*"]);
+
+        let comments = adapter.extract_comments(&tree, source);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].kind, crate::ir::CommentKind::Line);
+        assert_eq!(comments[1].kind, crate::ir::CommentKind::Doc);
+        assert_eq!(comments[1].text, "Doc");
+    }
+}
@@ -8,20 +8,73 @@
 //! - **Rust**: Full support for functions, structs, enums, traits, impl blocks
 //! - **Python**: Functions (def/async), classes, imports, decorators, docstrings
 //! - **TypeScript/JavaScript**: Functions, classes, interfaces, types, imports/exports
+//!
+//! # Signature Fleet
+//!
+//! Declarations with signature and body spans, visibility, docs and imports;
+//! no Zoom bodies yet.
+//!
+//! - **Go**: Functions, methods, struct/interface types, constants, variables
+//! - **Java**: Classes, interfaces, enums, records, methods, constructors, fields
+//! - **C#**: Namespaces, classes, structs, interfaces, enums, records, members
 
+pub mod csharp_adapter;
+pub mod go_adapter;
+pub mod java_adapter;
 pub mod python_adapter;
 pub mod rust_adapter;
 pub mod typescript_adapter;
 
 use crate::ir::{
-    Block, Comment, Declaration, ImportLike, LanguageId, Span, UnknownNode, Visibility,
+    Block, Comment, CommentKind, Declaration, ImportLike, LanguageId, Span, UnknownNode, Visibility,
 };
 
 // Re-export all adapters
+pub use csharp_adapter::CSharpTreeSitterAdapter;
+pub use go_adapter::GoTreeSitterAdapter;
+pub use java_adapter::JavaTreeSitterAdapter;
 pub use python_adapter::PythonTreeSitterAdapter;
 pub use rust_adapter::RustTreeSitterAdapter;
 pub use typescript_adapter::TypeScriptTreeSitterAdapter;
 
+/// What an adapter can extract beyond declarations and imports
+///
+/// Adapters differ in fidelity; callers query this instead of assuming every
+/// language gets bodies, visibility and docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AdapterCapabilities {
+    /// `extract_body` returns blocks for Zoom mode
+    pub zoom_bodies: bool,
+    /// `extract_visibility` reflects the language's visibility rules
+    pub visibility: bool,
+    /// Declarations carry `doc_comment` when the source documents them
+    pub doc_comments: bool,
+}
+
+impl AdapterCapabilities {
+    /// Every capability supported
+    pub const FULL: Self = Self {
+        zoom_bodies: true,
+        visibility: true,
+        doc_comments: true,
+    };
+
+    /// Human-readable notes for each missing capability
+    pub fn fidelity_notes(&self) -> Vec<&'static str> {
+        let mut notes = Vec::new();
+        if !self.zoom_bodies {
+            notes.push("function bodies are not available for zoom");
+        }
+        if !self.visibility {
+            notes.push("visibility is not detected; all symbols are reported as unknown");
+        }
+        if !self.doc_comments {
+            notes.push("doc comments are not extracted; documentation coverage is not measured");
+        }
+        notes
+    }
+}
+
 /// Trait for language-specific adapters
 ///
 /// Each adapter is responsible for:
@@ -59,6 +112,13 @@ pub trait LanguageAdapter: Send + Sync {
     /// Determine visibility from a node
     fn extract_visibility(&self, node: &tree_sitter::Node, source: &str) -> Visibility;
 
+    /// What this adapter extracts beyond declarations and imports
+    ///
+    /// Defaults to no optional capabilities; adapters opt in to what they support.
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities::default()
+    }
+
     /// Extract unknown/error nodes from a parse tree
     fn extract_errors(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<UnknownNode> {
         let mut errors = Vec::new();
@@ -123,6 +183,104 @@ pub fn node_to_span(node: &tree_sitter::Node) -> Span {
     }
 }
 
+/// Span of a declaration's signature: from its start up to its body
+pub fn signature_span(node: &tree_sitter::Node, body: &tree_sitter::Node) -> Span {
+    Span {
+        start: node.start_byte(),
+        end: body.start_byte(),
+        start_line: node.start_position().row + 1,
+        end_line: body.start_position().row + 1,
+        start_column: node.start_position().column,
+        end_column: body.start_position().column,
+    }
+}
+
+/// Comment text without its `//`, `///`, `/*`, `/**` or `*/` markers
+pub fn strip_comment_markers(text: &str) -> &str {
+    let text = text.trim();
+    let text = match text.strip_prefix("/*") {
+        Some(block) => block.trim_end_matches("*/").trim_start_matches(['*', '!']),
+        None => text.trim_start_matches('/'),
+    };
+    text.trim()
+}
+
+/// Every comment under `node` (any node kind ending in `comment`), marked
+/// as documentation when it starts with one of `doc_prefixes`
+pub fn collect_comments(
+    node: &tree_sitter::Node,
+    source: &str,
+    doc_prefixes: &[&str],
+) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut pending = vec![*node];
+    while let Some(node) = pending.pop() {
+        if node.kind().ends_with("comment") {
+            let text = node_text(&node, source);
+            let kind = if doc_prefixes.iter().any(|p| text.starts_with(p)) {
+                CommentKind::Doc
+            } else if text.starts_with("/*") {
+                CommentKind::Block
+            } else {
+                CommentKind::Line
+            };
+            comments.push(Comment {
+                text: strip_comment_markers(text).to_string(),
+                kind,
+                span: node_to_span(&node),
+                attached_to: None,
+            });
+            continue;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        pending.extend(children.into_iter().rev());
+    }
+    comments
+}
+
+/// The doc comment right above `node`: the run of adjacent comment siblings
+/// (no blank line in between) that `is_doc` accepts
+pub fn leading_doc_comment(
+    node: &tree_sitter::Node,
+    source: &str,
+    is_doc: impl Fn(&str) -> bool,
+) -> Option<Comment> {
+    let mut lines = Vec::new();
+    let mut span: Option<Span> = None;
+    let mut next_row = node.start_position().row;
+    let mut prev = node.prev_sibling();
+    while let Some(comment) = prev {
+        let text = node_text(&comment, source);
+        if !comment.kind().ends_with("comment")
+            || comment.end_position().row + 1 < next_row
+            || !is_doc(text)
+        {
+            break;
+        }
+        lines.insert(0, strip_comment_markers(text).to_string());
+        let start = node_to_span(&comment);
+        span = Some(match span {
+            Some(end) => Span {
+                start: start.start,
+                start_line: start.start_line,
+                start_column: start.start_column,
+                ..end
+            },
+            None => start,
+        });
+        next_row = comment.start_position().row;
+        prev = comment.prev_sibling();
+    }
+
+    Some(Comment {
+        text: lines.join("\n"),
+        kind: CommentKind::Doc,
+        span: span?,
+        attached_to: None,
+    })
+}
+
 /// Get the text content of a Tree-sitter node
 pub fn node_text<'a>(node: &tree_sitter::Node, source: &'a str) -> &'a str {
     &source[node.start_byte()..node.end_byte()]
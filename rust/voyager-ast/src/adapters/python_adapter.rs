@@ -3,7 +3,7 @@
 //! Extracts structural information from Python source files using Tree-sitter.
 //! Supports functions (def/async def), classes, imports, decorators, and docstrings.
 
-use super::{find_child_by_kind, node_text, node_to_span, LanguageAdapter};
+use super::{find_child_by_kind, node_text, node_to_span, AdapterCapabilities, LanguageAdapter};
 use crate::ir::{
     Block, Call, Comment, CommentKind, ControlFlow, ControlFlowKind, Declaration, DeclarationKind,
     ImportKind, ImportLike, LanguageId, Parameter, Span, Visibility,
@@ -38,6 +38,11 @@ impl LanguageAdapter for PythonTreeSitterAdapter {
         self.language.clone()
     }
 
+    fn capabilities(&self) -> AdapterCapabilities {
+        // Visibility follows the _name / __name conventions
+        AdapterCapabilities::FULL
+    }
+
     fn extract_declarations(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Declaration> {
         let mut declarations = Vec::new();
         let root = tree.root_node();
@@ -3,7 +3,10 @@
 //! Extracts structural information from Rust source files using Tree-sitter.
 //! Supports functions, structs, enums, traits, impl blocks, and more.
 
-use super::{find_child_by_kind, find_children_by_kind, node_text, node_to_span, LanguageAdapter};
+use super::{
+    find_child_by_kind, find_children_by_kind, node_text, node_to_span, AdapterCapabilities,
+    LanguageAdapter,
+};
 use crate::ir::{
     Block, Call, Comment, CommentKind, ControlFlow, ControlFlowKind, Declaration, DeclarationKind,
     ImportKind, ImportLike, LanguageId, Parameter, Span, Visibility,
@@ -38,6 +41,10 @@ impl LanguageAdapter for RustTreeSitterAdapter {
         self.language.clone()
     }
 
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities::FULL
+    }
+
     fn extract_declarations(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Declaration> {
         let mut declarations = Vec::new();
         let root = tree.root_node();
@@ -3,7 +3,7 @@
 //! Extracts structural information from TypeScript and JavaScript source files
 //! using Tree-sitter. Supports functions, classes, interfaces, types, imports/exports.
 
-use super::{find_child_by_kind, node_text, node_to_span, LanguageAdapter};
+use super::{find_child_by_kind, node_text, node_to_span, AdapterCapabilities, LanguageAdapter};
 use crate::ir::{
     Block, Call, Comment, CommentKind, ControlFlow, ControlFlowKind, Declaration, DeclarationKind,
     ImportKind, ImportLike, LanguageId, Parameter, Span, Visibility,
@@ -56,6 +56,15 @@ impl LanguageAdapter for TypeScriptTreeSitterAdapter {
         self.language.clone()
     }
 
+    fn capabilities(&self) -> AdapterCapabilities {
+        // JSDoc comments are not attached to declarations yet
+        AdapterCapabilities {
+            zoom_bodies: true,
+            visibility: true,
+            doc_comments: false,
+        }
+    }
+
     fn extract_declarations(&self, tree: &tree_sitter::Tree, source: &str) -> Vec<Declaration> {
         let mut declarations = Vec::new();
         let root = tree.root_node();
@@ -0,0 +1,272 @@
+//! Built-in fixture corpus for the bundled adapters
+//!
+//! Each language gets a clean fixture covering the common top-level
+//! constructs. Rust also carries a broken fixture so the error-surfacing
+//! rule is exercised on every run. New adapters start from an empty corpus
+//! and add their own fixtures.
+
+use super::Fixture;
+use crate::ir::{DeclarationKind, LanguageId};
+
+/// Built-in fixtures for `language` (empty for languages without an adapter)
+pub fn corpus(language: LanguageId) -> Vec<Fixture> {
+    match language {
+        LanguageId::Rust => rust(),
+        LanguageId::Python => python(),
+        LanguageId::TypeScript => typescript(),
+        LanguageId::JavaScript => javascript(),
+        LanguageId::Go => go(),
+        LanguageId::Java => java(),
+        LanguageId::CSharp => csharp(),
+        _ => Vec::new(),
+    }
+}
+
+fn rust() -> Vec<Fixture> {
+    vec![
+        Fixture::new(
+            "rust/items",
+            r#"use std::fmt;
+use std::collections::HashMap;
+
+const LIMIT: usize = 10;
+
+pub struct Config {
+    name: String,
+}
+
+pub enum Mode {
+    Fast,
+    Slow,
+}
+
+pub trait Render {
+    fn render(&self) -> String;
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config { name: String::new() }
+    }
+}
+
+pub fn run(config: &Config) -> usize {
+    config.name.len() + LIMIT
+}
+"#,
+        )
+        .with_import("std::fmt")
+        .with_import("std::collections::HashMap")
+        .with_declaration("LIMIT", DeclarationKind::Constant)
+        .with_declaration("Config", DeclarationKind::Struct)
+        .with_declaration("Mode", DeclarationKind::Enum)
+        .with_declaration("Render", DeclarationKind::Trait)
+        .with_declaration("new", DeclarationKind::Method)
+        .with_declaration("run", DeclarationKind::Function),
+        Fixture::new(
+            "rust/broken",
+            "fn broken( {\n    // Missing closing paren\n}\n\nfn valid_function() {\n    println!(\"I'm fine\");\n}\n",
+        )
+        .with_declaration("valid_function", DeclarationKind::Function)
+        .with_syntax_errors(0.75),
+    ]
+}
+
+fn python() -> Vec<Fixture> {
+    vec![Fixture::new(
+        "python/module",
+        r#"import os
+from pathlib import Path
+
+
+class Loader:
+    def __init__(self, root):
+        self.root = Path(root)
+
+    def load(self, name):
+        return (self.root / name).read_text()
+
+
+def main():
+    return Loader(os.getcwd())
+"#,
+    )
+    .with_import("os")
+    .with_import("pathlib")
+    .with_declaration("Loader", DeclarationKind::Class)
+    .with_declaration("main", DeclarationKind::Function)]
+}
+
+fn typescript() -> Vec<Fixture> {
+    vec![Fixture::new(
+        "typescript/module",
+        r#"import { readFileSync } from "fs";
+
+export interface Options {
+    path: string;
+}
+
+export type Id = string;
+
+export enum Level {
+    Low,
+    High,
+}
+
+export class Reader {
+    read(options: Options): string {
+        return readFileSync(options.path, "utf8");
+    }
+}
+
+export function open(path: string): Reader {
+    return new Reader();
+}
+"#,
+    )
+    .with_import("fs")
+    .with_declaration("Options", DeclarationKind::Interface)
+    .with_declaration("Id", DeclarationKind::Type)
+    .with_declaration("Level", DeclarationKind::Enum)
+    .with_declaration("Reader", DeclarationKind::Class)
+    .with_declaration("open", DeclarationKind::Function)]
+}
+
+fn javascript() -> Vec<Fixture> {
+    vec![Fixture::new(
+        "javascript/module",
+        r#"import { readFileSync } from "fs";
+
+export class Reader {
+    read(path) {
+        return readFileSync(path, "utf8");
+    }
+}
+
+export function open(path) {
+    return new Reader();
+}
+"#,
+    )
+    .with_import("fs")
+    .with_declaration("Reader", DeclarationKind::Class)
+    .with_declaration("open", DeclarationKind::Function)]
+}
+
+fn go() -> Vec<Fixture> {
+    vec![Fixture::new(
+        "go/package",
+        r#"package server
+
+import (
+	"fmt"
+	"net/http"
+)
+
+const Port = 8080
+
+type Server struct {
+	Name string
+}
+
+type Handler interface {
+	Serve(w http.ResponseWriter) error
+}
+
+func (s *Server) Start(
+	addr string,
+	handler Handler,
+) error {
+	return fmt.Errorf("%s", addr)
+}
+
+func New(name string) *Server {
+	return &Server{Name: name}
+}
+"#,
+    )
+    .with_import("fmt")
+    .with_import("net/http")
+    .with_declaration("Port", DeclarationKind::Constant)
+    .with_declaration("Server", DeclarationKind::Struct)
+    .with_declaration("Handler", DeclarationKind::Interface)
+    .with_declaration("Start", DeclarationKind::Method)
+    .with_declaration("New", DeclarationKind::Function)]
+}
+
+fn java() -> Vec<Fixture> {
+    vec![Fixture::new(
+        "java/class",
+        r#"package com.example;
+
+import java.util.List;
+import java.util.Map;
+
+public interface Store {
+    List<String> keys();
+}
+
+public enum Mode { FAST, SLOW }
+
+/** An in-memory store. */
+public class MemoryStore implements Store {
+    private final Map<String, String> items;
+
+    public MemoryStore(Map<String, String> items) {
+        this.items = items;
+    }
+
+    public List<String> keys() {
+        return List.copyOf(items.keySet());
+    }
+}
+"#,
+    )
+    .with_import("java.util.List")
+    .with_import("java.util.Map")
+    .with_declaration("Store", DeclarationKind::Interface)
+    .with_declaration("Mode", DeclarationKind::Enum)
+    .with_declaration("MemoryStore", DeclarationKind::Class)
+    .with_declaration("items", DeclarationKind::Variable)
+    .with_declaration("keys", DeclarationKind::Method)]
+}
+
+fn csharp() -> Vec<Fixture> {
+    vec![Fixture::new(
+        "csharp/namespace",
+        r#"using System;
+using System.Collections.Generic;
+
+namespace Shop
+{
+    public interface ICart
+    {
+        int Count { get; }
+    }
+
+    public enum Status { Open, Closed }
+
+    /// <summary>A shopping cart.</summary>
+    public class Cart : ICart
+    {
+        private readonly List<string> items = new();
+
+        public int Count => items.Count;
+
+        public void Add(string item)
+        {
+            items.Add(item);
+        }
+    }
+}
+"#,
+    )
+    .with_import("System")
+    .with_import("System.Collections.Generic")
+    .with_declaration("Shop", DeclarationKind::Namespace)
+    .with_declaration("ICart", DeclarationKind::Interface)
+    .with_declaration("Status", DeclarationKind::Enum)
+    .with_declaration("Cart", DeclarationKind::Class)
+    .with_declaration("Count", DeclarationKind::Variable)
+    .with_declaration("Add", DeclarationKind::Method)]
+}
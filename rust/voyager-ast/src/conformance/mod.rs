@@ -0,0 +1,365 @@
+//! Conformance Kit for Language Adapters
+//!
+//! A reusable harness that every `LanguageAdapter` should pass before it is
+//! registered. Each [`Fixture`] is a source snippet plus the declarations and
+//! imports a correct adapter must find, and a ceiling on how much of the file
+//! may end up in `UnknownNode` regions.
+//!
+//! Missing declarations and imports lower the adapter's accuracy instead of
+//! failing outright ("Telescope, Not Compiler"); the kit fails when accuracy
+//! drops below [`TARGET_ACCURACY`] or a fixture breaks a hard rule: too many
+//! unknown bytes, or syntax errors that were silently dropped.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use voyager_ast::conformance::{assert_conformance, corpus, Fixture};
+//! use voyager_ast::{DeclarationKind, LanguageId};
+//!
+//! let adapter = MyRubyAdapter::new();
+//! let mut fixtures = corpus(LanguageId::Ruby);
+//! fixtures.push(
+//!     Fixture::new("endless", "def twice(x) = x * 2\n")
+//!         .with_declaration("twice", DeclarationKind::Function),
+//! );
+//! assert_conformance(&adapter, &fixtures);
+//! ```
+
+mod corpus;
+
+pub use corpus::corpus;
+
+use crate::adapters::LanguageAdapter;
+use crate::error::{AstError, Result};
+use crate::ir::{Declaration, DeclarationKind, ImportLike, LanguageId, UnknownNode};
+
+/// Minimum share of expected declarations and imports an adapter must find
+pub const TARGET_ACCURACY: f64 = 0.9;
+
+/// A source snippet with the structure a correct adapter must extract
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// Short name used in reports
+    pub name: String,
+    /// Source text to parse
+    pub source: String,
+    /// Declarations that must be found (at any nesting depth)
+    pub declarations: Vec<(String, DeclarationKind)>,
+    /// Imports that must be found; matches any import whose source contains it
+    pub imports: Vec<String>,
+    /// Highest acceptable share of source bytes inside unknown regions
+    pub max_unknown_ratio: f64,
+    /// Whether the fixture contains syntax errors that must be surfaced
+    pub expect_errors: bool,
+}
+
+impl Fixture {
+    /// Create a fixture that must parse cleanly
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+            declarations: Vec::new(),
+            imports: Vec::new(),
+            max_unknown_ratio: 0.0,
+            expect_errors: false,
+        }
+    }
+
+    /// Require a declaration with this name and kind
+    pub fn with_declaration(mut self, name: impl Into<String>, kind: DeclarationKind) -> Self {
+        self.declarations.push((name.into(), kind));
+        self
+    }
+
+    /// Require an import whose source contains `source`
+    pub fn with_import(mut self, source: impl Into<String>) -> Self {
+        self.imports.push(source.into());
+        self
+    }
+
+    /// Mark the fixture as broken: errors must be reported as unknown regions,
+    /// covering at most `max_unknown_ratio` of the source
+    pub fn with_syntax_errors(mut self, max_unknown_ratio: f64) -> Self {
+        self.expect_errors = true;
+        self.max_unknown_ratio = max_unknown_ratio;
+        self
+    }
+}
+
+/// Outcome of running one fixture
+#[derive(Debug, Clone, Default)]
+pub struct FixtureResult {
+    pub name: String,
+    pub declarations_expected: usize,
+    pub declarations_found: usize,
+    pub imports_expected: usize,
+    pub imports_found: usize,
+    /// Share of source bytes covered by unknown regions
+    pub unknown_ratio: f64,
+    /// Expected items the adapter did not find
+    pub missing: Vec<String>,
+    /// Hard-rule violations (fail the kit regardless of accuracy)
+    pub violations: Vec<String>,
+}
+
+/// Results of a conformance run for one adapter
+#[derive(Debug, Clone)]
+pub struct ConformanceReport {
+    pub language: LanguageId,
+    pub results: Vec<FixtureResult>,
+}
+
+impl ConformanceReport {
+    /// Share of expected declarations and imports that were found
+    pub fn accuracy(&self) -> f64 {
+        let expected: usize = self
+            .results
+            .iter()
+            .map(|r| r.declarations_expected + r.imports_expected)
+            .sum();
+        let found: usize = self
+            .results
+            .iter()
+            .map(|r| r.declarations_found + r.imports_found)
+            .sum();
+        if expected == 0 {
+            1.0
+        } else {
+            found as f64 / expected as f64
+        }
+    }
+
+    /// Whether the adapter meets the accuracy target with no violations
+    pub fn passed(&self) -> bool {
+        self.accuracy() >= TARGET_ACCURACY && self.results.iter().all(|r| r.violations.is_empty())
+    }
+
+    /// Human-readable summary, one line per fixture
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "{:?} adapter: {:.1}% accuracy (target {:.0}%)\n",
+            self.language,
+            self.accuracy() * 100.0,
+            TARGET_ACCURACY * 100.0
+        );
+        for r in &self.results {
+            out.push_str(&format!(
+                "  {}: {}/{} declarations, {}/{} imports, {:.1}% unknown\n",
+                r.name,
+                r.declarations_found,
+                r.declarations_expected,
+                r.imports_found,
+                r.imports_expected,
+                r.unknown_ratio * 100.0
+            ));
+            for m in &r.missing {
+                out.push_str(&format!("    missing: {}\n", m));
+            }
+            for v in &r.violations {
+                out.push_str(&format!("    violation: {}\n", v));
+            }
+        }
+        out
+    }
+}
+
+/// Parse one fixture with `adapter` and score it
+pub fn check_fixture(adapter: &dyn LanguageAdapter, fixture: &Fixture) -> Result<FixtureResult> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&adapter.tree_sitter_language())
+        .map_err(|e| AstError::TreeSitterError(e.to_string()))?;
+    let tree = parser
+        .parse(&fixture.source, None)
+        .ok_or_else(|| AstError::parse_error("Failed to parse fixture"))?;
+
+    let declarations = adapter.extract_declarations(&tree, &fixture.source);
+    let imports = adapter.extract_imports(&tree, &fixture.source);
+    let unknown = adapter.extract_errors(&tree, &fixture.source);
+
+    let mut result = FixtureResult {
+        name: fixture.name.clone(),
+        declarations_expected: fixture.declarations.len(),
+        imports_expected: fixture.imports.len(),
+        unknown_ratio: unknown_ratio(&unknown, fixture.source.len()),
+        ..Default::default()
+    };
+
+    for (name, kind) in &fixture.declarations {
+        if contains_declaration(&declarations, name, *kind) {
+            result.declarations_found += 1;
+        } else {
+            result.missing.push(format!("{} {}", kind.as_str(), name));
+        }
+    }
+    for source in &fixture.imports {
+        if contains_import(&imports, source) {
+            result.imports_found += 1;
+        } else {
+            result.missing.push(format!("import {}", source));
+        }
+    }
+
+    if result.unknown_ratio > fixture.max_unknown_ratio {
+        result.violations.push(format!(
+            "{:.1}% of the source is unknown (max {:.1}%)",
+            result.unknown_ratio * 100.0,
+            fixture.max_unknown_ratio * 100.0
+        ));
+    }
+    if fixture.expect_errors && unknown.is_empty() {
+        result
+            .violations
+            .push("syntax errors were not reported as unknown regions".to_string());
+    }
+
+    Ok(result)
+}
+
+/// Run every fixture against `adapter`
+pub fn run_conformance(
+    adapter: &dyn LanguageAdapter,
+    fixtures: &[Fixture],
+) -> Result<ConformanceReport> {
+    let results = fixtures
+        .iter()
+        .map(|f| check_fixture(adapter, f))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ConformanceReport {
+        language: adapter.language(),
+        results,
+    })
+}
+
+/// Run the kit and panic with the report if the adapter does not conform
+pub fn assert_conformance(adapter: &dyn LanguageAdapter, fixtures: &[Fixture]) {
+    let report = match run_conformance(adapter, fixtures) {
+        Ok(r) => r,
+        Err(e) => panic!("conformance run failed: {}", e),
+    };
+    assert!(
+        report.passed(),
+        "adapter failed conformance:\n{}",
+        report.summary()
+    );
+}
+
+fn contains_declaration(declarations: &[Declaration], name: &str, kind: DeclarationKind) -> bool {
+    declarations.iter().any(|d| {
+        (d.name == name && d.kind == kind) || contains_declaration(&d.children, name, kind)
+    })
+}
+
+fn contains_import(imports: &[ImportLike], source: &str) -> bool {
+    imports.iter().any(|i| i.source.contains(source))
+}
+
+/// Share of `len` bytes covered by unknown regions (overlaps counted once)
+fn unknown_ratio(unknown: &[UnknownNode], len: usize) -> f64 {
+    if len == 0 {
+        return 0.0;
+    }
+    let mut spans: Vec<(usize, usize)> =
+        unknown.iter().map(|u| (u.span.start, u.span.end)).collect();
+    spans.sort_unstable();
+
+    let mut covered = 0;
+    let mut reach = 0;
+    for (start, end) in spans {
+        let start = start.max(reach);
+        if end > start {
+            covered += end - start;
+            reach = end;
+        }
+    }
+    covered.min(len) as f64 / len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{
+        CSharpTreeSitterAdapter, GoTreeSitterAdapter, JavaTreeSitterAdapter,
+        PythonTreeSitterAdapter, RustTreeSitterAdapter, TypeScriptTreeSitterAdapter,
+    };
+    use crate::ir::Span;
+
+    #[test]
+    fn test_rust_adapter_conforms() {
+        assert_conformance(&RustTreeSitterAdapter::new(), &corpus(LanguageId::Rust));
+    }
+
+    #[test]
+    fn test_python_adapter_conforms() {
+        assert_conformance(&PythonTreeSitterAdapter::new(), &corpus(LanguageId::Python));
+    }
+
+    #[test]
+    fn test_typescript_adapter_conforms() {
+        assert_conformance(
+            &TypeScriptTreeSitterAdapter::new(),
+            &corpus(LanguageId::TypeScript),
+        );
+    }
+
+    #[test]
+    fn test_javascript_adapter_conforms() {
+        assert_conformance(
+            &TypeScriptTreeSitterAdapter::javascript(),
+            &corpus(LanguageId::JavaScript),
+        );
+    }
+
+    #[test]
+    fn test_go_adapter_conforms() {
+        assert_conformance(&GoTreeSitterAdapter::new(), &corpus(LanguageId::Go));
+    }
+
+    #[test]
+    fn test_java_adapter_conforms() {
+        assert_conformance(&JavaTreeSitterAdapter::new(), &corpus(LanguageId::Java));
+    }
+
+    #[test]
+    fn test_csharp_adapter_conforms() {
+        assert_conformance(&CSharpTreeSitterAdapter::new(), &corpus(LanguageId::CSharp));
+    }
+
+    #[test]
+    fn test_missing_declarations_lower_accuracy() {
+        let fixtures = vec![Fixture::new("wrong", "fn real() {}\n")
+            .with_declaration("real", DeclarationKind::Function)
+            .with_declaration("imaginary", DeclarationKind::Struct)];
+        let report = run_conformance(&RustTreeSitterAdapter::new(), &fixtures).unwrap();
+
+        assert_eq!(report.accuracy(), 0.5);
+        assert!(!report.passed());
+        assert!(report.summary().contains("missing: struct imaginary"));
+    }
+
+    #[test]
+    fn test_unreported_errors_are_violations() {
+        // Clean source declared as broken: no unknown regions to find
+        let fixtures = vec![Fixture::new("clean", "fn ok() {}\n").with_syntax_errors(0.5)];
+        let report = run_conformance(&RustTreeSitterAdapter::new(), &fixtures).unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.results[0].violations.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_ratio_merges_overlaps() {
+        let node = |start, end| UnknownNode {
+            span: Span {
+                start,
+                end,
+                ..Default::default()
+            },
+            reason: None,
+            raw_text: None,
+        };
+        let unknown = vec![node(0, 10), node(5, 20), node(50, 60)];
+        assert_eq!(unknown_ratio(&unknown, 100), 0.3);
+        assert_eq!(unknown_ratio(&[], 0), 0.0);
+    }
+}
@@ -7,8 +7,10 @@
 //! 2. **Serialization**: Full serde support for caching and export
 //! 3. **Error Tolerance**: UnknownNode/UnparsedBlock for graceful degradation
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 // ============================================================================
 // Language Identification
@@ -202,6 +204,116 @@ impl Span {
     pub fn is_empty(&self) -> bool {
         self.start >= self.end
     }
+
+    /// The source text covered by this span (O(1) byte slicing)
+    ///
+    /// Returns `None` if the span is out of bounds or not on UTF-8 boundaries
+    /// for `source`, e.g. when applied to a different version of the file.
+    pub fn slice<'a>(&self, source: &'a str) -> Option<&'a str> {
+        source.get(self.start..self.end)
+    }
+}
+
+/// Line table for converting between byte offsets and positions
+///
+/// Built once per source in O(n); every conversion afterwards is a binary
+/// search or a direct lookup, so editors and LSP integrations never need to
+/// re-split the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    /// Total source length in bytes
+    len: usize,
+}
+
+impl LineIndex {
+    /// Build the line table for `source`
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        Self {
+            line_starts,
+            len: source.len(),
+        }
+    }
+
+    /// Number of lines (a trailing newline starts an empty last line)
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Line (1-indexed) and byte column (0-indexed) of a byte offset
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&s| s <= offset) - 1;
+        (line + 1, offset - self.line_starts[line])
+    }
+
+    /// Byte offset of a line (1-indexed) and byte column (0-indexed)
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let offset = start + column;
+        (offset <= self.line_end(line)?).then_some(offset)
+    }
+
+    /// Byte range of a line (1-indexed), excluding its newline
+    pub fn line_range(&self, line: usize) -> Option<std::ops::Range<usize>> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        Some(start..self.line_end(line)?)
+    }
+
+    /// Build a span with both byte offsets and line/column positions
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let (start_line, start_column) = self.line_col(start);
+        let (end_line, end_column) = self.line_col(end);
+        Span {
+            start,
+            end,
+            start_line,
+            end_line,
+            start_column,
+            end_column,
+        }
+    }
+
+    /// LSP position (0-indexed line, UTF-16 character) of a byte offset
+    pub fn to_lsp(&self, source: &str, offset: usize) -> (u32, u32) {
+        let (line, column) = self.line_col(offset);
+        let start = self.line_starts[line - 1];
+        let character: usize = source
+            .get(start..start + column)
+            .map(|s| s.chars().map(char::len_utf16).sum())
+            .unwrap_or(column);
+        ((line - 1) as u32, character as u32)
+    }
+
+    /// Byte offset of an LSP position (0-indexed line, UTF-16 character)
+    ///
+    /// Characters past the end of the line clamp to the line end, as LSP
+    /// clients expect.
+    pub fn from_lsp(&self, source: &str, line: u32, character: u32) -> Option<usize> {
+        let range = self.line_range(line as usize + 1)?;
+        let text = source.get(range.clone())?;
+        let mut units = 0;
+        for (i, c) in text.char_indices() {
+            if units >= character as usize {
+                return Some(range.start + i);
+            }
+            units += c.len_utf16();
+        }
+        Some(range.end)
+    }
+
+    /// End of a line's content (before its `\n`)
+    fn line_end(&self, line: usize) -> Option<usize> {
+        let index = line.checked_sub(1)?;
+        self.line_starts.get(index)?;
+        Some(match self.line_starts.get(index + 1) {
+            Some(&next) => next - 1,
+            None => self.len,
+        })
+    }
 }
 
 /// A source region with optional language override (for embedded languages)
@@ -351,8 +463,12 @@ impl Declaration {
 }
 
 /// Kind of declaration
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+///
+/// The core kinds are the constructs languages share. Frameworks build their
+/// own out of them (a React component is a function, a Django view a
+/// function taking `request`), which [`CustomKind`] names in a namespace:
+/// `react:component`. Kinds serialize as their names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DeclarationKind {
     Function,
     Method,
@@ -369,9 +485,54 @@ pub enum DeclarationKind {
     Impl,
     Macro,
     Other,
+    /// A framework construct, see [`CustomKind`]
+    Custom(CustomKind),
 }
 
+/// Every core kind
+const CORE_KINDS: [DeclarationKind; 15] = [
+    DeclarationKind::Function,
+    DeclarationKind::Method,
+    DeclarationKind::Class,
+    DeclarationKind::Struct,
+    DeclarationKind::Enum,
+    DeclarationKind::Interface,
+    DeclarationKind::Trait,
+    DeclarationKind::Type,
+    DeclarationKind::Constant,
+    DeclarationKind::Variable,
+    DeclarationKind::Module,
+    DeclarationKind::Namespace,
+    DeclarationKind::Impl,
+    DeclarationKind::Macro,
+    DeclarationKind::Other,
+];
+
 impl DeclarationKind {
+    /// A core kind by name, or a registered custom kind
+    pub fn parse(name: &str) -> Option<Self> {
+        CORE_KINDS
+            .into_iter()
+            .find(|kind| kind.as_str() == name)
+            .or_else(|| CustomKind::lookup(name).map(Self::Custom))
+    }
+
+    /// The core kind: this one, or the one a custom kind is made of
+    pub fn base(&self) -> Self {
+        match self {
+            Self::Custom(kind) => kind.base(),
+            kind => *kind,
+        }
+    }
+
+    /// The namespace of a custom kind
+    pub fn namespace(&self) -> Option<&'static str> {
+        match self {
+            Self::Custom(kind) => Some(kind.namespace()),
+            _ => None,
+        }
+    }
+
     /// Get string representation
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -390,7 +551,199 @@ impl DeclarationKind {
             Self::Impl => "impl",
             Self::Macro => "macro",
             Self::Other => "other",
+            Self::Custom(kind) => kind.name(),
+        }
+    }
+}
+
+impl Serialize for DeclarationKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DeclarationKind {
+    /// A custom kind not registered in this process is registered as made
+    /// of [`DeclarationKind::Other`], until a rule gives it its base
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match Self::parse(&name) {
+            Some(kind) => Ok(kind),
+            None => CustomKind::register(&name, Self::Other)
+                .map(Self::Custom)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// A namespaced declaration kind, such as `react:component`, made of a core
+/// kind
+///
+/// Adapters, plugins and config rules register the kinds they give
+/// declarations. A registered kind lives for the rest of the process, which
+/// keeps [`DeclarationKind`] `Copy`; at most [`MAX_CUSTOM_KINDS`] can be.
+#[derive(Debug, Clone, Copy)]
+pub struct CustomKind(&'static CustomKindEntry);
+
+#[derive(Debug)]
+struct CustomKindEntry {
+    name: String,
+    /// Index of the base in [`CORE_KINDS`]
+    base: AtomicUsize,
+}
+
+/// How many custom kinds a process can register
+pub const MAX_CUSTOM_KINDS: usize = 1024;
+
+static CUSTOM_KINDS: Mutex<Vec<&'static CustomKindEntry>> = Mutex::new(Vec::new());
+
+/// Index of core kind `kind` in [`CORE_KINDS`]
+fn core_index(kind: DeclarationKind) -> usize {
+    CORE_KINDS
+        .iter()
+        .position(|core| *core == kind)
+        .unwrap_or(CORE_KINDS.len() - 1)
+}
+
+impl PartialEq for CustomKind {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for CustomKind {}
+
+impl std::hash::Hash for CustomKind {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.name.hash(state);
+    }
+}
+
+impl CustomKind {
+    /// Register `name`, `namespace:kind` in lowercase letters, digits, `_`
+    /// and `-`, as made of `base` (a custom base stands for its own base)
+    ///
+    /// Registering a name again gives the same kind. A kind made of
+    /// [`DeclarationKind::Other`] (as one first seen in serialized data is)
+    /// takes the first other base it is registered with; any other change of
+    /// base is an error.
+    pub fn register(name: &str, base: DeclarationKind) -> Result<Self, String> {
+        if !Self::is_valid_name(name) {
+            return Err(format!(
+                "'{}' is not a namespaced kind such as 'react:component'",
+                name
+            ));
+        }
+        let base = base.base();
+        let mut kinds = CUSTOM_KINDS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = kinds.iter().find(|entry| entry.name == name) {
+            let kind = Self(entry);
+            let current = kind.base();
+            if current == DeclarationKind::Other {
+                entry.base.store(core_index(base), Ordering::Relaxed);
+            } else if current != base && base != DeclarationKind::Other {
+                return Err(format!(
+                    "'{}' is already a kind of {}, not {}",
+                    name,
+                    current.as_str(),
+                    base.as_str()
+                ));
+            }
+            return Ok(kind);
         }
+        if kinds.len() >= MAX_CUSTOM_KINDS {
+            return Err(format!(
+                "Cannot register '{}': {} custom kinds are already registered",
+                name, MAX_CUSTOM_KINDS
+            ));
+        }
+        let entry = Box::leak(Box::new(CustomKindEntry {
+            name: name.to_string(),
+            base: AtomicUsize::new(core_index(base)),
+        }));
+        kinds.push(entry);
+        Ok(Self(entry))
+    }
+
+    /// Whether `name` has the form `namespace:kind`
+    pub fn is_valid_name(name: &str) -> bool {
+        let valid = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        };
+        name.split_once(':')
+            .is_some_and(|(ns, kind)| valid(ns) && valid(kind))
+    }
+
+    /// The kind registered as `name`
+    pub fn lookup(name: &str) -> Option<Self> {
+        let kinds = CUSTOM_KINDS.lock().unwrap_or_else(|e| e.into_inner());
+        kinds
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| Self(entry))
+    }
+
+    /// `namespace:kind`
+    pub fn name(&self) -> &'static str {
+        &self.0.name
+    }
+
+    pub fn namespace(&self) -> &'static str {
+        self.0.name.split_once(':').map_or("", |(ns, _)| ns)
+    }
+
+    /// The core kind this one is made of
+    pub fn base(&self) -> DeclarationKind {
+        CORE_KINDS[self.0.base.load(Ordering::Relaxed)]
+    }
+}
+
+/// A selection of declaration kinds: `function,react:*`
+///
+/// A core kind selects the custom kinds made of it too; `namespace:*`
+/// selects every kind in a namespace. The empty filter selects everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KindFilter {
+    terms: Vec<String>,
+}
+
+impl KindFilter {
+    /// Parse a comma-separated list of core kinds, custom kinds and
+    /// `namespace:*` terms; custom kinds need not be registered yet
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        for term in list.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let known = DeclarationKind::parse(term).is_some()
+                || CustomKind::is_valid_name(
+                    &term
+                        .strip_suffix(":*")
+                        .map_or(term.to_string(), |ns| format!("{}:all", ns)),
+                );
+            if !known {
+                return Err(format!("'{}' is not a declaration kind", term));
+            }
+            terms.push(term.to_string());
+        }
+        Ok(Self { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether `kind` is selected
+    pub fn matches(&self, kind: DeclarationKind) -> bool {
+        self.terms.is_empty()
+            || self.terms.iter().any(|term| {
+                term == kind.as_str()
+                    || term == kind.base().as_str()
+                    || term
+                        .strip_suffix(":*")
+                        .is_some_and(|ns| kind.namespace() == Some(ns))
+            })
     }
 }
 
@@ -861,6 +1214,56 @@ mod tests {
         assert_eq!(span.end_column, 0); // Default
     }
 
+    #[test]
+    fn test_span_slice() {
+        let source = "fn a() {}\nfn b() {}\n";
+        let span = Span::new(10, 19, 2, 2);
+        assert_eq!(span.slice(source), Some("fn b() {}"));
+        assert_eq!(Span::new(10, 99, 2, 2).slice(source), None);
+    }
+
+    #[test]
+    fn test_line_index_round_trip() {
+        let source = "fn a() {}\n\nfn b() {}\n";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 4);
+
+        assert_eq!(index.line_col(0), (1, 0));
+        assert_eq!(index.line_col(11), (3, 0));
+        assert_eq!(index.line_col(14), (3, 3));
+        assert_eq!(index.offset(3, 3), Some(14));
+        assert_eq!(index.offset(3, 99), None);
+        assert_eq!(index.line_range(2), Some(10..10));
+        assert_eq!(index.line_range(3), Some(11..20));
+
+        let span = index.span(11, 20);
+        assert_eq!((span.start_line, span.start_column), (3, 0));
+        assert_eq!((span.end_line, span.end_column), (3, 9));
+        assert_eq!(span.slice(source), Some("fn b() {}"));
+    }
+
+    #[test]
+    fn test_line_index_lsp_positions() {
+        // "é" is 2 bytes but 1 UTF-16 unit; "😀" is 4 bytes and 2 units
+        let source = "let é = 1;\nlet s = \"😀x\";\n";
+        let index = LineIndex::new(source);
+
+        let x = source.find('x').unwrap();
+        assert_eq!(index.to_lsp(source, x), (1, 11));
+        assert_eq!(index.from_lsp(source, 1, 11), Some(x));
+
+        let eq = source.find('=').unwrap();
+        assert_eq!(index.to_lsp(source, eq), (0, 6));
+        assert_eq!(index.from_lsp(source, 0, 6), Some(eq));
+
+        // Past the end of the line clamps
+        assert_eq!(
+            index.from_lsp(source, 0, 99),
+            Some(source.find('\n').unwrap())
+        );
+        assert_eq!(index.from_lsp(source, 9, 0), None);
+    }
+
     #[test]
     fn test_span_contains_line() {
         let span = Span::new(0, 100, 5, 15);
@@ -1100,6 +1503,74 @@ mod tests {
     // Visibility Tests
     // =========================================================================
 
+    #[test]
+    fn test_custom_kinds() {
+        let component =
+            CustomKind::register("test-react:component", DeclarationKind::Function).unwrap();
+        let kind = DeclarationKind::Custom(component);
+        assert_eq!(kind.as_str(), "test-react:component");
+        assert_eq!(kind.base(), DeclarationKind::Function);
+        assert_eq!(kind.namespace(), Some("test-react"));
+        assert_eq!(DeclarationKind::parse("test-react:component"), Some(kind));
+        assert_eq!(
+            DeclarationKind::parse("class"),
+            Some(DeclarationKind::Class)
+        );
+        assert_eq!(DeclarationKind::parse("test-react:hook"), None);
+
+        // Registering again gives the same kind; another base is an error
+        assert_eq!(
+            CustomKind::register("test-react:component", DeclarationKind::Other),
+            Ok(component)
+        );
+        assert!(CustomKind::register("test-react:component", DeclarationKind::Class).is_err());
+        assert!(CustomKind::register("component", DeclarationKind::Function).is_err());
+        assert!(CustomKind::register("React:Component", DeclarationKind::Function).is_err());
+
+        // Serialized by name; unknown names come back made of Other
+        assert_eq!(
+            serde_json::to_string(&kind).unwrap(),
+            "\"test-react:component\""
+        );
+        let back: DeclarationKind = serde_json::from_str("\"test-react:component\"").unwrap();
+        assert_eq!(back, kind);
+        let unseen: DeclarationKind = serde_json::from_str("\"test-tf:resource\"").unwrap();
+        assert_eq!(unseen.base(), DeclarationKind::Other);
+        // ... until a rule gives them their base
+        let resource = CustomKind::register("test-tf:resource", DeclarationKind::Struct).unwrap();
+        assert_eq!(DeclarationKind::Custom(resource), unseen);
+        assert_eq!(unseen.base(), DeclarationKind::Struct);
+        assert!(CustomKind::register("test-tf:resource", DeclarationKind::Function).is_err());
+        assert!(serde_json::from_str::<DeclarationKind>("\"widget\"").is_err());
+    }
+
+    #[test]
+    fn test_kind_filter() {
+        let component = DeclarationKind::Custom(
+            CustomKind::register("test-vue:component", DeclarationKind::Function).unwrap(),
+        );
+        let all = KindFilter::default();
+        assert!(all.is_empty());
+        assert!(all.matches(DeclarationKind::Class) && all.matches(component));
+
+        let functions = KindFilter::parse("function").unwrap();
+        assert!(functions.matches(DeclarationKind::Function));
+        assert!(functions.matches(component));
+        assert!(!functions.matches(DeclarationKind::Method));
+
+        let vue = KindFilter::parse("test-vue:*, class").unwrap();
+        assert!(vue.matches(component));
+        assert!(vue.matches(DeclarationKind::Class));
+        assert!(!vue.matches(DeclarationKind::Function));
+
+        let exact = KindFilter::parse("test-vue:component").unwrap();
+        assert!(exact.matches(component));
+        assert!(!exact.matches(DeclarationKind::Function));
+
+        assert!(KindFilter::parse("widget").is_err());
+        assert!(KindFilter::parse("Vue:*").is_err());
+    }
+
     #[test]
     fn test_visibility_default() {
         let vis = Visibility::default();
@@ -46,6 +46,7 @@
 #![allow(unused_variables)]
 
 pub mod adapters;
+pub mod conformance;
 pub mod error;
 pub mod ir;
 pub mod provider;
@@ -61,6 +62,7 @@ pub use ir::{
     CommentKind,
     ControlFlow,
     ControlFlowKind,
+    CustomKind,
     // Declarations
     Declaration,
     DeclarationKind,
@@ -69,8 +71,11 @@ pub use ir::{
     ImportKind,
     // Imports
     ImportLike,
+    KindFilter,
     // Language
     LanguageId,
+    // Position conversion
+    LineIndex,
     Parameter,
     Region,
     Span,
@@ -80,7 +85,7 @@ pub use ir::{
     Visibility,
 };
 
-pub use adapters::LanguageAdapter;
+pub use adapters::{AdapterCapabilities, LanguageAdapter};
 pub use error::AstError;
 pub use provider::{
     AstProvider, ContextWindow, IndexError, IndexOptions, IndexStats, MicroscopeModel,
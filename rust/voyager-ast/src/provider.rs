@@ -6,7 +6,7 @@
 //! - `MicroscopeModel` for symbol zoom
 
 use crate::error::Result;
-use crate::ir::{Block, Declaration, File, LanguageId};
+use crate::ir::{Block, Declaration, DeclarationKind, File, KindFilter, LanguageId, Visibility};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::Path;
@@ -21,10 +21,12 @@ pub struct IndexOptions {
     /// Maximum files to process (0 = unlimited)
     pub max_files: usize,
 
-    /// File patterns to include (glob syntax)
+    /// File patterns to include (glob syntax, matched against the path
+    /// and its parents, as [`project_walk::style_matches`] does)
     pub include_patterns: Vec<String>,
 
-    /// File patterns to exclude (glob syntax)
+    /// Ignore patterns for the walk (glob syntax, `!` negations, last match
+    /// wins; see [`project_walk::matches_patterns`])
     pub exclude_patterns: Vec<String>,
 
     /// Whether to extract doc comments
@@ -38,6 +40,77 @@ pub struct IndexOptions {
 
     /// Whether to extract nested declarations in Index mode
     pub extract_nested: bool,
+
+    /// Whether to leave out private declarations, and what they contain
+    pub skip_private: bool,
+
+    /// Whether to leave out test files and test modules (see
+    /// [`IndexOptions::is_test_path`])
+    pub skip_tests: bool,
+
+    /// Maximum declarations kept per file, nested ones included, in source
+    /// order (0 = unlimited)
+    pub max_declarations_per_file: usize,
+
+    /// Declaration kinds to keep (empty = all); a declaration of another
+    /// kind stays while it contains one that is kept
+    pub kinds: KindFilter,
+}
+
+impl IndexOptions {
+    /// Whether `path` is a test file: under a `tests`, `test` or `__tests__`
+    /// directory, or named like `test_*.py`, `*_test.*`, `*.test.*`,
+    /// `*.spec.*` or `conftest.py`
+    pub fn is_test_path(path: &Path) -> bool {
+        let in_test_dir = path.parent().is_some_and(|dir| {
+            dir.components()
+                .any(|c| matches!(c.as_os_str().to_str(), Some("tests" | "test" | "__tests__")))
+        });
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let stem = name.split('.').next().unwrap_or("");
+        in_test_dir
+            || name == "conftest.py"
+            || (name.ends_with(".py") && stem.starts_with("test_"))
+            || stem.ends_with("_test")
+            || name.contains(".test.")
+            || name.contains(".spec.")
+    }
+
+    /// Drop the declarations these options leave out of `file`: private
+    /// ones, test modules (`mod tests`), those of unselected kinds and those
+    /// past the per-file cap
+    ///
+    /// Returns how many declarations were dropped, nested ones included.
+    pub fn filter_declarations(&self, file: &mut File) -> usize {
+        fn retain(decls: &mut Vec<Declaration>, options: &IndexOptions, budget: &mut usize) {
+            decls.retain_mut(|decl| {
+                let test_module = decl.kind == DeclarationKind::Module
+                    && matches!(decl.name.as_str(), "tests" | "test");
+                if (options.skip_private && decl.visibility == Visibility::Private)
+                    || (options.skip_tests && test_module)
+                {
+                    return false;
+                }
+                let selected = options.kinds.matches(decl.kind);
+                if selected {
+                    if *budget == 0 {
+                        return false;
+                    }
+                    *budget -= 1;
+                }
+                retain(&mut decl.children, options, budget);
+                selected || !decl.children.is_empty()
+            });
+        }
+
+        let before = file.total_declarations();
+        let mut budget = match self.max_declarations_per_file {
+            0 => usize::MAX,
+            max => max,
+        };
+        retain(&mut file.declarations, self, &mut budget);
+        before - file.total_declarations()
+    }
 }
 
 /// Options for symbol zoom (Microscope mode)
@@ -117,6 +190,29 @@ impl PlanetariumModel {
             .collect()
     }
 
+    /// Find declarations of the kinds `kinds` selects, nested ones included
+    pub fn find_by_kind(&self, kinds: &KindFilter) -> Vec<(&str, &Declaration)> {
+        fn collect<'a>(
+            path: &'a str,
+            decl: &'a Declaration,
+            kinds: &KindFilter,
+            found: &mut Vec<(&'a str, &'a Declaration)>,
+        ) {
+            if kinds.matches(decl.kind) {
+                found.push((path, decl));
+            }
+            for child in &decl.children {
+                collect(path, child, kinds, found);
+            }
+        }
+
+        let mut found = Vec::new();
+        for (path, decl) in self.all_declarations() {
+            collect(path, decl, kinds, &mut found);
+        }
+        found
+    }
+
     /// Get total declaration count
     pub fn total_declarations(&self) -> usize {
         self.files.values().map(|f| f.total_declarations()).sum()
@@ -138,6 +234,10 @@ pub struct IndexStats {
     /// Total imports found
     pub imports_found: usize,
 
+    /// Declarations left out by the private, test and per-file filters
+    #[serde(default)]
+    pub declarations_skipped: usize,
+
     /// Number of unknown/error regions
     pub unknown_regions: usize,
 
@@ -223,6 +323,10 @@ pub trait AstProvider: Send + Sync {
     /// - Import statements
     /// - File-level comments
     ///
+    /// Files are walked as pm_encoder walks them (see `project_walk`):
+    /// hygiene exclusions like `.git`, `.gitignore` and the options'
+    /// patterns decide what is left out. Other dotfiles are indexed.
+    ///
     /// # Arguments
     /// * `root` - Root directory to index
     /// * `options` - Indexing options
@@ -284,6 +388,26 @@ mod tests {
         assert_eq!(json1, json2, "Model serialization must be deterministic");
     }
 
+    #[test]
+    fn test_is_test_path() {
+        for path in [
+            "tests/cli.rs",
+            "pkg/test/helpers.py",
+            "src/__tests__/app.tsx",
+            "test_models.py",
+            "models_test.py",
+            "parser_test.rs",
+            "app.test.ts",
+            "button.spec.tsx",
+            "conftest.py",
+        ] {
+            assert!(IndexOptions::is_test_path(Path::new(path)), "{}", path);
+        }
+        for path in ["src/lib.rs", "testing.py", "test_data.rs", "latest/app.ts"] {
+            assert!(!IndexOptions::is_test_path(Path::new(path)), "{}", path);
+        }
+    }
+
     #[test]
     fn test_index_options_default() {
         let opts = IndexOptions::default();
@@ -313,6 +437,10 @@ mod tests {
             follow_symlinks: true,
             languages: vec![LanguageId::Rust, LanguageId::Python],
             extract_nested: true,
+            skip_private: true,
+            skip_tests: true,
+            max_declarations_per_file: 200,
+            kinds: KindFilter::parse("function").unwrap(),
         };
 
         assert_eq!(opts.max_files, 100);
@@ -322,6 +450,9 @@ mod tests {
         assert!(opts.follow_symlinks);
         assert_eq!(opts.languages.len(), 2);
         assert!(opts.extract_nested);
+        assert!(opts.skip_private && opts.skip_tests);
+        assert_eq!(opts.max_declarations_per_file, 200);
+        assert!(!opts.kinds.is_empty());
     }
 
     #[test]
@@ -334,6 +465,10 @@ mod tests {
             follow_symlinks: false,
             languages: vec![LanguageId::Python],
             extract_nested: false,
+            skip_private: false,
+            skip_tests: false,
+            max_declarations_per_file: 0,
+            kinds: KindFilter::default(),
         };
 
         let cloned = opts.clone();
@@ -510,6 +645,7 @@ mod tests {
             files_skipped: 5,
             declarations_found: 500,
             imports_found: 200,
+            declarations_skipped: 0,
             unknown_regions: 10,
             parse_time_ms: 1500,
             by_language: BTreeMap::new(),
@@ -535,6 +671,7 @@ mod tests {
             files_skipped: 2,
             declarations_found: 50,
             imports_found: 20,
+            declarations_skipped: 7,
             unknown_regions: 1,
             parse_time_ms: 500,
             by_language: BTreeMap::new(),
@@ -545,6 +682,7 @@ mod tests {
 
         assert_eq!(deserialized.files_processed, 10);
         assert_eq!(deserialized.declarations_found, 50);
+        assert_eq!(deserialized.declarations_skipped, 7);
     }
 
     // =========================================================================
@@ -4,10 +4,11 @@
 //! for parsing files across languages.
 
 use crate::adapters::{
+    AdapterCapabilities, CSharpTreeSitterAdapter, GoTreeSitterAdapter, JavaTreeSitterAdapter,
     LanguageAdapter, PythonTreeSitterAdapter, RustTreeSitterAdapter, TypeScriptTreeSitterAdapter,
 };
 use crate::error::{AstError, Result};
-use crate::ir::{File, LanguageId, Span};
+use crate::ir::{File, LanguageId, LineIndex, Span};
 use crate::provider::{
     AstProvider, IndexError, IndexOptions, IndexStats, LanguageStats, MicroscopeModel,
     PlanetariumModel, ZoomOptions,
@@ -37,6 +38,11 @@ impl AdapterRegistry {
                                                                                 // Note: JSX (.jsx) uses same JavaScript grammar but with different LanguageId
                                                                                 // For now, JSX files will use JavaScript adapter
 
+        // Signature Fleet: declarations and signatures, no Zoom bodies
+        registry.register(Box::new(GoTreeSitterAdapter::new()));
+        registry.register(Box::new(JavaTreeSitterAdapter::new()));
+        registry.register(Box::new(CSharpTreeSitterAdapter::new()));
+
         registry
     }
 
@@ -55,6 +61,11 @@ impl AdapterRegistry {
         self.adapters.keys().copied().collect()
     }
 
+    /// Get the capabilities of the adapter for a language
+    pub fn capabilities(&self, language: LanguageId) -> Option<AdapterCapabilities> {
+        self.get(language).map(|a| a.capabilities())
+    }
+
     /// Check if a language is supported
     pub fn supports(&self, language: LanguageId) -> bool {
         self.adapters.contains_key(&language)
@@ -163,7 +174,9 @@ impl AstProvider for TreeSitterProvider {
             }
 
             match self.process_file(&file_path, root, options) {
-                Ok(Some(file)) => {
+                Ok(Some(mut file)) => {
+                    stats.declarations_skipped += options.filter_declarations(&mut file);
+
                     // Update stats
                     stats.files_processed += 1;
                     stats.declarations_found += file.total_declarations();
@@ -198,7 +211,8 @@ impl AstProvider for TreeSitterProvider {
                     });
 
                     // Still add partial results if available
-                    if let Some(partial) = e.take_partial() {
+                    if let Some(mut partial) = e.take_partial() {
+                        stats.declarations_skipped += options.filter_declarations(&mut partial);
                         let relative_path = file_path
                             .strip_prefix(root)
                             .unwrap_or(&file_path)
@@ -274,21 +288,27 @@ impl AstProvider for TreeSitterProvider {
 
         // Extract context if requested
         let context = if options.context_lines > 0 {
-            let lines: Vec<&str> = source.lines().collect();
-            let start_line = declaration.span.start_line.saturating_sub(1);
-            let end_line = declaration.span.end_line;
+            let index = LineIndex::new(&source);
+            // A trailing newline does not start another line of context
+            let last_line = index.line_count() - usize::from(source.ends_with('\n'));
+            let line_text = |line: usize| {
+                if line > last_line {
+                    return None;
+                }
+                index
+                    .line_range(line)
+                    .and_then(|range| source.get(range))
+                    .map(|text| text.trim_end_matches('\r').to_string())
+            };
 
-            let before_start = start_line.saturating_sub(options.context_lines);
-            let after_end = (end_line + options.context_lines).min(lines.len());
+            let start_line = declaration.span.start_line;
+            let end_line = declaration.span.end_line;
+            let before_start = start_line.saturating_sub(options.context_lines).max(1);
 
             Some(crate::provider::ContextWindow {
-                before: lines[before_start..start_line]
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect(),
-                after: lines[end_line..after_end]
-                    .iter()
-                    .map(|s| s.to_string())
+                before: (before_start..start_line).filter_map(line_text).collect(),
+                after: (end_line + 1..=end_line + options.context_lines)
+                    .filter_map(line_text)
                     .collect(),
             })
         } else {
@@ -296,7 +316,7 @@ impl AstProvider for TreeSitterProvider {
         };
 
         // Extract source text
-        let source_text = Some(source[declaration.span.start..declaration.span.end].to_string());
+        let source_text = declaration.span.slice(&source).map(str::to_string);
 
         Ok(MicroscopeModel {
             file_path: file_path.display().to_string(),
@@ -312,13 +332,17 @@ impl AstProvider for TreeSitterProvider {
     }
 
     fn supported_languages(&self) -> &[LanguageId] {
-        // Core Fleet (Phase 1B): Rust, Python, TypeScript, TSX, JavaScript
+        // Core Fleet (Phase 1B): Rust, Python, TypeScript, TSX, JavaScript;
+        // Signature Fleet: Go, Java, C#
         static LANGUAGES: &[LanguageId] = &[
             LanguageId::Rust,
             LanguageId::Python,
             LanguageId::TypeScript,
             LanguageId::Tsx,
             LanguageId::JavaScript,
+            LanguageId::Go,
+            LanguageId::Java,
+            LanguageId::CSharp,
         ];
         LANGUAGES
     }
@@ -327,91 +351,39 @@ impl AstProvider for TreeSitterProvider {
 #[allow(dead_code)]
 impl TreeSitterProvider {
     /// Collect files to process
+    ///
+    /// The walk applies pm_encoder's rules (see [`project_walk`]): hygiene
+    /// exclusions, `.gitignore`, and `exclude_patterns` as ignore patterns.
+    /// Of the files it finds, those in a supported language are kept, when
+    /// they match one of `include_patterns` (if any) and aren't tests that
+    /// `skip_tests` leaves out.
     fn collect_files(
         &self,
         root: &Path,
         options: &IndexOptions,
     ) -> Result<Vec<std::path::PathBuf>> {
-        use std::fs;
-
-        let mut files = Vec::new();
-
-        fn visit_dir(
-            dir: &Path,
-            files: &mut Vec<std::path::PathBuf>,
-            options: &IndexOptions,
-            registry: &AdapterRegistry,
-        ) -> std::io::Result<()> {
-            for entry in fs::read_dir(dir)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                // Skip hidden files and directories
-                if path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .map(|n| n.starts_with('.'))
-                    .unwrap_or(false)
-                {
-                    continue;
-                }
-
-                // Skip common non-source directories
-                if path.is_dir() {
-                    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                    if matches!(
-                        name,
-                        "node_modules" | "target" | "build" | "dist" | "__pycache__" | ".git"
-                    ) {
-                        continue;
-                    }
-
-                    if options.follow_symlinks || !path.is_symlink() {
-                        visit_dir(&path, files, options, registry)?;
-                    }
-                } else if path.is_file() {
-                    // Check if we support this file type
-                    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-                    let language = LanguageId::from_extension(ext);
-
-                    if registry.supports(language) {
-                        // Check include patterns
-                        if !options.include_patterns.is_empty() {
-                            // Simple glob matching (could use globset for full support)
-                            let path_str = path.to_string_lossy();
-                            let matches = options.include_patterns.iter().any(|p| {
-                                path_str.contains(p.trim_start_matches('*').trim_end_matches('*'))
-                            });
-                            if !matches {
-                                continue;
-                            }
-                        }
-
-                        // Check exclude patterns
-                        if !options.exclude_patterns.is_empty() {
-                            let path_str = path.to_string_lossy();
-                            let excluded = options.exclude_patterns.iter().any(|p| {
-                                path_str.contains(p.trim_start_matches('*').trim_end_matches('*'))
-                            });
-                            if excluded {
-                                continue;
-                            }
-                        }
-
-                        files.push(path);
-                    }
-                }
-            }
-            Ok(())
-        }
-
-        visit_dir(root, &mut files, options, &self.registry)
-            .map_err(|e| AstError::IoError(e.to_string()))?;
-
-        // Sort for determinism
-        files.sort();
-
-        Ok(files)
+        let walk = project_walk::WalkOptions {
+            follow_links: options.follow_symlinks,
+            ..Default::default()
+        };
+        let files = project_walk::walk_files(root, &walk, &options.exclude_patterns)
+            .map_err(AstError::IoError)?;
+
+        Ok(files
+            .into_iter()
+            .filter(|path| {
+                let Some(rel) = project_walk::relative_path(root, path) else {
+                    return false;
+                };
+                let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                self.registry.supports(LanguageId::from_extension(ext))
+                    && (options.include_patterns.is_empty()
+                        || options.include_patterns.iter().any(|p| {
+                            project_walk::style_matches(&rel, p, walk.pattern_style, false)
+                        }))
+                    && !(options.skip_tests && IndexOptions::is_test_path(Path::new(&rel)))
+            })
+            .collect())
     }
 
     /// Process a single file
@@ -457,6 +429,7 @@ impl TreeSitterProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::{CustomKind, DeclarationKind, KindFilter};
     use std::fs;
     use tempfile::TempDir;
 
@@ -509,9 +482,33 @@ mod tests {
         assert!(languages.contains(&LanguageId::TypeScript));
         assert!(languages.contains(&LanguageId::Tsx));
         assert!(languages.contains(&LanguageId::JavaScript));
+        // Signature Fleet
+        assert!(languages.contains(&LanguageId::Go));
+        assert!(languages.contains(&LanguageId::Java));
+        assert!(languages.contains(&LanguageId::CSharp));
         assert!(!languages.contains(&LanguageId::Unknown));
     }
 
+    #[test]
+    fn test_registry_capabilities() {
+        let registry = AdapterRegistry::new();
+
+        let rust = registry.capabilities(LanguageId::Rust).unwrap();
+        assert_eq!(rust, AdapterCapabilities::FULL);
+        assert!(rust.fidelity_notes().is_empty());
+
+        let ts = registry.capabilities(LanguageId::TypeScript).unwrap();
+        assert!(ts.zoom_bodies);
+        assert!(!ts.doc_comments);
+        assert_eq!(ts.fidelity_notes().len(), 1);
+
+        let go = registry.capabilities(LanguageId::Go).unwrap();
+        assert!(!go.zoom_bodies);
+        assert!(go.visibility && go.doc_comments);
+
+        assert!(registry.capabilities(LanguageId::Unknown).is_none());
+    }
+
     #[test]
     fn test_registry_register_custom_adapter() {
         let mut registry = AdapterRegistry {
@@ -620,7 +617,8 @@ mod tests {
         assert!(languages.contains(&LanguageId::TypeScript));
         assert!(languages.contains(&LanguageId::Tsx));
         assert!(languages.contains(&LanguageId::JavaScript));
-        assert_eq!(languages.len(), 5);
+        assert!(languages.contains(&LanguageId::Go));
+        assert_eq!(languages.len(), 8);
     }
 
     #[test]
@@ -797,20 +795,40 @@ function Greeting({ name }: Props): JSX.Element {
     }
 
     #[test]
-    fn test_index_project_skips_hidden_files() {
+    fn test_index_project_walks_like_pm_encoder() {
         let temp_dir = TempDir::new().unwrap();
-
-        fs::write(temp_dir.path().join("visible.rs"), "fn visible() {}").unwrap();
-        fs::write(temp_dir.path().join(".hidden.rs"), "fn hidden() {}").unwrap();
-
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("gen")).unwrap();
+
+        fs::write(root.join("visible.rs"), "fn visible() {}").unwrap();
+        fs::write(root.join(".hidden.rs"), "fn hidden() {}").unwrap();
+        fs::write(root.join(".git/hook.py"), "def hook(): pass").unwrap();
+        fs::write(root.join("gen/api.rs"), "fn api() {}").unwrap();
+        fs::write(root.join("gen/keep.rs"), "fn keep() {}").unwrap();
+        fs::write(root.join("scratch.rs"), "fn scratch() {}").unwrap();
+        fs::write(root.join(".gitignore"), "scratch.rs\n").unwrap();
+
+        // Dotfiles are up to the patterns; .git and .gitignore'd files are not
         let provider = TreeSitterProvider::new();
-        let options = IndexOptions::default();
-
-        let model = provider.index_project(temp_dir.path(), &options).unwrap();
+        let options = IndexOptions {
+            exclude_patterns: vec![
+                ".hidden.rs".to_string(),
+                "gen".to_string(),
+                "!gen/keep.rs".to_string(),
+            ],
+            ..Default::default()
+        };
+        let model = provider.index_project(root, &options).unwrap();
+        let mut files: Vec<&str> = model.files.keys().map(String::as_str).collect();
+        files.sort();
+        assert_eq!(files, ["gen/keep.rs", "visible.rs"]);
 
-        assert_eq!(model.stats.files_processed, 1);
-        assert!(model.files.contains_key("visible.rs"));
-        assert!(!model.files.contains_key(".hidden.rs"));
+        let model = provider
+            .index_project(root, &IndexOptions::default())
+            .unwrap();
+        assert!(model.files.contains_key(".hidden.rs"));
+        assert_eq!(model.stats.files_processed, 4);
     }
 
     #[test]
@@ -868,7 +886,7 @@ function Greeting({ name }: Props): JSX.Element {
 
         let provider = TreeSitterProvider::new();
         let options = IndexOptions {
-            exclude_patterns: vec!["test_".to_string()],
+            exclude_patterns: vec!["test_*".to_string()],
             ..Default::default()
         };
 
@@ -878,6 +896,84 @@ function Greeting({ name }: Props): JSX.Element {
         assert!(model.files.contains_key("main.rs"));
     }
 
+    #[test]
+    fn test_index_project_public_surface() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("tests")).unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "pub fn a() {}\nfn hidden() {}\npub struct B;\npub fn c() {}\npub fn d() {}\n\
+             #[cfg(test)]\nmod tests {\n    fn check() {}\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("app.test.ts"),
+            "export function t() {}",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("tests/cli.rs"), "fn t() {}").unwrap();
+
+        let provider = TreeSitterProvider::new();
+        let model = provider
+            .index_project(temp_dir.path(), &IndexOptions::default())
+            .unwrap();
+        assert_eq!(model.stats.files_processed, 3);
+        assert_eq!(model.stats.declarations_skipped, 0);
+
+        let options = IndexOptions {
+            skip_private: true,
+            skip_tests: true,
+            max_declarations_per_file: 3,
+            ..Default::default()
+        };
+        let model = provider.index_project(temp_dir.path(), &options).unwrap();
+        assert_eq!(model.stats.files_processed, 1);
+        let names: Vec<&str> = model.files["lib.rs"]
+            .declarations
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, ["a", "B", "c"]);
+        assert_eq!(model.stats.declarations_found, 3);
+        assert!(model.stats.declarations_skipped >= 3);
+    }
+
+    #[test]
+    fn test_index_project_kinds() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "pub struct B;\nimpl B {\n    pub fn m() {}\n}\npub fn f() {}\n",
+        )
+        .unwrap();
+
+        let provider = TreeSitterProvider::new();
+        let options = IndexOptions {
+            kinds: KindFilter::parse("method").unwrap(),
+            ..Default::default()
+        };
+        let mut model = provider.index_project(temp_dir.path(), &options).unwrap();
+        // The impl stays for the method it holds
+        let file = &model.files["lib.rs"];
+        assert_eq!(file.declarations.len(), 1);
+        assert_eq!(file.declarations[0].kind, DeclarationKind::Impl);
+        assert_eq!(file.declarations[0].children[0].name, "m");
+
+        // Queries select custom kinds by name, namespace or base
+        let handler = CustomKind::register("test-index:handler", DeclarationKind::Method).unwrap();
+        let decl = &mut model.files.get_mut("lib.rs").unwrap().declarations[0].children[0];
+        decl.kind = DeclarationKind::Custom(handler);
+        for filter in ["test-index:*", "test-index:handler", "method"] {
+            let found = model.find_by_kind(&KindFilter::parse(filter).unwrap());
+            let found: Vec<(&str, &str)> =
+                found.iter().map(|(p, d)| (*p, d.name.as_str())).collect();
+            assert_eq!(found, [("lib.rs", "m")], "{}", filter);
+        }
+        assert!(model
+            .find_by_kind(&KindFilter::parse("function").unwrap())
+            .is_empty());
+    }
+
     #[test]
     fn test_index_project_include_patterns() {
         let temp_dir = TempDir::new().unwrap();
@@ -887,7 +983,7 @@ function Greeting({ name }: Props): JSX.Element {
 
         let provider = TreeSitterProvider::new();
         let options = IndexOptions {
-            include_patterns: vec!["main".to_string()],
+            include_patterns: vec!["main.*".to_string()],
             ..Default::default()
         };
 
@@ -1014,6 +1110,27 @@ function Greeting({ name }: Props): JSX.Element {
         assert!(!ctx.before.is_empty() || !ctx.after.is_empty());
     }
 
+    #[test]
+    fn test_zoom_context_lines_at_file_edges() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.rs");
+        fs::write(&file_path, "// one\nfn target() {}\n// three\n").unwrap();
+
+        let provider = TreeSitterProvider::new();
+        let options = ZoomOptions {
+            context_lines: 5,
+            ..Default::default()
+        };
+        let model = provider
+            .zoom_into(&file_path, "function:target:2", &options)
+            .unwrap();
+
+        let ctx = model.context.unwrap();
+        assert_eq!(ctx.before, vec!["// one"]);
+        assert_eq!(ctx.after, vec!["// three"]);
+        assert_eq!(model.source_text.as_deref(), Some("fn target() {}"));
+    }
+
     #[test]
     fn test_zoom_into_with_control_flow() {
         let temp_dir = TempDir::new().unwrap();